@@ -10,7 +10,7 @@ use git_repository::{
     objs::{bstr::BStr, tree::EntryRef},
     odb,
     prelude::*,
-    refs::{file::ReferenceExt, peel},
+    refs::peel,
     traverse::{tree, tree::visit::Action},
 };
 
@@ -26,11 +26,10 @@ fn main() -> anyhow::Result<()> {
         let repo = git_repository::discover(directory)?;
         let name = args.next().unwrap_or_else(|| "HEAD".into());
         let packed = repo.refs.packed_buffer()?;
+        let mut reference = repo.refs.find(&name, packed.as_ref())?;
         let commit_id = repo
             .refs
-            .find(&name, packed.as_ref())?
-            .peel_to_id_in_place(&repo.refs, packed.as_ref(), peel::none)?
-            .to_owned();
+            .peel_to_id_in_place(&mut reference, packed.as_ref(), peel::none)?;
         (repo, commit_id)
     };
     let db = &repo.odb;
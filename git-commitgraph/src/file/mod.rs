@@ -15,6 +15,7 @@ mod access;
 pub mod commit;
 mod init;
 pub mod verify;
+pub(crate) mod write;
 
 const CHUNK_LOOKUP_SIZE: usize = 12;
 const COMMIT_DATA_ENTRY_SIZE: usize = SHA1_SIZE + 16;
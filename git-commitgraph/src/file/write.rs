@@ -0,0 +1,103 @@
+use std::io::{self, Write};
+
+use byteorder::{BigEndian, WriteBytesExt};
+use git_features::hash;
+use git_hash::ObjectId;
+
+use crate::file::{
+    ChunkId, BASE_GRAPHS_LIST_CHUNK_ID, CHUNK_LOOKUP_SIZE, COMMIT_DATA_CHUNK_ID, EXTENDED_EDGES_LIST_CHUNK_ID,
+    EXTENDED_EDGES_MASK, FAN_LEN, HEADER_LEN, LAST_EXTENDED_EDGE_MASK, NO_PARENT, OID_FAN_CHUNK_ID,
+    OID_LOOKUP_CHUNK_ID, SENTINEL_CHUNK_ID, SIGNATURE,
+};
+
+/// A commit to be written into a graph file.
+pub(crate) struct Entry<'a> {
+    pub id: &'a git_hash::oid,
+    pub root_tree_id: &'a git_hash::oid,
+    /// The graph positions of all parents, in order.
+    pub parents: Vec<u32>,
+    pub generation: u32,
+    pub committer_timestamp: u64,
+}
+
+/// Serialize a graph file containing `entries`, which must be sorted by id, on top of the graph files with the checksums in
+/// `base_ids` into `out`, and return its trailing checksum.
+pub(crate) fn write_to(out: impl io::Write, base_ids: &[ObjectId], entries: &[Entry<'_>]) -> io::Result<ObjectId> {
+    let mut fan = [0u32; FAN_LEN];
+    let mut lookup = Vec::new();
+    let mut data = Vec::new();
+    let mut edges = Vec::new();
+    for entry in entries {
+        fan[usize::from(entry.id.first_byte())] += 1;
+        lookup.extend_from_slice(entry.id.as_bytes());
+        data.extend_from_slice(entry.root_tree_id.as_bytes());
+        let (parent1, parent2) = match entry.parents.as_slice() {
+            [] => (NO_PARENT, NO_PARENT),
+            [p1] => (*p1, NO_PARENT),
+            [p1, p2] => (*p1, *p2),
+            [p1, rest @ ..] => {
+                let edge_index = (edges.len() / 4) as u32;
+                for (idx, parent) in rest.iter().enumerate() {
+                    let last = if idx + 1 == rest.len() {
+                        LAST_EXTENDED_EDGE_MASK
+                    } else {
+                        0
+                    };
+                    write_u32(&mut edges, parent | last);
+                }
+                (*p1, EXTENDED_EDGES_MASK | edge_index)
+            }
+        };
+        write_u32(&mut data, parent1);
+        write_u32(&mut data, parent2);
+        write_u64(
+            &mut data,
+            (u64::from(entry.generation) << 34) | (entry.committer_timestamp & 0x0003_ffff_ffff),
+        );
+    }
+    let mut fan_bytes = Vec::with_capacity(FAN_LEN * 4);
+    let mut count = 0;
+    for commits_with_first_byte in fan.iter() {
+        count += commits_with_first_byte;
+        write_u32(&mut fan_bytes, count);
+    }
+
+    let mut chunks: Vec<(ChunkId, Vec<u8>)> = vec![
+        (OID_FAN_CHUNK_ID, fan_bytes),
+        (OID_LOOKUP_CHUNK_ID, lookup),
+        (COMMIT_DATA_CHUNK_ID, data),
+    ];
+    if !edges.is_empty() {
+        chunks.push((EXTENDED_EDGES_LIST_CHUNK_ID, edges));
+    }
+    if !base_ids.is_empty() {
+        chunks.push((
+            BASE_GRAPHS_LIST_CHUNK_ID,
+            base_ids.iter().flat_map(|id| id.as_bytes().iter().cloned()).collect(),
+        ));
+    }
+
+    let mut out = hash::Write::new(out, git_hash::Kind::Sha1);
+    out.write_all(SIGNATURE)?;
+    out.write_all(&[1, 1, chunks.len() as u8, base_ids.len() as u8])?;
+    let mut offset = (HEADER_LEN + (chunks.len() + 1) * CHUNK_LOOKUP_SIZE) as u64;
+    for (id, chunk) in &chunks {
+        out.write_all(id)?;
+        out.write_u64::<BigEndian>(offset)?;
+        offset += chunk.len() as u64;
+    }
+    out.write_all(&SENTINEL_CHUNK_ID)?;
+    out.write_u64::<BigEndian>(offset)?;
+    for (_, chunk) in chunks {
+        out.write_all(&chunk)?;
+    }
+    out.write_trailer()
+}
+
+fn write_u32(out: &mut Vec<u8>, value: u32) {
+    out.write_u32::<BigEndian>(value).expect("writing to a vec never fails");
+}
+
+fn write_u64(out: &mut Vec<u8>, value: u64) {
+    out.write_u64::<BigEndian>(value).expect("writing to a vec never fails");
+}
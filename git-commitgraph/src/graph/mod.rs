@@ -7,6 +7,8 @@ use std::fmt;
 
 use crate::file::File;
 
+pub use init::Error;
+
 /// A complete commit graph.
 ///
 /// The data in the commit graph may come from a monolithic `objects/info/commit-graph` file, or it
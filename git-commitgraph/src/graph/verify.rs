@@ -203,3 +203,6 @@ impl Graph {
         Ok(stats)
     }
 }
+
+///
+pub mod objects;
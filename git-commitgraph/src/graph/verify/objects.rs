@@ -0,0 +1,133 @@
+//! Verify the commits in a graph against the commit objects they were created from.
+use std::ops::Range;
+
+use git_features::parallel::{self, reduce::IdentityWithResult};
+use git_object::{commit::ref_iter::Token, CommitRefIter};
+
+use crate::{file::commit, graph, Graph};
+
+/// The error used in [`verify_objects()`][Graph::verify_objects()].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Commit(#[from] commit::Error),
+    #[error("Commit {id} could not be found in the object database")]
+    NotFound { id: git_hash::ObjectId },
+    #[error("Commit {id} could not be decoded")]
+    Decode {
+        id: git_hash::ObjectId,
+        source: git_object::decode::Error,
+    },
+    #[error("Commit {id} is missing its {field}")]
+    MissingField {
+        id: git_hash::ObjectId,
+        field: &'static str,
+    },
+    #[error("Commit {id}'s root tree should be {expected} but is {actual}")]
+    RootTreeId {
+        id: git_hash::ObjectId,
+        expected: git_hash::ObjectId,
+        actual: git_hash::ObjectId,
+    },
+    #[error("Commit {id}'s parents should be {expected:?} but are {actual:?}")]
+    Parents {
+        id: git_hash::ObjectId,
+        expected: Vec<git_hash::ObjectId>,
+        actual: Vec<git_hash::ObjectId>,
+    },
+    #[error("Commit {id}'s committer timestamp should be {expected} but is {actual}")]
+    CommitterTimestamp {
+        id: git_hash::ObjectId,
+        expected: u64,
+        actual: u64,
+    },
+}
+
+impl Graph {
+    /// Look up each commit in the graph with `find(id, buf) -> Option<CommitRefIter>` and assure its root tree, parents and
+    /// committer timestamp match the ones stored in the graph, like `git commit-graph verify` does.
+    ///
+    /// Commits are verified in chunks on up to `thread_limit` threads, and the amount of verified commits is returned.
+    /// As the checks of [`verify_integrity()`][Graph::verify_integrity()] aren't repeated here, both should be used for
+    /// a complete verification.
+    pub fn verify_objects<Find>(&self, find: Find, thread_limit: Option<usize>) -> Result<u32, Error>
+    where
+        Find: for<'a> Fn(&git_hash::oid, &'a mut Vec<u8>) -> Option<CommitRefIter<'a>> + Send + Sync,
+    {
+        let num_commits = self.num_commits();
+        let (chunk_size, thread_limit, _) =
+            parallel::optimize_chunk_size_and_thread_limit(1000, Some(num_commits as usize), thread_limit, None);
+        let chunk_size = chunk_size as u32;
+        let chunks = (0..num_commits)
+            .step_by(chunk_size as usize)
+            .map(|start| start..(start + chunk_size).min(num_commits));
+
+        parallel::in_parallel_if(
+            || num_commits > chunk_size,
+            chunks,
+            thread_limit,
+            |_thread_index| Vec::new(),
+            |positions: Range<u32>, buf: &mut Vec<u8>| {
+                positions
+                    .map(graph::Position)
+                    .try_for_each(|pos| self.verify_object(pos, &find, buf))
+            },
+            IdentityWithResult::<(), Error>::default(),
+        )?;
+        Ok(num_commits)
+    }
+
+    fn verify_object<Find>(&self, pos: graph::Position, find: &Find, buf: &mut Vec<u8>) -> Result<(), Error>
+    where
+        Find: for<'a> Fn(&git_hash::oid, &'a mut Vec<u8>) -> Option<CommitRefIter<'a>>,
+    {
+        let commit = self.commit_at(pos);
+        let id = commit.id().to_owned();
+        let actual_parents = commit
+            .iter_parents()
+            .map(|parent| parent.map(|pos| self.id_at(pos).to_owned()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut root_tree_id = None;
+        let mut expected_parents = Vec::with_capacity(actual_parents.len());
+        let mut committer_timestamp = None;
+        for token in find(commit.id(), buf).ok_or(Error::NotFound { id })? {
+            match token.map_err(|source| Error::Decode { id, source })? {
+                Token::Tree { id } => root_tree_id = Some(id),
+                Token::Parent { id } => expected_parents.push(id),
+                Token::Committer { signature } => {
+                    committer_timestamp = Some(signature.time.time as u64);
+                    break;
+                }
+                Token::Author { .. } => {}
+                _ => break,
+            }
+        }
+
+        let expected_root_tree_id = root_tree_id.ok_or(Error::MissingField { id, field: "tree" })?;
+        if expected_root_tree_id != commit.root_tree_id() {
+            return Err(Error::RootTreeId {
+                id,
+                expected: expected_root_tree_id,
+                actual: commit.root_tree_id().to_owned(),
+            });
+        }
+        if expected_parents != actual_parents {
+            return Err(Error::Parents {
+                id,
+                expected: expected_parents,
+                actual: actual_parents,
+            });
+        }
+        let expected_timestamp = committer_timestamp.ok_or(Error::MissingField { id, field: "committer" })?;
+        if expected_timestamp != commit.committer_timestamp() {
+            return Err(Error::CommitterTimestamp {
+                id,
+                expected: expected_timestamp,
+                actual: commit.committer_timestamp(),
+            });
+        }
+        Ok(())
+    }
+}
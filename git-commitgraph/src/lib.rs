@@ -7,13 +7,19 @@
 //! As generating the full commit graph from scratch can take some time, git may write new commits
 //! to separate [files][file::File] instead of overwriting the original file.
 //! Eventually, git will merge these files together as the number of files grows.
+//!
+//! New graph files can be [written][write()] from commits obtained from the object database.
 #![forbid(unsafe_code)]
 #![deny(rust_2018_idioms, missing_docs)]
 
 pub mod file;
 pub mod graph;
+pub mod repair;
+pub mod write;
 
 pub use graph::Graph;
+pub use repair::repair;
+pub use write::write;
 
 /// The number of generations that are considered 'infinite' commit history.
 pub const GENERATION_NUMBER_INFINITY: u32 = 0xffff_ffff;
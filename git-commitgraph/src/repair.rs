@@ -0,0 +1,252 @@
+//! Repair split commit-graph chains whose layers went missing or got corrupted.
+//!
+//! A chain in `objects/info/commit-graphs` consists of the `commit-graph-chain` file listing the hashes of its layers, from
+//! the base to the tip, and one `graph-<hash>.graph` file per layer. Each layer refers to the commits of the layers below it
+//! by position, which is why a single broken layer invalidates all layers above it.
+//!
+//! [`repair()`] keeps the intact layers below the first broken one, and writes the commits of the layers it drops into a
+//! new layer on top of them with the help of the object database.
+use std::{
+    convert::TryInto,
+    io,
+    path::{Path, PathBuf},
+};
+
+use git_hash::{oid, ObjectId};
+
+use crate::{
+    file::{self, File},
+    graph,
+    write::{self, collect_commits, write_atomically, write_layer},
+    Graph,
+};
+
+/// The error returned by [`repair()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error<E: std::error::Error + 'static> {
+    #[error("Could not read or write '{}'", .path.display())]
+    Io {
+        #[source]
+        err: std::io::Error,
+        path: PathBuf,
+    },
+    #[error(transparent)]
+    Graph(#[from] graph::Error),
+    #[error("Could not find commit {0} in the object database")]
+    MissingCommit(ObjectId),
+    #[error("The chain would have more than 255 base layers")]
+    TooManyLayers,
+    #[error("{0}")]
+    Find(#[source] E),
+}
+
+impl<E: std::error::Error + 'static> From<write::Error<E>> for Error<E> {
+    fn from(err: write::Error<E>) -> Self {
+        match err {
+            write::Error::Io { err, path } => Error::Io { err, path },
+            write::Error::Graph(err) => Error::Graph(err),
+            write::Error::MissingCommit(id) => Error::MissingCommit(id),
+            write::Error::Find(err) => Error::Find(err),
+        }
+    }
+}
+
+/// A problem with a layer of a commit-graph chain, as found by [`repair()`].
+#[derive(Debug)]
+pub enum Problem {
+    /// A line of the `commit-graph-chain` file isn't a hash.
+    InvalidChainLine(String),
+    /// The file of the layer with the given hash doesn't exist.
+    MissingLayer(ObjectId),
+    /// The file of the layer with hash `id` couldn't be parsed.
+    Corrupt {
+        /// The hash of the layer.
+        id: ObjectId,
+        /// The reason for the layer to be unusable.
+        err: file::Error,
+    },
+    /// The checksum over the content of the layer with hash `id` is `actual`.
+    ChecksumMismatch {
+        /// The hash of the layer as listed in the chain.
+        id: ObjectId,
+        /// The checksum over its content.
+        actual: ObjectId,
+    },
+    /// The layer with hash `id` doesn't refer to the layers below it in the chain.
+    BaseGraphMismatch {
+        /// The hash of the layer.
+        id: ObjectId,
+    },
+}
+
+/// A commit as obtained from the object database, to be written into a repaired chain.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Commit {
+    /// The id of the commit's tree.
+    pub tree: ObjectId,
+    /// The ids of the commit's parents, in order.
+    pub parents: Vec<ObjectId>,
+    /// The time the commit was committed at, in seconds since the unix epoch.
+    pub committer_timestamp: u64,
+}
+
+/// The outcome of [`repair()`].
+#[derive(Debug, Default)]
+pub struct Outcome {
+    /// The problems of all broken layers, which doesn't include intact layers above them. The chain is only changed if there is at least one.
+    pub problems: Vec<Problem>,
+    /// The hashes of the layers that were kept, from the base to the tip.
+    pub kept_layers: Vec<ObjectId>,
+    /// The hashes of the layers that were removed from the chain, which includes intact layers above broken ones.
+    pub removed_layers: Vec<ObjectId>,
+    /// The hash of the layer written on top of the kept layers, if there were commits to write.
+    pub written_layer: Option<ObjectId>,
+    /// The amount of commits in the written layer.
+    pub num_written_commits: u32,
+}
+
+/// Check the chain of commit-graph layers in `commit_graphs_dir`, usually `objects/info/commit-graphs`, and rewrite it
+/// if layers are missing, corrupt, or don't refer to the layers below them.
+///
+/// The intact layers below the first broken one are kept, and all commits reachable from commits of the removed layers that
+/// can still be read, as well as from `tips`, are written into a new layer on top of them. Commits that aren't in the kept layers
+/// are looked up with `find(id) -> Result<Option<Commit>, E>`, which fails with [`Error::MissingCommit`] if a commit
+/// doesn't exist. `tips` should be all commits the graph is supposed to contain, like the peeled tips of all references, as
+/// commits of missing layers can only be recovered that way.
+///
+/// Nothing is changed if no problem was found, and the [outcome][Outcome] lists all problems along with how the chain was changed.
+pub fn repair<E>(
+    commit_graphs_dir: impl AsRef<Path>,
+    tips: impl IntoIterator<Item = ObjectId>,
+    mut find: impl FnMut(&oid) -> Result<Option<Commit>, E>,
+) -> Result<Outcome, Error<E>>
+where
+    E: std::error::Error + 'static,
+{
+    let dir = commit_graphs_dir.as_ref();
+    let chain_path = dir.join("commit-graph-chain");
+    let chain = match std::fs::read_to_string(&chain_path) {
+        Ok(chain) => chain,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(Error::Io { err, path: chain_path }),
+    };
+
+    let mut out = Outcome::default();
+    let mut kept_files = Vec::new();
+    let mut dropped_files = Vec::new();
+    for line in chain.lines() {
+        let id = match ObjectId::from_hex(line.trim().as_bytes()) {
+            Ok(id) => id,
+            Err(_) => {
+                out.problems.push(Problem::InvalidChainLine(line.to_owned()));
+                continue;
+            }
+        };
+        let file = match File::at(layer_path(dir, &id)) {
+            Ok(file) => file,
+            Err(file::Error::Io { err, .. }) if err.kind() == io::ErrorKind::NotFound => {
+                out.problems.push(Problem::MissingLayer(id));
+                out.removed_layers.push(id);
+                continue;
+            }
+            Err(err) => {
+                out.problems.push(Problem::Corrupt { id, err });
+                out.removed_layers.push(id);
+                continue;
+            }
+        };
+        let problem = match file.verify_checksum() {
+            Err((actual, _)) => Some(Problem::ChecksumMismatch { id, actual }),
+            Ok(actual) if actual != id => Some(Problem::ChecksumMismatch { id, actual }),
+            Ok(_)
+                if out.problems.is_empty()
+                    && (usize::from(file.base_graph_count()) != kept_files.len()
+                        || !file.iter_base_graph_ids().eq(kept_files.iter().map(File::checksum))) =>
+            {
+                Some(Problem::BaseGraphMismatch { id })
+            }
+            Ok(_) => None,
+        };
+        let content_is_intact = !matches!(problem, Some(Problem::ChecksumMismatch { .. }));
+        match problem {
+            Some(problem) => out.problems.push(problem),
+            None if out.problems.is_empty() => {
+                out.kept_layers.push(id);
+                kept_files.push(file);
+                continue;
+            }
+            None => {}
+        }
+        out.removed_layers.push(id);
+        if content_is_intact {
+            dropped_files.push(file);
+        }
+    }
+    if out.problems.is_empty() {
+        return Ok(out);
+    }
+
+    let graph = if kept_files.is_empty() {
+        None
+    } else {
+        Some(Graph::new(kept_files)?)
+    };
+    let mut tips: Vec<_> = tips.into_iter().map(|id| (id, true)).collect();
+    tips.extend(
+        dropped_files
+            .iter()
+            .flat_map(|file| file.iter_ids().map(|id| (id.to_owned(), false))),
+    );
+    drop(dropped_files);
+
+    let commits = collect_commits(graph.as_ref(), tips, &mut find)?;
+    let mut chain_content = String::new();
+    for id in &out.kept_layers {
+        chain_content.push_str(&id.to_string());
+        chain_content.push('\n');
+    }
+    if !commits.is_empty() {
+        if out.kept_layers.len() > 255 {
+            return Err(Error::TooManyLayers);
+        }
+        let (id, data) = write_layer(graph.as_ref(), &out.kept_layers, &commits)?;
+        write_atomically(&layer_path(dir, &id), &data)?;
+        chain_content.push_str(&id.to_string());
+        chain_content.push('\n');
+        out.removed_layers.retain(|removed| *removed != id);
+        out.written_layer = Some(id);
+        out.num_written_commits = commits.len().try_into().expect("commit count checked when writing");
+    }
+    drop(graph);
+
+    if chain_content.is_empty() {
+        std::fs::remove_file(&chain_path)
+            .or_else(ignore_not_found)
+            .map_err(|err| Error::Io {
+                err,
+                path: chain_path.clone(),
+            })?;
+    } else {
+        write_atomically(&chain_path, chain_content.as_bytes())?;
+    }
+    for id in &out.removed_layers {
+        let path = layer_path(dir, id);
+        std::fs::remove_file(&path)
+            .or_else(ignore_not_found)
+            .map_err(|err| Error::Io { err, path })?;
+    }
+    Ok(out)
+}
+
+fn layer_path(dir: &Path, id: &oid) -> PathBuf {
+    dir.join(format!("graph-{}.graph", id))
+}
+
+fn ignore_not_found(err: io::Error) -> io::Result<()> {
+    if err.kind() == io::ErrorKind::NotFound {
+        Ok(())
+    } else {
+        Err(err)
+    }
+}
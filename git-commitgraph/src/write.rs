@@ -0,0 +1,201 @@
+//! Write commit-graph files from commits obtained from the object database.
+//!
+//! [`write()`] produces a single `objects/info/commit-graph` file with all commits reachable from the given tips, like
+//! `git commit-graph write` does without `--split`.
+use std::{
+    cmp::{max, min},
+    collections::{BTreeMap, HashMap, HashSet},
+    convert::TryInto,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use git_hash::{oid, ObjectId};
+
+pub use crate::repair::Commit;
+use crate::{file, graph, Graph, GENERATION_NUMBER_MAX, MAX_COMMITS};
+
+/// The error returned by [`write()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error<E: std::error::Error + 'static> {
+    #[error("Could not write '{}'", .path.display())]
+    Io {
+        #[source]
+        err: std::io::Error,
+        path: PathBuf,
+    },
+    #[error(transparent)]
+    Graph(#[from] graph::Error),
+    #[error("Could not find commit {0} in the object database")]
+    MissingCommit(ObjectId),
+    #[error("{0}")]
+    Find(#[source] E),
+}
+
+/// The outcome of [`write()`].
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    /// The path of the written file.
+    pub path: PathBuf,
+    /// The checksum of the written file.
+    pub id: ObjectId,
+    /// The amount of commits in the written file.
+    pub num_commits: u32,
+    /// The largest generation number of all written commits, or `None` if there were no commits to write.
+    pub max_generation: Option<u32>,
+}
+
+/// Write all commits reachable from `tips` into the `commit-graph` file in `objects_info_dir`, usually `objects/info`, replacing
+/// any previous one.
+///
+/// Commits are looked up with `find(id) -> Result<Option<Commit>, E>`, which fails with [`Error::MissingCommit`] if a commit
+/// doesn't exist. A split chain in `commit-graphs` is left alone, but git prefers the `commit-graph` file over it.
+pub fn write<E>(
+    objects_info_dir: impl AsRef<Path>,
+    tips: impl IntoIterator<Item = ObjectId>,
+    mut find: impl FnMut(&oid) -> Result<Option<Commit>, E>,
+) -> Result<Outcome, Error<E>>
+where
+    E: std::error::Error + 'static,
+{
+    let commits = collect_commits(None, tips.into_iter().map(|id| (id, true)).collect(), &mut find)?;
+    let (id, data) = write_layer(None, &[], &commits)?;
+    let path = objects_info_dir.as_ref().join("commit-graph");
+    write_atomically(&path, &data)?;
+    Ok(Outcome {
+        path,
+        id,
+        num_commits: commits.len().try_into().expect("commit count checked when writing"),
+        max_generation: commits.values().map(|(_, generation)| *generation).max(),
+    })
+}
+
+/// Commits to be written, along with their generation number.
+pub(crate) type Commits = BTreeMap<ObjectId, (Commit, u32)>;
+
+/// Obtain all commits reachable from `tips` which aren't in `graph`, along with their generation number.
+///
+/// Tips which aren't required are skipped if they don't exist, as they are commits from removed layers which may have been pruned.
+pub(crate) fn collect_commits<E>(
+    graph: Option<&Graph>,
+    tips: Vec<(ObjectId, bool)>,
+    find: &mut impl FnMut(&oid) -> Result<Option<Commit>, E>,
+) -> Result<Commits, Error<E>>
+where
+    E: std::error::Error + 'static,
+{
+    let generation_in_graph = |id: &oid| graph.and_then(|g| g.commit_by_id(id)).map(|c| c.generation());
+    let mut found = HashMap::new();
+    let mut queue = tips;
+    while let Some((id, required)) = queue.pop() {
+        if found.contains_key(&id) || generation_in_graph(&id).is_some() {
+            continue;
+        }
+        let commit = match find(&id).map_err(Error::Find)? {
+            Some(commit) => commit,
+            None if required => return Err(Error::MissingCommit(id)),
+            None => continue,
+        };
+        queue.extend(commit.parents.iter().map(|parent| (*parent, true)));
+        found.insert(id, commit);
+    }
+
+    let mut generations = HashMap::<ObjectId, u32>::new();
+    let mut visited = HashSet::new();
+    for tip in found.keys() {
+        let mut stack = vec![*tip];
+        while let Some(id) = stack.last().cloned() {
+            if generations.contains_key(&id) {
+                stack.pop();
+                continue;
+            }
+            let parents = &found[&id].parents;
+            let pending: Vec<_> = parents
+                .iter()
+                .filter(|p| generation_in_graph(p).is_none() && !generations.contains_key(*p))
+                .cloned()
+                .collect();
+            if pending.is_empty() || !visited.insert(id) {
+                let max_parent_generation = parents
+                    .iter()
+                    .map(|p| {
+                        generation_in_graph(p)
+                            .or_else(|| generations.get(p).cloned())
+                            .unwrap_or(0)
+                    })
+                    .fold(0, max);
+                generations.insert(id, min(max_parent_generation + 1, GENERATION_NUMBER_MAX));
+                stack.pop();
+            } else {
+                stack.extend(pending);
+            }
+        }
+    }
+    Ok(found
+        .into_iter()
+        .map(|(id, commit)| {
+            let generation = generations[&id];
+            (id, (commit, generation))
+        })
+        .collect())
+}
+
+/// Serialize a layer with `commits` on top of the layers of `graph` with the hashes `base_ids`, and return its checksum along
+/// with its content.
+pub(crate) fn write_layer<E>(
+    graph: Option<&Graph>,
+    base_ids: &[ObjectId],
+    commits: &Commits,
+) -> Result<(ObjectId, Vec<u8>), Error<E>>
+where
+    E: std::error::Error + 'static,
+{
+    let base_commits = graph.map_or(0, |g| g.num_commits());
+    let total = u64::from(base_commits) + commits.len() as u64;
+    if total > u64::from(MAX_COMMITS) {
+        return Err(Error::Graph(graph::Error::TooManyCommits(total)));
+    }
+    let positions: HashMap<&ObjectId, u32> = commits
+        .keys()
+        .enumerate()
+        .map(|(pos, id)| (id, base_commits + pos as u32))
+        .collect();
+    let position = |id: &ObjectId| match positions.get(id) {
+        Some(pos) => *pos,
+        None => {
+            graph
+                .and_then(|g| g.lookup(id))
+                .expect("parents are either in the graph or written")
+                .0
+        }
+    };
+    let entries: Vec<_> = commits
+        .iter()
+        .map(|(id, (commit, generation))| file::write::Entry {
+            id,
+            root_tree_id: &commit.tree,
+            parents: commit.parents.iter().map(position).collect(),
+            generation: *generation,
+            committer_timestamp: commit.committer_timestamp,
+        })
+        .collect();
+    let mut data = Vec::new();
+    let id = file::write::write_to(&mut data, base_ids, &entries).expect("writing to a vec never fails");
+    Ok((id, data))
+}
+
+/// Write `data` to a temporary file next to `path` and move it into place.
+pub(crate) fn write_atomically<E>(path: &Path, data: &[u8]) -> Result<(), Error<E>>
+where
+    E: std::error::Error + 'static,
+{
+    let tmp_path = path.with_extension("lock");
+    std::fs::File::create(&tmp_path)
+        .and_then(|mut file| file.write_all(data))
+        .and_then(|_| std::fs::rename(&tmp_path, path))
+        .map_err(|err| Error::Io {
+            err,
+            path: path.to_owned(),
+        })
+}
@@ -0,0 +1,31 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+git checkout -q --orphan root
+git commit -q --allow-empty -m root
+
+git checkout -q -b parent1 root
+git commit -q --allow-empty -m parent1
+
+git checkout -q -b parent2 root
+git commit -q --allow-empty -m parent2
+
+git checkout -q -b parent3 root
+git commit -q --allow-empty -m parent3
+
+git checkout -q -b three_parents parent1
+git merge -q -m three_parents --no-ff parent2 parent3 >/dev/null
+
+git checkout -q -b two_parents parent2
+git merge -q -m two_parents --no-ff parent3 >/dev/null
+
+git checkout -q -b child three_parents
+git commit -q --allow-empty -m child
+
+git show-ref -s root parent1 parent2 parent3 | git commit-graph write --no-progress --split=no-merge --stdin-commits
+git show-ref -s three_parents two_parents | git commit-graph write --no-progress --split=no-merge --stdin-commits
+git show-ref -s child | git commit-graph write --no-progress --split=no-merge --stdin-commits
+git repack -adq
@@ -0,0 +1,175 @@
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use bstr::ByteSlice;
+use git_commitgraph::{repair, Graph};
+use git_hash::ObjectId;
+use git_testtools::scripted_fixture_repo_writable;
+
+fn git(repo: &Path, args: &[&str]) -> Vec<u8> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .env_remove("GIT_DIR")
+        .output()
+        .expect("git is available");
+    assert!(output.status.success(), "{}", output.stderr.as_bstr());
+    output.stdout
+}
+
+fn commits(repo: &Path) -> HashMap<ObjectId, repair::Commit> {
+    let id = |hex: &str| ObjectId::from_hex(hex.as_bytes()).expect("valid hex");
+    git(repo, &["log", "--all", "--format=%H %T %ct %P"])
+        .lines()
+        .map(|line| {
+            let line = line.to_str().expect("valid UTF-8");
+            let mut tokens = line.split_whitespace();
+            let commit = id(tokens.next().expect("commit"));
+            let tree = id(tokens.next().expect("tree"));
+            let committer_timestamp = tokens.next().expect("time").parse().expect("valid time");
+            (
+                commit,
+                repair::Commit {
+                    tree,
+                    parents: tokens.map(id).collect(),
+                    committer_timestamp,
+                },
+            )
+        })
+        .collect()
+}
+
+fn rev_parse(repo: &Path, name: &str) -> ObjectId {
+    ObjectId::from_hex(git(repo, &["rev-parse", name]).trim_end()).expect("valid hex")
+}
+
+fn commit_graphs_dir(repo: &Path) -> PathBuf {
+    repo.join(".git").join("objects").join("info").join("commit-graphs")
+}
+
+fn chain(commit_graphs: &Path) -> Vec<ObjectId> {
+    std::fs::read_to_string(commit_graphs.join("commit-graph-chain"))
+        .expect("chain exists")
+        .lines()
+        .map(|line| ObjectId::from_hex(line.as_bytes()).expect("valid hex"))
+        .collect()
+}
+
+fn layer(commit_graphs: &Path, id: &ObjectId) -> PathBuf {
+    commit_graphs.join(format!("graph-{}.graph", id))
+}
+
+fn assert_graph_is_valid(repo: &Path, commits: &HashMap<ObjectId, repair::Commit>) -> crate::Result {
+    let graph = Graph::from_commit_graphs_dir(commit_graphs_dir(repo))?;
+    graph.verify_integrity(|_| Ok::<_, Infallible>(()))?;
+    assert_eq!(
+        graph.num_commits() as usize,
+        commits.len(),
+        "all commits are in the graph"
+    );
+    for (id, expected) in commits {
+        let commit = graph.commit_by_id(id).expect("present");
+        assert_eq!(commit.root_tree_id(), expected.tree);
+        assert_eq!(commit.committer_timestamp(), expected.committer_timestamp);
+        let parents = commit
+            .iter_parents()
+            .map(|pos| pos.map(|pos| graph.id_at(pos).to_owned()))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(parents, expected.parents, "parents of {}", id);
+    }
+
+    git(repo, &["commit-graph", "verify", "--no-progress"]);
+    Ok(())
+}
+
+#[test]
+fn intact_chains_are_left_alone() -> crate::Result {
+    let dir = scripted_fixture_repo_writable("split_chain_with_merges.sh")?;
+    let commit_graphs = commit_graphs_dir(dir.path());
+    let before = chain(&commit_graphs);
+
+    let out = repair(&commit_graphs, None, |_| -> Result<_, Infallible> {
+        unreachable!("intact chains need no lookup")
+    })?;
+    assert!(out.problems.is_empty());
+    assert_eq!(out.kept_layers, before);
+    assert!(out.removed_layers.is_empty());
+    assert_eq!(out.written_layer, None);
+    assert_eq!(chain(&commit_graphs), before);
+    Ok(())
+}
+
+#[test]
+fn missing_layers_are_rewritten_from_the_layers_above_them_and_the_tips() -> crate::Result {
+    let dir = scripted_fixture_repo_writable("split_chain_with_merges.sh")?;
+    let repo = dir.path();
+    let commit_graphs = commit_graphs_dir(repo);
+    let before = chain(&commit_graphs);
+    std::fs::remove_file(layer(&commit_graphs, &before[1]))?;
+
+    let commits = commits(repo);
+    let out = repair(&commit_graphs, Some(rev_parse(repo, "two_parents")), |id| {
+        Ok::<_, Infallible>(commits.get(id).cloned())
+    })?;
+    assert!(matches!(out.problems.as_slice(), [repair::Problem::MissingLayer(id)] if *id == before[1]));
+    assert_eq!(out.kept_layers, &before[..1]);
+    assert_eq!(out.removed_layers, &before[1..]);
+    assert_eq!(
+        out.num_written_commits, 3,
+        "the octopus merge is found through the child in the intact layer above it"
+    );
+    let written = out.written_layer.expect("a layer was written");
+    assert_eq!(chain(&commit_graphs), vec![before[0], written]);
+    assert!(
+        !layer(&commit_graphs, &before[2]).exists(),
+        "removed layers are deleted"
+    );
+
+    assert_graph_is_valid(repo, &commits)
+}
+
+#[test]
+fn corrupt_layers_are_replaced_with_commits_reachable_from_the_tips() -> crate::Result {
+    let dir = scripted_fixture_repo_writable("split_chain_with_merges.sh")?;
+    let repo = dir.path();
+    let commit_graphs = commit_graphs_dir(repo);
+    let before = chain(&commit_graphs);
+    let tip_layer = layer(&commit_graphs, &before[2]);
+    let mut data = std::fs::read(&tip_layer)?;
+    let pos = data.len() - 30;
+    data[pos] ^= 0xff;
+    std::fs::write(&tip_layer, data)?;
+
+    let commits = commits(repo);
+    let out = repair(&commit_graphs, Some(rev_parse(repo, "child")), |id| {
+        Ok::<_, Infallible>(commits.get(id).cloned())
+    })?;
+    assert!(matches!(
+        out.problems.as_slice(),
+        [repair::Problem::ChecksumMismatch { id, .. }] if *id == before[2]
+    ));
+    assert_eq!(out.kept_layers, &before[..2]);
+    assert_eq!(out.num_written_commits, 1);
+
+    assert_graph_is_valid(repo, &commits)
+}
+
+#[test]
+fn tips_must_exist() -> crate::Result {
+    let dir = scripted_fixture_repo_writable("split_chain_with_merges.sh")?;
+    let commit_graphs = commit_graphs_dir(dir.path());
+    let before = chain(&commit_graphs);
+    std::fs::remove_file(layer(&commit_graphs, &before[0]))?;
+
+    let tip = ObjectId::from_hex(b"1111111111111111111111111111111111111111")?;
+    let err =
+        repair(&commit_graphs, Some(tip), |_| Ok::<_, Infallible>(None)).expect_err("missing commits can't be written");
+    assert!(matches!(err, repair::Error::MissingCommit(id) if id == tip));
+    assert_eq!(chain(&commit_graphs), before, "nothing was changed");
+    Ok(())
+}
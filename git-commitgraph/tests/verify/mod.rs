@@ -0,0 +1,57 @@
+mod objects {
+    use git_commitgraph::{graph::verify::objects::Error, Graph};
+    use git_odb::{pack::cache::Never, FindExt};
+
+    use crate::{inspect_refs, make_readonly_repo};
+
+    fn graph_and_odb(
+        script: &str,
+    ) -> Result<(Graph, git_odb::linked::Store, std::path::PathBuf), Box<dyn std::error::Error>> {
+        let repo_dir = make_readonly_repo(script);
+        let objects_dir = repo_dir.join(".git").join("objects");
+        Ok((
+            Graph::from_info_dir(objects_dir.join("info"))?,
+            git_odb::linked::Store::at(objects_dir)?,
+            repo_dir,
+        ))
+    }
+
+    #[test]
+    fn all_commits_match_their_objects() -> crate::Result {
+        for script in &["octopus_merges.sh", "split_chain_with_merges.sh"] {
+            let (graph, odb, _) = graph_and_odb(script)?;
+            for thread_limit in &[Some(1), None] {
+                let num_commits =
+                    graph.verify_objects(|id, buf| odb.find_commit_iter(id, buf, &mut Never).ok(), *thread_limit)?;
+                assert_eq!(num_commits, graph.num_commits());
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn mismatching_objects_are_detected() -> crate::Result {
+        let (graph, odb, repo_dir) = graph_and_odb("single_parent.sh")?;
+        let refs = inspect_refs(&repo_dir, &["parent", "child"]);
+        let (parent, child) = (refs["parent"].id(), refs["child"].id());
+
+        let err = graph
+            .verify_objects(
+                |id, buf| {
+                    let id = if id == child { parent } else { id };
+                    odb.find_commit_iter(id, buf, &mut Never).ok()
+                },
+                None,
+            )
+            .unwrap_err();
+        assert!(
+            matches!(&err, Error::Parents { id, expected, actual } if id == &child.to_owned() && expected.is_empty() && actual == &[parent.to_owned()]),
+            "the child was given the parentless commit instead of its own, got {:?}",
+            err
+        );
+
+        let err = graph.verify_objects(|_id, _buf| None, None).unwrap_err();
+        assert!(matches!(err, Error::NotFound { .. }));
+        Ok(())
+    }
+}
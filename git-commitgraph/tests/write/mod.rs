@@ -0,0 +1,102 @@
+use std::{collections::HashMap, convert::Infallible, path::Path, process::Command};
+
+use bstr::ByteSlice;
+use git_commitgraph::{write, Graph};
+use git_hash::ObjectId;
+use git_testtools::scripted_fixture_repo_writable;
+
+fn git(repo: &Path, args: &[&str]) -> Vec<u8> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .env_remove("GIT_DIR")
+        .output()
+        .expect("git is available");
+    assert!(output.status.success(), "{}", output.stderr.as_bstr());
+    output.stdout
+}
+
+fn commits(repo: &Path) -> HashMap<ObjectId, write::Commit> {
+    let id = |hex: &str| ObjectId::from_hex(hex.as_bytes()).expect("valid hex");
+    git(repo, &["log", "--all", "--format=%H %T %ct %P"])
+        .lines()
+        .map(|line| {
+            let line = line.to_str().expect("valid UTF-8");
+            let mut tokens = line.split_whitespace();
+            let commit = id(tokens.next().expect("commit"));
+            let tree = id(tokens.next().expect("tree"));
+            let committer_timestamp = tokens.next().expect("time").parse().expect("valid time");
+            (
+                commit,
+                write::Commit {
+                    tree,
+                    parents: tokens.map(id).collect(),
+                    committer_timestamp,
+                },
+            )
+        })
+        .collect()
+}
+
+fn rev_parse(repo: &Path, name: &str) -> ObjectId {
+    ObjectId::from_hex(git(repo, &["rev-parse", name]).trim_end()).expect("valid hex")
+}
+
+#[test]
+fn all_commits_reachable_from_the_tips_are_written() -> crate::Result {
+    let dir = scripted_fixture_repo_writable("octopus_merges.sh")?;
+    let repo = dir.path();
+    let info_dir = repo.join(".git").join("objects").join("info");
+    std::fs::remove_file(info_dir.join("commit-graph"))?;
+
+    let commits = commits(repo);
+    let tips = ["three_parents", "four_parents"]
+        .iter()
+        .map(|name| rev_parse(repo, name));
+    let out = write(&info_dir, tips, |id| Ok::<_, Infallible>(commits.get(id).cloned()))?;
+    assert_eq!(out.path, info_dir.join("commit-graph"));
+    assert_eq!(out.num_commits as usize, commits.len());
+    assert_eq!(out.max_generation, Some(3));
+
+    let graph = Graph::at(&out.path)?;
+    graph.verify_integrity(|_| Ok::<_, Infallible>(()))?;
+    assert_eq!(graph.num_commits() as usize, commits.len());
+    for (id, expected) in &commits {
+        let commit = graph.commit_by_id(id).expect("present");
+        assert_eq!(commit.root_tree_id(), expected.tree);
+        assert_eq!(commit.committer_timestamp(), expected.committer_timestamp);
+        let parents = commit
+            .iter_parents()
+            .map(|pos| pos.map(|pos| graph.id_at(pos).to_owned()))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(parents, expected.parents, "parents of {}", id);
+    }
+    assert_eq!(
+        graph
+            .commit_by_id(rev_parse(repo, "four_parents"))
+            .expect("present")
+            .generation(),
+        3
+    );
+
+    git(repo, &["commit-graph", "verify", "--no-progress"]);
+    Ok(())
+}
+
+#[test]
+fn tips_must_exist() -> crate::Result {
+    let dir = scripted_fixture_repo_writable("octopus_merges.sh")?;
+    let info_dir = dir.path().join(".git").join("objects").join("info");
+    let before = std::fs::read(info_dir.join("commit-graph"))?;
+
+    let tip = ObjectId::from_hex(b"1111111111111111111111111111111111111111")?;
+    let err = write(&info_dir, Some(tip), |_| Ok::<_, Infallible>(None)).expect_err("missing commits can't be written");
+    assert!(matches!(err, write::Error::MissingCommit(id) if id == tip));
+    assert_eq!(
+        std::fs::read(info_dir.join("commit-graph"))?,
+        before,
+        "nothing was changed"
+    );
+    Ok(())
+}
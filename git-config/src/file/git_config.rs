@@ -149,19 +149,41 @@ impl<'event> GitConfig<'event> {
         let mut config = Self::new();
 
         for path in paths {
-            let other = Self::open(path)?;
-            for (section_id, section_header) in other.section_headers {
-                config.push_section(
-                    section_header.name.0.to_owned(),
-                    section_header.subsection_name.to_owned(),
-                    other.sections[&section_id].clone(),
-                );
-            }
+            config.append(Self::open(path)?);
         }
 
         Ok(config)
     }
 
+    /// Appends all sections of `other` to this instance, so that its values take precedence over the ones already present
+    /// when retrieving single values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use git_config::file::GitConfig;
+    /// # use git_config::values::Boolean;
+    /// # use std::convert::TryFrom;
+    /// let mut git_config = GitConfig::try_from("[core]\n\tbare = false")?;
+    /// git_config.append(GitConfig::try_from("[core]\n\tbare = true")?);
+    /// assert!(matches!(git_config.value::<Boolean>("core", None, "bare")?, Boolean::True(_)));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn append(&mut self, other: Self) {
+        let GitConfig {
+            mut section_headers,
+            mut sections,
+            section_order,
+            ..
+        } = other;
+        for section_id in section_order {
+            self.push_section_internal(
+                section_headers.remove(&section_id).expect("each section has a header"),
+                sections.remove(&section_id).expect("each header has a section"),
+            );
+        }
+    }
+
     /// Generates a config from the environment variables. This is neither
     /// zero-copy nor zero-alloc. See [`git-config`'s documentation] on
     /// environment variable for more information.
@@ -420,6 +442,49 @@ impl<'event> GitConfig<'event> {
             .collect()
     }
 
+    /// Like [`sections_by_name()`][GitConfig::sections_by_name()], but also returns the header of each section to
+    /// make their subsection names accessible.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use git_config::file::GitConfig;
+    /// # use std::convert::TryFrom;
+    /// let config = r#"
+    ///     [core]
+    ///         a = b
+    ///     [core "apple"]
+    ///         e = f
+    /// "#;
+    /// let git_config = GitConfig::try_from(config).unwrap();
+    /// let subsections: Vec<_> = git_config
+    ///     .sections_by_name_with_header("core")
+    ///     .into_iter()
+    ///     .map(|(header, _body)| header.subsection_name.as_deref())
+    ///     .collect();
+    /// assert_eq!(subsections, vec![None, Some("apple")]);
+    /// ```
+    #[must_use]
+    pub fn sections_by_name_with_header<'lookup>(
+        &self,
+        section_name: &'lookup str,
+    ) -> Vec<(&ParsedSectionHeader<'event>, &SectionBody<'event>)> {
+        self.get_section_ids_by_name(section_name)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|id| {
+                (
+                    self.section_headers
+                        .get(&id)
+                        .expect("section doesn't have a header from from lookup"),
+                    self.sections
+                        .get(&id)
+                        .expect("section doesn't have id from from lookup"),
+                )
+            })
+            .collect()
+    }
+
     /// Adds a new section to config. If a subsection name was provided, then
     /// the generated header will use the modern subsection syntax. Returns a
     /// reference to the new section for immediate editing.
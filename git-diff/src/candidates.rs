@@ -0,0 +1,185 @@
+//! A prefilter to find objects that are identical or likely similar to each other, without comparing their content.
+//!
+//! Both rename and copy detection as well as the selection of delta bases in packs need to pair objects which are likely to
+//! be similar. Comparing each object with each other one is quadratic, which is why this prefilter pairs objects with the same
+//! id immediately and proposes only a bounded amount of candidates of similar size for all others.
+//! The caller then computes the actual similarity of candidates, e.g. by creating a delta.
+use std::collections::HashMap;
+
+use git_hash::ObjectId;
+
+/// An object to pair with others, identified by its `id` and the `size` of its content.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub struct Item {
+    /// The id of the object, which is the same for objects with the same content.
+    pub id: ObjectId,
+    /// The size of the object's content in bytes.
+    pub size: u64,
+}
+
+/// Limits to keep the prefilter and the comparisons of its candidates sub-quadratic.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub struct Limits {
+    /// The size of the smaller of two objects must be at least this percentage of the size of the larger one for them to be
+    /// candidates, as objects of very different sizes can't be similar. `git` uses the minimal similarity for this, which
+    /// defaults to 50.
+    pub min_size_percentage: u32,
+    /// The most candidates to propose for each object, preferring the ones closest in size. This is like `pack.window`,
+    /// which defaults to 10.
+    pub max_candidates: usize,
+    /// If the amount of sources multiplied by the amount of destinations exceeds this value, only exact matches are found.
+    /// This is like the square of `diff.renameLimit`, which defaults to 1000 squared.
+    pub max_pairs: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            min_size_percentage: 50,
+            max_candidates: 10,
+            max_pairs: 1000 * 1000,
+        }
+    }
+}
+
+impl Limits {
+    /// Return true if objects of size `a` and `b` are close enough in size to be similar.
+    pub fn sizes_are_similar(&self, a: u64, b: u64) -> bool {
+        let (smaller, larger) = if a < b { (a, b) } else { (b, a) };
+        smaller as u128 * 100 >= larger as u128 * self.min_size_percentage as u128
+    }
+}
+
+/// An index over sources to find the ones identical or similar to other objects quickly.
+pub struct Index<'a> {
+    sources: &'a [Item],
+    /// The index of the first source of each id.
+    by_id: HashMap<ObjectId, usize>,
+    /// The indices of all non-empty sources, sorted by size.
+    by_size: Vec<usize>,
+}
+
+impl<'a> Index<'a> {
+    /// Create a new index over `sources`. All indices returned later refer to this slice.
+    pub fn new(sources: &'a [Item]) -> Self {
+        let mut by_id = HashMap::with_capacity(sources.len());
+        for (idx, item) in sources.iter().enumerate() {
+            by_id.entry(item.id).or_insert(idx);
+        }
+        let mut by_size: Vec<_> = (0..sources.len()).filter(|idx| sources[*idx].size != 0).collect();
+        by_size.sort_by_key(|idx| (sources[*idx].size, *idx));
+        Index {
+            sources,
+            by_id,
+            by_size,
+        }
+    }
+
+    /// Return the sources this index was created from.
+    pub fn sources(&self) -> &[Item] {
+        self.sources
+    }
+
+    /// Return the index of the first source with the given `id`, if there is one.
+    pub fn exact(&self, id: &git_hash::oid) -> Option<usize> {
+        self.by_id.get(id).copied()
+    }
+
+    /// Put the indices of the sources with a size similar to `item` into `out`, closest in size first, according to `limits`.
+    /// Of sources that are equally close in size, larger ones come first.
+    ///
+    /// Sources with the same id as `item` are never proposed, and neither are empty objects.
+    pub fn similar(&self, item: &Item, limits: &Limits, out: &mut Vec<usize>) {
+        out.clear();
+        if item.size == 0 || limits.max_candidates == 0 {
+            return;
+        }
+        let size = |idx: usize| self.sources[self.by_size[idx]].size;
+        let mut above = self.by_size.partition_point(|idx| self.sources[*idx].size < item.size);
+        let mut below = above;
+        while out.len() < limits.max_candidates {
+            let (next_below, next_above) = (
+                below
+                    .checked_sub(1)
+                    .filter(|idx| limits.sizes_are_similar(size(*idx), item.size)),
+                Some(above).filter(|idx| *idx < self.by_size.len() && limits.sizes_are_similar(size(*idx), item.size)),
+            );
+            let next = match (next_below, next_above) {
+                (None, None) => break,
+                (Some(idx), None) => {
+                    below = idx;
+                    idx
+                }
+                (None, Some(idx)) => {
+                    above += 1;
+                    idx
+                }
+                (Some(lower), Some(upper)) => {
+                    if item.size - size(lower) < size(upper) - item.size {
+                        below = lower;
+                        lower
+                    } else {
+                        above += 1;
+                        upper
+                    }
+                }
+            };
+            let source = self.by_size[next];
+            if self.sources[source].id != item.id {
+                out.push(source);
+            }
+        }
+    }
+}
+
+/// A destination paired with a source.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub struct Match {
+    /// The index into the sources.
+    pub source: usize,
+    /// The index into the destinations.
+    pub destination: usize,
+}
+
+/// The outcome of [`find()`].
+#[derive(Default, PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Outcome {
+    /// Destinations whose id is the same as the one of a source, and which are thus identical.
+    ///
+    /// The same source may be matched with multiple destinations, as is the case for copies.
+    pub exact: Vec<Match>,
+    /// Destinations without an exact match along with the sources that are close enough in size to be similar, closest in
+    /// size first. Destinations without any candidate are omitted.
+    pub similar: Vec<(usize, Vec<usize>)>,
+    /// If true, there were too many pairs to find similar candidates according to [`Limits::max_pairs`], and only exact
+    /// matches were found.
+    pub limit_exceeded: bool,
+}
+
+/// Pair `destinations` with `sources` which are identical, and find candidates among `sources` which may be similar for all other
+/// destinations according to `limits`.
+///
+/// For rename and copy detection, sources are deleted or modified files, and destinations are added files.
+pub fn find(sources: &[Item], destinations: &[Item], limits: &Limits) -> Outcome {
+    let index = Index::new(sources);
+    let mut out = Outcome::default();
+    let mut unmatched = Vec::new();
+    for (destination, item) in destinations.iter().enumerate() {
+        match index.exact(&item.id) {
+            Some(source) => out.exact.push(Match { source, destination }),
+            None => unmatched.push(destination),
+        }
+    }
+    if sources.len().saturating_mul(unmatched.len()) > limits.max_pairs {
+        out.limit_exceeded = true;
+        return out;
+    }
+    let mut buf = Vec::new();
+    for destination in unmatched {
+        index.similar(&destinations[destination], limits, &mut buf);
+        if !buf.is_empty() {
+            out.similar.push((destination, buf.clone()));
+        }
+    }
+    out
+}
@@ -2,5 +2,8 @@
 #![forbid(unsafe_code, rust_2018_idioms)]
 #[deny(missing_docs)]
 
+///
+pub mod candidates;
+
 ///
 pub mod tree;
@@ -0,0 +1,93 @@
+use git_diff::candidates::{find, Index, Item, Limits, Match};
+use git_hash::ObjectId;
+
+fn item(id: u8, size: u64) -> Item {
+    let mut bytes = [0u8; 20];
+    bytes[19] = id;
+    Item {
+        id: ObjectId::from(bytes),
+        size,
+    }
+}
+
+#[test]
+fn identical_ids_are_matched_exactly_and_excluded_from_similarity_candidates() {
+    let sources = [item(1, 100), item(2, 100), item(3, 1000)];
+    let destinations = [item(2, 100), item(4, 110), item(2, 100), item(5, 0)];
+    let out = find(&sources, &destinations, &Limits::default());
+    assert_eq!(
+        out.exact,
+        vec![
+            Match {
+                source: 1,
+                destination: 0
+            },
+            Match {
+                source: 1,
+                destination: 2
+            }
+        ],
+        "copies match the same source"
+    );
+    assert_eq!(
+        out.similar,
+        vec![(1, vec![1, 0])],
+        "the large source isn't similar in size, and empty destinations have no candidates"
+    );
+    assert!(!out.limit_exceeded);
+}
+
+#[test]
+fn similar_candidates_are_closest_in_size_first_and_bounded() {
+    let sources: Vec<_> = (1..=20).map(|id| item(id, id as u64 * 10)).collect();
+    let index = Index::new(&sources);
+    let mut out = Vec::new();
+    let limits = Limits {
+        max_candidates: 4,
+        ..Default::default()
+    };
+    index.similar(&item(100, 104), &limits, &mut out);
+    assert_eq!(
+        out.iter().map(|idx| sources[*idx].size).collect::<Vec<_>>(),
+        vec![100, 110, 90, 120]
+    );
+
+    index.similar(&item(100, 30), &Limits::default(), &mut out);
+    assert_eq!(
+        out.iter().map(|idx| sources[*idx].size).collect::<Vec<_>>(),
+        vec![30, 40, 20, 50, 60],
+        "only sources of at least half and at most double the size are similar, preferring larger ones if equally close"
+    );
+
+    index.similar(&sources[4], &Limits::default(), &mut out);
+    assert!(!out.contains(&4), "objects are never similar to themselves");
+}
+
+#[test]
+fn too_many_pairs_only_yield_exact_matches() {
+    let sources = [item(1, 100), item(2, 100)];
+    let destinations = [item(1, 100), item(3, 100), item(4, 100)];
+    let out = find(
+        &sources,
+        &destinations,
+        &Limits {
+            max_pairs: 3,
+            ..Default::default()
+        },
+    );
+    assert_eq!(out.exact.len(), 1);
+    assert!(out.similar.is_empty());
+    assert!(
+        out.limit_exceeded,
+        "2 sources and 2 unmatched destinations are more than 3 pairs"
+    );
+}
+
+#[test]
+fn sizes_are_similar_if_the_smaller_one_is_large_enough() {
+    let limits = Limits::default();
+    assert!(limits.sizes_are_similar(50, 100));
+    assert!(limits.sizes_are_similar(100, 50));
+    assert!(!limits.sizes_are_similar(49, 100));
+    assert!(limits.sizes_are_similar(0, 0));
+}
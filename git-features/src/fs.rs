@@ -53,3 +53,61 @@ pub mod walkdir {
 
 #[cfg(any(feature = "walkdir", feature = "jwalk"))]
 pub use self::walkdir::{walkdir_new, walkdir_sorted_new, WalkDir};
+
+/// Which written files to synchronize to permanent storage before moving them into place, trading performance for durability
+/// against power loss similar to `core.fsync` and `core.fsyncMethod` in `git`.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub enum Fsync {
+    /// Leave it to the operating system to write files eventually, which is fastest but may lose recent writes on power loss.
+    None,
+    /// Synchronize loose objects and packs, but not references or the index.
+    Objects,
+    /// Synchronize objects, references, reference logs and the index.
+    Everything,
+    /// Like [`Everything`][Fsync::Everything], but instead of synchronizing each loose object when it's written, all of them
+    /// are synchronized together once the caller finished writing a batch of objects, similar to `core.fsyncMethod=batch`.
+    Batch,
+}
+
+impl Default for Fsync {
+    fn default() -> Self {
+        Fsync::None
+    }
+}
+
+impl Fsync {
+    /// Returns true if each loose object should be synchronized right after it was written.
+    pub fn loose_object(&self) -> bool {
+        matches!(self, Fsync::Objects | Fsync::Everything)
+    }
+
+    /// Returns true if loose objects should be synchronized together once a batch of them was written.
+    pub fn loose_object_batch(&self) -> bool {
+        matches!(self, Fsync::Batch)
+    }
+
+    /// Returns true if packs and their indices should be synchronized.
+    pub fn pack(&self) -> bool {
+        !matches!(self, Fsync::None)
+    }
+
+    /// Returns true if references, reference logs and the index should be synchronized.
+    pub fn metadata(&self) -> bool {
+        matches!(self, Fsync::Everything | Fsync::Batch)
+    }
+}
+
+/// Synchronize the directory at `path` to make the creation, renaming or removal of files in it durable.
+///
+/// This does nothing on platforms that can't open directories for this purpose, like Windows.
+pub fn sync_dir(path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        std::fs::File::open(path)?.sync_all()
+    }
+    #[cfg(not(unix))]
+    {
+        drop(path);
+        Ok(())
+    }
+}
@@ -135,7 +135,7 @@ pub fn bytes(
         progress.inc_by(out.len());
         hasher.update(out);
         if should_interrupt.load(std::sync::atomic::Ordering::SeqCst) {
-            return Err(std::io::ErrorKind::Interrupted.into());
+            return Err(crate::interrupt::error());
         }
     }
 
@@ -184,6 +184,16 @@ mod write {
                 },
             }
         }
+
+        /// Write the hash of all bytes written so far to `inner` without hashing it, flush `inner` and return the hash.
+        ///
+        /// This is the trailing checksum of most git file formats, after which nothing else is written.
+        pub fn write_trailer(&mut self) -> std::io::Result<git_hash::ObjectId> {
+            let checksum = git_hash::ObjectId::from(self.hash.clone().digest());
+            self.inner.write_all(checksum.as_slice())?;
+            self.inner.flush()?;
+            Ok(checksum)
+        }
     }
 }
 #[cfg(any(feature = "rustsha1", feature = "fast-sha1"))]
@@ -52,7 +52,7 @@ where
 
 /// A wrapper for implementors of [`std::io::Read`] or [`std::io::BufRead`] with interrupt support.
 ///
-/// It fails a [read][`std::io::Read::read`] while an interrupt was requested.
+/// It fails a [read][`std::io::Read::read`] or [fill_buf][`std::io::BufRead::fill_buf`] while an interrupt was requested.
 pub struct Read<'a, R> {
     /// The actual implementor of [`std::io::Read`] to which interrupt support will be added.
     pub inner: R,
@@ -66,7 +66,7 @@ where
 {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         if self.should_interrupt.load(Ordering::Relaxed) {
-            return Err(io::ErrorKind::Interrupted.into());
+            return Err(error());
         }
         self.inner.read(buf)
     }
@@ -77,6 +77,9 @@ where
     R: io::BufRead,
 {
     fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.should_interrupt.load(Ordering::Relaxed) {
+            return Err(error());
+        }
         self.inner.fill_buf()
     }
 
@@ -84,3 +87,37 @@ where
         self.inner.consume(amt)
     }
 }
+
+/// A wrapper for implementors of [`std::io::Write`] with interrupt support.
+///
+/// It fails a [write][`std::io::Write::write`] while an interrupt was requested.
+pub struct Write<'a, W> {
+    /// The actual implementor of [`std::io::Write`] to which interrupt support will be added.
+    pub inner: W,
+    /// The flag to trigger interruption
+    pub should_interrupt: &'a AtomicBool,
+}
+
+impl<'a, W> io::Write for Write<'a, W>
+where
+    W: io::Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.should_interrupt.load(Ordering::Relaxed) {
+            return Err(error());
+        }
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// The error returned by interrupted reads and writes, which is _not_ of kind [`Interrupted`][io::ErrorKind::Interrupted] as
+/// `read_exact()`, `read_line()` and `write_all()` would retry those forever.
+///
+/// Use it to fail other IO operations which observe an interrupt in the same way.
+pub fn error() -> io::Error {
+    io::Error::new(io::ErrorKind::Other, "Interrupted")
+}
@@ -44,6 +44,9 @@ pub use serial::{in_parallel, join};
 mod eager_iter;
 pub use eager_iter::{EagerIter, EagerIterIf};
 
+pub mod threads;
+pub use threads::num_threads;
+
 /// A no-op returning the input _(`desired_chunk_size`, `Some(thread_limit)`, `thread_limit)_ used
 /// when the `parallel` feature toggle is not set.
 #[cfg(not(feature = "parallel"))]
@@ -65,7 +68,7 @@ pub fn optimize_chunk_size_and_thread_limit(
 /// * `thread_limit` is the amount of threads to use at most, if `Some`.
 ///    Otherwise this knowledge will not affect the output of this function.
 /// * `available_threads` is the total amount of threads available, if `Some`.
-///    Otherwise the actual amount of available threads is determined by querying the system.
+///    Otherwise the [default thread limit][threads::default()] is used.
 ///
 /// `Note` that this implementation is available only if the `parallel` feature toggle is set.
 #[cfg(feature = "parallel")]
@@ -75,10 +78,10 @@ pub fn optimize_chunk_size_and_thread_limit(
     thread_limit: Option<usize>,
     available_threads: Option<usize>,
 ) -> (usize, Option<usize>, usize) {
-    let available_threads = available_threads.unwrap_or_else(num_cpus::get);
-    let available_threads = thread_limit
-        .map(|l| if l == 0 { available_threads } else { l })
-        .unwrap_or(available_threads);
+    let available_threads = match thread_limit {
+        Some(limit) if limit > 0 => limit,
+        _ => available_threads.unwrap_or_else(|| threads::default().num_threads()),
+    };
 
     let (lower, upper) = (50, 1000);
     let (chunk_size, thread_limit) = num_items
@@ -109,23 +112,6 @@ pub fn optimize_chunk_size_and_thread_limit(
     (chunk_size, Some(thread_limit), thread_limit)
 }
 
-/// Always returns 1, available when the `parallel` feature toggle is unset.
-#[cfg(not(feature = "parallel"))]
-fn num_threads(_thread_limit: Option<usize>) -> usize {
-    1
-}
-
-/// Returns the amount of threads the system can effectively use as the amount of its logical cores.
-///
-/// Only available with the `parallel` feature toggle set.
-#[cfg(feature = "parallel")]
-fn num_threads(thread_limit: Option<usize>) -> usize {
-    let logical_cores = || num_cpus::get();
-    thread_limit
-        .map(|l| if l == 0 { logical_cores() } else { l })
-        .unwrap_or_else(logical_cores)
-}
-
 /// Run [`in_parallel()`] only if the given `condition()` returns true when eagerly evaluated.
 ///
 /// For parameters, see the documentation of [`in_parallel()`]
@@ -0,0 +1,118 @@
+//! Configure the amount of threads to use by all parallel operations which aren't given an explicit `thread_limit`.
+//!
+//! The default is taken from the first of the following that is set:
+//!
+//! * the limit configured with [`set_default()`], which is what applications embedding `gitoxide` should use,
+//! * the [`GITOXIDE_THREADS`][ENV_VAR] environment variable, which is ignored if its value is invalid,
+//! * [`Limit::All`].
+//!
+//! Operations given a `thread_limit` of `Some(n)` with `n > 0` use `n` threads instead.
+//!
+//! The priority of threads, their _niceness_, can't be configured as not all of them are spawned by `gitoxide`, and a lower
+//! priority for some threads would only slow down the ones waiting for them. To keep a machine responsive, lower the
+//! priority of the whole process instead, for example with `nice`, or use [`Limit::AllButOne`].
+use std::{str::FromStr, sync::RwLock};
+
+use once_cell::sync::Lazy;
+
+/// The environment variable to set the default thread limit with, using the values understood by [`Limit::from_str()`].
+pub const ENV_VAR: &str = "GITOXIDE_THREADS";
+
+/// The amount of threads to use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Limit {
+    /// Use as many threads as there are logical cores.
+    All,
+    /// Use one thread less than there are logical cores, but at least one, to leave room for other work on the machine.
+    AllButOne,
+    /// Use exactly the given amount of threads, or as many as there are logical cores if it is 0.
+    Count(usize),
+}
+
+impl Default for Limit {
+    fn default() -> Self {
+        Limit::All
+    }
+}
+
+impl Limit {
+    /// Return the amount of threads to use on this machine, which is always 1 if the `parallel` feature toggle is unset.
+    pub fn num_threads(self) -> usize {
+        if cfg!(not(feature = "parallel")) {
+            return 1;
+        }
+        match self {
+            Limit::All | Limit::Count(0) => logical_cores(),
+            Limit::AllButOne => logical_cores().saturating_sub(1).max(1),
+            Limit::Count(count) => count,
+        }
+    }
+}
+
+/// The error returned by [`Limit::from_str()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The value that couldn't be parsed.
+    pub value: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "'{}' is not a thread limit - expected a number of threads, 'all' or 'soft'",
+            self.value
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl FromStr for Limit {
+    type Err = ParseError;
+
+    /// Parse `all` or `0` as [`Limit::All`], `soft` as [`Limit::AllButOne`] and any other number as [`Limit::Count`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.trim() {
+            "all" | "0" => Limit::All,
+            "soft" => Limit::AllButOne,
+            count => Limit::Count(count.parse().map_err(|_| ParseError { value: s.into() })?),
+        })
+    }
+}
+
+static DEFAULT: Lazy<RwLock<Option<Limit>>> = Lazy::new(Default::default);
+static FROM_ENV: Lazy<Option<Limit>> = Lazy::new(|| std::env::var(ENV_VAR).ok().and_then(|v| v.parse().ok()));
+
+/// Set the thread limit to use by default for the entire process, or unset it to use the environment again with `None`.
+pub fn set_default(limit: impl Into<Option<Limit>>) {
+    *DEFAULT.write().expect("no panics while holding the lock") = limit.into();
+}
+
+/// Return the thread limit to use if an operation isn't given a `thread_limit`, as described in the [module documentation][self].
+pub fn default() -> Limit {
+    DEFAULT
+        .read()
+        .expect("no panics while holding the lock")
+        .or(*FROM_ENV)
+        .unwrap_or_default()
+}
+
+/// Return the amount of threads to use for the given `thread_limit`, which falls back to [`default()`] if it is `None` or 0.
+pub fn num_threads(thread_limit: Option<usize>) -> usize {
+    match thread_limit {
+        Some(limit) if limit > 0 => Limit::Count(limit),
+        _ => default(),
+    }
+    .num_threads()
+}
+
+#[cfg(feature = "parallel")]
+fn logical_cores() -> usize {
+    num_cpus::get()
+}
+
+#[cfg(not(feature = "parallel"))]
+fn logical_cores() -> usize {
+    1
+}
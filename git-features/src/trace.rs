@@ -0,0 +1,211 @@
+//! Observe what gitoxide does with events similar to the ones of `git`'s trace2 facility by [subscribing][subscribe()] to them.
+//!
+//! Events are emitted for [regions][region()] like phases of a fetch, for [child processes][child()] and for [data points][data()],
+//! and all of them belong to the [session][session_id()] of the current process.
+//! Emitting events is cheap as long as there is no subscriber.
+use std::{
+    fmt::Display,
+    process::Command,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use once_cell::sync::Lazy;
+
+/// The environment variable to pass the [session id][session_id()] to child processes, which use it as prefix of their own.
+pub const PARENT_SESSION_ID_ENV: &str = "GIT_TRACE2_PARENT_SID";
+
+/// An event as received by [`Subscriber`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum Event<'a> {
+    /// A [region][region()] was entered.
+    RegionEnter { category: &'static str, label: &'a str },
+    /// A [region][region()] was left after `elapsed` time.
+    RegionLeave {
+        category: &'static str,
+        label: &'a str,
+        elapsed: Duration,
+    },
+    /// A [child process][child()] identified by `id` is about to be spawned, with `command` being its debug representation.
+    ChildStart { id: u64, command: &'a str },
+    /// The [child process][child()] identified by `id` exited with `code` after `elapsed` time, which is `None` if it was
+    /// terminated by a signal.
+    ChildExit {
+        id: u64,
+        code: Option<i32>,
+        elapsed: Duration,
+    },
+    /// A [data point][data()] with a `key` and its `value`.
+    Data {
+        category: &'static str,
+        key: &'static str,
+        value: &'a str,
+    },
+}
+
+/// A receiver of all [events][Event] of this process.
+pub trait Subscriber: Send + Sync {
+    /// Called with each `event` of the session identified by `session_id`, on the thread that emitted it.
+    fn event(&self, session_id: &str, event: &Event<'_>);
+}
+
+impl<F> Subscriber for F
+where
+    F: Fn(&str, &Event<'_>) + Send + Sync,
+{
+    fn event(&self, session_id: &str, event: &Event<'_>) {
+        self(session_id, event)
+    }
+}
+
+type Subscribers = Vec<(usize, Arc<dyn Subscriber>)>;
+
+static SUBSCRIBERS: Lazy<RwLock<Subscribers>> = Lazy::new(Default::default);
+static SUBSCRIBER_COUNT: AtomicUsize = AtomicUsize::new(0);
+static NEXT_SUBSCRIPTION_ID: AtomicUsize = AtomicUsize::new(0);
+static NEXT_CHILD_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Unsubscribes the [`Subscriber`] passed to [`subscribe()`] when dropped.
+#[must_use = "the subscriber is removed once the subscription is dropped"]
+pub struct Subscription(usize);
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let mut subscribers = SUBSCRIBERS.write().expect("no panics while subscribing");
+        subscribers.retain(|(id, _)| *id != self.0);
+        SUBSCRIBER_COUNT.store(subscribers.len(), Ordering::SeqCst);
+    }
+}
+
+/// Receive all events of this process with `subscriber` until the returned [`Subscription`] is dropped.
+pub fn subscribe(subscriber: impl Subscriber + 'static) -> Subscription {
+    let id = NEXT_SUBSCRIPTION_ID.fetch_add(1, Ordering::SeqCst);
+    let mut subscribers = SUBSCRIBERS.write().expect("no panics while subscribing");
+    subscribers.push((id, Arc::new(subscriber)));
+    SUBSCRIBER_COUNT.store(subscribers.len(), Ordering::SeqCst);
+    Subscription(id)
+}
+
+/// Returns true if there is at least one subscriber, which can be used to avoid computing expensive data points.
+pub fn is_enabled() -> bool {
+    SUBSCRIBER_COUNT.load(Ordering::Relaxed) != 0
+}
+
+fn emit(event: &Event<'_>) {
+    if !is_enabled() {
+        return;
+    }
+    let subscribers: Vec<_> = SUBSCRIBERS
+        .read()
+        .expect("no panics while subscribing")
+        .iter()
+        .map(|(_, s)| Arc::clone(s))
+        .collect();
+    for subscriber in subscribers {
+        subscriber.event(session_id(), event);
+    }
+}
+
+/// Return the id of this process's session, which is prefixed with the one of the parent process if it is
+/// set in [`PARENT_SESSION_ID_ENV`].
+///
+/// Like with `git`, it's sent to servers with the `session-id` capability if they support it and it's enabled.
+pub fn session_id() -> &'static str {
+    static SESSION_ID: Lazy<String> = Lazy::new(|| {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let own = format!(
+            "{}.{:06}-P{:08x}",
+            now.as_secs(),
+            now.subsec_micros(),
+            std::process::id()
+        );
+        match std::env::var(PARENT_SESSION_ID_ENV) {
+            Ok(parent) if !parent.is_empty() => format!("{}/{}", parent, own),
+            _ => own,
+        }
+    });
+    &SESSION_ID
+}
+
+/// A region of work which is left when dropped, as created by [`region()`].
+pub struct Region {
+    category: &'static str,
+    label: Option<String>,
+    start: Instant,
+}
+
+impl Drop for Region {
+    fn drop(&mut self) {
+        if let Some(label) = self.label.take() {
+            emit(&Event::RegionLeave {
+                category: self.category,
+                label: &label,
+                elapsed: self.start.elapsed(),
+            });
+        }
+    }
+}
+
+/// Enter a region named `label` within `category`, like `fetch` and `negotiate (round 1)`, which is left once the returned
+/// [`Region`] is dropped.
+pub fn region(category: &'static str, label: impl Display) -> Region {
+    let label = is_enabled().then(|| label.to_string());
+    if let Some(label) = &label {
+        emit(&Event::RegionEnter { category, label });
+    }
+    Region {
+        category,
+        label,
+        start: Instant::now(),
+    }
+}
+
+/// Emit a data point with `key` and `value` within `category`, like the version of the protocol used to talk to a server.
+pub fn data(category: &'static str, key: &'static str, value: impl Display) {
+    if is_enabled() {
+        emit(&Event::Data {
+            category,
+            key,
+            value: &value.to_string(),
+        });
+    }
+}
+
+/// A child process as created by [`child()`].
+pub struct Child {
+    id: u64,
+    start: Instant,
+}
+
+impl Child {
+    /// Indicate that the child process exited with `code`, or `None` if it was terminated by a signal.
+    pub fn exit(self, code: Option<i32>) {
+        emit(&Event::ChildExit {
+            id: self.id,
+            code,
+            elapsed: self.start.elapsed(),
+        });
+    }
+}
+
+/// Prepare `command` to be spawned as child process by passing on the [session id][session_id()], and announce it.
+///
+/// Call [`Child::exit()`] once its exit status is known.
+pub fn child(command: &mut Command) -> Child {
+    command.env(PARENT_SESSION_ID_ENV, session_id());
+    let id = NEXT_CHILD_ID.fetch_add(1, Ordering::Relaxed);
+    if is_enabled() {
+        emit(&Event::ChildStart {
+            id,
+            command: &format!("{:?}", command),
+        });
+    }
+    Child {
+        id,
+        start: Instant::now(),
+    }
+}
@@ -22,10 +22,21 @@ mod impls {
     where
         W: io::Write,
     {
-        /// Create a new instance writing compressed bytes to `inner`.
+        /// Create a new instance writing compressed bytes to `inner`, optimizing for speed over size.
         pub fn new(inner: W) -> deflate::Write<W> {
+            Self::with_level(inner, Compression::fast().level())
+        }
+
+        /// Create a new instance writing compressed bytes to `inner` using the given compression `level`, from 0 for no
+        /// compression to 9 for the best compression.
+        ///
+        /// # Panics
+        ///
+        /// If `level` is larger than 9.
+        pub fn with_level(inner: W, level: u32) -> deflate::Write<W> {
+            assert!(level <= 9, "zlib compression levels range from 0 to 9");
             deflate::Write {
-                compressor: Compress::new(Compression::fast(), true),
+                compressor: Compress::new(Compression::new(level), true),
                 inner,
                 buf: [0; deflate::BUF_SIZE],
             }
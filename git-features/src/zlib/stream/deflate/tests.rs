@@ -64,6 +64,23 @@ mod deflate_stream {
         assert_deflate_buffer(out, b"hello")
     }
 
+    #[test]
+    fn compression_levels() -> Result<(), Box<dyn std::error::Error>> {
+        let data = b"hello world, hello world, hello world".repeat(100);
+        let compress = |level| -> io::Result<Vec<u8>> {
+            let mut w = deflate::Write::with_level(Vec::new(), level);
+            w.write_all(&data)?;
+            w.flush()?;
+            Ok(w.inner)
+        };
+        let (stored, best) = (compress(0)?, compress(9)?);
+        assert!(stored.len() > data.len(), "level 0 stores data without compressing it");
+        assert!(best.len() < data.len() / 10);
+
+        assert_deflate_buffer(stored, &data)?;
+        assert_deflate_buffer(best, &data)
+    }
+
     fn assert_deflate_buffer(out: Vec<u8>, expected: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
         let mut actual = Vec::new();
         InflateReader::from_read(out.as_slice()).read_to_end(&mut actual)?;
@@ -11,3 +11,23 @@ fn size_of_sha1() {
 fn size_of_sha1() {
     assert_eq!(std::mem::size_of::<Sha1>(), 104)
 }
+
+mod write {
+    use std::io::Write;
+
+    use git_features::hash;
+
+    #[test]
+    fn trailer_is_the_hash_of_all_bytes_written_before_it() -> std::io::Result<()> {
+        let mut out = hash::Write::new(Vec::new(), git_hash::Kind::Sha1);
+        out.write_all(b"hello")?;
+        let trailer = out.write_trailer()?;
+
+        let mut expected = hash::Sha1::default();
+        expected.update(b"hello");
+        assert_eq!(trailer, git_hash::ObjectId::from(expected.digest()));
+        assert_eq!(&out.inner[..5], b"hello");
+        assert_eq!(&out.inner[5..], trailer.as_slice(), "the trailer is appended without hashing it");
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+mod read_and_write {
+    use std::{
+        io::{BufRead, Read, Write},
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    use git_features::interrupt;
+
+    #[test]
+    fn reads_fail_once_interrupted() {
+        let should_interrupt = AtomicBool::new(false);
+        let mut read = interrupt::Read {
+            inner: &b"hello\nworld\n"[..],
+            should_interrupt: &should_interrupt,
+        };
+        let mut line = String::new();
+        read.read_line(&mut line).expect("not yet interrupted");
+        assert_eq!(line, "hello\n");
+
+        should_interrupt.store(true, Ordering::SeqCst);
+        assert_eq!(
+            read.read_line(&mut line).unwrap_err().to_string(),
+            "Interrupted",
+            "buffered reads are interrupted as well, without being retried forever"
+        );
+        assert_eq!(read.read_exact(&mut [0; 4]).unwrap_err().to_string(), "Interrupted");
+    }
+
+    #[test]
+    fn writes_fail_once_interrupted() {
+        let should_interrupt = AtomicBool::new(false);
+        let mut write = interrupt::Write {
+            inner: Vec::new(),
+            should_interrupt: &should_interrupt,
+        };
+        write.write_all(b"hello").expect("not yet interrupted");
+
+        should_interrupt.store(true, Ordering::SeqCst);
+        assert_eq!(write.write_all(b"world").unwrap_err().to_string(), "Interrupted");
+        assert_eq!(write.inner, b"hello", "nothing is written after the interruption");
+        write.flush().expect("flushing what was written is always possible");
+    }
+}
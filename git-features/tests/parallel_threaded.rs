@@ -120,3 +120,51 @@ mod optimize_chunk_size_and_thread_limit {
         }
     }
 }
+
+mod threads {
+    use git_features::parallel::{
+        num_threads,
+        threads::{self, Limit},
+    };
+
+    #[test]
+    fn limits_can_be_parsed() {
+        assert_eq!("all".parse(), Ok(Limit::All));
+        assert_eq!("0".parse(), Ok(Limit::All));
+        assert_eq!("soft".parse(), Ok(Limit::AllButOne));
+        assert_eq!(" 3\n".parse(), Ok(Limit::Count(3)));
+        assert!("-1".parse::<Limit>().is_err());
+        assert!("half".parse::<Limit>().is_err());
+    }
+
+    #[test]
+    fn soft_limit_leaves_one_core_but_uses_at_least_one_thread() {
+        let all = Limit::All.num_threads();
+        assert_eq!(Limit::AllButOne.num_threads(), (all - 1).max(1));
+        assert_eq!(Limit::Count(0).num_threads(), all, "0 means no limit");
+        assert_eq!(Limit::Count(7).num_threads(), 7, "counts may exceed the amount of cores");
+    }
+
+    #[test]
+    fn the_default_is_used_unless_a_thread_limit_is_given() {
+        threads::set_default(Limit::Count(3));
+        assert_eq!(threads::default(), Limit::Count(3));
+        assert_eq!(num_threads(None), 3);
+        assert_eq!(num_threads(Some(0)), 3, "0 means the default");
+        assert_eq!(num_threads(Some(5)), 5, "explicit limits always win");
+        assert_eq!(
+            git_features::parallel::optimize_chunk_size_and_thread_limit(50, None, None, None),
+            (50, Some(3), 3)
+        );
+
+        threads::set_default(None);
+        assert_eq!(
+            threads::default(),
+            std::env::var(threads::ENV_VAR)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(Limit::All),
+            "without explicit default, the environment is used"
+        );
+    }
+}
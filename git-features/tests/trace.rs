@@ -0,0 +1,81 @@
+use std::{
+    process::Command,
+    sync::{Arc, Mutex},
+};
+
+use git_features::trace::{self, Event};
+
+type Events = Arc<Mutex<Vec<String>>>;
+
+/// Record events matching `filter` in a format that is easy to compare, as other tests may emit events concurrently.
+fn record(filter: &'static str) -> (trace::Subscription, Events) {
+    let events = Events::default();
+    let subscription = trace::subscribe({
+        let events = events.clone();
+        move |session_id: &str, event: &Event<'_>| {
+            assert_eq!(session_id, trace::session_id());
+            let event = match event {
+                Event::RegionEnter { category, label } => format!("enter {} {}", category, label),
+                Event::RegionLeave { category, label, .. } => format!("leave {} {}", category, label),
+                Event::Data { category, key, value } => format!("data {} {}={}", category, key, value),
+                Event::ChildStart { command, .. } => format!("child-start {}", command),
+                Event::ChildExit { code, .. } => format!("child-exit {:?}", code),
+            };
+            if event.contains(filter) {
+                events.lock().expect("no panic").push(event);
+            }
+        }
+    });
+    (subscription, events)
+}
+
+#[test]
+fn regions_and_data_are_emitted_in_order() {
+    let (subscription, events) = record("regions-test");
+    {
+        let _outer = trace::region("regions-test", "outer");
+        let _inner = trace::region("regions-test", format_args!("round {}", 1));
+        trace::data("regions-test", "answer", 42);
+    }
+    drop(subscription);
+    trace::data("regions-test", "ignored", "not recorded after unsubscribing");
+    assert_eq!(
+        *events.lock().expect("no panic"),
+        vec![
+            "enter regions-test outer",
+            "enter regions-test round 1",
+            "data regions-test answer=42",
+            "leave regions-test round 1",
+            "leave regions-test outer",
+        ]
+    );
+}
+
+#[test]
+fn children_receive_the_session_id() {
+    let (_subscription, events) = record("child-test");
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", "echo $GIT_TRACE2_PARENT_SID; exit 3", "child-test"]);
+    let child = trace::child(&mut cmd);
+    let output = cmd.output().expect("sh is available");
+    child.exit(output.status.code());
+
+    assert_eq!(
+        String::from_utf8(output.stdout).expect("valid UTF-8").trim_end(),
+        trace::session_id()
+    );
+    let events = events.lock().expect("no panic");
+    assert_eq!(events.len(), 1, "only the start contains the command");
+    assert!(events[0].starts_with("child-start"));
+}
+
+#[test]
+fn session_ids_start_with_the_time_and_contain_the_process_id() {
+    let id = trace::session_id();
+    assert!(
+        id.ends_with(&format!("-P{:08x}", std::process::id())),
+        "{} ends with the process id",
+        id
+    );
+    assert_eq!(id, trace::session_id(), "it's the same throughout the process");
+}
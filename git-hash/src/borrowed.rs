@@ -1,4 +1,4 @@
-use std::{convert::TryInto, fmt};
+use std::{convert::TryInto, fmt, io};
 
 use crate::{ObjectId, SIZE_OF_SHA1_DIGEST};
 
@@ -31,15 +31,9 @@ pub struct HexDisplay<'a> {
 
 impl<'a> fmt::Display for HexDisplay<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self.inner.kind() {
-            crate::Kind::Sha1 => {
-                let buf = self.inner.to_sha1_hex();
-                f.write_str(
-                    std::str::from_utf8(&buf[..self.hex_len.min(crate::Kind::Sha1.len_in_hex())])
-                        .expect("hex is always utf8 representable"),
-                )
-            }
-        }
+        let mut buf = [0u8; crate::Kind::longest().len_in_hex()];
+        let hex = self.inner.hex_to_buf(&mut buf);
+        f.write_str(&hex[..self.hex_len.min(hex.len())])
     }
 }
 
@@ -48,9 +42,8 @@ impl fmt::Debug for oid {
         match self.kind() {
             crate::Kind::Sha1 => f.write_str("Sha1(")?,
         }
-        for b in self.as_bytes() {
-            write!(f, "{:02x}", b)?;
-        }
+        let mut buf = [0u8; crate::Kind::longest().len_in_hex()];
+        f.write_str(self.hex_to_buf(&mut buf))?;
         f.write_str(")")
     }
 }
@@ -118,6 +111,21 @@ impl oid {
             hex_len: len,
         }
     }
+
+    /// Write ourselves in hexadecimal notation into `buf` and return the written portion as string, without allocating.
+    ///
+    /// **Panics** if `buf` is shorter than [`len_in_hex()`][crate::Kind::len_in_hex()] of our [kind][oid::kind()].
+    pub fn hex_to_buf<'a>(&self, buf: &'a mut [u8]) -> &'a mut str {
+        let buf = &mut buf[..self.kind().len_in_hex()];
+        hex::encode_to_slice(&self.bytes, buf).expect("to count correctly");
+        std::str::from_utf8_mut(buf).expect("hex is always valid UTF-8")
+    }
+
+    /// Write ourselves to `out` in hexadecimal notation, without allocating.
+    pub fn write_hex_to(&self, mut out: impl io::Write) -> io::Result<()> {
+        let mut buf = [0u8; crate::Kind::longest().len_in_hex()];
+        out.write_all(self.hex_to_buf(&mut buf).as_bytes())
+    }
 }
 
 /// Sha1 specific methods
@@ -166,12 +174,10 @@ impl<'a> From<&'a [u8; SIZE_OF_SHA1_DIGEST]> for &'a oid {
     }
 }
 
-impl fmt::Display for &oid {
+impl fmt::Display for oid {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for b in self.as_bytes() {
-            write!(f, "{:02x}", b)?;
-        }
-        Ok(())
+        let mut buf = [0u8; crate::Kind::longest().len_in_hex()];
+        f.write_str(self.hex_to_buf(&mut buf))
     }
 }
 
@@ -10,6 +10,9 @@ pub use borrowed::oid;
 mod owned;
 pub use owned::ObjectId;
 
+pub mod object_id;
+pub use object_id::Prefix;
+
 #[allow(missing_docs)]
 pub mod decode {
     use std::str::FromStr;
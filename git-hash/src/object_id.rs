@@ -0,0 +1,168 @@
+//! Types to refer to objects by a part of their id.
+use std::{cmp::Ordering, convert::TryFrom, fmt};
+
+use crate::{oid, ObjectId};
+
+/// A partial object id made of the first [`hex_len()`][Prefix::hex_len()] hexadecimal characters of an [`ObjectId`], as used
+/// to abbreviate object ids.
+///
+/// All bits past the prefix are zero, making it the smallest object id it can be a prefix of.
+#[derive(PartialEq, Eq, Hash, Ord, PartialOrd, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Prefix {
+    bytes: ObjectId,
+    hex_len: usize,
+}
+
+///
+pub mod prefix {
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned by [`Prefix::new()`][super::Prefix::new()].
+        #[derive(Debug, PartialEq, Eq)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            TooShort { hex_len: usize } {
+                display("A prefix of {} hexadecimal characters is shorter than the minimum of {}", hex_len, super::Prefix::MIN_HEX_LEN)
+            }
+            TooLong { object_kind: crate::Kind, hex_len: usize } {
+                display("An object of kind {:?} cannot be prefixed by {} hexadecimal characters", object_kind, hex_len)
+            }
+        }
+    }
+
+    ///
+    pub mod from_hex {
+        use quick_error::quick_error;
+
+        quick_error! {
+            /// The error returned by [`Prefix::from_hex()`][super::super::Prefix::from_hex()].
+            #[derive(Debug, PartialEq, Eq)]
+            #[allow(missing_docs)]
+            pub enum Error {
+                TooShort { hex_len: usize } {
+                    display("A prefix of {} hexadecimal characters is shorter than the minimum of {}", hex_len, super::super::Prefix::MIN_HEX_LEN)
+                }
+                TooLong { hex_len: usize } {
+                    display("A prefix of {} hexadecimal characters is longer than any object id", hex_len)
+                }
+                Invalid { c: char, index: usize } {
+                    display("Invalid character {:?} at position {} in hexadecimal prefix", c, index)
+                }
+            }
+        }
+    }
+}
+
+impl Prefix {
+    /// The smallest amount of hexadecimal characters a prefix may have, which is what `git` allows as well.
+    pub const MIN_HEX_LEN: usize = 4;
+
+    /// Create a prefix from the first `hex_len` hexadecimal characters of `id`.
+    pub fn new(id: impl AsRef<oid>, hex_len: usize) -> Result<Self, prefix::Error> {
+        let id = id.as_ref();
+        if hex_len > id.kind().len_in_hex() {
+            return Err(prefix::Error::TooLong {
+                object_kind: id.kind(),
+                hex_len,
+            });
+        }
+        if hex_len < Self::MIN_HEX_LEN {
+            return Err(prefix::Error::TooShort { hex_len });
+        }
+
+        let mut bytes = id.to_owned();
+        let slice = bytes.as_mut_slice();
+        let num_bytes = hex_len / 2;
+        if hex_len % 2 == 1 {
+            slice[num_bytes] &= 0xf0;
+            slice[num_bytes + 1..].iter_mut().for_each(|b| *b = 0);
+        } else {
+            slice[num_bytes..].iter_mut().for_each(|b| *b = 0);
+        }
+        Ok(Prefix { bytes, hex_len })
+    }
+
+    /// Create a prefix from `hex`, the abbreviated hexadecimal form of an object id.
+    pub fn from_hex(hex: &str) -> Result<Self, prefix::from_hex::Error> {
+        use prefix::from_hex::Error;
+        let hex_len = hex.len();
+        if hex_len > crate::Kind::longest().len_in_hex() {
+            return Err(Error::TooLong { hex_len });
+        }
+        if hex_len < Self::MIN_HEX_LEN {
+            return Err(Error::TooShort { hex_len });
+        }
+        if let Some((index, c)) = hex.chars().enumerate().find(|(_, c)| !c.is_ascii_hexdigit()) {
+            return Err(Error::Invalid { c, index });
+        }
+
+        let mut buf = [b'0'; crate::Kind::longest().len_in_hex()];
+        buf[..hex_len].copy_from_slice(hex.as_bytes());
+        buf.make_ascii_lowercase();
+        let bytes = ObjectId::from_hex(&buf).expect("valid hex of the correct length");
+        Ok(Prefix { bytes, hex_len })
+    }
+
+    /// Return the prefix as object id, with all bits past the prefix set to zero.
+    pub fn as_oid(&self) -> &oid {
+        &self.bytes
+    }
+
+    /// Return the amount of hexadecimal characters making up this prefix.
+    pub fn hex_len(&self) -> usize {
+        self.hex_len
+    }
+
+    /// Compare this prefix to the first [`hex_len()`][Prefix::hex_len()] hexadecimal characters of `candidate`, yielding
+    /// [`Ordering::Equal`] if `candidate` starts with this prefix.
+    pub fn cmp_oid(&self, candidate: &oid) -> Ordering {
+        let (prefix, candidate) = (self.bytes.as_bytes(), candidate.as_bytes());
+        let num_bytes = self.hex_len / 2;
+        prefix[..num_bytes].cmp(&candidate[..num_bytes]).then_with(|| {
+            if self.hex_len % 2 == 1 {
+                prefix[num_bytes].cmp(&(candidate[num_bytes] & 0xf0))
+            } else {
+                Ordering::Equal
+            }
+        })
+    }
+}
+
+impl AsRef<oid> for Prefix {
+    fn as_ref(&self) -> &oid {
+        self.as_oid()
+    }
+}
+
+impl TryFrom<&str> for Prefix {
+    type Error = prefix::from_hex::Error;
+
+    fn try_from(hex: &str) -> Result<Self, Self::Error> {
+        Prefix::from_hex(hex)
+    }
+}
+
+impl std::str::FromStr for Prefix {
+    type Err = prefix::from_hex::Error;
+
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        Prefix::from_hex(hex)
+    }
+}
+
+impl From<ObjectId> for Prefix {
+    fn from(id: ObjectId) -> Self {
+        Prefix {
+            hex_len: id.kind().len_in_hex(),
+            bytes: id,
+        }
+    }
+}
+
+impl fmt::Display for Prefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.bytes.to_hex(self.hex_len).fmt(f)
+    }
+}
@@ -15,9 +15,8 @@ impl std::fmt::Debug for ObjectId {
         match self {
             ObjectId::Sha1(_hash) => f.write_str("Sha1(")?,
         }
-        for b in self.as_bytes() {
-            write!(f, "{:02x}", b)?;
-        }
+        let mut buf = [0u8; crate::Kind::longest().len_in_hex()];
+        f.write_str(self.hex_to_buf(&mut buf))?;
         f.write_str(")")
     }
 }
@@ -41,9 +40,9 @@ impl ObjectId {
         }
     }
 
-    /// Write ourselves to `out` in hexadecimal notation
-    pub fn write_hex_to(&self, mut out: impl io::Write) -> io::Result<()> {
-        out.write_all(&self.to_sha1_hex())
+    /// Write ourselves to `out` in hexadecimal notation, without allocating.
+    pub fn write_hex_to(&self, out: impl io::Write) -> io::Result<()> {
+        self.as_ref().write_hex_to(out)
     }
 
     /// The hash of an empty tree
@@ -174,10 +173,7 @@ impl Borrow<crate::oid> for ObjectId {
 
 impl fmt::Display for ObjectId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for b in self.as_bytes() {
-            write!(f, "{:02x}", b)?;
-        }
-        Ok(())
+        fmt::Display::fmt(self.as_ref(), f)
     }
 }
 
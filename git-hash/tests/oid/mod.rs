@@ -13,3 +13,37 @@ mod short_hex {
         );
     }
 }
+
+mod hex_to_buf {
+    #[test]
+    fn writes_hex_into_the_beginning_of_the_buffer() {
+        let id_hex = "0123456789abcdef123456789abcdef123456789";
+        let id = git_hash::ObjectId::from_hex(id_hex.as_bytes()).expect("valid input");
+        let mut buf = [b'x'; 64];
+        assert_eq!(id.hex_to_buf(&mut buf), id_hex);
+        assert_eq!(&buf[40..], &[b'x'; 24][..], "bytes past the hash are left untouched");
+    }
+
+    #[test]
+    #[should_panic]
+    fn panics_if_the_buffer_is_too_small() {
+        let mut buf = [0u8; 39];
+        git_hash::ObjectId::null_sha1().hex_to_buf(&mut buf);
+    }
+}
+
+mod write_hex_to {
+    #[test]
+    fn matches_display() {
+        let id = git_hash::ObjectId::from_hex(b"0123456789abcdef123456789abcdef123456789").expect("valid input");
+        let mut buf = Vec::new();
+        id.write_hex_to(&mut buf).expect("writing to a vec never fails");
+        assert_eq!(buf, id.to_string().as_bytes());
+        assert_eq!(
+            id.to_string(),
+            id.as_ref().to_string(),
+            "owned and borrowed ids display the same"
+        );
+        assert_eq!(format!("{:?}", id), "Sha1(0123456789abcdef123456789abcdef123456789)");
+    }
+}
@@ -0,0 +1,86 @@
+mod new {
+    use git_hash::{object_id::prefix::Error, ObjectId, Prefix};
+
+    #[test]
+    fn zeroes_all_bits_past_the_prefix() {
+        let id = ObjectId::from_hex(b"0123456789abcdef123456789abcdef123456789").expect("valid input");
+        for hex_len in Prefix::MIN_HEX_LEN..=40 {
+            let prefix = Prefix::new(id, hex_len).expect("valid length");
+            assert_eq!(prefix.hex_len(), hex_len);
+            assert_eq!(prefix.to_string(), id.to_hex(hex_len).to_string());
+            assert_eq!(
+                prefix.as_oid().to_string(),
+                format!("{:0<40}", id.to_hex(hex_len).to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn lengths_out_of_bounds_are_rejected() {
+        let id = ObjectId::null_sha1();
+        assert_eq!(Prefix::new(id, 3), Err(Error::TooShort { hex_len: 3 }));
+        assert_eq!(
+            Prefix::new(id, 41),
+            Err(Error::TooLong {
+                object_kind: git_hash::Kind::Sha1,
+                hex_len: 41
+            })
+        );
+    }
+}
+
+mod from_hex {
+    use git_hash::{object_id::prefix::from_hex::Error, Prefix};
+
+    #[test]
+    fn odd_and_even_lengths_and_upper_case() {
+        assert_eq!(Prefix::from_hex("abcde").expect("valid").to_string(), "abcde");
+        assert_eq!(Prefix::from_hex("ABCD").expect("valid").to_string(), "abcd");
+        assert_eq!(
+            Prefix::from_hex("abcde").expect("valid").as_oid().to_string(),
+            "abcde00000000000000000000000000000000000"
+        );
+    }
+
+    #[test]
+    fn invalid_input_is_rejected() {
+        assert_eq!(Prefix::from_hex("abc"), Err(Error::TooShort { hex_len: 3 }));
+        assert_eq!(Prefix::from_hex(&"a".repeat(41)), Err(Error::TooLong { hex_len: 41 }));
+        assert_eq!(Prefix::from_hex("abcg"), Err(Error::Invalid { c: 'g', index: 3 }));
+    }
+}
+
+mod cmp_oid {
+    use std::cmp::Ordering;
+
+    use git_hash::{ObjectId, Prefix};
+
+    fn id(hex: &str) -> ObjectId {
+        ObjectId::from_hex(hex.as_bytes()).expect("valid input")
+    }
+
+    #[test]
+    fn only_the_prefix_is_compared() {
+        let prefix = Prefix::from_hex("abcde").expect("valid");
+        assert_eq!(
+            prefix.cmp_oid(&id("abcde00000000000000000000000000000000000")),
+            Ordering::Equal
+        );
+        assert_eq!(
+            prefix.cmp_oid(&id("abcdeffffffffffffffffffffffffffffffffff0")),
+            Ordering::Equal
+        );
+        assert_eq!(
+            prefix.cmp_oid(&id("abcdf00000000000000000000000000000000000")),
+            Ordering::Less
+        );
+        assert_eq!(
+            prefix.cmp_oid(&id("abcdd00000000000000000000000000000000000")),
+            Ordering::Greater
+        );
+        assert_eq!(
+            prefix.cmp_oid(&id("abcc000000000000000000000000000000000000")),
+            Ordering::Greater
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use std::cmp::Ordering;
+
+use bstr::{BStr, ByteSlice};
+
+use crate::{Entry, Extension, State};
+
+/// Extensions that describe the entries and become invalid once they change.
+const ENTRY_DEPENDENT_EXTENSIONS: &[&[u8; 4]] = &[b"UNTR"];
+
+impl State {
+    /// Return all entries, sorted by path and stage.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Return the cache tree, if present.
+    pub fn tree(&self) -> Option<&crate::cache_tree::Tree> {
+        self.tree.as_ref()
+    }
+
+    /// Return all extensions that aren't decoded, in the order they appear in the file.
+    pub fn extensions(&self) -> &[Extension] {
+        &self.extensions
+    }
+
+    /// Return the entry at `path` with stage 0, which is the only one unless there is a merge conflict.
+    pub fn entry_by_path(&self, path: &BStr) -> Option<&Entry> {
+        self.entries
+            .binary_search_by(|e| cmp(e, path, 0))
+            .ok()
+            .map(|idx| &self.entries[idx])
+    }
+
+    /// Mark all entries whose filesystem metadata [is racy][crate::entry::Stat::is_racy()] in relation to `timestamp`, the
+    /// earliest time the index file can be written at, by setting their size to zero, and return the amount of smudged entries.
+    ///
+    /// This is what git does to entries modified in the same timestamp granularity the index is written in: as their
+    /// metadata won't match the file anymore, they are compared by content until they are added again, instead of being
+    /// considered unchanged after another modification which doesn't change the metadata. Submodules are skipped.
+    pub fn smudge_racy_entries(&mut self, timestamp: crate::entry::Time) -> usize {
+        let mut num_smudged = 0;
+        for entry in self
+            .entries
+            .iter_mut()
+            .filter(|e| e.mode != crate::entry::Mode::Commit && e.stat.size != 0 && e.stat.is_racy(timestamp))
+        {
+            entry.stat.size = 0;
+            num_smudged += 1;
+        }
+        num_smudged
+    }
+
+    /// Insert `entry` or replace the one with the same path and stage.
+    ///
+    /// Like `git add`, an entry at stage 0 resolves a conflict by removing all other stages of its path, and entries it conflicts
+    /// with in the work tree are removed: the ones at its leading directories and the ones below it, if it was a directory.
+    ///
+    /// Replacing an entry only to update its filesystem metadata keeps the cache tree valid.
+    pub fn upsert(&mut self, entry: Entry) {
+        let path = entry.path.clone();
+        let mut changed = false;
+        if entry.flags.stage == 0 {
+            let path = path.as_slice();
+            let len = self.entries.len();
+            self.entries.retain(|e| {
+                let other = e.path.as_slice();
+                let is_parent = path.len() > other.len() && path.starts_with(other) && path[other.len()] == b'/';
+                let is_child = other.len() > path.len() && other.starts_with(path) && other[path.len()] == b'/';
+                !(other == path && e.flags.stage != 0 || is_parent || is_child)
+            });
+            changed = self.entries.len() != len;
+        }
+        match self
+            .entries
+            .binary_search_by(|e| cmp(e, entry.path.as_bstr(), entry.flags.stage))
+        {
+            Ok(idx) => {
+                let previous = &self.entries[idx];
+                changed |= previous.id != entry.id
+                    || previous.mode != entry.mode
+                    || previous.flags.intent_to_add != entry.flags.intent_to_add;
+                self.entries[idx] = entry;
+            }
+            Err(idx) => {
+                changed = true;
+                self.entries.insert(idx, entry);
+            }
+        }
+        if changed {
+            self.invalidate_extensions(path.as_bstr());
+        }
+    }
+
+    /// Remove all entries at `path`, at all stages, and return true if there was at least one.
+    pub fn remove(&mut self, path: &BStr) -> bool {
+        let len = self.entries.len();
+        self.entries.retain(|e| e.path != path);
+        let removed = self.entries.len() != len;
+        if removed {
+            self.invalidate_extensions(path);
+        }
+        removed
+    }
+
+    /// Invalidate all extensions that depend on the entry at `path`.
+    pub(crate) fn invalidate_extensions(&mut self, path: &BStr) {
+        if let Some(tree) = &mut self.tree {
+            tree.invalidate(path);
+        }
+        self.extensions
+            .retain(|e| !ENTRY_DEPENDENT_EXTENSIONS.contains(&&e.signature));
+    }
+}
+
+fn cmp(entry: &Entry, path: &BStr, stage: u8) -> Ordering {
+    entry
+        .path
+        .as_bstr()
+        .cmp(path)
+        .then_with(|| entry.flags.stage.cmp(&stage))
+}
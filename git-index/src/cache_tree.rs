@@ -0,0 +1,147 @@
+//! The cache tree extension (`TREE`), which remembers the ids of the trees that index entries were last written to, so that
+//! writing a tree from the index only has to write the trees of directories that changed since.
+use std::{cmp::Ordering, convert::TryInto};
+
+use bstr::{BStr, BString, ByteSlice};
+use git_hash::ObjectId;
+
+/// The signature of the cache tree extension.
+pub(crate) const SIGNATURE: [u8; 4] = *b"TREE";
+
+/// A directory of the index along with the id of the tree written for it, if it didn't change since.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tree {
+    /// The name of the directory, which is empty for the root.
+    pub name: BString,
+    /// The id of the tree written for all index entries in this directory, or `None` if entries were changed since.
+    pub id: Option<ObjectId>,
+    /// The amount of index entries in this directory and below. Only meaningful if `id` is set.
+    pub num_entries: usize,
+    /// The subdirectories, ordered by the length of their name and then by name like git does.
+    pub children: Vec<Tree>,
+}
+
+impl Tree {
+    /// Mark this tree as well as all of its children leading to `path` as changed, along with the tree at `path` itself if
+    /// it is a directory.
+    pub fn invalidate(&mut self, path: &BStr) {
+        self.id = None;
+        let mut tree = self;
+        for component in path.split_str("/") {
+            match tree.children.iter_mut().find(|child| child.name == component) {
+                Some(child) => {
+                    child.id = None;
+                    tree = child;
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Place `tree` at the directory `path`, replacing what was there, and mark all trees leading to it as changed, creating
+    /// the ones that don't exist yet.
+    pub fn insert(&mut self, path: &BStr, tree: Tree) {
+        self.id = None;
+        let mut parent = self;
+        let mut components = path.split_str("/").peekable();
+        while let Some(component) = components.next() {
+            let pos = match parent.children.iter().position(|child| child.name == component) {
+                Some(pos) => pos,
+                None => {
+                    parent.children.push(Tree {
+                        name: component.into(),
+                        id: None,
+                        num_entries: 0,
+                        children: Vec::new(),
+                    });
+                    parent.children.sort_by(cmp);
+                    parent
+                        .children
+                        .iter()
+                        .position(|child| child.name == component)
+                        .expect("just inserted")
+                }
+            };
+            let child = &mut parent.children[pos];
+            if components.peek().is_none() {
+                *child = Tree {
+                    name: component.into(),
+                    ..tree
+                };
+                return;
+            }
+            child.id = None;
+            parent = child;
+        }
+    }
+
+    /// Return the child with `name`, if present.
+    pub fn child(&self, name: &BStr) -> Option<&Tree> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    pub(crate) fn from_bytes(data: &[u8], hash_kind: git_hash::Kind) -> Option<Tree> {
+        let mut data = data;
+        let tree = decode(&mut data, hash_kind)?;
+        if data.is_empty() {
+            Some(tree)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn write_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.name);
+        out.push(0);
+        match self.id {
+            Some(id) => {
+                out.extend_from_slice(format!("{} {}\n", self.num_entries, self.children.len()).as_bytes());
+                out.extend_from_slice(id.as_slice());
+            }
+            None => out.extend_from_slice(format!("-1 {}\n", self.children.len()).as_bytes()),
+        }
+        for child in &self.children {
+            child.write_to(out);
+        }
+    }
+}
+
+/// Order trees by the length of their name first, and by name second, which is the order git keeps them in.
+pub(crate) fn cmp(a: &Tree, b: &Tree) -> Ordering {
+    a.name.len().cmp(&b.name.len()).then_with(|| a.name.cmp(&b.name))
+}
+
+fn decode(data: &mut &[u8], hash_kind: git_hash::Kind) -> Option<Tree> {
+    let hash_len = match hash_kind {
+        git_hash::Kind::Sha1 => git_hash::SIZE_OF_SHA1_DIGEST,
+    };
+    let mut until = |delimiter: u8| -> Option<&[u8]> {
+        let pos = data.find_byte(delimiter)?;
+        let (bytes, rest) = data.split_at(pos);
+        *data = &rest[1..];
+        Some(bytes)
+    };
+    let name = until(0)?.into();
+    let num_entries: i64 = until(b' ')?.to_str().ok()?.parse().ok()?;
+    let num_children: usize = until(b'\n')?.to_str().ok()?.parse().ok()?;
+    let (id, num_entries) = match num_entries.try_into() {
+        Ok(num_entries) => {
+            if data.len() < hash_len {
+                return None;
+            }
+            let (id, rest) = data.split_at(hash_len);
+            *data = rest;
+            (Some(ObjectId::from(id)), num_entries)
+        }
+        Err(_) => (None, 0),
+    };
+    let children = (0..num_children)
+        .map(|_| decode(data, hash_kind))
+        .collect::<Option<_>>()?;
+    Some(Tree {
+        name,
+        id,
+        num_entries,
+        children,
+    })
+}
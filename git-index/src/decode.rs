@@ -0,0 +1,194 @@
+use std::convert::TryInto;
+
+use bstr::{BString, ByteSlice};
+use git_hash::ObjectId;
+
+use crate::{
+    entry::{Flags, Mode, Stat, Time},
+    Entry, Extension, State, Version,
+};
+
+/// The error returned by [`State::from_bytes()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The index file is truncated or corrupt")]
+    Corrupt,
+    #[error("Not an index file")]
+    Signature,
+    #[error("Index file version {version} is not supported")]
+    UnsupportedVersion { version: u32 },
+    #[error("Entry for path '{path}' has invalid mode {mode:o}")]
+    InvalidMode { path: BString, mode: u32 },
+    #[error("The mandatory extension '{}' is not supported", .signature.as_bstr())]
+    MandatoryExtension { signature: [u8; 4] },
+    #[error("The checksum of the index file doesn't match its content")]
+    Checksum,
+}
+
+impl State {
+    /// Decode the index file in `data`, with object ids of kind `hash_kind`, after verifying its checksum.
+    pub fn from_bytes(data: &[u8], hash_kind: git_hash::Kind) -> Result<Self, Error> {
+        let hash_len = match hash_kind {
+            git_hash::Kind::Sha1 => git_hash::SIZE_OF_SHA1_DIGEST,
+        };
+        if data.len() < 12 + hash_len {
+            return Err(Error::Corrupt);
+        }
+        let (data, checksum) = data.split_at(data.len() - hash_len);
+        let mut hasher = git_features::hash::hasher(hash_kind);
+        hasher.update(data);
+        if hasher.digest()[..] != *checksum {
+            return Err(Error::Checksum);
+        }
+
+        let mut input = Input(data);
+        if input.bytes(4)? != crate::SIGNATURE {
+            return Err(Error::Signature);
+        }
+        let version = match input.u32()? {
+            2 => Version::V2,
+            3 => Version::V3,
+            4 => Version::V4,
+            version => return Err(Error::UnsupportedVersion { version }),
+        };
+        let num_entries = input.u32()? as usize;
+        let mut entries = Vec::<Entry>::with_capacity(num_entries);
+        for _ in 0..num_entries {
+            let start = input.0.len();
+            let time = |input: &mut Input<'_>| -> Result<Time, Error> {
+                Ok(Time {
+                    secs: input.u32()?,
+                    nsecs: input.u32()?,
+                })
+            };
+            let ctime = time(&mut input)?;
+            let mtime = time(&mut input)?;
+            let dev = input.u32()?;
+            let ino = input.u32()?;
+            let mode = input.u32()?;
+            let stat = Stat {
+                ctime,
+                mtime,
+                dev,
+                ino,
+                uid: input.u32()?,
+                gid: input.u32()?,
+                size: input.u32()?,
+            };
+            let id = ObjectId::from(input.bytes(hash_len)?);
+            let flags = input.u16()?;
+            let extended = if flags & 0x4000 != 0 {
+                if version == Version::V2 {
+                    return Err(Error::Corrupt);
+                }
+                input.u16()?
+            } else {
+                0
+            };
+            let path = if version == Version::V4 {
+                let strip = input.varint()?;
+                let previous = entries.last().map_or(&[][..], |e| e.path.as_slice());
+                let keep = previous.len().checked_sub(strip).ok_or(Error::Corrupt)?;
+                let mut path = BString::from(&previous[..keep]);
+                path.extend_from_slice(input.until_nul()?);
+                path
+            } else {
+                let name_len = match (flags & 0xfff) as usize {
+                    0xfff => input.0.find_byte(0).ok_or(Error::Corrupt)?,
+                    len => len,
+                };
+                let path = input.bytes(name_len)?;
+                let consumed = start - input.0.len();
+                let padding = 8 - consumed % 8;
+                input.bytes(padding)?;
+                path.into()
+            };
+            entries.push(Entry {
+                stat,
+                id,
+                mode: Mode::from_u32(mode).ok_or_else(|| Error::InvalidMode {
+                    path: path.clone(),
+                    mode,
+                })?,
+                flags: Flags {
+                    stage: ((flags >> 12) & 0b11) as u8,
+                    assume_valid: flags & 0x8000 != 0,
+                    skip_worktree: extended & 0x4000 != 0,
+                    intent_to_add: extended & 0x2000 != 0,
+                },
+                path,
+            });
+        }
+
+        let mut tree = None;
+        let mut extensions = Vec::new();
+        while !input.0.is_empty() {
+            let signature: [u8; 4] = input.bytes(4)?.try_into().expect("four bytes");
+            let len = input.u32()? as usize;
+            let data = input.bytes(len)?;
+            if signature == crate::cache_tree::SIGNATURE {
+                tree = Some(crate::cache_tree::Tree::from_bytes(data, hash_kind).ok_or(Error::Corrupt)?);
+                continue;
+            }
+            if signature[0].is_ascii_lowercase() {
+                return Err(Error::MandatoryExtension { signature });
+            }
+            extensions.push(Extension {
+                signature,
+                data: data.to_owned(),
+            });
+        }
+
+        Ok(State {
+            version,
+            entries,
+            tree,
+            extensions,
+        })
+    }
+}
+
+struct Input<'a>(&'a [u8]);
+
+impl<'a> Input<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < len {
+            return Err(Error::Corrupt);
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_be_bytes(self.bytes(4)?.try_into().expect("four bytes")))
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_be_bytes(self.bytes(2)?.try_into().expect("two bytes")))
+    }
+
+    /// Return the bytes up to the next NUL byte, consuming the NUL byte as well.
+    fn until_nul(&mut self) -> Result<&'a [u8], Error> {
+        let pos = self.0.find_byte(0).ok_or(Error::Corrupt)?;
+        let bytes = self.bytes(pos)?;
+        self.bytes(1)?;
+        Ok(bytes)
+    }
+
+    /// Decode a variable length integer as used by git for offsets.
+    fn varint(&mut self) -> Result<usize, Error> {
+        let mut byte = self.bytes(1)?[0];
+        let mut value = (byte & 0x7f) as usize;
+        while byte & 0x80 != 0 {
+            byte = self.bytes(1)?[0];
+            value = value
+                .checked_add(1)
+                .and_then(|v| v.checked_mul(128))
+                .ok_or(Error::Corrupt)?
+                + (byte & 0x7f) as usize;
+        }
+        Ok(value)
+    }
+}
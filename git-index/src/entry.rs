@@ -0,0 +1,157 @@
+//! Entries of the index, one per path and stage.
+use bstr::BString;
+use git_hash::ObjectId;
+
+/// A point in time as stored in the index, with seconds since the unix epoch.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Time {
+    /// The seconds since the unix epoch.
+    pub secs: u32,
+    /// The nanoseconds on top of `secs`.
+    pub nsecs: u32,
+}
+
+/// The filesystem metadata of a file as it was when it was last added to the index, used to quickly tell if it may have changed.
+///
+/// All values are truncated to 32 bits.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Stat {
+    /// The time the metadata of the file last changed.
+    pub ctime: Time,
+    /// The time the content of the file last changed.
+    pub mtime: Time,
+    /// The device the file is on.
+    pub dev: u32,
+    /// The inode of the file.
+    pub ino: u32,
+    /// The id of the user owning the file.
+    pub uid: u32,
+    /// The id of the group owning the file.
+    pub gid: u32,
+    /// The size of the file in bytes.
+    pub size: u32,
+}
+
+impl Time {
+    /// Return true if both times are the same, comparing nanoseconds only if both have them as filesystems and implementations
+    /// not supporting them store zero.
+    pub fn matches(&self, other: &Time) -> bool {
+        self.secs == other.secs && (self.nsecs == 0 || other.nsecs == 0 || self.nsecs == other.nsecs)
+    }
+}
+
+impl Stat {
+    /// Return true if `other`, the metadata of the file as observed in the work tree, matches this one, which means the file is
+    /// considered unchanged unless this metadata [is racy][Stat::is_racy()].
+    pub fn matches(&self, other: &Stat) -> bool {
+        self.mtime.matches(&other.mtime)
+            && self.ctime.matches(&other.ctime)
+            && self.dev == other.dev
+            && self.ino == other.ino
+            && self.uid == other.uid
+            && self.gid == other.gid
+            && self.size == other.size
+    }
+
+    /// Return true if this metadata was recorded by an index written at `timestamp`, the modification time of the index file,
+    /// and the file may have been modified once more within the same timestamp granularity without its metadata reflecting it.
+    ///
+    /// Such entries are only known to be unchanged after comparing their content. Nanoseconds are only compared if both times
+    /// have them, and seconds alone are conservatively considered racy if they are equal.
+    pub fn is_racy(&self, timestamp: Time) -> bool {
+        let mtime = self.mtime;
+        timestamp.secs < mtime.secs
+            || (timestamp.secs == mtime.secs
+                && (timestamp.nsecs == 0 || mtime.nsecs == 0 || timestamp.nsecs <= mtime.nsecs))
+    }
+}
+
+/// The kind of an entry, which is the same as the mode of the corresponding entry in a tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Mode {
+    /// A regular file.
+    File,
+    /// A regular file with the executable bit set.
+    FileExecutable,
+    /// A symbolic link whose target is the content of the blob.
+    Symlink,
+    /// A submodule, with the entry pointing to a commit.
+    Commit,
+}
+
+impl Mode {
+    /// Return the mode as stored in the index file.
+    pub fn to_u32(self) -> u32 {
+        match self {
+            Mode::File => 0o100644,
+            Mode::FileExecutable => 0o100755,
+            Mode::Symlink => 0o120000,
+            Mode::Commit => 0o160000,
+        }
+    }
+
+    /// Parse the mode as stored in the index file, or return `None` if it's not a valid mode.
+    ///
+    /// Like git, regular files are only distinguished by their executable bit.
+    pub fn from_u32(mode: u32) -> Option<Self> {
+        Some(match mode & 0o170000 {
+            0o100000 if mode & 0o100 != 0 => Mode::FileExecutable,
+            0o100000 => Mode::File,
+            0o120000 => Mode::Symlink,
+            0o160000 => Mode::Commit,
+            _ => return None,
+        })
+    }
+}
+
+/// Flags of an entry that aren't derived from its path.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Flags {
+    /// The stage of the entry, which is 0 unless it's part of a merge conflict. 1 is the common ancestor, 2 is 'ours'
+    /// and 3 is 'theirs'.
+    pub stage: u8,
+    /// If true, the file is considered unchanged without looking at the work tree.
+    pub assume_valid: bool,
+    /// If true, the file isn't expected in the work tree, as set by sparse checkouts.
+    pub skip_worktree: bool,
+    /// If true, the entry is only a placeholder for a file that is going to be added, as set by `git add -N`.
+    pub intent_to_add: bool,
+}
+
+impl Flags {
+    /// Return true if flags are set that can only be stored in index files of version 3 or higher.
+    pub fn is_extended(&self) -> bool {
+        self.skip_worktree || self.intent_to_add
+    }
+}
+
+/// A tracked path along with the object id of its content.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Entry {
+    /// The filesystem metadata of the file when it was last added.
+    pub stat: Stat,
+    /// The id of the blob with the content of the file, or the commit of a submodule.
+    pub id: ObjectId,
+    /// The kind of the entry.
+    pub mode: Mode,
+    /// Flags of the entry.
+    pub flags: Flags,
+    /// The slash-separated path relative to the work tree.
+    pub path: BString,
+}
+
+impl Entry {
+    /// Create an entry for `path` with the given `mode` and `id` without any filesystem metadata, like
+    /// `git update-index --cacheinfo` does.
+    ///
+    /// As its metadata doesn't match the file in the work tree, the file will be compared by content when needed.
+    pub fn from_cacheinfo(mode: Mode, id: ObjectId, path: impl Into<BString>) -> Self {
+        Entry {
+            stat: Stat::default(),
+            id,
+            mode,
+            flags: Flags::default(),
+            path: path.into(),
+        }
+    }
+}
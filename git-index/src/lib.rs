@@ -1 +1,61 @@
-#![forbid(unsafe_code, rust_2018_idioms)]
+//! Read and write the git index file, also known as staging area or cache, which lists all tracked files along with the
+//! object id of their content and the filesystem metadata observed when they were last added.
+//!
+//! Versions 2 to 4 of the [format](https://git-scm.com/docs/index-format) are supported. The [cache tree][cache_tree::Tree]
+//! is decoded to speed up [writing trees][State::write_tree()], while other extensions are kept as opaque [blobs][Extension]
+//! and written back as they were, except for the ones that depend on the entries, which are dropped once entries change.
+#![forbid(unsafe_code)]
+#![deny(rust_2018_idioms, missing_docs)]
+
+mod access;
+pub mod cache_tree;
+///
+pub mod decode;
+pub mod entry;
+///
+pub mod read_tree;
+mod write;
+///
+pub mod write_tree;
+
+pub use entry::Entry;
+
+/// The signature at the beginning of every index file.
+const SIGNATURE: &[u8] = b"DIRC";
+
+/// The version of the index file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Version {
+    /// The original format, with entries padded to multiples of 8 bytes.
+    V2 = 2,
+    /// Like `V2`, but with additional flags per entry, which are needed for [`entry::Flags::skip_worktree`] and
+    /// [`entry::Flags::intent_to_add`].
+    V3 = 3,
+    /// Like `V3`, but without padding and with paths stored relative to the path of the previous entry.
+    V4 = 4,
+}
+
+impl Default for Version {
+    fn default() -> Self {
+        Version::V2
+    }
+}
+
+/// An extension of the index file which isn't decoded, like the resolve-undo information (`REUC`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Extension {
+    /// The four bytes identifying the extension. An uppercase first byte marks it as optional.
+    pub signature: [u8; 4],
+    /// The data of the extension, not including its signature and size.
+    pub data: Vec<u8>,
+}
+
+/// The in-memory representation of an index file.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct State {
+    /// The version to write the file with. It is raised automatically if entries need extended flags.
+    pub version: Version,
+    entries: Vec<Entry>,
+    tree: Option<cache_tree::Tree>,
+    extensions: Vec<Extension>,
+}
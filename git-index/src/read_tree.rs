@@ -0,0 +1,132 @@
+use bstr::{BStr, BString, ByteSlice};
+use git_hash::oid;
+use git_object::tree::EntryMode;
+use git_odb::{pack, FindExt};
+
+use crate::{cache_tree, entry::Mode, Entry, State};
+
+/// The error returned by [`State::from_tree()`] and [`State::read_tree()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error<E: std::error::Error + 'static> {
+    #[error(transparent)]
+    Find(#[from] pack::find::existing_iter::Error<E>),
+    #[error(transparent)]
+    Decode(#[from] git_object::decode::Error),
+    #[error("Cannot read a tree into '{prefix}' as there are entries in the way")]
+    PrefixExists { prefix: BString },
+}
+
+impl State {
+    /// Create an index with entries for all files in `tree`, obtaining all trees from `odb`, like `git read-tree` does.
+    ///
+    /// The entries don't have any filesystem metadata, and the [cache tree][State::tree()] knows all trees.
+    pub fn from_tree<F>(tree: &oid, odb: &F) -> Result<Self, Error<F::Error>>
+    where
+        F: git_odb::Find,
+    {
+        let mut entries = Vec::new();
+        let tree = read_dir(tree, BString::default(), "".into(), odb, &mut entries)?;
+        Ok(State {
+            version: Default::default(),
+            entries,
+            tree: Some(tree),
+            extensions: Vec::new(),
+        })
+    }
+
+    /// Replace all entries with the ones of `tree`, or if `prefix` is set, add them to the entries below the slash-separated
+    /// `prefix` directory, like `git read-tree --prefix` does. All trees are obtained from `odb`.
+    ///
+    /// With `prefix`, it's an error if there are entries at or below it, or at one of its leading directories. The cache tree
+    /// learns the trees below `prefix` so only the ones leading to it have to be written again.
+    pub fn read_tree<F>(&mut self, tree: &oid, prefix: Option<&BStr>, odb: &F) -> Result<(), Error<F::Error>>
+    where
+        F: git_odb::Find,
+    {
+        let prefix = match prefix.map(|p| p.trim_end_with(|c| c == '/')).filter(|p| !p.is_empty()) {
+            Some(prefix) => prefix,
+            None => {
+                *self = State {
+                    version: self.version,
+                    ..State::from_tree(tree, odb)?
+                };
+                return Ok(());
+            }
+        };
+        let is_in_the_way = |path: &[u8]| {
+            let (shorter, longer) = if path.len() < prefix.len() {
+                (path, prefix)
+            } else {
+                (prefix, path)
+            };
+            longer.starts_with(shorter) && (longer.len() == shorter.len() || longer[shorter.len()] == b'/')
+        };
+        if self.entries.iter().any(|e| is_in_the_way(&e.path)) {
+            return Err(Error::PrefixExists { prefix: prefix.into() });
+        }
+
+        let mut path = BString::from(prefix);
+        path.push(b'/');
+        let mut entries = Vec::new();
+        let subtree = read_dir(tree, path, "".into(), odb, &mut entries)?;
+        self.entries.extend(entries);
+        self.entries
+            .sort_by(|a, b| a.path.cmp(&b.path).then_with(|| a.flags.stage.cmp(&b.flags.stage)));
+        self.invalidate_extensions(prefix.as_bstr());
+        self.tree
+            .get_or_insert_with(|| cache_tree::Tree {
+                name: Default::default(),
+                id: None,
+                num_entries: 0,
+                children: Vec::new(),
+            })
+            .insert(prefix.as_bstr(), subtree);
+        Ok(())
+    }
+}
+
+/// Add entries for all files in `tree` to `entries`, prefixing their path with `prefix`, and return the cache tree for the
+/// directory `name`.
+fn read_dir<F>(
+    tree: &oid,
+    prefix: BString,
+    name: &BStr,
+    odb: &F,
+    entries: &mut Vec<Entry>,
+) -> Result<cache_tree::Tree, Error<F::Error>>
+where
+    F: git_odb::Find,
+{
+    let mut buf = Vec::new();
+    let items = odb
+        .find_tree_iter(tree, &mut buf, &mut pack::cache::Never)?
+        .map(|entry| entry.map(|e| (e.mode, BString::from(e.filename), e.oid.to_owned())))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let num_entries_before = entries.len();
+    let mut children = Vec::new();
+    for (mode, filename, id) in items {
+        let mut path = prefix.clone();
+        path.extend_from_slice(&filename);
+        let mode = match mode {
+            EntryMode::Tree => {
+                path.push(b'/');
+                children.push(read_dir(&id, path, filename.as_bstr(), odb, entries)?);
+                continue;
+            }
+            EntryMode::Blob => Mode::File,
+            EntryMode::BlobExecutable => Mode::FileExecutable,
+            EntryMode::Link => Mode::Symlink,
+            EntryMode::Commit => Mode::Commit,
+        };
+        entries.push(Entry::from_cacheinfo(mode, id, path));
+    }
+    children.sort_by(cache_tree::cmp);
+    Ok(cache_tree::Tree {
+        name: name.into(),
+        id: Some(tree.to_owned()),
+        num_entries: entries.len() - num_entries_before,
+        children,
+    })
+}
@@ -0,0 +1,113 @@
+use std::{
+    convert::TryFrom,
+    io::{self, Write},
+};
+
+use crate::{State, Version};
+
+impl State {
+    /// Write this index in the file format to `out`, followed by the checksum of kind `hash_kind`.
+    ///
+    /// The file is written in the [version][State::version] of this instance, or in [`Version::V3`] if it is lower but
+    /// entries have [extended flags][crate::entry::Flags::is_extended()].
+    pub fn write_to(&self, out: impl Write, hash_kind: git_hash::Kind) -> io::Result<()> {
+        let mut out = git_features::hash::Write::new(out, hash_kind);
+        let version = if self.version == Version::V2 && self.entries.iter().any(|e| e.flags.is_extended()) {
+            Version::V3
+        } else {
+            self.version
+        };
+        let too_large = |_| io::Error::new(io::ErrorKind::Other, "Index is too large to be written");
+
+        out.write_all(crate::SIGNATURE)?;
+        out.write_all(&(version as u32).to_be_bytes())?;
+        out.write_all(&u32::try_from(self.entries.len()).map_err(too_large)?.to_be_bytes())?;
+        let mut previous_path: &[u8] = &[];
+        let mut buf = Vec::new();
+        for entry in &self.entries {
+            buf.clear();
+            let stat = &entry.stat;
+            for value in &[
+                stat.ctime.secs,
+                stat.ctime.nsecs,
+                stat.mtime.secs,
+                stat.mtime.nsecs,
+                stat.dev,
+                stat.ino,
+                entry.mode.to_u32(),
+                stat.uid,
+                stat.gid,
+                stat.size,
+            ] {
+                buf.extend_from_slice(&value.to_be_bytes());
+            }
+            buf.extend_from_slice(entry.id.as_slice());
+
+            let flags = &entry.flags;
+            let extended = version >= Version::V3 && flags.is_extended();
+            let mut bits = (entry.path.len().min(0xfff) as u16) | (u16::from(flags.stage & 0b11) << 12);
+            if flags.assume_valid {
+                bits |= 0x8000;
+            }
+            if extended {
+                bits |= 0x4000;
+            }
+            buf.extend_from_slice(&bits.to_be_bytes());
+            if extended {
+                let mut bits = 0u16;
+                if flags.skip_worktree {
+                    bits |= 0x4000;
+                }
+                if flags.intent_to_add {
+                    bits |= 0x2000;
+                }
+                buf.extend_from_slice(&bits.to_be_bytes());
+            }
+
+            if version == Version::V4 {
+                let common = previous_path
+                    .iter()
+                    .zip(entry.path.iter())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                encode_varint(previous_path.len() - common, &mut buf);
+                buf.extend_from_slice(&entry.path[common..]);
+                buf.push(0);
+                previous_path = &entry.path;
+            } else {
+                buf.extend_from_slice(&entry.path);
+                let padding = 8 - buf.len() % 8;
+                buf.resize(buf.len() + padding, 0);
+            }
+            out.write_all(&buf)?;
+        }
+
+        if let Some(tree) = &self.tree {
+            buf.clear();
+            tree.write_to(&mut buf);
+            out.write_all(&crate::cache_tree::SIGNATURE)?;
+            out.write_all(&u32::try_from(buf.len()).map_err(too_large)?.to_be_bytes())?;
+            out.write_all(&buf)?;
+        }
+        for extension in &self.extensions {
+            out.write_all(&extension.signature)?;
+            out.write_all(&u32::try_from(extension.data.len()).map_err(too_large)?.to_be_bytes())?;
+            out.write_all(&extension.data)?;
+        }
+
+        out.write_trailer()?;
+        Ok(())
+    }
+}
+
+/// Encode `value` as variable length integer as used by git for offsets.
+fn encode_varint(mut value: usize, out: &mut Vec<u8>) {
+    let mut bytes = vec![(value & 0x7f) as u8];
+    value >>= 7;
+    while value != 0 {
+        value -= 1;
+        bytes.push(0x80 | (value & 0x7f) as u8);
+        value >>= 7;
+    }
+    out.extend(bytes.iter().rev());
+}
@@ -0,0 +1,129 @@
+use bstr::{BString, ByteSlice};
+use git_hash::ObjectId;
+use git_object::tree;
+
+use crate::{cache_tree, entry::Mode, Entry, State};
+
+/// The error returned by [`State::write_tree()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error<E: std::error::Error + 'static> {
+    #[error("Cannot write a tree while '{path}' is unmerged")]
+    Unmerged { path: BString },
+    #[error(transparent)]
+    Write(E),
+}
+
+impl State {
+    /// Write the trees for all entries into `odb` using `hash_kind` and return the id of the root tree, like `git write-tree`.
+    ///
+    /// Only the trees of directories that changed since the [cache tree][cache_tree::Tree] was last updated are written,
+    /// which is updated to contain all trees afterwards. Entries that are only [intended to be added][crate::entry::Flags::intent_to_add]
+    /// are skipped, and unmerged entries are an error.
+    pub fn write_tree<W>(&mut self, odb: &W, hash_kind: git_hash::Kind) -> Result<ObjectId, Error<W::Error>>
+    where
+        W: git_odb::Write,
+        W::Error: 'static,
+    {
+        if let Some(entry) = self.entries.iter().find(|e| e.flags.stage != 0) {
+            return Err(Error::Unmerged {
+                path: entry.path.clone(),
+            });
+        }
+        let tree = write_dir(&self.entries, 0, BString::default(), self.tree.take(), odb, hash_kind)?;
+        let id = tree.id.expect("written trees are valid");
+        self.tree = Some(tree);
+        Ok(id)
+    }
+}
+
+/// Write the tree for `entries`, all of which are in the directory `name` whose path is `prefix_len` bytes long including
+/// the trailing slash, unless `cached` is still valid.
+fn write_dir<W>(
+    entries: &[Entry],
+    prefix_len: usize,
+    name: BString,
+    cached: Option<cache_tree::Tree>,
+    odb: &W,
+    hash_kind: git_hash::Kind,
+) -> Result<cache_tree::Tree, Error<W::Error>>
+where
+    W: git_odb::Write,
+    W::Error: 'static,
+{
+    let mut cached_children = match cached {
+        Some(cached) if cached.id.is_some() && cached.num_entries == entries.len() => {
+            return Ok(cache_tree::Tree { name, ..cached })
+        }
+        Some(cached) => cached.children,
+        None => Vec::new(),
+    };
+
+    let mut tree_entries = Vec::new();
+    let mut children = Vec::new();
+    let mut idx = 0;
+    while idx < entries.len() {
+        let entry = &entries[idx];
+        let relative_path = &entry.path[prefix_len..];
+        match relative_path.find_byte(b'/') {
+            Some(pos) => {
+                let dir_prefix = &entry.path[..prefix_len + pos + 1];
+                let end = idx
+                    + entries[idx..]
+                        .iter()
+                        .take_while(|e| e.path.starts_with(dir_prefix))
+                        .count();
+                let dir_name = &relative_path[..pos];
+                let cached = cached_children
+                    .iter()
+                    .position(|child| child.name == dir_name)
+                    .map(|pos| cached_children.swap_remove(pos));
+                let child = write_dir(
+                    &entries[idx..end],
+                    dir_prefix.len(),
+                    dir_name.into(),
+                    cached,
+                    odb,
+                    hash_kind,
+                )?;
+                let id = child.id.expect("written trees are valid");
+                if id != ObjectId::empty_tree(hash_kind) {
+                    tree_entries.push(tree::Entry {
+                        mode: tree::EntryMode::Tree,
+                        filename: dir_name.into(),
+                        oid: id,
+                    });
+                }
+                children.push(child);
+                idx = end;
+            }
+            None => {
+                if !entry.flags.intent_to_add {
+                    tree_entries.push(tree::Entry {
+                        mode: match entry.mode {
+                            Mode::File => tree::EntryMode::Blob,
+                            Mode::FileExecutable => tree::EntryMode::BlobExecutable,
+                            Mode::Symlink => tree::EntryMode::Link,
+                            Mode::Commit => tree::EntryMode::Commit,
+                        },
+                        filename: relative_path.into(),
+                        oid: entry.id,
+                    });
+                }
+                idx += 1;
+            }
+        }
+    }
+
+    tree_entries.sort();
+    let id = odb
+        .write(git_object::Tree { entries: tree_entries }, hash_kind)
+        .map_err(Error::Write)?;
+    children.sort_by(cache_tree::cmp);
+    Ok(cache_tree::Tree {
+        name,
+        id: Some(id),
+        num_entries: entries.len(),
+        children,
+    })
+}
@@ -0,0 +1,32 @@
+#!/bin/bash
+set -eu -o pipefail
+
+function make_repo() {
+  git init -q "$1"
+  (cd "$1"
+    git config commit.gpgsign false
+    mkdir -p dir/sub
+    touch a dir/b dir/sub/c
+    echo content > executable && chmod +x executable
+    ln -s a link
+    git add .
+    git commit -q -m c1
+    touch dir/new
+    git add dir/new
+  )
+}
+
+make_repo v2
+
+make_repo v3
+(cd v3 && git update-index --skip-worktree a)
+
+make_repo v4
+(cd v4 && git update-index --index-version 4)
+
+(cd v2
+  git rev-parse HEAD^{tree} > ../v2.head-tree
+  cp .git/index .git/index.copy
+  GIT_INDEX_FILE=.git/index.copy git write-tree > ../v2.tree
+  rm .git/index.copy
+)
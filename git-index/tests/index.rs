@@ -0,0 +1,326 @@
+use std::{cell::Cell, path::PathBuf};
+
+use bstr::ByteSlice;
+use git_hash::ObjectId;
+use git_index::{
+    entry::{Flags, Mode},
+    Entry, State, Version,
+};
+
+type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+fn index_path(name: &str) -> Result<PathBuf> {
+    Ok(git_testtools::scripted_fixture_repo_read_only("make_index.sh")?
+        .join(name)
+        .join(".git")
+        .join("index"))
+}
+
+fn decode(data: &[u8]) -> Result<State> {
+    Ok(State::from_bytes(data, git_hash::Kind::Sha1)?)
+}
+
+fn encode(state: &State) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    state.write_to(&mut buf, git_hash::Kind::Sha1)?;
+    Ok(buf)
+}
+
+#[test]
+fn files_written_by_git_round_trip_in_all_versions() -> Result {
+    for (name, version) in [("v2", Version::V2), ("v3", Version::V3), ("v4", Version::V4)] {
+        let data = std::fs::read(index_path(name)?)?;
+        let state = decode(&data)?;
+        assert_eq!(state.version, version);
+        assert_eq!(
+            state
+                .entries()
+                .iter()
+                .map(|e| e.path.to_str_lossy())
+                .collect::<Vec<_>>(),
+            vec!["a", "dir/b", "dir/new", "dir/sub/c", "executable", "link"]
+        );
+        assert_eq!(
+            state.entries().iter().map(|e| e.mode).collect::<Vec<_>>(),
+            vec![
+                Mode::File,
+                Mode::File,
+                Mode::File,
+                Mode::File,
+                Mode::FileExecutable,
+                Mode::Symlink
+            ]
+        );
+        assert_eq!(
+            state.entries()[0].flags.skip_worktree,
+            version == Version::V3,
+            "only the v3 file has extended flags"
+        );
+        assert!(state.entries()[4].stat.size > 0 && state.entries()[4].stat.mtime.secs > 0);
+        assert!(state.tree().is_some(), "git writes a cache tree");
+        assert!(state.extensions().is_empty());
+        assert_eq!(encode(&state)?, data, "{} is written exactly as git wrote it", name);
+    }
+    Ok(())
+}
+
+#[test]
+fn changed_entries_are_readable_by_git() -> Result {
+    let dir = git_testtools::scripted_fixture_repo_writable("make_index.sh")?;
+    let repo = dir.path().join("v4");
+    let index = repo.join(".git").join("index");
+    let mut state = decode(&std::fs::read(&index)?)?;
+
+    let id = ObjectId::from_hex(b"d95f3ad14dee633a758d2e331151e950dd13e4ed")?;
+    state.upsert(Entry::from_cacheinfo(Mode::FileExecutable, id, "dir"));
+    state.upsert(Entry::from_cacheinfo(Mode::File, id, "b"));
+    assert!(state.remove("link".into()));
+    assert!(!state.remove("link".into()));
+    assert_eq!(state.tree().and_then(|t| t.id), None, "the cache tree is invalidated");
+    assert_eq!(state.entry_by_path("b".into()).map(|e| e.id), Some(id));
+    std::fs::write(&index, encode(&state)?)?;
+
+    let out = std::process::Command::new("git")
+        .args(["ls-files", "-s"])
+        .current_dir(&repo)
+        .output()?;
+    assert!(out.status.success(), "{}", out.stderr.as_bstr());
+    assert_eq!(
+        out.stdout.as_bstr(),
+        "100644 e69de29bb2d1d6434b8b29ae775ad8c2e48c5391 0\ta\n\
+         100644 d95f3ad14dee633a758d2e331151e950dd13e4ed 0\tb\n\
+         100755 d95f3ad14dee633a758d2e331151e950dd13e4ed 0\tdir\n\
+         100755 d95f3ad14dee633a758d2e331151e950dd13e4ed 0\texecutable\n",
+        "paths below 'dir' are replaced by the file 'dir'"
+    );
+    Ok(())
+}
+
+#[test]
+fn long_paths_and_extended_flags_round_trip() -> Result {
+    let long_path = "x".repeat(5000);
+    for version in [Version::V2, Version::V4] {
+        let mut state = State::default();
+        state.version = version;
+        state.upsert(Entry::from_cacheinfo(
+            Mode::File,
+            ObjectId::null_sha1(),
+            long_path.as_str(),
+        ));
+        let mut entry = Entry::from_cacheinfo(Mode::Commit, ObjectId::null_sha1(), "submodule");
+        entry.flags = Flags {
+            intent_to_add: true,
+            ..Default::default()
+        };
+        state.upsert(entry);
+
+        let decoded = decode(&encode(&state)?)?;
+        assert_eq!(
+            decoded.version,
+            version.max(Version::V3),
+            "extended flags need at least version 3"
+        );
+        assert_eq!(decoded.entries(), state.entries());
+    }
+    Ok(())
+}
+
+/// An object database which counts the objects written to it without storing them.
+#[derive(Default)]
+struct CountingSink(Cell<usize>);
+
+impl git_odb::Write for CountingSink {
+    type Error = std::io::Error;
+
+    fn write_stream(
+        &self,
+        kind: git_object::Kind,
+        size: u64,
+        from: impl std::io::Read,
+        hash: git_hash::Kind,
+    ) -> std::result::Result<ObjectId, Self::Error> {
+        self.0.set(self.0.get() + 1);
+        git_odb::sink().write_stream(kind, size, from, hash)
+    }
+}
+
+fn fixture_tree(name: &str) -> Result<ObjectId> {
+    let path = git_testtools::scripted_fixture_repo_read_only("make_index.sh")?.join(name);
+    Ok(ObjectId::from_hex(std::fs::read(path)?.trim())?)
+}
+
+#[test]
+fn write_tree_only_writes_trees_that_changed_since_the_cache_tree_was_written() -> Result {
+    let mut state = decode(&std::fs::read(index_path("v2")?)?)?;
+    let odb = CountingSink::default();
+    let id = state.write_tree(&odb, git_hash::Kind::Sha1)?;
+    assert_eq!(id, fixture_tree("v2.tree")?, "the same tree as 'git write-tree'");
+    assert_eq!(
+        odb.0.get(),
+        2,
+        "'dir/sub' is still valid, only 'dir' and the root are written"
+    );
+
+    let tree = state.tree().expect("cache tree present");
+    assert_eq!(tree.id, Some(id));
+    assert_eq!(tree.num_entries, 6);
+    let dir = tree.child("dir".into()).expect("dir is cached");
+    assert_eq!((dir.num_entries, dir.children.len()), (3, 1));
+
+    let odb = CountingSink::default();
+    assert_eq!(state.write_tree(&odb, git_hash::Kind::Sha1)?, id);
+    assert_eq!(odb.0.get(), 0, "nothing changed");
+
+    let mut refreshed = state.entry_by_path("dir/sub/c".into()).expect("present").clone();
+    refreshed.stat.mtime.secs += 1;
+    state.upsert(refreshed);
+    assert_eq!(
+        state.tree().and_then(|t| t.id),
+        Some(id),
+        "changes to filesystem metadata keep the cache tree valid"
+    );
+
+    state.upsert(Entry::from_cacheinfo(
+        Mode::File,
+        ObjectId::empty_tree(git_hash::Kind::Sha1),
+        "dir/sub/d",
+    ));
+    state.write_tree(&odb, git_hash::Kind::Sha1)?;
+    assert_eq!(odb.0.get(), 3, "all trees leading to the new entry are written");
+
+    let mut unmerged = Entry::from_cacheinfo(Mode::File, ObjectId::null_sha1(), "a");
+    unmerged.flags.stage = 2;
+    state.upsert(unmerged);
+    assert!(matches!(
+        state.write_tree(&odb, git_hash::Kind::Sha1),
+        Err(git_index::write_tree::Error::Unmerged { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn read_tree_creates_entries_and_a_valid_cache_tree() -> Result {
+    let odb = git_odb::linked::Store::at(index_path("v2")?.with_file_name("objects"))?;
+    let head_tree = fixture_tree("v2.head-tree")?;
+    let mut state = State::from_tree(&head_tree, &odb)?;
+    assert_eq!(
+        state
+            .entries()
+            .iter()
+            .map(|e| (e.path.to_str_lossy(), e.mode))
+            .collect::<Vec<_>>(),
+        vec![
+            ("a".into(), Mode::File),
+            ("dir/b".into(), Mode::File),
+            ("dir/sub/c".into(), Mode::File),
+            ("executable".into(), Mode::FileExecutable),
+            ("link".into(), Mode::Symlink)
+        ]
+    );
+    let sink = CountingSink::default();
+    assert_eq!(state.write_tree(&sink, git_hash::Kind::Sha1)?, head_tree);
+    assert_eq!(sink.0.get(), 0, "the cache tree knows all trees");
+
+    state.read_tree(&head_tree, Some("other/".into()), &odb)?;
+    assert_eq!(state.entries().len(), 10);
+    assert_eq!(
+        state.entry_by_path("other/dir/sub/c".into()).map(|e| e.id),
+        state.entry_by_path("dir/sub/c".into()).map(|e| e.id)
+    );
+    assert_eq!(state.tree().and_then(|t| t.id), None, "the cache tree is invalidated");
+    let sink = CountingSink::default();
+    state.write_tree(&sink, git_hash::Kind::Sha1)?;
+    assert_eq!(
+        sink.0.get(),
+        1,
+        "the trees below the prefix are known, only the root is written"
+    );
+
+    for prefix in ["other", "other/dir", "a/b"] {
+        assert!(
+            matches!(
+                state.read_tree(&head_tree, Some(prefix.into()), &odb),
+                Err(git_index::read_tree::Error::PrefixExists { .. })
+            ),
+            "entries at, below or above '{}' are in the way",
+            prefix
+        );
+    }
+
+    state.read_tree(&head_tree, None, &odb)?;
+    assert_eq!(state.entries().len(), 5, "without prefix, all entries are replaced");
+    Ok(())
+}
+
+#[test]
+fn corrupt_files_are_rejected() -> Result {
+    let mut data = std::fs::read(index_path("v2")?)?;
+    data[20] ^= 1;
+    assert!(matches!(
+        State::from_bytes(&data, git_hash::Kind::Sha1),
+        Err(git_index::decode::Error::Checksum)
+    ));
+    assert!(matches!(
+        State::from_bytes(&data[..10], git_hash::Kind::Sha1),
+        Err(git_index::decode::Error::Corrupt)
+    ));
+    Ok(())
+}
+
+#[test]
+fn racy_entries_are_smudged_to_be_compared_by_content() {
+    use git_index::entry::{Stat, Time};
+    let time = |secs, nsecs| Time { secs, nsecs };
+    let entry = |path: &str, mtime, size| Entry {
+        stat: Stat {
+            mtime,
+            size,
+            ..Default::default()
+        },
+        ..Entry::from_cacheinfo(Mode::File, ObjectId::null_sha1(), path)
+    };
+    let timestamp = time(100, 500);
+    assert!(!entry("a", time(99, 900), 1).stat.is_racy(timestamp));
+    assert!(!entry("a", time(100, 400), 1).stat.is_racy(timestamp));
+    assert!(entry("a", time(100, 500), 1).stat.is_racy(timestamp));
+    assert!(entry("a", time(101, 0), 1).stat.is_racy(timestamp));
+    assert!(
+        entry("a", time(100, 0), 1).stat.is_racy(timestamp),
+        "without nanoseconds, the same second is racy"
+    );
+
+    let mut state = State::default();
+    state.upsert(entry("old", time(99, 0), 1));
+    state.upsert(entry("racy", time(100, 600), 1));
+    state.upsert(entry("empty", time(100, 600), 0));
+    state.upsert(Entry {
+        stat: entry("", time(101, 0), 1).stat,
+        ..Entry::from_cacheinfo(Mode::Commit, ObjectId::null_sha1(), "submodule")
+    });
+    assert_eq!(state.smudge_racy_entries(timestamp), 1);
+    let sizes: Vec<_> = state
+        .entries()
+        .iter()
+        .map(|e| (e.path.to_str_lossy(), e.stat.size))
+        .collect();
+    assert_eq!(
+        sizes,
+        vec![
+            ("empty".into(), 0),
+            ("old".into(), 1),
+            ("racy".into(), 0),
+            ("submodule".into(), 1)
+        ],
+        "only racy files are smudged, as empty ones are already compared by content"
+    );
+    assert!(
+        !state.entries()[2].stat.matches(&entry("racy", time(100, 600), 1).stat),
+        "smudged entries never match the file"
+    );
+    assert!(
+        entry("a", time(100, 0), 1)
+            .stat
+            .matches(&entry("a", time(100, 600), 1).stat),
+        "nanoseconds are ignored if one side doesn't have them"
+    );
+}
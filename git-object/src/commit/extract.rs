@@ -0,0 +1,132 @@
+//! Read individual fields from the raw data of a commit without decoding it entirely.
+//!
+//! These functions only look at the header lines they need and are meant for hot paths like graph traversals or the
+//! creation of commit-graph files, which need nothing but the tree, the parents and the commit time.
+//! As no other field is validated, use [`CommitRef::from_bytes()`][crate::CommitRef::from_bytes()] or a
+//! [`CommitRefIter`][crate::CommitRefIter] if the commit as a whole is supposed to be well-formed.
+use git_hash::ObjectId;
+use quick_error::quick_error;
+
+quick_error! {
+    /// The error returned by the field extractors in this module.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        MissingField { name: &'static str } {
+            display("The commit has no '{}' header line", name)
+        }
+        InvalidField { name: &'static str } {
+            display("The value of the '{}' header line could not be decoded", name)
+        }
+    }
+}
+
+/// Return the id of the tree of the commit in `data`, which must be the first header line.
+pub fn tree_id(data: &[u8]) -> Result<ObjectId, Error> {
+    let name = "tree";
+    let line = header_lines(data).next().ok_or(Error::MissingField { name })?;
+    match field_value(line, name) {
+        Some(hex) => parse_id(hex, name),
+        None => Err(Error::MissingField { name }),
+    }
+}
+
+/// Return an iterator over the ids of all parents of the commit in `data`, in order.
+///
+/// Iteration stops at the first header line past the parents, which is never decoded.
+pub fn parent_ids(data: &[u8]) -> ParentIds<'_> {
+    ParentIds {
+        data: match data.iter().position(|b| *b == b'\n') {
+            Some(pos) if field_value(&data[..pos], "tree").is_some() => &data[pos + 1..],
+            _ => data,
+        },
+    }
+}
+
+/// Return the time at which the commit in `data` was committed, as stored in its `committer` header line.
+///
+/// Only the time is decoded, the name and email of the committer are skipped.
+pub fn committer_time(data: &[u8]) -> Result<git_actor::Time, Error> {
+    let name = "committer";
+    let value = header_lines(data)
+        .find_map(|line| field_value(line, name))
+        .ok_or(Error::MissingField { name })?;
+    parse_time(value).ok_or(Error::InvalidField { name })
+}
+
+/// An iterator over the parent ids of a commit, created by [`parent_ids()`].
+pub struct ParentIds<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for ParentIds<'a> {
+    type Item = Result<ObjectId, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let name = "parent";
+        let (line, rest) = match self.data.iter().position(|b| *b == b'\n') {
+            Some(pos) => (&self.data[..pos], &self.data[pos + 1..]),
+            None => (self.data, &[][..]),
+        };
+        match field_value(line, name) {
+            Some(hex) => {
+                self.data = rest;
+                let res = parse_id(hex, name);
+                if res.is_err() {
+                    self.data = &[];
+                }
+                Some(res)
+            }
+            None => {
+                self.data = &[];
+                None
+            }
+        }
+    }
+}
+
+fn header_lines(data: &[u8]) -> impl Iterator<Item = &[u8]> {
+    data.split(|b| *b == b'\n').take_while(|line| !line.is_empty())
+}
+
+fn field_value<'a>(line: &'a [u8], name: &str) -> Option<&'a [u8]> {
+    line.strip_prefix(name.as_bytes())
+        .and_then(|value| value.strip_prefix(b" "))
+}
+
+fn parse_id(hex: &[u8], name: &'static str) -> Result<ObjectId, Error> {
+    if hex.iter().any(|b| b.is_ascii_uppercase()) {
+        return Err(Error::InvalidField { name });
+    }
+    ObjectId::from_hex(hex).map_err(|_| Error::InvalidField { name })
+}
+
+/// Parse the `<seconds> <+|-><hhmm>` suffix of a signature, following the closing `>` of the email.
+fn parse_time(signature: &[u8]) -> Option<git_actor::Time> {
+    let email_end = signature.iter().rposition(|b| *b == b'>')?;
+    let mut tokens = std::str::from_utf8(&signature[email_end + 1..])
+        .ok()?
+        .split(' ')
+        .filter(|token| !token.is_empty());
+    let time = tokens.next()?.parse().ok()?;
+    let offset = tokens.next()?;
+    if tokens.next().is_some() || offset.len() != 5 || !offset.as_bytes()[1..].iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    let sign = match offset.as_bytes()[0] {
+        b'+' => git_actor::Sign::Plus,
+        b'-' => git_actor::Sign::Minus,
+        _ => return None,
+    };
+    let hours: i32 = offset[1..3].parse().ok()?;
+    let minutes: i32 = offset[3..5].parse().ok()?;
+    let offset = hours * 3600 + minutes * 60;
+    Some(git_actor::Time {
+        time,
+        offset: match sign {
+            git_actor::Sign::Plus => offset,
+            git_actor::Sign::Minus => -offset,
+        },
+        sign,
+    })
+}
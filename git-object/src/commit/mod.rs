@@ -3,6 +3,7 @@ use bstr::{BStr, ByteSlice};
 use crate::{Commit, CommitRef, TagRef};
 
 mod decode;
+pub mod extract;
 ///
 pub mod message;
 
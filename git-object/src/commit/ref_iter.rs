@@ -52,6 +52,17 @@ impl<'a> CommitRefIter<'a> {
         self.next().and_then(Result::ok).and_then(Token::into_id)
     }
 
+    /// Returns an iterator over the ids of all parents which reads nothing past the last parent, see
+    /// [`extract::parent_ids()`][crate::commit::extract::parent_ids()].
+    ///
+    /// The iterator is empty if tokens past the parents were already consumed.
+    pub fn parent_ids(self) -> crate::commit::extract::ParentIds<'a> {
+        match self.state {
+            State::Tree | State::Parents => crate::commit::extract::parent_ids(self.data),
+            _ => crate::commit::extract::parent_ids(&[]),
+        }
+    }
+
     /// Returns all signatures, first the author, then the committer, if there is no decoding error.
     ///
     /// Errors are coerced into options, hiding whether there was an error or not. The caller knows if there was an error or not
@@ -0,0 +1,102 @@
+use git_object::{
+    commit::extract::{self, Error},
+    CommitRef, CommitRefIter,
+};
+
+use crate::{
+    hex_to_id,
+    immutable::{fixture_bytes, linus_signature, signature},
+};
+
+#[test]
+fn fields_match_a_full_decode() -> crate::Result {
+    for name in &[
+        "merge.txt",
+        "mergetag.txt",
+        "signed.txt",
+        "signed-with-encoding.txt",
+        "two-multiline-headers.txt",
+        "unsigned.txt",
+        "whitespace.txt",
+    ] {
+        let data = fixture_bytes("commit", name);
+        let commit = CommitRef::from_bytes(&data)?;
+        assert_eq!(extract::tree_id(&data)?, commit.tree(), "{}", name);
+        assert_eq!(
+            extract::parent_ids(&data).collect::<Result<Vec<_>, _>>()?,
+            commit.parents().collect::<Vec<_>>(),
+            "{}",
+            name
+        );
+        assert_eq!(extract::committer_time(&data)?, commit.committer.time, "{}", name);
+    }
+    Ok(())
+}
+
+#[test]
+fn merge() -> crate::Result {
+    let data = fixture_bytes("commit", "merge.txt");
+    assert_eq!(
+        extract::tree_id(&data)?,
+        hex_to_id("0cf16ce8e229b59a761198975f0c0263229faf82")
+    );
+    assert_eq!(
+        extract::parent_ids(&data).collect::<Result<Vec<_>, _>>()?,
+        vec![
+            hex_to_id("6a6054db4ce3c1e4e6a37f8c4d7acb63a4d6ad71"),
+            hex_to_id("c91d592913d47ac4e4a76daf16fd649b276e211e")
+        ]
+    );
+    assert_eq!(extract::committer_time(&data)?, signature(1592454738).time);
+    Ok(())
+}
+
+#[test]
+fn negative_timezone_offset() -> crate::Result {
+    let data = fixture_bytes("commit", "mergetag.txt");
+    assert_eq!(extract::committer_time(&data)?, linus_signature(1591996221).time);
+    Ok(())
+}
+
+#[test]
+fn root_commits_have_no_parents() {
+    let data = fixture_bytes("commit", "unsigned.txt");
+    assert_eq!(extract::parent_ids(&data).count(), 0);
+}
+
+#[test]
+fn parents_from_an_unused_iterator() -> crate::Result {
+    let data = fixture_bytes("commit", "merge.txt");
+    assert_eq!(
+        CommitRefIter::from_bytes(&data)
+            .parent_ids()
+            .collect::<Result<Vec<_>, _>>()?,
+        extract::parent_ids(&data).collect::<Result<Vec<_>, _>>()?
+    );
+    Ok(())
+}
+
+#[test]
+fn invalid_input() {
+    assert_eq!(
+        extract::tree_id(b"parent 0cf16ce8e229b59a761198975f0c0263229faf82\n"),
+        Err(Error::MissingField { name: "tree" })
+    );
+    assert_eq!(
+        extract::tree_id(b"tree 0cf16ce8\n"),
+        Err(Error::InvalidField { name: "tree" })
+    );
+    assert_eq!(
+        extract::parent_ids(b"tree 0cf16ce8e229b59a761198975f0c0263229faf82\nparent xyz\n").collect::<Vec<_>>(),
+        vec![Err(Error::InvalidField { name: "parent" })]
+    );
+    assert_eq!(
+        extract::committer_time(b"tree 0cf16ce8e229b59a761198975f0c0263229faf82\n\ncommitter a <b> 1 +0000\n"),
+        Err(Error::MissingField { name: "committer" }),
+        "header lines end at the first empty line"
+    );
+    assert_eq!(
+        extract::committer_time(b"committer a <b> 1 0000\n"),
+        Err(Error::InvalidField { name: "committer" })
+    );
+}
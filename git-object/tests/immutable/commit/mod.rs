@@ -146,6 +146,7 @@ mod method {
     }
 }
 
+mod extract;
 mod from_bytes;
 mod iter;
 mod message;
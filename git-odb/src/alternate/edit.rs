@@ -0,0 +1,160 @@
+use std::{
+    borrow::Cow,
+    fs, io,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use git_object::bstr::ByteSlice;
+
+use crate::alternate::{parse, unquote};
+
+/// Returned by [`add()`][crate::alternate::add()] and [`remove()`][crate::alternate::remove()].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not read or write the alternates file")]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Parse(#[from] parse::Error),
+    #[error(transparent)]
+    Resolve(#[from] super::Error),
+    #[error("Alternate '{}' can't be written to an alternates file", .0.display())]
+    PathConversion(PathBuf),
+    #[error("The alternate '{}' is not an object directory", .0.display())]
+    NotADirectory(PathBuf),
+    #[error("Using '{}' as alternate of '{}' would form a cycle", .alternate.display(), .objects_directory.display())]
+    Cycle {
+        objects_directory: PathBuf,
+        alternate: PathBuf,
+    },
+    #[error("Could not move the alternates file into place")]
+    Persist(#[from] tempfile::PersistError),
+}
+
+/// Add `alternate` to the alternates of the object database at `objects_directory`, and return `false` if it was listed already.
+///
+/// `alternate` is written as given, so relative paths keep working if all repositories are moved together. They are relative
+/// to `objects_directory` like when reading them. It's an error if `alternate` isn't an existing directory, or if using it
+/// would form a cycle, as `objects_directory` would be an alternate of itself.
+///
+/// The alternates file is replaced atomically, keeping all of its other lines including comments.
+pub fn add(objects_directory: impl AsRef<Path>, alternate: impl AsRef<Path>) -> Result<bool, Error> {
+    let (objects_directory, alternate) = (objects_directory.as_ref(), alternate.as_ref());
+    let alternate_canonicalized = objects_directory
+        .join(alternate)
+        .canonicalize()
+        .ok()
+        .filter(|p| p.is_dir())
+        .ok_or_else(|| Error::NotADirectory(alternate.to_owned()))?;
+    let own = objects_directory.canonicalize()?;
+    let cycle = || Error::Cycle {
+        objects_directory: objects_directory.to_owned(),
+        alternate: alternate.to_owned(),
+    };
+    if alternate_canonicalized == own {
+        return Err(cycle());
+    }
+    for transitive in super::resolve(&alternate_canonicalized)? {
+        if transitive.canonicalize()? == own {
+            return Err(cycle());
+        }
+    }
+
+    let mut content = read(objects_directory)?;
+    for existing in parse::content(&content)? {
+        if existing == alternate
+            || objects_directory.join(existing).canonicalize().ok().as_ref() == Some(&alternate_canonicalized)
+        {
+            return Ok(false);
+        }
+    }
+    if !content.is_empty() && !content.ends_with(b"\n") {
+        content.push(b'\n');
+    }
+    content.extend_from_slice(&quote(alternate)?);
+    content.push(b'\n');
+    write(objects_directory, &content)?;
+    Ok(true)
+}
+
+/// Remove `alternate` from the alternates of the object database at `objects_directory`, and return `false` if it wasn't listed.
+///
+/// Lines are removed if they are the same path as `alternate`, or if both resolve to the same existing directory.
+/// Comments are kept, and the alternates file is removed if nothing else remains.
+pub fn remove(objects_directory: impl AsRef<Path>, alternate: impl AsRef<Path>) -> Result<bool, Error> {
+    let (objects_directory, alternate) = (objects_directory.as_ref(), alternate.as_ref());
+    let alternate_canonicalized = objects_directory.join(alternate).canonicalize().ok();
+    let content = read(objects_directory)?;
+
+    let mut out = Vec::with_capacity(content.len());
+    let mut removed = false;
+    for line in content.lines_with_terminator() {
+        let listed = parse::content(line)?.pop();
+        let is_alternate = listed.map_or(false, |listed| {
+            listed == alternate
+                || (alternate_canonicalized.is_some()
+                    && objects_directory.join(listed).canonicalize().ok() == alternate_canonicalized)
+        });
+        if is_alternate {
+            removed = true;
+        } else {
+            out.extend_from_slice(line);
+        }
+    }
+    if !removed {
+        return Ok(false);
+    }
+    if out.iter().all(u8::is_ascii_whitespace) {
+        fs::remove_file(path(objects_directory))?;
+    } else {
+        write(objects_directory, &out)?;
+    }
+    Ok(true)
+}
+
+fn path(objects_directory: &Path) -> PathBuf {
+    objects_directory.join("info").join("alternates")
+}
+
+fn read(objects_directory: &Path) -> Result<Vec<u8>, Error> {
+    match fs::read(path(objects_directory)) {
+        Ok(content) => Ok(content),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn write(objects_directory: &Path, content: &[u8]) -> Result<(), Error> {
+    let info = objects_directory.join("info");
+    fs::create_dir_all(&info)?;
+    let mut file = tempfile::NamedTempFile::new_in(&info)?;
+    file.write_all(content)?;
+    file.persist(path(objects_directory))?;
+    Ok(())
+}
+
+/// Return `path` as line of an alternates file, quoting it if it would be misread otherwise.
+fn quote(path: &Path) -> Result<Cow<'_, [u8]>, Error> {
+    let bytes = <[u8]>::from_path(path).ok_or_else(|| Error::PathConversion(path.to_owned()))?;
+    if !(bytes.starts_with(b"\"") || bytes.starts_with(b"#") || bytes.iter().any(|b| b.is_ascii_control())) {
+        return Ok(bytes.into());
+    }
+    let mut out = vec![b'"'];
+    for &b in bytes {
+        match b {
+            b'"' | b'\\' => out.extend_from_slice(&[b'\\', b]),
+            b'\n' => out.extend_from_slice(b"\\n"),
+            b'\t' => out.extend_from_slice(b"\\t"),
+            b if b.is_ascii_control() => out.extend_from_slice(format!("\\{:03o}", b).as_bytes()),
+            b => out.push(b),
+        }
+    }
+    out.push(b'"');
+    debug_assert_eq!(
+        unquote::ansi_c(out.as_bstr()).ok().as_deref(),
+        Some(bytes.as_bstr()),
+        "quoting round-trips"
+    );
+    Ok(out.into())
+}
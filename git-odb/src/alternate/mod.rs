@@ -1,6 +1,6 @@
 //! A file with directories of other git object databases to use when reading objects.
 //!
-//! This inherently makes alternates read-only.
+//! This inherently makes alternates read-only. The alternates file itself can be changed with [`add()`] and [`remove()`].
 //!
 //! An alternate file in `<git-dir>/info/alternates` can look as follows:
 //!
@@ -20,6 +20,9 @@ use std::{fs, io, path::PathBuf};
 
 use crate::store::compound;
 
+///
+pub mod edit;
+pub use edit::{add, remove};
 ///
 pub mod parse;
 #[allow(missing_docs)]
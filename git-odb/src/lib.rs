@@ -8,6 +8,7 @@
 //!
 //! * [`loose::Store`]
 //!   * A database storing one object per file, named by its hash, using zlib compression.
+//!   * With the `experimental-zstd` feature, objects can be compressed with zstd instead, which `git` can't read.
 //!   * O(1) reads and writes, bound by IO operations per second
 //! * [`compound::Store`]
 //!   * A database using a [`loose::Store`] for writes and multiple [`pack::Bundle`]s for object reading. It can also refer to multiple
@@ -15,13 +16,16 @@
 //!   * This is the database closely resembling the object database in a git repository, and probably what most people would want to use.
 //! * [`linked::Store`]
 //!   * A database containing various [`compound::Stores`][compound::Store] as gathered from `alternates` files.
+//! * [`memory::Proxy`]
+//!   * A database keeping written objects in memory while reading all others from the database it wraps.
 pub use git_pack as pack;
 pub use pack::{data, Find, FindExt};
 
 mod store;
-pub use store::{compound, linked, loose, sink, Sink};
+pub use store::{compound, linked, loose, memory, sink, PrefixLookupResult, Sink};
 
 pub mod alternate;
 
-mod write;
+///
+pub mod write;
 pub use write::Write;
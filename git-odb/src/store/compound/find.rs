@@ -2,7 +2,8 @@ use git_pack::data;
 
 use crate::{
     pack,
-    store::{compound, loose},
+    store::{compound, insert_prefix_candidate, loose},
+    PrefixLookupResult,
 };
 
 /// Returned by [`compound::Store::try_find()`]
@@ -22,6 +23,47 @@ pub(crate) struct PackLocation {
 }
 
 impl compound::Store {
+    /// Return true if the given object `id` is contained in the store, either in a pack or as loose object.
+    pub fn contains(&self, id: impl AsRef<git_hash::oid>) -> bool {
+        let id = id.as_ref();
+        self.internal_find_packed(id).is_some() || self.loose.contains(id)
+    }
+
+    /// Return the id of the only object in a pack or loose object whose id starts with `prefix`, `Some(Err(()))` if there are
+    /// multiple such objects, or `None` if there is none.
+    pub fn lookup_prefix(&self, prefix: git_hash::Prefix) -> Result<Option<PrefixLookupResult>, loose::find::Error> {
+        let mut candidate = None;
+        Ok(if self.insert_prefix_candidate(prefix, &mut candidate)? {
+            candidate.map(Ok)
+        } else {
+            Some(Err(()))
+        })
+    }
+
+    /// Record the object matching `prefix` in `candidate`, returning `false` if this yields multiple objects.
+    pub(crate) fn insert_prefix_candidate(
+        &self,
+        prefix: git_hash::Prefix,
+        candidate: &mut Option<git_hash::ObjectId>,
+    ) -> Result<bool, loose::find::Error> {
+        for bundle in &self.bundles {
+            match bundle.index.lookup_prefix(prefix) {
+                Some(Ok(idx)) => {
+                    if !insert_prefix_candidate(candidate, bundle.index.oid_at_index(idx)) {
+                        return Ok(false);
+                    }
+                }
+                Some(Err(())) => return Ok(false),
+                None => {}
+            }
+        }
+        Ok(match self.loose.lookup_prefix(prefix)? {
+            Some(Ok(id)) => insert_prefix_candidate(candidate, &id),
+            Some(Err(())) => false,
+            None => true,
+        })
+    }
+
     /// Find an object as identified by [`ObjectId`][git_hash::ObjectId] and store its data in full in the provided `buffer`.
     /// This will search the object in all contained object databases.
     /// Use a `pack_cache` to accelerate pack access by reducing the amount of work duplication, or [`pack::cache::Never`] to disable any caching.
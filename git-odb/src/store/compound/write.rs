@@ -2,7 +2,10 @@ use std::io::Read;
 
 use git_object::Kind;
 
-use crate::store::{compound, loose};
+use crate::{
+    store::{compound, loose},
+    write::Outcome,
+};
 
 impl crate::write::Write for compound::Store {
     type Error = loose::write::Error;
@@ -24,4 +27,16 @@ impl crate::write::Write for compound::Store {
     ) -> Result<git_hash::ObjectId, Self::Error> {
         self.loose.write_stream(kind, size, from, hash)
     }
+
+    fn write_stream_if_missing(
+        &self,
+        kind: Kind,
+        size: u64,
+        from: impl Read,
+        expected_id: Option<&git_hash::oid>,
+        hash: git_hash::Kind,
+    ) -> Result<Outcome, Self::Error> {
+        self.loose
+            .write_stream_unless_contained(kind, size, from, expected_id, hash, |id| self.contains(id))
+    }
 }
@@ -6,19 +6,27 @@ use git_pack::{data::Object, find::Entry};
 use crate::{
     pack,
     pack::{bundle::Location, Bundle},
-    store::{compound, linked},
+    store::{compound, linked, loose},
+    PrefixLookupResult,
 };
 
 impl linked::Store {
     /// Return true if the given object `id` is contained in the store.
     pub fn contains(&self, id: impl AsRef<oid>) -> bool {
         let id = id.as_ref();
+        self.dbs.iter().any(|db| db.contains(id))
+    }
+
+    /// Return the id of the only object in any of our databases whose id starts with `prefix`, `Some(Err(()))` if there are
+    /// multiple such objects, or `None` if there is none.
+    pub fn lookup_prefix(&self, prefix: git_hash::Prefix) -> Result<Option<PrefixLookupResult>, loose::find::Error> {
+        let mut candidate = None;
         for db in self.dbs.iter() {
-            if db.internal_find_packed(id).is_some() || db.loose.contains(id) {
-                return true;
+            if !db.insert_prefix_candidate(prefix, &mut candidate)? {
+                return Ok(Some(Err(())));
             }
         }
-        false
+        Ok(candidate.map(Ok))
     }
 }
 
@@ -36,7 +36,7 @@ impl linked::Store {
         // TODO: actually do this efficiently by only loading or discarding what changed. Probably redirect the non-alternates impl
         //       to the compound db to deal with pack refreshing.
         let first_db = self.dbs.remove(0);
-        let base_path = first_db.loose.path;
+        let base_path = first_db.loose.path.clone();
         *self = Self::at(base_path)?;
         Ok(self)
     }
@@ -14,6 +14,9 @@ pub mod init;
 
 mod find;
 
+///
+pub mod stream;
+
 ///
 mod write;
 
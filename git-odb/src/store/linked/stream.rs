@@ -0,0 +1,106 @@
+use std::io::{self, Read};
+
+use git_features::zlib;
+use git_hash::oid;
+
+use crate::{
+    pack,
+    store::{compound, linked},
+};
+
+/// A reader over the decompressed content of an object, as returned by [`linked::Store::try_stream()`].
+pub struct Stream<'a> {
+    /// The kind of the object.
+    pub kind: git_object::Kind,
+    /// The size of the object's content in bytes.
+    pub size: u64,
+    inner: Box<dyn io::Read + 'a>,
+}
+
+impl<'a> io::Read for Stream<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+/// The decompressed content of an undeltified pack entry, which fails if the entry ends before its decompressed size was read.
+struct Undeltified<'a> {
+    inner: io::Take<zlib::stream::inflate::ReadBoxed<&'a [u8]>>,
+}
+
+impl<'a> io::Read for Undeltified<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let num_read = self.inner.read(buf)?;
+        if num_read == 0 && !buf.is_empty() && self.inner.limit() != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "The pack entry ended before its decompressed size was reached",
+            ));
+        }
+        Ok(num_read)
+    }
+}
+
+impl linked::Store {
+    /// Return a reader over the decompressed content of the object identified by `id` along with its kind and size, or `None`
+    /// if there is no such object in any of our databases.
+    ///
+    /// Loose objects and undeltified objects in packs are decompressed while reading, so their content is never held in
+    /// memory as a whole. Deltified objects have to be resolved first, which happens in `buf`.
+    pub fn try_stream<'a>(
+        &'a self,
+        id: impl AsRef<oid>,
+        buf: &'a mut Vec<u8>,
+    ) -> Result<Option<Stream<'a>>, compound::find::Error> {
+        let id = id.as_ref();
+        for db in self.dbs.iter() {
+            match db.internal_find_packed(id) {
+                Some(compound::find::PackLocation {
+                    bundle_index,
+                    entry_index,
+                }) => {
+                    let bundle = &db.bundles[bundle_index];
+                    let entry = bundle.pack.entry(bundle.index.pack_offset_at_index(entry_index));
+                    if let Some(kind) = entry.header.as_kind() {
+                        let data = bundle
+                            .pack
+                            .entry_slice(entry.data_offset..bundle.pack.pack_end() as u64)
+                            .unwrap_or_default();
+                        let inflate = zlib::stream::inflate::ReadBoxed {
+                            inner: data,
+                            decompressor: Box::new(zlib::Inflate::default().state),
+                        };
+                        return Ok(Some(Stream {
+                            kind,
+                            size: entry.decompressed_size,
+                            inner: Box::new(Undeltified {
+                                inner: inflate.take(entry.decompressed_size),
+                            }),
+                        }));
+                    }
+                    let object = db.internal_get_packed_object_by_index(
+                        bundle_index,
+                        entry_index,
+                        buf,
+                        &mut pack::cache::Never,
+                    )?;
+                    return Ok(Some(Stream {
+                        kind: object.kind,
+                        size: object.data.len() as u64,
+                        inner: Box::new(object.data),
+                    }));
+                }
+                None => {
+                    if let Some(stream) = db.loose.try_stream(id)? {
+                        return Ok(Some(Stream {
+                            kind: stream.kind,
+                            size: stream.size,
+                            inner: Box::new(stream),
+                        }));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+}
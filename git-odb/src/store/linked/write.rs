@@ -2,7 +2,18 @@ use std::io::Read;
 
 use git_object::Kind;
 
-use crate::store::{linked, loose};
+use crate::{
+    store::{linked, loose},
+    write::Outcome,
+};
+
+impl linked::Store {
+    /// Synchronize all objects written in [batch mode][git_features::fs::Fsync::Batch] to permanent storage, as done by
+    /// [`loose::Store::sync_batch()`] for each of our databases.
+    pub fn sync_batch(&self) -> Result<(), loose::write::Error> {
+        self.dbs.iter().try_for_each(|db| db.loose.sync_batch())
+    }
+}
 
 impl crate::write::Write for linked::Store {
     type Error = loose::write::Error;
@@ -24,4 +35,17 @@ impl crate::write::Write for linked::Store {
     ) -> Result<git_hash::ObjectId, Self::Error> {
         self.dbs[0].loose.write_stream(kind, size, from, hash)
     }
+
+    fn write_stream_if_missing(
+        &self,
+        kind: Kind,
+        size: u64,
+        from: impl Read,
+        expected_id: Option<&git_hash::oid>,
+        hash: git_hash::Kind,
+    ) -> Result<Outcome, Self::Error> {
+        self.dbs[0]
+            .loose
+            .write_stream_unless_contained(kind, size, from, expected_id, hash, |id| self.contains(id))
+    }
 }
@@ -1,9 +1,20 @@
-use std::{convert::TryInto, fs, io::Read, path::PathBuf};
+use std::{
+    convert::TryInto,
+    fs,
+    io::{self, BufRead, Read},
+    path::{Path, PathBuf},
+};
 
 use git_features::zlib;
 use git_pack::{data, loose::object::header};
 
-use crate::store::loose::{sha1_path, Store, HEADER_READ_UNCOMPRESSED_BYTES};
+use crate::{
+    store::{
+        insert_prefix_candidate,
+        loose::{is_zstd, sha1_path, Store, HEADER_READ_UNCOMPRESSED_BYTES},
+    },
+    PrefixLookupResult,
+};
 
 /// Returned by [`Store::try_find()`]
 #[derive(thiserror::Error, Debug)]
@@ -14,6 +25,8 @@ pub enum Error {
         source: zlib::inflate::Error,
         path: PathBuf,
     },
+    #[error("The loose object at '{path}' needs the 'experimental-zstd' feature as it is compressed with zstd")]
+    ZstdUnsupported { path: PathBuf },
     #[error(transparent)]
     Decode(#[from] header::Error),
     #[error("Could not {action} data at '{path}'")]
@@ -24,6 +37,28 @@ pub enum Error {
     },
 }
 
+/// A reader over the decompressed content of a loose object, as returned by [`Store::try_stream()`].
+pub struct Stream {
+    /// The kind of the object.
+    pub kind: git_object::Kind,
+    /// The size of the object's content in bytes.
+    pub size: u64,
+    inner: io::Take<Box<dyn io::BufRead>>,
+}
+
+impl io::Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let num_read = self.inner.read(buf)?;
+        if num_read == 0 && !buf.is_empty() && self.inner.limit() != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "The loose object ended before the size in its header was reached",
+            ));
+        }
+        Ok(num_read)
+    }
+}
+
 /// Object lookup
 impl Store {
     const OPEN_ACTION: &'static str = "open";
@@ -33,6 +68,48 @@ impl Store {
         sha1_path(id.as_ref(), self.path.clone()).is_file()
     }
 
+    /// Return the id of the only object whose id starts with `prefix`, `Some(Err(()))` if there are multiple such objects,
+    /// or `None` if there is none.
+    pub fn lookup_prefix(&self, prefix: git_hash::Prefix) -> Result<Option<PrefixLookupResult>, Error> {
+        let mut buf = [0u8; git_hash::Kind::longest().len_in_hex()];
+        let hex = prefix.as_oid().hex_to_buf(&mut buf);
+        let (dir_name, file_name_prefix) = hex[..prefix.hex_len()].split_at(2);
+        let dir = self.path.join(dir_name);
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(Error::Io {
+                    source: err,
+                    action: "read directory",
+                    path: dir,
+                })
+            }
+        };
+
+        let mut candidate = None;
+        for entry in entries {
+            let entry = entry.map_err(|err| Error::Io {
+                source: err,
+                action: "read directory",
+                path: dir.clone(),
+            })?;
+            let file_name = entry.file_name();
+            let file_name = match file_name.to_str() {
+                Some(name) if name.starts_with(file_name_prefix) => name,
+                _ => continue,
+            };
+            let id = match git_hash::ObjectId::from_hex(format!("{}{}", dir_name, file_name).as_bytes()) {
+                Ok(id) => id,
+                Err(_) => continue,
+            };
+            if !insert_prefix_candidate(&mut candidate, &id) {
+                return Ok(Some(Err(())));
+            }
+        }
+        Ok(candidate.map(Ok))
+    }
+
     /// Return the object identified by the given [`ObjectId`][git_hash::ObjectId] if present in this database,
     /// writing its raw data into the given `out` buffer.
     ///
@@ -66,6 +143,54 @@ impl Store {
         }
     }
 
+    /// Return a reader over the decompressed content of the object identified by `id` along with its kind and size as
+    /// stated in its header, or `None` if there is no such object.
+    ///
+    /// Unlike [`try_find()`][Store::try_find()], the content is decompressed while reading and never held in memory as a whole.
+    pub fn try_stream(&self, id: impl AsRef<git_hash::oid>) -> Result<Option<Stream>, Error> {
+        let path = sha1_path(id.as_ref(), self.path.clone());
+        let file = match fs::File::open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(Error::Io {
+                    source: err,
+                    action: Self::OPEN_ACTION,
+                    path,
+                })
+            }
+        };
+        let mut file = io::BufReader::new(file);
+        let starts_with_zstd = is_zstd(file.fill_buf().map_err(|e| Error::Io {
+            source: e,
+            action: "read",
+            path: path.to_owned(),
+        })?);
+        let mut inflate: Box<dyn io::BufRead> = if starts_with_zstd {
+            Self::zstd_reader(file, &path)?
+        } else {
+            Box::new(io::BufReader::new(zlib::stream::inflate::ReadBoxed {
+                inner: file,
+                decompressor: Box::new(zlib::Inflate::default().state),
+            }))
+        };
+        let mut header_buf = Vec::new();
+        (&mut inflate)
+            .take(HEADER_READ_UNCOMPRESSED_BYTES as u64)
+            .read_until(0, &mut header_buf)
+            .map_err(|e| Error::Io {
+                source: e,
+                action: "deflate",
+                path,
+            })?;
+        let (kind, size, _header_size) = header::decode(&header_buf)?;
+        Ok(Some(Stream {
+            kind,
+            size,
+            inner: inflate.take(size),
+        }))
+    }
+
     fn find_inner<'a>(&self, id: &git_hash::oid, buf: &'a mut Vec<u8>) -> Result<data::Object<'a>, Error> {
         let path = sha1_path(id, self.path.clone());
 
@@ -83,17 +208,24 @@ impl Store {
                 action: "read",
                 path: path.to_owned(),
             })?;
-            buf.resize(bytes_read + HEADER_READ_UNCOMPRESSED_BYTES, 0);
-            let (input, output) = buf.split_at_mut(bytes_read);
-            (
-                inflate
-                    .once(&input[..bytes_read], output)
-                    .map_err(|e| Error::DecompressFile {
-                        source: e,
-                        path: path.to_owned(),
-                    })?,
-                bytes_read,
-            )
+            if is_zstd(buf) {
+                // Place the decompressed object behind the compressed one as if it was inflated in one go.
+                let decompressed = Self::zstd_decompress(buf, &path)?;
+                buf.extend_from_slice(&decompressed);
+                ((zlib::Status::StreamEnd, bytes_read, decompressed.len()), bytes_read)
+            } else {
+                buf.resize(bytes_read + HEADER_READ_UNCOMPRESSED_BYTES, 0);
+                let (input, output) = buf.split_at_mut(bytes_read);
+                (
+                    inflate
+                        .once(&input[..bytes_read], output)
+                        .map_err(|e| Error::DecompressFile {
+                            source: e,
+                            path: path.to_owned(),
+                        })?,
+                    bytes_read,
+                )
+            }
         };
         assert_ne!(
             status,
@@ -143,4 +275,33 @@ impl Store {
             pack_location: None,
         })
     }
+
+    #[cfg(feature = "experimental-zstd")]
+    fn zstd_decompress(input: &[u8], path: &Path) -> Result<Vec<u8>, Error> {
+        zstd::stream::decode_all(input).map_err(|e| Error::Io {
+            source: e,
+            action: "decompress",
+            path: path.to_owned(),
+        })
+    }
+
+    #[cfg(not(feature = "experimental-zstd"))]
+    fn zstd_decompress(_input: &[u8], path: &Path) -> Result<Vec<u8>, Error> {
+        Err(Error::ZstdUnsupported { path: path.to_owned() })
+    }
+
+    #[cfg(feature = "experimental-zstd")]
+    fn zstd_reader(file: io::BufReader<fs::File>, path: &Path) -> Result<Box<dyn io::BufRead>, Error> {
+        let decoder = zstd::stream::read::Decoder::with_buffer(file).map_err(|e| Error::Io {
+            source: e,
+            action: "decompress",
+            path: path.to_owned(),
+        })?;
+        Ok(Box::new(io::BufReader::new(decoder)))
+    }
+
+    #[cfg(not(feature = "experimental-zstd"))]
+    fn zstd_reader(_file: io::BufReader<fs::File>, path: &Path) -> Result<Box<dyn io::BufRead>, Error> {
+        Err(Error::ZstdUnsupported { path: path.to_owned() })
+    }
 }
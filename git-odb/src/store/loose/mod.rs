@@ -1,11 +1,26 @@
 //! An object database storing each object in a zlib compressed file with its hash in the path
 const HEADER_READ_UNCOMPRESSED_BYTES: usize = 512;
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::Mutex};
 
 /// A database for reading and writing objects to disk, one file per object.
 pub struct Store {
     /// The directory in which objects are stored, containing 256 folders representing the hashes first byte.
     pub path: PathBuf,
+    /// The zlib compression level from 0 to 9 to use when writing objects, which is 1 by default to favor speed over size
+    /// just like `git` does. It corresponds to the `core.looseCompression` configuration.
+    pub compression_level: u32,
+    /// If true, objects are written compressed with zstd instead of zlib, using [`compression_level`][Store::compression_level]
+    /// as zstd level, where 0 selects the zstd default. It's false by default.
+    ///
+    /// Objects written this way can't be read by `git`, so this is only suitable for repositories that are never
+    /// accessed by it. Reading objects compressed with zstd works no matter how this is set.
+    #[cfg(feature = "experimental-zstd")]
+    pub zstd: bool,
+    /// Whether and when to synchronize written objects to permanent storage. With [`Fsync::Batch`][git_features::fs::Fsync::Batch],
+    /// [`sync_batch()`][Store::sync_batch()] must be called once a batch of objects was written.
+    pub fsync: git_features::fs::Fsync,
+    /// Objects written in batch mode which weren't synchronized yet.
+    unsynced: Mutex<Vec<PathBuf>>,
 }
 
 /// Initialization
@@ -17,10 +32,23 @@ impl Store {
     pub fn at(objects_directory: impl Into<PathBuf>) -> Store {
         Store {
             path: objects_directory.into(),
+            compression_level: 1,
+            #[cfg(feature = "experimental-zstd")]
+            zstd: false,
+            fsync: Default::default(),
+            unsynced: Default::default(),
         }
     }
 }
 
+/// The first bytes of each zstd frame, which can't be the start of a zlib stream.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Return true if `data` starts with an object compressed with zstd rather than zlib.
+fn is_zstd(data: &[u8]) -> bool {
+    data.starts_with(&ZSTD_MAGIC)
+}
+
 fn sha1_path(id: &git_hash::oid, mut root: PathBuf) -> PathBuf {
     match id.kind() {
         git_hash::Kind::Sha1 => {
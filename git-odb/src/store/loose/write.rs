@@ -4,7 +4,7 @@ use git_features::{hash, zlib::stream::deflate};
 use tempfile::NamedTempFile;
 
 use super::Store;
-use crate::store::loose;
+use crate::{store::loose, write::Outcome};
 
 /// Returned by the [`crate::Write`] trait implementation of [`Store`]
 #[derive(thiserror::Error, Debug)]
@@ -74,9 +74,55 @@ impl crate::write::Write for Store {
             }
         }
     }
+
+    /// Write the given stream in `from` to disk unless an object with `expected_id` or the same id is stored already.
+    fn write_stream_if_missing(
+        &self,
+        kind: git_object::Kind,
+        size: u64,
+        from: impl io::Read,
+        expected_id: Option<&git_hash::oid>,
+        hash: git_hash::Kind,
+    ) -> Result<Outcome, Self::Error> {
+        self.write_stream_unless_contained(kind, size, from, expected_id, hash, |id| self.contains(id))
+    }
+}
+
+/// A temporary file receiving the compressed object.
+enum CompressedTempfile {
+    Zlib(deflate::Write<NamedTempFile>),
+    #[cfg(feature = "experimental-zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, NamedTempFile>),
 }
 
-type CompressedTempfile = deflate::Write<NamedTempFile>;
+impl io::Write for CompressedTempfile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            CompressedTempfile::Zlib(w) => w.write(buf),
+            #[cfg(feature = "experimental-zstd")]
+            CompressedTempfile::Zstd(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            CompressedTempfile::Zlib(w) => w.flush(),
+            #[cfg(feature = "experimental-zstd")]
+            CompressedTempfile::Zstd(w) => w.flush(),
+        }
+    }
+}
+
+impl CompressedTempfile {
+    /// End the compressed stream and return the file it was written to.
+    fn finish(self) -> io::Result<NamedTempFile> {
+        match self {
+            CompressedTempfile::Zlib(w) => Ok(w.into_inner()),
+            #[cfg(feature = "experimental-zstd")]
+            CompressedTempfile::Zstd(w) => w.finish(),
+        }
+    }
+}
 
 impl Store {
     fn write_header(
@@ -85,14 +131,12 @@ impl Store {
         size: u64,
         hash: git_hash::Kind,
     ) -> Result<hash::Write<CompressedTempfile>, Error> {
-        let mut to = hash::Write::new(
-            deflate::Write::new(NamedTempFile::new_in(&self.path).map_err(|err| Error::Io {
-                source: err,
-                message: "create named temp file in",
-                path: self.path.to_owned(),
-            })?),
-            hash,
-        );
+        let file = NamedTempFile::new_in(&self.path).map_err(|err| Error::Io {
+            source: err,
+            message: "create named temp file in",
+            path: self.path.to_owned(),
+        })?;
+        let mut to = hash::Write::new(self.compressed(file)?, hash);
 
         git_pack::loose::object::header::encode(kind, size, &mut to).map_err(|err| Error::Io {
             source: err,
@@ -102,11 +146,63 @@ impl Store {
         Ok(to)
     }
 
+    fn compressed(&self, file: NamedTempFile) -> io::Result<CompressedTempfile> {
+        #[cfg(feature = "experimental-zstd")]
+        if self.zstd {
+            return zstd::stream::write::Encoder::new(file, self.compression_level as i32)
+                .map(CompressedTempfile::Zstd);
+        }
+        Ok(CompressedTempfile::Zlib(deflate::Write::with_level(
+            file,
+            self.compression_level,
+        )))
+    }
+
+    /// Write the object in `from` unless `contains` returns true for `expected_id`, or for the actual id of the object
+    /// after hashing it, in which case the temporary file is discarded.
+    ///
+    /// This allows databases using this store for writing to consider objects they store elsewhere, like in packs.
+    pub(crate) fn write_stream_unless_contained(
+        &self,
+        kind: git_object::Kind,
+        size: u64,
+        mut from: impl io::Read,
+        expected_id: Option<&git_hash::oid>,
+        hash: git_hash::Kind,
+        contains: impl Fn(&git_hash::oid) -> bool,
+    ) -> Result<Outcome, Error> {
+        if let Some(id) = expected_id {
+            if contains(id) {
+                return Ok(Outcome::AlreadyExists(id.to_owned()));
+            }
+        }
+        match hash {
+            git_hash::Kind::Sha1 => {
+                let mut to = self.write_header(kind, size, hash)?;
+                io::copy(&mut from, &mut to).map_err(|err| Error::Io {
+                    source: err,
+                    message: "stream all data into tempfile in",
+                    path: self.path.to_owned(),
+                })?;
+                to.flush()?;
+                let hash::Write { hash, inner: file } = to;
+                let id = git_hash::ObjectId::from(hash.digest());
+                if contains(&id) {
+                    return Ok(Outcome::AlreadyExists(id));
+                }
+                self.persist_object(id, file).map(Outcome::Written)
+            }
+        }
+    }
+
     fn finalize_object(
         &self,
         hash::Write { hash, inner: file }: hash::Write<CompressedTempfile>,
     ) -> Result<git_hash::ObjectId, Error> {
-        let id = git_hash::ObjectId::from(hash.digest());
+        self.persist_object(git_hash::ObjectId::from(hash.digest()), file)
+    }
+
+    fn persist_object(&self, id: git_hash::ObjectId, file: CompressedTempfile) -> Result<git_hash::ObjectId, Error> {
         let object_path = loose::sha1_path(&id, self.path.clone());
         let object_dir = object_path
             .parent()
@@ -117,11 +213,62 @@ impl Store {
                 _ => return Err(err.into()),
             }
         }
-        let file = file.into_inner();
-        file.persist(&object_path).map_err(|err| Error::Persist {
+        let file = file.finish()?;
+        if self.fsync.loose_object() {
+            file.as_file().sync_all()?;
+        }
+        let file = file.persist(&object_path).map_err(|err| Error::Persist {
             source: err,
-            target: object_path,
+            target: object_path.clone(),
         })?;
+        if self.fsync.loose_object() {
+            git_features::fs::sync_dir(object_dir)?;
+        } else if self.fsync.loose_object_batch() {
+            drop(file);
+            self.unsynced.lock().expect("no panic while locked").push(object_path);
+        }
         Ok(id)
     }
+
+    /// Synchronize all objects written since the last call to permanent storage if [`fsync`][Store::fsync] is
+    /// [`Fsync::Batch`][git_features::fs::Fsync::Batch], along with the directories containing them.
+    ///
+    /// Doing so once after writing many objects is faster than synchronizing each of them, as each directory is
+    /// synchronized only once and the operating system can write all objects at its own pace until then.
+    ///
+    /// Objects which weren't synchronized yet when the store is dropped are synchronized then, ignoring errors.
+    pub fn sync_batch(&self) -> Result<(), Error> {
+        let mut paths = std::mem::take(&mut *self.unsynced.lock().expect("no panic while locked"));
+        paths.sort();
+        for path in &paths {
+            fs::File::open(path)
+                .and_then(|file| file.sync_all())
+                .map_err(|err| Error::Io {
+                    source: err,
+                    message: "synchronize object",
+                    path: path.to_owned(),
+                })?;
+        }
+        paths.dedup_by(|a, b| a.parent() == b.parent());
+        for dir in paths.iter().filter_map(|path| path.parent()) {
+            git_features::fs::sync_dir(dir).map_err(|err| Error::Io {
+                source: err,
+                message: "synchronize object directory",
+                path: dir.to_owned(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// The amount of objects written in [batch mode][git_features::fs::Fsync::Batch] which weren't synchronized yet
+    /// with [`sync_batch()`][Store::sync_batch()].
+    pub fn num_unsynced(&self) -> usize {
+        self.unsynced.lock().expect("no panic while locked").len()
+    }
+}
+
+impl Drop for Store {
+    fn drop(&mut self) {
+        self.sync_batch().ok();
+    }
 }
@@ -0,0 +1,159 @@
+use std::{
+    collections::BTreeMap,
+    convert::TryInto,
+    io::{self, Read},
+    ops::{Deref, DerefMut},
+    sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use git_hash::ObjectId;
+use git_object::Kind;
+
+use crate::Write;
+
+/// The objects held in memory by a [`Proxy`], along with their kind and encoded data.
+pub type Storage = BTreeMap<ObjectId, (Kind, Vec<u8>)>;
+
+/// An object database which keeps all written objects in memory while reading objects from an `inner` database
+/// if they are not present in memory.
+///
+/// This allows to stage objects, for instance when rewriting history, without persisting them until their
+/// [storage][Proxy::into_storage()] is [written][Proxy::persist_into()] to an actual object database.
+pub struct Proxy<T> {
+    inner: T,
+    memory: RwLock<Storage>,
+}
+
+/// Create a new [`Proxy`] which reads objects from `inner` but writes them into memory.
+pub fn proxy<T>(inner: T) -> Proxy<T> {
+    Proxy {
+        inner,
+        memory: Default::default(),
+    }
+}
+
+/// Create a new [`Proxy`] without a database to read from, which keeps all objects in memory only.
+///
+/// This allows working with objects without any filesystem access, for instance in tests.
+pub fn store() -> Proxy<crate::linked::Store> {
+    proxy(crate::linked::Store { dbs: Vec::new() })
+}
+
+impl<T> Proxy<T> {
+    /// Return the amount of objects held in memory.
+    pub fn num_objects_in_memory(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Remove all objects from memory and return them.
+    pub fn take_storage(&self) -> Storage {
+        std::mem::take(&mut *self.write())
+    }
+
+    /// Turn this instance into the objects it kept in memory, dropping the `inner` database.
+    pub fn into_storage(self) -> Storage {
+        self.memory.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Write all objects kept in memory into `out`, leaving this instance without objects in memory, and return the amount
+    /// of written objects.
+    pub fn persist_into<W>(&self, out: &W, hash: git_hash::Kind) -> Result<usize, W::Error>
+    where
+        W: Write,
+    {
+        let storage = self.take_storage();
+        for (kind, data) in storage.values() {
+            out.write_buf(*kind, data, hash)?;
+        }
+        Ok(storage.len())
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Storage> {
+        self.memory.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Storage> {
+        self.memory.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Proxy<crate::linked::Store> {
+    /// Return true if the object with `id` is held in memory or contained in the `inner` database.
+    pub fn contains(&self, id: impl AsRef<git_hash::oid>) -> bool {
+        let id = id.as_ref();
+        self.read().contains_key(id) || self.inner.contains(id)
+    }
+}
+
+impl<T> Deref for Proxy<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<T> DerefMut for Proxy<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+impl<T> crate::Find for Proxy<T>
+where
+    T: crate::Find,
+{
+    type Error = T::Error;
+
+    fn try_find<'a>(
+        &self,
+        id: impl AsRef<git_hash::oid>,
+        buffer: &'a mut Vec<u8>,
+        pack_cache: &mut impl git_pack::cache::DecodeEntry,
+    ) -> Result<Option<git_pack::data::Object<'a>>, Self::Error> {
+        let id = id.as_ref();
+        let kind = match self.read().get(id) {
+            Some((kind, data)) => {
+                buffer.clear();
+                buffer.extend_from_slice(data);
+                *kind
+            }
+            None => return self.inner.try_find(id, buffer, pack_cache),
+        };
+        Ok(Some(git_pack::data::Object::new(kind, buffer)))
+    }
+
+    fn location_by_oid(&self, id: impl AsRef<git_hash::oid>, buf: &mut Vec<u8>) -> Option<git_pack::bundle::Location> {
+        let id = id.as_ref();
+        if self.read().contains_key(id) {
+            return None;
+        }
+        self.inner.location_by_oid(id, buf)
+    }
+
+    fn bundle_by_pack_id(&self, pack_id: u32) -> Option<&git_pack::Bundle> {
+        self.inner.bundle_by_pack_id(pack_id)
+    }
+
+    fn entry_by_location(&self, location: &git_pack::bundle::Location) -> Option<git_pack::find::Entry<'_>> {
+        self.inner.entry_by_location(location)
+    }
+}
+
+impl<T> crate::Write for Proxy<T> {
+    type Error = io::Error;
+
+    fn write_stream(
+        &self,
+        kind: Kind,
+        size: u64,
+        from: impl io::Read,
+        hash: git_hash::Kind,
+    ) -> Result<ObjectId, Self::Error> {
+        let mut data = Vec::with_capacity(size.try_into().expect("object size to fit into usize"));
+        from.take(size).read_to_end(&mut data)?;
+        let id = crate::sink().write_buf(kind, &data, hash)?;
+        self.write().insert(id, (kind, data));
+        Ok(id)
+    }
+}
@@ -1,8 +1,26 @@
 pub use sink::{sink, Sink};
 
+/// The result of looking up objects by a prefix of their id, the id of the only matching object or `Err(())` if there are
+/// multiple objects with that prefix.
+pub type PrefixLookupResult = Result<git_hash::ObjectId, ()>;
+
+/// Record `id` as the object matching a prefix in `candidate`, returning `false` if another object matched it before.
+fn insert_prefix_candidate(candidate: &mut Option<git_hash::ObjectId>, id: &git_hash::oid) -> bool {
+    match candidate {
+        Some(existing) => existing.as_ref() == id,
+        None => {
+            *candidate = Some(id.to_owned());
+            true
+        }
+    }
+}
+
 pub mod compound;
 pub mod linked;
 pub mod loose;
 
+///
+pub mod memory;
+
 ///
 pub mod sink;
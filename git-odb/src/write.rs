@@ -34,4 +34,40 @@ pub trait Write {
         from: impl io::Read,
         hash: git_hash::Kind,
     ) -> Result<git_hash::ObjectId, Self::Error>;
+    /// As [`write_stream`][Write::write_stream], but don't write the object if it already exists in the database, which
+    /// saves IO when importing many objects of which some may be present already.
+    ///
+    /// If the id of the object is known, pass it as `expected_id` to check for its existence before reading any of `from`.
+    /// Otherwise `from` is hashed and the check happens before the object is persisted.
+    ///
+    /// The default implementation always writes the object.
+    fn write_stream_if_missing(
+        &self,
+        kind: git_object::Kind,
+        size: u64,
+        from: impl io::Read,
+        expected_id: Option<&git_hash::oid>,
+        hash: git_hash::Kind,
+    ) -> Result<Outcome, Self::Error> {
+        let _ = expected_id;
+        self.write_stream(kind, size, from, hash).map(Outcome::Written)
+    }
+}
+
+/// The outcome of [`Write::write_stream_if_missing()`].
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub enum Outcome {
+    /// The object was written and has the given id.
+    Written(git_hash::ObjectId),
+    /// The object with the given id already existed in the database, and nothing was written.
+    AlreadyExists(git_hash::ObjectId),
+}
+
+impl Outcome {
+    /// Return the id of the object, whether it was written or not.
+    pub fn id(&self) -> &git_hash::oid {
+        match self {
+            Outcome::Written(id) | Outcome::AlreadyExists(id) => id,
+        }
+    }
 }
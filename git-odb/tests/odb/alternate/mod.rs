@@ -100,3 +100,101 @@ fn no_alternate_in_first_objects_dir() -> crate::Result {
     assert!(alternate::resolve(tmp.path())?.is_empty());
     Ok(())
 }
+
+mod edit {
+    use std::path::Path;
+
+    use git_odb::alternate;
+
+    fn alternates_file(objects: &Path) -> std::io::Result<String> {
+        std::fs::read_to_string(objects.join("info").join("alternates"))
+    }
+
+    #[test]
+    fn add_and_remove_keep_other_lines_and_resolve_relative_paths() -> crate::Result {
+        let tmp = git_testtools::tempfile::TempDir::new()?;
+        let (fork, a, b) = (tmp.path().join("fork"), tmp.path().join("a"), tmp.path().join("b"));
+        for dir in &[&fork, &a, &b] {
+            std::fs::create_dir_all(dir.join("info"))?;
+        }
+        std::fs::write(fork.join("info").join("alternates"), "# shared objects\n")?;
+
+        assert!(alternate::add(&fork, "../a")?);
+        assert!(!alternate::add(&fork, &a)?, "the same directory is only added once");
+        assert!(alternate::add(&fork, &b)?);
+        assert_eq!(
+            alternates_file(&fork)?,
+            format!("# shared objects\n../a\n{}\n", b.display())
+        );
+        assert_eq!(
+            alternate::resolve(&fork)?
+                .iter()
+                .map(|p| p.canonicalize())
+                .collect::<Result<Vec<_>, _>>()?,
+            vec![b.canonicalize()?, a.canonicalize()?]
+        );
+
+        assert!(
+            alternate::remove(&fork, &a)?,
+            "paths are compared by the directory they point to"
+        );
+        assert!(!alternate::remove(&fork, &a)?);
+        assert_eq!(alternates_file(&fork)?, format!("# shared objects\n{}\n", b.display()));
+        assert!(alternate::remove(&fork, &b)?);
+        assert_eq!(alternates_file(&fork)?, "# shared objects\n", "comments are kept");
+        Ok(())
+    }
+
+    #[test]
+    fn removing_the_last_alternate_removes_the_file() -> crate::Result {
+        let tmp = git_testtools::tempfile::TempDir::new()?;
+        let (fork, a) = (tmp.path().join("fork"), tmp.path().join("a"));
+        std::fs::create_dir_all(&a)?;
+        std::fs::create_dir_all(&fork)?;
+
+        assert!(alternate::add(&fork, &a)?);
+        assert!(alternate::remove(&fork, &a)?);
+        assert!(!fork.join("info").join("alternates").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn paths_that_would_be_misread_are_quoted() -> crate::Result {
+        let tmp = git_testtools::tempfile::TempDir::new()?;
+        let (fork, a) = (tmp.path().join("fork"), tmp.path().join("#a\tb"));
+        std::fs::create_dir_all(&a)?;
+        std::fs::create_dir_all(&fork)?;
+
+        assert!(alternate::add(&fork, &a)?);
+        assert_eq!(
+            alternates_file(&fork)?,
+            format!("\"{}\"\n", a.display().to_string().replace('\t', "\\t"))
+        );
+        assert_eq!(alternate::resolve(&fork)?, vec![a.clone()]);
+        Ok(())
+    }
+
+    #[test]
+    fn cycles_and_missing_directories_are_rejected() -> crate::Result {
+        let tmp = git_testtools::tempfile::TempDir::new()?;
+        let (a, b, c) = (tmp.path().join("a"), tmp.path().join("b"), tmp.path().join("c"));
+        for dir in &[&a, &b, &c] {
+            std::fs::create_dir_all(dir)?;
+        }
+        assert!(alternate::add(&a, &b)?);
+        assert!(alternate::add(&b, &c)?);
+
+        for (objects, alternate) in [(&c, &a), (&a, &a), (&c, &b)] {
+            assert!(matches!(
+                alternate::add(objects, alternate),
+                Err(alternate::edit::Error::Cycle { .. })
+            ));
+        }
+        assert!(matches!(
+            alternate::add(&a, tmp.path().join("missing")),
+            Err(alternate::edit::Error::NotADirectory(_))
+        ));
+        assert!(alternate::add(&a, &c)?, "adding transitive alternates directly is fine");
+        Ok(())
+    }
+}
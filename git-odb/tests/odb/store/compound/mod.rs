@@ -15,6 +15,28 @@ mod init {
     }
 }
 
+mod write {
+    use git_odb::{write::Outcome, Write};
+
+    use crate::{hex_to_id, odb::store::compound::db};
+
+    #[test]
+    fn if_missing_considers_packed_objects() -> crate::Result {
+        let id = hex_to_id("501b297447a8255d3533c6858bb692575cdefaa0");
+        assert_eq!(
+            db().write_stream_if_missing(
+                git_object::Kind::Blob,
+                0,
+                std::io::empty(),
+                Some(&id),
+                git_hash::Kind::Sha1
+            )?,
+            Outcome::AlreadyExists(id)
+        );
+        Ok(())
+    }
+}
+
 mod locate {
     use git_odb::compound::Store;
 
@@ -40,3 +62,41 @@ mod locate {
         can_locate(&db(), "dd25c539efbb0ab018caa4cda2d133285634e9b5"); // pack c043
     }
 }
+
+mod lookup_prefix {
+    use crate::{hex_to_id, odb::store::compound::db};
+
+    fn lookup_prefix(hex_prefix: &str) -> Option<git_odb::PrefixLookupResult> {
+        db().lookup_prefix(git_hash::Prefix::from_hex(hex_prefix).expect("valid prefix"))
+            .expect("no read error")
+    }
+
+    #[test]
+    fn unique_loose_and_packed_objects() {
+        assert_eq!(
+            lookup_prefix("37d4"),
+            Some(Ok(hex_to_id("37d4e6c5c48ba0d245164c4e10d5f41140cab980"))),
+            "loose"
+        );
+        assert_eq!(
+            lookup_prefix("501b2"),
+            Some(Ok(hex_to_id("501b297447a8255d3533c6858bb692575cdefaa0"))),
+            "packed"
+        );
+        assert_eq!(
+            lookup_prefix("a706d"),
+            Some(Ok(hex_to_id("a706d7cd20fc8ce71489f34b50cf01011c104193"))),
+            "loose with a packed object sharing a shorter prefix"
+        );
+    }
+
+    #[test]
+    fn objects_sharing_a_prefix_are_ambiguous() {
+        assert_eq!(lookup_prefix("a706"), Some(Err(())), "across packs and loose objects");
+    }
+
+    #[test]
+    fn missing_objects_are_not_found() {
+        assert_eq!(lookup_prefix("0000"), None);
+    }
+}
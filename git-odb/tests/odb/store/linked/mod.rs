@@ -71,6 +71,34 @@ mod locate {
     }
 }
 
+mod stream {
+    use std::io::Read;
+
+    use git_odb::{pack, Find};
+
+    use crate::odb::store::linked::db;
+
+    #[test]
+    fn loose_and_packed_objects_stream_the_same_content_as_they_decode_to() -> crate::Result {
+        let db = db();
+        let (mut buf, mut stream_buf, mut streamed) = (Vec::new(), Vec::new(), Vec::new());
+        for id in db.iter() {
+            let id = id?;
+            let object = db.try_find(id, &mut buf, &mut pack::cache::Never)?.expect("present");
+            let mut stream = db.try_stream(id, &mut stream_buf)?.expect("present");
+            assert_eq!(stream.kind, object.kind);
+            assert_eq!(stream.size, object.data.len() as u64);
+            streamed.clear();
+            stream.read_to_end(&mut streamed)?;
+            assert_eq!(streamed, object.data, "{}", id);
+        }
+        assert!(db
+            .try_stream(git_hash::ObjectId::null_sha1(), &mut stream_buf)?
+            .is_none());
+        Ok(())
+    }
+}
+
 mod init {
     use std::convert::TryFrom;
 
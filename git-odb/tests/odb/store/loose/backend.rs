@@ -58,6 +58,114 @@ mod write {
         }
         Ok(())
     }
+
+    #[test]
+    fn write_stream_if_missing_skips_existing_objects() -> Result<(), Box<dyn std::error::Error>> {
+        use git_odb::write::Outcome;
+
+        let dir = tempfile::tempdir()?;
+        let db = loose::Store::at(dir.path());
+        let data = b"hello world";
+        let outcome = db.write_stream_if_missing(git_object::Kind::Blob, 11, &data[..], None, git_hash::Kind::Sha1)?;
+        let id = outcome.id().to_owned();
+        assert_eq!(outcome, Outcome::Written(id));
+
+        assert_eq!(
+            db.write_stream_if_missing(git_object::Kind::Blob, 11, &data[..], None, git_hash::Kind::Sha1)?,
+            Outcome::AlreadyExists(id),
+            "existing objects are detected after hashing"
+        );
+        assert_eq!(
+            db.write_stream_if_missing(
+                git_object::Kind::Blob,
+                11,
+                std::io::empty(),
+                Some(&id),
+                git_hash::Kind::Sha1
+            )?,
+            Outcome::AlreadyExists(id),
+            "with a known id, the input isn't read at all"
+        );
+        assert_eq!(
+            std::fs::read_dir(dir.path())?.count(),
+            1,
+            "temporary files of skipped objects are removed"
+        );
+        assert_eq!(db.iter().count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn compression_level_is_configurable() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let data = b"hello world, hello world, hello world".repeat(100);
+        let mut sizes = Vec::new();
+        for level in &[0, 9] {
+            let mut db = loose::Store::at(dir.path().join(level.to_string()));
+            std::fs::create_dir(&db.path)?;
+            db.compression_level = *level;
+            let id = db.write_buf(git_object::Kind::Blob, &data, git_hash::Kind::Sha1)?;
+            let hex = id.to_sha1_hex_string();
+            sizes.push(std::fs::metadata(db.path.join(&hex[..2]).join(&hex[2..]))?.len() as usize);
+            assert_eq!(db.try_find(id, &mut Vec::new())?.expect("id present").data, data);
+        }
+        assert!(sizes[0] > data.len(), "level 0 stores objects uncompressed");
+        assert!(sizes[1] < data.len() / 10);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "experimental-zstd")]
+    fn zstd_compressed_objects_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        use std::io::Read;
+
+        let dir = tempfile::tempdir()?;
+        let mut db = loose::Store::at(dir.path());
+        db.zstd = true;
+        let mut buf = Vec::new();
+        let mut buf2 = Vec::new();
+        for oid in object_ids() {
+            let obj = locate_oid(oid, &mut buf);
+            assert_eq!(db.write_buf(obj.kind, obj.data, git_hash::Kind::Sha1)?, oid);
+            let hex = oid.to_sha1_hex_string();
+            let written = std::fs::read(db.path.join(&hex[..2]).join(&hex[2..]))?;
+            assert_eq!(&written[..4], &[0x28, 0xb5, 0x2f, 0xfd], "it's a zstd frame");
+
+            let reader = loose::Store::at(dir.path());
+            let found = reader.try_find(oid, &mut buf2)?.expect("id present");
+            assert_eq!(found.kind, obj.kind, "objects are read no matter how the store is configured");
+            assert_eq!(found.data, obj.data);
+
+            let mut stream = reader.try_stream(oid)?.expect("id present");
+            assert_eq!(stream.kind, obj.kind);
+            assert_eq!(stream.size, obj.data.len() as u64);
+            let mut streamed = Vec::new();
+            stream.read_to_end(&mut streamed)?;
+            assert_eq!(streamed, obj.data);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn fsync_policies_write_readable_objects() -> Result<(), Box<dyn std::error::Error>> {
+        use git_features::fs::Fsync;
+
+        let dir = tempfile::tempdir()?;
+        let mut buf = Vec::new();
+        for fsync in &[Fsync::None, Fsync::Objects, Fsync::Everything, Fsync::Batch] {
+            let mut db = loose::Store::at(dir.path().join(format!("{:?}", fsync)));
+            std::fs::create_dir(&db.path)?;
+            db.fsync = *fsync;
+            for oid in object_ids() {
+                let obj = locate_oid(oid, &mut buf);
+                assert_eq!(db.write_buf(obj.kind, obj.data, git_hash::Kind::Sha1)?, oid);
+            }
+            db.sync_batch()?;
+            db.sync_batch()?;
+            assert_eq!(db.iter().count(), object_ids().len());
+        }
+        Ok(())
+    }
 }
 
 mod locate {
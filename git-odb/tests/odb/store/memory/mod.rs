@@ -0,0 +1,112 @@
+use git_odb::{memory, Find, FindExt, Write};
+
+use crate::{
+    fixture_path,
+    store::loose::backend::{locate_oid, object_ids},
+};
+
+fn db() -> memory::Proxy<git_odb::linked::Store> {
+    memory::proxy(git_odb::linked::Store::at(fixture_path("objects")).expect("valid object path"))
+}
+
+#[test]
+fn objects_of_the_inner_database_can_be_found() {
+    let db = db();
+    let mut buf = Vec::new();
+    for oid in object_ids() {
+        let expected = locate_oid(oid, &mut buf).data.to_owned();
+        let mut buf = Vec::new();
+        assert_eq!(
+            db.find(oid, &mut buf, &mut git_pack::cache::Never)
+                .expect("present")
+                .data,
+            expected
+        );
+    }
+    assert_eq!(db.num_objects_in_memory(), 0);
+}
+
+#[test]
+fn written_objects_are_kept_in_memory_until_persisted() -> crate::Result {
+    let db = db();
+    let id = db.write_buf(git_object::Kind::Blob, b"staged", git_hash::Kind::Sha1)?;
+    assert_eq!(
+        id,
+        git_odb::sink().write_buf(git_object::Kind::Blob, b"staged", git_hash::Kind::Sha1)?
+    );
+    assert_eq!(db.num_objects_in_memory(), 1);
+    assert!(!db.dbs[0].loose.contains(id), "the inner database is untouched");
+
+    let mut buf = Vec::new();
+    assert_eq!(db.find_blob(id, &mut buf, &mut git_pack::cache::Never)?.data, b"staged");
+
+    let tmp = tempfile::tempdir()?;
+    let out = git_odb::loose::Store::at(tmp.path());
+    assert_eq!(db.persist_into(&out, git_hash::Kind::Sha1)?, 1);
+    assert_eq!(db.num_objects_in_memory(), 0);
+    assert!(out.contains(id));
+    Ok(())
+}
+
+#[test]
+fn a_store_without_inner_database_keeps_all_objects_in_memory() -> crate::Result {
+    let db = memory::store();
+    let hash = git_hash::Kind::Sha1;
+    let blob = db.write_buf(git_object::Kind::Blob, b"content", hash)?;
+    let tree = db.write(
+        git_object::Tree {
+            entries: vec![git_object::tree::Entry {
+                mode: git_object::tree::EntryMode::Blob,
+                filename: "file".into(),
+                oid: blob,
+            }],
+        },
+        hash,
+    )?;
+    let signature = git_actor::Signature {
+        name: "name".into(),
+        email: "name@example.com".into(),
+        time: git_actor::Time {
+            time: 0,
+            offset: 0,
+            sign: git_actor::Sign::Plus,
+        },
+    };
+    let commit = db.write(
+        git_object::Commit {
+            tree,
+            parents: Default::default(),
+            author: signature.clone(),
+            committer: signature.clone(),
+            encoding: None,
+            message: "message".into(),
+            extra_headers: Vec::new(),
+        },
+        hash,
+    )?;
+    let tag = db.write(
+        git_object::Tag {
+            target: commit,
+            target_kind: git_object::Kind::Commit,
+            name: "v1".into(),
+            tagger: Some(signature),
+            message: "tag".into(),
+            pgp_signature: None,
+        },
+        hash,
+    )?;
+    assert_eq!(db.num_objects_in_memory(), 4);
+
+    let mut buf = Vec::new();
+    let cache = &mut git_pack::cache::Never;
+    let target = db.find_tag(tag, &mut buf, cache)?.target();
+    let tree = db.find_commit(target, &mut buf, cache)?.tree();
+    let blob = db.find_tree(tree, &mut buf, cache)?.entries[0].oid.to_owned();
+    assert_eq!(db.find_blob(blob, &mut buf, cache)?.data, b"content");
+
+    let missing = git_hash::ObjectId::null_sha1();
+    assert!(db.try_find(missing, &mut buf, cache)?.is_none());
+    assert!(db.contains(tag), "objects in memory are contained");
+    assert!(!db.contains(missing));
+    Ok(())
+}
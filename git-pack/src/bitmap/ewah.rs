@@ -0,0 +1,54 @@
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::bitmap::Bitmap;
+
+const HEADER_LEN: usize = 8;
+const TRAILER_LEN: usize = 4;
+const WORD_LEN: usize = 8;
+const RUNNING_BITS: u32 = 32;
+
+/// Return the size in bytes of the serialized EWAH bitmap at the start of `data`, or `None` if it's truncated.
+pub(crate) fn size(data: &[u8]) -> Option<usize> {
+    let num_words = BigEndian::read_u32(data.get(4..HEADER_LEN)?) as usize;
+    let size = HEADER_LEN + num_words.checked_mul(WORD_LEN)? + TRAILER_LEN;
+    if size <= data.len() {
+        Some(size)
+    } else {
+        None
+    }
+}
+
+/// Decode the EWAH compressed bitmap at the start of `data` the way git serializes them, or return `None` if it's malformed.
+pub(crate) fn decode(data: &[u8]) -> Option<Bitmap> {
+    size(data)?;
+    let num_bits = BigEndian::read_u32(&data[..4]) as usize;
+    let num_words = BigEndian::read_u32(&data[4..HEADER_LEN]) as usize;
+    let max_words = (num_bits + 63) / 64;
+    let compressed: Vec<u64> = data[HEADER_LEN..HEADER_LEN + num_words * WORD_LEN]
+        .chunks(WORD_LEN)
+        .map(BigEndian::read_u64)
+        .collect();
+
+    let mut words = Vec::with_capacity(max_words);
+    let mut pos = 0;
+    while pos < compressed.len() {
+        let marker = compressed[pos];
+        let running_bit = marker & 1 == 1;
+        let running_len = ((marker >> 1) & ((1 << RUNNING_BITS) - 1)) as usize;
+        let num_literals = (marker >> (1 + RUNNING_BITS)) as usize;
+        let literals = compressed.get(pos + 1..pos + 1 + num_literals)?;
+        if words.len() + running_len + num_literals > max_words {
+            return None;
+        }
+        words.extend(std::iter::repeat(if running_bit { !0 } else { 0 }).take(running_len));
+        words.extend_from_slice(literals);
+        pos += 1 + num_literals;
+    }
+    words.resize(max_words, 0);
+    if num_bits % 64 != 0 {
+        if let Some(last) = words.last_mut() {
+            *last &= (1 << (num_bits % 64)) - 1;
+        }
+    }
+    Some(Bitmap { words })
+}
@@ -0,0 +1,104 @@
+use std::{collections::HashMap, convert::TryFrom, path::Path};
+
+use byteorder::{BigEndian, ByteOrder};
+use filebuffer::FileBuffer;
+use git_hash::SIZE_OF_SHA1_DIGEST as SHA1_SIZE;
+
+use crate::bitmap::{ewah, Bitmap, Entry, Error, File};
+
+const SIGNATURE: &[u8] = b"BITM";
+const HEADER_LEN: usize = 12 + SHA1_SIZE;
+const ENTRY_HEADER_LEN: usize = 6;
+
+const OPTION_FULL_DAG: u16 = 0x1;
+const OPTION_HASH_CACHE: u16 = 0x4;
+
+/// Instantiation
+impl File {
+    /// Open the bitmap file at the given `path`.
+    pub fn at(path: impl AsRef<Path>) -> Result<File, Error> {
+        Self::try_from(path.as_ref())
+    }
+}
+
+impl TryFrom<&Path> for File {
+    type Error = Error;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        let data = FileBuffer::open(path).map_err(|e| Error::Io {
+            source: e,
+            path: path.to_owned(),
+        })?;
+        let corrupt = |message: String| Error::Corrupt { message };
+        if data.len() < HEADER_LEN + SHA1_SIZE {
+            return Err(corrupt(format!(
+                "Bitmap file of size {} is too small for even an empty bitmap",
+                data.len()
+            )));
+        }
+        if &data[..SIGNATURE.len()] != SIGNATURE {
+            return Err(corrupt("Bitmap file does not start with the expected signature".into()));
+        }
+        match BigEndian::read_u16(&data[4..6]) {
+            1 => {}
+            version => return Err(Error::UnsupportedVersion { version }),
+        }
+        let options = BigEndian::read_u16(&data[6..8]);
+        if options & OPTION_FULL_DAG == 0 {
+            return Err(corrupt("Bitmap file doesn't contain the full commit graph".into()));
+        }
+        let num_entries = BigEndian::read_u32(&data[8..12]) as usize;
+        let checksum = git_hash::ObjectId::from_20_bytes(&data[12..HEADER_LEN]);
+
+        let end = data.len() - SHA1_SIZE;
+        let mut ofs = HEADER_LEN;
+        let next_bitmap = |ofs: &mut usize| {
+            let bitmap_ofs = *ofs;
+            let size = ewah::size(&data[bitmap_ofs..end])
+                .ok_or_else(|| corrupt(format!("The bitmap at offset {} is truncated", bitmap_ofs)))?;
+            *ofs += size;
+            Ok::<_, Error>(bitmap_ofs)
+        };
+        let mut kinds: [Bitmap; 4] = Default::default();
+        for kind in kinds.iter_mut() {
+            let bitmap_ofs = next_bitmap(&mut ofs)?;
+            *kind = ewah::decode(&data[bitmap_ofs..end])
+                .ok_or_else(|| corrupt(format!("The bitmap at offset {} is corrupt", bitmap_ofs)))?;
+        }
+
+        let mut entries = Vec::with_capacity(num_entries);
+        let mut entry_by_index_position = HashMap::with_capacity(num_entries);
+        for entry_index in 0..num_entries {
+            let header = data
+                .get(ofs..ofs + ENTRY_HEADER_LEN)
+                .filter(|_| ofs + ENTRY_HEADER_LEN <= end)
+                .ok_or_else(|| corrupt(format!("Bitmap entry {} is truncated", entry_index)))?;
+            let index_position = BigEndian::read_u32(&header[..4]);
+            let xor_offset = header[4];
+            if usize::from(xor_offset) > entry_index {
+                return Err(corrupt(format!(
+                    "Bitmap entry {} refers to an entry before the first one",
+                    entry_index
+                )));
+            }
+            ofs += ENTRY_HEADER_LEN;
+            let bitmap_ofs = next_bitmap(&mut ofs)?;
+            entry_by_index_position.insert(index_position, entries.len());
+            entries.push(Entry {
+                index_position,
+                xor_offset,
+                bitmap_ofs,
+            });
+        }
+
+        Ok(File {
+            data,
+            path: path.to_owned(),
+            checksum,
+            has_hash_cache: options & OPTION_HASH_CACHE != 0,
+            kinds,
+            entries,
+            entry_by_index_position,
+        })
+    }
+}
@@ -0,0 +1,216 @@
+//! Reachability bitmaps, which store the set of objects reachable from selected commits to avoid traversing the commit graph.
+//!
+//! They are written by `git repack --write-bitmap-index` for a single pack into `pack-<hash>.bitmap`, or by
+//! `git multi-pack-index write --bitmap` for all packs of a [multi-pack index][crate::multi_index] into
+//! `multi-pack-index-<checksum>.bitmap`. In both cases each bit corresponds to an object in pack order as defined by a
+//! [reverse index][crate::index::reverse], which is why they are queried through a [`Reachability`] instance combining all of them.
+use std::{collections::HashMap, path::PathBuf};
+
+use filebuffer::FileBuffer;
+
+use crate::index::reverse;
+
+mod ewah;
+mod init;
+mod reachability;
+pub use reachability::Reachability;
+
+/// The error returned when opening or querying bitmaps.
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not open bitmap file at '{path}'")]
+    Io {
+        source: std::io::Error,
+        path: std::path::PathBuf,
+    },
+    #[error("{message}")]
+    Corrupt { message: String },
+    #[error("Unsupported bitmap version: {version}")]
+    UnsupportedVersion { version: u16 },
+    #[error("The bitmap belongs to {actual}, but the index belongs to {expected}")]
+    Mismatch {
+        expected: git_hash::ObjectId,
+        actual: git_hash::ObjectId,
+    },
+    #[error(transparent)]
+    ReverseIndex(#[from] reverse::Error),
+}
+
+/// A set of objects, each identified by its position in pack order.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Default)]
+pub struct Bitmap {
+    words: Vec<u64>,
+}
+
+impl Bitmap {
+    /// Returns true if the object at `pack_position` is contained in this set.
+    pub fn contains(&self, pack_position: u32) -> bool {
+        self.words
+            .get(pack_position as usize / 64)
+            .map_or(false, |word| word & (1 << (pack_position % 64)) != 0)
+    }
+
+    /// Return the amount of objects in this set.
+    pub fn count(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Iterate over the pack positions of all objects in this set, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.words.iter().enumerate().flat_map(|(word_index, word)| {
+            let mut word = *word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros();
+                word &= word - 1;
+                Some(word_index as u32 * 64 + bit)
+            })
+        })
+    }
+
+    /// Return the objects contained in this set and in `other`.
+    pub fn intersection(&self, other: &Bitmap) -> Bitmap {
+        Bitmap {
+            words: self.words.iter().zip(other.words.iter()).map(|(a, b)| a & b).collect(),
+        }
+    }
+
+    fn xor(&mut self, other: &Bitmap) {
+        if self.words.len() < other.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        for (a, b) in self.words.iter_mut().zip(other.words.iter()) {
+            *a ^= b;
+        }
+    }
+}
+
+/// The objects a bitmap can refer to, as stored in the index of a single pack or in a multi-pack index.
+pub trait Index {
+    /// The amount of objects in the index.
+    fn num_objects(&self) -> u32;
+    /// Return the position of the object with `id` in the index, if it's present.
+    fn lookup(&self, id: &git_hash::oid) -> Option<u32>;
+    /// Return the id of the object at `index`.
+    fn oid_at_index(&self, index: u32) -> &git_hash::oid;
+    /// The checksum bitmaps and reverse indices use to refer to the index, which is the checksum of the pack data file
+    /// for single packs.
+    fn checksum(&self) -> git_hash::ObjectId;
+}
+
+impl Index for crate::index::File {
+    fn num_objects(&self) -> u32 {
+        self.num_objects()
+    }
+
+    fn lookup(&self, id: &git_hash::oid) -> Option<u32> {
+        self.lookup(id)
+    }
+
+    fn oid_at_index(&self, index: u32) -> &git_hash::oid {
+        self.oid_at_index(index)
+    }
+
+    fn checksum(&self) -> git_hash::ObjectId {
+        self.pack_checksum()
+    }
+}
+
+impl Index for crate::multi_index::File {
+    fn num_objects(&self) -> u32 {
+        self.num_objects()
+    }
+
+    fn lookup(&self, id: &git_hash::oid) -> Option<u32> {
+        self.lookup(id)
+    }
+
+    fn oid_at_index(&self, index: u32) -> &git_hash::oid {
+        self.oid_at_index(index)
+    }
+
+    fn checksum(&self) -> git_hash::ObjectId {
+        self.checksum()
+    }
+}
+
+/// A bitmap file with the objects reachable from selected commits, along with the objects of each kind.
+pub struct File {
+    data: FileBuffer,
+    path: PathBuf,
+    checksum: git_hash::ObjectId,
+    has_hash_cache: bool,
+    /// The bitmaps for commits, trees, blobs and tags.
+    kinds: [Bitmap; 4],
+    entries: Vec<Entry>,
+    entry_by_index_position: HashMap<u32, usize>,
+}
+
+struct Entry {
+    index_position: u32,
+    xor_offset: u8,
+    bitmap_ofs: usize,
+}
+
+/// Access
+impl File {
+    /// The path of the opened bitmap file.
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// The checksum of the pack data file or multi-pack index this bitmap belongs to.
+    pub fn checksum(&self) -> &git_hash::oid {
+        &self.checksum
+    }
+
+    /// The amount of commits with a bitmap of the objects reachable from them.
+    pub fn num_commits(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the file contains a hash of the path of each object, which is used to find good delta bases.
+    pub fn has_hash_cache(&self) -> bool {
+        self.has_hash_cache
+    }
+
+    /// Return the set of all objects of the given `kind`.
+    pub fn objects_of_kind(&self, kind: git_object::Kind) -> &Bitmap {
+        use git_object::Kind::*;
+        &self.kinds[match kind {
+            Commit => 0,
+            Tree => 1,
+            Blob => 2,
+            Tag => 3,
+        }]
+    }
+
+    /// Return the objects reachable from the commit at `index_position` in the index this bitmap belongs to, or `None`
+    /// if there is no bitmap for it.
+    pub fn reachable_from_index_position(&self, index_position: u32) -> Result<Option<Bitmap>, Error> {
+        let mut entry_index = match self.entry_by_index_position.get(&index_position) {
+            Some(entry_index) => *entry_index,
+            None => return Ok(None),
+        };
+        let mut chain = Vec::new();
+        loop {
+            let entry = &self.entries[entry_index];
+            chain.push(entry.bitmap_ofs);
+            if entry.xor_offset == 0 {
+                break;
+            }
+            entry_index -= usize::from(entry.xor_offset);
+        }
+        let mut out = Bitmap::default();
+        for bitmap_ofs in chain.into_iter().rev() {
+            let bitmap = ewah::decode(&self.data[bitmap_ofs..]).ok_or_else(|| Error::Corrupt {
+                message: format!("The bitmap at offset {} is corrupt", bitmap_ofs),
+            })?;
+            out.xor(&bitmap);
+        }
+        Ok(Some(out))
+    }
+}
@@ -0,0 +1,150 @@
+use std::path::Path;
+
+use crate::{
+    bitmap::{self, Bitmap, Error, Index},
+    index::{self, reverse},
+    multi_index,
+};
+
+/// Answers reachability queries with a [bitmap file][bitmap::File] for the objects of an [index][Index], whose
+/// pack order is defined by a [reverse index][reverse::Index].
+///
+/// It works the same for bitmaps of single packs and multi-pack indices.
+pub struct Reachability<'a, I> {
+    index: &'a I,
+    bitmaps: bitmap::File,
+    order: reverse::Index,
+    pack_positions: Vec<u32>,
+}
+
+/// Instantiation
+impl<'a, I: Index> Reachability<'a, I> {
+    /// Combine `index` with the `bitmaps` and the pack `order` belonging to it.
+    pub fn new(index: &'a I, bitmaps: bitmap::File, order: reverse::Index) -> Result<Self, Error> {
+        let checksum = index.checksum();
+        if bitmaps.checksum() != checksum {
+            return Err(Error::Mismatch {
+                expected: checksum,
+                actual: bitmaps.checksum().to_owned(),
+            });
+        }
+        if order.num_objects() != index.num_objects() {
+            return Err(Error::Corrupt {
+                message: format!(
+                    "The reverse index has {} objects, but the index has {}",
+                    order.num_objects(),
+                    index.num_objects()
+                ),
+            });
+        }
+        if let Some(entry) = bitmaps
+            .entries
+            .iter()
+            .find(|entry| entry.index_position >= index.num_objects())
+        {
+            return Err(Error::Corrupt {
+                message: format!(
+                    "A bitmap refers to the commit at index position {}, but there are only {} objects",
+                    entry.index_position,
+                    index.num_objects()
+                ),
+            });
+        }
+        let pack_positions = order.pack_positions();
+        Ok(Reachability {
+            index,
+            bitmaps,
+            order,
+            pack_positions,
+        })
+    }
+}
+
+impl<'a> Reachability<'a, index::File> {
+    /// Open the `.bitmap` file next to `index` along with its `.rev` file, or compute the pack order if there is none.
+    ///
+    /// Returns `None` if there is no bitmap for the pack.
+    pub fn from_pack_index(index: &'a index::File) -> Result<Option<Self>, Error> {
+        let bitmap_path = index.path().with_extension("bitmap");
+        if !bitmap_path.is_file() {
+            return Ok(None);
+        }
+        let bitmaps = bitmap::File::at(bitmap_path)?;
+        let rev_path = index.path().with_extension("rev");
+        let order = if rev_path.is_file() {
+            reverse::Index::at(rev_path, index.num_objects(), &index.pack_checksum())?
+        } else {
+            reverse::Index::from_pack_index(index)
+        };
+        Self::new(index, bitmaps, order).map(Some)
+    }
+}
+
+impl<'a> Reachability<'a, multi_index::File> {
+    /// Open the `multi-pack-index-<checksum>.bitmap` file next to `index` and obtain the pack order from the index itself
+    /// or the `.rev` file next to it.
+    ///
+    /// Returns `None` if there is no bitmap for the multi-pack index.
+    pub fn from_multi_index(index: &'a multi_index::File) -> Result<Option<Self>, Error> {
+        let checksum = index.checksum();
+        let path = |extension: &str| {
+            index
+                .path()
+                .parent()
+                .unwrap_or_else(|| Path::new("."))
+                .join(format!("multi-pack-index-{}.{}", checksum, extension))
+        };
+        let bitmap_path = path("bitmap");
+        if !bitmap_path.is_file() {
+            return Ok(None);
+        }
+        let bitmaps = bitmap::File::at(bitmap_path)?;
+        let order = match index.reverse_index() {
+            Some(order) => order?,
+            None => reverse::Index::at(path("rev"), index.num_objects(), &checksum)?,
+        };
+        Self::new(index, bitmaps, order).map(Some)
+    }
+}
+
+/// Queries
+impl<'a, I: Index> Reachability<'a, I> {
+    /// Return the objects reachable from `commit`, including itself, or `None` if there is no bitmap for it.
+    pub fn reachable_from(&self, commit: impl AsRef<git_hash::oid>) -> Result<Option<Bitmap>, Error> {
+        match self.index.lookup(commit.as_ref()) {
+            Some(index_position) => self.bitmaps.reachable_from_index_position(index_position),
+            None => Ok(None),
+        }
+    }
+
+    /// Return true if `bitmap` contains the object with `id`.
+    pub fn contains(&self, bitmap: &Bitmap, id: impl AsRef<git_hash::oid>) -> bool {
+        self.index.lookup(id.as_ref()).map_or(false, |index_position| {
+            bitmap.contains(self.pack_positions[index_position as usize])
+        })
+    }
+
+    /// Iterate over the ids of all objects in `bitmap`, in pack order.
+    pub fn ids<'b>(&'b self, bitmap: &'b Bitmap) -> impl Iterator<Item = &'a git_hash::oid> + 'b {
+        let index: &'a I = self.index;
+        bitmap
+            .iter()
+            .filter(move |pack_position| *pack_position < self.order.num_objects())
+            .map(move |pack_position| index.oid_at_index(self.order.index_position(pack_position)))
+    }
+
+    /// Return the set of all objects of the given `kind`.
+    pub fn objects_of_kind(&self, kind: git_object::Kind) -> &Bitmap {
+        self.bitmaps.objects_of_kind(kind)
+    }
+
+    /// The bitmap file used to answer queries.
+    pub fn bitmaps(&self) -> &bitmap::File {
+        &self.bitmaps
+    }
+
+    /// The order of objects the bitmaps refer to.
+    pub fn order(&self) -> &reverse::Index {
+        &self.order
+    }
+}
@@ -55,6 +55,8 @@ mod verify {
             self.index.verify_integrity(
                 Some((&self.pack, verify_mode, traversal, make_pack_lookup_cache)),
                 thread_limit,
+                0,
+                None,
                 progress,
                 should_interrupt,
             )
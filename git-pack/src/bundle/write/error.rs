@@ -2,7 +2,9 @@ use std::io;
 
 use git_tempfile::handle::Writable;
 
+/// The error returned by [`Bundle::write_to_directory()`][crate::Bundle::write_to_directory()].
 #[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
 pub enum Error {
     #[error("An IO error occurred when reading the pack or creating a temporary file")]
     Io(#[from] io::Error),
@@ -12,4 +14,6 @@ pub enum Error {
     Perist(#[from] git_tempfile::handle::persist::Error<Writable>),
     #[error(transparent)]
     IndexWrite(#[from] crate::index::write::Error),
+    #[error("Could not open the written index to generate its reverse index")]
+    IndexOpen(#[from] crate::index::init::Error),
 }
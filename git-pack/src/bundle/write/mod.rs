@@ -1,6 +1,6 @@
 use std::{
     io,
-    path::{Path, PathBuf},
+    path::Path,
     sync::{atomic::AtomicBool, Arc},
 };
 
@@ -11,7 +11,7 @@ use git_tempfile::{handle::Writable, AutoRemove, ContainingDirectory};
 use crate::data;
 
 mod error;
-use error::Error;
+pub use error::Error;
 
 mod types;
 use types::{LockWriter, PassThrough};
@@ -107,21 +107,15 @@ impl crate::Bundle {
                 (Box::new(pack_entries_iter), pack_kind)
             }
         };
-        let (outcome, data_path, index_path) = crate::Bundle::inner_write(
+        crate::Bundle::inner_write(
             directory,
             progress,
             options,
             data_file,
             pack_entries_iter,
-            should_interrupt,
-        )?;
-
-        Ok(Outcome {
-            index: outcome,
             pack_kind,
-            data_path,
-            index_path,
-        })
+            should_interrupt,
+        )
     }
 
     /// Equivalent to [`write_to_directory()`][crate::Bundle::write_to_directory()] but offloads reading of the pack into its own thread, hence the `Send + 'static'` bounds.
@@ -195,21 +189,15 @@ impl crate::Bundle {
         let pack_entries_iter =
             git_features::parallel::EagerIterIf::new(move || num_objects > 25_000, pack_entries_iter, 5_000, 5);
 
-        let (outcome, data_path, index_path) = crate::Bundle::inner_write(
+        crate::Bundle::inner_write(
             directory,
             progress,
             options,
             data_file,
             pack_entries_iter,
-            should_interrupt,
-        )?;
-
-        Ok(Outcome {
-            index: outcome,
             pack_kind,
-            data_path,
-            index_path,
-        })
+            should_interrupt,
+        )
     }
 
     fn inner_write(
@@ -219,13 +207,16 @@ impl crate::Bundle {
             thread_limit,
             iteration_mode: _,
             index_kind,
+            write_reverse_index,
+            fsync,
         }: Options,
         data_file: Arc<parking_lot::Mutex<git_tempfile::Handle<Writable>>>,
         pack_entries_iter: impl Iterator<Item = Result<data::input::Entry, data::input::Error>>,
+        pack_kind: data::Version,
         should_interrupt: &AtomicBool,
-    ) -> Result<(crate::index::write::Outcome, Option<PathBuf>, Option<PathBuf>), Error> {
+    ) -> Result<Outcome, Error> {
         let indexing_progress = progress.add_child("create index file");
-        Ok(match directory {
+        let (index, data_path, index_path, reverse_index_path) = match directory {
             Some(directory) => {
                 let directory = directory.as_ref();
                 let mut index_file = git_tempfile::new(directory, ContainingDirectory::Exists, AutoRemove::Tempfile)?;
@@ -246,10 +237,14 @@ impl crate::Bundle {
                 let data_path = directory.join(format!("{}.pack", outcome.data_hash.to_sha1_hex_string()));
                 let index_path = data_path.with_extension("idx");
 
-                Arc::try_unwrap(data_file)
+                let mut data_file = Arc::try_unwrap(data_file)
                     .expect("only one handle left after pack was consumed")
-                    .into_inner()
-                    .persist(&data_path)?;
+                    .into_inner();
+                if fsync.pack() {
+                    data_file.with_mut(|f| f.as_file().sync_all())??;
+                    index_file.with_mut(|f| f.as_file().sync_all())??;
+                }
+                data_file.persist(&data_path)?;
                 index_file
                     .persist(&index_path)
                     .map_err(|err| {
@@ -259,7 +254,26 @@ impl crate::Bundle {
                         ));
                         err
                     })?;
-                (outcome, Some(data_path), Some(index_path))
+
+                let reverse_index_path = if write_reverse_index {
+                    let index = crate::index::File::at(&index_path)?;
+                    let reverse_index_path = data_path.with_extension("rev");
+                    let mut reverse_index_file =
+                        git_tempfile::new(directory, ContainingDirectory::Exists, AutoRemove::Tempfile)?;
+                    crate::index::reverse::Index::from_pack_index(&index)
+                        .write_to(&mut reverse_index_file, &outcome.data_hash)?;
+                    if fsync.pack() {
+                        reverse_index_file.with_mut(|f| f.as_file().sync_all())??;
+                    }
+                    reverse_index_file.persist(&reverse_index_path)?;
+                    Some(reverse_index_path)
+                } else {
+                    None
+                };
+                if fsync.metadata() {
+                    git_features::fs::sync_dir(directory)?;
+                }
+                (outcome, Some(data_path), Some(index_path), reverse_index_path)
             }
             None => (
                 crate::index::File::write_data_iter_to_stream(
@@ -273,7 +287,15 @@ impl crate::Bundle {
                 )?,
                 None,
                 None,
+                None,
             ),
+        };
+        Ok(Outcome {
+            index,
+            pack_kind,
+            data_path,
+            index_path,
+            reverse_index_path,
         })
     }
 }
@@ -12,6 +12,12 @@ pub struct Options {
     pub iteration_mode: crate::data::input::Mode,
     /// The version of pack index to write, should be [`crate::index::Version::default()`]
     pub index_kind: crate::index::Version,
+    /// If true, write a reverse index into a `.rev` file next to the index to allow opening bitmaps and verifying huge packs
+    /// without computing the pack order first, similar to `pack.writeReverseIndex`.
+    pub write_reverse_index: bool,
+    /// Synchronize the pack, index and reverse index to permanent storage before moving them into place
+    /// unless this is [`Fsync::None`][git_features::fs::Fsync::None].
+    pub fsync: git_features::fs::Fsync,
 }
 
 impl Default for Options {
@@ -21,6 +27,8 @@ impl Default for Options {
             thread_limit: None,
             iteration_mode: crate::data::input::Mode::Verify,
             index_kind: Default::default(),
+            write_reverse_index: false,
+            fsync: Default::default(),
         }
     }
 }
@@ -39,6 +47,8 @@ pub struct Outcome {
     pub index_path: Option<PathBuf>,
     /// The path to the pack data file
     pub data_path: Option<PathBuf>,
+    /// The path to the reverse index file, if [`Options::write_reverse_index`] was set.
+    pub reverse_index_path: Option<PathBuf>,
 }
 
 impl Outcome {
@@ -82,6 +82,10 @@ mod memory {
             }
             res
         }
+
+        fn memory_in_bytes(&self) -> usize {
+            self.inner.weight() + self.free_list.iter().map(Vec::capacity).sum::<usize>()
+        }
     }
 }
 
@@ -158,6 +162,11 @@ mod _static {
             }
             res
         }
+
+        fn memory_in_bytes(&self) -> usize {
+            self.inner.iter().map(|e| e.data.capacity()).sum::<usize>()
+                + self.free_list.iter().map(Vec::capacity).sum::<usize>()
+        }
     }
 }
 
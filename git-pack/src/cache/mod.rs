@@ -1,4 +1,4 @@
-use std::ops::DerefMut;
+use std::ops::{Deref, DerefMut};
 
 use git_object::Kind;
 
@@ -13,6 +13,10 @@ pub trait DecodeEntry {
     /// Attempt to fetch the object at `offset` and store its decoded bytes in `out`, as previously stored with [`DecodeEntry::put()`], and return
     /// its (object `kind`, `decompressed_size`)
     fn get(&mut self, pack_id: u32, offset: u64, out: &mut Vec<u8>) -> Option<(git_object::Kind, usize)>;
+    /// Return the amount of bytes currently used to hold cached objects, or `0` if this cache doesn't hold any.
+    fn memory_in_bytes(&self) -> usize {
+        0
+    }
 }
 
 /// A cache that stores nothing and retrieves nothing, thus it _never_ caches.
@@ -34,6 +38,10 @@ impl<T: DecodeEntry + ?Sized> DecodeEntry for Box<T> {
     fn get(&mut self, pack_id: u32, offset: u64, out: &mut Vec<u8>) -> Option<(Kind, usize)> {
         self.deref_mut().get(pack_id, offset, out)
     }
+
+    fn memory_in_bytes(&self) -> usize {
+        self.deref().memory_in_bytes()
+    }
 }
 
 /// A way of storing and retrieving entire objects to and from a cache.
@@ -43,6 +51,11 @@ pub trait Object {
 
     /// Try to retrieve the object named `id` and place its data into `out` if available and return `Some(kind)` if found.
     fn get(&mut self, id: &git_hash::ObjectId, out: &mut Vec<u8>) -> Option<git_object::Kind>;
+
+    /// Return the amount of bytes currently used to hold cached objects, or `0` if this cache doesn't hold any.
+    fn memory_in_bytes(&self) -> usize {
+        0
+    }
 }
 
 /// Various implementations of [`DecodeEntry`] using least-recently-used algorithms.
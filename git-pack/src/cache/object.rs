@@ -97,6 +97,10 @@ mod memory {
             }
             res
         }
+
+        fn memory_in_bytes(&self) -> usize {
+            self.inner.weight() + self.free_list.iter().map(Vec::capacity).sum::<usize>()
+        }
     }
 }
 #[cfg(feature = "object-cache-dynamic")]
@@ -152,4 +156,9 @@ impl<T: cache::Object + ?Sized> cache::Object for Box<T> {
         use std::ops::DerefMut;
         self.deref_mut().get(id, out)
     }
+
+    fn memory_in_bytes(&self) -> usize {
+        use std::ops::Deref;
+        self.deref().memory_in_bytes()
+    }
 }
@@ -0,0 +1,267 @@
+use std::convert::TryFrom;
+
+/// The amount of bytes that are fingerprinted at once, which is also the smallest amount of bytes we try to copy from the base.
+const WINDOW: usize = 16;
+/// The modulus of the fingerprints, a polynomial of degree 31 over GF(2).
+const POLYNOMIAL: u64 = 0xab59_b4d1;
+/// The amount of bits to shift a fingerprint to the right to obtain the bits to reduce when a byte is shifted in.
+const SHIFT: u32 = 23;
+/// The most offsets to keep per bucket, to bound the time spent on highly repetitive bases.
+const MAX_BUCKET_LEN: usize = 64;
+/// The largest amount of bytes to copy with a single instruction, which is also the limit of older versions of git.
+const MAX_COPY: usize = 0x10000;
+/// The largest amount of bytes to insert with a single instruction.
+const MAX_INSERT: usize = 0x7f;
+
+/// Reduce `value` modulo [`POLYNOMIAL`].
+const fn reduce(mut value: u64) -> u64 {
+    let mut bit = 63;
+    while bit >= 31 {
+        if value & (1 << bit) != 0 {
+            value ^= POLYNOMIAL << (bit - 31);
+        }
+        bit -= 1;
+    }
+    value
+}
+
+/// `T[i]` turns the bits `i` that are shifted out of a fingerprint into the remainder they leave, and `U[c]` is the share of the
+/// byte `c` in the fingerprint of a window which it is the first byte of.
+const fn tables() -> ([u32; 256], [u32; 256]) {
+    let mut t = [0u32; 256];
+    let mut u = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        t[i] = (reduce((i as u64) << 31) ^ ((i as u64 & 1) << 31)) as u32;
+        let mut value = i as u64;
+        let mut shifts = 0;
+        while shifts < 8 * (WINDOW - 1) {
+            value = reduce(value << 1);
+            shifts += 1;
+        }
+        u[i] = value as u32;
+        i += 1;
+    }
+    (t, u)
+}
+
+const TABLES: ([u32; 256], [u32; 256]) = tables();
+
+/// Add `byte` to the end of the window with the fingerprint `fingerprint`.
+fn push(fingerprint: u32, byte: u8) -> u32 {
+    ((fingerprint << 8) | byte as u32) ^ TABLES.0[(fingerprint >> SHIFT) as usize]
+}
+
+/// Move the window with the fingerprint `fingerprint` by one byte, removing `first` and adding `byte`.
+fn roll(fingerprint: u32, first: u8, byte: u8) -> u32 {
+    push(fingerprint ^ TABLES.1[first as usize], byte)
+}
+
+fn fingerprint(window: &[u8]) -> u32 {
+    window.iter().fold(0, |fingerprint, byte| push(fingerprint, *byte))
+}
+
+/// An index of the Rabin fingerprints of a base object, to create deltas of any amount of objects against it.
+///
+/// This works like `git`'s `diff-delta.c`: the fingerprints of non-overlapping windows of the base are indexed, and all
+/// windows of an object are looked up to find ranges to copy from the base.
+pub struct Index<'a> {
+    base: &'a [u8],
+    mask: u32,
+    /// The fingerprints and offsets of windows of the base, in buckets by fingerprint.
+    buckets: Vec<Vec<(u32, u32)>>,
+}
+
+impl<'a> Index<'a> {
+    /// Create an index for deltas against `base`.
+    ///
+    /// # Panics
+    ///
+    /// If `base` is larger than 4GB, which git doesn't support either.
+    pub fn new(base: &'a [u8]) -> Self {
+        assert!(
+            u32::try_from(base.len()).is_ok(),
+            "bases of deltas must be smaller than 4GB"
+        );
+        let num_buckets = (base.len() / WINDOW).max(1).next_power_of_two();
+        let mut buckets = vec![Vec::new(); num_buckets];
+        let mask = (num_buckets - 1) as u32;
+        // Later windows are added first so that earlier ones are preferred in case of bucket overflow, as they are visited last.
+        for (ofs, window) in base.chunks_exact(WINDOW).enumerate().rev() {
+            let fingerprint = fingerprint(window);
+            buckets[(fingerprint & mask) as usize].push((fingerprint, (ofs * WINDOW) as u32));
+        }
+        for bucket in &mut buckets {
+            if bucket.len() > MAX_BUCKET_LEN {
+                let step = bucket.len() as f32 / MAX_BUCKET_LEN as f32;
+                *bucket = (0..MAX_BUCKET_LEN)
+                    .map(|idx| bucket[(idx as f32 * step) as usize])
+                    .collect();
+            }
+        }
+        Index { base, mask, buckets }
+    }
+
+    /// Return the base object the deltas are created against.
+    pub fn base(&self) -> &[u8] {
+        self.base
+    }
+
+    /// Return a delta which produces `target` from the base, or `None` if it would be larger than `max_size` bytes.
+    ///
+    /// Use a `max_size` smaller than `target` to only obtain deltas that are worth storing.
+    pub fn create(&self, target: &[u8], max_size: Option<usize>) -> Option<Vec<u8>> {
+        let mut out = Vec::new();
+        if self.encode(target, max_size.unwrap_or(usize::MAX), &mut out) {
+            Some(out)
+        } else {
+            None
+        }
+    }
+
+    /// Return the size in bytes of the delta which produces `target` from the base, without keeping it in memory.
+    pub fn delta_size(&self, target: &[u8]) -> usize {
+        let mut counter = Counter(0);
+        self.encode(target, usize::MAX, &mut counter);
+        counter.0
+    }
+
+    /// Find the longest range of the base that starts like the window of `target` at `pos` with fingerprint `fingerprint`,
+    /// and return its offset and length.
+    fn find_copy(&self, target: &[u8], pos: usize, fingerprint: u32) -> Option<(usize, usize)> {
+        let mut best: Option<(usize, usize)> = None;
+        for &(candidate, ofs) in &self.buckets[(fingerprint & self.mask) as usize] {
+            if candidate != fingerprint {
+                continue;
+            }
+            let ofs = ofs as usize;
+            let len = self.base[ofs..]
+                .iter()
+                .zip(&target[pos..])
+                .take_while(|(a, b)| a == b)
+                .count();
+            if best.map_or(true, |(_, best_len)| len > best_len) {
+                best = Some((ofs, len));
+            }
+        }
+        best.filter(|(_, len)| *len >= WINDOW)
+    }
+
+    fn encode(&self, target: &[u8], max_size: usize, out: &mut impl Output) -> bool {
+        encode_size(self.base.len(), out);
+        encode_size(target.len(), out);
+
+        let mut insert_start = 0;
+        let mut pos = 0;
+        let mut fingerprint = target.get(..WINDOW).map_or(0, self::fingerprint);
+        while pos + WINDOW <= target.len() {
+            if let Some((mut ofs, mut len)) = self.find_copy(target, pos, fingerprint) {
+                // Extend the copy backwards into the bytes which would have to be inserted otherwise.
+                while pos > insert_start && ofs > 0 && self.base[ofs - 1] == target[pos - 1] {
+                    ofs -= 1;
+                    pos -= 1;
+                    len += 1;
+                }
+                encode_insert(&target[insert_start..pos], out);
+                encode_copy(ofs, len, out);
+                pos += len;
+                insert_start = pos;
+                if let Some(window) = target.get(pos..pos + WINDOW) {
+                    fingerprint = self::fingerprint(window);
+                }
+            } else {
+                if let Some(&byte) = target.get(pos + WINDOW) {
+                    fingerprint = roll(fingerprint, target[pos], byte);
+                }
+                pos += 1;
+            }
+            if out.len() > max_size {
+                return false;
+            }
+        }
+        encode_insert(&target[insert_start..], out);
+        out.len() <= max_size
+    }
+}
+
+/// Return a delta which produces `target` from `base`.
+///
+/// Use an [`Index`] to create deltas of multiple objects against the same base, to limit their size or to learn their size.
+pub fn create(base: &[u8], target: &[u8]) -> Vec<u8> {
+    Index::new(base).create(target, None).expect("deltas are unlimited")
+}
+
+/// Where encoded deltas are written to.
+trait Output {
+    fn extend(&mut self, bytes: &[u8]);
+    fn len(&self) -> usize;
+}
+
+impl Output for Vec<u8> {
+    fn extend(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes)
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+}
+
+/// An output which only counts bytes.
+struct Counter(usize);
+
+impl Output for Counter {
+    fn extend(&mut self, bytes: &[u8]) {
+        self.0 += bytes.len()
+    }
+
+    fn len(&self) -> usize {
+        self.0
+    }
+}
+
+/// The inverse of [`decode_header_size()`][super::decode_header_size()].
+fn encode_size(mut size: usize, out: &mut impl Output) {
+    let mut buf = [0u8; 10];
+    let mut len = 0;
+    loop {
+        buf[len] = (size & 0x7f) as u8;
+        size >>= 7;
+        if size == 0 {
+            len += 1;
+            break;
+        }
+        buf[len] |= 0x80;
+        len += 1;
+    }
+    out.extend(&buf[..len]);
+}
+
+fn encode_insert(data: &[u8], out: &mut impl Output) {
+    for chunk in data.chunks(MAX_INSERT) {
+        out.extend(&[chunk.len() as u8]);
+        out.extend(chunk);
+    }
+}
+
+fn encode_copy(mut ofs: usize, mut len: usize, out: &mut impl Output) {
+    while len > 0 {
+        let size = len.min(MAX_COPY);
+        let mut buf = [0u8; 8];
+        let mut buf_len = 1;
+        for (bit, value) in [(0, ofs), (4, size)] {
+            for byte in 0..if bit == 0 { 4 } else { 3 } {
+                let value = (value >> (byte * 8)) as u8;
+                if value != 0 {
+                    buf[0] |= 1 << (bit + byte);
+                    buf[buf_len] = value;
+                    buf_len += 1;
+                }
+            }
+        }
+        buf[0] |= 0x80;
+        out.extend(&buf[..buf_len]);
+        ofs += size;
+        len -= size;
+    }
+}
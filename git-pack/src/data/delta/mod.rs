@@ -0,0 +1,199 @@
+//! Create and apply deltas in the format git uses in packs, which describe an object as instructions to copy ranges of a base
+//! object or to insert new data.
+//!
+//! A delta starts with the sizes of the base and the result, followed by instructions. [`Index`] creates deltas against a base,
+//! while [`resolve()`] applies them to it. [`base_candidates()`] and [`best()`] help choosing the base to create a delta against.
+use std::convert::TryFrom;
+
+mod create;
+pub use create::{create, Index};
+
+/// Return the indices of the `objects` to try as base for a delta of each of them, which are the ones closest in size
+/// according to `limits`, without comparing their content.
+///
+/// All `objects` should be of the same kind. Objects are never proposed as base of objects with the same id, and
+/// [`Limits::max_pairs`][git_diff::candidates::Limits::max_pairs] is ignored as the amount of candidates per object is bounded.
+pub fn base_candidates(
+    objects: &[git_diff::candidates::Item],
+    limits: &git_diff::candidates::Limits,
+) -> Vec<Vec<usize>> {
+    let index = git_diff::candidates::Index::new(objects);
+    let mut buf = Vec::new();
+    objects
+        .iter()
+        .map(|object| {
+            index.similar(object, limits, &mut buf);
+            buf.clone()
+        })
+        .collect()
+}
+
+/// Create deltas of `target` against each of the `bases` identified by an arbitrary token, and return the token of the base
+/// producing the smallest delta along with the delta, or `None` if no delta is smaller than half of `target`.
+pub fn best<T>(target: &[u8], bases: impl IntoIterator<Item = (T, impl AsRef<[u8]>)>) -> Option<(T, Vec<u8>)> {
+    let mut best: Option<(T, Vec<u8>)> = None;
+    for (token, base) in bases {
+        let max_size = best
+            .as_ref()
+            .map_or(target.len() / 2, |(_, delta)| delta.len().saturating_sub(1));
+        if let Some(delta) = Index::new(base.as_ref()).create(target, Some(max_size)) {
+            best = Some((token, delta));
+        }
+    }
+    best
+}
+
+/// Given the decompressed pack delta `d`, decode a size in bytes (either the base object size or the result object size)
+/// Equivalent to [this canonical git function](https://github.com/git/git/blob/311531c9de557d25ac087c1637818bd2aad6eb3a/delta.h#L89)
+pub fn decode_header_size(d: &[u8]) -> (u64, usize) {
+    let mut i = 0;
+    let mut size = 0u64;
+    let mut consumed = 0;
+    for cmd in d.iter() {
+        consumed += 1;
+        size |= (*cmd as u64 & 0x7f) << i;
+        i += 7;
+        if *cmd & 0x80 == 0 {
+            break;
+        }
+    }
+    (size, consumed)
+}
+
+/// Apply the delta instructions in `data`, which follow the headers decoded with [`decode_header_size()`], to `base` and
+/// write the result into `target`, which must have the size of the result.
+///
+/// # Panics
+///
+/// If the instructions are malformed or don't fit `base` and `target`, which is why [`resolve()`] should be used for deltas
+/// that weren't validated before.
+pub fn apply(base: &[u8], mut target: &mut [u8], data: &[u8]) {
+    let mut i = 0;
+    while let Some(cmd) = data.get(i) {
+        i += 1;
+        match cmd {
+            cmd if cmd & 0b1000_0000 != 0 => {
+                let (mut ofs, mut size): (u32, u32) = (0, 0);
+                if cmd & 0b0000_0001 != 0 {
+                    ofs = data[i] as u32;
+                    i += 1;
+                }
+                if cmd & 0b0000_0010 != 0 {
+                    ofs |= (data[i] as u32) << 8;
+                    i += 1;
+                }
+                if cmd & 0b0000_0100 != 0 {
+                    ofs |= (data[i] as u32) << 16;
+                    i += 1;
+                }
+                if cmd & 0b0000_1000 != 0 {
+                    ofs |= (data[i] as u32) << 24;
+                    i += 1;
+                }
+                if cmd & 0b0001_0000 != 0 {
+                    size = data[i] as u32;
+                    i += 1;
+                }
+                if cmd & 0b0010_0000 != 0 {
+                    size |= (data[i] as u32) << 8;
+                    i += 1;
+                }
+                if cmd & 0b0100_0000 != 0 {
+                    size |= (data[i] as u32) << 16;
+                    i += 1;
+                }
+                if size == 0 {
+                    size = 0x10000; // 65536
+                }
+                let ofs = ofs as usize;
+                std::io::Write::write(&mut target, &base[ofs..ofs + size as usize])
+                    .expect("delta copy from base: byte slices must match");
+            }
+            0 => panic!("encountered unsupported command code: 0"),
+            size => {
+                std::io::Write::write(&mut target, &data[i..i + *size as usize])
+                    .expect("delta copy data: slice sizes to match up");
+                i += *size as usize;
+            }
+        }
+    }
+    assert_eq!(i, data.len());
+    assert_eq!(target.len(), 0);
+}
+
+///
+pub mod resolve {
+    /// The error returned by [`resolve()`][super::resolve()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The delta expects a base of {expected} bytes, but it has {actual} bytes")]
+        BaseSize { expected: u64, actual: usize },
+        #[error("The delta is truncated or contains invalid instructions")]
+        Corrupt,
+    }
+}
+
+/// Apply `delta`, including its headers, to `base` and place the result into `out`, after clearing it.
+///
+/// In contrast to [`apply()`], the delta is validated to fit `base` and to produce a result of the size it claims.
+pub fn resolve(base: &[u8], delta: &[u8], out: &mut Vec<u8>) -> Result<(), resolve::Error> {
+    use resolve::Error;
+    let header_size = |data: &[u8]| -> Result<(u64, usize), Error> {
+        let (size, consumed) = decode_header_size(data);
+        match consumed.checked_sub(1).map(|last| data[last]) {
+            Some(last) if last & 0x80 == 0 => Ok((size, consumed)),
+            _ => Err(Error::Corrupt),
+        }
+    };
+    let (base_size, consumed) = header_size(delta)?;
+    if base_size != base.len() as u64 {
+        return Err(Error::BaseSize {
+            expected: base_size,
+            actual: base.len(),
+        });
+    }
+    let (result_size, result_consumed) = header_size(&delta[consumed..])?;
+    let data = &delta[consumed + result_consumed..];
+    let result_size = usize::try_from(result_size).map_err(|_| Error::Corrupt)?;
+
+    out.clear();
+    out.reserve(result_size.min(delta.len().saturating_mul(128)));
+    let mut bytes = data.iter();
+    while let Some(&cmd) = bytes.next() {
+        if cmd & 0b1000_0000 != 0 {
+            let mut value = |bits: std::ops::Range<u8>| -> Result<usize, Error> {
+                let mut value = 0;
+                for (shift, bit) in bits.enumerate() {
+                    if cmd & (1 << bit) != 0 {
+                        value |= (*bytes.next().ok_or(Error::Corrupt)? as usize) << (shift * 8);
+                    }
+                }
+                Ok(value)
+            };
+            let ofs = value(0..4)?;
+            let size = match value(4..7)? {
+                0 => 0x10000,
+                size => size,
+            };
+            let copy = ofs
+                .checked_add(size)
+                .and_then(|end| base.get(ofs..end))
+                .ok_or(Error::Corrupt)?;
+            out.extend_from_slice(copy);
+        } else if cmd == 0 {
+            return Err(Error::Corrupt);
+        } else {
+            let insert = bytes.as_slice().get(..cmd as usize).ok_or(Error::Corrupt)?;
+            out.extend_from_slice(insert);
+            bytes = bytes.as_slice()[cmd as usize..].iter();
+        }
+        if out.len() > result_size {
+            return Err(Error::Corrupt);
+        }
+    }
+    if out.len() != result_size {
+        return Err(Error::Corrupt);
+    }
+    Ok(())
+}
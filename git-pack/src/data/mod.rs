@@ -122,4 +122,4 @@ impl File {
     }
 }
 
-pub(crate) mod delta;
+pub mod delta;
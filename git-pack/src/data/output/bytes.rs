@@ -1,6 +1,7 @@
-use std::io::Write;
+use std::{collections::HashSet, io::Write};
 
 use git_features::hash;
+use git_hash::ObjectId;
 
 use crate::data::output;
 
@@ -15,6 +16,20 @@ where
     Io(#[from] std::io::Error),
     #[error(transparent)]
     Input(E),
+    #[error("Entry {id} is a delta referring to its base by offset, which isn't allowed")]
+    OfsDeltaNotAllowed { id: ObjectId },
+    #[error("Entry {id} is a delta against {base_id} which isn't in the pack, but thin packs aren't allowed")]
+    ExternalBase { id: ObjectId, base_id: ObjectId },
+}
+
+/// The kinds of deltas the receiver of a pack can handle, to assure only those are written.
+struct DeltaValidation {
+    allow_ofs_delta: bool,
+    allow_thin_pack: bool,
+    /// The ids of all objects written so far, if thin packs aren't allowed.
+    ids: HashSet<ObjectId>,
+    /// The ids of ref-delta objects and their base whose base wasn't written before them.
+    pending_bases: Vec<(ObjectId, ObjectId)>,
 }
 
 /// An implementation of [`Iterator`] to write [encoded entries][output::Entry] to an inner implementation each time
@@ -39,6 +54,8 @@ pub struct FromEntriesIter<I, W> {
     pack_offsets_and_validity: Vec<(u64, bool)>,
     /// If we are done, no additional writes will occour
     is_done: bool,
+    /// If set, deltas are checked to be of a kind the receiver can handle.
+    delta_validation: Option<DeltaValidation>,
 }
 
 impl<I, W, E> FromEntriesIter<I, W>
@@ -81,9 +98,25 @@ where
             written: 0,
             header_info: Some((version, num_entries)),
             is_done: false,
+            delta_validation: None,
         }
     }
 
+    /// Fail with an error instead of writing deltas the receiver can't handle, which are deltas referring to their base by offset
+    /// unless `allow_ofs_delta` is set, and deltas against objects outside of the pack unless `allow_thin_pack` is set.
+    ///
+    /// These usually correspond to the `ofs-delta` and `thin-pack` capabilities the receiver advertised. As a delta may precede
+    /// its base in a pack, missing bases are only detected once all entries were written, right before the trailer.
+    pub fn validate_deltas(mut self, allow_ofs_delta: bool, allow_thin_pack: bool) -> Self {
+        self.delta_validation = Some(DeltaValidation {
+            allow_ofs_delta,
+            allow_thin_pack,
+            ids: HashSet::new(),
+            pending_bases: Vec::new(),
+        });
+        self
+    }
+
     /// Consume this instance and return the `output` implementation.
     ///
     /// _Note_ that the `input` iterator can be moved out of this instance beforehand.
@@ -111,6 +144,9 @@ where
                         self.pack_offsets_and_validity.push((0, false));
                         continue;
                     };
+                    if let Some(validation) = &mut self.delta_validation {
+                        validation.check(&entry)?;
+                    }
                     self.pack_offsets_and_validity.push((self.written, true));
                     let header = entry.to_entry_header(self.entry_version, |index| {
                         let (base_offset, is_valid_object) = self.pack_offsets_and_validity[index];
@@ -124,18 +160,51 @@ where
                 }
             }
             None => {
-                let digest = self.output.hash.clone().digest();
-                self.output.inner.write_all(&digest[..])?;
-                self.written += digest.len() as u64;
-                self.output.inner.flush()?;
+                if let Some(validation) = &self.delta_validation {
+                    validation.check_pending_bases()?;
+                }
+                let trailer = self.output.write_trailer()?;
+                self.written += trailer.as_slice().len() as u64;
                 self.is_done = true;
-                self.trailer = Some(git_hash::ObjectId::from(digest));
+                self.trailer = Some(trailer);
             }
         };
         Ok(self.written - previous_written)
     }
 }
 
+impl DeltaValidation {
+    fn check<E: std::error::Error + 'static>(&mut self, entry: &output::Entry) -> Result<(), Error<E>> {
+        match entry.kind {
+            output::entry::Kind::DeltaRef { .. } if !self.allow_ofs_delta => {
+                return Err(Error::OfsDeltaNotAllowed { id: entry.id })
+            }
+            output::entry::Kind::DeltaOid { id: base_id } if !self.allow_thin_pack && !self.ids.contains(&base_id) => {
+                self.pending_bases.push((entry.id, base_id))
+            }
+            _ => {}
+        }
+        if !self.allow_thin_pack {
+            self.ids.insert(entry.id);
+        }
+        Ok(())
+    }
+
+    fn check_pending_bases<E: std::error::Error + 'static>(&self) -> Result<(), Error<E>> {
+        match self
+            .pending_bases
+            .iter()
+            .find(|(_, base_id)| !self.ids.contains(base_id))
+        {
+            Some((id, base_id)) => Err(Error::ExternalBase {
+                id: *id,
+                base_id: *base_id,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
 impl<I, W, E> Iterator for FromEntriesIter<I, W>
 where
     I: Iterator<Item = Result<Vec<output::Entry>, E>>,
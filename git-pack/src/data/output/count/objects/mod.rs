@@ -69,7 +69,7 @@ where
         iter: objects_ids,
         size: chunk_size,
     };
-    let seen_objs = dashmap::DashSet::<ObjectId, cache::object::State>::default();
+    let seen_objs = &dashmap::DashSet::<ObjectId, cache::object::State>::default();
     let progress = Arc::new(parking_lot::Mutex::new(progress));
 
     parallel::in_parallel(
@@ -95,7 +95,7 @@ where
                 expand::this(
                     &db,
                     input_object_expansion,
-                    &seen_objs,
+                    seen_objs,
                     oids,
                     buf1,
                     buf2,
@@ -109,6 +109,10 @@ where
         },
         reduce::Statistics::new(progress),
     )
+    .map(|(counts, mut outcome)| {
+        outcome.seen_objects_bytes = seen_objs.len() * std::mem::size_of::<ObjectId>();
+        (counts, outcome)
+    })
 }
 
 /// Like [`objects()`] but using a single thread only to mostly save on the otherwise required overhead.
@@ -141,6 +145,10 @@ where
         should_interrupt,
         false,
     )
+    .map(|(counts, mut outcome)| {
+        outcome.seen_objects_bytes = seen_objs.borrow().len() * std::mem::size_of::<ObjectId>();
+        (counts, outcome)
+    })
 }
 
 mod expand {
@@ -376,6 +384,8 @@ mod expand {
                 AsIs => push_obj_count_unique(&mut out, seen_objs, &id, &obj, progress, stats, false),
             }
         }
+        outcome.peak_buffer_bytes = buf1.capacity() + buf2.capacity();
+        outcome.peak_cache_bytes = cache.memory_in_bytes() + obj_cache.memory_in_bytes();
         Ok((out, outcome))
     }
 
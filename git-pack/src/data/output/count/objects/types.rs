@@ -12,6 +12,12 @@ pub struct Outcome {
     pub decoded_objects: usize,
     /// The total amount of encountered objects. Should be `expanded_objects + input_objects`.
     pub total_objects: usize,
+    /// The amount of bytes used by the ids of all objects seen while counting, without the overhead of the set holding them.
+    pub seen_objects_bytes: usize,
+    /// The most bytes used by the object buffers of a single thread.
+    pub peak_buffer_bytes: usize,
+    /// The most bytes used by the pack and object caches of a single thread.
+    pub peak_cache_bytes: usize,
 }
 
 impl Outcome {
@@ -22,12 +28,18 @@ impl Outcome {
             decoded_objects,
             expanded_objects,
             total_objects,
+            seen_objects_bytes,
+            peak_buffer_bytes,
+            peak_cache_bytes,
         }: Self,
     ) {
         self.input_objects += input_objects;
         self.decoded_objects += decoded_objects;
         self.expanded_objects += expanded_objects;
         self.total_objects += total_objects;
+        self.seen_objects_bytes += seen_objects_bytes;
+        self.peak_buffer_bytes = self.peak_buffer_bytes.max(peak_buffer_bytes);
+        self.peak_cache_bytes = self.peak_cache_bytes.max(peak_cache_bytes);
     }
 }
 
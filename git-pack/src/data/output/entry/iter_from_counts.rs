@@ -48,6 +48,9 @@ pub fn iter_from_counts<Find, Cache>(
         version,
         mode,
         allow_thin_pack,
+        allow_ofs_delta,
+        deterministic,
+        compression_level,
         thread_limit,
         chunk_size,
     }: Options,
@@ -65,7 +68,7 @@ where
     let (chunk_size, thread_limit, _) =
         parallel::optimize_chunk_size_and_thread_limit(chunk_size, Some(counts.len()), thread_limit, None);
     let chunks = util::ChunkRanges::new(chunk_size, counts.len());
-    {
+    if mode == Mode::PackCopyAndBaseObjects {
         let progress = Arc::new(parking_lot::Mutex::new(progress.add_child("resolving")));
         progress.lock().init(None, git_features::progress::count("counts"));
         let enough_counts_present = counts.len() > 4_000;
@@ -124,6 +127,10 @@ where
                 (_, _) => unreachable!("counts were resolved beforehand"),
             });
 
+            if deterministic {
+                make_order_independent_of_count_and_pack_paths(&mut counts);
+            }
+
             let mut index: Vec<(u32, std::ops::Range<usize>)> = Vec::new();
             let mut chunks_pack_start = counts.partition_point(|e| e.entry_pack_location.is_none());
             let mut slice = &counts[chunks_pack_start..];
@@ -137,11 +144,18 @@ where
                 chunks_pack_start += pack_end;
             }
 
+            index.sort_by_key(|e| e.0);
             progress.set(counts.len());
             progress.show_throughput(start);
 
             index
         }
+        Mode::BaseObjects => {
+            if deterministic {
+                counts.sort_by_key(|e| e.id);
+            }
+            Vec::new()
+        }
     };
     let counts = Arc::new(counts);
     let progress = Arc::new(parking_lot::Mutex::new(progress));
@@ -172,6 +186,7 @@ where
                     out.push(match count
                         .entry_pack_location
                         .as_ref()
+                        .filter(|_| mode == Mode::PackCopyAndBaseObjects)
                         .and_then(|l| db.entry_by_location(l).map(|pe| (l, pe)))
                     {
                         Some((location, pack_entry)) => {
@@ -219,12 +234,21 @@ where
                             ) {
                                 Some(entry) => {
                                     stats.objects_copied_from_pack += 1;
-                                    entry
+                                    entry.map(|mut entry| {
+                                        if let output::entry::Kind::DeltaRef { object_index } = entry.kind {
+                                            if !allow_ofs_delta {
+                                                entry.kind = output::entry::Kind::DeltaOid {
+                                                    id: counts[object_index].id,
+                                                };
+                                            }
+                                        }
+                                        entry
+                                    })
                                 }
                                 None => match db.try_find(count.id, buf, cache).map_err(Error::FindExisting)? {
                                     Some(obj) => {
                                         stats.decoded_and_recompressed_objects += 1;
-                                        output::Entry::from_data(count, &obj)
+                                        output::Entry::from_data_with_compression_level(count, &obj, compression_level)
                                     }
                                     None => {
                                         stats.missing_objects += 1;
@@ -236,7 +260,7 @@ where
                         None => match db.try_find(count.id, buf, cache).map_err(Error::FindExisting)? {
                             Some(obj) => {
                                 stats.decoded_and_recompressed_objects += 1;
-                                output::Entry::from_data(count, &obj)
+                                output::Entry::from_data_with_compression_level(count, &obj, compression_level)
                             }
                             None => {
                                 stats.missing_objects += 1;
@@ -246,6 +270,9 @@ where
                     }?);
                     progress.inc();
                 }
+                stats.peak_chunk_bytes = out.iter().map(|entry| entry.compressed_data.len()).sum();
+                stats.peak_buffer_bytes = buf.capacity();
+                stats.peak_cache_bytes = cache.memory_in_bytes();
                 Ok((chunk_id, out, stats))
             }
         },
@@ -253,6 +280,28 @@ where
     )
 }
 
+/// Sort `counts`, which are sorted by pack location already, such that their order only depends on the objects themselves.
+///
+/// Objects which aren't packed are ordered by id, while the order of objects in a pack is kept so deltas can be copied.
+/// Packs are ordered by the smallest id among their objects as pack ids are derived from their path.
+fn make_order_independent_of_count_and_pack_paths(counts: &mut Vec<output::Count>) {
+    let packed_start = counts.partition_point(|e| e.entry_pack_location.is_none());
+    counts[..packed_start].sort_by_key(|e| e.id);
+
+    let mut packs = Vec::new();
+    let mut packed = counts.split_off(packed_start);
+    while !packed.is_empty() {
+        let pack_id = packed[0].entry_pack_location.as_ref().expect("packed object").pack_id;
+        let pack_end =
+            packed.partition_point(|e| e.entry_pack_location.as_ref().expect("packed object").pack_id == pack_id);
+        let rest = packed.split_off(pack_end);
+        packs.push((packed.iter().map(|e| e.id).min().expect("non-empty"), packed));
+        packed = rest;
+    }
+    packs.sort_by_key(|e| e.0);
+    counts.extend(packs.into_iter().flat_map(|(_, counts)| counts));
+}
+
 mod util {
     #[derive(Clone)]
     pub struct ChunkRanges {
@@ -344,6 +393,12 @@ mod types {
         pub objects_copied_from_pack: usize,
         /// The amount of objects that ref to their base as ref-delta, an indication for a thin back being created.
         pub ref_delta_objects: usize,
+        /// The most bytes of compressed entry data held by a single chunk, which stays in memory until it is consumed.
+        pub peak_chunk_bytes: usize,
+        /// The most bytes used by the object buffer of a single thread.
+        pub peak_buffer_bytes: usize,
+        /// The most bytes used by the pack cache of a single thread.
+        pub peak_cache_bytes: usize,
     }
 
     impl Outcome {
@@ -354,12 +409,18 @@ mod types {
                 missing_objects,
                 objects_copied_from_pack,
                 ref_delta_objects,
+                peak_chunk_bytes,
+                peak_buffer_bytes,
+                peak_cache_bytes,
             }: Self,
         ) {
             self.decoded_and_recompressed_objects += decoded_objects;
             self.missing_objects += missing_objects;
             self.objects_copied_from_pack += objects_copied_from_pack;
             self.ref_delta_objects += ref_delta_objects;
+            self.peak_chunk_bytes = self.peak_chunk_bytes.max(peak_chunk_bytes);
+            self.peak_buffer_bytes = self.peak_buffer_bytes.max(peak_buffer_bytes);
+            self.peak_cache_bytes = self.peak_cache_bytes.max(peak_cache_bytes);
         }
     }
 
@@ -372,6 +433,9 @@ mod types {
         /// from existing pack compression and spending the smallest possible time on compressing unpacked objects at
         /// the cost of bandwidth.
         PackCopyAndBaseObjects,
+        /// Decompress all objects and write them as base objects, without copying deltas from packs. This yields larger packs
+        /// and takes more time, but each of their objects can be read without resolving deltas.
+        BaseObjects,
     }
 
     /// Configuration options for the pack generation functions provied in [this module][crate::data::output].
@@ -388,7 +452,22 @@ mod types {
         /// commit graph. Please note that thin packs are not valid packs at rest, thus they are only valid for packs in transit.
         ///
         /// If set to false, delta objects will be decompressed and recompressed as base objects.
+        ///
+        /// This corresponds to the `thin-pack` capability of the receiver.
         pub allow_thin_pack: bool,
+        /// If set, deltas against objects in the pack refer to their base by offset, which is the most compact encoding.
+        /// Otherwise they refer to their base by id, which is what receivers without the `ofs-delta` capability need.
+        pub allow_ofs_delta: bool,
+        /// If set, objects are written in an order which only depends on the objects themselves, and not on the order in which
+        /// they were counted or the paths of the packs they are copied from. This yields byte-identical packs for the same
+        /// objects in the same object database, even if they were counted with multiple threads.
+        ///
+        /// Note that compression is always performed with the same level, but different zlib implementations may produce
+        /// different output for it.
+        pub deterministic: bool,
+        /// The zlib compression level from 0 to 9 for objects which aren't copied from existing packs. It corresponds to the
+        /// `pack.compression` configuration, but defaults to 1 to favor speed over size.
+        pub compression_level: u32,
         /// The amount of objects per chunk or unit of work to be sent to threads for processing
         /// TODO: could this become the window size?
         pub chunk_size: usize,
@@ -402,6 +481,9 @@ mod types {
                 thread_limit: None,
                 mode: Mode::PackCopyAndBaseObjects,
                 allow_thin_pack: false,
+                allow_ofs_delta: true,
+                deterministic: false,
+                compression_level: 1,
                 chunk_size: 10,
                 version: Default::default(),
             }
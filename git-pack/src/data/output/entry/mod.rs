@@ -122,14 +122,23 @@ impl output::Entry {
         })
     }
 
-    /// Create a new instance from the given `oid` and its corresponding git `obj`ect data.
+    /// Create a new instance from the given `oid` and its corresponding git `obj`ect data, compressing it for speed over size.
     pub fn from_data(count: &output::Count, obj: &data::Object<'_>) -> Result<Self, Error> {
+        Self::from_data_with_compression_level(count, obj, 1)
+    }
+
+    /// Like [`from_data()`][Self::from_data()], but compresses the object with the zlib compression `level` from 0 to 9.
+    pub fn from_data_with_compression_level(
+        count: &output::Count,
+        obj: &data::Object<'_>,
+        level: u32,
+    ) -> Result<Self, Error> {
         Ok(output::Entry {
             id: count.id.to_owned(),
             kind: Kind::Base(obj.kind),
             decompressed_size: obj.data.len(),
             compressed_data: {
-                let mut out = git_features::zlib::stream::deflate::Write::new(Vec::new());
+                let mut out = git_features::zlib::stream::deflate::Write::with_level(Vec::new(), level);
                 if let Err(err) = std::io::copy(&mut &*obj.data, &mut out) {
                     match err.kind() {
                         std::io::ErrorKind::Other => return Err(Error::ZlibDeflate(err)),
@@ -13,6 +13,10 @@ const N32_HIGH_BIT: u32 = 1 << 31;
 
 pub(crate) type PackOffset = u64;
 
+/// The result of [`index::File::lookup_prefix()`], the index of the only object matching the prefix or `Err(())` if there are
+/// multiple objects with that prefix.
+pub type PrefixLookupResult = Result<u32, ()>;
+
 /// Represents an entry within a pack index file, effectively mapping object [`IDs`][git_hash::ObjectId] to pack data file locations.
 #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
 #[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
@@ -148,6 +152,33 @@ impl index::File {
         None
     }
 
+    /// Returns the `index` of the only object whose id starts with `prefix`, `Some(Err(()))` if there are multiple such
+    /// objects, or `None` if there is none.
+    pub fn lookup_prefix(&self, prefix: git_hash::Prefix) -> Option<PrefixLookupResult> {
+        let first_byte = prefix.as_oid().first_byte() as usize;
+        let mut upper_bound = self.num_objects;
+        let mut lower_bound = if first_byte != 0 { self.fan[first_byte - 1] } else { 0 };
+
+        // The prefix is the smallest id it matches, so bisect for the first id not smaller than it.
+        while lower_bound < upper_bound {
+            let mid = (lower_bound + upper_bound) / 2;
+            if self.oid_at_index(mid) < prefix.as_oid() {
+                lower_bound = mid + 1;
+            } else {
+                upper_bound = mid;
+            }
+        }
+
+        let matches = |idx: u32| idx < self.num_objects && prefix.cmp_oid(self.oid_at_index(idx)).is_eq();
+        matches(lower_bound).then(|| {
+            if matches(lower_bound + 1) {
+                Err(())
+            } else {
+                Ok(lower_bound)
+            }
+        })
+    }
+
     /// An iterator over all [`Entries`][Entry] of this index file.
     pub fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = Entry> + 'a> {
         match self.version {
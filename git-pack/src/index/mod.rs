@@ -131,7 +131,10 @@ const V2_SIGNATURE: &[u8] = b"\xfftOc";
 pub mod init;
 
 pub(crate) mod access;
-pub use access::Entry;
+pub use access::{Entry, PrefixLookupResult};
+
+///
+pub mod reverse;
 
 ///
 pub mod traverse;
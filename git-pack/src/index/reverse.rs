@@ -0,0 +1,224 @@
+//! Reverse indices map positions of objects in pack order to their position in an index, which is sorted by object id.
+//!
+//! They are stored in `.rev` files next to pack indices or multi-pack indices, and are what reachability bitmaps refer to.
+use std::{io, path::Path};
+
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+use filebuffer::FileBuffer;
+use git_features::hash;
+use git_hash::SIZE_OF_SHA1_DIGEST as SHA1_SIZE;
+
+use crate::index;
+
+/// Returned by [`Index::at()`] and [`Index::verify()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not open reverse index file at '{path}'")]
+    Io {
+        source: std::io::Error,
+        path: std::path::PathBuf,
+    },
+    #[error("{message}")]
+    Corrupt { message: String },
+    #[error("The reverse index belongs to {actual}, but was expected to belong to {expected}")]
+    ChecksumMismatch {
+        expected: git_hash::ObjectId,
+        actual: git_hash::ObjectId,
+    },
+}
+
+const SIGNATURE: &[u8] = b"RIDX";
+const HEADER_LEN: usize = 12;
+const N32_SIZE: usize = 4;
+const VERSION: u32 = 1;
+const HASH_ID_SHA1: u32 = 1;
+
+/// The order of objects in a pack, or in the pseudo-pack formed by all packs of a multi-pack index.
+///
+/// When read from a `.rev` file, positions are looked up in the memory-mapped file instead of being loaded into memory.
+pub struct Index {
+    data: Data,
+    num_objects: u32,
+}
+
+enum Data {
+    /// A memory-mapped `.rev` file with big-endian positions starting at `HEADER_LEN`.
+    Mapped(FileBuffer),
+    /// Positions computed from an index or copied out of a multi-pack index.
+    Owned(Vec<u32>),
+}
+
+impl std::fmt::Debug for Index {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Index")
+            .field("num_objects", &self.num_objects)
+            .field("mapped", &matches!(self.data, Data::Mapped(_)))
+            .finish()
+    }
+}
+
+/// Instantiation
+impl Index {
+    /// Open the `.rev` file at `path` for an index with `num_objects` which belongs to the pack or multi-pack index with `checksum`.
+    ///
+    /// Only the header, size and trailer are validated to keep opening cheap even for huge packs.
+    pub fn at(path: impl AsRef<Path>, num_objects: u32, checksum: &git_hash::oid) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let data = FileBuffer::open(path).map_err(|source| Error::Io {
+            source,
+            path: path.to_owned(),
+        })?;
+        let expected_len = HEADER_LEN + num_objects as usize * N32_SIZE + SHA1_SIZE * 2;
+        if data.len() != expected_len {
+            return Err(Error::Corrupt {
+                message: format!(
+                    "Reverse index of size {} should have size {} for {} objects",
+                    data.len(),
+                    expected_len,
+                    num_objects
+                ),
+            });
+        }
+        if &data[..SIGNATURE.len()] != SIGNATURE {
+            return Err(Error::Corrupt {
+                message: "Reverse index does not start with the expected signature".into(),
+            });
+        }
+        match (BigEndian::read_u32(&data[4..8]), BigEndian::read_u32(&data[8..12])) {
+            (VERSION, HASH_ID_SHA1) => {}
+            (version, hash) => {
+                return Err(Error::Corrupt {
+                    message: format!(
+                        "Unsupported reverse index version {} or hash function {}",
+                        version, hash
+                    ),
+                })
+            }
+        }
+        let trailer = HEADER_LEN + num_objects as usize * N32_SIZE;
+        let actual = git_hash::ObjectId::from_20_bytes(&data[trailer..trailer + SHA1_SIZE]);
+        if actual != checksum {
+            return Err(Error::ChecksumMismatch {
+                expected: checksum.to_owned(),
+                actual,
+            });
+        }
+        Ok(Index {
+            data: Data::Mapped(data),
+            num_objects,
+        })
+    }
+
+    /// Compute the order of objects in the pack belonging to `index`, which is what git does if there is no `.rev` file.
+    pub fn from_pack_index(index: &index::File) -> Self {
+        let mut index_positions: Vec<u32> = (0..index.num_objects()).collect();
+        index_positions.sort_by_key(|position| index.pack_offset_at_index(*position));
+        Index {
+            num_objects: index.num_objects(),
+            data: Data::Owned(index_positions),
+        }
+    }
+
+    /// Parse big-endian index positions as stored in `.rev` files or the `RIDX` chunk of multi-pack indices, and assure
+    /// they refer to each of the `num_objects` exactly once.
+    pub(crate) fn from_be_bytes(data: &[u8], num_objects: u32) -> Result<Self, String> {
+        let index_positions: Vec<u32> = data.chunks(N32_SIZE).map(BigEndian::read_u32).collect();
+        let mut seen = vec![false; num_objects as usize];
+        for position in &index_positions {
+            match seen.get_mut(*position as usize) {
+                Some(seen @ false) => *seen = true,
+                _ => return Err(format!("Reverse index position {} is invalid or duplicate", position)),
+            }
+        }
+        Ok(Index {
+            num_objects,
+            data: Data::Owned(index_positions),
+        })
+    }
+}
+
+/// Access
+impl Index {
+    /// The amount of objects in the pack.
+    pub fn num_objects(&self) -> u32 {
+        self.num_objects
+    }
+
+    /// Return the position in the index of the object at `pack_position`.
+    ///
+    /// Note that positions read from `.rev` files are not validated, use [`verify()`][Index::verify()] for that.
+    ///
+    /// # Panics
+    ///
+    /// If `pack_position` is out of bounds.
+    pub fn index_position(&self, pack_position: u32) -> u32 {
+        match &self.data {
+            Data::Mapped(data) => {
+                let start = HEADER_LEN + pack_position as usize * N32_SIZE;
+                assert!(pack_position < self.num_objects, "pack position out of bounds");
+                BigEndian::read_u32(&data[start..start + N32_SIZE])
+            }
+            Data::Owned(positions) => positions[pack_position as usize],
+        }
+    }
+
+    /// Iterate over the positions in the index of all objects, in pack order.
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..self.num_objects).map(move |pack_position| self.index_position(pack_position))
+    }
+
+    /// Return the position in pack order for each position in the index.
+    pub fn pack_positions(&self) -> Vec<u32> {
+        let mut out = vec![0; self.num_objects as usize];
+        for (pack_position, index_position) in self.iter().enumerate() {
+            out[index_position as usize] = pack_position as u32;
+        }
+        out
+    }
+
+    /// Assure that each position in the index is referred to exactly once, which is only done by default for
+    /// positions that don't come from a `.rev` file.
+    pub fn verify(&self) -> Result<(), Error> {
+        let mut seen = vec![false; self.num_objects as usize];
+        for position in self.iter() {
+            match seen.get_mut(position as usize) {
+                Some(seen @ false) => *seen = true,
+                _ => {
+                    return Err(Error::Corrupt {
+                        message: format!("Reverse index position {} is invalid or duplicate", position),
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writing
+impl Index {
+    /// Write this reverse index in the format of `.rev` files to `out`, for the pack or multi-pack index with `checksum`.
+    ///
+    /// Returns the checksum of the written file, which is also its trailer.
+    pub fn write_to(&self, out: impl io::Write, checksum: &git_hash::oid) -> io::Result<git_hash::ObjectId> {
+        use io::Write;
+        let mut out = io::BufWriter::with_capacity(8 * 4096, hash::Write::new(out, git_hash::Kind::Sha1));
+        out.write_all(SIGNATURE)?;
+        out.write_u32::<BigEndian>(VERSION)?;
+        out.write_u32::<BigEndian>(HASH_ID_SHA1)?;
+        for position in self.iter() {
+            out.write_u32::<BigEndian>(position)?;
+        }
+        out.write_all(checksum.as_bytes())?;
+
+        out.into_inner()?.write_trailer()
+    }
+}
+
+impl PartialEq for Index {
+    fn eq(&self, other: &Self) -> bool {
+        self.num_objects == other.num_objects && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for Index {}
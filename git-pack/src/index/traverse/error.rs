@@ -41,6 +41,8 @@ pub enum Error<E: std::error::Error + Send + Sync + 'static> {
         offset: u64,
         kind: git_object::Kind,
     },
+    #[error("The function receiving each traversed object failed")]
+    ObjectFn(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
     #[error("Interrupted")]
     Interrupted,
 }
@@ -19,7 +19,11 @@ impl index::File {
     /// Iterate through all _decoded objects_ in the given `pack` and handle them with a `Processor`, using an index to reduce waste
     /// at the cost of memory.
     ///
+    /// `num_largest_objects` is the amount of [largest objects][index::traverse::Outcome::largest_objects] to track, and
+    /// `on_object` is called with each object once all objects were verified.
+    ///
     /// For more details, see the documentation on the [`traverse()`][index::File::traverse()] method.
+    #[allow(clippy::too_many_arguments)]
     pub fn traverse_with_index<P, Processor, E>(
         &self,
         check: SafetyCheck,
@@ -28,6 +32,8 @@ impl index::File {
         mut progress: P,
         pack: &crate::data::File,
         should_interrupt: Arc<AtomicBool>,
+        num_largest_objects: usize,
+        on_object: Option<&mut index::traverse::ObjectFn<'_>>,
     ) -> Result<(git_hash::ObjectId, index::traverse::Outcome, P), Error<E>>
     where
         P: Progress,
@@ -70,7 +76,7 @@ impl index::File {
                     |id| self.lookup(id).map(|idx| self.pack_offset_at_index(idx)),
                 )?;
                 let there_are_enough_objects = || self.num_objects > 10_000;
-                let mut outcome = digest_statistics(tree.traverse(
+                let items = tree.traverse(
                     there_are_enough_objects,
                     |slice, out| pack.entry_slice(slice).map(|entry| out.copy_from_slice(entry)),
                     progress.add_child("Resolving"),
@@ -120,7 +126,8 @@ impl index::File {
                             res => res,
                         }
                     },
-                )?);
+                )?;
+                let mut outcome = digest_statistics(items, num_largest_objects, on_object)?;
                 outcome.pack_size = pack.data_len() as u64;
                 Ok(outcome)
             },
@@ -170,14 +177,34 @@ impl From<crate::index::Entry> for EntryWithDefault {
     }
 }
 
-fn digest_statistics(items: VecDeque<crate::cache::delta::Item<EntryWithDefault>>) -> index::traverse::Outcome {
+fn digest_statistics<E>(
+    items: VecDeque<crate::cache::delta::Item<EntryWithDefault>>,
+    num_largest_objects: usize,
+    mut on_object: Option<&mut index::traverse::ObjectFn<'_>>,
+) -> Result<index::traverse::Outcome, Error<E>>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
     let mut res = index::traverse::Outcome::default();
     let average = &mut res.average;
     for item in &items {
+        if let Some(on_object) = on_object.as_mut() {
+            let stats = crate::data::decode_entry::Outcome {
+                kind: item.data.object_kind,
+                num_deltas: item.data.level as u32,
+                decompressed_size: item.data.decompressed_size,
+                compressed_size: item.data.compressed_size as usize,
+                object_size: item.data.object_size,
+            };
+            on_object(&item.data.index_entry, &stats).map_err(Error::ObjectFn)?;
+        }
         res.total_compressed_entries_size += item.data.compressed_size;
         res.total_decompressed_entries_size += item.data.decompressed_size;
         res.total_object_size += item.data.object_size;
         *res.objects_per_chain_length.entry(item.data.level as u32).or_insert(0) += 1;
+        *res.objects_per_size_class
+            .entry(index::traverse::Outcome::size_class(item.data.object_size))
+            .or_insert(0) += 1;
 
         average.decompressed_size += item.data.decompressed_size;
         average.compressed_size += item.data.compressed_size as usize;
@@ -190,6 +217,13 @@ fn digest_statistics(items: VecDeque<crate::cache::delta::Item<EntryWithDefault>
             Tag => res.num_tags += 1,
             Commit => res.num_commits += 1,
         };
+        index::traverse::Object {
+            id: item.data.index_entry.oid,
+            kind: item.data.object_kind,
+            object_size: item.data.object_size,
+            num_deltas: item.data.level as u32,
+        }
+        .insert_if_largest(&mut res.largest_objects, num_largest_objects);
     }
 
     average.decompressed_size /= items.len() as u64;
@@ -197,5 +231,5 @@ fn digest_statistics(items: VecDeque<crate::cache::delta::Item<EntryWithDefault>
     average.object_size /= items.len() as u64;
     average.num_deltas /= items.len() as u32;
 
-    res
+    Ok(res)
 }
@@ -18,16 +18,15 @@ mod error;
 pub use error::Error;
 
 mod types;
-pub use types::{Algorithm, Outcome, SafetyCheck};
+pub use types::{Algorithm, Object, ObjectFn, Outcome, SafetyCheck};
 
 mod options {
     use std::sync::{atomic::AtomicBool, Arc};
 
-    use crate::index::traverse::{Algorithm, SafetyCheck};
+    use crate::index::traverse::{Algorithm, ObjectFn, SafetyCheck};
 
     /// Traversal options for [`traverse()`][crate::index::File::traverse()]
-    #[derive(Debug, Clone)]
-    pub struct Options {
+    pub struct Options<'a> {
         /// The algorithm to employ.
         pub algorithm: Algorithm,
         /// If `Some`, only use the given amount of threads. Otherwise, the amount of threads to use will be selected based on
@@ -38,18 +37,37 @@ mod options {
         /// A flag to indicate whether the algorithm should be interrupted. Will be checked occasionally allow stopping a running
         /// computation.
         pub should_interrupt: Arc<AtomicBool>,
+        /// The amount of [largest objects][crate::index::traverse::Outcome::largest_objects] to track, or 0 to not track any.
+        pub num_largest_objects: usize,
+        /// If `Some`, the function to call with each object once it was verified.
+        pub on_object: Option<&'a mut ObjectFn<'a>>,
     }
 
-    impl Default for Options {
+    impl Default for Options<'_> {
         fn default() -> Self {
             Self {
                 algorithm: Algorithm::Lookup,
                 thread_limit: Default::default(),
                 check: Default::default(),
                 should_interrupt: Default::default(),
+                num_largest_objects: 0,
+                on_object: None,
             }
         }
     }
+
+    impl std::fmt::Debug for Options<'_> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("Options")
+                .field("algorithm", &self.algorithm)
+                .field("thread_limit", &self.thread_limit)
+                .field("check", &self.check)
+                .field("should_interrupt", &self.should_interrupt)
+                .field("num_largest_objects", &self.num_largest_objects)
+                .field("on_object", &self.on_object.is_some())
+                .finish()
+        }
+    }
 }
 pub use options::Options;
 
@@ -86,7 +104,9 @@ impl index::File {
             thread_limit,
             check,
             should_interrupt,
-        }: Options,
+            num_largest_objects,
+            on_object,
+        }: Options<'_>,
     ) -> Result<(git_hash::ObjectId, Outcome, Option<P>), Error<E>>
     where
         P: Progress,
@@ -110,11 +130,20 @@ impl index::File {
                     thread_limit,
                     check,
                     should_interrupt,
+                    num_largest_objects,
+                    on_object,
                 },
             ),
-            Algorithm::DeltaTreeLookup => {
-                self.traverse_with_index(check, thread_limit, new_processor, progress, pack, should_interrupt)
-            }
+            Algorithm::DeltaTreeLookup => self.traverse_with_index(
+                check,
+                thread_limit,
+                new_processor,
+                progress,
+                pack,
+                should_interrupt,
+                num_largest_objects,
+                on_object,
+            ),
         }
         .map(|(a, b, p)| (a, b, p.into_inner()))
     }
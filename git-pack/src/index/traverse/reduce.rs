@@ -5,7 +5,7 @@ use std::{
 
 use git_features::{parallel, progress::Progress};
 
-use crate::{data, index::traverse};
+use crate::{data, index, index::traverse};
 
 fn add_decode_result(lhs: &mut data::decode_entry::Outcome, rhs: data::decode_entry::Outcome) {
     lhs.num_deltas += rhs.num_deltas;
@@ -30,6 +30,8 @@ pub struct Reducer<'a, P, E> {
     entries_seen: usize,
     stats: traverse::Outcome,
     should_interrupt: &'a AtomicBool,
+    num_largest_objects: usize,
+    on_object: Option<&'a mut traverse::ObjectFn<'a>>,
     _error: std::marker::PhantomData<E>,
 }
 
@@ -42,6 +44,8 @@ where
         pack_data_len_in_bytes: usize,
         check: traverse::SafetyCheck,
         should_interrupt: &'a AtomicBool,
+        num_largest_objects: usize,
+        on_object: Option<&'a mut traverse::ObjectFn<'a>>,
     ) -> Self {
         let stats = traverse::Outcome {
             pack_size: pack_data_len_in_bytes as u64,
@@ -53,6 +57,8 @@ where
             then: Instant::now(),
             entries_seen: 0,
             should_interrupt,
+            num_largest_objects,
+            on_object,
             stats,
             _error: Default::default(),
         }
@@ -64,7 +70,7 @@ where
     P: Progress,
     E: std::error::Error + Send + Sync + 'static,
 {
-    type Input = Result<Vec<data::decode_entry::Outcome>, traverse::Error<E>>;
+    type Input = Result<Vec<(index::Entry, data::decode_entry::Outcome)>, traverse::Error<E>>;
     type FeedProduce = ();
     type Output = traverse::Outcome;
     type Error = traverse::Error<E>;
@@ -78,14 +84,24 @@ where
             res => res,
         }?;
         self.entries_seen += chunk_stats.len();
+        if let Some(on_object) = self.on_object.as_mut() {
+            for (index_entry, stats) in &chunk_stats {
+                on_object(index_entry, stats).map_err(traverse::Error::ObjectFn)?;
+            }
+        }
 
         let chunk_total = chunk_stats.into_iter().fold(
             data::decode_entry::Outcome::default_from_kind(git_object::Kind::Tree),
-            |mut total, stats| {
+            |mut total, (index_entry, stats)| {
                 *self.stats.objects_per_chain_length.entry(stats.num_deltas).or_insert(0) += 1;
                 self.stats.total_decompressed_entries_size += stats.decompressed_size;
                 self.stats.total_compressed_entries_size += stats.compressed_size as u64;
                 self.stats.total_object_size += stats.object_size as u64;
+                *self
+                    .stats
+                    .objects_per_size_class
+                    .entry(traverse::Outcome::size_class(stats.object_size))
+                    .or_insert(0) += 1;
                 use git_object::Kind::*;
                 match stats.kind {
                     Commit => self.stats.num_commits += 1,
@@ -93,6 +109,13 @@ where
                     Blob => self.stats.num_blobs += 1,
                     Tag => self.stats.num_tags += 1,
                 }
+                traverse::Object {
+                    id: index_entry.oid,
+                    kind: stats.kind,
+                    object_size: stats.object_size,
+                    num_deltas: stats.num_deltas,
+                }
+                .insert_if_largest(&mut self.stats.largest_objects, self.num_largest_objects);
                 add_decode_result(&mut total, stats);
                 total
             },
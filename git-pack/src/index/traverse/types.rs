@@ -11,6 +11,10 @@ pub struct Outcome {
     /// A length of 0 indicates full objects, and everything above that involves the given amount
     /// of delta objects.
     pub objects_per_chain_length: BTreeMap<u32, u32>,
+    /// A mapping of the [size class][Outcome::size_class()] of decompressed objects to the amount of objects in it.
+    ///
+    /// Together with `objects_per_chain_length` it shows whether large objects or long delta chains dominate a pack.
+    pub objects_per_size_class: BTreeMap<u32, u32>,
     /// The amount of bytes in all compressed streams, one per entry
     pub total_compressed_entries_size: u64,
     /// The amount of bytes in all decompressed streams, one per entry
@@ -27,6 +31,48 @@ pub struct Outcome {
     pub num_tags: u32,
     /// The amount of objects encountered that where blobs
     pub num_blobs: u32,
+    /// The largest objects by decompressed size, largest first, with up to as many objects as configured by the
+    /// `num_largest_objects` option of the traversal.
+    pub largest_objects: Vec<Object>,
+}
+
+/// A function called with the index entry and the decoding outcome of each object once it was verified during traversal,
+/// to process information about individual objects as they are encountered instead of only obtaining the aggregated [`Outcome`].
+///
+/// Returning an error aborts the traversal.
+pub type ObjectFn<'a> = dyn FnMut(
+        &crate::index::Entry,
+        &crate::data::decode_entry::Outcome,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>
+    + Send
+    + 'a;
+
+/// An object encountered during traversal, as tracked in [`Outcome::largest_objects`].
+#[derive(Debug, PartialEq, Eq, Hash, Ord, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Object {
+    /// The id of the object.
+    pub id: git_hash::ObjectId,
+    /// The kind of the object.
+    pub kind: git_object::Kind,
+    /// The size of the decompressed object in bytes, after resolving all deltas.
+    pub object_size: u64,
+    /// The length of the delta chain of the object, with 0 indicating a full object.
+    pub num_deltas: u32,
+}
+
+impl Object {
+    /// Insert this object into `largest_objects`, sorted largest first, if it is among the `limit` largest ones seen so far.
+    ///
+    /// Objects of the same size are ordered by id to make the outcome independent of the order in which objects are seen.
+    pub(crate) fn insert_if_largest(self, largest_objects: &mut Vec<Object>, limit: usize) {
+        let key = |object: &Object| (std::cmp::Reverse(object.object_size), object.id);
+        let pos = largest_objects.partition_point(|existing| key(existing) < key(&self));
+        if pos < limit {
+            largest_objects.insert(pos, self);
+            largest_objects.truncate(limit);
+        }
+    }
 }
 
 impl Default for Outcome {
@@ -34,6 +80,7 @@ impl Default for Outcome {
         Outcome {
             average: crate::data::decode_entry::Outcome::default_from_kind(git_object::Kind::Tree),
             objects_per_chain_length: Default::default(),
+            objects_per_size_class: Default::default(),
             total_compressed_entries_size: 0,
             total_decompressed_entries_size: 0,
             total_object_size: 0,
@@ -42,10 +89,21 @@ impl Default for Outcome {
             num_commits: 0,
             num_trees: 0,
             num_tags: 0,
+            largest_objects: Vec::new(),
         }
     }
 }
 
+impl Outcome {
+    /// Return the size class of an object with `object_size` decompressed bytes, which is the amount of bits needed to represent
+    /// its size.
+    ///
+    /// Class `0` holds empty objects only, and class `n` holds objects of at least `2^(n-1)` and less than `2^n` bytes.
+    pub fn size_class(object_size: u64) -> u32 {
+        u64::BITS - object_size.leading_zeros()
+    }
+}
+
 /// The ways to validate decoded objects before passing them to the processor.
 #[derive(Debug, PartialEq, Eq, Hash, Ord, PartialOrd, Clone, Copy)]
 #[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
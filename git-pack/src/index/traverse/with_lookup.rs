@@ -11,11 +11,10 @@ use crate::{data, index, index::util};
 mod options {
     use std::sync::{atomic::AtomicBool, Arc};
 
-    use crate::index::traverse::SafetyCheck;
+    use crate::index::traverse::{ObjectFn, SafetyCheck};
 
     /// Traversal options for [`traverse()`][crate::index::File::traverse_with_lookup()]
-    #[derive(Debug, Clone)]
-    pub struct Options {
+    pub struct Options<'a> {
         /// If `Some`, only use the given amount of threads. Otherwise, the amount of threads to use will be selected based on
         /// the amount of available logical cores.
         pub thread_limit: Option<usize>,
@@ -24,17 +23,36 @@ mod options {
         /// A flag to indicate whether the algorithm should be interrupted. Will be checked occasionally allow stopping a running
         /// computation.
         pub should_interrupt: Arc<AtomicBool>,
+        /// The amount of [largest objects][crate::index::traverse::Outcome::largest_objects] to track, or 0 to not track any.
+        pub num_largest_objects: usize,
+        /// If `Some`, the function to call with each object once it was verified, in the order in which chunks of objects
+        /// are completed.
+        pub on_object: Option<&'a mut ObjectFn<'a>>,
     }
 
-    impl Default for Options {
+    impl Default for Options<'_> {
         fn default() -> Self {
             Self {
                 thread_limit: Default::default(),
                 check: Default::default(),
                 should_interrupt: Default::default(),
+                num_largest_objects: 0,
+                on_object: None,
             }
         }
     }
+
+    impl std::fmt::Debug for Options<'_> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("Options")
+                .field("thread_limit", &self.thread_limit)
+                .field("check", &self.check)
+                .field("should_interrupt", &self.should_interrupt)
+                .field("num_largest_objects", &self.num_largest_objects)
+                .field("on_object", &self.on_object.is_some())
+                .finish()
+        }
+    }
 }
 use std::sync::atomic::Ordering;
 
@@ -56,7 +74,9 @@ impl index::File {
             thread_limit,
             check,
             should_interrupt,
-        }: Options,
+            num_largest_objects,
+            on_object,
+        }: Options<'_>,
     ) -> Result<(git_hash::ObjectId, index::traverse::Outcome, P), Error<E>>
     where
         P: Progress,
@@ -117,7 +137,7 @@ impl index::File {
                     state_per_thread,
                     |entries: &[index::Entry],
                      (cache, ref mut processor, buf, progress)|
-                     -> Result<Vec<data::decode_entry::Outcome>, Error<_>> {
+                     -> Result<Vec<(index::Entry, data::decode_entry::Outcome)>, Error<_>> {
                         progress.init(
                             Some(entries.len()),
                             Some(unit::dynamic(unit::Human::new(
@@ -146,11 +166,18 @@ impl index::File {
                                 }
                                 res => res,
                             }?;
-                            stats.push(stat);
+                            stats.push((index_entry.clone(), stat));
                         }
                         Ok(stats)
                     },
-                    Reducer::from_progress(&reduce_progress, pack.data_len(), check, &should_interrupt),
+                    Reducer::from_progress(
+                        &reduce_progress,
+                        pack.data_len(),
+                        check,
+                        &should_interrupt,
+                        num_largest_objects,
+                        on_object.map(|f| f as &mut index::traverse::ObjectFn<'_>),
+                    ),
                 )
             },
         );
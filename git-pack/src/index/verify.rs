@@ -111,6 +111,8 @@ impl index::File {
     ///
     /// The `thread_limit` optionally specifies the amount of threads to be used for the [pack traversal][index::File::traverse()].
     /// `make_cache` is only used in case a `pack` is specified, use existing implementations in the [`crate::cache`] module.
+    /// `num_largest_objects` is the amount of [largest objects][index::traverse::Outcome::largest_objects] to track while
+    /// traversing the `pack`, and `on_object` is called with [each verified object][index::traverse::ObjectFn] if set.
     ///
     /// # Tradeoffs
     ///
@@ -125,6 +127,8 @@ impl index::File {
             impl Fn() -> C + Send + Sync,
         )>,
         thread_limit: Option<usize>,
+        num_largest_objects: usize,
+        on_object: Option<&mut index::traverse::ObjectFn<'_>>,
         progress: Option<P>,
         should_interrupt: Arc<AtomicBool>,
     ) -> Result<
@@ -153,6 +157,8 @@ impl index::File {
                         thread_limit,
                         check: index::traverse::SafetyCheck::All,
                         should_interrupt,
+                        num_largest_objects,
+                        on_object: on_object.map(|f| f as &mut index::traverse::ObjectFn<'_>),
                     },
                 )
                 .map(|(id, outcome, root)| (id, Some(outcome), root)),
@@ -15,6 +15,8 @@
 
 pub use find_traits::{Find, FindExt};
 
+///
+pub mod bitmap;
 ///
 pub mod bundle;
 /// A bundle of pack data and the corresponding pack index
@@ -35,6 +37,8 @@ pub mod data;
 mod find_traits;
 ///
 pub mod index;
+///
+pub mod multi_index;
 
 ///
 pub mod loose;
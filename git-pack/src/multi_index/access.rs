@@ -0,0 +1,85 @@
+use byteorder::{BigEndian, ByteOrder};
+use git_hash::SIZE_OF_SHA1_DIGEST as SHA1_SIZE;
+
+use crate::{
+    index::reverse,
+    multi_index::{self, N32_HIGH_BIT},
+};
+
+const N32_SIZE: usize = 4;
+const N64_SIZE: usize = 8;
+
+/// Access
+impl multi_index::File {
+    /// Returns the object id at the given `index`, which ranges from 0 to [`num_objects()`][multi_index::File::num_objects()].
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    pub fn oid_at_index(&self, index: u32) -> &git_hash::oid {
+        assert!(index < self.num_objects, "index {} is out of bounds", index);
+        let start = self.lookup_ofs + index as usize * SHA1_SIZE;
+        git_hash::oid::try_from(&self.data[start..start + SHA1_SIZE]).expect("20 bytes SHA1 to be alright")
+    }
+
+    /// Returns the id of the pack, a position in [`index_names()`][multi_index::File::index_names()], and the offset into
+    /// its data file of the object at `index`.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    pub fn pack_id_and_pack_offset_at_index(&self, index: u32) -> (u32, u64) {
+        assert!(index < self.num_objects, "index {} is out of bounds", index);
+        let start = self.offsets_ofs + index as usize * N64_SIZE;
+        let pack_id = BigEndian::read_u32(&self.data[start..start + N32_SIZE]);
+        let offset = BigEndian::read_u32(&self.data[start + N32_SIZE..start + N64_SIZE]);
+        let offset = match self.large_offsets_ofs {
+            Some(large_offsets_ofs) if offset & N32_HIGH_BIT != 0 => {
+                let start = large_offsets_ofs + (offset ^ N32_HIGH_BIT) as usize * N64_SIZE;
+                BigEndian::read_u64(&self.data[start..start + N64_SIZE])
+            }
+            _ => u64::from(offset),
+        };
+        (pack_id, offset)
+    }
+
+    /// Returns the `index` of the given object id for use with [`oid_at_index()`][multi_index::File::oid_at_index()] or
+    /// [`pack_id_and_pack_offset_at_index()`][multi_index::File::pack_id_and_pack_offset_at_index()].
+    pub fn lookup(&self, id: impl AsRef<git_hash::oid>) -> Option<u32> {
+        let id = id.as_ref();
+        let first_byte = id.first_byte() as usize;
+        let mut upper_bound = self.fan[first_byte];
+        let mut lower_bound = if first_byte != 0 { self.fan[first_byte - 1] } else { 0 };
+
+        while lower_bound < upper_bound {
+            let mid = (lower_bound + upper_bound) / 2;
+            let mid_sha = self.oid_at_index(mid);
+
+            use std::cmp::Ordering::*;
+            match id.cmp(mid_sha) {
+                Less => upper_bound = mid,
+                Equal => return Some(mid),
+                Greater => lower_bound = mid + 1,
+            }
+        }
+        None
+    }
+
+    /// Returns the trailing hash over all bytes of this file, which is also used to name its reverse index and bitmap.
+    pub fn checksum(&self) -> git_hash::ObjectId {
+        git_hash::ObjectId::from_20_bytes(&self.data[self.data.len() - SHA1_SIZE..])
+    }
+
+    /// Returns the order of objects in the pseudo-pack formed by all packs if it's stored in this file, which is the case
+    /// if it was written with a bitmap by git 2.35 or newer.
+    ///
+    /// Otherwise it's stored in a `.rev` file next to this one, named after the [checksum][multi_index::File::checksum()].
+    pub fn reverse_index(&self) -> Option<Result<reverse::Index, reverse::Error>> {
+        let start = self.reverse_index_ofs?;
+        let data = &self.data[start..start + self.num_objects as usize * N32_SIZE];
+        Some(
+            reverse::Index::from_be_bytes(data, self.num_objects)
+                .map_err(|message| reverse::Error::Corrupt { message }),
+        )
+    }
+}
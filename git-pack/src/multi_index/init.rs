@@ -0,0 +1,204 @@
+use std::{
+    convert::{TryFrom, TryInto},
+    path::{Path, PathBuf},
+};
+
+use byteorder::{BigEndian, ByteOrder};
+use filebuffer::FileBuffer;
+use git_hash::SIZE_OF_SHA1_DIGEST as SHA1_SIZE;
+
+use crate::multi_index::{self, FAN_LEN, N32_HIGH_BIT};
+
+/// Returned by [`multi_index::File::at()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not open multi-pack index file at '{path}'")]
+    Io {
+        source: std::io::Error,
+        path: std::path::PathBuf,
+    },
+    #[error("{message}")]
+    Corrupt { message: String },
+    #[error("Unsupported multi-pack index version: {version}")]
+    UnsupportedVersion { version: u8 },
+    #[error("Unsupported object hash of multi-pack index: {version}")]
+    UnsupportedObjectHash { version: u8 },
+    #[error("The multi-pack index is missing the required chunk '{}'", String::from_utf8_lossy(.id))]
+    MissingChunk { id: ChunkId },
+}
+
+pub(crate) type ChunkId = [u8; 4];
+
+pub(crate) const SIGNATURE: &[u8] = b"MIDX";
+pub(crate) const HEADER_LEN: usize = 12;
+pub(crate) const CHUNK_LOOKUP_SIZE: usize = 12;
+const N32_SIZE: usize = 4;
+const N64_SIZE: usize = 8;
+
+pub(crate) const PACK_NAMES: ChunkId = *b"PNAM";
+pub(crate) const OID_FAN: ChunkId = *b"OIDF";
+pub(crate) const OID_LOOKUP: ChunkId = *b"OIDL";
+pub(crate) const OBJECT_OFFSETS: ChunkId = *b"OOFF";
+pub(crate) const LARGE_OFFSETS: ChunkId = *b"LOFF";
+const REVERSE_INDEX: ChunkId = *b"RIDX";
+
+/// Instantiation
+impl multi_index::File {
+    /// Open the multi-pack index file at the given `path`.
+    pub fn at(path: impl AsRef<Path>) -> Result<multi_index::File, Error> {
+        Self::try_from(path.as_ref())
+    }
+}
+
+impl TryFrom<&Path> for multi_index::File {
+    type Error = Error;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        let data = FileBuffer::open(&path).map_err(|e| Error::Io {
+            source: e,
+            path: path.to_owned(),
+        })?;
+        let corrupt = |message: String| Error::Corrupt { message };
+        if data.len() < HEADER_LEN + CHUNK_LOOKUP_SIZE + SHA1_SIZE {
+            return Err(corrupt(format!(
+                "Multi-pack index of size {} is too small for even an empty index",
+                data.len()
+            )));
+        }
+        if &data[..SIGNATURE.len()] != SIGNATURE {
+            return Err(corrupt(
+                "Multi-pack index does not start with the expected signature".into(),
+            ));
+        }
+        match data[4] {
+            1 => {}
+            version => return Err(Error::UnsupportedVersion { version }),
+        }
+        match data[5] {
+            1 => {}
+            version => return Err(Error::UnsupportedObjectHash { version }),
+        }
+        let num_chunks = usize::from(data[6]);
+        if data[7] != 0 {
+            return Err(corrupt(format!(
+                "Multi-pack index refers to {} base files, which isn't supported",
+                data[7]
+            )));
+        }
+        let num_packs = BigEndian::read_u32(&data[8..12]);
+
+        let trailer = data.len() - SHA1_SIZE;
+        let lookup_end = HEADER_LEN + (num_chunks + 1) * CHUNK_LOOKUP_SIZE;
+        if lookup_end > trailer {
+            return Err(corrupt(format!(
+                "Multi-pack index is too small to hold {} chunks",
+                num_chunks
+            )));
+        }
+        let mut chunks = Vec::with_capacity(num_chunks);
+        for entry in data[HEADER_LEN..lookup_end].chunks(CHUNK_LOOKUP_SIZE) {
+            let id: ChunkId = entry[..4].try_into().expect("4 bytes");
+            let offset: usize = BigEndian::read_u64(&entry[4..])
+                .try_into()
+                .map_err(|_| corrupt("Multi-pack index chunk offset doesn't fit into memory".into()))?;
+            chunks.push((id, offset));
+        }
+        for window in chunks.windows(2) {
+            let ((id, start), (_, end)) = (window[0], window[1]);
+            if start < lookup_end || end < start || end > trailer {
+                return Err(corrupt(format!(
+                    "Multi-pack index chunk '{}' has an invalid range {}..{}",
+                    String::from_utf8_lossy(&id),
+                    start,
+                    end
+                )));
+            }
+        }
+        let chunk = |wanted: ChunkId| {
+            chunks
+                .windows(2)
+                .find(|window| window[0].0 == wanted)
+                .map(|window| window[0].1..window[1].1)
+        };
+        let required = |id: ChunkId| chunk(id).ok_or(Error::MissingChunk { id });
+
+        let fan_range = required(OID_FAN)?;
+        if fan_range.len() != FAN_LEN * N32_SIZE {
+            return Err(corrupt(format!(
+                "Multi-pack index fan chunk has size {}",
+                fan_range.len()
+            )));
+        }
+        let mut fan = [0; FAN_LEN];
+        for (c, f) in data[fan_range].chunks(N32_SIZE).zip(fan.iter_mut()) {
+            *f = BigEndian::read_u32(c);
+        }
+        let num_objects = fan[FAN_LEN - 1];
+        let num_objects_usize = num_objects as usize;
+
+        let lookup = required(OID_LOOKUP)?;
+        let offsets = required(OBJECT_OFFSETS)?;
+        if lookup.len() != num_objects_usize * SHA1_SIZE || offsets.len() != num_objects_usize * N64_SIZE {
+            return Err(corrupt(format!(
+                "Multi-pack index lookup or offset chunk doesn't fit the amount of objects, {}",
+                num_objects
+            )));
+        }
+        let large_offsets = chunk(LARGE_OFFSETS);
+        if large_offsets.as_ref().map_or(false, |r| r.len() % N64_SIZE != 0) {
+            return Err(corrupt(
+                "Multi-pack index large offsets chunk has an invalid size".into(),
+            ));
+        }
+        let num_large_offsets = large_offsets.as_ref().map_or(0, |r| r.len() / N64_SIZE);
+        for entry in data[offsets.clone()].chunks(N64_SIZE) {
+            let offset = BigEndian::read_u32(&entry[N32_SIZE..]);
+            if BigEndian::read_u32(&entry[..N32_SIZE]) >= num_packs
+                || (offset & N32_HIGH_BIT != 0 && (offset ^ N32_HIGH_BIT) as usize >= num_large_offsets)
+            {
+                return Err(corrupt(
+                    "Multi-pack index refers to a pack or large offset that doesn't exist".into(),
+                ));
+            }
+        }
+        let reverse_index = chunk(REVERSE_INDEX);
+        if reverse_index
+            .as_ref()
+            .map_or(false, |r| r.len() != num_objects_usize * N32_SIZE)
+        {
+            return Err(corrupt(
+                "Multi-pack index reverse index chunk doesn't fit the amount of objects".into(),
+            ));
+        }
+
+        let index_names: Vec<PathBuf> = data[required(PACK_NAMES)?]
+            .split(|b| *b == 0)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                std::str::from_utf8(name)
+                    .map(PathBuf::from)
+                    .map_err(|_| corrupt("Multi-pack index contains a pack name which isn't valid UTF-8".into()))
+            })
+            .collect::<Result<_, _>>()?;
+        if index_names.len() != num_packs as usize {
+            return Err(corrupt(format!(
+                "Multi-pack index should contain {} pack names, but has {}",
+                num_packs,
+                index_names.len()
+            )));
+        }
+
+        Ok(multi_index::File {
+            data,
+            path: path.to_owned(),
+            num_objects,
+            fan,
+            index_names,
+            lookup_ofs: lookup.start,
+            offsets_ofs: offsets.start,
+            large_offsets_ofs: large_offsets.map(|r| r.start),
+            reverse_index_ofs: reverse_index.map(|r| r.start),
+        })
+    }
+}
@@ -0,0 +1,53 @@
+//! A multi-pack index, which maps object ids to their location in any of the packs it covers.
+//!
+//! It is stored in `objects/pack/multi-pack-index` and written by `git multi-pack-index write`, usually along with a
+//! [reverse index][crate::index::reverse] and a [reachability bitmap][crate::bitmap].
+use std::path::PathBuf;
+
+use filebuffer::FileBuffer;
+
+const FAN_LEN: usize = 256;
+const N32_HIGH_BIT: u32 = 1 << 31;
+
+/// A representation of a multi-pack index file.
+pub struct File {
+    data: FileBuffer,
+    path: PathBuf,
+    num_objects: u32,
+    fan: [u32; FAN_LEN],
+    index_names: Vec<PathBuf>,
+    lookup_ofs: usize,
+    offsets_ofs: usize,
+    large_offsets_ofs: Option<usize>,
+    reverse_index_ofs: Option<usize>,
+}
+
+/// Basic file information
+impl File {
+    /// The path of the opened multi-pack index file.
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+    /// The amount of objects stored in all packs.
+    pub fn num_objects(&self) -> u32 {
+        self.num_objects
+    }
+    /// The amount of packs covered by this index.
+    pub fn num_packs(&self) -> u32 {
+        self.index_names.len() as u32
+    }
+    /// The names of the pack index files of all covered packs, relative to the directory containing this file and sorted by name.
+    ///
+    /// Their position is the pack id as returned by [`pack_id_and_pack_offset_at_index()`][File::pack_id_and_pack_offset_at_index()].
+    pub fn index_names(&self) -> &[PathBuf] {
+        &self.index_names
+    }
+}
+
+mod access;
+///
+pub mod init;
+///
+pub mod verify;
+///
+pub mod write;
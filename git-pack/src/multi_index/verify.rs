@@ -0,0 +1,154 @@
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use git_features::progress::{self, Progress};
+use git_hash::SIZE_OF_SHA1_DIGEST as SHA1_SIZE;
+
+use crate::{index, multi_index};
+
+/// Returned by [`multi_index::File::verify_checksum()`] and [`multi_index::File::verify_integrity()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("multi-pack index checksum mismatch: expected {expected}, got {actual}")]
+    Mismatch {
+        expected: git_hash::ObjectId,
+        actual: git_hash::ObjectId,
+    },
+    #[error("The fan-out table decreases at entry {index}")]
+    Fan { index: usize },
+    #[error("Object {id} at index {index} is not sorted or not in the fan-out bucket of its first byte")]
+    OutOfOrder { id: git_hash::ObjectId, index: u32 },
+    #[error("Could not open the index of pack {pack_id} at '{}'", .path.display())]
+    PackIndex {
+        pack_id: u32,
+        path: PathBuf,
+        source: index::init::Error,
+    },
+    #[error(
+        "Object {id} is supposed to be at offset {expected} of pack {pack_id}, but its index has it at {actual:?}"
+    )]
+    OffsetMismatch {
+        id: git_hash::ObjectId,
+        pack_id: u32,
+        expected: u64,
+        actual: Option<u64>,
+    },
+    #[error("Interrupted")]
+    Interrupted,
+}
+
+/// Verify and validate the content of the multi-pack index file
+impl multi_index::File {
+    /// Validate that our [`checksum()`][multi_index::File::checksum()] matches the actual contents of this file, and return it if it does.
+    pub fn verify_checksum(
+        &self,
+        mut progress: impl Progress,
+        should_interrupt: &AtomicBool,
+    ) -> Result<git_hash::ObjectId, Error> {
+        let data_len_without_trailer = self.data.len() - SHA1_SIZE;
+        let actual = match git_features::hash::bytes_of_file(
+            &self.path,
+            data_len_without_trailer,
+            git_hash::Kind::Sha1,
+            &mut progress,
+            should_interrupt,
+        ) {
+            Ok(id) => id,
+            Err(_io_err) => {
+                let start = std::time::Instant::now();
+                let mut hasher = git_features::hash::Sha1::default();
+                hasher.update(&self.data[..data_len_without_trailer]);
+                progress.inc_by(data_len_without_trailer);
+                progress.show_throughput(start);
+                git_hash::ObjectId::new_sha1(hasher.digest())
+            }
+        };
+
+        let expected = self.checksum();
+        if actual == expected {
+            Ok(actual)
+        } else {
+            Err(Error::Mismatch { actual, expected })
+        }
+    }
+
+    /// Validate the checksum of this file, that its fan-out table agrees with its sorted object ids and, if `check_packs` is true,
+    /// that the index of each covered pack has each object at the offset stored here. Return the checksum if all is well.
+    ///
+    /// The pack indices are expected next to this file, with the [names][multi_index::File::index_names()] stored in it.
+    /// Note that the structure of offsets and pack ids is already validated when [opening][multi_index::File::at()] the file.
+    pub fn verify_integrity(
+        &self,
+        mut progress: impl Progress,
+        check_packs: bool,
+        should_interrupt: &AtomicBool,
+    ) -> Result<git_hash::ObjectId, Error> {
+        let checksum = self.verify_checksum(progress.add_child("Sha1 of multi-pack index"), should_interrupt)?;
+
+        if let Some(index) = self.fan.windows(2).position(|w| w[0] > w[1]) {
+            return Err(Error::Fan { index: index + 1 });
+        }
+
+        let pack_indices = if check_packs {
+            let dir = self.path.parent().unwrap_or_else(|| std::path::Path::new("."));
+            self.index_names
+                .iter()
+                .enumerate()
+                .map(|(pack_id, name)| {
+                    let path = dir.join(name);
+                    index::File::at(&path).map_err(|source| Error::PackIndex {
+                        pack_id: pack_id as u32,
+                        path,
+                        source,
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            Vec::new()
+        };
+
+        progress.init(Some(self.num_objects as usize), progress::count("objects"));
+        let start = std::time::Instant::now();
+        let mut previous: Option<&git_hash::oid> = None;
+        for index in 0..self.num_objects {
+            if should_interrupt.load(Ordering::Relaxed) {
+                return Err(Error::Interrupted);
+            }
+            let id = self.oid_at_index(index);
+            let first_byte = id.first_byte() as usize;
+            let bucket_start = if first_byte == 0 { 0 } else { self.fan[first_byte - 1] };
+            if previous.map_or(false, |previous| previous >= id)
+                || index < bucket_start
+                || index >= self.fan[first_byte]
+            {
+                return Err(Error::OutOfOrder {
+                    id: id.to_owned(),
+                    index,
+                });
+            }
+            previous = Some(id);
+
+            if check_packs {
+                let (pack_id, expected) = self.pack_id_and_pack_offset_at_index(index);
+                let pack_index = &pack_indices[pack_id as usize];
+                let actual = pack_index
+                    .lookup(id)
+                    .map(|index_in_pack| pack_index.pack_offset_at_index(index_in_pack));
+                if actual != Some(expected) {
+                    return Err(Error::OffsetMismatch {
+                        id: id.to_owned(),
+                        pack_id,
+                        expected,
+                        actual,
+                    });
+                }
+            }
+            progress.inc();
+        }
+        progress.show_throughput(start);
+        Ok(checksum)
+    }
+}
@@ -0,0 +1,159 @@
+use std::{
+    cmp::Reverse,
+    convert::TryFrom,
+    io,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use git_hash::{ObjectId, SIZE_OF_SHA1_DIGEST as SHA1_SIZE};
+
+use crate::{
+    index,
+    multi_index::{
+        self,
+        init::{
+            ChunkId, CHUNK_LOOKUP_SIZE, HEADER_LEN, LARGE_OFFSETS, OBJECT_OFFSETS, OID_FAN, OID_LOOKUP, PACK_NAMES,
+            SIGNATURE,
+        },
+        FAN_LEN, N32_HIGH_BIT,
+    },
+};
+
+/// Returned by [`multi_index::File::write_from_index_paths()`].
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not write the multi-pack index")]
+    Io(#[from] io::Error),
+    #[error("Could not open the pack index at '{}'", .path.display())]
+    PackIndex { path: PathBuf, source: index::init::Error },
+    #[error("The pack index at '{}' must have a file name which is valid UTF-8", .path.display())]
+    InvalidPath { path: PathBuf },
+    #[error("A multi-pack index can't hold more than {} objects", u32::MAX)]
+    TooManyObjects,
+}
+
+/// The outcome of [`multi_index::File::write_from_index_paths()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// The checksum of the written file.
+    pub checksum: ObjectId,
+    /// The amount of objects in all packs, counting objects contained in multiple packs once.
+    pub num_objects: u32,
+    /// The names of the pack indices, sorted by name, which is the order they have in the written file.
+    pub index_names: Vec<PathBuf>,
+}
+
+/// Writing
+impl multi_index::File {
+    /// Write a multi-pack index for the packs whose indices are at `index_paths` to `out`, like `git multi-pack-index write` does,
+    /// but without a reverse index or bitmap.
+    ///
+    /// The file names of the indices are stored, so the written file must be placed in the directory containing them.
+    /// Objects contained in more than one pack are looked up in the pack that was modified most recently.
+    pub fn write_from_index_paths(index_paths: Vec<PathBuf>, mut out: impl io::Write) -> Result<Outcome, Error> {
+        let mut packs = index_paths
+            .into_iter()
+            .map(|path| match path.file_name().and_then(|name| name.to_str()) {
+                Some(name) => Ok((PathBuf::from(name), path)),
+                None => Err(Error::InvalidPath { path }),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        packs.sort_by(|a, b| a.0.cmp(&b.0));
+        packs.dedup_by(|a, b| a.0 == b.0);
+
+        let mut entries = Vec::new();
+        for (pack_id, (_, path)) in packs.iter().enumerate() {
+            let index = index::File::at(path).map_err(|source| Error::PackIndex {
+                path: path.clone(),
+                source,
+            })?;
+            let modified = std::fs::metadata(path.with_extension("pack"))
+                .and_then(|meta| meta.modified())
+                .unwrap_or(UNIX_EPOCH);
+            entries.extend(index.iter().map(|entry| {
+                (
+                    entry.oid,
+                    Reverse::<SystemTime>(modified),
+                    pack_id as u32,
+                    entry.pack_offset,
+                )
+            }));
+        }
+        entries.sort();
+        entries.dedup_by_key(|entry| entry.0);
+        let num_objects = u32::try_from(entries.len()).map_err(|_| Error::TooManyObjects)?;
+
+        let mut names = Vec::new();
+        for (name, _) in &packs {
+            names.extend_from_slice(name.to_str().expect("checked to be valid UTF-8").as_bytes());
+            names.push(0);
+        }
+        while names.len() % 4 != 0 {
+            names.push(0);
+        }
+        let mut counts = [0u32; FAN_LEN];
+        for (id, ..) in &entries {
+            counts[id.first_byte() as usize] += 1;
+        }
+        let mut fan = Vec::with_capacity(FAN_LEN * 4);
+        let mut num_objects_so_far = 0;
+        for count in counts.iter() {
+            num_objects_so_far += count;
+            fan.extend_from_slice(&num_objects_so_far.to_be_bytes());
+        }
+        let mut lookup = Vec::with_capacity(entries.len() * SHA1_SIZE);
+        let mut offsets = Vec::with_capacity(entries.len() * 8);
+        let mut large_offsets = Vec::new();
+        for (id, _, pack_id, offset) in &entries {
+            lookup.extend_from_slice(id.as_bytes());
+            offsets.extend_from_slice(&pack_id.to_be_bytes());
+            let offset = if *offset >= u64::from(N32_HIGH_BIT) {
+                let large_offset_index = (large_offsets.len() / 8) as u32;
+                large_offsets.extend_from_slice(&offset.to_be_bytes());
+                large_offset_index | N32_HIGH_BIT
+            } else {
+                *offset as u32
+            };
+            offsets.extend_from_slice(&offset.to_be_bytes());
+        }
+
+        let mut chunks: Vec<(ChunkId, Vec<u8>)> = vec![
+            (PACK_NAMES, names),
+            (OID_FAN, fan),
+            (OID_LOOKUP, lookup),
+            (OBJECT_OFFSETS, offsets),
+        ];
+        if !large_offsets.is_empty() {
+            chunks.push((LARGE_OFFSETS, large_offsets));
+        }
+
+        let mut data = Vec::new();
+        data.extend_from_slice(SIGNATURE);
+        data.extend_from_slice(&[1, 1, chunks.len() as u8, 0]);
+        data.extend_from_slice(&(packs.len() as u32).to_be_bytes());
+        let mut offset = (HEADER_LEN + (chunks.len() + 1) * CHUNK_LOOKUP_SIZE) as u64;
+        for (id, chunk) in &chunks {
+            data.extend_from_slice(id);
+            data.extend_from_slice(&offset.to_be_bytes());
+            offset += chunk.len() as u64;
+        }
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&offset.to_be_bytes());
+        for (_, chunk) in &chunks {
+            data.extend_from_slice(chunk);
+        }
+        let mut hasher = git_features::hash::Sha1::default();
+        hasher.update(&data);
+        let checksum = ObjectId::new_sha1(hasher.digest());
+        data.extend_from_slice(checksum.as_bytes());
+        out.write_all(&data)?;
+
+        Ok(Outcome {
+            checksum,
+            num_objects,
+            index_names: packs.into_iter().map(|(name, _)| name).collect(),
+        })
+    }
+}
@@ -0,0 +1,28 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+git checkout -q -b main
+echo a > a
+git add a && git commit -qm a
+mkdir dir && echo b > dir/b
+git add dir && git commit -qm b
+git tag -m "an annotated tag" first
+echo c > c
+git add c && git commit -qm c
+
+git -c pack.writeReverseIndex=true repack -adbq
+git rev-list --objects --no-object-names main > main.objects
+
+echo d > d
+git add d && git commit -qm d
+git checkout -q -b other HEAD~2
+echo e > e
+git add e && git commit -qm e
+git repack -dq
+
+git multi-pack-index write --bitmap
+git rev-list --objects --no-object-names other > other.objects
+git rev-list --objects --no-object-names --all > all.objects
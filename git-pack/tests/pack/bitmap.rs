@@ -0,0 +1,236 @@
+use std::{collections::BTreeSet, path::PathBuf};
+
+use git_hash::ObjectId;
+use git_pack::{
+    bitmap::{self, Reachability},
+    index::{self, reverse},
+    multi_index,
+};
+
+use crate::scripted_fixture_repo_read_only;
+
+fn repo() -> crate::Result<PathBuf> {
+    scripted_fixture_repo_read_only("make_repo_with_bitmaps.sh")
+}
+
+fn pack_dir() -> crate::Result<PathBuf> {
+    Ok(repo()?.join(".git").join("objects").join("pack"))
+}
+
+fn expected_objects(name: &str) -> crate::Result<BTreeSet<ObjectId>> {
+    Ok(std::fs::read_to_string(repo()?.join(format!("{}.objects", name)))?
+        .lines()
+        .map(|hex| ObjectId::from_hex(hex.as_bytes()).expect("valid hex"))
+        .collect())
+}
+
+fn rev_parse(name: &str) -> crate::Result<ObjectId> {
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(repo()?)
+        .args(&["rev-parse", name])
+        .output()?;
+    Ok(ObjectId::from_hex(output.stdout.strip_suffix(b"\n").expect("newline"))?)
+}
+
+fn reachable_ids(
+    reachability: &Reachability<'_, impl bitmap::Index>,
+    commit: ObjectId,
+) -> crate::Result<BTreeSet<ObjectId>> {
+    let bitmap = reachability.reachable_from(commit)?.expect("bitmap for the tip");
+    Ok(reachability.ids(&bitmap).map(ToOwned::to_owned).collect())
+}
+
+fn pack_index_with_bitmap() -> crate::Result<index::File> {
+    let bitmap_path = std::fs::read_dir(pack_dir()?)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .find(|path| {
+            path.extension().map_or(false, |ext| ext == "bitmap")
+                && path
+                    .file_name()
+                    .map_or(false, |name| name.to_string_lossy().starts_with("pack-"))
+        })
+        .expect("a pack bitmap");
+    Ok(index::File::at(bitmap_path.with_extension("idx"))?)
+}
+
+mod single_pack {
+    use super::*;
+
+    #[test]
+    fn reverse_index_files_match_the_computed_pack_order() -> crate::Result {
+        let index = pack_index_with_bitmap()?;
+        let from_file = reverse::Index::at(
+            index.path().with_extension("rev"),
+            index.num_objects(),
+            &index.pack_checksum(),
+        )?;
+        from_file.verify()?;
+        assert_eq!(from_file, reverse::Index::from_pack_index(&index));
+
+        let mut buf = Vec::new();
+        from_file.write_to(&mut buf, &index.pack_checksum())?;
+        assert_eq!(
+            buf,
+            std::fs::read(index.path().with_extension("rev"))?,
+            "writing produces exactly what git wrote"
+        );
+        assert!(matches!(
+            reverse::Index::at(
+                index.path().with_extension("rev"),
+                index.num_objects(),
+                &index.index_checksum()
+            ),
+            Err(reverse::Error::ChecksumMismatch { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn reachable_objects() -> crate::Result {
+        let index = pack_index_with_bitmap()?;
+        let reachability = Reachability::from_pack_index(&index)?.expect("bitmap present");
+        assert_eq!(
+            reachable_ids(&reachability, rev_parse("main~1")?)?,
+            expected_objects("main")?
+        );
+
+        let bitmap = reachability.reachable_from(rev_parse("main~1")?)?.expect("present");
+        assert!(reachability.contains(&bitmap, rev_parse("main~1^{tree}")?));
+        assert!(
+            !reachability.contains(&bitmap, rev_parse("first")?),
+            "tags aren't reachable from commits"
+        );
+        assert_eq!(reachability.objects_of_kind(git_object::Kind::Commit).count(), 3);
+        assert_eq!(reachability.objects_of_kind(git_object::Kind::Tag).count(), 1);
+        Ok(())
+    }
+}
+
+mod multi_pack {
+    use super::*;
+
+    fn multi_index() -> crate::Result<multi_index::File> {
+        Ok(multi_index::File::at(pack_dir()?.join("multi-pack-index"))?)
+    }
+
+    #[test]
+    fn access() -> crate::Result {
+        let index = multi_index()?;
+        assert_eq!(index.num_packs(), 2);
+        assert!(index
+            .index_names()
+            .iter()
+            .all(|name| name.extension().map_or(false, |ext| ext == "idx")));
+        assert_eq!(index.num_objects() as usize, expected_objects("all")?.len());
+
+        for name in index.index_names() {
+            let pack_index = index::File::at(pack_dir()?.join(name))?;
+            for entry in pack_index.iter() {
+                let position = index.lookup(&entry.oid).expect("all objects are indexed");
+                assert_eq!(index.oid_at_index(position), entry.oid);
+                let (pack_id, offset) = index.pack_id_and_pack_offset_at_index(position);
+                let pack_of_object = &index.index_names()[pack_id as usize];
+                if pack_of_object == name {
+                    assert_eq!(offset, entry.pack_offset);
+                }
+            }
+        }
+        assert_eq!(index.lookup(ObjectId::null_sha1()), None);
+        Ok(())
+    }
+
+    #[test]
+    fn verify_integrity() -> crate::Result {
+        let index = multi_index()?;
+        let should_interrupt = std::sync::atomic::AtomicBool::new(false);
+        use git_features::progress::Discard;
+        assert_eq!(index.verify_checksum(Discard, &should_interrupt)?, index.checksum());
+        assert_eq!(
+            index.verify_integrity(Discard, true, &should_interrupt)?,
+            index.checksum(),
+            "the offsets of all objects match the ones in the pack indices"
+        );
+
+        let tmp = tempfile::tempdir()?;
+        let path = tmp.path().join("multi-pack-index");
+        let mut data = std::fs::read(index.path())?;
+        let last_lookup_byte = data.len() - git_hash::SIZE_OF_SHA1_DIGEST - 1;
+        data[last_lookup_byte] ^= 0xff;
+        std::fs::write(&path, data)?;
+        let corrupt = multi_index::File::at(&path)?;
+        assert!(matches!(
+            corrupt.verify_integrity(Discard, false, &should_interrupt),
+            Err(multi_index::verify::Error::Mismatch { .. })
+        ));
+
+        std::fs::copy(index.path(), &path)?;
+        assert!(
+            matches!(
+                multi_index::File::at(&path)?.verify_integrity(Discard, true, &should_interrupt),
+                Err(multi_index::verify::Error::PackIndex { pack_id: 0, .. })
+            ),
+            "pack indices are expected next to the multi-pack index"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn write_from_index_paths() -> crate::Result {
+        let expected = multi_index()?;
+        let tmp = tempfile::tempdir()?;
+        let mut index_paths = Vec::new();
+        for name in expected.index_names().iter().rev() {
+            let path = tmp.path().join(name);
+            std::fs::copy(pack_dir()?.join(name), &path)?;
+            std::fs::copy(
+                pack_dir()?.join(name).with_extension("pack"),
+                path.with_extension("pack"),
+            )?;
+            index_paths.push(path);
+        }
+
+        let path = tmp.path().join("multi-pack-index");
+        let outcome = multi_index::File::write_from_index_paths(index_paths, std::fs::File::create(&path)?)?;
+        assert_eq!(outcome.index_names, expected.index_names(), "names are sorted");
+        assert_eq!(outcome.num_objects, expected.num_objects());
+
+        let index = multi_index::File::at(&path)?;
+        let should_interrupt = std::sync::atomic::AtomicBool::new(false);
+        assert_eq!(
+            index.verify_integrity(git_features::progress::Discard, true, &should_interrupt)?,
+            outcome.checksum,
+            "the offsets of all objects match the ones in the pack indices"
+        );
+        for idx in 0..expected.num_objects() {
+            assert_eq!(index.oid_at_index(idx), expected.oid_at_index(idx));
+        }
+        assert!(index.reverse_index().is_none(), "no reverse index is written");
+        Ok(())
+    }
+
+    #[test]
+    fn reachable_objects_across_packs() -> crate::Result {
+        let index = multi_index()?;
+        assert!(index.reverse_index().is_some(), "the pack order is stored in the index");
+        let reachability = Reachability::from_multi_index(&index)?.expect("bitmap present");
+        assert_eq!(
+            reachable_ids(&reachability, rev_parse("other")?)?,
+            expected_objects("other")?
+        );
+        assert_eq!(reachability.objects_of_kind(git_object::Kind::Commit).count(), 5);
+        assert_eq!(
+            reachability
+                .ids(reachability.objects_of_kind(git_object::Kind::Tag))
+                .map(ToOwned::to_owned)
+                .collect::<Vec<_>>(),
+            vec![rev_parse("first")?]
+        );
+        assert_eq!(
+            reachability.reachable_from(ObjectId::null_sha1())?,
+            None,
+            "unknown objects have no bitmap"
+        );
+        Ok(())
+    }
+}
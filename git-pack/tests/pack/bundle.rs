@@ -97,6 +97,7 @@ mod write_to_directory {
             pack_kind: pack::data::Version::V2,
             index_path: None,
             data_path: None,
+            reverse_index_path: None,
         })
     }
 
@@ -134,6 +135,34 @@ mod write_to_directory {
         Ok(())
     }
 
+    #[test]
+    fn given_a_directory_with_reverse_index() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = TempDir::new()?;
+        let res = write_pack_with_options(
+            Some(&dir),
+            SMALL_PACK,
+            pack::bundle::write::Options {
+                write_reverse_index: true,
+                fsync: git_features::fs::Fsync::Everything,
+                ..Default::default()
+            },
+        )?;
+        let reverse_index_path = res.reverse_index_path.expect("written");
+        assert_eq!(
+            reverse_index_path,
+            dir.path()
+                .join(format!("{}.rev", res.index.data_hash.to_sha1_hex_string()))
+        );
+
+        let index = pack::index::File::at(res.index_path.expect("written"))?;
+        let reverse_index =
+            pack::index::reverse::Index::at(&reverse_index_path, index.num_objects(), &index.pack_checksum())?;
+        reverse_index.verify()?;
+        assert_eq!(reverse_index, pack::index::reverse::Index::from_pack_index(&index));
+        assert_eq!(fs::read_dir(&dir)?.count(), 3, "pack, index and reverse index");
+        Ok(())
+    }
+
     fn file_name(entry: &fs::DirEntry) -> String {
         entry.path().file_name().unwrap().to_str().unwrap().to_owned()
     }
@@ -141,6 +170,24 @@ mod write_to_directory {
     fn write_pack(
         directory: Option<impl AsRef<Path>>,
         pack_file: &str,
+    ) -> Result<pack::bundle::write::Outcome, Box<dyn std::error::Error>> {
+        write_pack_with_options(
+            directory,
+            pack_file,
+            pack::bundle::write::Options {
+                thread_limit: None,
+                iteration_mode: pack::data::input::Mode::Verify,
+                index_kind: pack::index::Version::V2,
+                write_reverse_index: false,
+                fsync: Default::default(),
+            },
+        )
+    }
+
+    fn write_pack_with_options(
+        directory: Option<impl AsRef<Path>>,
+        pack_file: &str,
+        options: pack::bundle::write::Options,
     ) -> Result<pack::bundle::write::Outcome, Box<dyn std::error::Error>> {
         let pack_file = fs::File::open(fixture_path(pack_file))?;
         static SHOULD_INTERRUPT: AtomicBool = AtomicBool::new(false);
@@ -151,11 +198,7 @@ mod write_to_directory {
             progress::Discard,
             &SHOULD_INTERRUPT,
             None,
-            pack::bundle::write::Options {
-                thread_limit: None,
-                iteration_mode: pack::data::input::Mode::Verify,
-                index_kind: pack::index::Version::V2,
-            },
+            options,
         )
         .map_err(Into::into)
     }
@@ -0,0 +1,141 @@
+use git_pack::data::delta;
+
+/// Deterministic pseudo-random bytes, to have content which can't be compressed.
+fn noise(len: usize, mut seed: u32) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as u8
+        })
+        .collect()
+}
+
+fn resolve(base: &[u8], delta: &[u8]) -> Result<Vec<u8>, delta::resolve::Error> {
+    let mut out = Vec::new();
+    delta::resolve(base, delta, &mut out)?;
+    Ok(out)
+}
+
+fn apply(base: &[u8], delta: &[u8]) -> Vec<u8> {
+    let (base_size, consumed) = delta::decode_header_size(delta);
+    assert_eq!(base_size, base.len() as u64);
+    let (result_size, result_consumed) = delta::decode_header_size(&delta[consumed..]);
+    let mut out = vec![0; result_size as usize];
+    delta::apply(base, &mut out, &delta[consumed + result_consumed..]);
+    out
+}
+
+#[test]
+fn created_deltas_reproduce_the_target() -> crate::Result {
+    let base = noise(200_000, 1);
+    let mut target = base[..50_000].to_vec();
+    target.extend_from_slice(b"inserted in the middle");
+    target.extend_from_slice(&base[120_000..]);
+    target.extend_from_slice(&base[10..100_000]);
+
+    for (base, target) in [
+        (&base[..], &target[..]),
+        (&base, &base),
+        (&base, &noise(1000, 2)),
+        (&[][..], &target),
+        (&base, &[][..]),
+        (b"short", b"shorter"),
+    ] {
+        let delta = delta::create(base, target);
+        assert_eq!(resolve(base, &delta)?, target);
+        assert_eq!(
+            apply(base, &delta),
+            target,
+            "the delta can be applied like the ones in packs"
+        );
+        assert_eq!(delta::Index::new(base).delta_size(target), delta.len());
+    }
+    Ok(())
+}
+
+#[test]
+fn similar_objects_produce_small_deltas_and_size_limits_are_respected() {
+    let base = noise(100_000, 3);
+    let mut target = base.clone();
+    target[30_000..30_010].copy_from_slice(b"0123456789");
+    target.truncate(90_000);
+
+    let index = delta::Index::new(&base);
+    let size = index.delta_size(&target);
+    assert!(size < 100, "only a few instructions are needed, got {} bytes", size);
+    assert_eq!(index.create(&target, Some(size)).map(|d| d.len()), Some(size));
+    assert_eq!(
+        index.create(&target, Some(size - 1)),
+        None,
+        "deltas larger than the limit are dropped"
+    );
+
+    let unrelated = noise(10_000, 4);
+    assert!(
+        index.create(&unrelated, Some(unrelated.len())).is_none(),
+        "unrelated content can't be expressed as smaller delta"
+    );
+}
+
+#[test]
+fn invalid_deltas_are_rejected_when_resolving() {
+    let base = noise(1000, 5);
+    let target = [&base[500..], &b"new"[..]].concat();
+    let delta = delta::create(&base, &target);
+
+    assert!(matches!(
+        resolve(&base[1..], &delta),
+        Err(delta::resolve::Error::BaseSize {
+            expected: 1000,
+            actual: 999
+        })
+    ));
+    for len in 0..delta.len() {
+        assert!(
+            matches!(resolve(&base, &delta[..len]), Err(delta::resolve::Error::Corrupt)),
+            "truncated at {}",
+            len
+        );
+    }
+    let mut with_invalid_instruction = delta.clone();
+    with_invalid_instruction.push(0);
+    assert!(resolve(&base, &with_invalid_instruction).is_err());
+}
+
+#[test]
+fn bases_are_chosen_among_candidates_of_similar_size() {
+    use git_diff::candidates::{Item, Limits};
+
+    let base = noise(10_000, 6);
+    let objects = vec![
+        base.clone(),
+        [&base[..5000], &b"changed"[..], &base[5000..]].concat(),
+        noise(9_000, 7),
+        noise(100, 8),
+    ];
+    let items: Vec<_> = objects
+        .iter()
+        .enumerate()
+        .map(|(idx, data)| {
+            let mut id = [0u8; 20];
+            id[0] = idx as u8;
+            Item {
+                id: id.into(),
+                size: data.len() as u64,
+            }
+        })
+        .collect();
+    let candidates = delta::base_candidates(&items, &Limits::default());
+    assert_eq!(candidates, vec![vec![1, 2], vec![0, 2], vec![0, 1], vec![]]);
+
+    let (base_idx, delta) = delta::best(&objects[1], candidates[1].iter().map(|idx| (*idx, &objects[*idx])))
+        .expect("a small delta against the original");
+    assert_eq!(base_idx, 0);
+    assert_eq!(resolve(&objects[0], &delta).expect("valid"), objects[1]);
+    assert!(
+        delta::best(&objects[2], candidates[2].iter().map(|idx| (*idx, &objects[*idx]))).is_none(),
+        "unrelated content yields no delta worth storing"
+    );
+}
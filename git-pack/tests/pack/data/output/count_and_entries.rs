@@ -112,12 +112,14 @@ fn traversals() -> crate::Result {
                 expanded_objects: 0,
                 decoded_objects: 16,
                 total_objects: 16,
+                ..Default::default()
             },
             output::entry::iter_from_counts::Outcome {
                 decoded_and_recompressed_objects: 0,
                 missing_objects: 0,
                 objects_copied_from_pack: 16,
                 ref_delta_objects: 0,
+                ..Default::default()
             },
             hex_to_id("b920bbb055e1efb9080592a409d3975738b6efb3"),
             None,
@@ -145,12 +147,14 @@ fn traversals() -> crate::Result {
                 expanded_objects: 102,
                 decoded_objects: 18,
                 total_objects: 103,
+                ..Default::default()
             },
             output::entry::iter_from_counts::Outcome {
                 decoded_and_recompressed_objects: 0,
                 missing_objects: 0,
                 objects_copied_from_pack: 103,
                 ref_delta_objects: 74,
+                ..Default::default()
             },
             hex_to_id("25114bd8820b393c402cd53ad8ec7f6a84bb0633"),
             Some(hex_to_id("29ab9797aff1ca826afb699680356695d19c5acb")),
@@ -178,12 +182,14 @@ fn traversals() -> crate::Result {
                 expanded_objects: 102,
                 decoded_objects: 18,
                 total_objects: 103,
+                ..Default::default()
             },
             output::entry::iter_from_counts::Outcome {
                 decoded_and_recompressed_objects: 74,
                 missing_objects: 0,
                 objects_copied_from_pack: 29,
                 ref_delta_objects: 0,
+                ..Default::default()
             },
             hex_to_id("d83d42128e40957c5174920189a0390b5a70f446"),
             None,
@@ -199,12 +205,14 @@ fn traversals() -> crate::Result {
                 expanded_objects: 852,
                 decoded_objects: 57,
                 total_objects: 868,
+                ..Default::default()
             },
             output::entry::iter_from_counts::Outcome {
                 decoded_and_recompressed_objects: 0,
                 missing_objects: 0,
                 objects_copied_from_pack: 868,
                 ref_delta_objects: 0,
+                ..Default::default()
             },
             hex_to_id("542ad1d1c7c762ea4e36907570ff9e4b5b7dde1b"),
             None,
@@ -220,12 +228,14 @@ fn traversals() -> crate::Result {
                 expanded_objects: 866,
                 decoded_objects: 208,
                 total_objects: 868,
+                ..Default::default()
             },
             output::entry::iter_from_counts::Outcome {
                 decoded_and_recompressed_objects: 0,
                 missing_objects: 0,
                 objects_copied_from_pack: 868,
                 ref_delta_objects: 0,
+                ..Default::default()
             },
             hex_to_id("542ad1d1c7c762ea4e36907570ff9e4b5b7dde1b"),
             None,
@@ -279,7 +289,21 @@ fn traversals() -> crate::Result {
         let counts_len = counts.len();
         assert_eq!(counts_len, expected_obj_count.total());
 
-        assert_eq!(stats, expected_counts_outcome);
+        assert!(
+            stats.seen_objects_bytes >= counts_len * std::mem::size_of::<git_hash::ObjectId>(),
+            "every counted object was seen"
+        );
+        assert!(stats.peak_buffer_bytes > 0, "objects were read into buffers");
+        assert_eq!(
+            output::count::objects::Outcome {
+                seen_objects_bytes: 0,
+                peak_buffer_bytes: 0,
+                peak_cache_bytes: 0,
+                ..stats
+            },
+            expected_counts_outcome,
+            "memory usage depends on the allocation strategy of buffers"
+        );
         assert_eq!(stats.total_objects, expected_obj_count.total());
 
         let mut entries_iter = output::entry::iter_from_counts(
@@ -304,7 +328,21 @@ fn traversals() -> crate::Result {
         assert_eq!(actual_count, expected_count);
         assert_eq!(counts_len, expected_count.total());
         let stats = entries_iter.finalize()?;
-        assert_eq!(stats, expected_entries_outcome);
+        assert_eq!(
+            stats.peak_chunk_bytes > 0,
+            counts_len > 0,
+            "compressed entries are held in memory"
+        );
+        assert_eq!(
+            output::entry::iter_from_counts::Outcome {
+                peak_chunk_bytes: 0,
+                peak_buffer_bytes: 0,
+                peak_cache_bytes: 0,
+                ..stats
+            },
+            expected_entries_outcome,
+            "memory usage depends on the allocation strategy of buffers"
+        );
 
         assert_eq!(
             expected_obj_count.total(),
@@ -403,3 +441,230 @@ fn write_and_verify(
 
     Ok(())
 }
+
+fn entries(
+    db: &Arc<linked::Store>,
+    input_object_expansion: count::objects::ObjectExpansion,
+    take: Option<usize>,
+    options: entry::iter_from_counts::Options,
+) -> crate::Result<Vec<output::Entry>> {
+    entries_from_counts(db, counts(db, input_object_expansion, take)?, options)
+}
+
+fn counts(
+    db: &Arc<linked::Store>,
+    input_object_expansion: count::objects::ObjectExpansion,
+    take: Option<usize>,
+) -> crate::Result<Vec<output::Count>> {
+    let head = hex_to_id("dfcb5e39ac6eb30179808bbab721e8a28ce1b52e");
+    let mut commits = commit::Ancestors::new(Some(head), commit::ancestors::State::default(), {
+        let db = Arc::clone(db);
+        move |oid, buf| db.find_commit_iter(oid, buf, &mut pack::cache::Never).ok()
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+    if let Some(take) = take {
+        commits.truncate(take);
+    }
+    let (counts, _) = output::count::objects(
+        db.clone(),
+        || (pack::cache::Never, pack::cache::object::Never),
+        commits.into_iter().map(Ok::<_, Infallible>),
+        progress::Discard,
+        &AtomicBool::new(false),
+        count::objects::Options {
+            input_object_expansion,
+            thread_limit: Some(1),
+            ..Default::default()
+        },
+    )?;
+    Ok(counts)
+}
+
+fn entries_from_counts(
+    db: &Arc<linked::Store>,
+    counts: Vec<output::Count>,
+    options: entry::iter_from_counts::Options,
+) -> crate::Result<Vec<output::Entry>> {
+    Ok(output::InOrderIter::from(output::entry::iter_from_counts(
+        counts,
+        db.clone(),
+        || pack::cache::Never,
+        progress::Discard,
+        options,
+    ))
+    .collect::<Result<Vec<_>, _>>()?
+    .into_iter()
+    .flatten()
+    .collect())
+}
+
+fn write_validated(
+    entries: Vec<output::Entry>,
+    allow_ofs_delta: bool,
+    allow_thin_pack: bool,
+) -> Result<Vec<u8>, output::bytes::Error<Infallible>> {
+    let mut pack = Vec::new();
+    let num_entries = entries.len() as u32;
+    for res in output::bytes::FromEntriesIter::new(
+        std::iter::once(Ok(entries)),
+        &mut pack,
+        num_entries,
+        pack::data::Version::V2,
+        git_hash::Kind::Sha1,
+    )
+    .validate_deltas(allow_ofs_delta, allow_thin_pack)
+    {
+        res?;
+    }
+    Ok(pack)
+}
+
+#[test]
+fn deltas_refer_to_their_base_by_id_if_offsets_are_not_allowed() -> crate::Result {
+    let db = db(DbKind::DeterministicGeneratedContent)?;
+    let entries_with_ofs_deltas = entries(
+        &db,
+        count::objects::ObjectExpansion::TreeContents,
+        None,
+        Default::default(),
+    )?;
+    let entries = entries(
+        &db,
+        count::objects::ObjectExpansion::TreeContents,
+        None,
+        entry::iter_from_counts::Options {
+            allow_ofs_delta: false,
+            ..Default::default()
+        },
+    )?;
+    let num_ofs_deltas = entries_with_ofs_deltas
+        .iter()
+        .filter(|e| matches!(e.kind, entry::Kind::DeltaRef { .. }))
+        .count();
+    assert_ne!(num_ofs_deltas, 0);
+    assert_eq!(
+        entries
+            .iter()
+            .filter(|e| matches!(e.kind, entry::Kind::DeltaOid { .. }))
+            .count(),
+        num_ofs_deltas,
+        "all deltas of the pack are written as ref-deltas"
+    );
+    assert!(!entries.iter().any(|e| matches!(e.kind, entry::Kind::DeltaRef { .. })));
+
+    assert!(matches!(
+        write_validated(entries_with_ofs_deltas, false, false),
+        Err(output::bytes::Error::OfsDeltaNotAllowed { .. })
+    ));
+
+    let pack = write_validated(entries, false, false)?;
+    let tmp = tempfile::TempDir::new()?;
+    let pack_path = tmp.path().join("new.pack");
+    std::fs::write(&pack_path, pack)?;
+    let out = std::process::Command::new("git")
+        .args(["index-pack", "--strict"])
+        .arg(&pack_path)
+        .output()?;
+    assert!(
+        out.status.success(),
+        "git can resolve deltas against bases in the pack: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    Ok(())
+}
+
+#[test]
+fn deltas_against_objects_outside_of_the_pack_are_rejected_unless_thin_packs_are_allowed() -> crate::Result {
+    let db = db(DbKind::DeterministicGeneratedContent)?;
+    let entries = entries(
+        &db,
+        count::objects::ObjectExpansion::TreeAdditionsComparedToAncestor,
+        Some(1),
+        entry::iter_from_counts::Options {
+            allow_thin_pack: true,
+            ..Default::default()
+        },
+    )?;
+    assert!(entries.iter().any(|e| matches!(e.kind, entry::Kind::DeltaOid { .. })));
+    assert!(write_validated(entries.clone(), true, true).is_ok());
+    assert!(matches!(
+        write_validated(entries, true, false),
+        Err(output::bytes::Error::ExternalBase { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn deterministic_packs_do_not_depend_on_the_order_of_counts() -> crate::Result {
+    let db = db(DbKind::DeterministicGeneratedContent)?;
+    let mut counts = counts(&db, count::objects::ObjectExpansion::TreeContents, None)?;
+    let half = counts.len() / 2;
+    for count in &mut counts[..half] {
+        // pretend these are loose objects, which are ordered by the traversal by default
+        count.entry_pack_location = count::PackLocation::LookedUp(None);
+    }
+    let mut reversed_counts = counts.clone();
+    reversed_counts.reverse();
+
+    let pack_from = |counts, deterministic| -> crate::Result<Vec<u8>> {
+        let entries = entries_from_counts(
+            &db,
+            counts,
+            entry::iter_from_counts::Options {
+                deterministic,
+                thread_limit: Some(4),
+                chunk_size: 10,
+                ..Default::default()
+            },
+        )?;
+        Ok(write_validated(entries, true, false)?)
+    };
+    assert_ne!(
+        pack_from(counts.clone(), false)?,
+        pack_from(reversed_counts.clone(), false)?,
+        "by default, objects are written in the order they were counted in"
+    );
+    let pack = pack_from(counts, true)?;
+    assert_eq!(pack, pack_from(reversed_counts, true)?);
+
+    let tmp = tempfile::TempDir::new()?;
+    let pack_path = tmp.path().join("new.pack");
+    std::fs::write(&pack_path, pack)?;
+    let out = std::process::Command::new("git")
+        .args(["index-pack", "--strict"])
+        .arg(&pack_path)
+        .output()?;
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    Ok(())
+}
+
+#[test]
+fn base_objects_mode_writes_no_deltas() -> crate::Result {
+    let db = db(DbKind::DeterministicGeneratedContent)?;
+    let counts = counts(&db, count::objects::ObjectExpansion::TreeContents, None)?;
+    let num_counts = counts.len();
+    let entries = entries_from_counts(
+        &db,
+        counts,
+        entry::iter_from_counts::Options {
+            mode: entry::iter_from_counts::Mode::BaseObjects,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(entries.len(), num_counts);
+    assert!(
+        entries.iter().all(|e| matches!(e.kind, entry::Kind::Base(_))),
+        "deltas in existing packs are resolved and recompressed"
+    );
+
+    let pack = write_validated(entries, true, false)?;
+    let tmp = tempfile::TempDir::new()?;
+    let pack_path = tmp.path().join("new.pack");
+    std::fs::write(&pack_path, pack)?;
+    let out = std::process::Command::new("git")
+        .args(["index-pack", "--strict"])
+        .arg(&pack_path)
+        .output()?;
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    Ok(())
+}
@@ -67,6 +67,35 @@ mod file {
                 }
                 Ok(())
             }
+
+            #[test]
+            fn lookup_prefix() -> Result<(), Box<dyn std::error::Error>> {
+                let idx = index::File::at(&fixture_path(INDEX_V2))?;
+                let ids: Vec<_> = idx.iter().map(|entry| entry.oid.to_string()).collect();
+                for id in &ids {
+                    for hex_len in git_hash::Prefix::MIN_HEX_LEN..=40 {
+                        let expected = match ids.iter().filter(|other| other[..hex_len] == id[..hex_len]).count() {
+                            1 => Ok(idx
+                                .lookup(git_hash::ObjectId::from_hex(id.as_bytes())?)
+                                .expect("id present")),
+                            _ => Err(()),
+                        };
+                        assert_eq!(
+                            idx.lookup_prefix(git_hash::Prefix::from_hex(&id[..hex_len])?),
+                            Some(expected),
+                            "{} with {} characters",
+                            id,
+                            hex_len
+                        );
+                    }
+                }
+                assert_eq!(
+                    idx.lookup_prefix(git_hash::Prefix::from_hex("ffff")?),
+                    None,
+                    "not in pack"
+                );
+                Ok(())
+            }
         }
 
         mod any {
@@ -224,6 +253,15 @@ mod file {
                         5 => 1,
                         6 => 1,
                     },
+                    objects_per_size_class: b_tree_map! {
+                        6 => 1,
+                        8 => 1,
+                        9 => 9,
+                        10 => 1,
+                        11 => 1,
+                        14 => 12,
+                        15 => 5,
+                    },
                     total_compressed_entries_size: 51753,
                     total_decompressed_entries_size: 103701,
                     total_object_size: 288658,
@@ -232,6 +270,20 @@ mod file {
                     num_tags: 0,
                     num_trees: 15,
                     pack_size: 51875,
+                    largest_objects: vec![
+                        index::traverse::Object {
+                            id: hex_to_id("15926d8d6d17d1cbdf7f03c457e8ff983270f363"),
+                            kind: object::Kind::Blob,
+                            object_size: 30637,
+                            num_deltas: 0,
+                        },
+                        index::traverse::Object {
+                            id: hex_to_id("4c97a057e41159f9767cf8704ed5ae181adf4d8d"),
+                            kind: object::Kind::Tree,
+                            object_size: 22357,
+                            num_deltas: 0,
+                        },
+                    ],
                 },
             ),
             (
@@ -249,6 +301,17 @@ mod file {
                         0 => 64,
                         1 => 3
                     },
+                    objects_per_size_class: b_tree_map! {
+                        7 => 2,
+                        8 => 5,
+                        9 => 15,
+                        10 => 15,
+                        11 => 8,
+                        12 => 14,
+                        13 => 5,
+                        14 => 2,
+                        15 => 1,
+                    },
                     total_compressed_entries_size: 48867,
                     total_decompressed_entries_size: 132823,
                     total_object_size: 140243,
@@ -257,6 +320,20 @@ mod file {
                     num_tags: 0,
                     num_trees: 2,
                     pack_size: 49113,
+                    largest_objects: vec![
+                        index::traverse::Object {
+                            id: hex_to_id("882265122665fe2a876e8b23e3ec6899cd0faa23"),
+                            kind: object::Kind::Blob,
+                            object_size: 29200,
+                            num_deltas: 0,
+                        },
+                        index::traverse::Object {
+                            id: hex_to_id("c04e8baa87f263b426de17556f63351ae254ae95"),
+                            kind: object::Kind::Blob,
+                            object_size: 11141,
+                            num_deltas: 0,
+                        },
+                    ],
                 },
             ),
             (
@@ -275,6 +352,14 @@ mod file {
                         1 => 6,
                         2 => 6,
                     },
+                    objects_per_size_class: b_tree_map! {
+                        6 => 14,
+                        7 => 3,
+                        8 => 15,
+                        9 => 2,
+                        10 => 4,
+                        11 => 4,
+                    },
                     total_compressed_entries_size: 3604,
                     total_decompressed_entries_size: 4997,
                     total_object_size: 12307,
@@ -283,6 +368,20 @@ mod file {
                     num_tags: 0,
                     num_trees: 14,
                     pack_size: 3732,
+                    largest_objects: vec![
+                        index::traverse::Object {
+                            id: hex_to_id("78bed30acc35358406b51120a2e7d29a0cebd6c1"),
+                            kind: object::Kind::Blob,
+                            object_size: 1181,
+                            num_deltas: 0,
+                        },
+                        index::traverse::Object {
+                            id: hex_to_id("531ea8f97a99eee41a7678d94f14d0dba6587c66"),
+                            kind: object::Kind::Blob,
+                            object_size: 1180,
+                            num_deltas: 1,
+                        },
+                    ],
                 },
             ),
         ] {
@@ -293,10 +392,18 @@ mod file {
             assert_eq!(pack.num_objects(), idx.num_objects());
             for algo in ALGORITHMS {
                 for mode in MODES {
+                    let mut objects = Vec::new();
                     assert_eq!(
                         idx.verify_integrity(
                             Some((&pack, *mode, *algo, || cache::Never)),
                             None,
+                            2,
+                            Some(
+                                &mut |entry: &index::Entry, outcome: &pack::data::decode_entry::Outcome| {
+                                    objects.push((entry.to_owned(), outcome.object_size));
+                                    Ok(())
+                                }
+                            ),
                             progress::Discard.into(),
                             Default::default()
                         )
@@ -306,6 +413,16 @@ mod file {
                         algo,
                         mode
                     );
+                    assert_eq!(
+                        objects.iter().map(|(_, object_size)| object_size).sum::<u64>(),
+                        stats.total_object_size,
+                        "each object is passed once"
+                    );
+                    objects.sort();
+                    assert!(
+                        objects.into_iter().map(|(entry, _)| entry).eq(idx.iter()),
+                        "all objects are passed with their index entry"
+                    );
                 }
             }
             let num_objects = stats
@@ -397,6 +514,8 @@ mod file {
                 idx.verify_integrity(
                     None::<(_, _, _, fn() -> cache::Never)>,
                     None,
+                    0,
+                    None,
                     progress::Discard.into(),
                     Default::default()
                 )
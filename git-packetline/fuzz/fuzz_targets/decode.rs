@@ -0,0 +1,29 @@
+#![no_main]
+
+use git_packetline::{decode, PacketLineRef, StreamingPeekableIter};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    for mode in &[decode::Mode::Strict, decode::Mode::Lenient] {
+        let mut anomalies = Vec::new();
+        let mut input = data;
+        while let Ok(decode::Stream::Complete { bytes_consumed, .. }) =
+            decode::streaming_with_mode(input, *mode, &mut anomalies)
+        {
+            input = &input[bytes_consumed..];
+        }
+
+        let mut rd = StreamingPeekableIter::new(data, &[PacketLineRef::Flush]);
+        rd.set_mode(*mode);
+        loop {
+            // Don't name this _; Rust may optimize it out.
+            let _peeked = rd
+                .peek_line()
+                .map(|res| res.map(|line| line.map(|line| line.as_slice().map(<[u8]>::len))));
+            match rd.read_line() {
+                Some(Ok(Ok(_))) => {}
+                _ => break,
+            }
+        }
+    }
+});
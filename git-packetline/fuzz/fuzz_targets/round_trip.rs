@@ -0,0 +1,34 @@
+#![no_main]
+
+use git_packetline::{decode, encode, PacketLineRef, StreamingPeekableIter};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Treat the first byte as line length to turn arbitrary input into an arbitrary amount of non-empty lines.
+    let (chunk_size, data) = match data.split_first() {
+        Some((size, data)) => ((*size as usize).max(1), data),
+        None => return,
+    };
+    let lines: Vec<_> = data.chunks(chunk_size).collect();
+
+    let mut encoded = Vec::new();
+    for line in &lines {
+        encode::data_to_write(line, &mut encoded).expect("lines are small enough");
+    }
+    encode::flush_to_write(&mut encoded).expect("writing to memory works");
+
+    for mode in &[decode::Mode::Strict, decode::Mode::Lenient] {
+        let mut rd = StreamingPeekableIter::new(encoded.as_slice(), &[PacketLineRef::Flush]);
+        rd.set_mode(*mode);
+        for line in &lines {
+            let decoded = rd
+                .read_line()
+                .expect("a line for each encoded one")
+                .expect("no IO error")
+                .expect("valid packet line");
+            assert_eq!(decoded, PacketLineRef::Data(line));
+        }
+        assert!(rd.read_line().is_none(), "the flush line ends the iteration");
+        assert!(rd.anomalies().is_empty(), "well-formed input never has anomalies");
+    }
+});
@@ -47,6 +47,30 @@ pub mod band {
     }
 }
 
+/// Determines how strictly packet lines are decoded.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub enum Mode {
+    /// Fail on every deviation from the packet line format.
+    Strict,
+    /// Tolerate deviations that stock git tolerates as well, and record them as [`Anomaly`] instead.
+    Lenient,
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Mode::Strict
+    }
+}
+
+/// A recoverable deviation from the packet line format as tolerated in [`Mode::Lenient`].
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub enum Anomaly {
+    /// A `0004` line without any data, which is decoded as empty data line.
+    EmptyDataLine,
+}
+
 /// A utility return type to support incremental parsing of packet lines.
 #[derive(Debug, Clone)]
 pub enum Stream<'a> {
@@ -102,6 +126,21 @@ pub fn hex_prefix(four_bytes: &[u8]) -> Result<PacketLineOrWantedSize<'_>, Error
     Ok(PacketLineOrWantedSize::Wanted(wanted_bytes - U16_HEX_BYTES as u16))
 }
 
+/// Like [`hex_prefix()`], but decode according to `mode` and push all tolerated deviations from the format to `anomalies`.
+pub fn hex_prefix_with_mode<'a>(
+    four_bytes: &'a [u8],
+    mode: Mode,
+    anomalies: &mut Vec<Anomaly>,
+) -> Result<PacketLineOrWantedSize<'a>, Error> {
+    match hex_prefix(four_bytes) {
+        Err(Error::DataIsEmpty) if mode == Mode::Lenient => {
+            anomalies.push(Anomaly::EmptyDataLine);
+            Ok(PacketLineOrWantedSize::Wanted(0))
+        }
+        res => res,
+    }
+}
+
 /// Obtain a `PacketLine` from `data` after assuring `data` is small enough to fit.
 pub fn to_data_line(data: &[u8]) -> Result<PacketLineRef<'_>, Error> {
     if data.len() > MAX_LINE_LEN {
@@ -113,13 +152,18 @@ pub fn to_data_line(data: &[u8]) -> Result<PacketLineRef<'_>, Error> {
 
 /// Decode `data` as packet line while reporting whether the data is complete or not using a [`Stream`].
 pub fn streaming(data: &[u8]) -> Result<Stream<'_>, Error> {
+    streaming_with_mode(data, Mode::Strict, &mut Vec::new())
+}
+
+/// Like [`streaming()`], but decode according to `mode` and push all tolerated deviations from the format to `anomalies`.
+pub fn streaming_with_mode<'a>(data: &'a [u8], mode: Mode, anomalies: &mut Vec<Anomaly>) -> Result<Stream<'a>, Error> {
     let data_len = data.len();
     if data_len < U16_HEX_BYTES {
         return Ok(Stream::Incomplete {
             bytes_needed: U16_HEX_BYTES - data_len,
         });
     }
-    let wanted_bytes = match hex_prefix(&data[..U16_HEX_BYTES])? {
+    let wanted_bytes = match hex_prefix_with_mode(&data[..U16_HEX_BYTES], mode, anomalies)? {
         PacketLineOrWantedSize::Wanted(s) => s as usize,
         PacketLineOrWantedSize::Line(line) => {
             return Ok(Stream::Complete {
@@ -148,7 +192,16 @@ pub fn streaming(data: &[u8]) -> Result<Stream<'_>, Error> {
 /// Note that failure also happens if there is not enough data to parse a complete packet line, as opposed to [`streaming()`] decoding
 /// succeeds in that case, stating how much more bytes are required.
 pub fn all_at_once(data: &[u8]) -> Result<PacketLineRef<'_>, Error> {
-    match streaming(data)? {
+    all_at_once_with_mode(data, Mode::Strict, &mut Vec::new())
+}
+
+/// Like [`all_at_once()`], but decode according to `mode` and push all tolerated deviations from the format to `anomalies`.
+pub fn all_at_once_with_mode<'a>(
+    data: &'a [u8],
+    mode: Mode,
+    anomalies: &mut Vec<Anomaly>,
+) -> Result<PacketLineRef<'a>, Error> {
+    match streaming_with_mode(data, mode, anomalies)? {
         Stream::Complete { line, .. } => Ok(line),
         Stream::Incomplete { bytes_needed } => Err(Error::NotEnoughData(bytes_needed)),
     }
@@ -82,6 +82,8 @@ pub struct StreamingPeekableIter<T> {
     #[cfg(any(feature = "blocking-io", feature = "async-io"))]
     buf: Vec<u8>,
     fail_on_err_lines: bool,
+    mode: decode::Mode,
+    anomalies: Vec<decode::Anomaly>,
     delimiters: &'static [PacketLineRef<'static>],
     is_done: bool,
     stopped_at: Option<PacketLineRef<'static>>,
@@ -16,10 +16,12 @@ where
     fn read_line_inner<'a>(
         reader: &mut T,
         buf: &'a mut Vec<u8>,
+        mode: decode::Mode,
+        anomalies: &mut Vec<decode::Anomaly>,
     ) -> io::Result<Result<PacketLineRef<'a>, decode::Error>> {
         let (hex_bytes, data_bytes) = buf.split_at_mut(4);
         reader.read_exact(hex_bytes)?;
-        let num_data_bytes = match decode::hex_prefix(hex_bytes) {
+        let num_data_bytes = match decode::hex_prefix_with_mode(hex_bytes, mode, anomalies) {
             Ok(decode::PacketLineOrWantedSize::Line(line)) => return Ok(Ok(line)),
             Ok(decode::PacketLineOrWantedSize::Wanted(additional_bytes)) => additional_bytes as usize,
             Err(err) => return Ok(Err(err)),
@@ -40,12 +42,14 @@ where
         buf: &'a mut Vec<u8>,
         delimiters: &[PacketLineRef<'static>],
         fail_on_err_lines: bool,
+        mode: decode::Mode,
+        anomalies: &mut Vec<decode::Anomaly>,
         buf_resize: bool,
     ) -> ExhaustiveOutcome<'a> {
         (
             false,
             None,
-            Some(match Self::read_line_inner(reader, buf) {
+            Some(match Self::read_line_inner(reader, buf, mode, anomalies) {
                 Ok(Ok(line)) => {
                     if delimiters.contains(&line) {
                         let stopped_at = delimiters.iter().find(|l| **l == line).cloned();
@@ -72,7 +76,9 @@ where
                     if buf_resize {
                         buf.resize(len, 0);
                     }
-                    Ok(Ok(crate::decode(buf).expect("only valid data here")))
+                    Ok(Ok(
+                        decode::all_at_once_with_mode(buf, mode, &mut Vec::new()).expect("only valid data here")
+                    ))
                 }
                 Ok(Err(err)) => {
                     buf.clear();
@@ -100,7 +106,12 @@ where
         if !self.peek_buf.is_empty() {
             std::mem::swap(&mut self.peek_buf, &mut self.buf);
             self.peek_buf.clear();
-            Some(Ok(Ok(crate::decode(&self.buf).expect("only valid data in peek buf"))))
+            Some(Ok(Ok(decode::all_at_once_with_mode(
+                &self.buf,
+                self.mode,
+                &mut Vec::new(),
+            )
+            .expect("only valid data in peek buf"))))
         } else {
             if self.buf.len() != MAX_LINE_LEN {
                 self.buf.resize(MAX_LINE_LEN, 0);
@@ -110,6 +121,8 @@ where
                 &mut self.buf,
                 self.delimiters,
                 self.fail_on_err_lines,
+                self.mode,
+                &mut self.anomalies,
                 false,
             );
             self.is_done = is_done;
@@ -132,13 +145,20 @@ where
                 &mut self.peek_buf,
                 self.delimiters,
                 self.fail_on_err_lines,
+                self.mode,
+                &mut self.anomalies,
                 true,
             );
             self.is_done = is_done;
             self.stopped_at = stopped_at;
             res
         } else {
-            Some(Ok(Ok(crate::decode(&self.peek_buf).expect("only valid data here"))))
+            Some(Ok(Ok(decode::all_at_once_with_mode(
+                &self.peek_buf,
+                self.mode,
+                &mut Vec::new(),
+            )
+            .expect("only valid data here"))))
         }
     }
 
@@ -1,6 +1,6 @@
 #[cfg(any(feature = "blocking-io", feature = "async-io"))]
 use crate::MAX_LINE_LEN;
-use crate::{PacketLineRef, StreamingPeekableIter, U16_HEX_BYTES};
+use crate::{decode, PacketLineRef, StreamingPeekableIter, U16_HEX_BYTES};
 
 #[cfg(any(feature = "blocking-io", feature = "async-io"))]
 type ExhaustiveOutcome<'a> = (
@@ -42,6 +42,8 @@ impl<T> StreamingPeekableIter<T> {
             peek_buf: Vec::new(),
             delimiters,
             fail_on_err_lines: false,
+            mode: decode::Mode::default(),
+            anomalies: Vec::new(),
             is_done: false,
             stopped_at: None,
         }
@@ -92,11 +94,31 @@ impl<T> StreamingPeekableIter<T> {
         self.fail_on_err_lines = value;
     }
 
+    /// Set the `mode` in which packet lines are decoded, which is [strict][decode::Mode::Strict] by default.
+    ///
+    /// In [lenient][decode::Mode::Lenient] mode, use [`anomalies()`][StreamingPeekableIter::anomalies()] to learn
+    /// which deviations from the packet line format were tolerated.
+    pub fn set_mode(&mut self, mode: decode::Mode) {
+        self.mode = mode;
+    }
+
+    /// Return the mode in which packet lines are decoded.
+    pub fn mode(&self) -> decode::Mode {
+        self.mode
+    }
+
+    /// Return all deviations from the packet line format tolerated so far, in the order they were encountered.
+    pub fn anomalies(&self) -> &[decode::Anomaly] {
+        &self.anomalies
+    }
+
     /// Replace the reader used with the given `read`, resetting all other iteration state as well.
     pub fn replace(&mut self, read: T) -> T {
         let prev = std::mem::replace(&mut self.read, read);
         self.reset();
         self.fail_on_err_lines = false;
+        self.mode = decode::Mode::default();
+        self.anomalies.clear();
         prev
     }
 
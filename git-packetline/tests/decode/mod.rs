@@ -135,6 +135,31 @@ mod streaming {
         assert_err_display(streaming(b"0004"), "Received an invalid empty line");
     }
 
+    #[test]
+    fn lenient_mode_decodes_empty_lines_and_records_them() -> crate::Result {
+        let mut anomalies = Vec::new();
+        assert_complete(
+            decode::streaming_with_mode(b"00040006a\n", decode::Mode::Lenient, &mut anomalies),
+            4,
+            PacketLineRef::Data(b""),
+        )?;
+        assert_eq!(anomalies, vec![decode::Anomaly::EmptyDataLine]);
+        assert_err_display(
+            decode::streaming_with_mode(b"0004", decode::Mode::Strict, &mut anomalies),
+            "Received an invalid empty line",
+        );
+        assert_eq!(anomalies.len(), 1, "nothing is recorded in strict mode");
+        Ok(())
+    }
+
+    #[test]
+    fn lenient_mode_does_not_tolerate_invalid_line_lengths() {
+        assert_err_display(
+            decode::streaming_with_mode(b"0003", decode::Mode::Lenient, &mut Vec::new()),
+            "Received an invalid line of length 3",
+        );
+    }
+
     mod incomplete {
         use git_packetline::decode::{self, streaming, Stream};
 
@@ -4,7 +4,7 @@ pub mod streaming_peek_iter {
     use std::{io, path::PathBuf};
 
     use bstr::ByteSlice;
-    use git_packetline::PacketLineRef;
+    use git_packetline::{decode, PacketLineRef};
 
     fn fixture_path(path: &str) -> PathBuf {
         PathBuf::from("tests/fixtures").join(path)
@@ -135,6 +135,43 @@ pub mod streaming_peek_iter {
         Ok(())
     }
 
+    #[maybe_async::test(feature = "blocking-io", async(feature = "async-io", async_std::test))]
+    async fn lenient_mode_tolerates_empty_data_lines() -> crate::Result {
+        let input = b"0005a00040005b0000";
+        let mut rd = git_packetline::StreamingPeekableIter::new(&input[..], &[PacketLineRef::Flush]);
+        let res = rd.read_line().await;
+        assert_eq!(res.expect("line")??, PacketLineRef::Data(b"a"));
+        let res = rd.read_line().await;
+        assert_eq!(
+            res.expect("line")?.unwrap_err().to_string(),
+            "Received an invalid empty line",
+            "strict mode is the default"
+        );
+
+        let mut rd = git_packetline::StreamingPeekableIter::new(&input[..], &[PacketLineRef::Flush]);
+        rd.set_mode(decode::Mode::Lenient);
+        let res = rd.read_line().await;
+        assert_eq!(res.expect("line")??, PacketLineRef::Data(b"a"));
+        let res = rd.peek_line().await;
+        assert_eq!(res.expect("line")??, PacketLineRef::Data(b""));
+        let res = rd.read_line().await;
+        assert_eq!(
+            res.expect("line")??,
+            PacketLineRef::Data(b""),
+            "peeked lines are decoded leniently too"
+        );
+        let res = rd.read_line().await;
+        assert_eq!(res.expect("line")??, PacketLineRef::Data(b"b"));
+        let res = rd.read_line().await;
+        assert!(res.is_none(), "the flush line ends the iteration");
+        assert_eq!(rd.anomalies(), &[decode::Anomaly::EmptyDataLine]);
+
+        rd.replace(&input[..]);
+        assert_eq!(rd.mode(), decode::Mode::Strict, "a 'replace' also resets the mode");
+        assert!(rd.anomalies().is_empty());
+        Ok(())
+    }
+
     #[maybe_async::test(feature = "blocking-io", async(feature = "async-io", async_std::test))]
     async fn peek() -> crate::Result {
         let bytes = fixture_bytes("v1/fetch/01-many-refs.response");
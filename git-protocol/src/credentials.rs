@@ -101,6 +101,7 @@ pub fn helper(action: Action<'_>) -> Result {
         } else {
             Stdio::null()
         });
+    let trace = git_features::trace::child(&mut cmd);
     let mut child = cmd.spawn()?;
     let mut stdin = child.stdin.take().expect("stdin to be configured");
 
@@ -113,6 +114,7 @@ pub fn helper(action: Action<'_>) -> Result {
     }
 
     let output = child.wait_with_output()?;
+    trace.exit(output.status.code());
     if !output.status.success() {
         return Err(Error::CredentialsHelperFailed(output.status.code()));
     }
@@ -31,7 +31,7 @@ mod with_io {
         /// Only V2
         fn all_argument_prefixes(&self) -> &'static [&'static str] {
             match self {
-                Command::LsRefs => &["symrefs", "peel", "ref-prefix "],
+                Command::LsRefs => &["symrefs", "peel", "ref-prefix ", "unborn"],
                 Command::Fetch => &[
                     "want ", // hex oid
                     "have ", // hex oid
@@ -193,7 +193,7 @@ mod with_io {
                             continue;
                         }
                         match *feature {
-                            "agent" => {}
+                            "agent" | "session-id" => {}
                             _ => panic!("{}: V2 feature/capability {} is not supported", self.as_str(), feature),
                         }
                     }
@@ -0,0 +1,71 @@
+use bstr::{BStr, BString, ByteSlice};
+use git_transport::{client::Capabilities, Protocol};
+
+use crate::fetch::Ref;
+
+/// A symbolic reference as advertised by the server, like `HEAD` pointing to `refs/heads/main`.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Symref {
+    /// The name of the symbolic reference, like `HEAD`.
+    pub name: BString,
+    /// The name of the reference it points to, like `refs/heads/main`.
+    pub target: BString,
+}
+
+/// What was learned about the server when connecting to it, as returned by [`fetch()`][crate::fetch()].
+#[derive(PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outcome {
+    /// The protocol version the server chose to speak.
+    pub protocol: Protocol,
+    /// The value of the server's `agent` capability, like `git/2.33.0`, if it advertised one.
+    pub agent: Option<BString>,
+    /// The value of the server's `object-format` capability, like `sha256`, or `sha1` if it didn't advertise one.
+    pub object_format: BString,
+    /// All symbolic references of the server along with their targets, as far as they were listed, including
+    /// [unborn][Ref::Unborn] ones like `HEAD` of an empty repository.
+    ///
+    /// Note that in protocol V2 these are only known if references were listed with the `symrefs` argument.
+    pub symrefs: Vec<Symref>,
+}
+
+impl Outcome {
+    /// Create a new instance from the `capabilities` advertised by a server speaking `protocol`, along with the symbolic
+    /// references among its `refs`.
+    pub fn new(protocol: Protocol, capabilities: &Capabilities, refs: &[Ref]) -> Self {
+        let value_of = |name: &str| {
+            capabilities
+                .capability(name)
+                .and_then(|c| c.value().map(ToOwned::to_owned))
+        };
+        Outcome {
+            protocol,
+            agent: value_of("agent"),
+            object_format: value_of("object-format").unwrap_or_else(|| "sha1".into()),
+            symrefs: refs
+                .iter()
+                .filter_map(|r| match r {
+                    Ref::Symbolic { path, target, .. } | Ref::Unborn { path, target } => Some(Symref {
+                        name: path.clone(),
+                        target: target.clone(),
+                    }),
+                    Ref::Direct { .. } | Ref::Peeled { .. } => None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Return the kind of hash used by the server for its objects, or `None` if it is not supported by us.
+    pub fn object_hash(&self) -> Option<git_hash::Kind> {
+        match self.object_format.as_bytes() {
+            b"sha1" => Some(git_hash::Kind::Sha1),
+            _ => None,
+        }
+    }
+
+    /// Return the target of the symbolic reference `name`, like `refs/heads/main` for `HEAD`.
+    pub fn symref_target(&self, name: &str) -> Option<&BStr> {
+        self.symrefs.iter().find(|s| s.name == name).map(|s| s.target.as_bstr())
+    }
+}
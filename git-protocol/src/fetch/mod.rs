@@ -10,6 +10,14 @@ pub fn agent() -> (&'static str, Option<&'static str>) {
     ("agent", Some(concat!("git/oxide-", env!("CARGO_PKG_VERSION"))))
 }
 
+/// Returns the [session id][git_features::trace::session_id()] of this process as key-value pair, for use as feature if the
+/// server supports the `session-id` capability.
+///
+/// Like `git` without `transfer.advertiseSID`, it's not sent by default, so delegates have to add it when preparing commands.
+pub fn session_id() -> (&'static str, Option<&'static str>) {
+    ("session-id", Some(git_features::trace::session_id()))
+}
+
 ///
 pub mod delegate;
 #[cfg(any(feature = "async-client", feature = "blocking-client"))]
@@ -19,6 +27,8 @@ pub use delegate::{Action, DelegateBlocking, LsRefsAction};
 mod error;
 pub use error::Error;
 ///
+pub mod handshake;
+///
 pub mod refs;
 pub use refs::Ref;
 ///
@@ -25,7 +25,7 @@ quick_error! {
             display("'{}' could not be parsed. A V1 ref line should be '<hex-hash> <path>'.", line)
         }
         MalformedV2RefLine(line: String) {
-            display("'{}' could not be parsed. A V2 ref line should be '<hex-hash> <path>[ (peeled|symref-target):<value>'.", line)
+            display("'{}' could not be parsed. A V2 ref line should be '<hex-hash> <path>[ (peeled|symref-target):<value>' or 'unborn <path> symref-target:<value>'.", line)
         }
         UnkownAttribute(attribute: String, line: String) {
             display("The ref attribute '{}' is unknown. Found in line '{}'", attribute, line)
@@ -67,16 +67,25 @@ pub enum Ref {
         /// The hash of the object the `target` ref points to.
         object: git_hash::ObjectId,
     },
+    /// A symbolic ref pointing to a `target` ref which doesn't exist yet, as is the case for `HEAD` in an empty repository.
+    Unborn {
+        /// The path at which the symbolic ref is located, like `HEAD`.
+        path: BString,
+        /// The path of the ref the symbolic ref points to, like `refs/heads/main`.
+        target: BString,
+    },
 }
 
 impl Ref {
     /// Provide shared fields referring to the ref itself, namely `(path, object id)`.
     /// In case of peeled refs, the tag object itself is returned as it is what the path refers to.
-    pub fn unpack(&self) -> (&BString, &git_hash::ObjectId) {
+    /// The object id is `None` for [unborn][Ref::Unborn] refs.
+    pub fn unpack(&self) -> (&BString, Option<&git_hash::ObjectId>) {
         match self {
             Ref::Direct { path, object, .. }
             | Ref::Peeled { path, tag: object, .. } // the tag acts as reference
-            | Ref::Symbolic { path, object, .. } => (path, object),
+            | Ref::Symbolic { path, object, .. } => (path, Some(object)),
+            Ref::Unborn { path, .. } => (path, None),
         }
     }
 }
@@ -87,6 +96,23 @@ pub(crate) mod shared {
 
     use crate::fetch::{refs, Ref};
 
+    impl InternalRef {
+        /// Convert this instance into a [`Ref`], or `None` if it is a symbolic ref without target for which no object was advertised.
+        ///
+        /// Symbolic refs advertised in the capabilities but not among the refs point to a ref that doesn't exist yet, like
+        /// `HEAD` in an empty repository.
+        pub(crate) fn into_ref(self) -> Option<Ref> {
+            match self {
+                InternalRef::SymbolicForLookup {
+                    path,
+                    target: Some(target),
+                } => Some(Ref::Unborn { path, target }),
+                InternalRef::SymbolicForLookup { target: None, .. } => None,
+                other => Some(other.into()),
+            }
+        }
+    }
+
     impl From<InternalRef> for Ref {
         fn from(v: InternalRef) -> Self {
             match v {
@@ -192,6 +218,17 @@ pub(crate) mod shared {
         if path.is_empty() {
             return Err(refs::Error::MalformedV1RefLine(trimmed.to_owned()));
         }
+        if path == "capabilities^{}" {
+            // Repositories without refs advertise their capabilities on a line with the null id instead of `HEAD`.
+            let object = git_hash::ObjectId::from_hex(hex_hash.as_bytes())?;
+            return if object.is_null() {
+                Ok(())
+            } else {
+                Err(refs::Error::InvariantViolation(
+                    "Expecting the capabilities^{} placeholder to point to the null id",
+                ))
+            };
+        }
         match path.strip_suffix("^{}") {
             Some(stripped) => {
                 let (previous_path, tag) =
@@ -239,6 +276,19 @@ pub(crate) mod shared {
 
     pub(in crate::fetch::refs) fn parse_v2(line: &str) -> Result<Ref, refs::Error> {
         let trimmed = line.trim_end();
+        if let Some(unborn) = trimmed.strip_prefix("unborn ") {
+            let mut tokens = unborn.splitn(2, ' ');
+            return match (
+                tokens.next(),
+                tokens.next().and_then(|a| a.strip_prefix("symref-target:")),
+            ) {
+                (Some(path), Some(target)) if !path.is_empty() && !target.is_empty() => Ok(Ref::Unborn {
+                    path: path.into(),
+                    target: target.into(),
+                }),
+                _ => Err(refs::Error::MalformedV2RefLine(trimmed.to_owned())),
+            };
+        }
         let mut tokens = trimmed.splitn(3, ' ');
         match (tokens.next(), tokens.next()) {
             (Some(hex_hash), Some(path)) => {
@@ -294,7 +344,7 @@ mod async_io {
     use futures_io::AsyncBufRead;
     use futures_lite::AsyncBufReadExt;
 
-    use crate::fetch::{refs, Ref};
+    use crate::fetch::{refs, refs::shared::InternalRef, Ref};
 
     /// Parse refs from the given input line by line. Protocol V2 is required for this to succeed.
     pub async fn from_v2_refs(in_refs: &mut (dyn AsyncBufRead + Unpin)) -> Result<Vec<Ref>, refs::Error> {
@@ -334,7 +384,7 @@ mod async_io {
             }
             refs::shared::parse_v1(number_of_possible_symbolic_refs_for_lookup, &mut out_refs, &line)?;
         }
-        Ok(out_refs.into_iter().map(Into::into).collect())
+        Ok(out_refs.into_iter().filter_map(InternalRef::into_ref).collect())
     }
 }
 #[cfg(feature = "async-client")]
@@ -344,7 +394,7 @@ pub use async_io::{from_v1_refs_received_as_part_of_handshake_and_capabilities,
 mod blocking_io {
     use std::io;
 
-    use crate::fetch::{refs, Ref};
+    use crate::fetch::{refs, refs::shared::InternalRef, Ref};
 
     /// Parse refs from the given input line by line. Protocol V2 is required for this to succeed.
     pub fn from_v2_refs(in_refs: &mut dyn io::BufRead) -> Result<Vec<Ref>, refs::Error> {
@@ -384,7 +434,7 @@ mod blocking_io {
             }
             refs::shared::parse_v1(number_of_possible_symbolic_refs_for_lookup, &mut out_refs, &line)?;
         }
-        Ok(out_refs.into_iter().map(Into::into).collect())
+        Ok(out_refs.into_iter().filter_map(InternalRef::into_ref).collect())
     }
 }
 #[cfg(feature = "blocking-client")]
@@ -91,6 +91,48 @@ dce0ea858eef7ff61ad345cc5cdac62203fb3c10 refs/tags/git-commitgraph-v0.0.0
     )
 }
 
+#[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+async fn extract_unborn_references_from_v2_refs() {
+    let input = &mut "unborn HEAD symref-target:refs/heads/main\n".as_bytes();
+    let out = refs::from_v2_refs(input).await.expect("no failure on valid input");
+    assert_eq!(
+        out,
+        vec![Ref::Unborn {
+            path: "HEAD".into(),
+            target: "refs/heads/main".into(),
+        }]
+    );
+
+    let input = &mut "unborn HEAD\n".as_bytes();
+    let res = refs::from_v2_refs(input).await;
+    assert!(
+        matches!(res, Err(refs::Error::MalformedV2RefLine(_))),
+        "unborn refs are only useful with their target"
+    );
+}
+
+#[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+async fn extract_references_from_v1_refs_of_empty_repository() {
+    let input = &mut "0000000000000000000000000000000000000000 capabilities^{}".as_bytes();
+    let out = refs::from_v1_refs_received_as_part_of_handshake_and_capabilities(
+        input,
+        Capabilities::from_bytes(b"\0symref=HEAD:refs/heads/main symref=MISSING_NAMESPACE_TARGET:(null)")
+            .expect("valid capabilities")
+            .0
+            .iter(),
+    )
+    .await
+    .expect("no failure from valid input");
+    assert_eq!(
+        out,
+        vec![Ref::Unborn {
+            path: "HEAD".into(),
+            target: "refs/heads/main".into(),
+        }],
+        "symbolic refs without object are unborn, and the placeholder for capabilities is no ref"
+    );
+}
+
 #[test]
 fn extract_symbolic_references_from_capabilities() -> Result<(), client::Error> {
     let caps = client::Capabilities::from_bytes(
@@ -1,6 +1,6 @@
 use std::io;
 
-use git_features::{progress, progress::Progress};
+use git_features::{progress, progress::Progress, trace};
 use git_transport::{
     client,
     client::{SetServiceResponse, TransportV2Ext},
@@ -10,7 +10,7 @@ use maybe_async::maybe_async;
 
 use crate::{
     credentials,
-    fetch::{refs, Action, Arguments, Command, Delegate, Error, LsRefsAction, Response},
+    fetch::{handshake, refs, Action, Arguments, Command, Delegate, Error, LsRefsAction, Response},
 };
 
 /// A way to indicate how to treat the connection underlying the transport, potentially allowing to reuse it.
@@ -49,6 +49,9 @@ impl Default for FetchConnection {
 /// * `progress` is used to emit progress messages.
 ///
 /// _Note_ that depending on the `delegate`, the actual action performed can be `ls-refs`, `clone` or `fetch`.
+///
+/// Returns what was learned about the server during the [handshake][handshake::Outcome], like the `HEAD` reference it
+/// points to or its object format.
 #[maybe_async]
 pub async fn fetch<F, D, T>(
     mut transport: T,
@@ -56,13 +59,14 @@ pub async fn fetch<F, D, T>(
     mut authenticate: F,
     mut progress: impl Progress,
     fetch_mode: FetchConnection,
-) -> Result<(), Error>
+) -> Result<handshake::Outcome, Error>
 where
     F: FnMut(credentials::Action<'_>) -> credentials::Result,
     D: Delegate,
     T: client::Transport,
 {
     let (protocol_version, parsed_refs, capabilities) = {
+        let _region = trace::region("fetch", "handshake");
         progress.init(None, progress::steps());
         progress.set_name("handshake");
         progress.step();
@@ -109,6 +113,16 @@ where
             Err(err) => Err(err),
         }?;
 
+        trace::data("fetch", "protocol", actual_protocol as usize);
+        for (key, name) in &[("server-agent", "agent"), ("server-session-id", "session-id")] {
+            if let Some(value) = capabilities
+                .capability(name)
+                .and_then(|c| c.value().map(ToOwned::to_owned))
+            {
+                trace::data("fetch", key, value);
+            }
+        }
+
         if !supported_versions.is_empty() && !supported_versions.contains(&actual_protocol) {
             return Err(Error::TransportProtocolPolicyViolation {
                 actual_version: actual_protocol,
@@ -144,6 +158,13 @@ where
             let ls_refs = Command::LsRefs;
             let mut ls_features = ls_refs.default_features(protocol_version, &capabilities);
             let mut ls_args = ls_refs.initial_arguments(&ls_features);
+            if capabilities
+                .capability(ls_refs.as_str())
+                .and_then(|cap| cap.supports("unborn"))
+                .unwrap_or_default()
+            {
+                ls_args.push("unborn".into());
+            }
             match delegate.prepare_ls_refs(&capabilities, &mut ls_args, &mut ls_features) {
                 Ok(LsRefsAction::Skip) => Vec::new(),
                 Ok(LsRefsAction::Continue) => {
@@ -156,6 +177,7 @@ where
 
                     progress.step();
                     progress.set_name("list refs");
+                    let _region = trace::region("fetch", "ls-refs");
                     let mut remote_refs = transport
                         .invoke(
                             ls_refs.as_str(),
@@ -176,6 +198,7 @@ where
             }
         }
     };
+    let outcome = handshake::Outcome::new(protocol_version, &capabilities, &parsed_refs);
 
     let fetch = Command::Fetch;
     let mut fetch_features = fetch.default_features(protocol_version, &capabilities);
@@ -184,9 +207,9 @@ where
             return if matches!(protocol_version, git_transport::Protocol::V1)
                 || matches!(fetch_mode, FetchConnection::TerminateOnSuccessfulCompletion)
             {
-                indicate_end_of_interaction(transport).await
+                indicate_end_of_interaction(transport).await.map(|()| outcome)
             } else {
-                Ok(())
+                Ok(outcome)
             };
         }
         Ok(Action::Continue) => {
@@ -206,6 +229,7 @@ where
     'negotiation: loop {
         progress.step();
         progress.set_name(format!("negotiate (round {})", round));
+        let negotiate_region = trace::region("fetch", format_args!("negotiate (round {})", round));
         round += 1;
         let action = delegate.negotiate(&parsed_refs, &mut arguments, previous_response.as_ref())?;
         let mut reader = arguments.send(&mut transport, action == Action::Cancel).await?;
@@ -213,9 +237,11 @@ where
             setup_remote_progress(&mut progress, &mut reader);
         }
         let response = Response::from_line_reader(protocol_version, &mut reader).await?;
+        drop(negotiate_region);
         previous_response = if response.has_pack() {
             progress.step();
             progress.set_name("receiving pack");
+            let _region = trace::region("fetch", "receive pack");
             if !sideband_all {
                 setup_remote_progress(&mut progress, &mut reader);
             }
@@ -233,7 +259,7 @@ where
     {
         indicate_end_of_interaction(transport).await?;
     }
-    Ok(())
+    Ok(outcome)
 }
 
 #[maybe_async]
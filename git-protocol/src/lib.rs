@@ -19,6 +19,16 @@ mod fetch_fn;
 #[cfg(any(feature = "blocking-client", feature = "async-client"))]
 pub use fetch_fn::{fetch, FetchConnection};
 
+///
+#[cfg(feature = "blocking-client")]
+pub mod proc_receive;
+///
+#[cfg(feature = "blocking-client")]
+pub mod push_cert;
+
+///
+pub mod refspec;
+
 mod remote_progress;
 pub use remote_progress::RemoteProgress;
 
@@ -0,0 +1,267 @@
+//! The protocol between `receive-pack` and the `proc-receive` hook, which handles updates to some references in its place.
+//!
+//! It allows servers to implement workflows like the ones of Gerrit or AGit, where a push to `refs/for/main` creates or
+//! updates a review instead of a reference by that name. Which references are handed to the hook is configured with
+//! `receive.procReceiveRefs`, see [`is_handled()`].
+//!
+//! The server [starts the exchange][exchange()] after the `pre-receive` hook succeeded by negotiating the protocol version,
+//! and sends all commands for references handled by the hook, followed by push options if both sides support them.
+//! The hook answers with a [report][Report] per reference, which may name the reference that was actually updated.
+use std::io;
+
+use bstr::{BStr, BString, ByteSlice};
+use git_hash::ObjectId;
+use git_transport::packetline::{self, PacketLineRef, StreamingPeekableIter};
+use quick_error::quick_error;
+
+quick_error! {
+    /// The error returned by [`exchange()`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Io(err: io::Error) {
+            display("Could not communicate with the proc-receive hook")
+            from()
+            source(err)
+        }
+        PacketLine(err: packetline::decode::Error) {
+            display("The proc-receive hook sent an invalid packet line")
+            from()
+            source(err)
+        }
+        UnexpectedEof {
+            display("The proc-receive hook closed the connection before sending a flush packet")
+        }
+        UnsupportedVersion(line: BString) {
+            display("The proc-receive hook doesn't support protocol version 1, it replied with '{}'", line)
+        }
+        InvalidLine(line: BString) {
+            display("The proc-receive hook sent the invalid line '{}'", line)
+        }
+        OptionWithoutReport(line: BString) {
+            display("The proc-receive hook sent '{}' before reporting on a reference", line)
+        }
+        UnknownReference(name: BString) {
+            display("The proc-receive hook reported on '{}' which wasn't sent to it", name)
+        }
+    }
+}
+
+/// The message used for references the hook didn't report on, as used by `git`.
+pub const MISSING_REPORT: &str = "proc-receive failed to report status";
+
+/// A reference update as requested by a client during a push, to be performed by the hook.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Command {
+    /// The id the reference is expected to have, or the null id if it should be created.
+    pub old_id: ObjectId,
+    /// The id the reference should have, or the null id if it should be deleted.
+    pub new_id: ObjectId,
+    /// The full name of the reference, like `refs/for/main`.
+    pub name: BString,
+}
+
+/// The capabilities the server supports, and which the hook may choose from.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy, Default)]
+pub struct Capabilities {
+    /// Push options were sent by the client and can be passed to the hook.
+    pub push_options: bool,
+    /// The client requested all reference updates to succeed or none of them.
+    pub atomic: bool,
+}
+
+/// The answer of the hook to a [`Command`].
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Default)]
+pub struct Report {
+    /// The name of the reference as sent in the [`Command`].
+    pub name: BString,
+    /// The reason for rejecting the update, or `None` if it succeeded.
+    pub rejected: Option<BString>,
+    /// The hook didn't handle the update, and asks `receive-pack` to perform it instead.
+    pub fall_through: bool,
+    /// The name of the reference that was actually updated, if it's not `name`.
+    pub ref_name: Option<BString>,
+    /// The id the updated reference had before, if it differs from the one in the [`Command`].
+    pub old_id: Option<ObjectId>,
+    /// The id the updated reference has now, if it differs from the one in the [`Command`].
+    pub new_id: Option<ObjectId>,
+    /// The update wasn't a fast-forward.
+    pub forced_update: bool,
+}
+
+/// Returns true if `command` should be handed to the `proc-receive` hook according to the `patterns` from
+/// `receive.procReceiveRefs`, in the order they were configured.
+///
+/// Patterns are prefixes of reference names matching whole path components, which can be negated with a leading `!`.
+/// They can be restricted to some kinds of updates with a leading `<modifiers>:`, where the modifiers are `a` for
+/// additions, `m` for modifications and `d` for deletions. Later patterns take precedence over earlier ones.
+pub fn is_handled(patterns: &[impl AsRef<BStr>], command: &Command) -> bool {
+    let kind = if command.old_id.is_null() {
+        b'a'
+    } else if command.new_id.is_null() {
+        b'd'
+    } else {
+        b'm'
+    };
+    for pattern in patterns.iter().rev() {
+        let mut pattern: &[u8] = pattern.as_ref();
+        if let Some((modifiers, rest)) = split_once(pattern, b':') {
+            if !modifiers.contains(&kind) {
+                continue;
+            }
+            pattern = rest;
+        }
+        let (negated, prefix) = match pattern.strip_prefix(b"!") {
+            Some(prefix) => (true, prefix),
+            None => (false, pattern),
+        };
+        let prefix = prefix.trim_end_with(|c| c == '/');
+        let matches = command
+            .name
+            .strip_prefix(prefix)
+            .map_or(false, |rest| rest.is_empty() || rest.starts_with(b"/"));
+        if matches {
+            return !negated;
+        }
+    }
+    false
+}
+
+/// Communicate with a `proc-receive` hook reading from `input` and writing to `out`, usually its standard output and input,
+/// to have it perform the given `commands`.
+///
+/// `push_options` are only sent if the hook and the `capabilities` support them.
+/// There is one report per command the hook answered to, or more if it updated multiple references for one of them, followed
+/// by a rejection with [`MISSING_REPORT`] for each command it didn't answer to.
+///
+/// Note that `out` is flushed, but callers have to close it themselves if the hook expects that.
+pub fn exchange(
+    input: impl io::Read,
+    mut out: impl io::Write,
+    commands: &[Command],
+    push_options: &[BString],
+    capabilities: Capabilities,
+) -> Result<Vec<Report>, Error> {
+    let mut version = b"version=1\0".to_vec();
+    let server_capabilities = [
+        (capabilities.push_options, "push-options"),
+        (capabilities.atomic, "atomic"),
+    ];
+    for (idx, (_, name)) in server_capabilities.iter().filter(|(enabled, _)| *enabled).enumerate() {
+        if idx != 0 {
+            version.push(b' ');
+        }
+        version.extend_from_slice(name.as_bytes());
+    }
+    packetline::encode::data_to_write(&version, &mut out)?;
+    packetline::encode::flush_to_write(&mut out)?;
+    out.flush()?;
+
+    let mut input = StreamingPeekableIter::new(input, &[PacketLineRef::Flush]);
+    let lines = read_section(&mut input)?;
+    let first = lines.first().cloned().unwrap_or_default();
+    let (hook_version, hook_capabilities) = match split_once(&first, 0) {
+        Some((version, capabilities)) => (version, capabilities),
+        None => (first.as_slice(), &b""[..]),
+    };
+    if hook_version != b"version=1" {
+        return Err(Error::UnsupportedVersion(first));
+    }
+    let send_push_options = capabilities.push_options
+        && hook_capabilities
+            .fields_with(|c| c == ' ')
+            .any(|c| c == b"push-options");
+
+    for command in commands {
+        let line = format!("{} {} {}", command.old_id, command.new_id, command.name);
+        packetline::encode::data_to_write(line.as_bytes(), &mut out)?;
+    }
+    packetline::encode::flush_to_write(&mut out)?;
+    if send_push_options {
+        for option in push_options {
+            packetline::encode::data_to_write(option, &mut out)?;
+        }
+        packetline::encode::flush_to_write(&mut out)?;
+    }
+    out.flush()?;
+
+    let mut reports: Vec<Report> = Vec::new();
+    for line in read_section(&mut input)? {
+        let (directive, rest) = split_once(&line, b' ').ok_or_else(|| Error::InvalidLine(line.clone()))?;
+        match directive {
+            b"ok" | b"ng" => {
+                let (name, reason) = match (directive, split_once(rest, b' ')) {
+                    (b"ng", Some((name, reason))) => (name, Some(reason.into())),
+                    (b"ng", None) => (rest, Some("failed".into())),
+                    _ => (rest, None),
+                };
+                if !commands.iter().any(|c| c.name == name) {
+                    return Err(Error::UnknownReference(name.into()));
+                }
+                reports.push(Report {
+                    name: name.into(),
+                    rejected: reason,
+                    ..Default::default()
+                });
+            }
+            b"option" => {
+                let report = reports
+                    .last_mut()
+                    .ok_or_else(|| Error::OptionWithoutReport(line.clone()))?;
+                let (key, value) = match split_once(rest, b' ') {
+                    Some((key, value)) => (key, Some(value)),
+                    None => (rest, None),
+                };
+                let id = |value: Option<&[u8]>| {
+                    value
+                        .and_then(|hex| ObjectId::from_hex(hex).ok())
+                        .ok_or_else(|| Error::InvalidLine(line.clone()))
+                };
+                match (key, value) {
+                    (b"fall-through", None) => report.fall_through = true,
+                    (b"forced-update", None) => report.forced_update = true,
+                    (b"refname", Some(name)) => report.ref_name = Some(name.into()),
+                    (b"old-oid", value) => report.old_id = Some(id(value)?),
+                    (b"new-oid", value) => report.new_id = Some(id(value)?),
+                    _ => return Err(Error::InvalidLine(line.clone())),
+                }
+            }
+            _ => return Err(Error::InvalidLine(line.clone())),
+        }
+    }
+
+    for command in commands {
+        if !reports.iter().any(|r| r.name == command.name) {
+            reports.push(Report {
+                name: command.name.clone(),
+                rejected: Some(MISSING_REPORT.into()),
+                ..Default::default()
+            });
+        }
+    }
+    Ok(reports)
+}
+
+/// Read all lines up to the next flush packet, without their trailing newline.
+fn read_section(input: &mut StreamingPeekableIter<impl io::Read>) -> Result<Vec<BString>, Error> {
+    let mut lines = Vec::new();
+    while let Some(line) = input.read_line() {
+        let line = match line {
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Err(Error::UnexpectedEof),
+            line => line??,
+        };
+        let text = line
+            .as_text()
+            .ok_or_else(|| Error::InvalidLine(format!("{:?}", line).into()))?;
+        lines.push(text.as_bstr().to_owned());
+    }
+    if input.stopped_at() != Some(PacketLineRef::Flush) {
+        return Err(Error::UnexpectedEof);
+    }
+    input.reset();
+    Ok(lines)
+}
+
+fn split_once(input: &[u8], byte: u8) -> Option<(&[u8], &[u8])> {
+    input.find_byte(byte).map(|pos| (&input[..pos], &input[pos + 1..]))
+}
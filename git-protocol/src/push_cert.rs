@@ -0,0 +1,415 @@
+//! Push certificates, which are signed by clients to prove who requested which reference updates, as sent with `git push --signed`.
+//!
+//! Servers advertise support with the `push-cert=<nonce>` capability, and clients answer with a [`Certificate`] that contains
+//! the [`nonce()`] and all commands, [signed][Certificate::sign()] by a callback. Servers [read][Certificate::read_packet_lines()]
+//! and [verify][Certificate::verify()] it, check the nonce with [`nonce::check()`], and store it as blob to make it available
+//! to hooks using the [environment][hook_env()] `git` would set.
+use std::io;
+
+use bstr::{BStr, BString, ByteSlice};
+use git_hash::ObjectId;
+use git_transport::{
+    client::Capabilities,
+    packetline::{self, PacketLineRef, StreamingPeekableIter},
+};
+use quick_error::quick_error;
+
+pub use crate::proc_receive::Command;
+
+quick_error! {
+    /// The error returned by [`Certificate::from_bytes()`] and [`Certificate::read_packet_lines()`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Io(err: io::Error) {
+            display("Could not read the push certificate")
+            from()
+            source(err)
+        }
+        PacketLine(err: packetline::decode::Error) {
+            display("The push certificate was sent with an invalid packet line")
+            from()
+            source(err)
+        }
+        UnexpectedEof {
+            display("The push certificate ended before 'push-cert-end'")
+        }
+        UnsupportedVersion(line: BString) {
+            display("Expected 'certificate version 0.1', got '{}'", line)
+        }
+        InvalidLine(line: BString) {
+            display("The push certificate contains the invalid line '{}'", line)
+        }
+        MissingHeader(name: &'static str) {
+            display("The push certificate lacks the '{}' header", name)
+        }
+    }
+}
+
+const VERSION: &[u8] = b"certificate version 0.1";
+const SIGNATURE_START: &[u8] = b"-----BEGIN ";
+
+/// A push certificate, listing the commands of a push along with information about the pusher.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Default)]
+pub struct Certificate {
+    /// The identity of the pusher along with the time of the push, like `Name <email> 1234567890 +0100`.
+    pub pusher: BString,
+    /// The URL of the repository pushed to, with credentials removed.
+    pub pushee: Option<BString>,
+    /// The nonce provided by the server in the `push-cert` capability.
+    pub nonce: Option<BString>,
+    /// The push options sent along with the push.
+    pub push_options: Vec<BString>,
+    /// The reference updates of the push.
+    pub commands: Vec<Command>,
+    /// The signature of the [`payload()`][Certificate::payload()], or empty if it wasn't signed yet.
+    pub signature: BString,
+}
+
+/// Return the nonce servers want to see in push certificates, or `None` if they don't support signed pushes.
+pub fn nonce(capabilities: &Capabilities) -> Option<BString> {
+    capabilities
+        .capability("push-cert")
+        .and_then(|c| c.value().map(ToOwned::to_owned))
+}
+
+impl Certificate {
+    /// Return the signed portion of the certificate, which is everything except for the signature.
+    pub fn payload(&self) -> Vec<u8> {
+        let mut out = VERSION.to_vec();
+        out.push(b'\n');
+        let mut header = |name: &str, value: &[u8]| {
+            out.extend_from_slice(name.as_bytes());
+            out.push(b' ');
+            out.extend_from_slice(value);
+            out.push(b'\n');
+        };
+        header("pusher", &self.pusher);
+        if let Some(pushee) = &self.pushee {
+            header("pushee", pushee);
+        }
+        if let Some(nonce) = &self.nonce {
+            header("nonce", nonce);
+        }
+        for option in &self.push_options {
+            header("push-option", option);
+        }
+        out.push(b'\n');
+        for command in &self.commands {
+            out.extend_from_slice(format!("{} {} {}\n", command.old_id, command.new_id, command.name).as_bytes());
+        }
+        out
+    }
+
+    /// Set the signature to the one `sign` produces for the [`payload()`][Certificate::payload()], usually a detached
+    /// and armored PGP signature.
+    pub fn sign<E>(&mut self, sign: impl FnOnce(&[u8]) -> Result<BString, E>) -> Result<(), E> {
+        self.signature = sign(&self.payload())?;
+        Ok(())
+    }
+
+    /// Return the complete certificate as stored in the blob given to hooks.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = self.payload();
+        out.extend_from_slice(&self.signature);
+        out
+    }
+
+    /// Parse a complete certificate from `data`, as produced by [`to_bytes()`][Certificate::to_bytes()].
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Error> {
+        let (headers, body) = match data.find(b"\n\n") {
+            Some(pos) => (&data[..pos], &data[pos + 2..]),
+            None => return Err(Error::UnexpectedEof),
+        };
+        let mut headers = headers.lines();
+        match headers.next() {
+            Some(line) if line == VERSION => {}
+            line => return Err(Error::UnsupportedVersion(line.unwrap_or_default().into())),
+        }
+        let mut out = Certificate::default();
+        for line in headers {
+            let (name, value) = line
+                .find_byte(b' ')
+                .map(|pos| (&line[..pos], line[pos + 1..].into()))
+                .ok_or_else(|| Error::InvalidLine(line.into()))?;
+            match name {
+                b"pusher" => out.pusher = value,
+                b"pushee" => out.pushee = Some(value),
+                b"nonce" => out.nonce = Some(value),
+                b"push-option" => out.push_options.push(value),
+                _ => return Err(Error::InvalidLine(line.into())),
+            }
+        }
+        if out.pusher.is_empty() {
+            return Err(Error::MissingHeader("pusher"));
+        }
+
+        let signature_start = if body.starts_with(SIGNATURE_START) {
+            0
+        } else {
+            body.find([b"\n", SIGNATURE_START].concat())
+                .map_or(body.len(), |pos| pos + 1)
+        };
+        for line in body[..signature_start].lines() {
+            out.commands.push(parse_command(line)?);
+        }
+        out.signature = body[signature_start..].into();
+        Ok(out)
+    }
+
+    /// Write this certificate as packet lines to `out` like a client would during a push, announcing the
+    /// given `capabilities`.
+    pub fn write_packet_lines(&self, capabilities: &[u8], mut out: impl io::Write) -> io::Result<()> {
+        let mut first = b"push-cert\0".to_vec();
+        first.extend_from_slice(capabilities);
+        packetline::encode::data_to_write(&first, &mut out)?;
+        for line in self.to_bytes().lines_with_terminator() {
+            packetline::encode::data_to_write(line, &mut out)?;
+        }
+        packetline::encode::data_to_write(b"push-cert-end\n", &mut out)?;
+        Ok(())
+    }
+
+    /// Read a certificate from packet lines in `input` like a server would during a push, returning the capabilities
+    /// sent by the client along with it.
+    pub fn read_packet_lines(input: &mut StreamingPeekableIter<impl io::Read>) -> Result<(BString, Self), Error> {
+        let mut capabilities = None;
+        let mut data = Vec::new();
+        loop {
+            let line = match input.read_line() {
+                Some(Ok(line)) => line?,
+                Some(Err(err)) if err.kind() != io::ErrorKind::UnexpectedEof => return Err(err.into()),
+                Some(Err(_)) | None => return Err(Error::UnexpectedEof),
+            };
+            let line = match line {
+                PacketLineRef::Data(line) => line,
+                other => return Err(Error::InvalidLine(format!("{:?}", other).into())),
+            };
+            match capabilities {
+                None => {
+                    let caps = line
+                        .strip_prefix(b"push-cert\0")
+                        .ok_or_else(|| Error::InvalidLine(line.into()))?;
+                    capabilities = Some(caps.trim_end_with(|c| c == '\n').as_bstr().to_owned());
+                }
+                Some(_) if line == b"push-cert-end\n" || line == b"push-cert-end" => break,
+                Some(_) => data.extend_from_slice(line),
+            }
+        }
+        Ok((capabilities.expect("set on first line"), Self::from_bytes(&data)?))
+    }
+
+    /// Verify the signature of this certificate with `verify`, which receives the payload and the signature and is only
+    /// called if there is a signature.
+    pub fn verify(&self, verify: impl FnOnce(&[u8], &[u8]) -> Verification) -> Verification {
+        if self.signature.is_empty() {
+            Verification::default()
+        } else {
+            verify(&self.payload(), &self.signature)
+        }
+    }
+}
+
+fn parse_command(line: &[u8]) -> Result<Command, Error> {
+    let invalid = || Error::InvalidLine(line.into());
+    let mut tokens = line.splitn(3, |b| *b == b' ');
+    let mut id = || {
+        tokens
+            .next()
+            .and_then(|hex| ObjectId::from_hex(hex).ok())
+            .ok_or_else(invalid)
+    };
+    let (old_id, new_id) = (id()?, id()?);
+    let name = tokens.next().filter(|n| !n.is_empty()).ok_or_else(invalid)?;
+    Ok(Command {
+        old_id,
+        new_id,
+        name: name.into(),
+    })
+}
+
+/// The state of a signature as determined by [`Certificate::verify()`].
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+pub enum SignatureStatus {
+    /// The signature is good and valid.
+    Good,
+    /// The signature is bad.
+    Bad,
+    /// The signature is good, but the validity of the key is unknown.
+    UnknownValidity,
+    /// The signature is good, but has expired.
+    Expired,
+    /// The signature is good, but was made by an expired key.
+    ExpiredKey,
+    /// The signature is good, but was made by a revoked key.
+    RevokedKey,
+    /// The signature can't be checked, for instance because the key is missing.
+    CannotCheck,
+    /// There is no signature.
+    Missing,
+}
+
+impl SignatureStatus {
+    /// The letter `git` uses for this status, as in `GIT_PUSH_CERT_STATUS` and the `%G?` format.
+    pub fn as_char(&self) -> char {
+        match self {
+            SignatureStatus::Good => 'G',
+            SignatureStatus::Bad => 'B',
+            SignatureStatus::UnknownValidity => 'U',
+            SignatureStatus::Expired => 'X',
+            SignatureStatus::ExpiredKey => 'Y',
+            SignatureStatus::RevokedKey => 'R',
+            SignatureStatus::CannotCheck => 'E',
+            SignatureStatus::Missing => 'N',
+        }
+    }
+}
+
+/// The outcome of verifying the signature of a [`Certificate`].
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Verification {
+    /// The state of the signature.
+    pub status: SignatureStatus,
+    /// The identity of the signer, if known.
+    pub signer: Option<BString>,
+    /// The fingerprint or id of the signing key, if known.
+    pub key: Option<BString>,
+}
+
+impl Default for Verification {
+    fn default() -> Self {
+        Verification {
+            status: SignatureStatus::Missing,
+            signer: None,
+            key: None,
+        }
+    }
+}
+
+///
+pub mod nonce {
+    use bstr::{BStr, BString, ByteSlice};
+
+    /// The result of [`check()`].
+    #[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+    pub enum Status {
+        /// The server didn't ask for a nonce.
+        Unsolicited,
+        /// The certificate doesn't have a nonce even though the server asked for one.
+        Missing,
+        /// The nonce wasn't issued by the server.
+        Bad,
+        /// The nonce is the one the server asked for, or a recent enough one it issued earlier.
+        Ok,
+        /// The nonce was issued by the server, but the given amount of seconds before or after the one it asked for,
+        /// which is more than the allowed slop.
+        Slop(i64),
+    }
+
+    impl Status {
+        /// The name `git` uses for this status in `GIT_PUSH_CERT_NONCE_STATUS`.
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                Status::Unsolicited => "UNSOLICITED",
+                Status::Missing => "MISSING",
+                Status::Bad => "BAD",
+                Status::Ok => "OK",
+                Status::Slop(_) => "SLOP",
+            }
+        }
+    }
+
+    /// Generate the nonce to advertise in the `push-cert` capability for the repository at `path` at `timestamp` in seconds
+    /// since epoch, using the secret `seed` configured in `receive.certNonceSeed`.
+    ///
+    /// It's `<timestamp>-<hmac>`, so nonces issued earlier can be verified without keeping them.
+    pub fn generate(seed: &[u8], path: &BStr, timestamp: u64) -> BString {
+        let mut message = path.to_vec();
+        message.extend_from_slice(format!(":{}", timestamp).as_bytes());
+        let mac = git_hash::ObjectId::from(super::hmac_sha1(seed, &message));
+        format!("{}-{}", timestamp, mac).into()
+    }
+
+    /// Check the `received` nonce of a certificate against the one the server `sent`, as [generated][generate()] with `seed`
+    /// and `path`.
+    ///
+    /// If `stateless` is true, like with the HTTP transport, the certificate may have a nonce issued in an earlier request,
+    /// which is accepted if it was issued at most `slop_limit` seconds before or after the one that was sent.
+    /// Otherwise the nonce must be exactly the one that was sent.
+    pub fn check(
+        seed: &[u8],
+        path: &BStr,
+        sent: Option<&BStr>,
+        received: Option<&BStr>,
+        stateless: bool,
+        slop_limit: u64,
+    ) -> Status {
+        let (sent, received) = match (sent, received) {
+            (None, _) => return Status::Unsolicited,
+            (Some(_), None) => return Status::Missing,
+            (Some(sent), Some(received)) if sent == received => return Status::Ok,
+            (Some(_), Some(_)) if !stateless => return Status::Bad,
+            (Some(sent), Some(received)) => (sent, received),
+        };
+        let timestamp = |nonce: &BStr| {
+            nonce
+                .find_byte(b'-')
+                .and_then(|pos| nonce[..pos].to_str().ok()?.parse::<u64>().ok())
+        };
+        let received_timestamp = match timestamp(received) {
+            Some(ts) if generate(seed, path, ts) == received => ts,
+            _ => return Status::Bad,
+        };
+        let slop = timestamp(sent).unwrap_or(received_timestamp) as i64 - received_timestamp as i64;
+        if slop_limit != 0 && slop.unsigned_abs() <= slop_limit {
+            Status::Ok
+        } else {
+            Status::Slop(slop)
+        }
+    }
+}
+
+/// Return the environment variables `git` sets for hooks when a push came with a certificate, stored as blob with `id`.
+///
+/// `verification` is the result of [`Certificate::verify()`], and `nonce` is the one the server sent along with the
+/// result of [`nonce::check()`], if it sent one.
+pub fn hook_env(
+    id: &git_hash::oid,
+    verification: &Verification,
+    nonce: Option<(&BStr, nonce::Status)>,
+) -> Vec<(&'static str, BString)> {
+    let mut out = vec![
+        ("GIT_PUSH_CERT", id.to_string().into()),
+        ("GIT_PUSH_CERT_SIGNER", verification.signer.clone().unwrap_or_default()),
+        ("GIT_PUSH_CERT_KEY", verification.key.clone().unwrap_or_default()),
+        ("GIT_PUSH_CERT_STATUS", verification.status.as_char().to_string().into()),
+    ];
+    if let Some((nonce, status)) = nonce {
+        out.push(("GIT_PUSH_CERT_NONCE", nonce.to_owned()));
+        out.push(("GIT_PUSH_CERT_NONCE_STATUS", status.as_str().into()));
+        if let nonce::Status::Slop(slop) = status {
+            out.push(("GIT_PUSH_CERT_NONCE_SLOP", slop.to_string().into()));
+        }
+    }
+    out
+}
+
+fn hmac_sha1(key: &[u8], message: &[u8]) -> [u8; 20] {
+    const BLOCK_SIZE: usize = 64;
+    let sha1 = |parts: &[&[u8]]| {
+        let mut hash = git_features::hash::Sha1::default();
+        for part in parts {
+            hash.update(part);
+        }
+        hash.digest()
+    };
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..20].copy_from_slice(&sha1(&[key]));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.iter().map(|b| b ^ byte).collect::<Vec<_>>();
+    let inner = sha1(&[&pad(0x36), message]);
+    sha1(&[&pad(0x5c), &inner])
+}
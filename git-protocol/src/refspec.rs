@@ -0,0 +1,268 @@
+//! Parse fetch refspecs like `+refs/heads/*:refs/remotes/origin/*` and [match][RefSpec::matches()] them against the references of
+//! a remote to learn which local references they would [map][map()] to.
+//!
+//! Sources and destinations may contain a single `*` each, which matches any amount of characters including `/`. Sources which
+//! aren't full reference names, like `main`, match the remote reference they abbreviate the same way `git` resolves names, so
+//! `main` matches `refs/heads/main` unless there is a `refs/tags/main`. Refspecs starting with `^` are negative and exclude all
+//! references they match from being mapped by the others.
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+use quick_error::quick_error;
+
+quick_error! {
+    /// The error returned by [`RefSpec::from_bytes()`].
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        Empty {
+            display("A refspec must not be empty")
+        }
+        NegativeWithDestination {
+            display("Negative refspecs must not have a destination")
+        }
+        NegativeForced {
+            display("Negative refspecs can't be forced")
+        }
+        NegativeEmpty {
+            display("Negative refspecs must have a source")
+        }
+        TooManyColons {
+            display("A refspec must have at most one ':' to separate its source from its destination")
+        }
+        TooManyWildcards(side: BString) {
+            display("'{}' may contain at most one '*'", side)
+        }
+        PatternMismatch {
+            display("Either both source and destination are patterns or none of them")
+        }
+        InvalidName(name: BString, err: git_validate::reference::name::Error) {
+            display("'{}' isn't a valid reference name", name)
+            source(err)
+        }
+    }
+}
+
+/// The way a [`RefSpec`] is applied.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub enum Mode {
+    /// Destinations may only be updated if the change is a fast-forward.
+    Normal,
+    /// Destinations may be updated even if the change isn't a fast-forward, as indicated by a leading `+`.
+    Force,
+    /// Matching references are excluded from being mapped by other refspecs, as indicated by a leading `^`.
+    Negative,
+}
+
+/// A refspec as used to fetch references from a remote and to store them locally.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct RefSpec {
+    /// How this refspec is applied.
+    pub mode: Mode,
+    /// The name or pattern of the remote references to match, which is `HEAD` if the refspec starts with `:`.
+    pub source: BString,
+    /// The name or pattern of the local references to update, or `None` if matching references shouldn't be stored.
+    pub destination: Option<BString>,
+}
+
+/// A remote reference matched by a [`RefSpec`] passed to [`map()`].
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mapping {
+    /// The full name of the remote reference.
+    pub remote: BString,
+    /// The full name of the local reference to update, or `None` if it shouldn't be stored.
+    pub local: Option<BString>,
+    /// The index of the refspec which matched the remote reference.
+    pub spec_index: usize,
+}
+
+/// The ways `git` tries to expand an abbreviated reference name, in order of precedence.
+const RULES: &[(&str, &str)] = &[
+    ("", ""),
+    ("refs/", ""),
+    ("refs/tags/", ""),
+    ("refs/heads/", ""),
+    ("refs/remotes/", ""),
+    ("refs/remotes/", "/HEAD"),
+];
+
+impl RefSpec {
+    /// Parse `spec` like `+refs/heads/*:refs/remotes/origin/*`, `refs/tags/v1.0` or `^refs/heads/wip/*`.
+    pub fn from_bytes(spec: &BStr) -> Result<Self, Error> {
+        let (mode, spec) = match spec.split_first() {
+            None => return Err(Error::Empty),
+            Some((b'+', rest)) if rest.starts_with(b"^") => return Err(Error::NegativeForced),
+            Some((b'+', rest)) => (Mode::Force, rest),
+            Some((b'^', rest)) => (Mode::Negative, rest),
+            Some(_) => (Mode::Normal, spec.as_bytes()),
+        };
+        let mut sides = spec.splitn(3, |b| *b == b':');
+        let source = sides.next().expect("at least one side");
+        let destination = sides.next();
+        if sides.next().is_some() {
+            return Err(Error::TooManyColons);
+        }
+        if mode == Mode::Negative {
+            if destination.is_some() {
+                return Err(Error::NegativeWithDestination);
+            }
+            if source.is_empty() {
+                return Err(Error::NegativeEmpty);
+            }
+        }
+        if destination.is_none() && source.is_empty() {
+            return Err(Error::Empty);
+        }
+        let source: BString = if source.is_empty() {
+            "HEAD".into()
+        } else {
+            source.into()
+        };
+        let destination = destination.filter(|dst| !dst.is_empty()).map(BString::from);
+        validate(source.as_ref())?;
+        if let Some(destination) = &destination {
+            validate(destination.as_ref())?;
+            if source.contains(&b'*') != destination.contains(&b'*') {
+                return Err(Error::PatternMismatch);
+            }
+        }
+        Ok(RefSpec {
+            mode,
+            source,
+            destination,
+        })
+    }
+
+    /// Return true if the source of this refspec contains a `*` to match multiple references.
+    pub fn is_pattern(&self) -> bool {
+        self.source.contains(&b'*')
+    }
+
+    /// Return true if the source of this refspec matches the full reference name `name`, either as pattern or as name it
+    /// abbreviates.
+    pub fn matches(&self, name: &BStr) -> bool {
+        if self.is_pattern() {
+            self.expand(name).is_some()
+        } else {
+            self.rank(name).is_some()
+        }
+    }
+
+    /// Return the name of the local reference the remote reference `name` maps to, or `None` if this refspec doesn't
+    /// [match][RefSpec::matches()] it or has no destination.
+    pub fn destination_of(&self, name: &BStr) -> Option<BString> {
+        let destination = self.destination.as_ref()?;
+        if !self.is_pattern() {
+            return self.rank(name).map(|_| destination.clone());
+        }
+        let matched = self.expand(name)?;
+        let star = destination
+            .find_byte(b'*')
+            .expect("destinations of patterns are patterns");
+        let mut out = BString::from(&destination[..star]);
+        out.push_str(matched);
+        out.push_str(&destination[star + 1..]);
+        Some(out)
+    }
+
+    /// Return the prefixes of all reference names this refspec may match, suitable as `ref-prefix` arguments when listing
+    /// references with protocol V2. Negative refspecs don't have prefixes as they only exclude references.
+    pub fn prefixes(&self) -> Vec<BString> {
+        match self.mode {
+            Mode::Negative => Vec::new(),
+            _ if self.is_pattern() => vec![self.source[..self.source.find_byte(b'*').expect("pattern")].into()],
+            _ => RULES
+                .iter()
+                .map(|(prefix, suffix)| format!("{}{}{}", prefix, self.source, suffix).into())
+                .collect(),
+        }
+    }
+
+    /// Return the part of `name` matched by the `*` of our source pattern.
+    fn expand<'a>(&self, name: &'a BStr) -> Option<&'a BStr> {
+        let star = self.source.find_byte(b'*')?;
+        let (prefix, suffix) = (&self.source[..star], &self.source[star + 1..]);
+        (name.len() >= prefix.len() + suffix.len() && name.starts_with(prefix) && name.ends_with(suffix))
+            .then(|| name[prefix.len()..name.len() - suffix.len()].as_bstr())
+    }
+
+    /// Return the precedence of the rule by which our source abbreviates `name`, lower is better.
+    fn rank(&self, name: &BStr) -> Option<usize> {
+        RULES.iter().position(|(prefix, suffix)| {
+            name.len() == prefix.len() + self.source.len() + suffix.len()
+                && name.starts_with(prefix.as_bytes())
+                && name.ends_with(suffix.as_bytes())
+                && name[prefix.len()..name.len() - suffix.len()] == self.source
+        })
+    }
+}
+
+impl std::fmt::Display for RefSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.mode {
+            Mode::Normal => {}
+            Mode::Force => f.write_str("+")?,
+            Mode::Negative => f.write_str("^")?,
+        }
+        write!(f, "{}", self.source)?;
+        if let Some(destination) = &self.destination {
+            write!(f, ":{}", destination)?;
+        }
+        Ok(())
+    }
+}
+
+/// Map the full names of remote references in `names` through `specs` the way `git fetch` does, and return all remote
+/// references matched by them in the order of `specs` and `names`.
+///
+/// Patterns map all references they match, whereas refspecs with a name as source map only the reference they abbreviate
+/// best, as `git` would resolve it. References matched by any [negative][Mode::Negative] refspec aren't mapped. Identical
+/// mappings are only returned once, but multiple remote references may still map to the same local reference.
+pub fn map<'a>(specs: &[RefSpec], names: impl IntoIterator<Item = &'a BStr>) -> Vec<Mapping> {
+    let names: Vec<_> = names
+        .into_iter()
+        .filter(|name| {
+            !specs
+                .iter()
+                .any(|spec| spec.mode == Mode::Negative && spec.matches(name))
+        })
+        .collect();
+    let mut out: Vec<Mapping> = Vec::new();
+    for (spec_index, spec) in specs.iter().enumerate().filter(|(_, s)| s.mode != Mode::Negative) {
+        let matched: Vec<&BStr> = if spec.is_pattern() {
+            names.iter().copied().filter(|name| spec.matches(name)).collect()
+        } else {
+            names
+                .iter()
+                .copied()
+                .filter_map(|name| spec.rank(name).map(|rank| (rank, name)))
+                .min_by_key(|(rank, _)| *rank)
+                .map(|(_, name)| name)
+                .into_iter()
+                .collect()
+        };
+        for name in matched {
+            let local = spec.destination_of(name);
+            if out.iter().any(|m| m.remote == name && m.local == local) {
+                continue;
+            }
+            out.push(Mapping {
+                remote: name.into(),
+                local,
+                spec_index,
+            });
+        }
+    }
+    out
+}
+
+fn validate(name: &BStr) -> Result<(), Error> {
+    if name.iter().filter(|b| **b == b'*').count() > 1 {
+        return Err(Error::TooManyWildcards(name.into()));
+    }
+    let without_wildcard = name.replace("*", "x");
+    git_validate::reference::name_partial(without_wildcard.as_bstr())
+        .map(|_| ())
+        .map_err(|err| Error::InvalidName(name.into(), err))
+}
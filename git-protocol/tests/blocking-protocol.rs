@@ -7,4 +7,7 @@ pub fn fixture_bytes(path: &str) -> Vec<u8> {
 
 mod credentials;
 mod fetch;
+mod proc_receive;
+mod push_cert;
+mod refspec;
 mod remote_progress;
@@ -4,8 +4,6 @@ use bstr::{BString, ByteSlice};
 use git_protocol::fetch::{self, Action, Arguments, LsRefsAction, Ref, Response};
 use git_transport::client::Capabilities;
 
-use crate::fixture_bytes;
-
 #[cfg(feature = "blocking-client")]
 type Cursor = std::io::Cursor<Vec<u8>>;
 #[cfg(feature = "async-client")]
@@ -36,8 +34,8 @@ impl fetch::DelegateBlocking for CloneDelegate {
         arguments: &mut Arguments,
         _previous_response: Option<&Response>,
     ) -> io::Result<Action> {
-        for r in refs {
-            arguments.want(r.unpack().1);
+        for id in refs.iter().filter_map(|r| r.unpack().1) {
+            arguments.want(id);
         }
         Ok(Action::Cancel)
     }
@@ -93,6 +91,7 @@ impl fetch::DelegateBlocking for CloneRefInWantDelegate {
 pub struct LsRemoteDelegate {
     refs: Vec<fetch::Ref>,
     abort_with: Option<std::io::Error>,
+    send_session_id: bool,
 }
 
 impl fetch::DelegateBlocking for LsRemoteDelegate {
@@ -101,10 +100,13 @@ impl fetch::DelegateBlocking for LsRemoteDelegate {
     }
     fn prepare_ls_refs(
         &mut self,
-        _server: &Capabilities,
+        server: &Capabilities,
         _arguments: &mut Vec<BString>,
-        _features: &mut Vec<(&str, Option<&str>)>,
+        features: &mut Vec<(&str, Option<&str>)>,
     ) -> std::io::Result<LsRefsAction> {
+        if self.send_session_id && server.contains("session-id") {
+            features.push(fetch::session_id());
+        }
         match self.abort_with.take() {
             Some(err) => Err(err),
             None => Ok(LsRefsAction::Continue),
@@ -249,6 +251,16 @@ mod async_io {
     }
 }
 
+/// The scripted repository whose clone is served by [`v1_clone_response()`].
+pub fn clone_repo() -> std::path::PathBuf {
+    git_testfixture::scripted_repo_read_only("make_clone_repo.txt").expect("valid script")
+}
+
+/// What a server using protocol V1 sends when the [repository][clone_repo()] is cloned.
+pub fn v1_clone_response() -> Vec<u8> {
+    git_testfixture::response::v1_clone(&clone_repo()).expect("readable repository")
+}
+
 pub fn oid(hex_sha: &str) -> git_hash::ObjectId {
     git_hash::ObjectId::from_hex(hex_sha.as_bytes()).expect("valid input")
 }
@@ -256,11 +268,10 @@ pub fn oid(hex_sha: &str) -> git_hash::ObjectId {
 #[cfg(feature = "async-client")]
 pub fn transport<W: futures_io::AsyncWrite + Unpin>(
     out: W,
-    path: &str,
+    response: Vec<u8>,
     desired_version: git_transport::Protocol,
     mode: git_transport::client::git::ConnectMode,
 ) -> git_transport::client::git::Connection<Cursor, W> {
-    let response = fixture_bytes(path);
     git_transport::client::git::Connection::new(
         Cursor::new(response),
         out,
@@ -274,11 +285,10 @@ pub fn transport<W: futures_io::AsyncWrite + Unpin>(
 #[cfg(feature = "blocking-client")]
 pub fn transport<W: std::io::Write>(
     out: W,
-    path: &str,
+    response: Vec<u8>,
     version: git_transport::Protocol,
     mode: git_transport::client::git::ConnectMode,
 ) -> git_transport::client::git::Connection<Cursor, W> {
-    let response = fixture_bytes(path);
     git_transport::client::git::Connection::new(
         Cursor::new(response),
         out,
@@ -3,7 +3,7 @@ use git_features::progress;
 use git_protocol::{fetch, FetchConnection};
 use git_transport::Protocol;
 
-use crate::fetch::{oid, transport, CloneDelegate, LsRemoteDelegate};
+use crate::fetch::{clone_repo, oid, transport, v1_clone_response, CloneDelegate, LsRemoteDelegate};
 
 #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
 async fn clone() -> crate::Result {
@@ -12,7 +12,7 @@ async fn clone() -> crate::Result {
     git_protocol::fetch(
         transport(
             out,
-            "v1/clone.response",
+            v1_clone_response(),
             Protocol::V1,
             git_transport::client::git::ConnectMode::Daemon,
         ),
@@ -22,7 +22,11 @@ async fn clone() -> crate::Result {
         FetchConnection::TerminateOnSuccessfulCompletion,
     )
     .await?;
-    assert_eq!(dlg.pack_bytes, 876, "It be able to read pack bytes");
+    assert_eq!(
+        dlg.pack_bytes,
+        git_testfixture::response::pack(&clone_repo())?.len(),
+        "It be able to read pack bytes"
+    );
     Ok(())
 }
 
@@ -32,11 +36,11 @@ async fn ls_remote() -> crate::Result {
     let mut delegate = LsRemoteDelegate::default();
     let mut transport = transport(
         out,
-        "v1/clone.response",
+        v1_clone_response(),
         Protocol::V1,
         git_transport::client::git::ConnectMode::Daemon,
     );
-    git_protocol::fetch(
+    let outcome = git_protocol::fetch(
         &mut transport,
         &mut delegate,
         git_protocol::credentials::helper,
@@ -45,17 +49,36 @@ async fn ls_remote() -> crate::Result {
     )
     .await?;
 
+    assert_eq!(
+        outcome,
+        fetch::handshake::Outcome {
+            protocol: Protocol::V1,
+            agent: Some("git-testfixture".into()),
+            object_format: "sha1".into(),
+            symrefs: vec![fetch::handshake::Symref {
+                name: "HEAD".into(),
+                target: "refs/heads/main".into()
+            }]
+        }
+    );
+    assert_eq!(outcome.object_hash(), Some(git_hash::Kind::Sha1));
+    assert_eq!(
+        outcome.symref_target("HEAD"),
+        Some("refs/heads/main".as_bytes().as_bstr())
+    );
+
+    let main = oid(std::fs::read_to_string(clone_repo().join("refs/heads/main"))?.trim());
     assert_eq!(
         delegate.refs,
         vec![
             fetch::Ref::Symbolic {
                 path: "HEAD".into(),
-                object: oid("808e50d724f604f69ab93c6da2919c014667bedb"),
-                target: "refs/heads/master".into()
+                object: main,
+                target: "refs/heads/main".into()
             },
             fetch::Ref::Direct {
-                path: "refs/heads/master".into(),
-                object: oid("808e50d724f604f69ab93c6da2919c014667bedb")
+                path: "refs/heads/main".into(),
+                object: main
             }
         ]
     );
@@ -75,7 +98,7 @@ async fn ls_remote_handshake_failure_due_to_downgrade() -> crate::Result {
     let err = match git_protocol::fetch(
         transport(
             out,
-            "v1/clone.response",
+            v1_clone_response(),
             Protocol::V2,
             git_transport::client::git::ConnectMode::Process,
         ),
@@ -3,7 +3,10 @@ use git_features::progress;
 use git_protocol::{fetch, FetchConnection};
 use git_transport::Protocol;
 
-use crate::fetch::{oid, transport, CloneDelegate, CloneRefInWantDelegate, LsRemoteDelegate};
+use crate::{
+    fetch::{oid, transport, CloneDelegate, CloneRefInWantDelegate, LsRemoteDelegate},
+    fixture_bytes,
+};
 
 #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
 async fn clone_abort_prep() -> crate::Result {
@@ -14,7 +17,7 @@ async fn clone_abort_prep() -> crate::Result {
     };
     let mut transport = transport(
         out,
-        "v2/clone.response",
+        fixture_bytes("v2/clone.response"),
         Protocol::V2,
         git_transport::client::git::ConnectMode::Daemon,
     );
@@ -58,11 +61,11 @@ async fn ls_remote() -> crate::Result {
     let mut delegate = LsRemoteDelegate::default();
     let mut transport = transport(
         out,
-        "v2/clone.response",
+        fixture_bytes("v2/clone.response"),
         Protocol::V2,
         git_transport::client::git::ConnectMode::Daemon,
     );
-    git_protocol::fetch(
+    let outcome = git_protocol::fetch(
         &mut transport,
         &mut delegate,
         git_protocol::credentials::helper,
@@ -71,6 +74,24 @@ async fn ls_remote() -> crate::Result {
     )
     .await?;
 
+    assert_eq!(
+        outcome,
+        fetch::handshake::Outcome {
+            protocol: Protocol::V2,
+            agent: Some("git/2.28.0".into()),
+            object_format: "sha1".into(),
+            symrefs: vec![fetch::handshake::Symref {
+                name: "HEAD".into(),
+                target: "refs/heads/master".into()
+            }]
+        }
+    );
+    assert_eq!(outcome.object_hash(), Some(git_hash::Kind::Sha1));
+    assert_eq!(
+        outcome.symref_target("HEAD"),
+        Some("refs/heads/master".as_bytes().as_bstr())
+    );
+
     assert_eq!(
         delegate.refs,
         vec![
@@ -102,6 +123,69 @@ async fn ls_remote() -> crate::Result {
     Ok(())
 }
 
+#[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+async fn ls_remote_with_session_id() -> crate::Result {
+    let server_session_ids = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let _subscription = git_features::trace::subscribe({
+        let server_session_ids = server_session_ids.clone();
+        move |_: &str, event: &git_features::trace::Event<'_>| {
+            if let git_features::trace::Event::Data {
+                key: "server-session-id",
+                value,
+                ..
+            } = event
+            {
+                server_session_ids.lock().expect("no panic").push(value.to_string());
+            }
+        }
+    });
+    let mut delegate = LsRemoteDelegate {
+        send_session_id: true,
+        ..Default::default()
+    };
+    let mut transport = transport(
+        Vec::new(),
+        fixture_bytes("v2/ls-remote-session-id.response"),
+        Protocol::V2,
+        git_transport::client::git::ConnectMode::Daemon,
+    );
+    git_protocol::fetch(
+        &mut transport,
+        &mut delegate,
+        git_protocol::credentials::helper,
+        progress::Discard,
+        FetchConnection::AllowReuse,
+    )
+    .await?;
+
+    assert_eq!(delegate.refs.len(), 2);
+    let session_id = format!("session-id={}\n", fetch::session_id().1.expect("value set"));
+    assert_eq!(
+        transport.into_inner().1.as_bstr(),
+        format!(
+            "0044git-upload-pack does/not/matter\0\0version=2\0value-only\0key=value\00014command=ls-refs
+001bagent={}
+{:04x}{}0001000csymrefs
+0009peel
+0000",
+            fetch::agent().1.expect("value set"),
+            session_id.len() + 4,
+            session_id
+        )
+        .as_bytes()
+        .as_bstr(),
+    );
+    assert!(
+        server_session_ids
+            .lock()
+            .expect("no panic")
+            .iter()
+            .any(|id| id == "server-1"),
+        "the session id of the server is traced"
+    );
+    Ok(())
+}
+
 #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
 async fn ls_remote_abort_in_prep_ls_refs() -> crate::Result {
     let out = Vec::new();
@@ -111,7 +195,7 @@ async fn ls_remote_abort_in_prep_ls_refs() -> crate::Result {
     };
     let mut transport = transport(
         out,
-        "v2/clone.response",
+        fixture_bytes("v2/clone.response"),
         Protocol::V2,
         git_transport::client::git::ConnectMode::Daemon,
     );
@@ -149,7 +233,7 @@ async fn ref_in_want() -> crate::Result {
     };
     let mut transport = transport(
         out,
-        "v2/clone-ref-in-want.response",
+        fixture_bytes("v2/clone-ref-in-want.response"),
         Protocol::V2,
         git_transport::client::git::ConnectMode::Daemon,
     );
@@ -0,0 +1,239 @@
+use bstr::ByteSlice;
+use git_hash::ObjectId;
+use git_protocol::proc_receive::{self, Capabilities, Command, Report};
+
+fn id(hex: &str) -> ObjectId {
+    ObjectId::from_hex(hex.as_bytes()).expect("valid hex")
+}
+
+const A: &str = "1111111111111111111111111111111111111111";
+const B: &str = "2222222222222222222222222222222222222222";
+const NULL: &str = "0000000000000000000000000000000000000000";
+
+fn command(old: &str, new: &str, name: &str) -> Command {
+    Command {
+        old_id: id(old),
+        new_id: id(new),
+        name: name.into(),
+    }
+}
+
+fn pkt(lines: &[&str]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for line in lines {
+        if *line == "0000" {
+            out.extend_from_slice(b"0000");
+        } else {
+            out.extend_from_slice(format!("{:04x}{}", line.len() + 4, line).as_bytes());
+        }
+    }
+    out
+}
+
+mod is_handled {
+    use super::{command, A, B, NULL};
+    use git_protocol::proc_receive::is_handled;
+
+    #[test]
+    fn prefixes_match_whole_components() {
+        let patterns = ["refs/for"];
+        assert!(is_handled(&patterns, &command(NULL, A, "refs/for/main")));
+        assert!(is_handled(&patterns, &command(NULL, A, "refs/for")));
+        assert!(!is_handled(&patterns, &command(NULL, A, "refs/fortune")));
+        assert!(is_handled(&["refs/for/"], &command(NULL, A, "refs/for/main")));
+        assert!(!is_handled(&[] as &[&str], &command(NULL, A, "refs/for/main")));
+    }
+
+    #[test]
+    fn later_patterns_take_precedence_and_can_be_negated() {
+        let patterns = ["refs/for", "!refs/for/private"];
+        assert!(is_handled(&patterns, &command(NULL, A, "refs/for/main")));
+        assert!(!is_handled(&patterns, &command(NULL, A, "refs/for/private/x")));
+        let patterns = ["!refs/for/private", "refs/for"];
+        assert!(is_handled(&patterns, &command(NULL, A, "refs/for/private/x")));
+    }
+
+    #[test]
+    fn modifiers_restrict_the_kind_of_update() {
+        let patterns = ["a:refs/heads", "md:refs/tags"];
+        assert!(is_handled(&patterns, &command(NULL, A, "refs/heads/new")));
+        assert!(!is_handled(&patterns, &command(A, B, "refs/heads/main")));
+        assert!(!is_handled(&patterns, &command(NULL, A, "refs/tags/new")));
+        assert!(is_handled(&patterns, &command(A, B, "refs/tags/v1")));
+        assert!(is_handled(&patterns, &command(A, NULL, "refs/tags/v1")));
+    }
+}
+
+#[test]
+fn exchange_sends_commands_and_push_options_and_parses_reports() -> crate::Result {
+    let commands = vec![
+        command(NULL, A, "refs/for/main/topic"),
+        command(A, B, "refs/for/next"),
+        command(A, B, "refs/for/other"),
+        command(A, B, "refs/heads/main"),
+    ];
+    let input = pkt(&[
+        "version=1\0push-options",
+        "0000",
+        "ok refs/for/main/topic",
+        "option refname refs/changes/23/123/1\n",
+        &format!("option new-oid {}", A),
+        "ok refs/for/main/topic",
+        "option refname refs/changes/24/124/1",
+        "option forced-update",
+        &format!("option old-oid {}", B),
+        "ng refs/for/next no review for next\n",
+        "ok refs/heads/main",
+        "option fall-through",
+        "0000",
+    ]);
+    let mut out = Vec::new();
+    let reports = proc_receive::exchange(
+        input.as_slice(),
+        &mut out,
+        &commands,
+        &["reviewer=a".into()],
+        Capabilities {
+            push_options: true,
+            atomic: true,
+        },
+    )?;
+
+    assert_eq!(
+        out.as_bstr(),
+        pkt(&[
+            "version=1\0push-options atomic",
+            "0000",
+            &format!("{} {} refs/for/main/topic", NULL, A),
+            &format!("{} {} refs/for/next", A, B),
+            &format!("{} {} refs/for/other", A, B),
+            &format!("{} {} refs/heads/main", A, B),
+            "0000",
+            "reviewer=a",
+            "0000"
+        ])
+        .as_bstr()
+    );
+    assert_eq!(
+        reports,
+        vec![
+            Report {
+                name: "refs/for/main/topic".into(),
+                ref_name: Some("refs/changes/23/123/1".into()),
+                new_id: Some(id(A)),
+                ..Default::default()
+            },
+            Report {
+                name: "refs/for/main/topic".into(),
+                ref_name: Some("refs/changes/24/124/1".into()),
+                old_id: Some(id(B)),
+                forced_update: true,
+                ..Default::default()
+            },
+            Report {
+                name: "refs/for/next".into(),
+                rejected: Some("no review for next".into()),
+                ..Default::default()
+            },
+            Report {
+                name: "refs/heads/main".into(),
+                fall_through: true,
+                ..Default::default()
+            },
+            Report {
+                name: "refs/for/other".into(),
+                rejected: Some(proc_receive::MISSING_REPORT.into()),
+                ..Default::default()
+            }
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn push_options_are_only_sent_if_the_hook_supports_them() -> crate::Result {
+    let commands = vec![command(NULL, A, "refs/for/main")];
+    let mut out = Vec::new();
+    proc_receive::exchange(
+        pkt(&["version=1", "0000", "ok refs/for/main", "0000"]).as_slice(),
+        &mut out,
+        &commands,
+        &["reviewer=a".into()],
+        Capabilities {
+            push_options: true,
+            atomic: false,
+        },
+    )?;
+    assert_eq!(
+        out.as_bstr(),
+        pkt(&[
+            "version=1\0push-options",
+            "0000",
+            &format!("{} {} refs/for/main", NULL, A),
+            "0000",
+        ])
+        .as_bstr()
+    );
+    Ok(())
+}
+
+mod invalid {
+    use bstr::BString;
+    use git_protocol::proc_receive::{self, Capabilities, Error};
+
+    use super::{command, pkt, A, NULL};
+
+    fn exchange(input: &[&str]) -> Result<Vec<proc_receive::Report>, Error> {
+        proc_receive::exchange(
+            pkt(input).as_slice(),
+            Vec::new(),
+            &[command(NULL, A, "refs/for/main")],
+            &[] as &[BString],
+            Capabilities::default(),
+        )
+    }
+
+    #[test]
+    fn unsupported_version() {
+        assert!(matches!(
+            exchange(&["version=2", "0000"]),
+            Err(Error::UnsupportedVersion(line)) if line == "version=2"
+        ));
+    }
+
+    #[test]
+    fn reports_on_unknown_references() {
+        assert!(matches!(
+            exchange(&["version=1", "0000", "ok refs/for/other", "0000"]),
+            Err(Error::UnknownReference(name)) if name == "refs/for/other"
+        ));
+    }
+
+    #[test]
+    fn options_without_report() {
+        assert!(matches!(
+            exchange(&["version=1", "0000", "option fall-through", "0000"]),
+            Err(Error::OptionWithoutReport(_))
+        ));
+    }
+
+    #[test]
+    fn unknown_directives() {
+        assert!(matches!(
+            exchange(&["version=1", "0000", "ok refs/for/main", "option frobnicate", "0000"]),
+            Err(Error::InvalidLine(_))
+        ));
+        assert!(matches!(
+            exchange(&["version=1", "0000", "maybe refs/for/main", "0000"]),
+            Err(Error::InvalidLine(_))
+        ));
+    }
+
+    #[test]
+    fn missing_flush() {
+        assert!(matches!(
+            exchange(&["version=1", "0000", "ok refs/for/main"]),
+            Err(Error::UnexpectedEof)
+        ));
+    }
+}
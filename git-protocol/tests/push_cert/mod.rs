@@ -0,0 +1,204 @@
+use bstr::{BStr, BString, ByteSlice};
+use git_hash::ObjectId;
+use git_protocol::push_cert::{self, nonce, Certificate, Command, SignatureStatus, Verification};
+use git_protocol::transport::packetline::{PacketLineRef, StreamingPeekableIter};
+
+const SIGNATURE: &str = "-----BEGIN PGP SIGNATURE-----\n\nabc\n-----END PGP SIGNATURE-----\n";
+
+fn certificate() -> Certificate {
+    Certificate {
+        pusher: "Pusher <pusher@example.com> 1234567890 +0100".into(),
+        pushee: Some("https://example.com/repo.git".into()),
+        nonce: Some("1234567890-8ad2a026ec10c2580a58de706001e1b0c232e9f0".into()),
+        push_options: vec!["ci.skip".into()],
+        commands: vec![
+            Command {
+                old_id: ObjectId::null_sha1(),
+                new_id: ObjectId::from_hex(b"1111111111111111111111111111111111111111").expect("valid"),
+                name: "refs/heads/main".into(),
+            },
+            Command {
+                old_id: ObjectId::from_hex(b"2222222222222222222222222222222222222222").expect("valid"),
+                new_id: ObjectId::null_sha1(),
+                name: "refs/tags/v1".into(),
+            },
+        ],
+        signature: BString::default(),
+    }
+}
+
+#[test]
+fn signed_certificates_round_trip() -> crate::Result {
+    let mut cert = certificate();
+    cert.sign(|payload| -> Result<BString, std::convert::Infallible> {
+        assert_eq!(
+            payload.as_bstr(),
+            "certificate version 0.1
+pusher Pusher <pusher@example.com> 1234567890 +0100
+pushee https://example.com/repo.git
+nonce 1234567890-8ad2a026ec10c2580a58de706001e1b0c232e9f0
+push-option ci.skip
+
+0000000000000000000000000000000000000000 1111111111111111111111111111111111111111 refs/heads/main
+2222222222222222222222222222222222222222 0000000000000000000000000000000000000000 refs/tags/v1
+"
+        );
+        Ok(SIGNATURE.into())
+    })?;
+    assert_eq!(
+        cert.to_bytes().as_bstr(),
+        [cert.payload(), SIGNATURE.into()].concat().as_bstr()
+    );
+    assert_eq!(Certificate::from_bytes(&cert.to_bytes())?, cert);
+
+    let minimal = Certificate {
+        pusher: "a <a@example.com> 0 +0000".into(),
+        ..Default::default()
+    };
+    assert_eq!(Certificate::from_bytes(&minimal.to_bytes())?, minimal);
+    Ok(())
+}
+
+#[test]
+fn packet_lines_round_trip() -> crate::Result {
+    let mut cert = certificate();
+    cert.signature = SIGNATURE.into();
+    let mut out = Vec::new();
+    cert.write_packet_lines(b"report-status atomic", &mut out)?;
+    out.extend_from_slice(b"0000");
+    assert!(out.starts_with(b"0022push-cert\0report-status atomic001ccertificate version 0.1\n"));
+    assert!(out.ends_with(b"0012push-cert-end\n0000"));
+
+    let mut input = StreamingPeekableIter::new(out.as_slice(), &[PacketLineRef::Flush]);
+    let (capabilities, parsed) = Certificate::read_packet_lines(&mut input)?;
+    assert_eq!(capabilities, "report-status atomic");
+    assert_eq!(parsed, cert);
+    assert!(input.read_line().is_none(), "only the flush packet remains");
+    Ok(())
+}
+
+#[test]
+fn invalid_certificates() {
+    assert!(matches!(
+        Certificate::from_bytes(b"certificate version 0.2\npusher a\n\n"),
+        Err(push_cert::Error::UnsupportedVersion(line)) if line == "certificate version 0.2"
+    ));
+    assert!(matches!(
+        Certificate::from_bytes(b"certificate version 0.1\nnonce 1\n\n"),
+        Err(push_cert::Error::MissingHeader("pusher"))
+    ));
+    assert!(matches!(
+        Certificate::from_bytes(b"certificate version 0.1\npusher a\n\nnot a command\n"),
+        Err(push_cert::Error::InvalidLine(_))
+    ));
+    assert!(matches!(
+        Certificate::from_bytes(b"certificate version 0.1\npusher a\n"),
+        Err(push_cert::Error::UnexpectedEof)
+    ));
+}
+
+#[test]
+fn verification_is_skipped_without_signature() {
+    let mut cert = certificate();
+    assert_eq!(
+        cert.verify(|_, _| unreachable!("not called without signature")),
+        Verification::default()
+    );
+    cert.signature = SIGNATURE.into();
+    let verification = cert.verify(|payload, signature| {
+        assert_eq!(payload, cert.payload().as_slice());
+        assert_eq!(signature, SIGNATURE.as_bytes());
+        Verification {
+            status: SignatureStatus::Good,
+            signer: Some("Pusher <pusher@example.com>".into()),
+            key: Some("ABCD".into()),
+        }
+    });
+    assert_eq!(verification.status.as_char(), 'G');
+}
+
+mod nonces {
+    use super::*;
+
+    const SEED: &[u8] = b"seed";
+
+    fn path() -> &'static BStr {
+        "/srv/repo.git".into()
+    }
+
+    #[test]
+    fn generate_uses_an_hmac_of_path_and_timestamp() {
+        assert_eq!(
+            nonce::generate(SEED, path(), 1234567890),
+            "1234567890-8ad2a026ec10c2580a58de706001e1b0c232e9f0"
+        );
+        assert_eq!(
+            nonce::generate(&[b'k'; 100], path(), 1234567890),
+            "1234567890-f19efa60b7382bffc3361a046d4fdf257677030d",
+            "keys longer than a block are hashed"
+        );
+    }
+
+    #[test]
+    fn check() {
+        let sent = nonce::generate(SEED, path(), 1234567890);
+        let earlier = nonce::generate(SEED, path(), 1234567800);
+        let check = |sent: Option<&BString>, received: Option<&BString>, stateless: bool, slop_limit: u64| {
+            nonce::check(
+                SEED,
+                path(),
+                sent.map(|n| n.as_bstr()),
+                received.map(|n| n.as_bstr()),
+                stateless,
+                slop_limit,
+            )
+        };
+        assert_eq!(check(None, Some(&sent), false, 0), nonce::Status::Unsolicited);
+        assert_eq!(check(Some(&sent), None, false, 0), nonce::Status::Missing);
+        assert_eq!(check(Some(&sent), Some(&sent), false, 0), nonce::Status::Ok);
+        assert_eq!(check(Some(&sent), Some(&earlier), false, 300), nonce::Status::Bad);
+        assert_eq!(check(Some(&sent), Some(&earlier), true, 300), nonce::Status::Ok);
+        assert_eq!(check(Some(&sent), Some(&earlier), true, 60), nonce::Status::Slop(90));
+        assert_eq!(check(Some(&sent), Some(&earlier), true, 0), nonce::Status::Slop(90));
+
+        let forged = BString::from("1234567800-44cabe761db215401a62b6e3e5fddd4006de0640");
+        assert_eq!(check(Some(&sent), Some(&forged), true, 300), nonce::Status::Bad);
+        assert_eq!(
+            check(Some(&sent), Some(&"garbage".into()), true, 300),
+            nonce::Status::Bad
+        );
+    }
+}
+
+#[test]
+fn hook_env() {
+    let id = ObjectId::from_hex(b"3333333333333333333333333333333333333333").expect("valid");
+    let verification = Verification {
+        status: SignatureStatus::UnknownValidity,
+        signer: Some("Pusher <pusher@example.com>".into()),
+        key: None,
+    };
+    let env = push_cert::hook_env(&id, &verification, Some(("1-abc".into(), nonce::Status::Slop(-5))));
+    let env: Vec<_> = env.iter().map(|(k, v)| (*k, v.to_str_lossy().into_owned())).collect();
+    let expected: Vec<(&str, String)> = vec![
+        ("GIT_PUSH_CERT", id.to_string()),
+        ("GIT_PUSH_CERT_SIGNER", "Pusher <pusher@example.com>".into()),
+        ("GIT_PUSH_CERT_KEY", "".into()),
+        ("GIT_PUSH_CERT_STATUS", "U".into()),
+        ("GIT_PUSH_CERT_NONCE", "1-abc".into()),
+        ("GIT_PUSH_CERT_NONCE_STATUS", "SLOP".into()),
+        ("GIT_PUSH_CERT_NONCE_SLOP", "-5".into()),
+    ];
+    assert_eq!(env, expected);
+    assert_eq!(push_cert::hook_env(&id, &verification, None).len(), 4);
+}
+
+#[test]
+fn nonce_is_taken_from_the_capability() -> crate::Result {
+    use git_protocol::transport::client::Capabilities;
+    let (caps, _) = Capabilities::from_bytes(b"\0report-status push-cert=1-abc atomic")?;
+    assert_eq!(push_cert::nonce(&caps).expect("present"), "1-abc");
+    let (caps, _) = Capabilities::from_bytes(b"\0report-status atomic")?;
+    assert_eq!(push_cert::nonce(&caps), None);
+    Ok(())
+}
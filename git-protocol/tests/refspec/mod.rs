@@ -0,0 +1,152 @@
+use bstr::{BStr, ByteSlice};
+use git_protocol::refspec::{self, Error, Mapping, Mode, RefSpec};
+
+fn parse(spec: &str) -> Result<RefSpec, Error> {
+    RefSpec::from_bytes(spec.as_bytes().as_bstr())
+}
+
+fn name(name: &str) -> &BStr {
+    name.as_bytes().as_bstr()
+}
+
+#[test]
+fn parse_modes_sources_and_destinations() -> crate::Result {
+    let spec = parse("+refs/heads/*:refs/remotes/origin/*")?;
+    assert_eq!(spec.mode, Mode::Force);
+    assert_eq!(spec.source, "refs/heads/*");
+    assert_eq!(spec.destination.as_ref().expect("set"), "refs/remotes/origin/*");
+    assert!(spec.is_pattern());
+
+    let spec = parse("main")?;
+    assert_eq!(spec.mode, Mode::Normal);
+    assert_eq!(spec.destination, None);
+    assert_eq!(spec, parse("main:")?, "an empty destination is the same as none");
+
+    assert_eq!(
+        parse(":refs/heads/remote-head")?.source,
+        "HEAD",
+        "an empty source is HEAD"
+    );
+    assert_eq!(parse("^refs/heads/wip/*")?.mode, Mode::Negative);
+
+    for spec in [
+        "+refs/heads/*:refs/remotes/origin/*",
+        "^refs/tags/v1.*",
+        "HEAD:refs/heads/x",
+    ] {
+        assert_eq!(parse(spec)?.to_string(), spec, "refspecs display as they were parsed");
+    }
+    Ok(())
+}
+
+#[test]
+fn parse_rejects_invalid_refspecs() {
+    for (spec, expected) in [
+        ("", "A refspec must not be empty"),
+        ("+^refs/heads/a", "Negative refspecs can't be forced"),
+        (
+            "^refs/heads/a:refs/heads/b",
+            "Negative refspecs must not have a destination",
+        ),
+        (
+            "a:b:c",
+            "A refspec must have at most one ':' to separate its source from its destination",
+        ),
+        ("refs/*/*:refs/*", "'refs/*/*' may contain at most one '*'"),
+        (
+            "refs/heads/*:refs/heads/main",
+            "Either both source and destination are patterns or none of them",
+        ),
+        ("refs/heads/a..b", "'refs/heads/a..b' isn't a valid reference name"),
+    ] {
+        assert_eq!(
+            parse(spec).expect_err("invalid").to_string(),
+            expected,
+            "for refspec '{}'",
+            spec
+        );
+    }
+}
+
+#[test]
+fn patterns_match_any_characters_including_slashes() -> crate::Result {
+    let spec = parse("refs/heads/*:refs/remotes/origin/*")?;
+    assert!(spec.matches(name("refs/heads/feature/a")));
+    assert!(!spec.matches(name("refs/tags/v1")));
+    assert_eq!(
+        spec.destination_of(name("refs/heads/feature/a")).expect("match"),
+        "refs/remotes/origin/feature/a"
+    );
+
+    let spec = parse("refs/heads/*-wip:refs/wip/*")?;
+    assert_eq!(
+        spec.destination_of(name("refs/heads/a-wip")).expect("match"),
+        "refs/wip/a"
+    );
+    assert_eq!(spec.destination_of(name("refs/heads/a")), None);
+    assert_eq!(spec.prefixes(), vec!["refs/heads/"]);
+    Ok(())
+}
+
+#[test]
+fn names_match_the_references_they_abbreviate() -> crate::Result {
+    let spec = parse("main:refs/remotes/origin/main")?;
+    assert!(spec.matches(name("refs/heads/main")));
+    assert!(spec.matches(name("refs/remotes/main/HEAD")));
+    assert!(!spec.matches(name("refs/heads/main-2")));
+    assert!(!spec.matches(name("refs/heads/sub/main")));
+    assert_eq!(
+        spec.destination_of(name("refs/heads/main")).expect("match"),
+        "refs/remotes/origin/main"
+    );
+    assert_eq!(
+        spec.prefixes(),
+        vec![
+            "main",
+            "refs/main",
+            "refs/tags/main",
+            "refs/heads/main",
+            "refs/remotes/main",
+            "refs/remotes/main/HEAD"
+        ]
+    );
+    assert!(
+        parse("^main")?.prefixes().is_empty(),
+        "negative refspecs don't list anything"
+    );
+    Ok(())
+}
+
+#[test]
+fn map_applies_all_refspecs_in_order_and_excludes_negative_matches() -> crate::Result {
+    let specs = [
+        parse("+refs/heads/*:refs/remotes/origin/*")?,
+        parse("^refs/heads/wip/*")?,
+        parse("v1:refs/tags/v1")?,
+        parse("refs/heads/main:refs/remotes/origin/main")?,
+        parse("HEAD")?,
+    ];
+    let names = [
+        "HEAD",
+        "refs/heads/main",
+        "refs/heads/wip/a",
+        "refs/heads/v1",
+        "refs/tags/v1",
+    ];
+    let mapping = |remote: &str, local: Option<&str>, spec_index| Mapping {
+        remote: remote.into(),
+        local: local.map(Into::into),
+        spec_index,
+    };
+    assert_eq!(
+        refspec::map(&specs, names.iter().map(|n| name(n))),
+        vec![
+            mapping("refs/heads/main", Some("refs/remotes/origin/main"), 0),
+            mapping("refs/heads/v1", Some("refs/remotes/origin/v1"), 0),
+            mapping("refs/tags/v1", Some("refs/tags/v1"), 2),
+            mapping("HEAD", None, 4),
+        ],
+        "tags are preferred over branches of the same name, and identical mappings are dropped"
+    );
+    Ok(())
+}
@@ -14,6 +14,8 @@
 //!     * one reference maps to a file on disk
 //!   * **packed**
 //!     * references are stored in a single human-readable file, along with their targets if they are symbolic.
+//! * **[memory][memory::Store]**
+//!   * references are kept in memory only, without reference logs, for instance to work without filesystem access.
 //! * **ref-table**
 //!   * supersedes all of the above to allow handling hundreds of thousands of references.
 #![forbid(unsafe_code)]
@@ -23,7 +25,7 @@ pub use git_object::bstr;
 use git_object::bstr::{BStr, BString};
 
 mod store;
-pub use store::{file, packed};
+pub use store::{file, memory, packed};
 
 mod fullname;
 ///
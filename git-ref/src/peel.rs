@@ -1,4 +1,11 @@
-/// A function for use in [`crate::file::ReferenceExt::peel_to_id_in_place()`] to indicate no peeling should happen.
+use std::{collections::BTreeSet, path::PathBuf};
+
+use git_hash::ObjectId;
+
+use crate::{file, FullName, Reference, Target};
+
+/// A function for use in [`crate::file::ReferenceExt::peel_to_id_in_place()`] or
+/// [`crate::memory::Store::peel_to_id_in_place()`] to indicate no peeling should happen.
 pub fn none(
     _id: git_hash::ObjectId,
     _buf: &mut Vec<u8>,
@@ -6,6 +13,66 @@ pub fn none(
     Ok(Some((git_object::Kind::Commit, &[])))
 }
 
+/// Follow all symbolic targets of `reference` using `follow` to obtain the next reference in the chain and peel the object it
+/// ultimately points to with `find`, using `cycle_path` to obtain the path to report if a cycle is detected.
+pub(crate) fn to_id_in_place<E: std::error::Error + Send + Sync + 'static>(
+    reference: &mut Reference,
+    mut follow: impl FnMut(&Reference) -> Option<Result<Reference, file::find::existing::Error>>,
+    cycle_path: impl FnOnce(&FullName) -> PathBuf,
+    mut find: impl FnMut(ObjectId, &mut Vec<u8>) -> Result<Option<(git_object::Kind, &[u8])>, E>,
+) -> Result<ObjectId, to_id::Error> {
+    match reference.peeled {
+        Some(peeled) => {
+            reference.target = Target::Peeled(peeled.to_owned());
+            Ok(peeled)
+        }
+        None => {
+            if reference.target.kind() == crate::Kind::Symbolic {
+                let mut seen = BTreeSet::new();
+                let cursor = &mut *reference;
+                while let Some(next) = follow(cursor) {
+                    let next = next?;
+                    if seen.contains(&next.name) {
+                        return Err(to_id::Error::Cycle(cycle_path(&cursor.name)));
+                    }
+                    *cursor = next;
+                    seen.insert(cursor.name.clone());
+                    const MAX_REF_DEPTH: usize = 5;
+                    if seen.len() == MAX_REF_DEPTH {
+                        return Err(to_id::Error::DepthLimitExceeded {
+                            max_depth: MAX_REF_DEPTH,
+                        });
+                    }
+                }
+            };
+            let mut buf = Vec::new();
+            let mut oid = reference.target.as_id().expect("peeled ref").to_owned();
+            let peeled_id = loop {
+                let (kind, data) = find(oid, &mut buf)
+                    .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send + Sync + 'static>)?
+                    .ok_or_else(|| to_id::Error::NotFound {
+                        oid,
+                        name: reference.name.0.clone(),
+                    })?;
+                match kind {
+                    git_object::Kind::Tag => {
+                        oid = git_object::TagRefIter::from_bytes(data).target_id().ok_or_else(|| {
+                            to_id::Error::NotFound {
+                                oid,
+                                name: reference.name.0.clone(),
+                            }
+                        })?;
+                    }
+                    _ => break oid,
+                };
+            };
+            reference.peeled = Some(peeled_id);
+            reference.target = Target::Peeled(peeled_id);
+            Ok(peeled_id)
+        }
+    }
+}
+
 ///
 pub mod to_id {
     use std::path::PathBuf;
@@ -16,7 +83,8 @@ pub mod to_id {
     use crate::file;
 
     quick_error! {
-        /// The error returned by [`crate::file::ReferenceExt::peel_to_id_in_place()`].
+        /// The error returned by [`crate::file::ReferenceExt::peel_to_id_in_place()`] and
+        /// [`crate::memory::Store::peel_to_id_in_place()`].
         #[derive(Debug)]
         #[allow(missing_docs)]
         pub enum Error {
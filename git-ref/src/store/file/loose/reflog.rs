@@ -159,6 +159,7 @@ pub mod create_or_update {
                                 writeln!(file)
                             }
                         })
+                        .and_then(|_| if self.fsync.metadata() { file.sync_all() } else { Ok(()) })
                         .map_err(|err| Error::Append {
                             err,
                             reflog_path: self.reflock_resource_to_log_path(lock),
@@ -235,6 +236,135 @@ pub mod create_or_update {
     pub use error::Error;
 }
 
+///
+pub mod expire {
+    use std::{convert::TryInto, io::Write};
+
+    use git_hash::oid;
+    use git_object::bstr::ByteSlice;
+
+    use crate::{
+        store::{file, file::log},
+        FullNameRef,
+    };
+
+    /// Determines which reflog entries [`file::Store::reflog_expire()`] removes.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct Options {
+        /// Remove all entries recorded before this time in seconds since the unix epoch, or none of them if `None`.
+        pub expire_before: Option<u32>,
+        /// Remove all entries recorded before this time in seconds since the unix epoch if the object they point to isn't
+        /// reachable from the reference anymore, or none of them if `None`.
+        pub expire_unreachable_before: Option<u32>,
+    }
+
+    /// The outcome of [`file::Store::reflog_expire()`].
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct Outcome {
+        /// The amount of entries that were removed.
+        pub removed: usize,
+        /// The amount of entries that were kept.
+        pub kept: usize,
+    }
+
+    impl file::Store {
+        /// Remove entries from the reflog of the reference `name` according to `options`, and rewrite it atomically if any
+        /// entry was removed. Return `Ok(None)` if there is no reflog.
+        ///
+        /// `is_reachable(id)` is called for entries that are candidates for removal only if they are unreachable, and is
+        /// expected to return true if the object `id` is reachable from the tip of the reference.
+        /// `lock_mode` determines what happens if the reference or its reflog are locked, as both are locked during the
+        /// operation to prevent concurrent updates.
+        pub fn reflog_expire<'a, Name, E>(
+            &self,
+            name: Name,
+            options: Options,
+            lock_mode: git_lock::acquire::Fail,
+            mut is_reachable: impl FnMut(&oid) -> bool,
+        ) -> Result<Option<Outcome>, Error>
+        where
+            Name: TryInto<FullNameRef<'a>, Error = E>,
+            crate::name::Error: From<E>,
+        {
+            let name: FullNameRef<'_> = name.try_into().map_err(|err| Error::RefnameValidation(err.into()))?;
+            let _reference_lock = git_lock::Marker::acquire_to_hold_resource(
+                self.base.join(name.to_path()),
+                lock_mode,
+                Some(self.base.clone()),
+            )?;
+            let log_path = self.reflog_path(name);
+            let log = match std::fs::read(&log_path) {
+                Ok(log) => log,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                Err(err) => return Err(err.into()),
+            };
+
+            let mut out = Vec::with_capacity(log.len());
+            let mut outcome = Outcome::default();
+            for (line, raw_line) in log::iter::forward(&log).zip(log.lines_with_terminator()) {
+                let line = line?;
+                let time = line.signature.time.time;
+                let expire = options.expire_before.map_or(false, |before| time < before)
+                    || options.expire_unreachable_before.map_or(false, |before| time < before)
+                        && !is_reachable(&line.new_oid());
+                if expire {
+                    outcome.removed += 1;
+                } else {
+                    outcome.kept += 1;
+                    out.extend_from_slice(raw_line);
+                }
+            }
+
+            if outcome.removed != 0 {
+                let mut file =
+                    git_lock::File::acquire_to_update_resource(&log_path, lock_mode, Some(self.reflog_root()))?;
+                file.with_mut(|f| f.write_all(&out))?;
+                file.commit()?;
+            }
+            Ok(Some(outcome))
+        }
+    }
+
+    mod error {
+        use quick_error::quick_error;
+
+        use crate::store::file::log;
+
+        quick_error! {
+            /// The error returned by [crate::file::Store::reflog_expire()].
+            #[derive(Debug)]
+            #[allow(missing_docs)]
+            pub enum Error {
+                RefnameValidation(err: crate::name::Error) {
+                    display("The reflog name or path is not a valid ref name")
+                    source(err)
+                }
+                Lock(err: git_lock::acquire::Error) {
+                    display("The reference or its reflog could not be locked")
+                    from()
+                    source(err)
+                }
+                Io(err: std::io::Error) {
+                    display("The reflog file could not be read or written")
+                    from()
+                    source(err)
+                }
+                Decode(err: log::iter::decode::Error) {
+                    display("A reflog entry could not be parsed")
+                    from()
+                    source(err)
+                }
+                Commit(err: git_lock::commit::Error<git_lock::File>) {
+                    display("The rewritten reflog could not be moved into place")
+                    from()
+                    source(err)
+                }
+            }
+        }
+    }
+    pub use error::Error;
+}
+
 mod error {
     use std::io;
 
@@ -29,6 +29,9 @@ pub struct Store {
     pub write_reflog: WriteReflog,
     /// The namespace to use for edits and reads
     pub namespace: Option<Namespace>,
+    /// Whether references, packed references and reference logs are synchronized to permanent storage when written,
+    /// which is the case if [`Fsync::metadata()`][git_features::fs::Fsync::metadata()] is true.
+    pub fsync: git_features::fs::Fsync,
 }
 
 /// A transaction on a file store
@@ -37,6 +40,7 @@ pub struct Transaction<'s> {
     packed_transaction: Option<crate::store::packed::Transaction>,
     updates: Option<Vec<transaction::Edit>>,
     packed_refs: transaction::PackedRefs,
+    hook: Option<Box<crate::transaction::hook::Fn>>,
 }
 
 pub(in crate::store::file) fn path_to_name(path: impl Into<PathBuf>) -> git_object::bstr::BString {
@@ -1,6 +1,13 @@
 use std::path::PathBuf;
 
-use crate::store::{file, packed};
+use crate::{
+    store::{
+        file,
+        file::transaction::{FindObjectFn, PackedRefs},
+        packed,
+    },
+    transaction::{Change, LogChange, PreviousValue, RefEdit},
+};
 
 impl file::Store {
     /// Return a packed transaction ready to receive updates. Use this to create or update `packed-refs`.
@@ -30,6 +37,55 @@ impl file::Store {
     pub fn packed_refs_path(&self) -> PathBuf {
         self.base.join("packed-refs")
     }
+
+    /// Write all loose references which aren't symbolic into the `packed-refs` file, creating it if needed, and remove the
+    /// loose reference files afterwards if `prune` is true. Their reference logs are kept either way.
+    ///
+    /// `find` is used to look up objects to peel annotated tags, and `lock_mode` determines what happens if a lock on any of
+    /// the involved files can't be obtained.
+    /// Loose references which can't be parsed are skipped, and the performed edits are returned.
+    ///
+    /// Note that just like all other transactions, the `packed-refs` file is written in sorted order and moved into place
+    /// atomically, and that there is no namespace support.
+    pub fn pack_refs(
+        &self,
+        find: Box<FindObjectFn>,
+        prune: bool,
+        lock_mode: git_lock::acquire::Fail,
+    ) -> Result<Vec<RefEdit>, pack_refs::Error> {
+        let mut edits = Vec::new();
+        for reference in self.loose_iter()? {
+            let reference = match reference {
+                Ok(r) => r,
+                Err(file::iter::loose::Error::ReferenceCreation { .. }) => continue,
+                Err(err) => return Err(err.into()),
+            };
+            if reference.kind() == crate::Kind::Symbolic {
+                continue;
+            }
+            edits.push(RefEdit {
+                change: Change::Update {
+                    log: LogChange::default(),
+                    expected: PreviousValue::MustExistAndMatch(reference.target.clone()),
+                    new: reference.target,
+                },
+                name: reference.name,
+                deref: false,
+            });
+        }
+        if edits.is_empty() {
+            return Ok(edits);
+        }
+        Ok(self
+            .transaction()
+            .packed_refs(if prune {
+                PackedRefs::DeletionsAndNonSymbolicUpdatesRemoveLooseSourceReference(find)
+            } else {
+                PackedRefs::DeletionsAndNonSymbolicUpdates(find)
+            })
+            .prepare(edits, lock_mode)?
+            .commit(&git_actor::Signature::empty())?)
+    }
 }
 
 ///
@@ -56,3 +112,38 @@ pub mod transaction {
         }
     }
 }
+
+///
+pub mod pack_refs {
+    use quick_error::quick_error;
+
+    use crate::store::file;
+
+    quick_error! {
+        /// The error returned by [`file::Store::pack_refs()`][crate::file::Store::pack_refs()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Io(err: std::io::Error) {
+                display("The loose references could not be listed")
+                source(err)
+                from()
+            }
+            LooseIter(err: file::iter::loose::Error) {
+                display("A loose reference could not be read")
+                source(err)
+                from()
+            }
+            Prepare(err: file::transaction::prepare::Error) {
+                display("The transaction to pack references could not be prepared")
+                source(err)
+                from()
+            }
+            Commit(err: file::transaction::commit::Error) {
+                display("The transaction to pack references could not be committed")
+                source(err)
+                from()
+            }
+        }
+    }
+}
@@ -1,5 +1,3 @@
-use std::collections::BTreeSet;
-
 use git_hash::ObjectId;
 
 use crate::{
@@ -81,58 +79,14 @@ impl ReferenceExt for Reference {
         &mut self,
         store: &file::Store,
         packed: Option<&packed::Buffer>,
-        mut find: impl FnMut(git_hash::ObjectId, &mut Vec<u8>) -> Result<Option<(git_object::Kind, &[u8])>, E>,
+        find: impl FnMut(git_hash::ObjectId, &mut Vec<u8>) -> Result<Option<(git_object::Kind, &[u8])>, E>,
     ) -> Result<ObjectId, peel::to_id::Error> {
-        match self.peeled {
-            Some(peeled) => {
-                self.target = Target::Peeled(peeled.to_owned());
-                Ok(peeled)
-            }
-            None => {
-                if self.target.kind() == crate::Kind::Symbolic {
-                    let mut seen = BTreeSet::new();
-                    let cursor = &mut *self;
-                    while let Some(next) = cursor.follow(store, packed) {
-                        let next = next?;
-                        if seen.contains(&next.name) {
-                            return Err(peel::to_id::Error::Cycle(store.base.join(cursor.name.to_path())));
-                        }
-                        *cursor = next;
-                        seen.insert(cursor.name.clone());
-                        const MAX_REF_DEPTH: usize = 5;
-                        if seen.len() == MAX_REF_DEPTH {
-                            return Err(peel::to_id::Error::DepthLimitExceeded {
-                                max_depth: MAX_REF_DEPTH,
-                            });
-                        }
-                    }
-                };
-                let mut buf = Vec::new();
-                let mut oid = self.target.as_id().expect("peeled ref").to_owned();
-                let peeled_id = loop {
-                    let (kind, data) = find(oid, &mut buf)
-                        .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send + Sync + 'static>)?
-                        .ok_or_else(|| peel::to_id::Error::NotFound {
-                            oid,
-                            name: self.name.0.clone(),
-                        })?;
-                    match kind {
-                        git_object::Kind::Tag => {
-                            oid = git_object::TagRefIter::from_bytes(data).target_id().ok_or_else(|| {
-                                peel::to_id::Error::NotFound {
-                                    oid,
-                                    name: self.name.0.clone(),
-                                }
-                            })?;
-                        }
-                        _ => break oid,
-                    };
-                };
-                self.peeled = Some(peeled_id);
-                self.target = Target::Peeled(peeled_id);
-                Ok(peeled_id)
-            }
-        }
+        peel::to_id_in_place(
+            self,
+            |reference| reference.follow(store, packed),
+            |name| store.base.join(name.to_path()),
+            find,
+        )
     }
 
     fn follow(
@@ -1,6 +1,6 @@
 use crate::{
     store::file::{transaction::PackedRefs, Transaction},
-    transaction::{Change, LogChange, RefEdit, RefLog},
+    transaction::{hook, Change, LogChange, RefEdit, RefLog},
     Target,
 };
 
@@ -23,8 +23,10 @@ impl<'s> Transaction<'s> {
     ///   along with empty parent directories
     ///
     /// Note that transactions will be prepared automatically as needed.
-    pub fn commit(self, committer: &git_actor::Signature) -> Result<Vec<RefEdit>, Error> {
-        let mut updates = self.updates.expect("BUG: must call prepare before commit");
+    ///
+    /// The [hook][Transaction::hook()] is called once all changes were made.
+    pub fn commit(mut self, committer: &git_actor::Signature) -> Result<Vec<RefEdit>, Error> {
+        let mut updates = self.updates.take().expect("BUG: must call prepare before commit");
         let delete_loose_refs = matches!(
             self.packed_refs,
             PackedRefs::DeletionsAndNonSymbolicUpdatesRemoveLooseSourceReference(_)
@@ -122,7 +124,7 @@ impl<'s> Transaction<'s> {
             }
         }
 
-        if let Some(t) = self.packed_transaction {
+        if let Some(t) = self.packed_transaction.take() {
             t.commit().map_err(Error::PackedTransactionCommit)?;
         }
 
@@ -148,6 +150,9 @@ impl<'s> Transaction<'s> {
                 drop(lock)
             }
         }
+        if let Some(hook) = self.hook.as_mut() {
+            hook(hook::State::Committed, &Self::hook_updates(&updates)).ok();
+        }
         Ok(updates.into_iter().map(|edit| edit.update).collect())
     }
 }
@@ -3,7 +3,7 @@ use git_object::bstr::BString;
 
 use crate::{
     store::{file, file::Transaction},
-    transaction::RefEdit,
+    transaction::{hook, RefEdit},
 };
 
 /// A function receiving an object id to resolve, returning its decompressed bytes.
@@ -76,6 +76,7 @@ impl file::Store {
             packed_transaction: None,
             updates: None,
             packed_refs: PackedRefs::default(),
+            hook: None,
         }
     }
 }
@@ -86,6 +87,28 @@ impl<'s> Transaction<'s> {
         self.packed_refs = packed_refs;
         self
     }
+
+    /// Call `hook` with all updates once the transaction is prepared, committed or aborted, allowing it to reject
+    /// the prepared transaction like the `reference-transaction` hook of `git`.
+    pub fn hook(mut self, hook: Box<crate::transaction::hook::Fn>) -> Self {
+        self.hook = Some(hook);
+        self
+    }
+
+    fn hook_updates(updates: &[Edit]) -> Vec<hook::Update> {
+        updates
+            .iter()
+            .filter_map(|edit| hook::Update::from_edit(&edit.update))
+            .collect()
+    }
+}
+
+impl<'s> Drop for Transaction<'s> {
+    fn drop(&mut self) {
+        if let (Some(hook), Some(updates)) = (self.hook.as_mut(), self.updates.as_ref()) {
+            hook(hook::State::Aborted, &Self::hook_updates(updates)).ok();
+        }
+    }
 }
 
 ///
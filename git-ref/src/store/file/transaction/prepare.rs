@@ -8,7 +8,7 @@ use crate::{
             Transaction,
         },
     },
-    transaction::{Change, LogChange, RefEdit, RefEditsExt, RefLog},
+    transaction::{hook, Change, LogChange, RefEdit, RefEditsExt, RefLog},
     Reference, Target,
 };
 
@@ -159,6 +159,9 @@ impl<'s> Transaction<'s> {
                     Target::Peeled(oid) => write!(file, "{}", oid),
                     Target::Symbolic(name) => write!(file, "ref: {}", name.0),
                 })?;
+                if store.fsync.metadata() {
+                    lock.with_mut(|file| file.sync_all())?;
+                }
 
                 lock.close()?
             }
@@ -275,6 +278,7 @@ impl<'s> Transaction<'s> {
                         .transpose()?
                 };
                 if let Some(transaction) = packed_transaction {
+                    let transaction = transaction.fsync(self.store.fsync.metadata());
                     self.packed_transaction = Some(match &mut self.packed_refs {
                         PackedRefs::DeletionsAndNonSymbolicUpdatesRemoveLooseSourceReference(f)
                         | PackedRefs::DeletionsAndNonSymbolicUpdates(f) => {
@@ -332,6 +336,13 @@ impl<'s> Transaction<'s> {
                 }
             }
         }
+        if let Some(hook) = self.hook.as_mut() {
+            let hook_updates = Self::hook_updates(&updates);
+            if let Err(err) = hook(hook::State::Prepared, &hook_updates) {
+                hook(hook::State::Aborted, &hook_updates).ok();
+                return Err(Error::Hook(err));
+            }
+        }
         self.updates = Some(updates);
         Ok(self)
     }
@@ -400,6 +411,10 @@ mod error {
                 from()
                 source(err)
             }
+            Hook(err: Box<dyn std::error::Error + Send + Sync + 'static>) {
+                display("The reference transaction was rejected by its hook")
+                source(&**err)
+            }
         }
     }
 }
@@ -0,0 +1,194 @@
+use std::{
+    collections::BTreeMap,
+    convert::TryInto,
+    path::Path,
+    sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use git_hash::ObjectId;
+use git_object::bstr::{BStr, BString, ByteSlice, ByteVec};
+use os_str_bytes::OsStrBytes;
+
+use crate::{file, peel, FullName, Namespace, PartialNameRef, Reference, Target};
+
+/// A store for references which keeps them in memory only, without any filesystem access.
+///
+/// It has no notion of packed references and doesn't keep reference logs, but otherwise finds and changes references
+/// like the [file store][crate::file::Store] does.
+#[derive(Debug, Default)]
+pub struct Store {
+    /// The namespace to use for edits and reads
+    pub namespace: Option<Namespace>,
+    /// All references by their full name including the namespace, along with their target.
+    refs: RwLock<References>,
+}
+
+type References = BTreeMap<FullName, Target>;
+
+/// An iterator over a snapshot of the references in a [`Store`], sorted by their name.
+pub struct Iter {
+    inner: std::vec::IntoIter<Reference>,
+}
+
+impl Iterator for Iter {
+    type Item = Reference;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+/// A transaction on a memory store
+pub struct Transaction<'s> {
+    store: &'s Store,
+    refs: Option<RwLockWriteGuard<'s, References>>,
+    updates: Option<Vec<crate::transaction::RefEdit>>,
+    hook: Option<Box<crate::transaction::hook::Fn>>,
+}
+
+impl Store {
+    /// Find a single reference by the given `partial` name, following the same lookup rules as
+    /// [`file::Store::try_find()`][crate::file::Store::try_find()] does.
+    ///
+    /// Returns `Ok(None)` if no such ref exists.
+    pub fn try_find<'a, Name, E>(&self, partial: Name) -> Result<Option<Reference>, file::find::Error>
+    where
+        Name: TryInto<PartialNameRef<'a>, Error = E>,
+        file::find::Error: From<E>,
+    {
+        let partial = partial.try_into()?;
+        Ok(find(&self.read(), self.namespace.as_ref(), partial.as_bstr()))
+    }
+
+    /// Similar to [`Store::try_find()`] but a non-existing ref is treated as error.
+    pub fn find<'a, Name, E>(&self, partial: Name) -> Result<Reference, file::find::existing::Error>
+    where
+        Name: TryInto<PartialNameRef<'a>, Error = E>,
+        crate::name::Error: From<E>,
+    {
+        let partial = partial
+            .try_into()
+            .map_err(|err| file::find::existing::Error::Find(file::find::Error::RefnameValidation(err.into())))?;
+        find(&self.read(), self.namespace.as_ref(), partial.as_bstr())
+            .ok_or_else(|| file::find::existing::Error::NotFound(partial.to_partial_path().into_owned()))
+    }
+
+    /// Return an iterator over all references in `refs/`, sorted by their name, which like in the file store excludes
+    /// `HEAD` and other references at the root.
+    ///
+    /// As the iterator operates on a snapshot, changes made while iterating aren't visible to it.
+    pub fn iter(&self) -> Iter {
+        self.iter_bytes_prefixed(b"refs/".as_bstr())
+    }
+
+    /// As [`iter(…)`][Store::iter()], but filters by `prefix`, i.e. "refs/heads".
+    pub fn iter_prefixed(&self, prefix: impl AsRef<Path>) -> Iter {
+        let prefix = prefix.as_ref().to_raw_bytes();
+        #[cfg(windows)]
+        let prefix = prefix.replace(b"\\", b"/");
+        self.iter_bytes_prefixed(prefix.as_bstr())
+    }
+
+    fn iter_bytes_prefixed(&self, prefix: &BStr) -> Iter {
+        let mut full_prefix = BString::default();
+        if let Some(namespace) = &self.namespace {
+            full_prefix.push_str(namespace.as_bstr());
+        }
+        full_prefix.push_str(prefix);
+        let refs: Vec<_> = self
+            .read()
+            .iter()
+            .filter(|(name, _)| name.as_bstr().starts_with_str(&full_prefix))
+            .map(|(name, target)| to_reference(name.clone(), target.clone(), self.namespace.as_ref()))
+            .collect();
+        Iter {
+            inner: refs.into_iter(),
+        }
+    }
+
+    /// Follow `reference` one level if it is symbolic and return the ref it refers to.
+    ///
+    /// Returns `None` if this is not a symbolic reference, hence the leaf of the chain.
+    pub fn follow(&self, reference: &Reference) -> Option<Result<Reference, file::find::existing::Error>> {
+        match reference.peeled {
+            Some(peeled) => Some(Ok(Reference {
+                name: reference.name.clone(),
+                target: Target::Peeled(peeled),
+                peeled: None,
+            })),
+            None => match &reference.target {
+                Target::Peeled(_) => None,
+                Target::Symbolic(full_name) => Some(self.find(full_name.to_partial())),
+            },
+        }
+    }
+
+    /// Follow all symbolic targets of `reference` and peel the object it ultimately points to using `find`, and return
+    /// its id, like [`file::ReferenceExt::peel_to_id_in_place()`][crate::file::ReferenceExt::peel_to_id_in_place()] does.
+    pub fn peel_to_id_in_place<E: std::error::Error + Send + Sync + 'static>(
+        &self,
+        reference: &mut Reference,
+        find: impl FnMut(ObjectId, &mut Vec<u8>) -> Result<Option<(git_object::Kind, &[u8])>, E>,
+    ) -> Result<ObjectId, peel::to_id::Error> {
+        peel::to_id_in_place(
+            reference,
+            |reference| self.follow(reference),
+            |name| name.to_path().into_owned(),
+            find,
+        )
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, References> {
+        self.refs.read().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn to_reference(name: FullName, target: Target, namespace: Option<&Namespace>) -> Reference {
+    let mut reference = Reference {
+        name,
+        target,
+        peeled: None,
+    };
+    if let Some(namespace) = namespace {
+        reference.strip_namespace(namespace);
+    }
+    reference
+}
+
+/// Find the reference matching `partial` in `refs`, whose names are prefixed with `namespace`, trying the same names in the
+/// same order as the file store does.
+fn find(refs: &References, namespace: Option<&Namespace>, partial: &BStr) -> Option<Reference> {
+    let mut candidates = Vec::new();
+    let is_all_uppercase = partial.iter().all(|b| b.is_ascii_uppercase());
+    if !partial.contains(&b'/') && is_all_uppercase {
+        candidates.push(partial.to_owned());
+    }
+    let refs_prefix: &[u8] = if partial == b"refs".as_bstr() || partial.starts_with(b"refs/") {
+        b""
+    } else {
+        b"refs/"
+    };
+    for inbetween in &["", "tags/", "heads/", "remotes/"] {
+        let mut name = BString::from(refs_prefix);
+        name.push_str(inbetween);
+        name.push_str(partial);
+        candidates.push(name);
+    }
+    let mut name = BString::from(refs_prefix);
+    name.push_str("remotes/");
+    name.push_str(partial);
+    name.push_str("/HEAD");
+    candidates.push(name);
+
+    candidates.into_iter().find_map(|name| {
+        let mut name = FullName(name);
+        if let Some(namespace) = namespace {
+            name.prefix_namespace(namespace);
+        }
+        let target = refs.get(&name)?.clone();
+        Some(to_reference(name, target, namespace))
+    })
+}
+
+///
+pub mod transaction;
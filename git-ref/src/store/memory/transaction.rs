@@ -0,0 +1,255 @@
+use std::{sync::TryLockError, time::Instant};
+
+use crate::{
+    store::memory::{self, find, Transaction},
+    transaction::{hook, Change, PreviousValue, RefEdit, RefEditsExt, RefLog},
+    FullName, Target,
+};
+
+/// Edits
+impl memory::Store {
+    /// Open a transaction to change references atomically.
+    ///
+    /// The transaction inherits the parent namespace.
+    pub fn transaction(&self) -> Transaction<'_> {
+        Transaction {
+            store: self,
+            refs: None,
+            updates: None,
+            hook: None,
+        }
+    }
+}
+
+impl<'s> Transaction<'s> {
+    /// Call `hook` with all updates once the transaction is prepared, committed or aborted, allowing it to reject
+    /// the prepared transaction like the `reference-transaction` hook of `git`.
+    pub fn hook(mut self, hook: Box<crate::transaction::hook::Fn>) -> Self {
+        self.hook = Some(hook);
+        self
+    }
+
+    /// Lock the store, determining how to fail if it is locked by another transaction with `lock_fail_mode`, and check
+    /// that all `edits` can be applied, splitting edits of symbolic references that are to be dereferenced.
+    ///
+    /// If the operation succeeds, the transaction can be committed or dropped to leave the store unchanged.
+    pub fn prepare(
+        mut self,
+        edits: impl IntoIterator<Item = RefEdit>,
+        lock_fail_mode: git_lock::acquire::Fail,
+    ) -> Result<Self, Error> {
+        assert!(self.updates.is_none(), "BUG: Must not call prepare(…) multiple times");
+        let store = self.store;
+        let refs = match lock_fail_mode {
+            git_lock::acquire::Fail::Immediately => match store.refs.try_write() {
+                Ok(refs) => refs,
+                Err(TryLockError::Poisoned(err)) => err.into_inner(),
+                Err(TryLockError::WouldBlock) => return Err(Error::Locked),
+            },
+            git_lock::acquire::Fail::AfterDurationWithBackoff(duration) => {
+                let start = Instant::now();
+                loop {
+                    match store.refs.try_write() {
+                        Ok(refs) => break refs,
+                        Err(TryLockError::Poisoned(err)) => break err.into_inner(),
+                        Err(TryLockError::WouldBlock) if start.elapsed() < duration => {
+                            std::thread::sleep(std::time::Duration::from_millis(1))
+                        }
+                        Err(TryLockError::WouldBlock) => return Err(Error::Locked),
+                    }
+                }
+            }
+        };
+
+        let namespace = store.namespace.as_ref();
+        let full_name = |name: &FullName| {
+            let mut name = name.clone();
+            if let Some(namespace) = namespace {
+                name.prefix_namespace(namespace);
+            }
+            name
+        };
+        let mut updates: Vec<_> = edits.into_iter().collect();
+        updates
+            .pre_process(
+                |name| find(&refs, namespace, name.as_bstr()).map(|r| r.target),
+                |_, update| update,
+            )
+            .map_err(Error::PreprocessingFailed)?;
+
+        for RefEdit { change, name, .. } in updates.iter_mut() {
+            let existing = refs.get(&full_name(name)).cloned();
+            let name = || name.as_bstr().to_owned();
+            match change {
+                Change::Delete { expected, .. } => {
+                    match (&expected, &existing) {
+                        (PreviousValue::MustNotExist, _) => {
+                            panic!("BUG: MustNotExist constraint makes no sense if references are to be deleted")
+                        }
+                        (PreviousValue::ExistingMustMatch(_), None)
+                        | (PreviousValue::MustExist, Some(_))
+                        | (PreviousValue::Any, None | Some(_)) => {}
+                        (PreviousValue::MustExist | PreviousValue::MustExistAndMatch(_), None) => {
+                            return Err(Error::DeleteReferenceMustExist { full_name: name() })
+                        }
+                        (
+                            PreviousValue::MustExistAndMatch(previous) | PreviousValue::ExistingMustMatch(previous),
+                            Some(existing),
+                        ) => {
+                            if previous != existing {
+                                return Err(Error::ReferenceOutOfDate {
+                                    full_name: name(),
+                                    expected: previous.clone(),
+                                    actual: existing.clone(),
+                                });
+                            }
+                        }
+                    }
+                    if let Some(existing) = existing {
+                        *expected = PreviousValue::MustExistAndMatch(existing);
+                    }
+                }
+                Change::Update { expected, new, .. } => {
+                    match (&expected, &existing) {
+                        (PreviousValue::Any, _)
+                        | (PreviousValue::MustExist, Some(_))
+                        | (PreviousValue::MustNotExist | PreviousValue::ExistingMustMatch(_), None) => {}
+                        (PreviousValue::MustExist, None) => {
+                            return Err(Error::MustExist {
+                                full_name: name(),
+                                expected: Target::Peeled(git_hash::ObjectId::null_sha1()),
+                            })
+                        }
+                        (PreviousValue::MustNotExist, Some(existing)) => {
+                            if *existing != *new {
+                                return Err(Error::MustNotExist {
+                                    full_name: name(),
+                                    actual: existing.clone(),
+                                    new: new.clone(),
+                                });
+                            }
+                        }
+                        (
+                            PreviousValue::MustExistAndMatch(previous) | PreviousValue::ExistingMustMatch(previous),
+                            Some(existing),
+                        ) => {
+                            if previous != existing {
+                                return Err(Error::ReferenceOutOfDate {
+                                    full_name: name(),
+                                    expected: previous.clone(),
+                                    actual: existing.clone(),
+                                });
+                            }
+                        }
+                        (PreviousValue::MustExistAndMatch(previous), None) => {
+                            return Err(Error::MustExist {
+                                full_name: name(),
+                                expected: previous.clone(),
+                            })
+                        }
+                    }
+                    if let Some(existing) = existing {
+                        *expected = PreviousValue::MustExistAndMatch(existing);
+                    }
+                }
+            }
+        }
+
+        if let Some(hook) = self.hook.as_mut() {
+            let hook_updates = Self::hook_updates(&updates);
+            if let Err(err) = hook(hook::State::Prepared, &hook_updates) {
+                hook(hook::State::Aborted, &hook_updates).ok();
+                return Err(Error::Hook(err));
+            }
+        }
+        self.refs = Some(refs);
+        self.updates = Some(updates);
+        Ok(self)
+    }
+
+    /// Make all [prepared][Transaction::prepare()] edits permanent and return them, with the previous value of each reference
+    /// set as the one seen while the store was locked.
+    ///
+    /// Edits which only affect the reference log, like the ones of dereferenced symbolic references, don't change the store.
+    /// The [hook][Transaction::hook()] is called once all changes were made.
+    pub fn commit(mut self) -> Vec<RefEdit> {
+        let updates = self.updates.take().expect("BUG: must call prepare before commit");
+        {
+            let mut refs = self.refs.take().expect("locked when prepared");
+            let namespace = self.store.namespace.as_ref();
+            for update in &updates {
+                let mut name = update.name.clone();
+                if let Some(namespace) = namespace {
+                    name.prefix_namespace(namespace);
+                }
+                match &update.change {
+                    Change::Update { log, new, .. } => {
+                        if log.mode == RefLog::AndReference {
+                            refs.insert(name, new.clone());
+                        }
+                    }
+                    Change::Delete { log, .. } => {
+                        if *log == RefLog::AndReference {
+                            refs.remove(&name);
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(hook) = self.hook.as_mut() {
+            hook(hook::State::Committed, &Self::hook_updates(&updates)).ok();
+        }
+        updates
+    }
+
+    fn hook_updates(updates: &[RefEdit]) -> Vec<hook::Update> {
+        updates.iter().filter_map(hook::Update::from_edit).collect()
+    }
+}
+
+impl<'s> Drop for Transaction<'s> {
+    fn drop(&mut self) {
+        if let (Some(hook), Some(updates)) = (self.hook.as_mut(), self.updates.as_ref()) {
+            hook(hook::State::Aborted, &Self::hook_updates(updates)).ok();
+        }
+    }
+}
+
+mod error {
+    use git_object::bstr::BString;
+    use quick_error::quick_error;
+
+    use crate::Target;
+
+    quick_error! {
+        /// The error returned by [`Transaction::prepare()`][super::Transaction::prepare()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Locked {
+                display("The reference store is locked by another transaction")
+            }
+            PreprocessingFailed(err: std::io::Error) {
+                display("Edit preprocessing failed with error: {}", err.to_string())
+                source(err)
+            }
+            DeleteReferenceMustExist { full_name: BString } {
+                display("The reference '{}' for deletion did not exist", full_name)
+            }
+            MustNotExist { full_name: BString, actual: Target, new: Target } {
+                display("Reference '{}' was not supposed to exist when writing it with value {}, but actual content was {}", full_name, new, actual)
+            }
+            MustExist { full_name: BString, expected: Target } {
+                display("Reference '{}' was supposed to exist with value {}, but didn't.", full_name, expected)
+            }
+            ReferenceOutOfDate { full_name: BString, expected: Target, actual: Target } {
+                display("The reference '{}' should have content {}, actual content was {}", full_name, expected, actual)
+            }
+            Hook(err: Box<dyn std::error::Error + Send + Sync + 'static>) {
+                display("The reference transaction was rejected by its hook")
+                source(&**err)
+            }
+        }
+    }
+}
+pub use error::Error;
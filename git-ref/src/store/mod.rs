@@ -1,5 +1,8 @@
 ///
 pub mod file;
 
+///
+pub mod memory;
+
 ///
 pub mod packed;
@@ -37,6 +37,7 @@ pub(crate) struct Transaction {
     lock: Option<git_lock::File>,
     #[allow(dead_code)] // It just has to be kept alive, hence no reads
     closed_lock: Option<git_lock::Marker>,
+    fsync: bool,
 }
 
 /// A reference as parsed from the `packed-refs` file
@@ -18,6 +18,7 @@ impl packed::Transaction {
             edits: None,
             lock: Some(lock),
             closed_lock: None,
+            fsync: false,
         }
     }
 
@@ -27,10 +28,20 @@ impl packed::Transaction {
             edits: None,
             lock: Some(lock),
             closed_lock: None,
+            fsync: false,
         }
     }
 }
 
+/// Configuration
+impl packed::Transaction {
+    /// If `toggle` is true, synchronize the new packed-refs file to permanent storage before moving it into place.
+    pub(crate) fn fsync(mut self, toggle: bool) -> Self {
+        self.fsync = toggle;
+        self
+    }
+}
+
 /// Access
 impl packed::Transaction {
     /// Returns our packed buffer
@@ -180,6 +191,9 @@ impl packed::Transaction {
         if num_written_lines == 0 {
             std::fs::remove_file(file.resource_path())?;
         } else {
+            if self.fsync {
+                file.with_mut(|f| f.sync_all())?;
+            }
             file.commit()?;
         }
         drop(refs_sorted);
@@ -237,6 +251,7 @@ impl packed::Buffer {
             lock: Some(lock),
             closed_lock: None,
             edits: None,
+            fsync: false,
         })
     }
 }
@@ -0,0 +1,101 @@
+//! Observe or veto transactions in the same way the `reference-transaction` hook of `git` does.
+//!
+//! A [hook function][Fn] is called with [`State::Prepared`] once all references are locked, and may reject the transaction
+//! by returning an error. Afterwards, it is called with [`State::Committed`] once the changes are permanent, or with
+//! [`State::Aborted`] if the prepared transaction was rejected or dropped without being committed.
+use std::io;
+
+use git_object::bstr::BString;
+
+use crate::{
+    transaction::{Change, RefEdit, RefLog},
+    FullName, Target,
+};
+
+/// The state of a transaction when the hook is called.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub enum State {
+    /// All references are locked and the transaction can still be rejected.
+    Prepared,
+    /// All changes were made permanent.
+    Committed,
+    /// The prepared transaction was rolled back.
+    Aborted,
+}
+
+impl State {
+    /// Return the name of the state as passed as only argument to the `reference-transaction` hook.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            State::Prepared => "prepared",
+            State::Committed => "committed",
+            State::Aborted => "aborted",
+        }
+    }
+}
+
+/// A change to a single reference as seen by the hook.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Update {
+    /// The name of the changed reference.
+    pub name: FullName,
+    /// The value of the reference before the change, or `None` if it didn't exist.
+    pub previous: Option<Target>,
+    /// The value of the reference after the change, or `None` if it is deleted.
+    pub new: Option<Target>,
+}
+
+impl Update {
+    /// Return the update `edit` represents, or `None` if it only affects the reference log which is when `git` doesn't
+    /// show it to the hook either.
+    ///
+    /// The previous value is only known for edits that were prepared.
+    pub fn from_edit(edit: &RefEdit) -> Option<Self> {
+        let (log_mode, new) = match &edit.change {
+            Change::Update { log, new, .. } => (log.mode, Some(new.clone())),
+            Change::Delete { log, .. } => (*log, None),
+        };
+        if log_mode == RefLog::Only {
+            return None;
+        }
+        Some(Update {
+            name: edit.name.clone(),
+            previous: edit.change.previous_value().map(|previous| previous.into_owned()),
+            new,
+        })
+    }
+
+    /// Write this update as line to `out` as expected on the standard input of the `reference-transaction` hook,
+    /// using the null id of `hash_kind` for values that don't exist.
+    pub fn write_to(&self, mut out: impl io::Write, hash_kind: git_hash::Kind) -> io::Result<()> {
+        fn write_value(out: &mut impl io::Write, value: &Option<Target>, hash_kind: git_hash::Kind) -> io::Result<()> {
+            match value {
+                Some(Target::Peeled(oid)) => write!(out, "{}", oid),
+                Some(Target::Symbolic(name)) => {
+                    out.write_all(b"ref:")?;
+                    out.write_all(name.as_bstr())
+                }
+                None => write!(out, "{}", git_hash::ObjectId::null(hash_kind)),
+            }
+        }
+        write_value(&mut out, &self.previous, hash_kind)?;
+        out.write_all(b" ")?;
+        write_value(&mut out, &self.new, hash_kind)?;
+        out.write_all(b" ")?;
+        out.write_all(self.name.as_bstr())?;
+        out.write_all(b"\n")
+    }
+
+    /// Return this update as line as expected on the standard input of the `reference-transaction` hook.
+    pub fn to_line(&self, hash_kind: git_hash::Kind) -> BString {
+        let mut buf = Vec::new();
+        self.write_to(&mut buf, hash_kind).expect("no error writing to vec");
+        buf.into()
+    }
+}
+
+/// A function called with the state of a transaction along with all updates it performs, which may reject a
+/// [prepared][State::Prepared] transaction by returning an error.
+///
+/// Errors returned in any other state are ignored.
+pub type Fn = dyn FnMut(State, &[Update]) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>;
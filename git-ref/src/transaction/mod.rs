@@ -124,3 +124,6 @@ pub enum RefLog {
 
 mod ext;
 pub use ext::RefEditsExt;
+
+///
+pub mod hook;
@@ -1,3 +1,4 @@
 mod find;
 mod iter;
+mod pack_refs;
 mod reflog;
@@ -0,0 +1,64 @@
+use git_object::bstr::BString;
+
+use crate::file::{store_with_packed_refs, store_writable};
+
+fn find_in(store: &git_ref::file::Store) -> crate::Result<Box<git_ref::file::transaction::FindObjectFn>> {
+    let odb = git_odb::compound::Store::at(store.base.join("objects"))?;
+    Ok(Box::new(move |oid, buf| {
+        odb.try_find(oid, buf, &mut git_odb::pack::cache::Never)
+            .map(|obj| obj.map(|obj| obj.kind))
+            .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send + Sync>)
+    }))
+}
+
+#[test]
+fn all_loose_refs_with_pruning_matches_git() -> crate::Result {
+    let (_keep, store) = store_writable("make_ref_repository.sh")?;
+    assert!(store.packed_buffer()?.is_none(), "no packed refs to start out with");
+
+    let edits = store.pack_refs(find_in(&store)?, true, git_lock::acquire::Fail::Immediately)?;
+    assert_eq!(edits.len(), 8, "all peeled loose refs are packed");
+    assert!(
+        store
+            .loose_iter()?
+            .filter_map(Result::ok)
+            .all(|r| r.kind() == git_ref::Kind::Symbolic),
+        "only symbolic refs are left"
+    );
+
+    let expected_pack_data: BString = std::fs::read(store_with_packed_refs()?.packed_refs_path())?.into();
+    let actual_packed_data: BString = std::fs::read(store.packed_refs_path())?.into();
+    assert_eq!(
+        actual_packed_data, expected_pack_data,
+        "sorted, with header and fully peeled tags just like git"
+    );
+
+    assert!(
+        store
+            .pack_refs(find_in(&store)?, true, git_lock::acquire::Fail::Immediately)?
+            .is_empty(),
+        "nothing left to pack"
+    );
+    Ok(())
+}
+
+#[test]
+fn without_pruning_keeps_loose_refs_and_updates_existing_packed_refs() -> crate::Result {
+    let (_keep, store) = store_writable("make_packed_ref_repository_for_overlay.sh")?;
+    let loose = store.find_loose("newer-as-loose")?;
+    let num_loose_refs = store.loose_iter()?.count();
+
+    let edits = store.pack_refs(find_in(&store)?, false, git_lock::acquire::Fail::Immediately)?;
+    assert_eq!(edits.len(), 1, "only a single loose ref isn't symbolic");
+    assert_eq!(store.loose_iter()?.count(), num_loose_refs, "loose refs are kept");
+    assert_eq!(
+        store
+            .packed_buffer()?
+            .expect("packed-refs")
+            .find("newer-as-loose")?
+            .target(),
+        loose.target.as_id().expect("peeled"),
+        "the outdated packed ref was updated"
+    );
+    Ok(())
+}
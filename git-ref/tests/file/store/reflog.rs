@@ -64,3 +64,95 @@ mod iter_rev {
         Ok(())
     }
 }
+
+mod expire {
+    use git_ref::file::log::expire::{Options, Outcome};
+
+    fn store_rw() -> crate::Result<(git_testtools::tempfile::TempDir, git_ref::file::Store)> {
+        let dir = git_testtools::scripted_fixture_repo_writable("make_repo_for_reflog.sh")?;
+        let store = git_ref::file::Store::at(dir.path().join(".git"), git_ref::file::WriteReflog::Normal);
+        Ok((dir, store))
+    }
+
+    fn lines(store: &git_ref::file::Store) -> crate::Result<Vec<git_ref::log::Line>> {
+        let mut buf = Vec::new();
+        Ok(store
+            .reflog_iter("refs/heads/main", &mut buf)?
+            .expect("exists")
+            .map(|l| l.map(|l| l.to_owned()))
+            .collect::<Result<_, _>>()?)
+    }
+
+    const FIXTURE_TIME: u32 = 946771200;
+
+    #[test]
+    fn non_existing_returns_none() -> crate::Result {
+        let (_keep, store) = store_rw()?;
+        assert!(store
+            .reflog_expire(
+                "refs/heads/does-not-exist",
+                Options::default(),
+                git_lock::acquire::Fail::Immediately,
+                |_| unreachable!("not called")
+            )?
+            .is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn nothing_expires_by_default_or_if_recent_enough() -> crate::Result {
+        let (_keep, store) = store_rw()?;
+        let previous = lines(&store)?;
+        for options in [
+            Options::default(),
+            Options {
+                expire_before: Some(FIXTURE_TIME),
+                expire_unreachable_before: Some(FIXTURE_TIME),
+            },
+        ] {
+            let outcome = store
+                .reflog_expire("refs/heads/main", options, git_lock::acquire::Fail::Immediately, |_| {
+                    false
+                })?
+                .expect("exists");
+            assert_eq!(outcome, Outcome { removed: 0, kept: 5 });
+        }
+        assert_eq!(lines(&store)?, previous, "the log is untouched");
+        Ok(())
+    }
+
+    #[test]
+    fn unreachable_entries_expire_separately() -> crate::Result {
+        let (_keep, store) = store_rw()?;
+        let previous = lines(&store)?;
+        let tip = previous.last().expect("non-empty").new_oid;
+        let outcome = store
+            .reflog_expire(
+                "refs/heads/main",
+                Options {
+                    expire_before: None,
+                    expire_unreachable_before: Some(FIXTURE_TIME + 1),
+                },
+                git_lock::acquire::Fail::Immediately,
+                |id| id == tip,
+            )?
+            .expect("exists");
+        assert_eq!(outcome, Outcome { removed: 4, kept: 1 });
+        assert_eq!(lines(&store)?, &previous[4..], "only the reachable entry is kept");
+
+        let outcome = store
+            .reflog_expire(
+                "refs/heads/main",
+                Options {
+                    expire_before: Some(FIXTURE_TIME + 1),
+                    expire_unreachable_before: None,
+                },
+                git_lock::acquire::Fail::Immediately,
+                |_| unreachable!("reachability doesn't matter"),
+            )?
+            .expect("exists");
+        assert_eq!(outcome, Outcome { removed: 1, kept: 0 });
+        assert!(lines(&store)?.is_empty(), "an empty log remains");
+        Ok(())
+    }
+}
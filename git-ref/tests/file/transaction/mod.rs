@@ -44,4 +44,6 @@ mod prepare_and_commit {
     mod create_or_update;
 
     mod delete;
+
+    mod hook;
 }
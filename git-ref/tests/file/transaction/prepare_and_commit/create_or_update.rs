@@ -710,6 +710,59 @@ fn packed_refs_creation_with_packed_refs_mode_prune_removes_original_loose_refs(
     Ok(())
 }
 
+#[test]
+fn loose_and_packed_refs_are_written_when_synchronizing_everything() -> crate::Result {
+    let (_keep, mut store) = store_writable("make_packed_ref_repository.sh")?;
+    store.fsync = git_features::fs::Fsync::Everything;
+    let old_id = hex_to_id("134385f6d781b7e97062102c6a483440bfda2a03");
+    let new_id = hex_to_id("0000000000000000000000000000000000000001");
+    let update = |expected: ObjectId| RefEdit {
+        change: Change::Update {
+            log: LogChange {
+                mode: RefLog::AndReference,
+                force_create_reflog: true,
+                message: "synchronized".into(),
+            },
+            expected: PreviousValue::MustExistAndMatch(Target::Peeled(expected)),
+            new: Target::Peeled(new_id),
+        },
+        name: "refs/heads/main".try_into().expect("valid name"),
+        deref: false,
+    };
+
+    store
+        .transaction()
+        .prepare(Some(update(old_id)), Fail::Immediately)?
+        .commit(&committer())?;
+    assert_eq!(
+        store.find_loose("main")?.target.as_id(),
+        Some(new_id.as_ref()),
+        "the loose ref is written"
+    );
+    assert_eq!(
+        reflog_lines(&store, "refs/heads/main")?
+            .last()
+            .expect("at least one line"),
+        &log_line(old_id, new_id, "synchronized"),
+        "the reflog is written"
+    );
+
+    store
+        .transaction()
+        .packed_refs(PackedRefs::DeletionsAndNonSymbolicUpdatesRemoveLooseSourceReference(
+            Box::new(|_, _| Ok(Some(git_object::Kind::Commit))),
+        ))
+        .prepare(Some(update(new_id)), Fail::Immediately)?
+        .commit(&committer())?;
+    assert!(store.try_find_loose("main")?.is_none(), "the loose ref was moved");
+    assert_eq!(
+        store.packed_buffer()?.expect("packed refs").find("main")?.target(),
+        new_id,
+        "the packed-refs file is written"
+    );
+    Ok(())
+}
+
 #[test]
 fn packed_refs_creation_with_packed_refs_mode_leave_keeps_original_loose_refs() -> crate::Result {
     let (_keep, store) = store_writable("make_packed_ref_repository_for_overlay.sh")?;
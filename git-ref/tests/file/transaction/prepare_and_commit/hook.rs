@@ -0,0 +1,186 @@
+use std::{
+    convert::TryInto,
+    sync::{Arc, Mutex},
+};
+
+use git_lock::acquire::Fail;
+use git_object::bstr::BString;
+use git_ref::{
+    file::transaction,
+    transaction::{hook, Change, LogChange, PreviousValue, RefEdit, RefLog},
+    Target,
+};
+use git_testtools::hex_to_id;
+
+use crate::file::{
+    store_writable,
+    transaction::prepare_and_commit::{committer, empty_store},
+};
+
+type Calls = Arc<Mutex<Vec<(hook::State, Vec<BString>)>>>;
+
+fn recording_hook(reject: bool) -> (Calls, Box<hook::Fn>) {
+    let calls = Calls::default();
+    let hook = {
+        let calls = Arc::clone(&calls);
+        move |state: hook::State, updates: &[hook::Update]| {
+            calls.lock().unwrap().push((
+                state,
+                updates
+                    .iter()
+                    .map(|update| update.to_line(git_hash::Kind::Sha1))
+                    .collect(),
+            ));
+            if reject && state == hook::State::Prepared {
+                Err(std::io::Error::new(std::io::ErrorKind::Other, "rejected").into())
+            } else {
+                Ok(())
+            }
+        }
+    };
+    (calls, Box::new(hook))
+}
+
+fn update_head(new: Target) -> crate::Result<RefEdit> {
+    Ok(RefEdit {
+        change: Change::Update {
+            log: LogChange::default(),
+            expected: PreviousValue::Any,
+            new,
+        },
+        name: "HEAD".try_into()?,
+        deref: true,
+    })
+}
+
+#[test]
+fn is_called_when_prepared_and_committed_with_the_referent_of_symbolic_refs() -> crate::Result {
+    let (_keep, store) = store_writable("make_repo_for_reflog.sh")?;
+    let (calls, hook) = recording_hook(false);
+    let new_id = hex_to_id("01dd4e2a978a9f5bd773dae6da7aa4a5ac1cdbbc");
+    store
+        .transaction()
+        .hook(hook)
+        .prepare(Some(update_head(Target::Peeled(new_id))?), Fail::Immediately)?
+        .commit(&committer())?;
+
+    let expected_lines = vec![BString::from(
+        "02a7a22d90d7c02fb494ed25551850b868e634f0 01dd4e2a978a9f5bd773dae6da7aa4a5ac1cdbbc refs/heads/main\n",
+    )];
+    assert_eq!(
+        *calls.lock().unwrap(),
+        vec![
+            (hook::State::Prepared, expected_lines.clone()),
+            (hook::State::Committed, expected_lines)
+        ],
+        "HEAD only has its reflog updated, which isn't shown to the hook"
+    );
+    Ok(())
+}
+
+#[test]
+fn creations_and_deletions_use_null_ids_and_symbolic_refs_are_prefixed() -> crate::Result {
+    let (_keep, store) = empty_store()?;
+    let (calls, hook) = recording_hook(false);
+    store
+        .transaction()
+        .hook(hook)
+        .prepare(
+            Some(RefEdit {
+                change: Change::Update {
+                    log: LogChange::default(),
+                    expected: PreviousValue::MustNotExist,
+                    new: Target::Symbolic("refs/heads/main".try_into()?),
+                },
+                name: "HEAD".try_into()?,
+                deref: false,
+            }),
+            Fail::Immediately,
+        )?
+        .commit(&committer())?;
+    assert_eq!(
+        calls.lock().unwrap()[1],
+        (
+            hook::State::Committed,
+            vec![BString::from(
+                "0000000000000000000000000000000000000000 ref:refs/heads/main HEAD\n"
+            )]
+        )
+    );
+
+    let (calls, hook) = recording_hook(false);
+    store
+        .transaction()
+        .hook(hook)
+        .prepare(
+            Some(RefEdit {
+                change: Change::Delete {
+                    expected: PreviousValue::Any,
+                    log: RefLog::AndReference,
+                },
+                name: "HEAD".try_into()?,
+                deref: false,
+            }),
+            Fail::Immediately,
+        )?
+        .commit(&committer())?;
+    assert_eq!(
+        calls.lock().unwrap()[1].1,
+        vec![BString::from(
+            "ref:refs/heads/main 0000000000000000000000000000000000000000 HEAD\n"
+        )]
+    );
+    Ok(())
+}
+
+#[test]
+fn rejection_when_prepared_aborts_the_transaction() -> crate::Result {
+    let (_keep, store) = store_writable("make_repo_for_reflog.sh")?;
+    let head_before = store.find_loose("refs/heads/main")?;
+    let (calls, hook) = recording_hook(true);
+    let res = store.transaction().hook(hook).prepare(
+        Some(update_head(Target::Peeled(hex_to_id(
+            "01dd4e2a978a9f5bd773dae6da7aa4a5ac1cdbbc",
+        )))?),
+        Fail::Immediately,
+    );
+    assert!(matches!(res, Err(transaction::prepare::Error::Hook(_))));
+    assert_eq!(
+        calls
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(state, _)| *state)
+            .collect::<Vec<_>>(),
+        vec![hook::State::Prepared, hook::State::Aborted]
+    );
+    assert_eq!(
+        store.find_loose("refs/heads/main")?,
+        head_before,
+        "the reference wasn't changed"
+    );
+    Ok(())
+}
+
+#[test]
+fn dropping_a_prepared_transaction_aborts_it() -> crate::Result {
+    let (_keep, store) = store_writable("make_repo_for_reflog.sh")?;
+    let (calls, hook) = recording_hook(false);
+    let transaction = store.transaction().hook(hook).prepare(
+        Some(update_head(Target::Peeled(hex_to_id(
+            "01dd4e2a978a9f5bd773dae6da7aa4a5ac1cdbbc",
+        )))?),
+        Fail::Immediately,
+    )?;
+    drop(transaction);
+    assert_eq!(
+        calls
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(state, _)| *state)
+            .collect::<Vec<_>>(),
+        vec![hook::State::Prepared, hook::State::Aborted]
+    );
+    Ok(())
+}
@@ -0,0 +1,244 @@
+use std::convert::TryInto;
+
+use git_lock::acquire::Fail;
+use git_ref::{
+    memory,
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+    Target,
+};
+use git_testtools::hex_to_id;
+
+fn update(name: &str, expected: PreviousValue, new: Target) -> RefEdit {
+    RefEdit {
+        change: Change::Update {
+            log: LogChange::default(),
+            expected,
+            new,
+        },
+        name: name.try_into().expect("valid name"),
+        deref: false,
+    }
+}
+
+fn peeled(hex: &str) -> Target {
+    Target::Peeled(hex_to_id(hex))
+}
+
+fn symbolic(name: &str) -> Target {
+    Target::Symbolic(name.try_into().expect("valid name"))
+}
+
+fn store_with_head_and_main() -> crate::Result<memory::Store> {
+    let store = memory::Store::default();
+    store
+        .transaction()
+        .prepare(
+            vec![
+                update("HEAD", PreviousValue::MustNotExist, symbolic("refs/heads/main")),
+                update(
+                    "refs/heads/main",
+                    PreviousValue::MustNotExist,
+                    peeled("134385f6d781b7e97062102c6a483440bfda2a03"),
+                ),
+            ],
+            Fail::Immediately,
+        )?
+        .commit();
+    Ok(store)
+}
+
+#[test]
+fn references_are_found_by_partial_name_like_in_the_file_store() -> crate::Result {
+    let store = store_with_head_and_main()?;
+    store
+        .transaction()
+        .prepare(
+            Some(update(
+                "refs/tags/v1",
+                PreviousValue::MustNotExist,
+                peeled("a4ddd5bd8ab3163e6ce5e0fa5c4d4e6fe3e1b2e6"),
+            )),
+            Fail::Immediately,
+        )?
+        .commit();
+
+    assert_eq!(store.find("HEAD")?.target, symbolic("refs/heads/main"));
+    assert_eq!(store.find("main")?.name.as_bstr(), "refs/heads/main");
+    assert_eq!(store.find("heads/main")?.name.as_bstr(), "refs/heads/main");
+    assert_eq!(store.find("v1")?.name.as_bstr(), "refs/tags/v1");
+    assert!(store.try_find("refs/heads/other")?.is_none());
+    assert!(matches!(
+        store.find("other").expect_err("missing"),
+        git_ref::file::find::existing::Error::NotFound(_)
+    ));
+    Ok(())
+}
+
+#[test]
+fn iteration_is_sorted_and_can_be_prefixed() -> crate::Result {
+    let store = store_with_head_and_main()?;
+    store
+        .transaction()
+        .prepare(
+            Some(update(
+                "refs/heads/dev",
+                PreviousValue::MustNotExist,
+                peeled("134385f6d781b7e97062102c6a483440bfda2a03"),
+            )),
+            Fail::Immediately,
+        )?
+        .commit();
+
+    let names: Vec<_> = store.iter().map(|r| r.name.as_bstr().to_string()).collect();
+    assert_eq!(
+        names,
+        vec!["refs/heads/dev", "refs/heads/main"],
+        "HEAD isn't in refs/ and thus not iterated"
+    );
+    let names: Vec<_> = store
+        .iter_prefixed("refs/heads/m")
+        .map(|r| r.name.as_bstr().to_string())
+        .collect();
+    assert_eq!(names, vec!["refs/heads/main"]);
+    Ok(())
+}
+
+#[test]
+fn updates_through_symbolic_refs_are_split_and_change_their_referent() -> crate::Result {
+    let store = store_with_head_and_main()?;
+    let new_id = "a4ddd5bd8ab3163e6ce5e0fa5c4d4e6fe3e1b2e6";
+    let mut edit = update(
+        "HEAD",
+        PreviousValue::MustExistAndMatch(peeled("134385f6d781b7e97062102c6a483440bfda2a03")),
+        peeled(new_id),
+    );
+    edit.deref = true;
+    let edits = store.transaction().prepare(Some(edit), Fail::Immediately)?.commit();
+
+    assert_eq!(edits.len(), 2, "the symbolic ref is split");
+    assert!(matches!(
+        &edits[0].change,
+        Change::Update { log, .. } if log.mode == RefLog::Only
+    ));
+    assert_eq!(edits[1].name.as_bstr(), "refs/heads/main");
+    assert_eq!(
+        edits[1].change.previous_value().map(|v| v.into_owned()),
+        Some(peeled("134385f6d781b7e97062102c6a483440bfda2a03")),
+        "previous values are filled in"
+    );
+    assert_eq!(
+        store.find("HEAD")?.target,
+        symbolic("refs/heads/main"),
+        "HEAD is unchanged"
+    );
+
+    let mut head = store.find("HEAD")?;
+    assert_eq!(
+        store.peel_to_id_in_place(&mut head, git_ref::peel::none)?,
+        hex_to_id(new_id)
+    );
+    Ok(())
+}
+
+#[test]
+fn failing_preconditions_leave_the_store_unchanged() -> crate::Result {
+    let store = store_with_head_and_main()?;
+    let err = store
+        .transaction()
+        .prepare(
+            vec![
+                update(
+                    "refs/heads/new",
+                    PreviousValue::MustNotExist,
+                    symbolic("refs/heads/main"),
+                ),
+                update(
+                    "refs/heads/main",
+                    PreviousValue::MustNotExist,
+                    peeled("a4ddd5bd8ab3163e6ce5e0fa5c4d4e6fe3e1b2e6"),
+                ),
+            ],
+            Fail::Immediately,
+        )
+        .err()
+        .expect("main exists");
+    assert!(matches!(err, memory::transaction::Error::MustNotExist { .. }));
+    assert!(store.try_find("new")?.is_none());
+
+    let transaction = store.transaction().prepare(
+        Some(update(
+            "refs/heads/new",
+            PreviousValue::Any,
+            symbolic("refs/heads/main"),
+        )),
+        Fail::Immediately,
+    )?;
+    drop(transaction);
+    assert!(
+        store.try_find("new")?.is_none(),
+        "dropping prepared transactions rolls back"
+    );
+    Ok(())
+}
+
+#[test]
+fn prepared_transactions_lock_the_store() -> crate::Result {
+    let store = store_with_head_and_main()?;
+    let _transaction = store.transaction().prepare(None, Fail::Immediately)?;
+    assert!(matches!(
+        store.transaction().prepare(None, Fail::Immediately).err(),
+        Some(memory::transaction::Error::Locked)
+    ));
+    Ok(())
+}
+
+#[test]
+fn deletions_remove_references() -> crate::Result {
+    let store = store_with_head_and_main()?;
+    store
+        .transaction()
+        .prepare(
+            Some(RefEdit {
+                change: Change::Delete {
+                    expected: PreviousValue::MustExist,
+                    log: RefLog::AndReference,
+                },
+                name: "refs/heads/main".try_into()?,
+                deref: false,
+            }),
+            Fail::Immediately,
+        )?
+        .commit();
+    assert!(store.try_find("main")?.is_none());
+    assert_eq!(store.iter().count(), 0);
+    assert!(store.try_find("HEAD")?.is_some(), "HEAD is left");
+    Ok(())
+}
+
+#[test]
+fn namespaces_partition_references() -> crate::Result {
+    let mut store = store_with_head_and_main()?;
+    store.namespace = git_ref::namespace::expand("foo")?.into();
+    assert!(store.try_find("main")?.is_none());
+    store
+        .transaction()
+        .prepare(
+            Some(update(
+                "refs/heads/main",
+                PreviousValue::MustNotExist,
+                peeled("a4ddd5bd8ab3163e6ce5e0fa5c4d4e6fe3e1b2e6"),
+            )),
+            Fail::Immediately,
+        )?
+        .commit();
+    assert_eq!(
+        store.find("main")?.target,
+        peeled("a4ddd5bd8ab3163e6ce5e0fa5c4d4e6fe3e1b2e6")
+    );
+    assert_eq!(store.iter().count(), 1);
+
+    store.namespace = None;
+    let names: Vec<_> = store.iter().map(|r| r.name.as_bstr().to_string()).collect();
+    assert_eq!(names, vec!["refs/heads/main", "refs/namespaces/foo/refs/heads/main"]);
+    Ok(())
+}
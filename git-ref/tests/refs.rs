@@ -2,6 +2,7 @@ type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
 
 mod file;
 mod fullname;
+mod memory;
 mod namespace;
 mod packed;
 mod reference;
@@ -0,0 +1,282 @@
+//! Stage files of the work tree, like `git add` does.
+use std::{borrow::Cow, collections::BTreeSet, convert::TryFrom, path::Path};
+
+use git_config::values::Boolean;
+use git_object::bstr::{BStr, BString, ByteSlice, ByteVec};
+use git_odb::Write;
+
+use crate::{
+    dirwalk, filter,
+    index::{entry, Entry},
+    Repository,
+};
+
+/// The error returned by [`Repository::add()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("A bare repository has no work tree to add files from")]
+    NoWorkTree,
+    #[error(transparent)]
+    Config(#[from] crate::config::Error),
+    #[error(transparent)]
+    Dirwalk(#[from] dirwalk::Error),
+    #[error(transparent)]
+    Index(#[from] crate::index::Error),
+    #[error("The path '{path}' can't be represented on this platform")]
+    IllformedPath { path: BString },
+    #[error("Could not read {}", path.display())]
+    Io {
+        source: std::io::Error,
+        path: std::path::PathBuf,
+    },
+    #[error(transparent)]
+    WriteBlob(#[from] git_odb::loose::write::Error),
+}
+
+/// The outcome of [`Repository::add()`], with all paths sorted.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// Paths that weren't in the index before.
+    pub added: Vec<BString>,
+    /// Paths whose content or mode changed.
+    pub modified: Vec<BString>,
+    /// Paths that were removed from the index as they don't exist in the work tree anymore.
+    pub removed: Vec<BString>,
+}
+
+impl Repository {
+    /// Stage all files matching `pathspecs` by writing their content as blobs and updating the index with their id, mode and
+    /// filesystem metadata, like `git add` does. Return which paths were added, modified or removed.
+    ///
+    /// Pathspecs are slash-separated paths relative to the work tree, matching the path itself and everything below it.
+    /// `.` or an empty pathspec match all files. Untracked files are found with [`Repository::dirwalk()`], which skips ignored
+    /// files, whereas tracked files are always updated. Tracked files that don't exist anymore are removed from the index.
+    /// Files whose metadata matches the one in the index aren't read again, unless they [might have changed][entry::Stat::is_racy()]
+    /// in the same timestamp granularity the index was written in.
+    ///
+    /// As for conversions, only end-of-line conversion of text files as configured by `core.autocrlf` is performed, and if
+    /// `core.fileMode` is false, the executable bit of tracked files is kept.
+    ///
+    /// Note that nested repositories are skipped, and that `.gitattributes` aren't consulted yet.
+    pub fn add<'a>(&self, pathspecs: impl IntoIterator<Item = &'a BStr>) -> Result<Outcome, Error> {
+        let work_tree = self.workdir().ok_or(Error::NoWorkTree)?;
+        let pathspecs: Vec<_> = pathspecs
+            .into_iter()
+            .map(|spec| spec.trim_end_with(|c| c == '/'))
+            .collect();
+        let is_selected = |path: &BStr| {
+            pathspecs.iter().any(|spec| {
+                spec.is_empty()
+                    || *spec == b"."
+                    || (path.starts_with(spec) && (path.len() == spec.len() || path[spec.len()] == b'/'))
+            })
+        };
+        let Conversion { autocrlf, file_mode } = Conversion::from_config(self)?;
+        let comparison = self.path_comparison()?;
+        let untracked = self.dirwalk(dirwalk::Options::default())?;
+        let index_time = self.index_timestamp();
+
+        self.edit_index(|state| {
+            let tracked = comparison.tracked_keys(state);
+            // Tracked files are known by their path in the index, even if the work tree spells it differently.
+            let mut paths: BTreeSet<BString> = untracked
+                .into_iter()
+                .filter(|e| matches!(e.kind, dirwalk::Kind::File | dirwalk::Kind::Symlink))
+                .map(|e| e.path)
+                .filter(|path| !tracked.contains(&*comparison.key(path.as_ref())))
+                .collect();
+            drop(tracked);
+            paths.extend(
+                state
+                    .entries()
+                    .iter()
+                    .filter(|e| e.mode != entry::Mode::Commit)
+                    .map(|e| e.path.clone()),
+            );
+
+            let mut out = Outcome::default();
+            for path in paths.into_iter().filter(|p| is_selected(p.as_ref())) {
+                let fs_path = work_tree.join(
+                    path.to_path()
+                        .map_err(|_| Error::IllformedPath { path: path.clone() })?,
+                );
+                let previous = comparison.entry_by_path(state, path.as_ref());
+                let meta = match std::fs::symlink_metadata(&fs_path) {
+                    Ok(meta) if meta.is_file() || meta.file_type().is_symlink() => Some(meta),
+                    Ok(_) => None,
+                    Err(err) if matches!(err.kind(), std::io::ErrorKind::NotFound) || is_not_a_directory(&err) => None,
+                    Err(source) => return Err(Error::Io { source, path: fs_path }),
+                };
+                let meta = match meta {
+                    Some(meta) => meta,
+                    None => {
+                        if state.remove(path.as_ref()) {
+                            out.removed.push(path);
+                        }
+                        continue;
+                    }
+                };
+
+                let stat = to_stat(&meta);
+                let mode = to_mode(&meta, file_mode, previous);
+                if let Some(previous) = previous {
+                    let is_racy = index_time.map_or(true, |index_time| previous.stat.is_racy(index_time));
+                    if previous.mode == mode && previous.stat.matches(&stat) && !is_racy {
+                        continue;
+                    }
+                }
+
+                let data = read_content(&fs_path, &meta, autocrlf)?;
+                let id = self
+                    .odb
+                    .write_stream_if_missing(
+                        git_object::Kind::Blob,
+                        data.len() as u64,
+                        data.as_slice(),
+                        None,
+                        self.hash_kind,
+                    )?
+                    .id()
+                    .to_owned();
+                match previous {
+                    None => out.added.push(path.clone()),
+                    Some(previous) if previous.id != id || previous.mode != mode => out.modified.push(path.clone()),
+                    Some(_) => {}
+                }
+                state.upsert(Entry {
+                    stat,
+                    ..Entry::from_cacheinfo(mode, id, path)
+                });
+            }
+            // All blobs must be durable before the index refers to them.
+            self.odb.sync_batch()?;
+            Ok(out)
+        })
+    }
+}
+
+/// How files in the work tree are converted into blobs and entries, as configured.
+pub(crate) struct Conversion {
+    /// If true, line endings of text files are converted to LF, which is the case if `core.autocrlf` is `true` or `input`.
+    pub autocrlf: bool,
+    /// If false, the executable bit of files is ignored, as configured by `core.fileMode`.
+    pub file_mode: bool,
+}
+
+impl Conversion {
+    pub(crate) fn from_config(repo: &Repository) -> Result<Self, crate::config::Error> {
+        let config = crate::config::open(repo)?;
+        let autocrlf = config
+            .value::<Cow<'_, [u8]>>("core", None, "autocrlf")
+            .ok()
+            .map_or(false, |value| {
+                value.eq_ignore_ascii_case(b"input")
+                    || Boolean::try_from(value).map_or(false, |b| matches!(b, Boolean::True(_)))
+            });
+        let file_mode = config
+            .value::<Boolean<'_>>("core", None, "fileMode")
+            .map_or(true, |b| matches!(b, Boolean::True(_)));
+        Ok(Conversion { autocrlf, file_mode })
+    }
+}
+
+/// Return the mode of the file with `meta`, keeping the executable bit of the `previous` entry unless `file_mode` is set.
+pub(crate) fn to_mode(meta: &std::fs::Metadata, file_mode: bool, previous: Option<&Entry>) -> entry::Mode {
+    if meta.file_type().is_symlink() {
+        entry::Mode::Symlink
+    } else if file_mode {
+        if is_executable(meta) {
+            entry::Mode::FileExecutable
+        } else {
+            entry::Mode::File
+        }
+    } else {
+        previous
+            .map(|e| e.mode)
+            .filter(|mode| *mode == entry::Mode::FileExecutable)
+            .unwrap_or(entry::Mode::File)
+    }
+}
+
+/// Read the content of the file at `path` as it is to be stored, with line endings converted to LF in text files if
+/// `autocrlf` is set.
+pub(crate) fn read_content(path: &Path, meta: &std::fs::Metadata, autocrlf: bool) -> Result<Vec<u8>, Error> {
+    let io_err = |source| Error::Io {
+        source,
+        path: path.to_owned(),
+    };
+    if meta.file_type().is_symlink() {
+        let target = std::fs::read_link(path).map_err(io_err)?;
+        return Ok(Vec::from_path_lossy(&target).into_owned());
+    }
+    let data = std::fs::read(path).map_err(io_err)?;
+    if autocrlf && !filter::is_binary(&data) && data.find(b"\r\n").is_some() {
+        Ok(data.replace(b"\r\n", b"\n"))
+    } else {
+        Ok(data)
+    }
+}
+
+pub(crate) fn is_not_a_directory(err: &std::io::Error) -> bool {
+    #[cfg(unix)]
+    {
+        err.raw_os_error() == Some(libc::ENOTDIR)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = err;
+        false
+    }
+}
+
+pub(crate) fn to_time(time: Option<std::time::SystemTime>) -> entry::Time {
+    let since_epoch = time
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .unwrap_or_default();
+    entry::Time {
+        secs: since_epoch.as_secs() as u32,
+        nsecs: since_epoch.subsec_nanos(),
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn to_stat(meta: &std::fs::Metadata) -> entry::Stat {
+    use std::os::unix::fs::MetadataExt;
+    entry::Stat {
+        ctime: entry::Time {
+            secs: meta.ctime() as u32,
+            nsecs: meta.ctime_nsec() as u32,
+        },
+        mtime: entry::Time {
+            secs: meta.mtime() as u32,
+            nsecs: meta.mtime_nsec() as u32,
+        },
+        dev: meta.dev() as u32,
+        ino: meta.ino() as u32,
+        uid: meta.uid(),
+        gid: meta.gid(),
+        size: meta.len() as u32,
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn to_stat(meta: &std::fs::Metadata) -> entry::Stat {
+    entry::Stat {
+        ctime: to_time(meta.created().ok()),
+        mtime: to_time(meta.modified().ok()),
+        size: meta.len() as u32,
+        ..Default::default()
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(meta: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o100 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_meta: &std::fs::Metadata) -> bool {
+    false
+}
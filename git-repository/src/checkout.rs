@@ -0,0 +1,354 @@
+//! Write the files of a tree into the work tree without losing data that isn't tracked.
+//!
+//! Before anything is written, all files are checked for [conflicts][Conflict]: paths that would end up in the same file on
+//! the filesystem at hand, untracked files that would be overwritten and files that would be written through a symbolic link.
+//! If there is any conflict, nothing is written unless [forced][Options::force].
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use git_features::progress::{self, Progress};
+use git_hash::ObjectId;
+use git_object::{
+    bstr::{BStr, BString, ByteSlice},
+    tree::EntryMode,
+};
+use git_odb::FindExt;
+use git_traverse::tree::{breadthfirst, recorder::Entry};
+
+use crate::{ext::TreeIterExt, Repository};
+
+/// Options for [`Repository::checkout()`].
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// The tree which is currently checked out, whose files are considered tracked and thus may be overwritten or removed.
+    /// If `None`, all existing files are considered untracked.
+    pub previous: Option<ObjectId>,
+    /// If true, write files despite conflicts, which overwrites untracked files, replaces symbolic links in the way with directories
+    /// and lets the last of colliding paths win. Conflicts are reported nonetheless.
+    pub force: bool,
+}
+
+/// A reason for not writing the files of a tree as it might lose data or write outside of the work tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    /// All of `paths` in the tree refer to the same file according to the repository's [path comparison][crate::fs::PathComparison],
+    /// like `README` and `readme` on case-insensitive filesystems.
+    Collision {
+        /// The colliding paths in the order they appear in the tree.
+        paths: Vec<BString>,
+    },
+    /// An untracked file or non-empty directory at `path` would be overwritten, either by a file or by a directory.
+    UntrackedFile {
+        /// The path of the file relative to the work tree.
+        path: BString,
+    },
+    /// The file at `path` would be written through the untracked symbolic link at `link`, which is one of its leading directories.
+    Symlink {
+        /// The path of the file that would be written.
+        path: BString,
+        /// The path of the symbolic link relative to the work tree.
+        link: BString,
+    },
+}
+
+/// The outcome of [`Repository::checkout()`].
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// All conflicts found before writing any file. If not empty, nothing was written unless forced.
+    pub conflicts: Vec<Conflict>,
+    /// The amount of files and symbolic links that were written.
+    pub files_written: usize,
+    /// The amount of symbolic links that were written, which are included in [`files_written`][Outcome::files_written].
+    pub symlinks_written: usize,
+    /// The amount of bytes written into files, or used as targets of symbolic links.
+    pub bytes_written: u64,
+    /// The amount of files of the previous tree which were removed as they aren't contained in the new tree.
+    pub files_removed: usize,
+}
+
+impl Outcome {
+    /// Return the paths of all [collisions][Conflict::Collision], one group of paths referring to the same file at a time.
+    pub fn collisions(&self) -> impl Iterator<Item = &[BString]> {
+        self.conflicts.iter().filter_map(|conflict| match conflict {
+            Conflict::Collision { paths } => Some(paths.as_slice()),
+            _ => None,
+        })
+    }
+}
+
+/// The error returned by [`Repository::checkout()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Cannot checkout files in a bare repository")]
+    NoWorkTree,
+    #[error(transparent)]
+    Config(#[from] crate::config::Error),
+    #[error(transparent)]
+    FindTree(#[from] git_pack::find::existing_iter::Error<git_odb::compound::find::Error>),
+    #[error(transparent)]
+    FindBlob(#[from] git_pack::find::existing_object::Error<git_odb::compound::find::Error>),
+    #[error(transparent)]
+    Traverse(#[from] breadthfirst::Error),
+    #[error("The path '{}' can't be represented on this platform", .path)]
+    IllformedPath { path: BString },
+    #[error("Could not write '{}'", .path.display())]
+    Io { source: std::io::Error, path: PathBuf },
+}
+
+impl Repository {
+    /// Write all files of `tree` into the work tree, replacing the files of the [previously checked out tree][Options::previous]
+    /// and removing the ones which aren't part of `tree` anymore.
+    ///
+    /// Paths are compared as configured by [`path_comparison()`][Repository::path_comparison()] to detect collisions and
+    /// whether files are tracked. If [conflicts][Outcome::conflicts] are detected, nothing is written unless [forced][Options::force].
+    /// Untracked files which already have the desired content don't conflict.
+    ///
+    /// `progress` receives a child for the amount of `files` written out of all files in `tree`, and one for the amount
+    /// of `bytes` written.
+    ///
+    /// Note that neither an index nor `HEAD` are updated.
+    pub fn checkout(
+        &self,
+        tree: impl Into<ObjectId>,
+        options: Options,
+        mut progress: impl Progress,
+    ) -> Result<Outcome, Error> {
+        let work_tree = self.work_tree.as_deref().ok_or(Error::NoWorkTree)?;
+        let comparison = self.path_comparison()?;
+        let entries = self.files_of(tree.into())?;
+        let previous: HashMap<BString, Entry> = match options.previous {
+            Some(previous) => self
+                .files_of(previous)?
+                .into_iter()
+                .map(|entry| (comparison.key(entry.filepath.as_ref()).into_owned(), entry))
+                .collect(),
+            None => HashMap::new(),
+        };
+        let is_tracked = |path: &BStr, link_only: bool| {
+            previous
+                .get(comparison.key(path).as_ref())
+                .map_or(false, |entry| !link_only || entry.mode == EntryMode::Link)
+        };
+
+        let mut out = Outcome::default();
+        let mut push = |conflict: Conflict| {
+            if !out.conflicts.contains(&conflict) {
+                out.conflicts.push(conflict);
+            }
+        };
+        for paths in comparison.collisions(entries.iter().map(|e| e.filepath.as_ref())) {
+            push(Conflict::Collision {
+                paths: paths.into_iter().map(ToOwned::to_owned).collect(),
+            });
+        }
+        let mut buf = Vec::new();
+        'entries: for entry in &entries {
+            let mut leading = entry.filepath.split_str("/").peekable();
+            let mut prefix = BString::default();
+            while let Some(component) = leading.next() {
+                if leading.peek().is_none() {
+                    break;
+                }
+                if !prefix.is_empty() {
+                    prefix.push(b'/');
+                }
+                prefix.extend_from_slice(component);
+                match std::fs::symlink_metadata(to_path(work_tree, prefix.as_ref())?) {
+                    Ok(meta) if meta.file_type().is_symlink() && !is_tracked(prefix.as_ref(), true) => {
+                        push(Conflict::Symlink {
+                            path: entry.filepath.clone(),
+                            link: prefix.clone(),
+                        });
+                        continue 'entries;
+                    }
+                    Ok(meta) if !meta.is_dir() && !is_tracked(prefix.as_ref(), false) => {
+                        push(Conflict::UntrackedFile { path: prefix.clone() });
+                        continue 'entries;
+                    }
+                    Ok(_) => {}
+                    Err(_) => continue 'entries,
+                }
+            }
+            let path = to_path(work_tree, entry.filepath.as_ref())?;
+            let is_conflict = match std::fs::symlink_metadata(&path) {
+                Ok(meta) if meta.is_dir() => {
+                    entry.mode != EntryMode::Commit
+                        && std::fs::read_dir(&path)
+                            .map_err(|err| io_err(err, &path))?
+                            .next()
+                            .is_some()
+                }
+                Ok(meta) => {
+                    !is_tracked(entry.filepath.as_ref(), false) && !self.has_content(entry, &meta, &path, &mut buf)?
+                }
+                Err(_) => false,
+            };
+            if is_conflict {
+                push(Conflict::UntrackedFile {
+                    path: entry.filepath.clone(),
+                });
+            }
+        }
+        if !out.conflicts.is_empty() && !options.force {
+            return Ok(out);
+        }
+
+        let start = Instant::now();
+        let mut files = progress.add_child("files");
+        files.init(Some(entries.len()), progress::count("files"));
+        let mut bytes = progress.add_child("bytes");
+        bytes.init(None, progress::bytes());
+        let mut written = HashSet::new();
+        for entry in &entries {
+            files.inc();
+            written.insert(comparison.key(entry.filepath.as_ref()).into_owned());
+            let path = to_path(work_tree, entry.filepath.as_ref())?;
+            create_leading_directories(work_tree, &path)?;
+            if entry.mode == EntryMode::Commit {
+                if !path.is_dir() {
+                    std::fs::create_dir(&path).map_err(|err| io_err(err, &path))?;
+                }
+                continue;
+            }
+            match std::fs::symlink_metadata(&path) {
+                Ok(meta) if meta.is_dir() => std::fs::remove_dir_all(&path),
+                Ok(_) => std::fs::remove_file(&path),
+                Err(_) => Ok(()),
+            }
+            .map_err(|err| io_err(err, &path))?;
+            let data = self
+                .odb
+                .find_blob(entry.oid, &mut buf, &mut git_pack::cache::Never)?
+                .data;
+            write_entry(entry.mode, data, &path).map_err(|err| io_err(err, &path))?;
+            out.files_written += 1;
+            if entry.mode == EntryMode::Link {
+                out.symlinks_written += 1;
+            }
+            out.bytes_written += data.len() as u64;
+            bytes.inc_by(data.len());
+        }
+        files.show_throughput(start);
+        bytes.show_throughput(start);
+        let canonical_work_tree = work_tree.canonicalize().map_err(|err| io_err(err, work_tree))?;
+        for (key, entry) in previous {
+            if written.contains(&key) || entry.mode == EntryMode::Commit {
+                continue;
+            }
+            let path = to_path(work_tree, entry.filepath.as_ref())?;
+            let is_below_work_tree = path
+                .parent()
+                .and_then(|dir| dir.canonicalize().ok())
+                .map_or(false, |dir| dir.starts_with(&canonical_work_tree));
+            if is_below_work_tree && std::fs::remove_file(&path).is_ok() {
+                out.files_removed += 1;
+                let mut dir = path.parent();
+                while let Some(parent) = dir.filter(|dir| *dir != work_tree) {
+                    if std::fs::remove_dir(parent).is_err() {
+                        break;
+                    }
+                    dir = parent.parent();
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Return all non-tree entries of `tree`, recursively.
+    fn files_of(&self, tree: ObjectId) -> Result<Vec<Entry>, Error> {
+        let mut buf = Vec::new();
+        let mut recorder = git_traverse::tree::Recorder::default();
+        self.odb
+            .find_tree_iter(tree, &mut buf, &mut git_pack::cache::Never)?
+            .traverse(
+                breadthfirst::State::default(),
+                |oid, buf| self.odb.find_tree_iter(oid, buf, &mut git_pack::cache::Never).ok(),
+                &mut recorder,
+            )?;
+        let mut files: Vec<_> = recorder.records.into_iter().filter(|e| !e.mode.is_tree()).collect();
+        files.sort_by(|a, b| a.filepath.cmp(&b.filepath));
+        Ok(files)
+    }
+
+    /// Return true if the existing file at `path` with `meta`data already is what `entry` would write.
+    fn has_content(
+        &self,
+        entry: &Entry,
+        meta: &std::fs::Metadata,
+        path: &Path,
+        buf: &mut Vec<u8>,
+    ) -> Result<bool, Error> {
+        if entry.mode == EntryMode::Commit {
+            return Ok(false);
+        }
+        let desired = self.odb.find_blob(entry.oid, buf, &mut git_pack::cache::Never)?.data;
+        Ok(match entry.mode {
+            EntryMode::Link if meta.file_type().is_symlink() => std::fs::read_link(path)
+                .map_err(|err| io_err(err, path))?
+                .to_str()
+                .map_or(false, |target| target.as_bytes() == desired),
+            EntryMode::Blob | EntryMode::BlobExecutable if meta.is_file() => {
+                meta.len() == desired.len() as u64 && std::fs::read(path).map_err(|err| io_err(err, path))? == desired
+            }
+            _ => false,
+        })
+    }
+}
+
+fn to_path(work_tree: &Path, path: &BStr) -> Result<PathBuf, Error> {
+    let relative = path
+        .to_path()
+        .map_err(|_| Error::IllformedPath { path: path.to_owned() })?;
+    Ok(work_tree.join(relative))
+}
+
+fn io_err(source: std::io::Error, path: &Path) -> Error {
+    Error::Io {
+        source,
+        path: path.to_owned(),
+    }
+}
+
+/// Create all leading directories of `path` below `work_tree`, replacing files and symbolic links in the way.
+fn create_leading_directories(work_tree: &Path, path: &Path) -> Result<(), Error> {
+    let relative = path
+        .parent()
+        .and_then(|dir| dir.strip_prefix(work_tree).ok())
+        .expect("paths are within the work tree");
+    let mut dir = work_tree.to_owned();
+    for component in relative.components() {
+        dir.push(component);
+        match std::fs::symlink_metadata(&dir) {
+            Ok(meta) if meta.is_dir() => continue,
+            Ok(_) => std::fs::remove_file(&dir).map_err(|err| io_err(err, &dir))?,
+            Err(_) => {}
+        }
+        std::fs::create_dir(&dir).map_err(|err| io_err(err, &dir))?;
+    }
+    Ok(())
+}
+
+fn write_entry(mode: EntryMode, data: &[u8], path: &Path) -> std::io::Result<()> {
+    match mode {
+        #[cfg(unix)]
+        EntryMode::Link => std::os::unix::fs::symlink(data.to_path().map_err(|_| illformed_link())?, path),
+        _ => {
+            std::fs::write(path, data)?;
+            #[cfg(unix)]
+            if mode == EntryMode::BlobExecutable {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(unix)]
+fn illformed_link() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::Other, "symbolic link target isn't a valid path")
+}
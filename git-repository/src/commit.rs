@@ -1,2 +1,139 @@
+use git_hash::ObjectId;
+use git_odb::{FindExt, Write};
+use git_ref::{
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+    Target,
+};
+
+use crate::{easy::ext::ReferenceAccessExt, Repository};
+
 /// An empty array of a type usable with the `git::easy` API to help declaring no parents should be used
 pub const NO_PARENT_IDS: [git_hash::ObjectId; 0] = [];
+
+///
+pub mod amend {
+    use git_hash::ObjectId;
+    use git_object::bstr::BString;
+
+    /// The parts of a commit to change when [amending][crate::Repository::amend()] it, leaving all others as they are.
+    #[derive(Default, Debug, Clone, PartialEq, Eq)]
+    pub struct Changes {
+        /// The new commit message.
+        pub message: Option<BString>,
+        /// The new tree.
+        pub tree: Option<ObjectId>,
+        /// The new author.
+        pub author: Option<git_actor::Signature>,
+        /// The new committer, which is also used in the reference log.
+        pub committer: Option<git_actor::Signature>,
+    }
+
+    /// The outcome of [`Repository::amend()`][crate::Repository::amend()].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Outcome {
+        /// The commit which was replaced.
+        pub previous: ObjectId,
+        /// The replacement commit the reference now points to.
+        pub id: ObjectId,
+    }
+
+    /// The error returned by [`Repository::amend()`][crate::Repository::amend()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        FindReference(#[from] git_ref::file::find::existing::Error),
+        #[error(transparent)]
+        PackedRefsOpen(#[from] git_ref::packed::buffer::open::Error),
+        #[error("Reference {} points to {}, not to the commit {} to amend", .name, .actual, .expected)]
+        ReferenceMoved {
+            name: BString,
+            expected: ObjectId,
+            actual: ObjectId,
+        },
+        #[error(transparent)]
+        FindExistingCommit(#[from] git_pack::find::existing_object::Error<git_odb::compound::find::Error>),
+        #[error(transparent)]
+        WriteObject(#[from] git_odb::loose::write::Error),
+        #[error(transparent)]
+        EditReference(#[from] crate::easy::reference::edit::Error),
+    }
+}
+
+impl Repository {
+    /// Replace `commit`, which `reference` must point to, with a copy that has `changes` applied and keeps all parents, and
+    /// point `reference` to the copy. `reference` may be symbolic like `HEAD`, in which case the reference it points to is
+    /// changed as well. The reference log is written as canonical git would do, like `commit (amend): <summary>`.
+    ///
+    /// To protect against losing commits, the operation fails if `reference` doesn't point to `commit`, even if this changed
+    /// only while the reference was locked according to `lock_mode`.
+    /// Signatures of the replaced commit are dropped as they would be invalid.
+    pub fn amend(
+        &self,
+        reference: impl AsRef<str>,
+        commit: impl Into<ObjectId>,
+        changes: amend::Changes,
+        lock_mode: git_lock::acquire::Fail,
+    ) -> Result<amend::Outcome, amend::Error> {
+        let previous = commit.into();
+        let packed = self.refs.packed_buffer()?;
+        let reference = self.refs.find(reference.as_ref(), packed.as_ref())?;
+        let mut peeled = reference.clone();
+        while let Some(next) = self.refs.follow(&peeled, packed.as_ref()) {
+            peeled = next?;
+        }
+        match peeled.target.as_id() {
+            Some(actual) if actual == previous => {}
+            actual => {
+                return Err(amend::Error::ReferenceMoved {
+                    name: reference.name.into_inner(),
+                    expected: previous,
+                    actual: actual.map(ToOwned::to_owned).unwrap_or_else(ObjectId::null_sha1),
+                })
+            }
+        }
+
+        let mut buf = Vec::new();
+        let mut commit: git_object::Commit = self
+            .odb
+            .find_commit(previous, &mut buf, &mut git_pack::cache::Never)?
+            .into();
+        if let Some(message) = changes.message {
+            commit.message = message;
+        }
+        if let Some(tree) = changes.tree {
+            commit.tree = tree;
+        }
+        if let Some(author) = changes.author {
+            commit.author = author;
+        }
+        if let Some(committer) = changes.committer {
+            commit.committer = committer;
+        }
+        commit.extra_headers.retain(|(name, _)| name != "gpgsig");
+        let id = self.odb.write(&commit, self.hash_kind)?;
+
+        self.to_easy().edit_reference(
+            RefEdit {
+                change: Change::Update {
+                    log: LogChange {
+                        mode: RefLog::AndReference,
+                        force_create_reflog: false,
+                        message: crate::reference::log::message(
+                            "commit (amend)",
+                            commit.message.as_ref(),
+                            commit.parents.len(),
+                        ),
+                    },
+                    expected: PreviousValue::MustExistAndMatch(Target::Peeled(previous)),
+                    new: Target::Peeled(id),
+                },
+                name: reference.name,
+                deref: true,
+            },
+            lock_mode,
+            Some(&commit.committer),
+        )?;
+        Ok(amend::Outcome { previous, id })
+    }
+}
@@ -0,0 +1,250 @@
+//! Utilities to read the configuration of a repository and to alter its repository-local configuration file at `.git/config`.
+use std::{
+    borrow::Cow,
+    ffi::OsString,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use git_config::{
+    file::{GitConfig, GitConfigFromEnvError},
+    values::{Boolean, Integer},
+};
+use git_features::{fs::Fsync, parallel::threads};
+
+use crate::{bstr::ByteSlice, open::Permissions, Repository};
+
+/// The error returned when the repository configuration couldn't be read or written.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Open(#[from] git_config::parser::ParserOrIoError<'static>),
+    #[error("The configuration passed through the environment is invalid")]
+    FromEnv(#[from] GitConfigFromEnvError),
+    #[error("The lock for the configuration file could not be obtained")]
+    AcquireLock(#[from] git_lock::acquire::Error),
+    #[error("Could not write the configuration file")]
+    Write(#[from] std::io::Error),
+    #[error("Could not move the changed configuration file into place")]
+    Commit(#[from] git_lock::commit::Error<git_lock::File>),
+    #[error("The thread limit in 'gitoxide.threads' is invalid")]
+    InvalidThreadLimit(#[source] threads::ParseError),
+}
+
+/// The error returned if a zlib compression level is configured outside of the valid range.
+#[derive(Debug, thiserror::Error)]
+#[error("The compression level in '{key}' must be between -1 and 9, but was {value}")]
+pub struct InvalidCompressionLevel {
+    /// The configuration key holding the invalid value, like `core.compression`.
+    pub key: String,
+    /// The invalid value.
+    pub value: i64,
+}
+
+/// Return the zlib compression level configured in `section.key`, like `core.looseCompression`, falling back to
+/// `core.compression`, or `None` if neither is set. `-1` is zlib's default level, 6.
+pub(crate) fn compression_level(
+    config: &GitConfig<'_>,
+    (section, key): (&'static str, &'static str),
+) -> Result<Option<u32>, InvalidCompressionLevel> {
+    let ((section, key), value) = match config.value::<Integer>(section, None, key) {
+        Ok(value) => ((section, key), value),
+        Err(_) => match config.value::<Integer>("core", None, "compression") {
+            Ok(value) => (("core", "compression"), value),
+            Err(_) => return Ok(None),
+        },
+    };
+    match value.value {
+        -1 => Ok(Some(6)),
+        level @ 0..=9 => Ok(Some(level as u32)),
+        value => Err(InvalidCompressionLevel {
+            key: format!("{}.{}", section, key),
+            value,
+        }),
+    }
+}
+
+/// Return the policy for synchronizing written files to permanent storage as configured by `core.fsync` and
+/// `core.fsyncMethod`, or by the older `core.fsyncObjectFiles` if `core.fsync` isn't set.
+///
+/// The components listed in `core.fsync` are approximated: any metadata component like `reference`, `index` or `committed`
+/// synchronizes everything, otherwise any object component like `objects` or `pack` synchronizes objects. Components
+/// removed with a `-` prefix are ignored. With `core.fsyncMethod=batch`, loose objects are synchronized in batches and
+/// everything else is synchronized as well. If nothing is configured, nothing is synchronized.
+pub(crate) fn fsync(config: &GitConfig<'_>) -> Fsync {
+    let batch = config
+        .value::<Cow<'_, [u8]>>("core", None, "fsyncMethod")
+        .map_or(false, |method| method.as_ref() == b"batch");
+    let fsync = match config.value::<Cow<'_, [u8]>>("core", None, "fsync") {
+        Ok(components) => {
+            let components: Vec<_> = components
+                .split(|b| *b == b',')
+                .map(|component| component.trim())
+                .filter(|component| !component.is_empty() && !component.starts_with(b"-"))
+                .collect();
+            let has = |names: &[&[u8]]| components.iter().any(|component| names.contains(component));
+            if has(&[b"all", b"committed", b"added", b"reference", b"index"]) {
+                Fsync::Everything
+            } else if has(&[
+                b"objects",
+                b"loose-object",
+                b"pack",
+                b"derived-metadata",
+                b"pack-metadata",
+                b"commit-graph",
+            ]) {
+                Fsync::Objects
+            } else {
+                Fsync::None
+            }
+        }
+        Err(_) => match config.value::<Boolean<'_>>("core", None, "fsyncObjectFiles") {
+            Ok(Boolean::True(_)) => Fsync::Objects,
+            _ => Fsync::None,
+        },
+    };
+    if batch && fsync != Fsync::None {
+        Fsync::Batch
+    } else {
+        fsync
+    }
+}
+
+impl Repository {
+    /// Return the thread limit configured in `gitoxide.threads`, with the values understood by
+    /// [`Limit::from_str()`][threads::Limit::from_str()], or `None` if it isn't set.
+    ///
+    /// Operations of this repository use it if they aren't given a thread limit, before falling back to the
+    /// [process-wide default][threads::default()].
+    pub fn thread_limit(&self) -> Result<Option<threads::Limit>, Error> {
+        let config = open(self)?;
+        match config.value::<Cow<'_, [u8]>>("gitoxide", None, "threads") {
+            Ok(value) => value
+                .to_str_lossy()
+                .parse()
+                .map(Some)
+                .map_err(Error::InvalidThreadLimit),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+/// Read the configuration of `repo`, which is its own configuration file along with the system-wide and global
+/// configuration files, as well as the configuration passed through the environment, as far as its permissions allow.
+///
+/// Values of later files override the ones of earlier ones, in the order system, global, repository and environment.
+/// Repositories in memory don't have a configuration file of their own.
+pub(crate) fn open(repo: &Repository) -> Result<GitConfig<'static>, Error> {
+    let local_config = (!repo.is_in_memory()).then(|| repo.git_dir().join("config"));
+    open_layered(repo.permissions, local_config.as_deref())
+}
+
+/// Like [`open()`], but without a repository, reading only the configuration files outside of it and the environment
+/// as far as `permissions` allow. This is the configuration that can be trusted even if the repository itself can't be.
+pub(crate) fn open_external(permissions: Permissions) -> Result<GitConfig<'static>, Error> {
+    open_layered(permissions, None)
+}
+
+fn open_layered(permissions: Permissions, local_config: Option<&Path>) -> Result<GitConfig<'static>, Error> {
+    let env = |name: &str| std::env::var_os(name).filter(|_| permissions.env);
+    let mut paths = external_config_paths(permissions, env);
+    paths.extend(local_config.map(ToOwned::to_owned));
+    let mut config = GitConfig::new();
+    for path in paths.iter().filter(|p| p.is_file()) {
+        config.append(GitConfig::open(path)?);
+    }
+    if permissions.env {
+        if let Some(env_config) = GitConfig::from_env()? {
+            config.append(env_config);
+        }
+    }
+    Ok(config)
+}
+
+/// Return the paths to the system-wide and global configuration files as allowed by `permissions`, in the order they
+/// should be read, using `env` to obtain environment variables.
+fn external_config_paths(permissions: Permissions, env: impl Fn(&str) -> Option<OsString>) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if permissions.system_config && env("GIT_CONFIG_NOSYSTEM").is_none() {
+        paths.push(env("GIT_CONFIG_SYSTEM").map_or_else(|| PathBuf::from("/etc/gitconfig"), PathBuf::from));
+    }
+    if permissions.global_config {
+        match env("GIT_CONFIG_GLOBAL") {
+            Some(path) => paths.push(path.into()),
+            None => {
+                let home = env("HOME").map(PathBuf::from);
+                if let Some(xdg_config_home) = env("XDG_CONFIG_HOME")
+                    .map(PathBuf::from)
+                    .or_else(|| home.as_ref().map(|home| home.join(".config")))
+                {
+                    paths.push(xdg_config_home.join("git").join("config"));
+                }
+                if let Some(home) = home {
+                    paths.push(home.join(".gitconfig"));
+                }
+            }
+        }
+    }
+    paths
+}
+
+/// Lock the configuration file of the repository at `git_dir`, read it and pass it to `edit`, to finally write all changes back
+/// to disk atomically and return the value produced by `edit`.
+///
+/// The operation fails immediately if the lock can't be obtained.
+pub(crate) fn edit<T>(git_dir: &Path, edit: impl FnOnce(&mut GitConfig<'static>) -> T) -> Result<T, Error> {
+    let path = git_dir.join("config");
+    let mut lock = git_lock::File::acquire_to_update_resource(&path, git_lock::acquire::Fail::Immediately, None)?;
+    let mut config = GitConfig::open(&path)?;
+    let res = edit(&mut config);
+    lock.write_all(&Vec::<u8>::from(&config))?;
+    lock.commit()?;
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<OsString> + 'a {
+        move |name| vars.iter().find(|(n, _)| *n == name).map(|(_, v)| v.into())
+    }
+
+    #[test]
+    fn external_config_paths_depend_on_permissions_and_environment() {
+        let vars = [("HOME", "/home/user")];
+        assert_eq!(
+            external_config_paths(Permissions::all(), env(&vars)),
+            vec![
+                PathBuf::from("/etc/gitconfig"),
+                "/home/user/.config/git/config".into(),
+                "/home/user/.gitconfig".into()
+            ]
+        );
+        assert_eq!(
+            external_config_paths(Permissions::isolated(), env(&vars)),
+            Vec::<PathBuf>::new()
+        );
+        assert_eq!(
+            external_config_paths(Permissions::all(), env(&[])),
+            vec![PathBuf::from("/etc/gitconfig")],
+            "without the environment, the global configuration can't be found"
+        );
+
+        let vars = [
+            ("GIT_CONFIG_NOSYSTEM", "1"),
+            ("GIT_CONFIG_GLOBAL", "/custom/global"),
+            ("HOME", "/home/user"),
+        ];
+        assert_eq!(
+            external_config_paths(Permissions::all(), env(&vars)),
+            vec![PathBuf::from("/custom/global")]
+        );
+        let vars = [("GIT_CONFIG_SYSTEM", "/custom/system"), ("XDG_CONFIG_HOME", "/xdg")];
+        assert_eq!(
+            external_config_paths(Permissions::all(), env(&vars)),
+            vec![PathBuf::from("/custom/system"), "/xdg/git/config".into()]
+        );
+    }
+}
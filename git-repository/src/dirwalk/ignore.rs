@@ -0,0 +1,268 @@
+use std::sync::Arc;
+
+use git_object::bstr::{BStr, BString, ByteSlice};
+
+/// A single line of an ignore file like `.gitignore`, matched against paths like git does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    /// The glob to match, without negation, trailing slash or leading slash.
+    pub glob: BString,
+    /// If true, a match re-includes a path excluded by an earlier pattern.
+    pub negative: bool,
+    /// If true, only directories are matched.
+    pub directory_only: bool,
+    /// If true, the glob is matched against the path relative to the directory containing the ignore file, or only against
+    /// the file name otherwise.
+    pub anchored: bool,
+}
+
+impl Pattern {
+    /// Parse a single `line` of an ignore file, returning `None` for empty lines and comments.
+    pub fn from_line(line: &[u8]) -> Option<Self> {
+        let mut line = line.strip_suffix(b"\r").unwrap_or(line);
+        while line.ends_with(b" ") && !line.ends_with(b"\\ ") {
+            line = &line[..line.len() - 1];
+        }
+        if line.is_empty() || line[0] == b'#' {
+            return None;
+        }
+        let negative = line[0] == b'!';
+        if negative {
+            line = &line[1..];
+        }
+        let directory_only = line.ends_with(b"/");
+        if directory_only {
+            line = &line[..line.len() - 1];
+        }
+        let anchored = line.contains(&b'/');
+        line = line.strip_prefix(b"/").unwrap_or(line);
+        if line.is_empty() {
+            return None;
+        }
+        Some(Pattern {
+            glob: line.into(),
+            negative,
+            directory_only,
+            anchored,
+        })
+    }
+
+    /// Return true if `relative_path`, the path relative to the directory containing the ignore file, matches this pattern,
+    /// with `is_dir` indicating whether it's a directory and `ignore_case` allowing ASCII characters to differ in case.
+    ///
+    /// Note that the negation is not taken into account.
+    pub fn matches(&self, relative_path: &BStr, is_dir: bool, ignore_case: bool) -> bool {
+        if self.directory_only && !is_dir {
+            return false;
+        }
+        let text = if self.anchored {
+            relative_path.as_bytes()
+        } else {
+            relative_path
+                .rfind_byte(b'/')
+                .map_or(relative_path.as_bytes(), |pos| &relative_path[pos + 1..])
+        };
+        if ignore_case {
+            wildmatch(&self.glob.to_ascii_lowercase(), &text.to_ascii_lowercase())
+        } else {
+            wildmatch(&self.glob, text)
+        }
+    }
+}
+
+/// All patterns of one ignore file along with the directory they apply to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Patterns {
+    /// The directory containing the ignore file relative to the work tree, with trailing slash unless it's the work tree itself,
+    /// or empty for ignore files outside of the work tree which apply to all paths.
+    pub base: BString,
+    /// The patterns in the order they appear in the file.
+    pub patterns: Vec<Pattern>,
+}
+
+impl Patterns {
+    /// Parse the content of an ignore file whose patterns apply to paths below `base`.
+    pub fn from_bytes(base: impl Into<BString>, bytes: &[u8]) -> Self {
+        Patterns {
+            base: base.into(),
+            patterns: bytes.lines().filter_map(Pattern::from_line).collect(),
+        }
+    }
+}
+
+/// All ignore files applying to a directory, with the ones of higher precedence last.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Stack(pub Vec<Arc<Patterns>>);
+
+impl Stack {
+    /// Return true if `path` relative to the work tree is ignored according to the last pattern matching it.
+    pub fn is_ignored(&self, path: &BStr, is_dir: bool, ignore_case: bool) -> bool {
+        for patterns in self.0.iter().rev() {
+            let relative_path = match path.strip_prefix(patterns.base.as_bytes()) {
+                Some(path) => path.as_bstr(),
+                None => continue,
+            };
+            if let Some(pattern) = patterns
+                .patterns
+                .iter()
+                .rev()
+                .find(|p| p.matches(relative_path, is_dir, ignore_case))
+            {
+                return !pattern.negative;
+            }
+        }
+        false
+    }
+}
+
+/// Match `text` against `glob` with `*` and `?` not matching slashes, `**` matching across directories and `[…]` matching
+/// a character class.
+fn wildmatch(glob: &[u8], text: &[u8]) -> bool {
+    match glob.first() {
+        None => text.is_empty(),
+        Some(b'*') if glob.starts_with(b"**/") => {
+            let glob = &glob[3..];
+            wildmatch(glob, text)
+                || text
+                    .iter()
+                    .enumerate()
+                    .any(|(pos, b)| *b == b'/' && wildmatch(glob, &text[pos + 1..]))
+        }
+        Some(b'*') if glob == b"**" => true,
+        Some(b'*') => {
+            let glob = &glob[1..];
+            for pos in 0..=text.len() {
+                if wildmatch(glob, &text[pos..]) {
+                    return true;
+                }
+                if text.get(pos) == Some(&b'/') {
+                    break;
+                }
+            }
+            false
+        }
+        Some(b'?') => matches!(text.first(), Some(b) if *b != b'/') && wildmatch(&glob[1..], &text[1..]),
+        Some(b'[') => match character_class(&glob[1..]) {
+            Some((is_match, rest)) => match text.first() {
+                Some(b) if *b != b'/' && is_match(*b) => wildmatch(rest, &text[1..]),
+                _ => false,
+            },
+            None => text.first() == Some(&b'[') && wildmatch(&glob[1..], &text[1..]),
+        },
+        Some(b'\\') if glob.len() > 1 => text.first() == Some(&glob[1]) && wildmatch(&glob[2..], &text[1..]),
+        Some(b) => text.first() == Some(b) && wildmatch(&glob[1..], &text[1..]),
+    }
+}
+
+/// Parse the character class at the beginning of `glob`, just past its opening bracket, and return a function to match a byte
+/// along with the rest of the glob, or `None` if the class isn't terminated.
+fn character_class(glob: &[u8]) -> Option<(impl Fn(u8) -> bool + '_, &[u8])> {
+    let negated = matches!(glob.first(), Some(b'!') | Some(b'^'));
+    let start = usize::from(negated);
+    let end = glob
+        .iter()
+        .enumerate()
+        .skip(start + 1)
+        .find(|(_, b)| **b == b']')
+        .map(|(pos, _)| pos)?;
+    let class = &glob[start..end];
+    let is_match = move |b: u8| {
+        let mut pos = 0;
+        let mut found = false;
+        while pos < class.len() {
+            if pos + 2 < class.len() && class[pos + 1] == b'-' {
+                found |= (class[pos]..=class[pos + 2]).contains(&b);
+                pos += 3;
+            } else {
+                found |= class[pos] == b;
+                pos += 1;
+            }
+        }
+        found != negated
+    };
+    Some((is_match, &glob[end + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(line: &str, path: &str, is_dir: bool) -> bool {
+        Pattern::from_line(line.as_bytes())
+            .expect("valid pattern")
+            .matches(path.into(), is_dir, false)
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!(Pattern::from_line(b"# comment"), None);
+        assert_eq!(Pattern::from_line(b"   "), None);
+        assert_eq!(
+            Pattern::from_line(b"!/build/  "),
+            Some(Pattern {
+                glob: "build".into(),
+                negative: true,
+                directory_only: true,
+                anchored: true
+            })
+        );
+        assert_eq!(
+            Pattern::from_line(b"\\#not-a-comment").map(|p| p.glob),
+            Some("\\#not-a-comment".into())
+        );
+    }
+
+    #[test]
+    fn globs() {
+        assert!(
+            matches("*.o", "dir/file.o", false),
+            "unanchored patterns match the file name"
+        );
+        assert!(
+            !matches("/*.o", "dir/file.o", false),
+            "anchored patterns match the whole path"
+        );
+        assert!(matches("dir/*.o", "dir/file.o", false));
+        assert!(
+            !matches("dir/*.o", "dir/sub/file.o", false),
+            "stars don't match slashes"
+        );
+        assert!(matches("dir/**/*.o", "dir/file.o", false));
+        assert!(matches("dir/**/*.o", "dir/a/b/file.o", false));
+        assert!(matches("**/target", "a/b/target", true));
+        assert!(matches("dir/**", "dir/a/b", false));
+        assert!(!matches("dir/**", "dir", true));
+        assert!(matches("file.[oa]", "file.a", false));
+        assert!(matches("file.[!oa]", "file.c", false));
+        assert!(matches("file[0-9]", "file7", false));
+        assert!(matches("fil?", "file", false));
+        assert!(matches("target/", "target", true));
+        assert!(
+            !matches("target/", "target", false),
+            "trailing slashes only match directories"
+        );
+        assert!(matches("\\*", "*", false));
+        assert!(!matches("\\*", "a", false));
+    }
+
+    #[test]
+    fn precedence_and_case() {
+        let stack = Stack(vec![
+            Arc::new(Patterns::from_bytes("", b"*.log\n!keep.log\n")),
+            Arc::new(Patterns::from_bytes("sub/", b"keep.log\n/local\n")),
+        ]);
+        assert!(stack.is_ignored("a.log".into(), false, false));
+        assert!(!stack.is_ignored("keep.log".into(), false, false), "later patterns win");
+        assert!(
+            stack.is_ignored("sub/keep.log".into(), false, false),
+            "deeper files win"
+        );
+        assert!(stack.is_ignored("sub/local".into(), false, false));
+        assert!(
+            !stack.is_ignored("local".into(), false, false),
+            "patterns apply below their base only"
+        );
+        assert!(!stack.is_ignored("A.LOG".into(), false, false));
+        assert!(stack.is_ignored("A.LOG".into(), false, true));
+    }
+}
@@ -0,0 +1,306 @@
+//! Find all files in the work tree quickly by reading directories in parallel, while skipping what git would skip.
+//!
+//! The walk never enters `.git` directories or nested repositories, prunes directories excluded by `.gitignore`,
+//! `.git/info/exclude` and `core.excludesFile`, and can be kept from crossing into other filesystems.
+//! What's left are the candidates for untracked files as needed to compute the status or to add files.
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use git_features::parallel::{in_parallel_if, Reduce};
+use git_object::bstr::{BString, ByteSlice, ByteVec};
+
+use crate::Repository;
+
+///
+pub mod ignore;
+
+/// Options for [`Repository::dirwalk()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// If true, paths excluded by ignore files aren't emitted and ignored directories aren't entered.
+    /// Otherwise all paths are emitted as not ignored.
+    pub respect_ignore: bool,
+    /// If true, ignored paths are emitted as well, [marked as such][Entry::ignored], but ignored directories still aren't entered.
+    pub emit_ignored: bool,
+    /// If true, directories on another device than the work tree, like mount points, are emitted but not entered.
+    ///
+    /// This has no effect on platforms other than unix.
+    pub one_file_system: bool,
+    /// If set, don't use more than this amount of threads.
+    /// Otherwise, use the [thread limit of the repository][crate::Repository::thread_limit()] if it is configured,
+    /// or the [default thread limit][git_features::parallel::threads::default()].
+    /// A value of 0 is interpreted as no-limit
+    pub thread_limit: Option<usize>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            respect_ignore: true,
+            emit_ignored: false,
+            one_file_system: false,
+            thread_limit: None,
+        }
+    }
+}
+
+/// The kind of a path found in the work tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Kind {
+    /// A regular file, executable or not.
+    File,
+    /// A symbolic link, which is never followed.
+    Symlink,
+    /// A directory which wasn't entered, as it is ignored or on another device.
+    Directory,
+    /// A directory containing a `.git` file or directory, which is a repository on its own and thus wasn't entered.
+    Repository,
+}
+
+/// A path found in the work tree by [`Repository::dirwalk()`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entry {
+    /// The slash-separated path relative to the work tree.
+    pub path: BString,
+    /// The kind of the path.
+    pub kind: Kind,
+    /// If true, the path is excluded by an ignore file. It's only emitted if [`Options::emit_ignored`] is set.
+    pub ignored: bool,
+}
+
+/// The error returned by [`Repository::dirwalk()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("A bare repository has no work tree to walk")]
+    NoWorkTree,
+    #[error(transparent)]
+    Config(#[from] crate::config::Error),
+    #[error("The path {} can't be represented as path in git", path.display())]
+    IllformedPath { path: PathBuf },
+    #[error("Could not read {}", path.display())]
+    Io { source: std::io::Error, path: PathBuf },
+}
+
+impl Repository {
+    /// Walk the work tree and return all files, symbolic links and directories that weren't entered, sorted by path.
+    ///
+    /// Directories are read in parallel one level at a time, with `.git` directories and nested repositories skipped
+    /// as configured in `options`. Ignore files are matched as git does, also honoring `core.ignoreCase`, with patterns in
+    /// deeper `.gitignore` files taking precedence over the ones further up, followed by `.git/info/exclude` and finally
+    /// `core.excludesFile`, which defaults to `$XDG_CONFIG_HOME/git/ignore` as far as the repository's
+    /// [permissions][crate::open::Permissions] allow.
+    ///
+    /// Note that tracked files are returned as well as the index isn't consulted.
+    pub fn dirwalk(&self, options: Options) -> Result<Vec<Entry>, Error> {
+        let work_tree = self.workdir().ok_or(Error::NoWorkTree)?;
+        let ignore_case = self.path_comparison()?.ignore_case;
+        let ignores = if options.respect_ignore {
+            self.global_ignores()?
+        } else {
+            Default::default()
+        };
+        let device = if options.one_file_system {
+            device_of(work_tree)?
+        } else {
+            None
+        };
+
+        let thread_limit = match options.thread_limit {
+            Some(limit) => Some(limit),
+            None => self.thread_limit()?.map(|limit| limit.num_threads()),
+        };
+
+        let mut entries = Vec::new();
+        let mut dirs = vec![Dir {
+            path: work_tree.to_owned(),
+            rel: BString::default(),
+            ignores,
+        }];
+        while !dirs.is_empty() {
+            let num_dirs = dirs.len();
+            dirs = in_parallel_if(
+                || num_dirs > 1,
+                dirs.into_iter(),
+                thread_limit,
+                |_| (),
+                |dir, _| read_dir(dir, &options, ignore_case, device),
+                Collect {
+                    entries: &mut entries,
+                    dirs: Vec::new(),
+                },
+            )?;
+        }
+        entries.sort();
+        Ok(entries)
+    }
+
+    /// Read all ignore files of the repository that apply to every directory, with the one taking precedence last.
+    fn global_ignores(&self) -> Result<ignore::Stack, Error> {
+        let env = |name: &str| {
+            std::env::var_os(name)
+                .filter(|_| self.permissions.env)
+                .map(PathBuf::from)
+        };
+        let config = crate::config::open(self)?;
+        let excludes_file = match config.value::<std::borrow::Cow<'_, [u8]>>("core", None, "excludesFile") {
+            Ok(path) => match path.strip_prefix(b"~/") {
+                Some(path) => env("HOME").map(|home| home.join(path.to_path_lossy())),
+                None => Some(path.to_path_lossy().into_owned()),
+            },
+            Err(_) if self.permissions.global_config => env("XDG_CONFIG_HOME")
+                .or_else(|| env("HOME").map(|home| home.join(".config")))
+                .map(|config_home| config_home.join("git").join("ignore")),
+            Err(_) => None,
+        };
+
+        let mut stack = ignore::Stack::default();
+        for path in excludes_file
+            .into_iter()
+            .chain(Some(self.git_dir().join("info").join("exclude")))
+        {
+            if let Some(patterns) = read_patterns(&path, BString::default())? {
+                stack.0.push(patterns);
+            }
+        }
+        Ok(stack)
+    }
+}
+
+/// A directory to read, along with all ignore files applying to its parent.
+struct Dir {
+    path: PathBuf,
+    rel: BString,
+    ignores: ignore::Stack,
+}
+
+fn read_dir(
+    Dir { path, rel, mut ignores }: Dir,
+    options: &Options,
+    ignore_case: bool,
+    device: Option<u64>,
+) -> Result<(Vec<Entry>, Vec<Dir>), Error> {
+    let io_err = |path: &Path| {
+        let path = path.to_owned();
+        move |source| Error::Io { source, path }
+    };
+    if options.respect_ignore {
+        let base = if rel.is_empty() {
+            BString::default()
+        } else {
+            let mut base = rel.clone();
+            base.push(b'/');
+            base
+        };
+        if let Some(patterns) = read_patterns(&path.join(".gitignore"), base)? {
+            ignores.0.push(patterns);
+        }
+    }
+
+    let mut entries = Vec::new();
+    let mut dirs = Vec::new();
+    for entry in std::fs::read_dir(&path).map_err(io_err(&path))? {
+        let entry = entry.map_err(io_err(&path))?;
+        let entry_path = entry.path();
+        let name = Vec::from_os_string(entry.file_name()).map_err(|_| Error::IllformedPath {
+            path: entry_path.clone(),
+        })?;
+        if name == b".git" || (ignore_case && name.eq_ignore_ascii_case(b".git")) {
+            continue;
+        }
+        let mut rel_path = rel.clone();
+        if !rel_path.is_empty() {
+            rel_path.push(b'/');
+        }
+        rel_path.extend_from_slice(&name);
+
+        let file_type = entry.file_type().map_err(io_err(&entry_path))?;
+        let kind = if file_type.is_dir() {
+            if entry_path.join(".git").symlink_metadata().is_ok() {
+                Kind::Repository
+            } else {
+                Kind::Directory
+            }
+        } else if file_type.is_symlink() {
+            Kind::Symlink
+        } else if file_type.is_file() {
+            Kind::File
+        } else {
+            continue;
+        };
+        let ignored =
+            options.respect_ignore && ignores.is_ignored(rel_path.as_ref(), kind >= Kind::Directory, ignore_case);
+        if ignored && !options.emit_ignored {
+            continue;
+        }
+        let enter = kind == Kind::Directory && !ignored && (device.is_none() || device_of(&entry_path)? == device);
+        if enter {
+            dirs.push(Dir {
+                path: entry_path,
+                rel: rel_path,
+                ignores: ignores.clone(),
+            });
+        } else {
+            entries.push(Entry {
+                path: rel_path,
+                kind,
+                ignored,
+            });
+        }
+    }
+    Ok((entries, dirs))
+}
+
+/// Read the ignore file at `path` whose patterns apply below `base`, or return `None` if it doesn't exist.
+fn read_patterns(path: &Path, base: BString) -> Result<Option<Arc<ignore::Patterns>>, Error> {
+    match std::fs::read(path) {
+        Ok(bytes) => Ok(Some(Arc::new(ignore::Patterns::from_bytes(base, &bytes)))),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(source) => Err(Error::Io {
+            source,
+            path: path.to_owned(),
+        }),
+    }
+}
+
+#[cfg(unix)]
+fn device_of(path: &Path) -> Result<Option<u64>, Error> {
+    use std::os::unix::fs::MetadataExt;
+    path.symlink_metadata()
+        .map(|meta| Some(meta.dev()))
+        .map_err(|source| Error::Io {
+            source,
+            path: path.to_owned(),
+        })
+}
+
+#[cfg(not(unix))]
+fn device_of(_path: &Path) -> Result<Option<u64>, Error> {
+    Ok(None)
+}
+
+/// Collect the entries of all directories of one level, and return the directories of the next level.
+struct Collect<'a> {
+    entries: &'a mut Vec<Entry>,
+    dirs: Vec<Dir>,
+}
+
+impl<'a> Reduce for Collect<'a> {
+    type Input = Result<(Vec<Entry>, Vec<Dir>), Error>;
+    type FeedProduce = ();
+    type Output = Vec<Dir>;
+    type Error = Error;
+
+    fn feed(&mut self, item: Self::Input) -> Result<Self::FeedProduce, Self::Error> {
+        let (entries, dirs) = item?;
+        self.entries.extend(entries);
+        self.dirs.extend(dirs);
+        Ok(())
+    }
+
+    fn finalize(self) -> Result<Self::Output, Self::Error> {
+        Ok(self.dirs)
+    }
+}
@@ -0,0 +1,153 @@
+//! Copy objects borrowed from alternates into the repository and stop using them, similar to `git repack -a -d` followed by
+//! removing `objects/info/alternates` as done by `git clone --dissociate`.
+use std::{convert::Infallible, io::Write, sync::atomic::AtomicBool};
+
+use git_odb::{Find, FindExt};
+use git_pack::data::output;
+
+use crate::Repository;
+
+/// The outcome of [`Repository::dissociate()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// The amount of reachable objects that were only available in alternates and have been written into a new pack.
+    pub packed: usize,
+    /// The object directories which were removed from the alternates of this repository.
+    pub alternates: Vec<std::path::PathBuf>,
+}
+
+/// The error returned by [`Repository::dissociate()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    PackedRefsOpen(#[from] git_ref::packed::buffer::open::Error),
+    #[error(transparent)]
+    IterateReferences(#[from] git_ref::file::iter::loose_then_packed::Error),
+    #[error(transparent)]
+    FindReference(#[from] git_ref::file::find::Error),
+    #[error(transparent)]
+    PeelReference(#[from] git_ref::peel::to_id::Error),
+    #[error(transparent)]
+    FindExistingObject(#[from] git_pack::find::existing::Error<git_odb::compound::find::Error>),
+    #[error(transparent)]
+    CountObjects(
+        #[from]
+        output::count::objects::Error<
+            git_pack::find::existing::Error<git_odb::compound::find::Error>,
+            git_traverse::commit::ancestors::Error,
+        >,
+    ),
+    #[error(transparent)]
+    CreatePackEntry(#[from] output::entry::Error),
+    #[error(transparent)]
+    WritePackData(#[from] output::bytes::Error<Infallible>),
+    #[error(transparent)]
+    WritePack(#[from] git_pack::bundle::write::Error),
+    #[error(transparent)]
+    RemoveAlternate(#[from] git_odb::alternate::edit::Error),
+    #[error("Could not iterate references or write the pack")]
+    Io(#[from] std::io::Error),
+}
+
+impl Repository {
+    /// Write all objects reachable from `HEAD` and references that are only available through alternates into a new pack,
+    /// compressed with `compression_level` from 0 to 9, and remove all alternates afterwards.
+    ///
+    /// This makes a repository cloned with a reference repository independent of it. Note that the object database of this
+    /// instance isn't refreshed, so it will still use the alternates until the repository is opened again.
+    pub fn dissociate(&self, compression_level: u32, should_interrupt: &AtomicBool) -> Result<Outcome, Error> {
+        let mut alternates = Vec::new();
+        if self.odb.dbs.len() < 2 {
+            return Ok(Outcome { packed: 0, alternates });
+        }
+
+        let packed = self.refs.packed_buffer()?;
+        let mut buf = Vec::new();
+        let (mut tips, mut tags) = (Vec::new(), Vec::new());
+        let head = self.refs.try_find("HEAD", packed.as_ref())?;
+        for reference in head.into_iter().map(Ok).chain(self.refs.iter(packed.as_ref())?) {
+            let mut reference = reference?;
+            let target = match reference.target.as_id() {
+                Some(id) => id.to_owned(),
+                None => continue,
+            };
+            let id = self
+                .refs
+                .peel_to_id_in_place(&mut reference, packed.as_ref(), |oid, buf| {
+                    self.odb
+                        .try_find(oid, buf, &mut git_pack::cache::Never)
+                        .map(|obj| obj.map(|obj| (obj.kind, obj.data)))
+                })?;
+            if id != target {
+                tags.push(target);
+            }
+            match self.odb.find(id, &mut buf, &mut git_pack::cache::Never)?.kind {
+                git_object::Kind::Commit => tips.push(id),
+                _ => tags.push(id),
+            }
+        }
+
+        let ancestors = git_traverse::commit::Ancestors::new(
+            tips,
+            git_traverse::commit::ancestors::State::default(),
+            |oid, buf| self.odb.find_commit_iter(oid, buf, &mut git_pack::cache::Never).ok(),
+        );
+        let (counts, _) = output::count::objects_unthreaded(
+            &self.odb,
+            (&mut git_pack::cache::Never, &mut git_pack::cache::object::Never),
+            tags.into_iter().map(Ok).chain(ancestors),
+            git_features::progress::Discard,
+            should_interrupt,
+            output::count::objects::ObjectExpansion::TreeContents,
+        )?;
+
+        let own = &self.odb.dbs[0];
+        let entries = counts
+            .into_iter()
+            .filter(|count| !own.contains(count.id))
+            .map(|count| -> Result<_, Error> {
+                let obj = self.odb.find(count.id, &mut buf, &mut git_pack::cache::Never)?;
+                Ok(output::Entry::from_data_with_compression_level(
+                    &count,
+                    &obj,
+                    compression_level,
+                )?)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let num_entries = entries.len();
+        if num_entries != 0 {
+            let mut pack = Vec::new();
+            let mut writer = output::bytes::FromEntriesIter::new(
+                std::iter::once(Ok::<_, Infallible>(entries)),
+                &mut pack,
+                num_entries as u32,
+                git_pack::data::Version::V2,
+                self.hash_kind,
+            );
+            for res in writer.by_ref() {
+                res?;
+            }
+            writer.into_write().flush()?;
+            git_pack::Bundle::write_to_directory(
+                pack.as_slice(),
+                Some(self.objects_dir().join("pack")),
+                git_features::progress::Discard,
+                should_interrupt,
+                None,
+                Default::default(),
+            )?;
+        }
+
+        for db in &self.odb.dbs[1..] {
+            if git_odb::alternate::remove(self.objects_dir(), &db.loose.path)? {
+                alternates.push(db.loose.path.clone());
+            }
+        }
+        Ok(Outcome {
+            packed: num_entries,
+            alternates,
+        })
+    }
+}
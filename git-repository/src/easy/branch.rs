@@ -0,0 +1,392 @@
+//!
+use std::{borrow::Cow, convert::TryInto, io::Write};
+
+use git_hash::ObjectId;
+use git_protocol::refspec::{self, RefSpec};
+use git_ref::{
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+    FullName, Target,
+};
+
+use crate::{
+    bstr::{BString, ByteSlice},
+    easy,
+    easy::{ext::ReferenceAccessExt, Reference},
+};
+
+const DEFAULT_LOCK_MODE: git_lock::acquire::Fail = git_lock::acquire::Fail::Immediately;
+
+/// A platform to create, rename and delete branches and to learn about their upstream, created with
+/// [`ReferenceAccessExt::branches()`][easy::ext::ReferenceAccessExt::branches()].
+///
+/// Branches are identified by their short name, like `main`, which is expanded to `refs/heads/main`. All changes are
+/// applied to references, reference logs and the `branch.<name>` section of the repository configuration alike.
+pub struct Platform<'repo, A> {
+    pub(crate) access: &'repo A,
+}
+
+/// The upstream of a branch as configured in `branch.<name>.remote` and `branch.<name>.merge`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Upstream {
+    /// The name of the remote, or `.` if the upstream is a branch in this repository.
+    pub remote: BString,
+    /// The full name of the branch on the remote, like `refs/heads/main`.
+    pub merge: FullName,
+    /// The full name of the local reference tracking `merge`, like `refs/remotes/origin/main`, or `None` if none of the
+    /// fetch refspecs of `remote` map `merge` to a local reference.
+    pub tracking: Option<FullName>,
+}
+
+fn full_name(name: &str) -> Result<FullName, git_validate::refname::Error> {
+    format!("refs/heads/{}", name).try_into()
+}
+
+fn reflog_path(git_dir: &std::path::Path, name: &FullName) -> std::path::PathBuf {
+    git_dir.join("logs").join(name.to_path())
+}
+
+impl<'repo, A> Platform<'repo, A>
+where
+    A: easy::Access + Sized,
+{
+    /// Create the branch `name` pointing to `target`, adhering to `constraint`, which is most commonly
+    /// [`PreviousValue::MustNotExist`] or [`PreviousValue::Any`] to force overwriting an existing branch.
+    pub fn create(
+        &self,
+        name: &str,
+        target: impl Into<ObjectId>,
+        constraint: PreviousValue,
+    ) -> Result<Reference<'repo, A>, easy::reference::edit::Error> {
+        let target = target.into();
+        self.access.reference(
+            format!("refs/heads/{}", name),
+            target,
+            constraint,
+            format!("branch: Created from {}", target),
+        )
+    }
+
+    /// Rename the branch `name` to `new_name` while keeping its reference log, adhering to `constraint` when creating the
+    /// new branch, which is most commonly [`PreviousValue::MustNotExist`] to avoid overwriting an existing branch.
+    ///
+    /// The `branch.<name>` configuration is renamed along with it, and if `HEAD` points to `name` it will point to `new_name`
+    /// afterwards.
+    pub fn rename(
+        &self,
+        name: &str,
+        new_name: &str,
+        constraint: PreviousValue,
+    ) -> Result<Reference<'repo, A>, rename::Error> {
+        let old = full_name(name)?;
+        let new = full_name(new_name)?;
+        let target = self.access.find_reference(old.to_partial())?.detach().target;
+        let head_points_to_old = self.access.head()?.referent_name() == Some(old.to_ref());
+        let git_dir = self.access.repo()?.git_dir().to_owned();
+
+        let previous_log = match std::fs::read(reflog_path(&git_dir, &old)) {
+            Ok(log) => Some(log),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut edits = vec![
+            RefEdit {
+                change: Change::Update {
+                    log: LogChange {
+                        mode: RefLog::AndReference,
+                        force_create_reflog: previous_log.is_some(),
+                        message: format!("Branch: renamed {} to {}", old.as_bstr(), new.as_bstr()).into(),
+                    },
+                    expected: constraint,
+                    new: target.clone(),
+                },
+                name: new.clone(),
+                deref: false,
+            },
+            RefEdit {
+                change: Change::Delete {
+                    expected: PreviousValue::MustExistAndMatch(target.clone()),
+                    log: RefLog::AndReference,
+                },
+                name: old.clone(),
+                deref: false,
+            },
+        ];
+        if head_points_to_old {
+            edits.push(RefEdit {
+                change: Change::Update {
+                    log: Default::default(),
+                    expected: PreviousValue::MustExistAndMatch(Target::Symbolic(old.clone())),
+                    new: Target::Symbolic(new.clone()),
+                },
+                name: "HEAD".try_into().expect("valid"),
+                deref: false,
+            });
+        }
+        self.access.edit_references(edits, DEFAULT_LOCK_MODE, None)?;
+
+        if let Some(mut log) = previous_log {
+            let new_log_path = reflog_path(&git_dir, &new);
+            match std::fs::read(&new_log_path) {
+                Ok(appended) => log.extend_from_slice(&appended),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+            let mut file = git_lock::File::acquire_to_update_resource(
+                &new_log_path,
+                DEFAULT_LOCK_MODE,
+                Some(git_dir.join("logs")),
+            )?;
+            file.write_all(&log)?;
+            file.commit()?;
+        }
+
+        crate::config::edit(&git_dir, |config| {
+            config
+                .rename_section(
+                    "branch",
+                    name,
+                    git_config::parser::SectionHeaderName(Cow::Borrowed("branch")),
+                    Cow::Owned(new_name.to_owned()),
+                )
+                .ok()
+        })?;
+
+        Ok(easy::Reference {
+            inner: git_ref::Reference {
+                name: new,
+                target,
+                peeled: None,
+            },
+            access: self.access,
+        })
+    }
+
+    /// Delete the branch `name` along with its reference log and its `branch.<name>` configuration.
+    ///
+    /// The branch `HEAD` points to cannot be deleted.
+    pub fn delete(&self, name: &str) -> Result<(), delete::Error> {
+        let full_name = full_name(name)?;
+        if self.access.head()?.referent_name() == Some(full_name.to_ref()) {
+            return Err(delete::Error::CheckedOut { name: full_name });
+        }
+        self.access.edit_reference(
+            RefEdit {
+                change: Change::Delete {
+                    expected: PreviousValue::MustExist,
+                    log: RefLog::AndReference,
+                },
+                name: full_name,
+                deref: false,
+            },
+            DEFAULT_LOCK_MODE,
+            None,
+        )?;
+        let git_dir = self.access.repo()?.git_dir().to_owned();
+        crate::config::edit(&git_dir, |config| {
+            config.remove_section("branch", name);
+        })?;
+        Ok(())
+    }
+
+    /// Configure the upstream of branch `name` to be the branch `merge`, like `refs/heads/main`, on `remote`, which is the name
+    /// of a remote like `origin` or `.` to track a branch of this repository.
+    pub fn set_upstream(&self, name: &str, remote: &str, merge: &str) -> Result<(), upstream::set::Error> {
+        full_name(name)?;
+        let merge: FullName = merge.try_into()?;
+        let git_dir = self.access.repo()?.git_dir().to_owned();
+        crate::config::edit(&git_dir, |config| {
+            let mut section = match config.section_mut("branch", Some(name)) {
+                Ok(section) => section,
+                Err(_) => config.new_section("branch", Cow::Owned(name.to_owned())),
+            };
+            section.set(
+                git_config::parser::Key("remote".into()),
+                Cow::Owned(remote.as_bytes().to_owned()),
+            );
+            section.set(
+                git_config::parser::Key("merge".into()),
+                Cow::Owned(merge.into_inner().into()),
+            );
+        })?;
+        Ok(())
+    }
+
+    /// Return the upstream configured for branch `name`, or `None` if there is no upstream.
+    ///
+    /// Note that the tracking reference is derived from the `remote.<remote>.fetch` refspecs, but it might not exist.
+    pub fn upstream(&self, name: &str) -> Result<Option<Upstream>, upstream::Error> {
+        let config = crate::config::open(&*self.access.repo()?)?;
+        let (remote, merge) = match (
+            config.get_raw_value("branch", Some(name), "remote"),
+            config.get_raw_value("branch", Some(name), "merge"),
+        ) {
+            (Ok(remote), Ok(merge)) => (BString::from(remote.into_owned()), merge),
+            _ => return Ok(None),
+        };
+        let merge: FullName = merge.as_bstr().try_into()?;
+        let tracking = if remote == "." {
+            Some(merge.clone())
+        } else {
+            let specs = config
+                .get_raw_multi_value("remote", Some(&remote.to_str_lossy()), "fetch")
+                .unwrap_or_default()
+                .iter()
+                .map(|spec| {
+                    RefSpec::from_bytes(spec.as_bstr()).map_err(|source| upstream::Error::Refspec {
+                        remote: remote.clone(),
+                        spec: spec.as_bstr().into(),
+                        source,
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            refspec::map(&specs, Some(merge.as_bstr()))
+                .into_iter()
+                .find_map(|mapping| mapping.local)
+                .map(TryInto::try_into)
+                .transpose()?
+        };
+        Ok(Some(Upstream {
+            remote,
+            merge,
+            tracking,
+        }))
+    }
+
+    /// Count the commits reachable only from branch `name` and only from its upstream tracking branch, or return `None`
+    /// if there is no upstream or its tracking branch doesn't exist.
+    ///
+    /// See [`Repository::ahead_behind()`][crate::Repository::ahead_behind()] for details.
+    pub fn ahead_behind(&self, name: &str) -> Result<Option<crate::ahead_behind::Outcome>, ahead_behind::Error> {
+        let tracking = match self.upstream(name)?.and_then(|upstream| upstream.tracking) {
+            Some(tracking) => tracking,
+            None => return Ok(None),
+        };
+        let upstream_id = match self.access.try_find_reference(tracking.to_partial())? {
+            Some(r) => r.into_fully_peeled_id()?.detach(),
+            None => return Ok(None),
+        };
+        let local_id = self
+            .access
+            .find_reference(full_name(name)?.to_partial())?
+            .into_fully_peeled_id()?
+            .detach();
+
+        Ok(Some(self.access.repo()?.ahead_behind(local_id, upstream_id)?))
+    }
+}
+
+///
+pub mod rename {
+    use crate::easy;
+
+    /// The error returned by [`Platform::rename()`][super::Platform::rename()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Name(#[from] git_validate::refname::Error),
+        #[error(transparent)]
+        FindExisting(#[from] easy::reference::find::existing::Error),
+        #[error(transparent)]
+        ReferenceEdit(#[from] easy::reference::edit::Error),
+        #[error("Could not read or write the reference log")]
+        Io(#[from] std::io::Error),
+        #[error(transparent)]
+        ReflogLock(#[from] git_lock::acquire::Error),
+        #[error(transparent)]
+        ReflogCommit(#[from] git_lock::commit::Error<git_lock::File>),
+        #[error(transparent)]
+        Config(#[from] crate::config::Error),
+        #[error(transparent)]
+        BorrowRepo(#[from] easy::borrow::repo::Error),
+    }
+}
+
+///
+pub mod delete {
+    use crate::easy;
+
+    /// The error returned by [`Platform::delete()`][super::Platform::delete()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Name(#[from] git_validate::refname::Error),
+        #[error("Refusing to delete branch '{}' as HEAD points to it", .name.as_bstr())]
+        CheckedOut { name: git_ref::FullName },
+        #[error(transparent)]
+        FindHead(#[from] easy::reference::find::existing::Error),
+        #[error(transparent)]
+        ReferenceEdit(#[from] easy::reference::edit::Error),
+        #[error(transparent)]
+        Config(#[from] crate::config::Error),
+        #[error(transparent)]
+        BorrowRepo(#[from] easy::borrow::repo::Error),
+    }
+}
+
+///
+pub mod upstream {
+    use crate::easy;
+
+    ///
+    pub mod set {
+        use crate::easy;
+
+        /// The error returned by [`Platform::set_upstream()`][crate::easy::branch::Platform::set_upstream()].
+        #[derive(Debug, thiserror::Error)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            #[error(transparent)]
+            Name(#[from] git_validate::refname::Error),
+            #[error(transparent)]
+            Config(#[from] crate::config::Error),
+            #[error(transparent)]
+            BorrowRepo(#[from] easy::borrow::repo::Error),
+        }
+    }
+
+    /// The error returned by [`Platform::upstream()`][super::Platform::upstream()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Name(#[from] git_validate::refname::Error),
+        #[error(transparent)]
+        Config(#[from] crate::config::Error),
+        #[error("The fetch refspec '{spec}' of remote '{remote}' is invalid")]
+        Refspec {
+            remote: crate::bstr::BString,
+            spec: crate::bstr::BString,
+            source: git_protocol::refspec::Error,
+        },
+        #[error(transparent)]
+        BorrowRepo(#[from] easy::borrow::repo::Error),
+    }
+}
+
+///
+pub mod ahead_behind {
+    use crate::easy;
+
+    /// The error returned by [`Platform::ahead_behind()`][super::Platform::ahead_behind()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Name(#[from] git_validate::refname::Error),
+        #[error(transparent)]
+        Upstream(#[from] super::upstream::Error),
+        #[error(transparent)]
+        Find(#[from] easy::reference::find::Error),
+        #[error(transparent)]
+        FindExisting(#[from] easy::reference::find::existing::Error),
+        #[error(transparent)]
+        Peel(#[from] easy::reference::peel::Error),
+        #[error(transparent)]
+        AheadBehind(#[from] crate::ahead_behind::Error),
+        #[error(transparent)]
+        BorrowRepo(#[from] easy::borrow::repo::Error),
+    }
+}
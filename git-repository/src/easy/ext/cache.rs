@@ -47,15 +47,19 @@ pub trait CacheAccessExt: easy::Access + Sized {
     /// the cache efficiency is low. Use `GITOXIDE_PACK_CACHE_MEMORY_IN_BYTES=512000` to use up to 512MB of RAM for the pack delta base
     /// cache. If none of these are set, the default cache is fast enough to nearly never cause a (marginal) slow-down while providing
     /// some gains most of the time. Note that the value given is _per-thread_.
+    ///
+    /// The environment is ignored if the repository was opened without [permission][crate::open::Permissions::env] to use it.
     fn apply_environment(self) -> easy::borrow::state::Result<Self> {
         #[cfg(not(feature = "max-performance"))]
         let pack_cache = git_pack::cache::Never;
         #[cfg(feature = "max-performance")]
         let pack_cache: crate::easy::PackCache = {
-            if std::env::var_os("GITOXIDE_DISABLE_PACK_CACHE").is_some() {
+            let use_env = self.repo().map_or(false, |repo| repo.permissions.env);
+            if use_env && std::env::var_os("GITOXIDE_DISABLE_PACK_CACHE").is_some() {
                 Box::new(git_pack::cache::Never)
             } else if let Some(num_bytes) = std::env::var("GITOXIDE_PACK_CACHE_MEMORY_IN_BYTES")
                 .ok()
+                .filter(|_| use_env)
                 .and_then(|v| <usize as std::str::FromStr>::from_str(&v).ok())
             {
                 Box::new(git_pack::cache::lru::MemoryCappedHashmap::new(num_bytes))
@@ -119,17 +119,23 @@ pub trait ObjectAccessExt: easy::Access + Sized {
         message: impl AsRef<str>,
         constraint: PreviousValue,
     ) -> Result<Reference<'_, Self>, tag::Error> {
-        // NOTE: This could be more efficient if we use a TagRef instead.
-        let tag = git_object::Tag {
-            target: target.as_ref().into(),
-            target_kind,
-            name: name.as_ref().into(),
-            tagger: tagger.map(|t| t.to_owned()),
-            message: message.as_ref().into(),
-            pgp_signature: None,
-        };
-        let tag_id = self.write_object(&tag)?;
-        super::ReferenceAccessExt::tag_reference(self, name, tag_id, constraint).map_err(Into::into)
+        write_tag(self, name, target, target_kind, tagger, message, constraint, None)
+    }
+
+    /// Like [`tag()`][ObjectAccessExt::tag()], but sign the tag object with `sign` before writing it, appending the signature
+    /// to its message. Signers running `gpg` or `ssh-keygen` are available in the [`sign`][crate::sign] module.
+    #[allow(clippy::too_many_arguments)]
+    fn tag_signed(
+        &self,
+        name: impl AsRef<str>,
+        target: impl AsRef<oid>,
+        target_kind: git_object::Kind,
+        tagger: Option<&git_actor::SignatureRef<'_>>,
+        message: impl AsRef<str>,
+        constraint: PreviousValue,
+        sign: &mut crate::sign::Fn,
+    ) -> Result<Reference<'_, Self>, tag::Error> {
+        write_tag(self, name, target, target_kind, tagger, message, constraint, Some(sign))
     }
 
     /// Create a new commit object with `author`, `committer` and `message` referring to `tree` with `parents`, and point `reference`
@@ -156,59 +162,123 @@ pub trait ObjectAccessExt: easy::Access + Sized {
         Name: TryInto<FullName, Error = E>,
         commit::Error: From<E>,
     {
-        use git_ref::{
-            transaction::{Change, RefEdit},
-            Target,
-        };
+        write_commit(self, reference, author, committer, message, tree, parents, None)
+    }
 
-        use crate::easy::ext::ReferenceAccessExt;
-
-        // TODO: possibly use CommitRef to save a few allocations (but will have to allocate for object ids anyway.
-        //       This can be made vastly more efficient though if we wanted to, so we lie in the API
-        let reference = reference.try_into()?;
-        let commit = git_object::Commit {
-            message: message.as_ref().into(),
-            tree: tree.into(),
-            author: author.to_owned(),
-            committer: committer.to_owned(),
-            encoding: None,
-            parents: parents.into_iter().map(|id| id.into()).collect(),
-            extra_headers: Default::default(),
-        };
+    /// Like [`commit()`][ObjectAccessExt::commit()], but sign the commit object with `sign` before writing it, storing the
+    /// signature in its `gpgsig` header. Signers running `gpg` or `ssh-keygen` are available in the [`sign`][crate::sign] module.
+    #[allow(clippy::too_many_arguments)]
+    fn commit_signed<Name, E>(
+        &self,
+        reference: Name,
+        author: &git_actor::SignatureRef<'_>,
+        committer: &git_actor::SignatureRef<'_>,
+        message: impl AsRef<str>,
+        tree: impl Into<ObjectId>,
+        parents: impl IntoIterator<Item = impl Into<ObjectId>>,
+        sign: &mut crate::sign::Fn,
+    ) -> Result<Oid<'_, Self>, commit::Error>
+    where
+        Name: TryInto<FullName, Error = E>,
+        commit::Error: From<E>,
+    {
+        write_commit(self, reference, author, committer, message, tree, parents, Some(sign))
+    }
+}
+
+impl<A> ObjectAccessExt for A where A: easy::Access + Sized {}
+
+#[allow(clippy::too_many_arguments)]
+fn write_tag<'a, A: ObjectAccessExt>(
+    access: &'a A,
+    name: impl AsRef<str>,
+    target: impl AsRef<oid>,
+    target_kind: git_object::Kind,
+    tagger: Option<&git_actor::SignatureRef<'_>>,
+    message: impl AsRef<str>,
+    constraint: PreviousValue,
+    sign: Option<&mut crate::sign::Fn>,
+) -> Result<Reference<'a, A>, tag::Error> {
+    // NOTE: This could be more efficient if we use a TagRef instead.
+    let mut tag = git_object::Tag {
+        target: target.as_ref().into(),
+        target_kind,
+        name: name.as_ref().into(),
+        tagger: tagger.map(|t| t.to_owned()),
+        message: message.as_ref().into(),
+        pgp_signature: None,
+    };
+    if let Some(sign) = sign {
+        crate::sign::tag(&mut tag, sign)?;
+    }
+    let tag_id = access.write_object(&tag)?;
+    super::ReferenceAccessExt::tag_reference(access, name, tag_id, constraint).map_err(Into::into)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn write_commit<'a, A: ObjectAccessExt, Name, E>(
+    access: &'a A,
+    reference: Name,
+    author: &git_actor::SignatureRef<'_>,
+    committer: &git_actor::SignatureRef<'_>,
+    message: impl AsRef<str>,
+    tree: impl Into<ObjectId>,
+    parents: impl IntoIterator<Item = impl Into<ObjectId>>,
+    sign: Option<&mut crate::sign::Fn>,
+) -> Result<Oid<'a, A>, commit::Error>
+where
+    Name: TryInto<FullName, Error = E>,
+    commit::Error: From<E>,
+{
+    use git_ref::{
+        transaction::{Change, RefEdit},
+        Target,
+    };
+
+    use crate::easy::ext::ReferenceAccessExt;
+
+    // TODO: possibly use CommitRef to save a few allocations (but will have to allocate for object ids anyway.
+    //       This can be made vastly more efficient though if we wanted to, so we lie in the API
+    let reference = reference.try_into()?;
+    let mut commit = git_object::Commit {
+        message: message.as_ref().into(),
+        tree: tree.into(),
+        author: author.to_owned(),
+        committer: committer.to_owned(),
+        encoding: None,
+        parents: parents.into_iter().map(|id| id.into()).collect(),
+        extra_headers: Default::default(),
+    };
 
-        let commit_id = self.write_object(&commit)?;
-        self.edit_reference(
-            RefEdit {
-                change: Change::Update {
-                    log: LogChange {
-                        mode: RefLog::AndReference,
-                        force_create_reflog: false,
-                        message: crate::reference::log::message(
-                            "commit",
-                            commit.message.as_ref(),
-                            commit.parents.len(),
-                        ),
-                    },
-                    expected: match commit.parents.get(0).map(|p| Target::Peeled(*p)) {
-                        Some(previous) => {
-                            if reference.as_bstr() == "HEAD" {
-                                PreviousValue::MustExistAndMatch(previous)
-                            } else {
-                                PreviousValue::ExistingMustMatch(previous)
-                            }
+    if let Some(sign) = sign {
+        crate::sign::commit(&mut commit, sign)?;
+    }
+    let commit_id = access.write_object(&commit)?;
+    access.edit_reference(
+        RefEdit {
+            change: Change::Update {
+                log: LogChange {
+                    mode: RefLog::AndReference,
+                    force_create_reflog: false,
+                    message: crate::reference::log::message("commit", commit.message.as_ref(), commit.parents.len()),
+                },
+                expected: match commit.parents.get(0).map(|p| Target::Peeled(*p)) {
+                    Some(previous) => {
+                        if reference.as_bstr() == "HEAD" {
+                            PreviousValue::MustExistAndMatch(previous)
+                        } else {
+                            PreviousValue::ExistingMustMatch(previous)
                         }
-                        None => PreviousValue::MustNotExist,
-                    },
-                    new: Target::Peeled(commit_id.inner),
+                    }
+                    None => PreviousValue::MustNotExist,
                 },
-                name: reference,
-                deref: true,
+                new: Target::Peeled(commit_id.inner),
             },
-            git_lock::acquire::Fail::Immediately,
-            Some(&commit.committer),
-        )?;
-        Ok(commit_id)
-    }
+            name: reference,
+            deref: true,
+        },
+        git_lock::acquire::Fail::Immediately,
+        Some(&commit.committer),
+    )?;
+    Ok(commit_id)
 }
-
-impl<A> ObjectAccessExt for A where A: easy::Access + Sized {}
@@ -16,6 +16,7 @@ use crate::{
     easy,
     easy::{ext::RepositoryAccessExt, reference, Reference},
     ext::ReferenceExt,
+    store::References,
 };
 
 const DEFAULT_LOCK_MODE: git_lock::acquire::Fail = git_lock::acquire::Fail::Immediately;
@@ -62,12 +63,13 @@ pub trait ReferenceAccessExt: easy::Access + Sized {
     ///
     /// Namespaces allow to partition references.
     fn namespace(&self) -> Result<Option<git_ref::Namespace>, easy::borrow::repo::Error> {
-        self.repo().map(|repo| repo.deref().refs.namespace.clone())
+        self.repo().map(|repo| repo.deref().refs.namespace().cloned())
     }
 
     /// Remove the currently set reference namespace and return it.
     fn clear_namespace(&mut self) -> Result<Option<git_ref::Namespace>, easy::borrow::repo::Error> {
-        self.repo_mut().map(|mut repo| repo.deref_mut().refs.namespace.take())
+        self.repo_mut()
+            .map(|mut repo| repo.deref_mut().refs.namespace_mut().take())
     }
 
     /// Set the reference namespace to the given value, like `"foo"` or `"foo/bar"`.
@@ -80,7 +82,7 @@ pub trait ReferenceAccessExt: easy::Access + Sized {
         git_validate::refname::Error: From<E>,
     {
         let namespace = git_ref::namespace::expand(namespace)?;
-        Ok(self.repo_mut()?.deref_mut().refs.namespace.replace(namespace))
+        Ok(self.repo_mut()?.deref_mut().refs.namespace_mut().replace(namespace))
     }
 
     // TODO: more tests or usage
@@ -150,6 +152,10 @@ pub trait ReferenceAccessExt: easy::Access + Sized {
     ///
     /// Returns all reference edits, which might be more than where provided due the splitting of symbolic references, and
     /// whose previous (_old_) values are the ones seen on in storage after the reference was locked.
+    ///
+    /// The [`reference-transaction`][crate::hook::reference_transaction()] hook is run if present and may reject the edits.
+    /// Objects written in [batch mode][git_features::fs::Fsync::Batch] are synchronized first, so all operations which
+    /// write objects and update references, like committing, amending, importing and rewriting, leave them durable.
     fn edit_references(
         &self,
         edits: impl IntoIterator<Item = RefEdit>,
@@ -165,11 +171,26 @@ pub trait ReferenceAccessExt: easy::Access + Sized {
             }
         };
         let repo = self.repo()?;
-        repo.refs
-            .transaction()
-            .prepare(edits, lock_mode)?
-            .commit(committer)
-            .map_err(Into::into)
+        // Objects written in batch mode must be durable before references point to them.
+        repo.odb.sync_batch()?;
+        let hook = crate::hook::reference_transaction(&repo)?;
+        let edits = match &repo.refs {
+            References::File(store) => {
+                let mut transaction = store.transaction();
+                if let Some(hook) = hook {
+                    transaction = transaction.hook(hook);
+                }
+                transaction.prepare(edits, lock_mode)?.commit(committer)?
+            }
+            References::Memory(store) => {
+                let mut transaction = store.transaction();
+                if let Some(hook) = hook {
+                    transaction = transaction.hook(hook);
+                }
+                transaction.prepare(edits, lock_mode)?.commit()
+            }
+        };
+        Ok(edits)
     }
 
     /// Return the repository head, an abstraction to help dealing with the `HEAD` reference.
@@ -220,6 +241,11 @@ pub trait ReferenceAccessExt: easy::Access + Sized {
         })
     }
 
+    /// Return a platform to create, rename and delete branches as well as to configure and query their upstream.
+    fn branches(&self) -> easy::branch::Platform<'_, Self> {
+        easy::branch::Platform { access: self }
+    }
+
     /// Try to find the reference named `name`, like `main`, `heads/branch`, `HEAD` or `origin/other`, and return it.
     ///
     /// Otherwise return `None` if the reference wasn't found.
@@ -1,6 +1,6 @@
-use std::ops::DerefMut;
+use std::{ops::DerefMut, path::Path};
 
-use crate::easy;
+use crate::{easy, easy::watch};
 
 /// The catch-all of extension traits.
 pub trait RepositoryAccessExt: easy::Access + Sized {
@@ -26,6 +26,33 @@ pub trait RepositoryAccessExt: easy::Access + Sized {
         self.repo_mut()?.deref_mut().odb.refresh()?;
         Ok(())
     }
+
+    /// Invalidate cached state affected by changes to the given `paths`, as reported by a file system watcher, so that
+    /// subsequent access sees changes made by other processes. Paths may be absolute or relative to the `.git` directory.
+    ///
+    /// Note that refreshing the object database requires mutable access to the repository, which fails for shared handles.
+    fn apply_changed_paths(
+        &self,
+        paths: impl IntoIterator<Item = impl AsRef<Path>>,
+    ) -> Result<watch::Outcome, watch::Error> {
+        let changes: Vec<_> = {
+            let repo = self.repo()?;
+            paths
+                .into_iter()
+                .filter_map(|path| watch::Change::classify(&repo, path.as_ref()))
+                .collect()
+        };
+        let mut outcome = watch::Outcome::default();
+        if changes.contains(&watch::Change::PackedRefs) {
+            self.state().invalidate_packed_refs()?;
+            outcome.packed_refs_invalidated = true;
+        }
+        if changes.contains(&watch::Change::ObjectDatabase) {
+            self.refresh_object_database()?;
+            outcome.object_database_refreshed = true;
+        }
+        Ok(outcome)
+    }
 }
 
 impl<A> RepositoryAccessExt for A where A: easy::Access + Sized {}
@@ -25,6 +25,7 @@ mod impls;
 pub(crate) mod ext;
 
 pub mod borrow;
+pub mod branch;
 pub mod commit;
 pub mod head;
 pub mod object;
@@ -33,6 +34,7 @@ pub mod oid;
 pub mod reference;
 pub mod state;
 pub mod tag;
+pub mod watch;
 
 /// The head reference, as created from looking at `.git/HEAD`, able to represent all of its possible states.
 ///
@@ -16,7 +16,8 @@ pub(crate) mod cache {
 pub use errors::{conversion, find, write};
 mod impls;
 pub mod peel;
-mod tree;
+///
+pub mod tree;
 
 impl Object {
     /// Infuse this owned object with an [`easy::Access`].
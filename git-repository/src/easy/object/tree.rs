@@ -1,11 +1,57 @@
+use std::collections::HashMap;
+
 use git_hash::ObjectId;
-use git_object::{bstr::BStr, TreeRefIter};
+use git_object::{
+    bstr::{BStr, BString, ByteSlice},
+    TreeRefIter,
+};
 
 use crate::{
     easy,
-    easy::{ext::ObjectAccessExt, object::find, TreeRef},
+    easy::{ext::ObjectAccessExt, TreeRef},
 };
 
+/// A memoizing cache for [path lookups][TreeRef::lookup_entry_cached()], remembering the entry each path component resolved
+/// to in each tree, to be shared across lookups in related trees like the ones of subsequent commits.
+///
+/// As trees are immutable, entries never expire. Use [`clear()`][Cache::clear()] to bound memory usage.
+#[derive(Default, Debug, Clone)]
+pub struct Cache {
+    entries: HashMap<(ObjectId, BString), Option<git_object::tree::Entry>>,
+}
+
+///
+pub mod lookup {
+    use crate::easy::object::find;
+
+    /// The error returned by [`TreeRef::lookup_path()`][crate::easy::TreeRef::lookup_path()] and its relatives.
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        FindExisting(#[from] find::existing::Error),
+        #[error(transparent)]
+        Decode(#[from] git_object::decode::Error),
+    }
+}
+
+impl Cache {
+    /// Return the amount of cached path components.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Return true if nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Remove all cached path components.
+    pub fn clear(&mut self) {
+        self.entries.clear()
+    }
+}
+
 impl<'repo, A> TreeRef<'repo, A>
 where
     A: easy::Access + Sized,
@@ -27,7 +73,7 @@ where
     /// Searching tree entries is currently done in sequence, which allows to the search to be allocation free. It would be possible
     /// to re-use a vector and use a binary search instead, which might be able to improve performance over all.
     /// However, a benchmark should be created first to have some data and see which trade-off to choose here.
-    pub fn lookup_path<I, P>(mut self, path: I) -> Result<Option<git_object::tree::Entry>, find::existing::Error>
+    pub fn lookup_path<I, P>(mut self, path: I) -> Result<Option<git_object::tree::Entry>, lookup::Error>
     where
         I: IntoIterator<Item = P>,
         P: PartialEq<BStr>,
@@ -35,10 +81,10 @@ where
         // let mut out = None;
         let mut path = path.into_iter().peekable();
         while let Some(component) = path.next() {
-            match TreeRefIter::from_bytes(&self.data)
-                .filter_map(Result::ok)
-                .find(|entry| component.eq(entry.filename))
-            {
+            let entry = TreeRefIter::from_bytes(&self.data)
+                .find(|entry| entry.as_ref().map_or(true, |entry| component.eq(entry.filename)))
+                .transpose()?;
+            match entry {
                 Some(entry) => {
                     if path.peek().is_none() {
                         return Ok(Some(entry.into()));
@@ -58,4 +104,60 @@ where
         }
         Ok(None)
     }
+
+    /// Look up the entry at the slash-separated `path`, like `a/b/c`, by following its components from this tree.
+    /// Empty components are ignored, and `None` is returned if the path doesn't exist or is empty.
+    pub fn lookup_entry(
+        self,
+        path: impl AsRef<BStr>,
+    ) -> Result<Option<git_object::tree::Entry>, lookup::Error> {
+        let path = path.as_ref();
+        if path.split_str("/").all(|c| c.is_empty()) {
+            return Ok(None);
+        }
+        self.lookup_path(path.split_str("/").filter(|c| !c.is_empty()))
+    }
+
+    /// Like [`lookup_entry()`][Self::lookup_entry()], but consult `cache` before loading sub-trees from the object
+    /// database, and remember all components that were resolved in the process.
+    pub fn lookup_entry_cached(
+        self,
+        path: impl AsRef<BStr>,
+        cache: &mut Cache,
+    ) -> Result<Option<git_object::tree::Entry>, lookup::Error> {
+        let access = self.access;
+        let mut tree_id = self.id;
+        let mut tree = Some(self);
+        let mut components = path.as_ref().split_str("/").filter(|c| !c.is_empty()).peekable();
+        while let Some(component) = components.next() {
+            let key = (tree_id, component.as_bstr().to_owned());
+            let entry = match cache.entries.get(&key) {
+                Some(entry) => entry.clone(),
+                None => {
+                    let tree = match tree.take() {
+                        Some(tree) => tree,
+                        None => match access.find_object(tree_id)?.try_into_tree() {
+                            Ok(tree) => tree,
+                            Err(_) => return Ok(None),
+                        },
+                    };
+                    let entry = TreeRefIter::from_bytes(&tree.data)
+                        .find(|entry| entry.as_ref().map_or(true, |entry| entry.filename == component))
+                        .transpose()?
+                        .map(Into::into);
+                    cache.entries.insert(key, entry.clone());
+                    entry
+                }
+            };
+            match entry {
+                Some(entry) if components.peek().is_none() => return Ok(Some(entry)),
+                Some(entry) if entry.mode.is_tree() => {
+                    tree = None;
+                    tree_id = entry.oid;
+                }
+                _ => return Ok(None),
+            }
+        }
+        Ok(None)
+    }
 }
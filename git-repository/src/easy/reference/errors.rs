@@ -30,9 +30,15 @@ pub mod edit {
         #[error(transparent)]
         FileTransactionCommit(#[from] git_ref::file::transaction::commit::Error),
         #[error(transparent)]
+        MemoryTransaction(#[from] git_ref::memory::transaction::Error),
+        #[error(transparent)]
         NameValidation(#[from] git_validate::reference::name::Error),
         #[error("BUG: The repository could not be borrowed")]
         BorrowRepo(#[from] easy::borrow::repo::Error),
+        #[error("Could not read the configuration to find the reference-transaction hook")]
+        Config(#[from] crate::config::Error),
+        #[error("Could not synchronize written objects before updating references")]
+        SyncObjects(#[from] git_odb::loose::write::Error),
     }
 }
 
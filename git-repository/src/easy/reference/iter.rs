@@ -6,7 +6,6 @@ use std::{
 };
 
 use git_odb::Find;
-use git_ref::file::ReferenceExt;
 
 use crate::easy;
 
@@ -23,7 +22,7 @@ where
 
 /// An iterator over references, with or without filter.
 pub struct Iter<'r, A> {
-    inner: git_ref::file::iter::LooseThenPacked<'r, 'r>,
+    inner: crate::store::references::Iter<'r, 'r>,
     packed_refs: Option<&'r git_ref::packed::Buffer>,
     peel: bool,
     access: &'r A,
@@ -90,13 +89,14 @@ where
                         let repo = self.access.repo()?;
                         let state = self.access.state();
                         let mut pack_cache = state.try_borrow_mut_pack_cache()?;
-                        r.peel_to_id_in_place(&repo.refs, self.packed_refs, |oid, buf| {
-                            repo.odb
-                                .try_find(oid, buf, pack_cache.deref_mut())
-                                .map(|po| po.map(|o| (o.kind, o.data)))
-                        })
-                        .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send + Sync + 'static>)
-                        .map(|_| r)
+                        repo.refs
+                            .peel_to_id_in_place(&mut r, self.packed_refs, |oid, buf| {
+                                repo.odb
+                                    .try_find(oid, buf, pack_cache.deref_mut())
+                                    .map(|po| po.map(|o| (o.kind, o.data)))
+                            })
+                            .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send + Sync + 'static>)
+                            .map(|_| r)
                     } else {
                         Ok(r)
                     }
@@ -40,11 +40,11 @@ where
     pub fn iter_rev(&mut self) -> Result<Option<ReverseIter<'_>>, init::Error> {
         let buf = self.buf.deref_mut();
         buf.resize(512, 0);
-        Ok(self
-            .reference
-            .borrow()
-            .inner
-            .log_iter_rev(&self.reference.borrow().access.repo()?.refs, buf)?)
+        let repo = self.reference.borrow().access.repo()?;
+        Ok(match repo.refs.as_file() {
+            Some(store) => self.reference.borrow().inner.log_iter_rev(store, buf)?,
+            None => None,
+        })
     }
 
     // TODO: tests
@@ -54,11 +54,11 @@ where
     /// For accessing only the most recent entries, see [`iter_rev()`][Logs::iter_rev()].
     pub fn iter(&mut self) -> Result<Option<ForwardIter<'_>>, init::Error> {
         let buf = self.buf.deref_mut();
-        Ok(self
-            .reference
-            .borrow()
-            .inner
-            .log_iter(&self.reference.borrow().access.repo()?.refs, buf)?)
+        let repo = self.reference.borrow().access.repo()?;
+        Ok(match repo.refs.as_file() {
+            Some(store) => self.reference.borrow().inner.log_iter(store, buf)?,
+            None => None,
+        })
     }
 }
 
@@ -2,7 +2,6 @@
 use std::ops::DerefMut;
 
 use git_odb::Find;
-use git_ref::file::ReferenceExt;
 
 use crate::{
     easy,
@@ -77,8 +76,8 @@ where
         let repo = self.access.repo()?;
         let state = self.access.state();
         let mut pack_cache = state.try_borrow_mut_pack_cache()?;
-        let oid = self.inner.peel_to_id_in_place(
-            &repo.refs,
+        let oid = repo.refs.peel_to_id_in_place(
+            &mut self.inner,
             state.assure_packed_refs_uptodate(&repo.refs)?.buffer.as_ref(),
             |oid, buf| {
                 repo.odb
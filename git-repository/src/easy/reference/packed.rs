@@ -3,8 +3,6 @@ use std::{
     time::SystemTime,
 };
 
-use git_ref::file;
-
 use crate::easy;
 
 #[derive(Debug, thiserror::Error)]
@@ -34,10 +32,21 @@ pub(crate) struct ModifieablePackedRefsBuffer {
 }
 
 impl ModifieablePackedRefsBuffer {
+    /// Discard the buffer to reload it on next access, even if the modification time of the file didn't change.
+    pub fn invalidate(&mut self) {
+        self.buffer = None;
+        self.modified = None;
+    }
+
+    /// Load the packed references of `refs` or reload them if they changed since, unless references are kept in memory.
     pub fn assure_packed_refs_uptodate(
         &mut self,
-        file: &file::Store,
+        refs: &crate::store::References,
     ) -> Result<(), git_ref::packed::buffer::open::Error> {
+        let file = match refs.as_file() {
+            Some(file) => file,
+            None => return Ok(()),
+        };
         let packed_refs_modified_time = || file.packed_refs_path().metadata().and_then(|m| m.modified()).ok();
         if self.buffer.is_none() {
             self.buffer = file.packed_buffer()?;
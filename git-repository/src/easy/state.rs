@@ -1,9 +1,7 @@
 //!
 use std::cell::{Ref, RefCell, RefMut};
 
-use git_ref::file;
-
-use crate::{easy, easy::borrow};
+use crate::{easy, easy::borrow, store};
 
 impl Clone for easy::State {
     fn clone(&self) -> Self {
@@ -28,14 +26,19 @@ impl Default for easy::State {
 impl easy::State {
     pub(crate) fn assure_packed_refs_uptodate(
         &self,
-        file: &file::Store,
+        refs: &store::References,
     ) -> Result<Ref<'_, easy::reference::packed::ModifieablePackedRefsBuffer>, easy::reference::packed::Error> {
         let mut packed_refs = self.packed_refs.try_borrow_mut()?;
-        packed_refs.assure_packed_refs_uptodate(file)?;
+        packed_refs.assure_packed_refs_uptodate(refs)?;
         drop(packed_refs);
         Ok(self.packed_refs.try_borrow()?)
     }
 
+    pub(crate) fn invalidate_packed_refs(&self) -> borrow::state::Result<()> {
+        self.packed_refs.try_borrow_mut()?.invalidate();
+        Ok(())
+    }
+
     #[inline]
     pub(crate) fn try_borrow_mut_pack_cache(&self) -> borrow::state::Result<RefMut<'_, easy::PackCache>> {
         self.pack_cache.try_borrow_mut().map_err(Into::into)
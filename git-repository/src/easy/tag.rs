@@ -9,6 +9,8 @@ mod error {
         #[error(transparent)]
         ReferenceNameValidation(#[from] git_ref::name::Error),
         #[error(transparent)]
+        Sign(#[from] crate::sign::Error),
+        #[error(transparent)]
         WriteObject(#[from] easy::object::write::Error),
         #[error(transparent)]
         ReferenceEdit(#[from] easy::reference::edit::Error),
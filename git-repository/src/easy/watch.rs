@@ -0,0 +1,84 @@
+//! Invalidate cached repository state based on changes to paths as reported by a file system watcher.
+//!
+//! Long-running processes can feed the paths reported by a watcher of their choice to
+//! [`RepositoryAccessExt::apply_changed_paths()`][easy::ext::RepositoryAccessExt::apply_changed_paths()] to pick up changes
+//! made by other processes, instead of polling or reopening the repository.
+use std::path::{Path, PathBuf};
+
+use crate::{easy, Repository};
+
+/// The kind of cached state affected by a change to a path.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub enum Change {
+    /// The `packed-refs` file changed, and cached packed references have to be reloaded.
+    PackedRefs,
+    /// Packs, multi-pack indices or alternates changed, and the object database has to be refreshed.
+    ObjectDatabase,
+}
+
+impl Change {
+    /// Classify the change to `path` in `repo`, or return `None` if it doesn't affect any cached state, as is the case for loose
+    /// references which are always read from disk, or for all paths if `repo` is kept in memory.
+    ///
+    /// `path` may be absolute or relative to the `.git` directory.
+    pub fn classify(repo: &Repository, path: &Path) -> Option<Self> {
+        if repo.is_in_memory() {
+            return None;
+        }
+        let path = if path.is_relative() {
+            repo.git_dir().join(path)
+        } else {
+            path.to_owned()
+        };
+        if let Some(relative) = strip_prefix(&path, repo.objects_dir()) {
+            let mut components = relative.components().map(|c| c.as_os_str());
+            return match (components.next(), components.next(), components.next()) {
+                (Some(dir), Some(name), None) if dir == "pack" => {
+                    let name = Path::new(name);
+                    let is_pack_or_index = name.extension().map_or(false, |ext| ext == "pack" || ext == "idx");
+                    if is_pack_or_index || name == Path::new("multi-pack-index") {
+                        Some(Change::ObjectDatabase)
+                    } else {
+                        None
+                    }
+                }
+                (Some(dir), Some(name), None) if dir == "info" && name == "alternates" => Some(Change::ObjectDatabase),
+                _ => None,
+            };
+        }
+        match strip_prefix(&path, repo.git_dir()) {
+            Some(relative) if relative == Path::new("packed-refs") => Some(Change::PackedRefs),
+            _ => None,
+        }
+    }
+}
+
+/// Return `path` relative to `base`, trying the canonical form of `base` as well as watchers may report canonical paths.
+fn strip_prefix(path: &Path, base: &Path) -> Option<PathBuf> {
+    path.strip_prefix(base).ok().map(ToOwned::to_owned).or_else(|| {
+        base.canonicalize()
+            .ok()
+            .and_then(|base| path.strip_prefix(base).ok().map(ToOwned::to_owned))
+    })
+}
+
+/// The outcome of [`RepositoryAccessExt::apply_changed_paths()`][easy::ext::RepositoryAccessExt::apply_changed_paths()].
+#[derive(Default, PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub struct Outcome {
+    /// If true, cached packed references were discarded to be reloaded on next access.
+    pub packed_refs_invalidated: bool,
+    /// If true, the object database was refreshed to pick up new or removed packs.
+    pub object_database_refreshed: bool,
+}
+
+/// The error returned by [`RepositoryAccessExt::apply_changed_paths()`][easy::ext::RepositoryAccessExt::apply_changed_paths()].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    BorrowRepo(#[from] easy::borrow::repo::Error),
+    #[error(transparent)]
+    BorrowState(#[from] easy::borrow::state::Error),
+    #[error(transparent)]
+    Refresh(#[from] easy::odb::refresh::Error),
+}
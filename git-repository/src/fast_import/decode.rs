@@ -0,0 +1,421 @@
+use std::io::BufRead;
+
+use git_hash::ObjectId;
+use git_object::{
+    bstr::{BString, ByteSlice},
+    tree::EntryMode,
+};
+
+use crate::fast_import::{Command, Commit, DataRef, FileChange, FileData, Tag};
+
+/// The error returned by [`Iter`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not read the stream")]
+    Io(#[from] std::io::Error),
+    #[error("Line {}: {}: {:?}", .line_number, .message, .line)]
+    Syntax {
+        line_number: usize,
+        message: &'static str,
+        line: BString,
+    },
+    #[error("Line {}: the command {:?} is not supported", .line_number, .line)]
+    Unsupported { line_number: usize, line: BString },
+    #[error("The stream ended unexpectedly")]
+    UnexpectedEof,
+}
+
+/// An iterator over the [commands][Command] of a stream in the fast-import format.
+///
+/// Comments, `progress`, `checkpoint`, `feature` and `option` commands as well as `original-oid` lines are skipped.
+pub struct Iter<R> {
+    read: R,
+    line: Vec<u8>,
+    /// If true, `line` holds a line that was read but not yet consumed.
+    line_pending: bool,
+    line_number: usize,
+    done: bool,
+}
+
+impl<R: BufRead> Iter<R> {
+    /// Create a new instance to parse commands from `read`.
+    pub fn new(read: R) -> Self {
+        Iter {
+            read,
+            line: Vec::new(),
+            line_pending: false,
+            line_number: 0,
+            done: false,
+        }
+    }
+
+    /// Read the next line without its line feed into `self.line`, returning false at the end of the stream.
+    fn next_line(&mut self) -> Result<bool, Error> {
+        if self.line_pending {
+            self.line_pending = false;
+            return Ok(true);
+        }
+        self.line.clear();
+        if self.read.read_until(b'\n', &mut self.line)? == 0 {
+            return Ok(false);
+        }
+        if self.line.last() == Some(&b'\n') {
+            self.line.pop();
+        }
+        self.line_number += 1;
+        Ok(true)
+    }
+
+    fn expect_line(&mut self) -> Result<(), Error> {
+        if self.next_line()? {
+            Ok(())
+        } else {
+            Err(Error::UnexpectedEof)
+        }
+    }
+
+    fn syntax_error(&self, message: &'static str) -> Error {
+        Error::Syntax {
+            line_number: self.line_number,
+            message,
+            line: self.line.clone().into(),
+        }
+    }
+
+    /// If the current line starts with `prefix`, return the remainder of the line.
+    fn strip_prefix(&self, prefix: &str) -> Option<&[u8]> {
+        self.line.strip_prefix(prefix.as_bytes())
+    }
+
+    /// Read the next line and return the remainder after `prefix` if it matches, or keep it for later otherwise.
+    fn optional(&mut self, prefix: &str) -> Result<Option<Vec<u8>>, Error> {
+        if !self.next_line()? {
+            return Ok(None);
+        }
+        match self.strip_prefix(prefix) {
+            Some(rest) => Ok(Some(rest.to_owned())),
+            None => {
+                self.line_pending = true;
+                Ok(None)
+            }
+        }
+    }
+
+    fn skip_original_oid(&mut self) -> Result<(), Error> {
+        self.optional("original-oid ").map(|_| ())
+    }
+
+    fn optional_mark(&mut self) -> Result<Option<u32>, Error> {
+        self.optional("mark ")?
+            .map(|mark| {
+                mark.strip_prefix(b":")
+                    .and_then(parse_u32)
+                    .ok_or_else(|| self.syntax_error("invalid mark"))
+            })
+            .transpose()
+    }
+
+    fn optional_signature(&mut self, prefix: &str) -> Result<Option<git_actor::Signature>, Error> {
+        self.optional(prefix)?
+            .map(|signature| {
+                git_actor::SignatureRef::from_bytes::<()>(&signature)
+                    .map(|s| s.to_owned())
+                    .map_err(|_| self.syntax_error("invalid signature"))
+            })
+            .transpose()
+    }
+
+    fn data_ref(&self, input: &[u8]) -> Result<DataRef, Error> {
+        match input.strip_prefix(b":") {
+            Some(mark) => parse_u32(mark).map(DataRef::Mark),
+            None => ObjectId::from_hex(input).ok().map(DataRef::Id),
+        }
+        .ok_or_else(|| self.syntax_error("expected a mark or object id"))
+    }
+
+    /// Read a `data` command along with its content, consuming an optional line feed after it.
+    fn data(&mut self) -> Result<Vec<u8>, Error> {
+        self.expect_line()?;
+        let spec = self
+            .strip_prefix("data ")
+            .ok_or_else(|| self.syntax_error("expected data"))?
+            .to_owned();
+        match spec.strip_prefix(b"<<") {
+            Some(delimiter) => {
+                let mut data = Vec::new();
+                loop {
+                    self.expect_line()?;
+                    if self.line == delimiter {
+                        break;
+                    }
+                    data.extend_from_slice(&self.line);
+                    data.push(b'\n');
+                }
+                Ok(data)
+            }
+            None => {
+                let len = std::str::from_utf8(&spec)
+                    .ok()
+                    .and_then(|len| len.parse::<usize>().ok())
+                    .ok_or_else(|| self.syntax_error("invalid data length"))?;
+                let mut data = vec![0; len];
+                self.read.read_exact(&mut data).map_err(|err| match err.kind() {
+                    std::io::ErrorKind::UnexpectedEof => Error::UnexpectedEof,
+                    _ => err.into(),
+                })?;
+                self.line_number += data.iter().filter(|b| **b == b'\n').count();
+                if self.read.fill_buf()?.first() == Some(&b'\n') {
+                    self.read.consume(1);
+                    self.line_number += 1;
+                }
+                Ok(data)
+            }
+        }
+    }
+
+    fn blob(&mut self) -> Result<Command, Error> {
+        let mark = self.optional_mark()?;
+        self.skip_original_oid()?;
+        let data = self.data()?;
+        Ok(Command::Blob { mark, data })
+    }
+
+    fn commit(&mut self, reference: BString) -> Result<Command, Error> {
+        let mark = self.optional_mark()?;
+        self.skip_original_oid()?;
+        let author = self.optional_signature("author ")?;
+        let committer = self
+            .optional_signature("committer ")?
+            .ok_or_else(|| self.syntax_error("expected committer"))?;
+        let encoding = self.optional("encoding ")?.map(Into::into);
+        let message = self.data()?.into();
+        let from = self.optional("from ")?.map(|from| self.data_ref(&from)).transpose()?;
+        let mut merges = Vec::new();
+        while let Some(merge) = self.optional("merge ")? {
+            merges.push(self.data_ref(&merge)?);
+        }
+
+        let mut changes = Vec::new();
+        while self.next_line()? {
+            if self.line.is_empty() {
+                break;
+            }
+            let change = if let Some(rest) = self.strip_prefix("M ") {
+                let rest = rest.to_owned();
+                let mut tokens = rest.splitn(3, |b| *b == b' ');
+                let (mode, data, path) = match (tokens.next(), tokens.next(), tokens.next()) {
+                    (Some(mode), Some(data), Some(path)) => (mode, data, path),
+                    _ => return Err(self.syntax_error("expected mode, data and path")),
+                };
+                let mode = parse_mode(mode).ok_or_else(|| self.syntax_error("invalid file mode"))?;
+                let path = self.path(path)?;
+                let data = if data == b"inline" {
+                    FileData::Inline(self.data()?)
+                } else {
+                    FileData::Ref(self.data_ref(data)?)
+                };
+                FileChange::Modify { mode, data, path }
+            } else if let Some(path) = self.strip_prefix("D ") {
+                FileChange::Delete { path: self.path(path)? }
+            } else if let Some(paths) = self.strip_prefix("C ") {
+                let (source, destination) = self.two_paths(paths)?;
+                FileChange::Copy { source, destination }
+            } else if let Some(paths) = self.strip_prefix("R ") {
+                let (source, destination) = self.two_paths(paths)?;
+                FileChange::Rename { source, destination }
+            } else if self.line == b"deleteall" {
+                FileChange::DeleteAll
+            } else if self.line.starts_with(b"N ") {
+                return Err(Error::Unsupported {
+                    line_number: self.line_number,
+                    line: self.line.clone().into(),
+                });
+            } else {
+                self.line_pending = true;
+                break;
+            };
+            changes.push(change);
+        }
+
+        Ok(Command::Commit(Commit {
+            reference,
+            mark,
+            author,
+            committer,
+            encoding,
+            message,
+            from,
+            merges,
+            changes,
+        }))
+    }
+
+    fn tag(&mut self, name: BString) -> Result<Command, Error> {
+        let mark = self.optional_mark()?;
+        let from = self
+            .optional("from ")?
+            .ok_or_else(|| self.syntax_error("expected from"))?;
+        let from = self.data_ref(&from)?;
+        self.skip_original_oid()?;
+        let tagger = self.optional_signature("tagger ")?;
+        let message = self.data()?.into();
+        Ok(Command::Tag(Tag {
+            name,
+            mark,
+            from,
+            tagger,
+            message,
+        }))
+    }
+
+    fn reset(&mut self, reference: BString) -> Result<Command, Error> {
+        let from = self.optional("from ")?.map(|from| self.data_ref(&from)).transpose()?;
+        Ok(Command::Reset { reference, from })
+    }
+
+    fn path(&self, input: &[u8]) -> Result<BString, Error> {
+        if input.starts_with(b"\"") {
+            match unquote(input) {
+                Some((path, [])) => Ok(path),
+                _ => Err(self.syntax_error("invalid quoted path")),
+            }
+        } else {
+            Ok(input.into())
+        }
+    }
+
+    fn two_paths(&self, input: &[u8]) -> Result<(BString, BString), Error> {
+        let (source, rest) = if input.starts_with(b"\"") {
+            unquote(input).ok_or_else(|| self.syntax_error("invalid quoted path"))?
+        } else {
+            let pos = input
+                .find_byte(b' ')
+                .ok_or_else(|| self.syntax_error("expected two paths"))?;
+            (input[..pos].into(), &input[pos..])
+        };
+        let destination = rest
+            .strip_prefix(b" ")
+            .ok_or_else(|| self.syntax_error("expected two paths"))?;
+        Ok((source, self.path(destination)?))
+    }
+
+    fn next_command(&mut self) -> Result<Option<Command>, Error> {
+        loop {
+            if !self.next_line()? {
+                return Ok(None);
+            }
+            let line = &self.line;
+            if line.is_empty()
+                || line.starts_with(b"#")
+                || line.starts_with(b"progress ")
+                || line.starts_with(b"feature ")
+                || line.starts_with(b"option ")
+                || line == b"checkpoint"
+            {
+                continue;
+            }
+            return if line == b"blob" {
+                self.blob()
+            } else if let Some(reference) = self.strip_prefix("commit ") {
+                let reference = reference.into();
+                self.commit(reference)
+            } else if let Some(name) = self.strip_prefix("tag ") {
+                let name = name.into();
+                self.tag(name)
+            } else if let Some(reference) = self.strip_prefix("reset ") {
+                let reference = reference.into();
+                self.reset(reference)
+            } else if line == b"done" {
+                Ok(Command::Done)
+            } else {
+                Err(Error::Unsupported {
+                    line_number: self.line_number,
+                    line: line.clone().into(),
+                })
+            }
+            .map(Some);
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Iter<R> {
+    type Item = Result<Command, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let res = self.next_command().transpose();
+        if matches!(res, None | Some(Err(_)) | Some(Ok(Command::Done))) {
+            self.done = true;
+        }
+        res
+    }
+}
+
+fn parse_u32(input: &[u8]) -> Option<u32> {
+    std::str::from_utf8(input).ok()?.parse().ok()
+}
+
+fn parse_mode(input: &[u8]) -> Option<EntryMode> {
+    Some(match input {
+        b"644" | b"100644" => EntryMode::Blob,
+        b"755" | b"100755" => EntryMode::BlobExecutable,
+        b"120000" => EntryMode::Link,
+        b"160000" => EntryMode::Commit,
+        b"040000" | b"40000" => EntryMode::Tree,
+        _ => return None,
+    })
+}
+
+/// Parse the C-style quoted string at the beginning of `input` and return it along with the remaining input.
+fn unquote(input: &[u8]) -> Option<(BString, &[u8])> {
+    let mut out = Vec::new();
+    let mut pos = 1;
+    loop {
+        let byte = *input.get(pos)?;
+        pos += 1;
+        match byte {
+            b'"' => return Some((out.into(), &input[pos..])),
+            b'\\' => {
+                let escaped = *input.get(pos)?;
+                pos += 1;
+                out.push(match escaped {
+                    b'a' => 0x07,
+                    b'b' => 0x08,
+                    b'f' => 0x0c,
+                    b'n' => b'\n',
+                    b'r' => b'\r',
+                    b't' => b'\t',
+                    b'v' => 0x0b,
+                    b'0'..=b'3' => {
+                        let digits = input.get(pos - 1..pos + 2)?;
+                        pos += 2;
+                        digits.iter().try_fold(0u8, |acc, d| match d {
+                            b'0'..=b'7' => Some(acc * 8 + (d - b'0')),
+                            _ => None,
+                        })?
+                    }
+                    other => other,
+                });
+            }
+            other => out.push(other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unquote_handles_escapes_and_returns_the_remainder() {
+        assert_eq!(
+            unquote(br#""a b\"c\\d\n\303\244" rest"#),
+            Some(("a b\"c\\d\n\u{e4}".into(), &b" rest"[..]))
+        );
+        assert_eq!(unquote(br#""unterminated"#), None);
+        assert_eq!(unquote(br#""\3x7""#), None, "invalid octal escape");
+    }
+}
@@ -0,0 +1,175 @@
+use std::io;
+
+use git_object::bstr::BStr;
+
+use crate::fast_import::{Command, Commit, DataRef, FileChange, FileData, Tag};
+
+/// Serialization
+impl Command {
+    /// Serialize this command to `out` in the fast-import format.
+    pub fn write_to(&self, mut out: impl io::Write) -> io::Result<()> {
+        match self {
+            Command::Blob { mark, data } => {
+                out.write_all(b"blob\n")?;
+                write_mark(*mark, &mut out)?;
+                write_data(data, &mut out)
+            }
+            Command::Commit(Commit {
+                reference,
+                mark,
+                author,
+                committer,
+                encoding,
+                message,
+                from,
+                merges,
+                changes,
+            }) => {
+                write_line(b"commit ", reference.as_ref(), &mut out)?;
+                write_mark(*mark, &mut out)?;
+                if let Some(author) = author {
+                    out.write_all(b"author ")?;
+                    author.write_to(&mut out)?;
+                    out.write_all(b"\n")?;
+                }
+                out.write_all(b"committer ")?;
+                committer.write_to(&mut out)?;
+                out.write_all(b"\n")?;
+                if let Some(encoding) = encoding {
+                    write_line(b"encoding ", encoding.as_ref(), &mut out)?;
+                }
+                write_data(message, &mut out)?;
+                if let Some(from) = from {
+                    out.write_all(b"from ")?;
+                    write_data_ref(from, &mut out)?;
+                }
+                for merge in merges {
+                    out.write_all(b"merge ")?;
+                    write_data_ref(merge, &mut out)?;
+                }
+                for change in changes {
+                    match change {
+                        FileChange::Modify { mode, data, path } => {
+                            out.write_all(b"M ")?;
+                            out.write_all(mode.as_bytes())?;
+                            match data {
+                                FileData::Ref(data) => {
+                                    out.write_all(b" ")?;
+                                    write_data_ref_inline(data, &mut out)?;
+                                    out.write_all(b" ")?;
+                                    write_path(path.as_ref(), &mut out)?;
+                                    out.write_all(b"\n")?;
+                                }
+                                FileData::Inline(data) => {
+                                    out.write_all(b" inline ")?;
+                                    write_path(path.as_ref(), &mut out)?;
+                                    out.write_all(b"\n")?;
+                                    write_data(data, &mut out)?;
+                                }
+                            }
+                        }
+                        FileChange::Delete { path } => {
+                            out.write_all(b"D ")?;
+                            write_path(path.as_ref(), &mut out)?;
+                            out.write_all(b"\n")?;
+                        }
+                        FileChange::Copy { source, destination } | FileChange::Rename { source, destination } => {
+                            out.write_all(if matches!(change, FileChange::Copy { .. }) {
+                                b"C "
+                            } else {
+                                b"R "
+                            })?;
+                            write_path(source.as_ref(), &mut out)?;
+                            out.write_all(b" ")?;
+                            write_path(destination.as_ref(), &mut out)?;
+                            out.write_all(b"\n")?;
+                        }
+                        FileChange::DeleteAll => out.write_all(b"deleteall\n")?,
+                    }
+                }
+                out.write_all(b"\n")
+            }
+            Command::Tag(Tag {
+                name,
+                mark,
+                from,
+                tagger,
+                message,
+            }) => {
+                write_line(b"tag ", name.as_ref(), &mut out)?;
+                write_mark(*mark, &mut out)?;
+                out.write_all(b"from ")?;
+                write_data_ref(from, &mut out)?;
+                if let Some(tagger) = tagger {
+                    out.write_all(b"tagger ")?;
+                    tagger.write_to(&mut out)?;
+                    out.write_all(b"\n")?;
+                }
+                write_data(message, &mut out)
+            }
+            Command::Reset { reference, from } => {
+                write_line(b"reset ", reference.as_ref(), &mut out)?;
+                if let Some(from) = from {
+                    out.write_all(b"from ")?;
+                    write_data_ref(from, &mut out)?;
+                }
+                out.write_all(b"\n")
+            }
+            Command::Done => out.write_all(b"done\n"),
+        }
+    }
+}
+
+fn write_line(prefix: &[u8], value: &BStr, mut out: impl io::Write) -> io::Result<()> {
+    out.write_all(prefix)?;
+    out.write_all(value)?;
+    out.write_all(b"\n")
+}
+
+fn write_mark(mark: Option<u32>, mut out: impl io::Write) -> io::Result<()> {
+    match mark {
+        Some(mark) => writeln!(out, "mark :{}", mark),
+        None => Ok(()),
+    }
+}
+
+fn write_data(data: &[u8], mut out: impl io::Write) -> io::Result<()> {
+    writeln!(out, "data {}", data.len())?;
+    out.write_all(data)?;
+    out.write_all(b"\n")
+}
+
+fn write_data_ref_inline(data_ref: &DataRef, mut out: impl io::Write) -> io::Result<()> {
+    match data_ref {
+        DataRef::Mark(mark) => write!(out, ":{}", mark),
+        DataRef::Id(id) => write!(out, "{}", id),
+    }
+}
+
+fn write_data_ref(data_ref: &DataRef, mut out: impl io::Write) -> io::Result<()> {
+    write_data_ref_inline(data_ref, &mut out)?;
+    out.write_all(b"\n")
+}
+
+/// Write `path`, quoting it C-style if it contains characters that would otherwise make the line ambiguous.
+fn write_path(path: &BStr, mut out: impl io::Write) -> io::Result<()> {
+    let needs_quoting = path
+        .iter()
+        .any(|b| matches!(b, b'"' | b'\\' | b' ' | 0x7f) || *b < 0x20);
+    if !needs_quoting {
+        return out.write_all(path);
+    }
+    out.write_all(b"\"")?;
+    for byte in path.iter() {
+        match byte {
+            b'"' => out.write_all(b"\\\"")?,
+            b'\\' => out.write_all(b"\\\\")?,
+            b'\n' => out.write_all(b"\\n")?,
+            b'\t' => out.write_all(b"\\t")?,
+            b'\r' => out.write_all(b"\\r")?,
+            b if *b < 0x20 || *b == 0x7f => write!(out, "\\{:03o}", b)?,
+            b => out.write_all(&[*b])?,
+        }
+    }
+    out.write_all(b"\"")
+}
@@ -0,0 +1,251 @@
+use std::{collections::HashMap, io};
+
+use git_hash::ObjectId;
+use git_object::{bstr::BString, tree::EntryMode};
+use git_odb::FindExt;
+
+use crate::{
+    fast_import::{Command, Commit, DataRef, FileChange, FileData, Tag},
+    Repository,
+};
+
+/// The error returned by [`Repository::fast_export()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    FindReference(#[from] git_ref::file::find::existing::Error),
+    #[error(transparent)]
+    PackedRefsOpen(#[from] git_ref::packed::buffer::open::Error),
+    #[error(transparent)]
+    FindExistingObject(#[from] git_pack::find::existing::Error<git_odb::compound::find::Error>),
+    #[error(transparent)]
+    FindExistingTypedObject(#[from] git_pack::find::existing_object::Error<git_odb::compound::find::Error>),
+    #[error(transparent)]
+    Decode(#[from] git_object::decode::Error),
+    #[error("Reference {} points to a {}, but only commits and tags of commits can be exported", .name, .kind)]
+    UnsupportedTarget { name: BString, kind: git_object::Kind },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// The outcome of [`Repository::fast_export()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    /// The amount of exported commits.
+    pub commits: usize,
+    /// The amount of exported blobs.
+    pub blobs: usize,
+    /// The amount of exported annotated tags.
+    pub tags: usize,
+}
+
+impl Repository {
+    /// Write the history reachable from the full reference names in `references`, like `refs/heads/main` or `HEAD`, to `out`
+    /// as stream in the fast-import format.
+    ///
+    /// Commits are written in topological order with their complete tree, signatures of commits and tags are dropped.
+    /// The stream is terminated with the `done` command.
+    pub fn fast_export(
+        &self,
+        references: impl IntoIterator<Item = impl AsRef<str>>,
+        mut out: impl io::Write,
+    ) -> Result<Outcome, Error> {
+        let packed = self.refs.packed_buffer()?;
+        let mut state = State {
+            repo: self,
+            marks: HashMap::new(),
+            outcome: Outcome::default(),
+            buf: Vec::new(),
+        };
+        let mut resets = Vec::new();
+        for name in references {
+            let name = name.as_ref();
+            let mut reference = self.refs.find(name, packed.as_ref())?;
+            while let Some(next) = self.refs.follow(&reference, packed.as_ref()) {
+                reference = next?;
+            }
+            let id = reference
+                .target
+                .as_id()
+                .expect("peeled after following symbolic refs")
+                .to_owned();
+            let kind = self.odb.find(id, &mut state.buf, &mut git_pack::cache::Never)?.kind;
+            match kind {
+                git_object::Kind::Commit => {
+                    state.export_history(id, name.into(), &mut out)?;
+                    resets.push(Command::Reset {
+                        reference: name.into(),
+                        from: Some(DataRef::Mark(state.marks[&id])),
+                    });
+                }
+                git_object::Kind::Tag => {
+                    let tag: git_object::Tag = self
+                        .odb
+                        .find_tag(id, &mut state.buf, &mut git_pack::cache::Never)?
+                        .into();
+                    if tag.target_kind != git_object::Kind::Commit {
+                        return Err(Error::UnsupportedTarget {
+                            name: name.into(),
+                            kind: tag.target_kind,
+                        });
+                    }
+                    state.export_history(tag.target, name.into(), &mut out)?;
+                    Command::Tag(Tag {
+                        name: name.strip_prefix("refs/tags/").map_or(tag.name, Into::into),
+                        mark: None,
+                        from: DataRef::Mark(state.marks[&tag.target]),
+                        tagger: tag.tagger,
+                        message: tag.message,
+                    })
+                    .write_to(&mut out)?;
+                    state.outcome.tags += 1;
+                }
+                kind => {
+                    return Err(Error::UnsupportedTarget {
+                        name: name.into(),
+                        kind,
+                    })
+                }
+            }
+        }
+        for reset in resets {
+            reset.write_to(&mut out)?;
+        }
+        Command::Done.write_to(&mut out)?;
+        Ok(state.outcome)
+    }
+}
+
+struct State<'repo> {
+    repo: &'repo Repository,
+    /// The marks of all exported commits and blobs.
+    marks: HashMap<ObjectId, u32>,
+    outcome: Outcome,
+    buf: Vec<u8>,
+}
+
+impl<'repo> State<'repo> {
+    fn mark(&mut self, id: ObjectId) -> u32 {
+        let mark = self.marks.len() as u32 + 1;
+        self.marks.insert(id, mark);
+        mark
+    }
+
+    /// Export all commits reachable from `tip` that weren't exported yet, parents first.
+    fn export_history(&mut self, tip: ObjectId, reference: BString, out: &mut impl io::Write) -> Result<(), Error> {
+        let mut pending = HashMap::new();
+        let mut stack = vec![(tip, false)];
+        while let Some((id, parents_exported)) = stack.pop() {
+            if self.marks.contains_key(&id) {
+                continue;
+            }
+            if parents_exported {
+                let commit = pending.remove(&id).expect("loaded when first seen");
+                self.export_commit(id, commit, &reference, out)?;
+                continue;
+            }
+            let commit: git_object::Commit = self
+                .repo
+                .odb
+                .find_commit(id, &mut self.buf, &mut git_pack::cache::Never)?
+                .into();
+            stack.push((id, true));
+            stack.extend(
+                commit
+                    .parents
+                    .iter()
+                    .rev()
+                    .filter(|parent| !self.marks.contains_key(*parent))
+                    .map(|parent| (*parent, false)),
+            );
+            pending.insert(id, commit);
+        }
+        Ok(())
+    }
+
+    fn export_commit(
+        &mut self,
+        id: ObjectId,
+        commit: git_object::Commit,
+        reference: &BString,
+        out: &mut impl io::Write,
+    ) -> Result<(), Error> {
+        let mut files = Vec::new();
+        self.collect_files(commit.tree, BString::default(), &mut files)?;
+        let mut changes = vec![FileChange::DeleteAll];
+        for (path, mode, id) in files {
+            let data = match mode {
+                EntryMode::Commit => DataRef::Id(id),
+                _ => match self.marks.get(&id) {
+                    Some(mark) => DataRef::Mark(*mark),
+                    None => {
+                        let data = self
+                            .repo
+                            .odb
+                            .find_blob(id, &mut self.buf, &mut git_pack::cache::Never)?
+                            .data
+                            .to_owned();
+                        let mark = self.mark(id);
+                        Command::Blob { mark: Some(mark), data }.write_to(&mut *out)?;
+                        self.outcome.blobs += 1;
+                        DataRef::Mark(mark)
+                    }
+                },
+            };
+            changes.push(FileChange::Modify {
+                mode,
+                data: FileData::Ref(data),
+                path,
+            });
+        }
+
+        let mut parents = commit.parents.iter().map(|parent| DataRef::Mark(self.marks[parent]));
+        let from = parents.next();
+        let merges = parents.collect();
+        Command::Commit(Commit {
+            reference: reference.clone(),
+            mark: Some(self.mark(id)),
+            author: Some(commit.author),
+            committer: commit.committer,
+            encoding: commit.encoding,
+            message: commit.message,
+            from,
+            merges,
+            changes,
+        })
+        .write_to(&mut *out)?;
+        self.outcome.commits += 1;
+        Ok(())
+    }
+
+    /// Recursively collect all non-tree entries of `tree` with their full path, prefixed with `prefix`.
+    fn collect_files(
+        &mut self,
+        tree: ObjectId,
+        prefix: BString,
+        out: &mut Vec<(BString, EntryMode, ObjectId)>,
+    ) -> Result<(), Error> {
+        let entries: Vec<_> = self
+            .repo
+            .odb
+            .find_tree(tree, &mut self.buf, &mut git_pack::cache::Never)?
+            .entries
+            .into_iter()
+            .map(|entry| (entry.filename.to_owned(), entry.mode, entry.oid.to_owned()))
+            .collect();
+        for (name, mode, id) in entries {
+            let mut path = prefix.clone();
+            if !path.is_empty() {
+                path.push(b'/');
+            }
+            path.extend_from_slice(&name);
+            if mode.is_tree() {
+                self.collect_files(id, path, out)?;
+            } else {
+                out.push((path, mode, id));
+            }
+        }
+        Ok(())
+    }
+}
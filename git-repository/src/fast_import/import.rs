@@ -0,0 +1,416 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+    io,
+};
+
+use git_hash::ObjectId;
+use git_object::{
+    bstr::{BStr, BString, ByteSlice},
+    tree::EntryMode,
+};
+use git_odb::{FindExt, Write};
+use git_ref::{
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+    FullName, Target,
+};
+
+use crate::{
+    easy::ext::ReferenceAccessExt,
+    fast_import::{decode, Command, Commit, DataRef, FileChange, FileData, Tag},
+    Repository,
+};
+
+/// The error returned by [`Repository::fast_import()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Decode(#[from] decode::Error),
+    #[error("The mark :{} was used before it was set", .mark)]
+    UnknownMark { mark: u32 },
+    #[error(transparent)]
+    FindExistingObject(#[from] git_pack::find::existing::Error<git_odb::compound::find::Error>),
+    #[error(transparent)]
+    FindExistingTypedObject(#[from] git_pack::find::existing_object::Error<git_odb::compound::find::Error>),
+    #[error(transparent)]
+    WriteObject(#[from] git_odb::loose::write::Error),
+    #[error(transparent)]
+    FindReference(#[from] git_ref::file::find::Error),
+    #[error(transparent)]
+    PackedRefsOpen(#[from] git_ref::packed::buffer::open::Error),
+    #[error(transparent)]
+    ReferenceName(#[from] git_validate::refname::Error),
+    #[error(transparent)]
+    EditReferences(#[from] crate::easy::reference::edit::Error),
+}
+
+/// The outcome of [`Repository::fast_import()`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// The objects of all marks set in the stream.
+    pub marks: HashMap<u32, ObjectId>,
+    /// The references that were set, along with the object they point to now.
+    pub references: BTreeMap<BString, ObjectId>,
+    /// The amount of created commits.
+    pub commits: usize,
+    /// The amount of created blobs.
+    pub blobs: usize,
+    /// The amount of created annotated tags.
+    pub tags: usize,
+}
+
+/// All non-tree entries of a tree by their full path.
+pub(crate) type Files = BTreeMap<BString, (EntryMode, ObjectId)>;
+
+impl Repository {
+    /// Read a stream in the fast-import format from `input`, write all objects it describes into the object database
+    /// and set the references it mentions in a single transaction, acquiring locks according to `lock_mode`.
+    ///
+    /// Similar to `git fast-import --force`, references are updated even if their changes aren't fast-forwards.
+    pub fn fast_import(&self, input: impl io::BufRead, lock_mode: git_lock::acquire::Fail) -> Result<Outcome, Error> {
+        let mut state = State {
+            repo: self,
+            packed: self.refs.packed_buffer()?,
+            tips: BTreeMap::new(),
+            last_commit: None,
+            outcome: Outcome::default(),
+            buf: Vec::new(),
+        };
+        for command in decode::Iter::new(input) {
+            match command? {
+                Command::Blob { mark, data } => {
+                    let id = self
+                        .odb
+                        .write_stream_if_missing(
+                            git_object::Kind::Blob,
+                            data.len() as u64,
+                            data.as_slice(),
+                            None,
+                            self.hash_kind,
+                        )?
+                        .id()
+                        .to_owned();
+                    state.set_mark(mark, id);
+                    state.outcome.blobs += 1;
+                }
+                Command::Commit(commit) => state.commit(commit)?,
+                Command::Tag(tag) => state.tag(tag)?,
+                Command::Reset { reference, from } => {
+                    let id = from.map(|from| state.resolve(from)).transpose()?;
+                    state.tips.insert(reference, id);
+                }
+                Command::Done => break,
+            }
+        }
+
+        let mut edits = Vec::new();
+        for (name, id) in state.tips.iter() {
+            if let Some(id) = id {
+                edits.push(RefEdit {
+                    change: Change::Update {
+                        log: LogChange {
+                            mode: RefLog::AndReference,
+                            force_create_reflog: false,
+                            message: "fast-import".into(),
+                        },
+                        expected: PreviousValue::Any,
+                        new: Target::Peeled(*id),
+                    },
+                    name: FullName::try_from(name.as_bstr())?,
+                    deref: false,
+                });
+                state.outcome.references.insert(name.clone(), *id);
+            }
+        }
+        self.to_easy().edit_references(edits, lock_mode, None)?;
+        Ok(state.outcome)
+    }
+}
+
+struct State<'repo> {
+    repo: &'repo Repository,
+    packed: Option<git_ref::packed::Buffer>,
+    /// The current tips of all references touched by the stream, or `None` if they were reset.
+    tips: BTreeMap<BString, Option<ObjectId>>,
+    /// The last commit created along with its files, as the next commit most likely builds on it.
+    last_commit: Option<(ObjectId, Files)>,
+    outcome: Outcome,
+    buf: Vec<u8>,
+}
+
+impl<'repo> State<'repo> {
+    fn set_mark(&mut self, mark: Option<u32>, id: ObjectId) {
+        if let Some(mark) = mark {
+            self.outcome.marks.insert(mark, id);
+        }
+    }
+
+    fn resolve(&self, data: DataRef) -> Result<ObjectId, Error> {
+        match data {
+            DataRef::Mark(mark) => self
+                .outcome
+                .marks
+                .get(&mark)
+                .copied()
+                .ok_or(Error::UnknownMark { mark }),
+            DataRef::Id(id) => Ok(id),
+        }
+    }
+
+    /// Return the current tip of `reference`, either as set by the stream or as found in the repository.
+    fn tip(&self, reference: &BStr) -> Result<Option<ObjectId>, Error> {
+        if let Some(tip) = self.tips.get(reference) {
+            return Ok(*tip);
+        }
+        Ok(self
+            .repo
+            .refs
+            .try_find(reference, self.packed.as_ref())?
+            .and_then(|r| r.target.as_id().map(ToOwned::to_owned)))
+    }
+
+    fn commit(&mut self, commit: Commit) -> Result<(), Error> {
+        let Commit {
+            reference,
+            mark,
+            author,
+            committer,
+            encoding,
+            message,
+            from,
+            merges,
+            changes,
+        } = commit;
+        let parent = match from {
+            Some(from) => Some(self.resolve(from)?).filter(|id| !id.is_null()),
+            None => self.tip(reference.as_ref())?,
+        };
+        let mut files = match (parent, self.last_commit.take()) {
+            (Some(parent), Some((last, files))) if parent == last => files,
+            (Some(parent), _) => {
+                let tree = self
+                    .repo
+                    .odb
+                    .find_commit(parent, &mut self.buf, &mut git_pack::cache::Never)?
+                    .tree();
+                let mut files = Files::new();
+                self.add_tree(tree, BString::default(), &mut files)?;
+                files
+            }
+            (None, _) => Files::new(),
+        };
+        for change in changes {
+            self.apply(change, &mut files)?;
+        }
+
+        let tree = write_tree(&self.repo.odb, self.repo.hash_kind, &files)?;
+        let mut parents: Vec<_> = parent.into_iter().collect();
+        for merge in merges {
+            parents.push(self.resolve(merge)?);
+        }
+        let id = self.repo.odb.write(
+            git_object::Commit {
+                tree,
+                parents: parents.into(),
+                author: author.unwrap_or_else(|| committer.clone()),
+                committer,
+                encoding,
+                message,
+                extra_headers: Vec::new(),
+            },
+            self.repo.hash_kind,
+        )?;
+        self.set_mark(mark, id);
+        self.tips.insert(reference, Some(id));
+        self.last_commit = Some((id, files));
+        self.outcome.commits += 1;
+        Ok(())
+    }
+
+    fn tag(&mut self, tag: Tag) -> Result<(), Error> {
+        let target = self.resolve(tag.from)?;
+        let target_kind = self
+            .repo
+            .odb
+            .find(target, &mut self.buf, &mut git_pack::cache::Never)?
+            .kind;
+        let id = self.repo.odb.write(
+            git_object::Tag {
+                target,
+                target_kind,
+                name: tag.name.clone(),
+                tagger: tag.tagger,
+                message: tag.message,
+                pgp_signature: None,
+            },
+            self.repo.hash_kind,
+        )?;
+        self.set_mark(tag.mark, id);
+        let mut reference = BString::from("refs/tags/");
+        reference.extend_from_slice(&tag.name);
+        self.tips.insert(reference, Some(id));
+        self.outcome.tags += 1;
+        Ok(())
+    }
+
+    fn apply(&mut self, change: FileChange, files: &mut Files) -> Result<(), Error> {
+        let is_rename = matches!(change, FileChange::Rename { .. });
+        match change {
+            FileChange::Modify { mode, data, path } => {
+                let id = match data {
+                    FileData::Inline(data) => {
+                        self.outcome.blobs += 1;
+                        self.repo
+                            .odb
+                            .write_stream_if_missing(
+                                git_object::Kind::Blob,
+                                data.len() as u64,
+                                data.as_slice(),
+                                None,
+                                self.repo.hash_kind,
+                            )?
+                            .id()
+                            .to_owned()
+                    }
+                    FileData::Ref(data) => self.resolve(data)?,
+                };
+                remove(files, path.as_ref());
+                if mode.is_tree() {
+                    self.add_tree(id, path, files)?;
+                } else {
+                    insert(files, path, mode, id);
+                }
+            }
+            FileChange::Delete { path } => remove(files, path.as_ref()),
+            FileChange::Copy { source, destination } | FileChange::Rename { source, destination } => {
+                let moved: Vec<_> = files
+                    .iter()
+                    .filter(|(path, _)| is_below(source.as_ref(), path.as_ref()))
+                    .map(|(path, entry)| {
+                        let mut new_path = destination.clone();
+                        new_path.extend_from_slice(&path[source.len()..]);
+                        (path.clone(), new_path, *entry)
+                    })
+                    .collect();
+                if is_rename {
+                    remove(files, source.as_ref());
+                }
+                remove(files, destination.as_ref());
+                for (_, new_path, (mode, id)) in moved {
+                    insert(files, new_path, mode, id);
+                }
+            }
+            FileChange::DeleteAll => files.clear(),
+        }
+        Ok(())
+    }
+
+    /// Recursively add all non-tree entries of `tree` to `files`, with their paths prefixed with `prefix`.
+    fn add_tree(&mut self, tree: ObjectId, prefix: BString, files: &mut Files) -> Result<(), Error> {
+        let entries: Vec<_> = self
+            .repo
+            .odb
+            .find_tree(tree, &mut self.buf, &mut git_pack::cache::Never)?
+            .entries
+            .into_iter()
+            .map(|entry| (entry.filename.to_owned(), entry.mode, entry.oid.to_owned()))
+            .collect();
+        for (name, mode, id) in entries {
+            let path = join(prefix.as_ref(), name.as_ref());
+            if mode.is_tree() {
+                self.add_tree(id, path, files)?;
+            } else {
+                files.insert(path, (mode, id));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write all trees needed to represent `files` into `odb` and return the id of the root tree.
+pub(crate) fn write_tree<W: git_odb::Write>(
+    odb: &W,
+    hash_kind: git_hash::Kind,
+    files: &Files,
+) -> Result<ObjectId, W::Error> {
+    enum Node<'a> {
+        File(EntryMode, ObjectId),
+        Dir(BTreeMap<&'a BStr, Node<'a>>),
+    }
+    fn write<'a, W: git_odb::Write>(
+        odb: &W,
+        hash_kind: git_hash::Kind,
+        entries: &BTreeMap<&'a BStr, Node<'a>>,
+    ) -> Result<ObjectId, W::Error> {
+        let mut tree = git_object::Tree { entries: Vec::new() };
+        for (name, node) in entries {
+            let (mode, oid) = match node {
+                Node::File(mode, id) => (*mode, *id),
+                Node::Dir(entries) => (EntryMode::Tree, write(odb, hash_kind, entries)?),
+            };
+            tree.entries.push(git_object::tree::Entry {
+                mode,
+                filename: (*name).to_owned(),
+                oid,
+            });
+        }
+        // Directories sort as if their names had a trailing slash.
+        tree.entries.sort_by(|a, b| {
+            let key = |e: &git_object::tree::Entry| {
+                let mut name = e.filename.to_vec();
+                if e.mode.is_tree() {
+                    name.push(b'/');
+                }
+                name
+            };
+            key(a).cmp(&key(b))
+        });
+        odb.write(tree, hash_kind)
+    }
+
+    let mut root = BTreeMap::new();
+    for (path, (mode, id)) in files {
+        let mut components = path.split_str("/").map(|c| c.as_bstr()).peekable();
+        let mut dir = &mut root;
+        while let Some(component) = components.next() {
+            if components.peek().is_none() {
+                dir.insert(component, Node::File(*mode, *id));
+            } else {
+                let node = dir.entry(component).or_insert_with(|| Node::Dir(BTreeMap::new()));
+                dir = match node {
+                    Node::Dir(entries) => entries,
+                    Node::File(..) => unreachable!("files never have children"),
+                };
+            }
+        }
+    }
+    write(odb, hash_kind, &root)
+}
+
+/// Returns true if `path` is `dir` itself or inside of it. An empty `dir` is the root and contains everything.
+fn is_below(dir: &BStr, path: &BStr) -> bool {
+    dir.is_empty() || (path.starts_with(dir) && (path.len() == dir.len() || path[dir.len()] == b'/'))
+}
+
+pub(crate) fn join(prefix: &BStr, name: &BStr) -> BString {
+    let mut path = prefix.to_owned();
+    if !path.is_empty() {
+        path.push(b'/');
+    }
+    path.extend_from_slice(name);
+    path
+}
+
+/// Remove the file or directory at `path`.
+fn remove(files: &mut Files, path: &BStr) {
+    files.retain(|candidate, _| !is_below(path, candidate.as_ref()));
+}
+
+/// Insert a file at `path`, removing files that are in the way as they are one of its parent directories.
+pub(crate) fn insert(files: &mut Files, path: BString, mode: EntryMode, id: ObjectId) {
+    for pos in path.find_iter("/") {
+        files.remove(path[..pos].as_bstr());
+    }
+    files.insert(path, (mode, id));
+}
@@ -0,0 +1,113 @@
+//! Read and write streams in the [`git fast-import`](https://git-scm.com/docs/git-fast-import) format, the canonical
+//! format to exchange repository histories with other tools, along with [exporting][Repository::fast_export()] and
+//! [importing][Repository::fast_import()] repositories.
+//!
+//! Only the most common subset of the format is supported, namely the `blob`, `commit`, `tag`, `reset` and `done` commands.
+//! Notes, `ls`, `cat-blob` and similar commands requiring interaction with the importer are not supported.
+use git_hash::ObjectId;
+use git_object::{bstr::BString, tree::EntryMode};
+
+#[cfg(doc)]
+use crate::Repository;
+
+///
+pub mod decode;
+mod encode;
+///
+pub mod export;
+///
+pub mod import;
+
+/// A reference to data or objects within a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DataRef {
+    /// A mark set by an earlier command in the same stream, like `:1`.
+    Mark(u32),
+    /// An object in the repository.
+    Id(ObjectId),
+}
+
+/// The data of a file to modify in a [`FileChange::Modify`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FileData {
+    /// Data given by mark or object id.
+    Ref(DataRef),
+    /// The data follows the command directly.
+    Inline(Vec<u8>),
+}
+
+/// A change to the files of a commit, relative to its first parent.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum FileChange {
+    /// Add or change the file at `path` to have `mode` and `data`.
+    ///
+    /// If `mode` is a [tree][EntryMode::Tree], the whole directory at `path` is replaced with the tree referred to by `data`.
+    Modify {
+        mode: EntryMode,
+        data: FileData,
+        path: BString,
+    },
+    /// Delete the file or directory at `path`.
+    Delete { path: BString },
+    /// Copy the file or directory at `source` to `destination`.
+    Copy { source: BString, destination: BString },
+    /// Move the file or directory at `source` to `destination`.
+    Rename { source: BString, destination: BString },
+    /// Remove all files, usually followed by modifications listing the complete content of the commit.
+    DeleteAll,
+}
+
+/// A commit to create on a branch.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Commit {
+    /// The reference to put the commit on, like `refs/heads/main`.
+    pub reference: BString,
+    /// The mark to assign to the commit.
+    pub mark: Option<u32>,
+    /// The author of the commit, or `None` if it is the same as the `committer`.
+    pub author: Option<git_actor::Signature>,
+    /// The committer of the commit.
+    pub committer: git_actor::Signature,
+    /// The encoding of the `message`, if not UTF-8.
+    pub encoding: Option<BString>,
+    /// The commit message.
+    pub message: BString,
+    /// The first parent, or `None` to use the current tip of `reference` as parent if there is one.
+    pub from: Option<DataRef>,
+    /// Additional parents.
+    pub merges: Vec<DataRef>,
+    /// The changes to apply to the tree of the first parent.
+    pub changes: Vec<FileChange>,
+}
+
+/// An annotated tag to create.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Tag {
+    /// The name of the tag without the `refs/tags/` prefix.
+    pub name: BString,
+    /// The mark to assign to the tag object.
+    pub mark: Option<u32>,
+    /// The object the tag points to.
+    pub from: DataRef,
+    /// The creator of the tag.
+    pub tagger: Option<git_actor::Signature>,
+    /// The tag message.
+    pub message: BString,
+}
+
+/// A command in a fast-import stream.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum Command {
+    /// Create a blob with `data`.
+    Blob { mark: Option<u32>, data: Vec<u8> },
+    /// Create a commit.
+    Commit(Commit),
+    /// Create an annotated tag.
+    Tag(Tag),
+    /// Set `reference` to point to `from`, or reset it to not exist if `None`, so the next commit on it has no parent.
+    Reset { reference: BString, from: Option<DataRef> },
+    /// Signal the end of the stream.
+    Done,
+}
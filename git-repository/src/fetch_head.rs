@@ -0,0 +1,95 @@
+//! Write the `FETCH_HEAD` file recording the references obtained by a fetch, in the format produced by `git fetch`.
+//!
+//! Each line holds the id of the fetched object, `not-for-merge` if it should not be merged by a subsequent `git pull`, and a
+//! description of the reference and the remote it was fetched from, like `branch 'main' of https://github.com/Byron/gitoxide`.
+use std::{io, path::PathBuf};
+
+use git_hash::ObjectId;
+use git_object::bstr::{BStr, BString, ByteSlice};
+
+use crate::Repository;
+
+/// The name of the file in the git directory.
+pub const FILE_NAME: &str = "FETCH_HEAD";
+
+/// A reference obtained by a fetch.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Entry {
+    /// The id the reference pointed to on the remote, which is the id of the tag object for annotated tags.
+    pub id: ObjectId,
+    /// The full name of the reference on the remote, like `refs/heads/main` or `HEAD`.
+    pub name: BString,
+    /// If true, the entry is to be merged by a subsequent pull, otherwise it is annotated with `not-for-merge`.
+    pub for_merge: bool,
+}
+
+impl Entry {
+    /// Return the description of this entry as fetched from `url`, like `branch 'main' of https://example.com/repo`.
+    ///
+    /// Like `git`, trailing slashes and a `.git` suffix are removed from `url`.
+    pub fn description(&self, url: &BStr) -> BString {
+        let name = self.name.as_bstr();
+        let (kind, what) = if name == "HEAD" {
+            ("", None)
+        } else if let Some(branch) = name.strip_prefix(b"refs/heads/") {
+            ("branch ", Some(branch.as_bstr()))
+        } else if let Some(tag) = name.strip_prefix(b"refs/tags/") {
+            ("tag ", Some(tag.as_bstr()))
+        } else if let Some(branch) = name.strip_prefix(b"refs/remotes/") {
+            ("remote-tracking branch ", Some(branch.as_bstr()))
+        } else {
+            ("", Some(name))
+        };
+        let mut out = BString::from(kind);
+        if let Some(what) = what {
+            out.extend_from_slice(b"'");
+            out.extend_from_slice(what);
+            out.extend_from_slice(b"' of ");
+        }
+        out.extend_from_slice(shorten_url(url));
+        out
+    }
+}
+
+/// Write `entries` fetched from `url` to `out` in the format of `FETCH_HEAD`.
+///
+/// Entries to be merged are written first, followed by all others, each group in the given order, just like `git fetch` does.
+pub fn encode(entries: &[Entry], url: &BStr, mut out: impl io::Write) -> io::Result<()> {
+    for for_merge in &[true, false] {
+        for entry in entries.iter().filter(|entry| entry.for_merge == *for_merge) {
+            write!(
+                out,
+                "{}\t{}\t",
+                entry.id,
+                if entry.for_merge { "" } else { "not-for-merge" }
+            )?;
+            out.write_all(&entry.description(url))?;
+            out.write_all(b"\n")?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove trailing slashes from `url`, followed by a trailing `.git`.
+fn shorten_url(url: &BStr) -> &[u8] {
+    let mut url = url.as_bytes();
+    while let Some(stripped) = url.strip_suffix(b"/") {
+        url = stripped;
+    }
+    match url.strip_suffix(b".git") {
+        Some(stripped) if url.len() > 5 => stripped,
+        _ => url,
+    }
+}
+
+impl Repository {
+    /// Replace the `FETCH_HEAD` file of this repository with `entries` fetched from `url`, see [`encode()`] for details,
+    /// and return its path.
+    pub fn write_fetch_head(&self, entries: &[Entry], url: &BStr) -> io::Result<PathBuf> {
+        let mut buf = Vec::new();
+        encode(entries, url, &mut buf)?;
+        let path = self.git_dir().join(FILE_NAME);
+        std::fs::write(&path, buf)?;
+        Ok(path)
+    }
+}
@@ -0,0 +1,78 @@
+//! Convert blobs into the form they take in the work tree, the counterpart of the conversions performed by
+//! [`Repository::add()`].
+use std::io::{self, Read};
+
+use git_config::values::Boolean;
+
+use crate::Repository;
+
+/// The amount of bytes at the beginning of a file to look at for null bytes to determine it's binary, like git does.
+pub const BINARY_DETECTION_WINDOW: usize = 8000;
+
+/// Return true if `data` is considered binary, which is the case if there is a null byte among its first
+/// [`BINARY_DETECTION_WINDOW`] bytes.
+pub fn is_binary(data: &[u8]) -> bool {
+    data[..data.len().min(BINARY_DETECTION_WINDOW)].contains(&0)
+}
+
+/// The conversions to apply to blobs as they are written to the work tree, as configured.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Smudge {
+    /// If true, line feeds of text files are converted to CRLF, which is the case if `core.autocrlf` is `true`.
+    pub autocrlf: bool,
+}
+
+impl Repository {
+    /// Return the conversions to apply to blobs written to the work tree as configured for this repository.
+    ///
+    /// Note that only end-of-line conversion as configured by `core.autocrlf` is supported, and that `.gitattributes`
+    /// aren't consulted yet.
+    pub fn smudge_filter(&self) -> Result<Smudge, crate::config::Error> {
+        let config = crate::config::open(self)?;
+        let autocrlf = config
+            .value::<Boolean<'_>>("core", None, "autocrlf")
+            .map_or(false, |b| matches!(b, Boolean::True(_)));
+        Ok(Smudge { autocrlf })
+    }
+}
+
+impl Smudge {
+    /// Copy all bytes of `input` to `out` while converting them as configured, and return the amount of bytes written.
+    ///
+    /// The input is streamed in chunks, and considered binary and left unconverted if there is a null byte among its first
+    /// 8000 bytes, just like `git` does. Line feeds which are already preceded by a carriage return aren't converted.
+    pub fn apply(&self, mut input: impl io::Read, mut out: impl io::Write) -> io::Result<u64> {
+        if !self.autocrlf {
+            return io::copy(&mut input, &mut out);
+        }
+        let mut chunk = Vec::with_capacity(BINARY_DETECTION_WINDOW);
+        (&mut input)
+            .take(BINARY_DETECTION_WINDOW as u64)
+            .read_to_end(&mut chunk)?;
+        if is_binary(&chunk) {
+            out.write_all(&chunk)?;
+            return Ok(chunk.len() as u64 + io::copy(&mut input, &mut out)?);
+        }
+
+        let mut written = 0;
+        let mut last = None;
+        let mut converted = Vec::with_capacity(BINARY_DETECTION_WINDOW * 2);
+        while !chunk.is_empty() {
+            converted.clear();
+            for &byte in &chunk {
+                if byte == b'\n' && last != Some(b'\r') {
+                    converted.push(b'\r');
+                }
+                converted.push(byte);
+                last = Some(byte);
+            }
+            out.write_all(&converted)?;
+            written += converted.len() as u64;
+            chunk.clear();
+            (&mut input)
+                .take(BINARY_DETECTION_WINDOW as u64)
+                .read_to_end(&mut chunk)?;
+        }
+        Ok(written)
+    }
+}
@@ -0,0 +1,254 @@
+//! Follow the history of a single file through renames, similar to `git log --follow <path>`.
+//!
+//! Whenever the followed path doesn't exist in any parent of a commit, rename detection is performed against its first
+//! parent to find the path the file had before, which is then followed instead.
+use git_diff::candidates;
+use git_hash::ObjectId;
+use git_object::{
+    bstr::{BString, ByteSlice},
+    tree::EntryMode,
+};
+use git_odb::FindExt;
+
+use crate::{ext::TreeIterExt, Repository};
+
+/// Options for [`Repository::follow()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// Limits for finding rename candidates among the files deleted by a commit.
+    pub limits: candidates::Limits,
+    /// The similarity in percent a deleted file must have with the followed one to be considered its previous version.
+    /// `git` defaults to 50.
+    pub min_similarity: u32,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            limits: Default::default(),
+            min_similarity: 50,
+        }
+    }
+}
+
+/// A commit which changed the followed file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
+pub struct Item {
+    /// The id of the commit.
+    pub id: ObjectId,
+    /// The path of the file in this commit, which is different from the path it has in its descendants if it was renamed later.
+    pub path: BString,
+    /// The path the file had in the first parent if this commit renamed it.
+    pub renamed_from: Option<BString>,
+}
+
+/// The error returned by [`Iter`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Traverse(#[from] git_traverse::commit::ancestors::Error),
+    #[error(transparent)]
+    FindExistingObject(#[from] git_pack::find::existing_object::Error<git_odb::compound::find::Error>),
+    #[error(transparent)]
+    FindExistingTree(#[from] git_pack::find::existing_iter::Error<git_odb::compound::find::Error>),
+    #[error(transparent)]
+    TreeChanges(#[from] git_diff::tree::changes::Error),
+    #[error(transparent)]
+    Decode(#[from] git_object::decode::Error),
+}
+
+/// An iterator over all commits that changed the followed file, created by [`Repository::follow()`].
+pub struct Iter<'repo> {
+    repo: &'repo Repository,
+    ancestors: Box<dyn Iterator<Item = Result<ObjectId, git_traverse::commit::ancestors::Error>> + 'repo>,
+    path: BString,
+    options: Options,
+    buf: Vec<u8>,
+}
+
+impl Repository {
+    /// Return an iterator over the commits reachable from `tip` which changed the file at `path`, like `a/b/c`, following it
+    /// through renames as configured by `options`.
+    ///
+    /// Like `git log --follow`, only a single path is followed at a time, even if the history is non-linear and the file has
+    /// different paths on different branches. Merges are only yielded if the file differs from all of their parents.
+    pub fn follow(&self, tip: impl Into<ObjectId>, path: impl Into<BString>, options: Options) -> Iter<'_> {
+        Iter {
+            repo: self,
+            ancestors: Box::new(git_traverse::commit::Ancestors::new(
+                Some(tip.into()),
+                git_traverse::commit::ancestors::State::default(),
+                move |oid, buf| self.odb.find_commit_iter(oid, buf, &mut git_pack::cache::Never).ok(),
+            )),
+            path: path.into(),
+            options,
+            buf: Vec::new(),
+        }
+    }
+}
+
+impl<'repo> Iter<'repo> {
+    fn handle(&mut self, id: ObjectId) -> Result<Option<Item>, Error> {
+        let (tree, parents) = {
+            let commit = self
+                .repo
+                .odb
+                .find_commit(id, &mut self.buf, &mut git_pack::cache::Never)?;
+            (commit.tree(), commit.parents().collect::<Vec<_>>())
+        };
+        let entry = match self.lookup(tree)? {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        let mut parent_trees = Vec::with_capacity(parents.len());
+        let mut exists_in_parent = false;
+        for parent in parents {
+            let parent_tree = self
+                .repo
+                .odb
+                .find_commit(parent, &mut self.buf, &mut git_pack::cache::Never)?
+                .tree();
+            match self.lookup(parent_tree)? {
+                Some(parent_entry) if parent_entry == entry => return Ok(None),
+                Some(_) => exists_in_parent = true,
+                None => {}
+            }
+            parent_trees.push(parent_tree);
+        }
+        let renamed_from = match parent_trees.first() {
+            Some(parent_tree) if !exists_in_parent => self.find_rename(*parent_tree, tree, entry.0)?,
+            _ => None,
+        };
+        let item = Item {
+            id,
+            path: self.path.clone(),
+            renamed_from: renamed_from.clone(),
+        };
+        if let Some(previous_path) = renamed_from {
+            self.path = previous_path;
+        }
+        Ok(Some(item))
+    }
+
+    /// Return the id and mode of the blob or link at the followed path in `tree`, or `None` if there is none or it's a tree.
+    fn lookup(&mut self, mut tree: ObjectId) -> Result<Option<(ObjectId, EntryMode)>, Error> {
+        let mut components = self.path.split_str("/").filter(|c| !c.is_empty()).peekable();
+        while let Some(component) = components.next() {
+            let entry = self
+                .repo
+                .odb
+                .find_tree_iter(tree, &mut self.buf, &mut git_pack::cache::Never)?
+                .find(|entry| entry.as_ref().map_or(true, |entry| entry.filename == component))
+                .transpose()?
+                .map(|entry| (entry.oid.to_owned(), entry.mode));
+            match entry {
+                Some(entry @ (_, EntryMode::Blob | EntryMode::BlobExecutable | EntryMode::Link))
+                    if components.peek().is_none() =>
+                {
+                    return Ok(Some(entry))
+                }
+                Some((oid, mode)) if mode.is_tree() => tree = oid,
+                _ => return Ok(None),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Find the path of a blob deleted between `parent_tree` and `tree` which is identical or most similar to the blob `id`.
+    fn find_rename(&mut self, parent_tree: ObjectId, tree: ObjectId, id: ObjectId) -> Result<Option<BString>, Error> {
+        let odb = &self.repo.odb;
+        let mut recorder = git_diff::tree::Recorder::default();
+        {
+            let mut parent_buf = Vec::new();
+            let mut buf = Vec::new();
+            odb.find_tree_iter(parent_tree, &mut parent_buf, &mut git_pack::cache::Never)?
+                .changes_needed(
+                    odb.find_tree_iter(tree, &mut buf, &mut git_pack::cache::Never)?,
+                    git_diff::tree::State::default(),
+                    |oid, buf| odb.find_tree_iter(oid, buf, &mut git_pack::cache::Never).ok(),
+                    &mut recorder,
+                )?;
+        }
+        let deleted: Vec<_> = recorder
+            .records
+            .into_iter()
+            .filter_map(|change| match change {
+                git_diff::tree::recorder::Change::Deletion { entry_mode, oid, path }
+                    if matches!(entry_mode, EntryMode::Blob | EntryMode::BlobExecutable) =>
+                {
+                    Some((oid, path))
+                }
+                _ => None,
+            })
+            .collect();
+        if deleted.is_empty() {
+            return Ok(None);
+        }
+
+        let target = odb
+            .find_blob(id, &mut self.buf, &mut git_pack::cache::Never)?
+            .data
+            .to_owned();
+        let mut sources = Vec::with_capacity(deleted.len());
+        for (oid, _) in &deleted {
+            let size = odb
+                .find_blob(oid, &mut self.buf, &mut git_pack::cache::Never)?
+                .data
+                .len();
+            sources.push(candidates::Item {
+                id: *oid,
+                size: size as u64,
+            });
+        }
+        let destination = candidates::Item {
+            id,
+            size: target.len() as u64,
+        };
+        let outcome = candidates::find(&sources, &[destination], &self.options.limits);
+        if let Some(exact) = outcome.exact.first() {
+            return Ok(Some(deleted[exact.source].1.clone()));
+        }
+        let mut best: Option<(u32, usize)> = None;
+        for source in outcome.similar.into_iter().flat_map(|(_, sources)| sources) {
+            let data = odb
+                .find_blob(sources[source].id, &mut self.buf, &mut git_pack::cache::Never)?
+                .data;
+            let score = similarity(data, &target);
+            if score >= self.options.min_similarity && best.map_or(true, |(best_score, _)| score > best_score) {
+                best = Some((score, source));
+            }
+        }
+        Ok(best.map(|(_, source)| deleted[source].1.clone()))
+    }
+}
+
+/// Return the similarity of `source` and `target` in percent, as the share of `target` that can be copied from `source`
+/// relative to the size of the larger one.
+fn similarity(source: &[u8], target: &[u8]) -> u32 {
+    let larger = source.len().max(target.len());
+    if larger == 0 {
+        return 100;
+    }
+    let delta_size = git_pack::data::delta::Index::new(source).delta_size(target);
+    let copied = target.len().saturating_sub(delta_size);
+    (copied * 100 / larger) as u32
+}
+
+impl<'repo> Iterator for Iter<'repo> {
+    type Item = Result<Item, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let id = match self.ancestors.next()? {
+                Ok(id) => id,
+                Err(err) => return Some(Err(err.into())),
+            };
+            match self.handle(id) {
+                Ok(Some(item)) => return Some(Ok(item)),
+                Ok(None) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Information about the filesystem a repository is stored on, and how to compare paths the way it does.
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use git_config::values::Boolean;
+use git_object::bstr::{BStr, BString, ByteSlice};
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+use crate::Repository;
+
+/// The capabilities of a filesystem as far as they affect how paths are stored and looked up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// If true, paths that differ only in case refer to the same file, like `README` and `readme`.
+    ///
+    /// This is the value of `core.ignoreCase`.
+    pub ignore_case: bool,
+    /// If true, paths in unicode normal form C and D refer to the same file, and the filesystem returns them in form D when
+    /// listing directories, like it's common on MacOS.
+    ///
+    /// This is the value of `core.precomposeUnicode`.
+    pub precompose_unicode: bool,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Capabilities {
+            ignore_case: cfg!(any(windows, target_os = "macos")),
+            precompose_unicode: cfg!(target_os = "macos"),
+        }
+    }
+}
+
+impl Capabilities {
+    /// Probe the filesystem containing the existing directory `dir` by creating and removing test files within it.
+    ///
+    /// If probing fails, the value for the current platform is assumed, as per [`Capabilities::default()`].
+    pub fn probe(dir: impl AsRef<Path>) -> Self {
+        let dir = dir.as_ref();
+        let defaults = Capabilities::default();
+        Capabilities {
+            ignore_case: probe_alias(dir, "_gix_probe_CaSe", "_GIX_PROBE_CASE").unwrap_or(defaults.ignore_case),
+            precompose_unicode: probe_alias(dir, "_gix_probe_\u{00c4}", "_gix_probe_A\u{0308}")
+                .unwrap_or(defaults.precompose_unicode),
+        }
+    }
+}
+
+/// Create the file `name` in `dir` and return true if it can be accessed as `alias` as well.
+fn probe_alias(dir: &Path, name: &str, alias: &str) -> std::io::Result<bool> {
+    let path = dir.join(name);
+    std::fs::OpenOptions::new().write(true).create_new(true).open(&path)?;
+    let is_alias = dir.join(alias).symlink_metadata().is_ok();
+    std::fs::remove_file(&path)?;
+    Ok(is_alias)
+}
+
+/// A way to compare paths in the index, the work tree or trees, so that paths which refer to the same file on a filesystem with
+/// given [`Capabilities`] compare equal, as git does.
+///
+/// The [default][PathComparison::default()] compares paths byte by byte.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PathComparison {
+    /// If true, ASCII characters are compared case-insensitively, exactly like git does when `core.ignoreCase` is set.
+    pub ignore_case: bool,
+    /// If true, paths in unicode normal form C and D are equal. Paths which aren't valid UTF-8 are compared byte by byte.
+    pub normalize_unicode: bool,
+}
+
+impl From<Capabilities> for PathComparison {
+    fn from(c: Capabilities) -> Self {
+        PathComparison {
+            ignore_case: c.ignore_case,
+            normalize_unicode: c.precompose_unicode,
+        }
+    }
+}
+
+impl PathComparison {
+    /// A comparison which only considers paths equal if they are byte-wise equal.
+    pub fn exact() -> Self {
+        PathComparison::default()
+    }
+
+    /// Return `path` in its canonical form, which is the same for all paths this instance considers equal and suitable for
+    /// use as key in maps.
+    pub fn key<'a>(&self, path: &'a BStr) -> Cow<'a, BStr> {
+        let mut path = Cow::Borrowed(path);
+        if self.normalize_unicode && !path.is_ascii() {
+            if let Ok(s) = path.to_str() {
+                if !is_nfc(s) {
+                    path = Cow::Owned(s.nfc().collect::<String>().into());
+                }
+            }
+        }
+        if self.ignore_case && path.iter().any(u8::is_ascii_uppercase) {
+            path = Cow::Owned(path.to_ascii_lowercase().into());
+        }
+        path
+    }
+
+    /// Return true if `a` and `b` refer to the same file.
+    pub fn eq(&self, a: &BStr, b: &BStr) -> bool {
+        a == b || self.key(a) == self.key(b)
+    }
+
+    /// Compare `a` with `b`, yielding [`Ordering::Equal`] for paths that refer to the same file.
+    pub fn cmp(&self, a: &BStr, b: &BStr) -> Ordering {
+        self.key(a).cmp(&self.key(b))
+    }
+
+    /// Return the entry of the index `state` at `path` with stage 0, or if there is none, the one whose path refers to the same file.
+    pub fn entry_by_path<'a>(&self, state: &'a git_index::State, path: &BStr) -> Option<&'a git_index::Entry> {
+        state.entry_by_path(path).or_else(|| {
+            if *self == PathComparison::exact() {
+                return None;
+            }
+            let key = self.key(path);
+            state
+                .entries()
+                .iter()
+                .find(|e| e.flags.stage == 0 && self.key(e.path.as_ref()) == key)
+        })
+    }
+
+    /// Return the [keys][PathComparison::key()] of all paths in the index `state`, to efficiently find out if many paths,
+    /// like the ones in the work tree, are tracked.
+    pub fn tracked_keys<'a>(&self, state: &'a git_index::State) -> HashSet<Cow<'a, BStr>> {
+        state.entries().iter().map(|e| self.key(e.path.as_ref())).collect()
+    }
+
+    /// Return all groups of two or more `paths` which refer to the same file, in the order they are first seen, for instance to
+    /// detect which files would overwrite each other on checkout.
+    pub fn collisions<'a>(&self, paths: impl IntoIterator<Item = &'a BStr>) -> Vec<Vec<&'a BStr>> {
+        let mut groups = Vec::<Vec<&'a BStr>>::new();
+        let mut group_by_key = HashMap::<BString, usize>::new();
+        for path in paths {
+            let key = self.key(path).into_owned();
+            match group_by_key.get(&key) {
+                Some(idx) => groups[*idx].push(path),
+                None => {
+                    group_by_key.insert(key, groups.len());
+                    groups.push(vec![path]);
+                }
+            }
+        }
+        groups.retain(|group| group.len() > 1);
+        groups
+    }
+}
+
+impl Repository {
+    /// Return the way paths of this repository have to be compared according to its `core.ignoreCase` and `core.precomposeUnicode`
+    /// configuration, which is set when [initializing][crate::init()] the repository after probing the filesystem it's on.
+    ///
+    /// Like in git, unset values default to `false`.
+    pub fn path_comparison(&self) -> Result<PathComparison, crate::config::Error> {
+        let config = crate::config::open(self)?;
+        let flag = |key: &str| {
+            config
+                .value::<Boolean<'_>>("core", None, key)
+                .map_or(false, |b| matches!(b, Boolean::True(_)))
+        };
+        Ok(PathComparison {
+            ignore_case: flag("ignoreCase"),
+            normalize_unicode: flag("precomposeUnicode"),
+        })
+    }
+}
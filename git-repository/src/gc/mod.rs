@@ -0,0 +1,3 @@
+//! Housekeeping operations to keep a repository lean and fast, similar to what `git gc` would do.
+///
+pub mod reflog;
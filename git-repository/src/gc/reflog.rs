@@ -0,0 +1,272 @@
+//! Expire reference log entries according to the `gc.reflogExpire` and `gc.reflogExpireUnreachable` configuration.
+use std::{
+    collections::HashSet,
+    convert::TryInto,
+    path::{Path, PathBuf},
+};
+
+use git_config::file::GitConfig;
+use git_ref::file::log::expire::{Options, Outcome};
+
+use crate::{
+    bstr::{BStr, ByteSlice},
+    easy::ext::ReferenceAccessExt,
+    Repository,
+};
+
+const DAY_IN_SECONDS: u32 = 24 * 60 * 60;
+
+/// The age at which reflog entries are removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Expire {
+    /// Never remove entries.
+    Never,
+    /// Remove entries older than the given amount of seconds, with `0` removing all of them.
+    After(u32),
+}
+
+/// The policy for expiring entries of a particular reflog, as obtained by [`Policy::from_config()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Policy {
+    /// When to remove entries, configured with `gc.reflogExpire`.
+    pub expire: Expire,
+    /// When to remove entries whose object isn't reachable from the reference anymore, configured with `gc.reflogExpireUnreachable`.
+    pub expire_unreachable: Expire,
+}
+
+impl Default for Policy {
+    /// The defaults used by `git`, removing all entries after 90 days, and unreachable ones after 30 days.
+    fn default() -> Self {
+        Policy {
+            expire: Expire::After(90 * DAY_IN_SECONDS),
+            expire_unreachable: Expire::After(30 * DAY_IN_SECONDS),
+        }
+    }
+}
+
+impl Policy {
+    /// Obtain the policy for the reflog of the reference `full_name`, like `refs/heads/main`, from `config`.
+    ///
+    /// Values in `gc.<pattern>.reflogExpire` and `gc.<pattern>.reflogExpireUnreachable` with `<pattern>` matching `full_name` take
+    /// precedence over `gc.reflogExpire` and `gc.reflogExpireUnreachable` respectively, and the [defaults][Policy::default()]
+    /// are used if there is no configuration at all.
+    pub fn from_config(config: &GitConfig<'_>, full_name: &BStr) -> Result<Self, Error> {
+        let mut global = (None, None);
+        let mut pattern_specific = (None, None);
+        for (header, body) in config.sections_by_name_with_header("gc") {
+            let (expire, expire_unreachable) = match &header.subsection_name {
+                None => (&mut global.0, &mut global.1),
+                Some(pattern) if glob_match(pattern.as_bytes(), full_name) => {
+                    (&mut pattern_specific.0, &mut pattern_specific.1)
+                }
+                Some(_) => continue,
+            };
+            for key in body.keys() {
+                let value = || body.value(key).expect("key exists");
+                if key.eq_ignore_ascii_case("reflogExpire") {
+                    *expire = Some(parse_expire(value().as_ref())?);
+                } else if key.eq_ignore_ascii_case("reflogExpireUnreachable") {
+                    *expire_unreachable = Some(parse_expire(value().as_ref())?);
+                }
+            }
+        }
+        let default = Policy::default();
+        Ok(Policy {
+            expire: pattern_specific.0.or(global.0).unwrap_or(default.expire),
+            expire_unreachable: pattern_specific.1.or(global.1).unwrap_or(default.expire_unreachable),
+        })
+    }
+
+    /// Convert this policy into options for expiring entries relative to `now` in seconds since the unix epoch.
+    pub fn to_options(&self, now: u32) -> Options {
+        let before = |expire: Expire| match expire {
+            Expire::Never => None,
+            Expire::After(age) => Some(now.saturating_sub(age)),
+        };
+        Options {
+            expire_before: before(self.expire),
+            expire_unreachable_before: before(self.expire_unreachable),
+        }
+    }
+}
+
+/// Parse values like `never`, `now`, `90.days.ago` or `2 weeks ago`.
+fn parse_expire(value: &[u8]) -> Result<Expire, Error> {
+    let invalid = || Error::InvalidValue {
+        value: value.as_bstr().to_owned(),
+    };
+    let value = value.to_str().map_err(|_| invalid())?.trim().to_ascii_lowercase();
+    Ok(match value.as_str() {
+        "never" | "false" => Expire::Never,
+        "now" | "all" => Expire::After(0),
+        _ => {
+            let mut tokens = value
+                .split(|c: char| c == '.' || c.is_whitespace())
+                .filter(|t| !t.is_empty());
+            let amount: u32 = tokens.next().and_then(|t| t.parse().ok()).ok_or_else(invalid)?;
+            let unit = tokens.next().ok_or_else(invalid)?;
+            if tokens.next() != Some("ago") || tokens.next().is_some() {
+                return Err(invalid());
+            }
+            let unit_in_seconds = match unit.strip_suffix('s').unwrap_or(unit) {
+                "second" => 1,
+                "minute" => 60,
+                "hour" => 60 * 60,
+                "day" => DAY_IN_SECONDS,
+                "week" => 7 * DAY_IN_SECONDS,
+                "month" => 30 * DAY_IN_SECONDS,
+                "year" => 365 * DAY_IN_SECONDS,
+                _ => return Err(invalid()),
+            };
+            Expire::After(amount.saturating_mul(unit_in_seconds))
+        }
+    })
+}
+
+/// Match `name` against `pattern` which may contain `*` to match any amount of characters, including none, and `?` to match
+/// a single character.
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|skip| glob_match(rest, &name[skip..])),
+        Some((b'?', rest)) => !name.is_empty() && glob_match(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && glob_match(rest, &name[1..]),
+    }
+}
+
+/// The error returned by [`Policy::from_config()`] and [`Repository::expire_reflog()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Reflog expiry value '{}' is invalid", .value)]
+    InvalidValue { value: crate::bstr::BString },
+    #[error(transparent)]
+    Config(#[from] crate::config::Error),
+    #[error(transparent)]
+    FindReference(#[from] crate::easy::reference::find::Error),
+    #[error(transparent)]
+    Peel(#[from] crate::easy::reference::peel::Error),
+    #[error(transparent)]
+    Ancestors(#[from] crate::easy::oid::ancestors::Error),
+    #[error(transparent)]
+    Traverse(#[from] git_traverse::commit::ancestors::Error),
+    #[error(transparent)]
+    Expire(#[from] git_ref::file::log::expire::Error),
+    #[error(transparent)]
+    Name(#[from] git_validate::refname::Error),
+    #[error("Could not list reflogs")]
+    Io(#[from] std::io::Error),
+}
+
+impl Repository {
+    /// Expire entries of the reflog of the reference `full_name`, like `refs/heads/main` or `HEAD`, according to the policy
+    /// configured in this repository, relative to `now` in seconds since the unix epoch.
+    ///
+    /// Entries are considered unreachable if their object can't be reached from the current tip of the reference.
+    /// Returns `Ok(None)` if there is no reflog.
+    pub fn expire_reflog(&self, full_name: &str, now: u32) -> Result<Option<Outcome>, Error> {
+        let config = crate::config::open(self)?;
+        self.expire_reflog_inner(&config, full_name, now)
+    }
+
+    /// Like [`expire_reflog()`][Repository::expire_reflog()], but expires all reflogs in the repository and returns the
+    /// accumulated outcome.
+    pub fn expire_reflogs(&self, now: u32) -> Result<Outcome, Error> {
+        if self.is_in_memory() {
+            return Ok(Outcome::default());
+        }
+        let config = crate::config::open(self)?;
+        let mut names = Vec::new();
+        let logs_dir = self.git_dir().join("logs");
+        collect_reflog_names(&logs_dir, &logs_dir, &mut names)?;
+
+        let mut total = Outcome::default();
+        for name in names {
+            if let Some(outcome) = self.expire_reflog_inner(&config, &name, now)? {
+                total.removed += outcome.removed;
+                total.kept += outcome.kept;
+            }
+        }
+        Ok(total)
+    }
+
+    fn expire_reflog_inner(&self, config: &GitConfig<'_>, full_name: &str, now: u32) -> Result<Option<Outcome>, Error> {
+        let refs = match self.refs.as_file() {
+            Some(refs) => refs,
+            None => return Ok(None),
+        };
+        let full_name: git_ref::FullName = full_name.try_into()?;
+        let options = Policy::from_config(config, full_name.as_bstr())?.to_options(now);
+        let easy = self.to_easy();
+        let tip = match easy.try_find_reference(full_name.to_partial())? {
+            Some(reference) => Some(reference.into_fully_peeled_id()?.detach()),
+            None => None,
+        };
+
+        // Traverse eagerly so a traversal error aborts expiry before the reflog is touched. An incomplete set would make
+        // reachable entries look unreachable and lose them.
+        let mut reachable = HashSet::new();
+        if let (Some(tip), Some(_)) = (tip, options.expire_unreachable_before) {
+            for id in crate::easy::Oid::from_id(tip, &easy).ancestors()?.all() {
+                reachable.insert(id?.detach());
+            }
+        }
+        Ok(refs.reflog_expire(
+            full_name.to_ref(),
+            options,
+            git_lock::acquire::Fail::Immediately,
+            |id| reachable.contains(id),
+        )?)
+    }
+}
+
+fn collect_reflog_names(root: &Path, dir: &Path, out: &mut Vec<String>) -> std::io::Result<()> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    for entry in entries {
+        let path: PathBuf = entry?.path();
+        if path.is_dir() {
+            collect_reflog_names(root, &path, out)?;
+        } else if let Some(name) = path.strip_prefix(root).ok().and_then(|p| p.to_str()) {
+            out.push(name.replace(std::path::MAIN_SEPARATOR, "/"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expiry_values() {
+        assert_eq!(parse_expire(b"never").unwrap(), Expire::Never);
+        assert_eq!(parse_expire(b"false").unwrap(), Expire::Never);
+        assert_eq!(parse_expire(b"now").unwrap(), Expire::After(0));
+        assert_eq!(parse_expire(b"all").unwrap(), Expire::After(0));
+        assert_eq!(
+            parse_expire(b"90.days.ago").unwrap(),
+            Expire::After(90 * DAY_IN_SECONDS)
+        );
+        assert_eq!(
+            parse_expire(b"2 weeks ago").unwrap(),
+            Expire::After(14 * DAY_IN_SECONDS)
+        );
+        assert_eq!(parse_expire(b"1.hour.ago").unwrap(), Expire::After(3600));
+        assert!(parse_expire(b"2021-10-01").is_err());
+        assert!(parse_expire(b"2.days").is_err());
+        assert!(parse_expire(b"2.fortnights.ago").is_err());
+    }
+
+    #[test]
+    fn glob_matching() {
+        assert!(glob_match(b"refs/stash", b"refs/stash"));
+        assert!(glob_match(b"refs/remotes/*", b"refs/remotes/origin/main"));
+        assert!(glob_match(b"refs/heads/?ain", b"refs/heads/main"));
+        assert!(!glob_match(b"refs/heads/*", b"refs/remotes/origin/main"));
+        assert!(!glob_match(b"refs/stash", b"refs/stashed"));
+    }
+}
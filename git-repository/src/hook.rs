@@ -0,0 +1,103 @@
+//! Find and run the hooks of a repository, which are executables in `core.hooksPath` or the `hooks` directory of the repository.
+//!
+//! Hooks are only found if the [permissions][crate::open::Permissions::execute_programs] of the repository allow
+//! executing programs.
+use std::{
+    borrow::Cow,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+use git_object::bstr::ByteSlice;
+use git_ref::transaction::hook;
+
+use crate::Repository;
+
+/// The error returned by the [`reference-transaction`][reference_transaction()] hook if it rejected the transaction.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not run the hook at '{}'", path.display())]
+    Spawn { source: std::io::Error, path: PathBuf },
+    #[error("The hook at '{}' rejected the operation with {status}", path.display())]
+    Rejected {
+        path: PathBuf,
+        status: std::process::ExitStatus,
+    },
+}
+
+/// Return the path to the executable hook with `name` in `repo`, or `None` if there is none or executing programs isn't permitted.
+pub fn path(repo: &Repository, name: &str) -> Result<Option<PathBuf>, crate::config::Error> {
+    if !repo.permissions.execute_programs {
+        return Ok(None);
+    }
+    let config = crate::config::open(repo)?;
+    let hooks_dir = match config.value::<Cow<'_, [u8]>>("core", None, "hooksPath") {
+        Ok(path) => working_dir(repo).join(path.to_path_lossy()),
+        Err(_) => repo.git_dir().join("hooks"),
+    };
+    let path = hooks_dir.join(name);
+    Ok(if is_executable(&path) { Some(path) } else { None })
+}
+
+/// Return a function to pass to [`Transaction::hook()`][git_ref::file::Transaction::hook()] which runs the
+/// `reference-transaction` hook of `repo` for each state of the transaction, or `None` if there is no such hook.
+///
+/// Like `git`, a non-zero exit status rejects prepared transactions, and is ignored otherwise.
+pub fn reference_transaction(repo: &Repository) -> Result<Option<Box<hook::Fn>>, crate::config::Error> {
+    let path = match path(repo, "reference-transaction")? {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let working_dir = working_dir(repo).to_owned();
+    let hash_kind = repo.hash_kind;
+    Ok(Some(Box::new(move |state: hook::State, updates: &[hook::Update]| {
+        let spawn_error = |source| Error::Spawn {
+            source,
+            path: path.clone(),
+        };
+        let mut child = Command::new(&path)
+            .arg(state.as_str())
+            .current_dir(&working_dir)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(spawn_error)?;
+        {
+            let mut stdin = child.stdin.take().expect("configured");
+            for update in updates {
+                // The hook may exit without reading its input, which is fine.
+                if update.write_to(&mut stdin, hash_kind).is_err() {
+                    break;
+                }
+            }
+            stdin.flush().ok();
+        }
+        let status = child.wait().map_err(spawn_error)?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(Error::Rejected {
+                path: path.clone(),
+                status,
+            }
+            .into())
+        }
+    })))
+}
+
+/// Hooks run in the working tree, or in the repository itself if it is bare.
+fn working_dir(repo: &Repository) -> &std::path::Path {
+    repo.workdir().unwrap_or_else(|| repo.git_dir())
+}
+
+#[cfg(unix)]
+fn is_executable(path: &std::path::Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).map_or(false, |meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &std::path::Path) -> bool {
+    path.is_file()
+}
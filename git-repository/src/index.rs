@@ -0,0 +1,126 @@
+//! Read the index of a repository and change it under a lock, as foundation for staging files, and convert between the index
+//! and trees.
+use std::path::PathBuf;
+
+pub use git_index::{entry, Entry, State, Version};
+
+use crate::Repository;
+
+/// The error returned when reading or changing the index.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not read the index file at {}", path.display())]
+    Read { source: std::io::Error, path: PathBuf },
+    #[error(transparent)]
+    Decode(#[from] git_index::decode::Error),
+    #[error("The lock for the index file could not be obtained")]
+    AcquireLock(#[from] git_lock::acquire::Error),
+    #[error("Could not write the index file")]
+    Write(#[from] std::io::Error),
+    #[error("Could not move the changed index file into place")]
+    Commit(#[from] git_lock::commit::Error<git_lock::File>),
+    #[error("The object {id} for '{path}' doesn't exist")]
+    MissingObject {
+        id: git_hash::ObjectId,
+        path: git_object::bstr::BString,
+    },
+    #[error(transparent)]
+    WriteTree(#[from] git_index::write_tree::Error<git_odb::loose::write::Error>),
+    #[error(transparent)]
+    ReadTree(#[from] git_index::read_tree::Error<git_odb::compound::find::Error>),
+}
+
+impl Repository {
+    /// Return the path to the index file, which may not exist yet.
+    pub fn index_path(&self) -> PathBuf {
+        self.git_dir().join("index")
+    }
+
+    /// Return the modification time of the index file, which entries are compared with to [tell if they are
+    /// racy][entry::Stat::is_racy()], or `None` if there is no index file.
+    pub fn index_timestamp(&self) -> Option<entry::Time> {
+        std::fs::symlink_metadata(self.index_path())
+            .ok()
+            .map(|meta| crate::add::to_time(meta.modified().ok()))
+    }
+
+    /// Read the index of the repository, or return an empty one if there is no index file yet.
+    pub fn index(&self) -> Result<State, Error> {
+        read(&self.index_path(), self.hash_kind)
+    }
+
+    /// Insert or replace `entries` in the index, just like `git update-index --add --cacheinfo` does, which is useful to stage
+    /// content that was never in the work tree. Entries can be created with [`Entry::from_cacheinfo()`].
+    ///
+    /// All objects must exist, except for the commits of submodules. The index is changed under a lock and written only if
+    /// all entries are valid.
+    pub fn update_index(&self, entries: impl IntoIterator<Item = Entry>) -> Result<(), Error> {
+        self.edit_index(|state| {
+            for entry in entries {
+                if entry.mode != entry::Mode::Commit && !self.odb.contains(entry.id) {
+                    return Err(Error::MissingObject {
+                        id: entry.id,
+                        path: entry.path,
+                    });
+                }
+                state.upsert(entry);
+            }
+            Ok(())
+        })
+    }
+
+    /// Write the trees for all entries of the index into the object database and return the id of the root tree, like
+    /// `git write-tree` does.
+    ///
+    /// Only directories that changed since the trees were last written are written again, and the index is updated to remember
+    /// all trees for the next time.
+    pub fn write_tree(&self) -> Result<git_hash::ObjectId, Error> {
+        self.edit_index(|state| Ok(state.write_tree(&self.odb, self.hash_kind)?))
+    }
+
+    /// Replace all entries of the index with the ones of `tree`, like `git read-tree` does, or add them below the directory
+    /// `prefix` if it is set, like `git read-tree --prefix` does. The latter fails if there already are entries in the way.
+    ///
+    /// The work tree isn't touched, and the new entries are without filesystem metadata.
+    pub fn read_tree(&self, tree: &git_hash::oid, prefix: Option<&git_object::bstr::BStr>) -> Result<(), Error> {
+        self.edit_index(|state| Ok(state.read_tree(tree, prefix, &self.odb)?))
+    }
+
+    /// Lock the index file, read it and pass it to `edit`, to finally write all changes back to disk atomically and return the
+    /// value produced by `edit`. Nothing is written if `edit` fails.
+    ///
+    /// Entries of files modified at or after the time the index is written are [smudged][State::smudge_racy_entries()], so a
+    /// later modification within the same timestamp granularity can't go unnoticed.
+    ///
+    /// The operation fails immediately if the lock can't be obtained. The index is synchronized to permanent storage
+    /// before it is moved into place if the [fsync policy][git_ref::file::Store::fsync] includes metadata.
+    pub(crate) fn edit_index<T, E>(&self, edit: impl FnOnce(&mut State) -> Result<T, E>) -> Result<T, E>
+    where
+        E: From<Error>,
+    {
+        let path = self.index_path();
+        let mut lock = git_lock::File::acquire_to_update_resource(&path, git_lock::acquire::Fail::Immediately, None)
+            .map_err(Error::from)?;
+        let mut state = read(&path, self.hash_kind)?;
+        let res = edit(&mut state)?;
+        state.smudge_racy_entries(crate::add::to_time(Some(std::time::SystemTime::now())));
+        state.write_to(&mut lock, self.hash_kind).map_err(Error::from)?;
+        if self.refs.as_file().map_or(false, |refs| refs.fsync.metadata()) {
+            lock.with_mut(|file| file.sync_all()).map_err(Error::from)?;
+        }
+        lock.commit().map_err(Error::from)?;
+        Ok(res)
+    }
+}
+
+fn read(path: &std::path::Path, hash_kind: git_hash::Kind) -> Result<State, Error> {
+    match std::fs::read(path) {
+        Ok(data) => Ok(State::from_bytes(&data, hash_kind)?),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(State::default()),
+        Err(source) => Err(Error::Read {
+            source,
+            path: path.to_owned(),
+        }),
+    }
+}
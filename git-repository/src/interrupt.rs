@@ -98,7 +98,7 @@ where
 
 /// A wrapper for implementors of [`std::io::Read`] or [`std::io::BufRead`] with interrupt support.
 ///
-/// It fails a [read][`std::io::Read::read`] while an interrupt was requested.
+/// It fails a [read][`std::io::Read::read`] or [fill_buf][`std::io::BufRead::fill_buf`] while an interrupt was requested.
 pub struct Read<R> {
     /// The actual implementor of [`std::io::Read`] to which interrupt support will be added.
     inner: git_features::interrupt::Read<'static, R>,
@@ -146,6 +146,47 @@ where
     }
 }
 
+/// A wrapper for implementors of [`std::io::Write`] with interrupt support.
+///
+/// It fails a [write][`std::io::Write::write`] while an interrupt was requested.
+pub struct Write<W> {
+    /// The actual implementor of [`std::io::Write`] to which interrupt support will be added.
+    inner: git_features::interrupt::Write<'static, W>,
+}
+
+impl<W> Write<W>
+where
+    W: io::Write,
+{
+    /// Create a new interruptible writer from `write`.
+    pub fn new(write: W) -> Self {
+        Write {
+            inner: git_features::interrupt::Write {
+                inner: write,
+                should_interrupt: &IS_INTERRUPTED,
+            },
+        }
+    }
+
+    /// Return the inner writer
+    pub fn into_inner(self) -> W {
+        self.inner.inner
+    }
+}
+
+impl<W> io::Write for Write<W>
+where
+    W: io::Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
 /// The flag behind all utility functions in this module.
 pub static IS_INTERRUPTED: AtomicBool = AtomicBool::new(false);
 
@@ -124,7 +124,7 @@ pub use git_object as objs;
 pub use git_object::bstr;
 #[cfg(feature = "unstable")]
 pub use git_odb as odb;
-#[cfg(all(feature = "unstable", feature = "git-protocol"))]
+#[cfg(feature = "unstable")]
 pub use git_protocol as protocol;
 pub use git_ref as refs;
 #[cfg(feature = "unstable")]
@@ -152,7 +152,7 @@ pub mod prelude {
 pub mod path;
 
 mod repository;
-pub use repository::{discover, init, open};
+pub use repository::{ahead_behind, discover, init, open};
 
 /// A repository path which either points to a work tree or the `.git` repository itself.
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
@@ -173,15 +173,16 @@ pub enum Path {
 /// Namely, this is an object database, a reference database to point to objects.
 pub struct Repository {
     /// A store for references to point at objects
-    pub refs: git_ref::file::Store,
+    pub refs: store::References,
     /// A store for objects that contain data
     #[cfg(feature = "unstable")]
-    pub odb: git_odb::linked::Store,
+    pub odb: store::Objects,
     #[cfg(not(feature = "unstable"))]
-    pub(crate) odb: git_odb::linked::Store,
+    pub(crate) odb: store::Objects,
     /// The path to the worktree at which to find checked out files
     pub work_tree: Option<PathBuf>,
     pub(crate) hash_kind: git_hash::Kind,
+    pub(crate) permissions: open::Permissions,
     // TODO: git-config should be here - it's read a lot but not written much in must applications, so shouldn't be in `State`.
     //       Probably it's best reload it on signal (in servers) or refresh it when it's known to have been changed similar to how
     //       packs are refreshed. This would be `git_config::fs::Config` when ready.
@@ -253,10 +254,31 @@ pub struct EasyArcExclusive {
 
 pub mod easy;
 
+pub mod add;
+pub mod checkout;
 ///
 pub mod commit;
+pub mod config;
+pub mod dirwalk;
+pub mod dissociate;
+pub mod fast_import;
+pub mod fetch_head;
+pub mod filter;
+#[cfg(feature = "git-diff")]
+pub mod follow;
+pub mod fs;
+pub mod gc;
+pub mod hook;
+pub mod index;
+pub mod maintenance;
+pub mod pack;
+pub mod quarantine;
 ///
 pub mod reference;
+pub mod rewrite;
+pub mod sign;
+pub mod status;
+pub mod store;
 
 /// The kind of `Repository`
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
@@ -0,0 +1,27 @@
+use git_odb::FindExt;
+use git_pack::find::existing_object;
+
+use super::Error;
+use crate::Repository;
+
+impl Repository {
+    /// Write all commits reachable from references into `objects/info/commit-graph` and return their amount.
+    pub(crate) fn write_commit_graph(&self) -> Result<u32, Error> {
+        let tips = self.reference_tips()?;
+        let info_dir = self.objects_dir().join("info");
+        std::fs::create_dir_all(&info_dir)?;
+        let mut buf = Vec::new();
+        let outcome = git_commitgraph::write::write(info_dir, tips, |id| {
+            match self.odb.find_commit(id, &mut buf, &mut git_pack::cache::Never) {
+                Ok(commit) => Ok(Some(git_commitgraph::write::Commit {
+                    tree: commit.tree(),
+                    parents: commit.parents().collect(),
+                    committer_timestamp: commit.committer.time.time.into(),
+                })),
+                Err(existing_object::Error::NotFound { .. }) => Ok(None),
+                Err(err) => Err(err),
+            }
+        })?;
+        Ok(outcome.num_commits)
+    }
+}
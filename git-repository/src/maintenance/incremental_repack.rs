@@ -0,0 +1,229 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use git_odb::FindExt;
+use git_pack::data::output;
+
+use super::{Error, TaskOutcome};
+use crate::Repository;
+
+/// The name of the multi-pack index in the pack directory.
+const MULTI_INDEX_NAME: &str = "multi-pack-index";
+/// The largest batch size used if none is configured, like in `git`.
+const MAX_AUTO_BATCH_SIZE: u64 = 2 * 1024 * 1024 * 1024;
+
+/// A pack in the pack directory.
+struct Pack {
+    /// The path to the pack index.
+    index_path: PathBuf,
+    /// The size of the pack data file in bytes.
+    size: u64,
+    /// The time the pack data file was last modified.
+    modified: SystemTime,
+    /// True if a `.keep` file prevents the pack from being deleted or repacked.
+    keep: bool,
+}
+
+impl Repository {
+    /// Write a multi-pack index for all packs, delete packs none of whose objects are referenced by it and write the objects
+    /// of the oldest packs whose size is smaller than `batch_size` into a new pack, like `git multi-pack-index expire` followed
+    /// by `git multi-pack-index repack` does.
+    ///
+    /// Repacked packs are deleted in the next run as the multi-pack index refers to the new pack for their objects. Note that
+    /// objects are put into the new pack without deltas, compressed with `compression_level`.
+    pub(crate) fn incremental_repack(
+        &self,
+        batch_size: Option<u64>,
+        lock_mode: git_lock::acquire::Fail,
+        compression_level: u32,
+    ) -> Result<TaskOutcome, Error> {
+        let pack_dir = self.objects_dir().join("pack");
+        let packs = packs_in(&pack_dir)?;
+        if packs.is_empty() {
+            return Ok(TaskOutcome::IncrementalRepack {
+                expired_packs: 0,
+                repacked_packs: 0,
+                packed_objects: 0,
+            });
+        }
+
+        let referenced = {
+            let index = write_multi_index(&pack_dir, &packs, lock_mode)?;
+            let mut referenced = vec![0u32; packs.len()];
+            for object_index in 0..index.num_objects() {
+                referenced[index.pack_id_and_pack_offset_at_index(object_index).0 as usize] += 1;
+            }
+            referenced
+        };
+        let mut expired_packs = 0;
+        let mut packs_and_referenced = Vec::new();
+        for (pack, referenced) in packs.into_iter().zip(referenced) {
+            if referenced == 0 && !pack.keep {
+                delete_pack(&pack.index_path)?;
+                expired_packs += 1;
+            } else {
+                packs_and_referenced.push((pack, referenced));
+            }
+        }
+        let packs: Vec<_> = packs_and_referenced.iter().map(|(pack, _)| pack).collect();
+        if packs.is_empty() {
+            std::fs::remove_file(pack_dir.join(MULTI_INDEX_NAME))?;
+            return Ok(TaskOutcome::IncrementalRepack {
+                expired_packs,
+                repacked_packs: 0,
+                packed_objects: 0,
+            });
+        } else if expired_packs != 0 {
+            write_multi_index(&pack_dir, packs.iter().copied(), lock_mode)?;
+        }
+
+        let batch_size = batch_size.unwrap_or_else(|| auto_batch_size(&packs));
+        let mut candidates: Vec<_> = packs_and_referenced
+            .iter()
+            .enumerate()
+            .filter(|(_, (pack, _))| !pack.keep)
+            .collect();
+        candidates.sort_by_key(|(_, (pack, _))| pack.modified);
+        let mut selected = Vec::new();
+        let mut total_size = 0;
+        for (pack_id, (pack, referenced)) in candidates {
+            if total_size >= batch_size {
+                break;
+            }
+            // Objects which are looked up in other packs won't be repacked, so they don't count.
+            let num_objects = git_pack::index::File::at(&pack.index_path)?.num_objects();
+            let expected_size = if num_objects == 0 {
+                0
+            } else {
+                (u128::from(pack.size) * u128::from(*referenced) / u128::from(num_objects)) as u64
+            };
+            if expected_size >= batch_size {
+                continue;
+            }
+            total_size += expected_size;
+            selected.push(pack_id as u32);
+        }
+        if total_size < batch_size || selected.len() < 2 {
+            return Ok(TaskOutcome::IncrementalRepack {
+                expired_packs,
+                repacked_packs: 0,
+                packed_objects: 0,
+            });
+        }
+
+        let ids: Vec<_> = {
+            let index = git_pack::multi_index::File::at(pack_dir.join(MULTI_INDEX_NAME))?;
+            (0..index.num_objects())
+                .filter(|object_index| selected.contains(&index.pack_id_and_pack_offset_at_index(*object_index).0))
+                .map(|object_index| index.oid_at_index(object_index).to_owned())
+                .collect()
+        };
+        // Packs written by previous tasks aren't known to our object database yet.
+        let odb = git_odb::linked::Store::at(self.objects_dir())?;
+        let mut buf = Vec::new();
+        let entries = ids
+            .into_iter()
+            .map(|id| -> Result<_, Error> {
+                let obj = odb.find(id, &mut buf, &mut git_pack::cache::Never)?;
+                let count = output::Count::from_data(id, &obj);
+                Ok(output::Entry::from_data_with_compression_level(
+                    &count,
+                    &obj,
+                    compression_level,
+                )?)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let packed_objects = entries.len();
+        self.write_pack(entries)?;
+        write_multi_index(&pack_dir, packs_in(&pack_dir)?.iter(), lock_mode)?;
+
+        Ok(TaskOutcome::IncrementalRepack {
+            expired_packs,
+            repacked_packs: selected.len(),
+            packed_objects,
+        })
+    }
+}
+
+/// Return all packs in `pack_dir` that have an index, sorted by the file name of their index like in a multi-pack index.
+fn packs_in(pack_dir: &Path) -> Result<Vec<Pack>, Error> {
+    let mut packs = Vec::new();
+    if !pack_dir.is_dir() {
+        return Ok(packs);
+    }
+    for entry in std::fs::read_dir(pack_dir)? {
+        let index_path = entry?.path();
+        if index_path.extension().map_or(true, |ext| ext != "idx") {
+            continue;
+        }
+        let metadata = match std::fs::metadata(index_path.with_extension("pack")) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+        packs.push(Pack {
+            keep: index_path.with_extension("keep").is_file(),
+            size: metadata.len(),
+            modified: metadata.modified().unwrap_or(UNIX_EPOCH),
+            index_path,
+        });
+    }
+    packs.sort_by(|a, b| a.index_path.file_name().cmp(&b.index_path.file_name()));
+    Ok(packs)
+}
+
+/// Write the multi-pack index for `packs` into `pack_dir` and return it, removing reverse indices and bitmaps of previous ones.
+fn write_multi_index<'a>(
+    pack_dir: &Path,
+    packs: impl IntoIterator<Item = &'a Pack>,
+    lock_mode: git_lock::acquire::Fail,
+) -> Result<git_pack::multi_index::File, Error> {
+    let path = pack_dir.join(MULTI_INDEX_NAME);
+    let mut file = git_lock::File::acquire_to_update_resource(&path, lock_mode, None)?;
+    git_pack::multi_index::File::write_from_index_paths(
+        packs.into_iter().map(|pack| pack.index_path.clone()).collect(),
+        &mut file,
+    )?;
+    file.commit()?;
+    for entry in std::fs::read_dir(pack_dir)? {
+        let entry = entry?;
+        if entry
+            .file_name()
+            .to_str()
+            .map_or(false, |name| name.starts_with("multi-pack-index-"))
+        {
+            std::fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(git_pack::multi_index::File::at(path)?)
+}
+
+/// Delete the pack with the index at `index_path` along with all files belonging to it, starting with the index to make it
+/// invisible to readers.
+fn delete_pack(index_path: &Path) -> Result<(), Error> {
+    for extension in &["idx", "pack", "rev", "bitmap"] {
+        match std::fs::remove_file(index_path.with_extension(extension)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err.into()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Return one more than the size of the second largest of `packs`, but at most 2GB, which repacks at least two packs if
+/// there are three or more.
+fn auto_batch_size(packs: &[&Pack]) -> u64 {
+    let (mut largest, mut second_largest) = (0, 0);
+    for pack in packs {
+        if pack.size > largest {
+            second_largest = largest;
+            largest = pack.size;
+        } else if pack.size > second_largest {
+            second_largest = pack.size;
+        }
+    }
+    (second_largest + 1).min(MAX_AUTO_BATCH_SIZE)
+}
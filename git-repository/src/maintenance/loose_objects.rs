@@ -0,0 +1,44 @@
+use git_pack::data::output;
+
+use super::{Error, TaskOutcome};
+use crate::Repository;
+
+impl Repository {
+    pub(crate) fn pack_loose_objects(&self, batch_size: usize, compression_level: u32) -> Result<TaskOutcome, Error> {
+        let db = &self.odb.dbs[0];
+        let mut deleted = 0;
+        let mut to_pack = Vec::new();
+        for id in db.loose.iter() {
+            let id = id?;
+            if db.bundles.iter().any(|b| b.index.lookup(id).is_some()) {
+                let mut buf = [0u8; git_hash::Kind::longest().len_in_hex()];
+                let hex = id.hex_to_buf(&mut buf);
+                std::fs::remove_file(db.loose.path.join(&hex[..2]).join(&hex[2..]))?;
+                deleted += 1;
+            } else if to_pack.len() < batch_size {
+                to_pack.push(id);
+            }
+        }
+
+        let packed = to_pack.len();
+        if packed != 0 {
+            let mut buf = Vec::new();
+            let entries = to_pack
+                .into_iter()
+                .filter_map(|id| -> Option<Result<_, Error>> {
+                    let obj = match db.loose.try_find(id, &mut buf) {
+                        Ok(obj) => obj?,
+                        Err(err) => return Some(Err(err.into())),
+                    };
+                    let count = output::Count::from_data(id, &obj);
+                    Some(
+                        output::Entry::from_data_with_compression_level(&count, &obj, compression_level)
+                            .map_err(Into::into),
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            self.write_pack(entries)?;
+        }
+        Ok(TaskOutcome::LooseObjects { deleted, packed })
+    }
+}
@@ -0,0 +1,369 @@
+//! Run housekeeping tasks similar to `git maintenance run --task=…`, suitable for calling periodically from servers.
+//!
+//! Only one maintenance run can be active per repository as it holds the `objects/maintenance.lock` while running.
+use std::{convert::Infallible, io::Write, str::FromStr, sync::atomic::AtomicBool};
+
+use git_config::{
+    file::GitConfig,
+    values::{Boolean, Integer},
+};
+use git_hash::ObjectId;
+use git_odb::{Find, FindExt};
+use git_pack::data::output;
+
+use crate::Repository;
+
+mod commit_graph;
+mod incremental_repack;
+mod loose_objects;
+#[cfg(feature = "blocking-network-client")]
+mod prefetch;
+
+/// A task to run as part of [`Repository::maintenance()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Task {
+    /// Write the commit-graph file for all commits reachable from references.
+    CommitGraph,
+    /// Fetch from all remotes into `refs/prefetch/` without updating any other reference.
+    #[cfg(feature = "blocking-network-client")]
+    Prefetch,
+    /// Delete loose objects that are already contained in packs and put the remaining ones into a new pack.
+    LooseObjects,
+    /// Write a multi-pack index, delete packs whose objects are all contained in newer packs and repack small packs into a
+    /// bigger one.
+    IncrementalRepack,
+}
+
+impl Task {
+    /// All tasks in the order they are run in.
+    pub const ALL: &'static [Task] = &[
+        #[cfg(feature = "blocking-network-client")]
+        Task::Prefetch,
+        Task::LooseObjects,
+        Task::IncrementalRepack,
+        Task::CommitGraph,
+    ];
+
+    /// The name of the task as used by `git` and in the `maintenance.<task>.enabled` configuration.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Task::CommitGraph => "commit-graph",
+            #[cfg(feature = "blocking-network-client")]
+            Task::Prefetch => "prefetch",
+            Task::LooseObjects => "loose-objects",
+            Task::IncrementalRepack => "incremental-repack",
+        }
+    }
+}
+
+impl FromStr for Task {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Task::ALL
+            .iter()
+            .find(|t| t.as_str() == s)
+            .copied()
+            .ok_or_else(|| format!("Unknown maintenance task '{}'", s))
+    }
+}
+
+/// Configure which tasks [`Repository::maintenance()`] runs, and how.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// The tasks to run. They are always run in the order given by [`Task::ALL`], and each task is run only once.
+    pub tasks: Vec<Task>,
+    /// The maximum amount of loose objects to put into a pack in one run of [`Task::LooseObjects`].
+    pub loose_objects_batch_size: usize,
+    /// The size in bytes the packs repacked in one run of [`Task::IncrementalRepack`] should at least have in total, or `None`
+    /// to use one more than the size of the second largest pack, but at most 2GB, like `git` does.
+    pub incremental_repack_batch_size: Option<u64>,
+    /// What to do if another maintenance run or one of the involved resources holds a lock.
+    pub lock_mode: git_lock::acquire::Fail,
+    /// The zlib compression level from 0 to 9 for objects put into packs.
+    pub pack_compression_level: u32,
+}
+
+impl Default for Options {
+    /// Run all tasks which don't need the network, similar to the tasks scheduled by `git maintenance start`.
+    fn default() -> Self {
+        Options {
+            tasks: vec![Task::LooseObjects, Task::IncrementalRepack, Task::CommitGraph],
+            loose_objects_batch_size: 50_000,
+            incremental_repack_batch_size: None,
+            lock_mode: git_lock::acquire::Fail::Immediately,
+            pack_compression_level: 1,
+        }
+    }
+}
+
+impl Options {
+    /// Obtain options from `config`, enabling tasks with `maintenance.<task>.enabled` and configuring the batch size with
+    /// `maintenance.loose-objects.batchSize`. If no task is enabled explicitly, the [default tasks][Options::default()] will be run.
+    ///
+    /// The compression level is configured with `pack.compression` or `core.compression`, and invalid levels are ignored.
+    pub fn from_config(config: &GitConfig<'_>) -> Self {
+        let mut opts = Options::default();
+        let enabled: Vec<_> = Task::ALL
+            .iter()
+            .filter(|task| {
+                config
+                    .value::<Boolean<'_>>("maintenance", Some(task.as_str()), "enabled")
+                    .map_or(false, |b| matches!(b, Boolean::True(_)))
+            })
+            .copied()
+            .collect();
+        if !enabled.is_empty() {
+            opts.tasks = enabled;
+        }
+        if let Ok(batch_size) = config.value::<Integer>("maintenance", Some("loose-objects"), "batchSize") {
+            if batch_size.value > 0 {
+                opts.loose_objects_batch_size = batch_size.value as usize;
+            }
+        }
+        if let Ok(Some(level)) = crate::config::compression_level(config, ("pack", "compression")) {
+            opts.pack_compression_level = level;
+        }
+        opts
+    }
+}
+
+/// The outcome of a single [`Task`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskOutcome {
+    /// The outcome of [`Task::CommitGraph`].
+    CommitGraph {
+        /// The amount of commits in the written commit-graph file.
+        num_commits: u32,
+    },
+    /// The outcome of [`Task::Prefetch`].
+    #[cfg(feature = "blocking-network-client")]
+    Prefetch {
+        /// The amount of references in `refs/prefetch/` which were created or updated.
+        updated_refs: usize,
+    },
+    /// The outcome of [`Task::LooseObjects`].
+    LooseObjects {
+        /// The amount of loose objects that were deleted as they are contained in a pack already.
+        deleted: usize,
+        /// The amount of loose objects written into a new pack. They will be deleted in the next run.
+        packed: usize,
+    },
+    /// The outcome of [`Task::IncrementalRepack`].
+    IncrementalRepack {
+        /// The amount of packs that were deleted as the multi-pack index didn't refer to any of their objects.
+        expired_packs: usize,
+        /// The amount of packs whose objects were written into a new pack. They will be deleted in the next run.
+        repacked_packs: usize,
+        /// The amount of objects in the new pack.
+        packed_objects: usize,
+    },
+}
+
+/// The outcome of [`Repository::maintenance()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// The tasks that were run, in order, along with their outcome.
+    pub tasks: Vec<(Task, TaskOutcome)>,
+}
+
+/// The error returned by [`Repository::maintenance()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Maintenance is already running or the lock could not be obtained")]
+    Lock(#[from] git_lock::acquire::Error),
+    #[error("Could not move the multi-pack index into place")]
+    CommitLock(#[from] git_lock::commit::Error<git_lock::File>),
+    #[error(transparent)]
+    OpenObjectDatabase(#[from] git_odb::linked::init::Error),
+    #[error(transparent)]
+    PackedRefsOpen(#[from] git_ref::packed::buffer::open::Error),
+    #[error(transparent)]
+    IterateReferences(#[from] git_ref::file::iter::loose_then_packed::Error),
+    #[error(transparent)]
+    FindReference(#[from] git_ref::file::find::Error),
+    #[error(transparent)]
+    PeelReference(#[from] git_ref::peel::to_id::Error),
+    #[error(transparent)]
+    FindExistingObject(#[from] git_pack::find::existing::Error<git_odb::compound::find::Error>),
+    #[error(transparent)]
+    IterateLooseObjects(#[from] git_odb::loose::iter::Error),
+    #[error(transparent)]
+    FindLooseObject(#[from] git_odb::loose::find::Error),
+    #[error(transparent)]
+    CreatePackEntry(#[from] output::entry::Error),
+    #[error(transparent)]
+    WritePackData(#[from] output::bytes::Error<Infallible>),
+    #[error(transparent)]
+    WritePack(#[from] git_pack::bundle::write::Error),
+    #[error(transparent)]
+    OpenPackIndex(#[from] git_pack::index::init::Error),
+    #[error(transparent)]
+    WriteMultiIndex(#[from] git_pack::multi_index::write::Error),
+    #[error(transparent)]
+    OpenMultiIndex(#[from] git_pack::multi_index::init::Error),
+    #[error(transparent)]
+    WriteCommitGraph(
+        #[from] git_commitgraph::write::Error<git_pack::find::existing_object::Error<git_odb::compound::find::Error>>,
+    ),
+    #[cfg(feature = "blocking-network-client")]
+    #[error(transparent)]
+    Config(#[from] crate::config::Error),
+    #[cfg(feature = "blocking-network-client")]
+    #[error("The fetch refspec '{spec}' of remote '{remote}' is invalid")]
+    Refspec {
+        remote: String,
+        spec: git_object::bstr::BString,
+        source: git_protocol::refspec::Error,
+    },
+    #[cfg(feature = "blocking-network-client")]
+    #[error(transparent)]
+    Connect(#[from] git_protocol::transport::client::connect::Error),
+    #[cfg(feature = "blocking-network-client")]
+    #[error(transparent)]
+    Fetch(#[from] git_protocol::fetch::Error),
+    #[cfg(feature = "blocking-network-client")]
+    #[error(transparent)]
+    ReferenceName(#[from] git_validate::refname::Error),
+    #[cfg(feature = "blocking-network-client")]
+    #[error(transparent)]
+    EditReferences(#[from] crate::easy::reference::edit::Error),
+    #[error("Could not read or delete files in the object database")]
+    Io(#[from] std::io::Error),
+}
+
+impl Repository {
+    /// Run the maintenance tasks configured in `options` one after another.
+    ///
+    /// Fails if maintenance is already running on this repository.
+    /// Note that the object database of this instance isn't refreshed, so newly written packs might not be visible.
+    pub fn maintenance(&self, options: &Options) -> Result<Outcome, Error> {
+        let _lock = git_lock::Marker::acquire_to_hold_resource(
+            self.objects_dir().join("maintenance"),
+            options.lock_mode,
+            None,
+        )?;
+
+        let mut out = Outcome { tasks: Vec::new() };
+        for task in Task::ALL.iter().filter(|t| options.tasks.contains(t)) {
+            let outcome = match task {
+                #[cfg(feature = "blocking-network-client")]
+                Task::Prefetch => TaskOutcome::Prefetch {
+                    updated_refs: self.prefetch(options.lock_mode)?,
+                },
+                Task::LooseObjects => {
+                    self.pack_loose_objects(options.loose_objects_batch_size, options.pack_compression_level)?
+                }
+                Task::IncrementalRepack => self.incremental_repack(
+                    options.incremental_repack_batch_size,
+                    options.lock_mode,
+                    options.pack_compression_level,
+                )?,
+                Task::CommitGraph => TaskOutcome::CommitGraph {
+                    num_commits: self.write_commit_graph()?,
+                },
+            };
+            out.tasks.push((*task, outcome));
+        }
+        Ok(out)
+    }
+
+    /// Return the commits pointed to by `HEAD` and all references after peeling them.
+    fn reference_tips(&self) -> Result<Vec<ObjectId>, Error> {
+        let packed = self.refs.packed_buffer()?;
+        let mut buf = Vec::new();
+        let mut tips = Vec::new();
+        let head = self.refs.try_find("HEAD", packed.as_ref())?;
+        for reference in head.into_iter().map(Ok).chain(self.refs.iter(packed.as_ref())?) {
+            let mut reference = reference?;
+            if reference.target.as_id().is_none() {
+                continue;
+            }
+            let id = self
+                .refs
+                .peel_to_id_in_place(&mut reference, packed.as_ref(), |oid, buf| {
+                    self.odb
+                        .try_find(oid, buf, &mut git_pack::cache::Never)
+                        .map(|obj| obj.map(|obj| (obj.kind, obj.data)))
+                })?;
+            if self.odb.find(id, &mut buf, &mut git_pack::cache::Never)?.kind == git_object::Kind::Commit {
+                tips.push(id);
+            }
+        }
+        tips.sort();
+        tips.dedup();
+        Ok(tips)
+    }
+
+    /// Write `entries` into a new pack in `objects/pack` and return the path to its index.
+    fn write_pack(&self, entries: Vec<output::Entry>) -> Result<std::path::PathBuf, Error> {
+        let num_entries = entries.len();
+        let mut pack = Vec::new();
+        let mut writer = output::bytes::FromEntriesIter::new(
+            std::iter::once(Ok::<_, Infallible>(entries)),
+            &mut pack,
+            num_entries as u32,
+            git_pack::data::Version::V2,
+            self.hash_kind,
+        );
+        for res in writer.by_ref() {
+            res?;
+        }
+        writer.into_write().flush()?;
+        Ok(git_pack::Bundle::write_to_directory(
+            pack.as_slice(),
+            Some(self.objects_dir().join("pack")),
+            git_features::progress::Discard,
+            &AtomicBool::new(false),
+            None,
+            Default::default(),
+        )?
+        .index_path
+        .expect("a directory was given"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::*;
+
+    #[test]
+    fn options_from_config() {
+        let config = GitConfig::try_from("[maintenance]\n\tauto = false\n").unwrap();
+        assert_eq!(Options::from_config(&config), Options::default());
+
+        let config = GitConfig::try_from(
+            "[maintenance \"incremental-repack\"]\n\tenabled = false\n[maintenance \"commit-graph\"]\n\tenabled = true\n[maintenance \"loose-objects\"]\n\tenabled = true\n\tbatchSize = 10\n[core]\n\tcompression = -1\n",
+        )
+        .unwrap();
+        assert_eq!(
+            Options::from_config(&config),
+            Options {
+                tasks: vec![Task::LooseObjects, Task::CommitGraph],
+                loose_objects_batch_size: 10,
+                pack_compression_level: 6,
+                ..Default::default()
+            }
+        );
+
+        let config = GitConfig::try_from("[core]\n\tcompression = 3\n[pack]\n\tcompression = 9\n").unwrap();
+        assert_eq!(Options::from_config(&config).pack_compression_level, 9);
+        let config = GitConfig::try_from("[pack]\n\tcompression = 10\n").unwrap();
+        assert_eq!(
+            Options::from_config(&config).pack_compression_level,
+            1,
+            "invalid levels are ignored"
+        );
+    }
+
+    #[test]
+    fn task_names_round_trip() {
+        for task in Task::ALL {
+            assert_eq!(task.as_str().parse::<Task>().unwrap(), *task);
+        }
+        assert!("unknown".parse::<Task>().is_err());
+    }
+}
@@ -0,0 +1,228 @@
+use std::{
+    convert::TryInto,
+    io,
+    path::PathBuf,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use git_features::progress::Progress;
+use git_hash::ObjectId;
+use git_object::bstr::{BString, ByteSlice};
+use git_odb::Find;
+use git_protocol::{
+    fetch::{Action, Arguments, LsRefsAction, Ref, Response},
+    refspec::{self, Mode, RefSpec},
+    transport,
+};
+use git_ref::{
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+    FullName, Target,
+};
+
+use super::Error;
+use crate::{prelude::ReferenceAccessExt, Repository};
+
+impl Repository {
+    /// Fetch from all remotes with a url into `refs/prefetch/` using their fetch refspecs, without tags, and return the amount
+    /// of references which changed, like `git fetch --prefetch` does for each remote.
+    ///
+    /// Note that prefetched references aren't deleted if they were removed on the remote.
+    pub(crate) fn prefetch(&self, lock_mode: git_lock::acquire::Fail) -> Result<usize, Error> {
+        let config = crate::config::open(self)?;
+        let mut remotes: Vec<String> = Vec::new();
+        for (header, _) in config.sections_by_name_with_header("remote") {
+            if let Some(name) = header.subsection_name.as_deref() {
+                if !remotes.iter().any(|remote| remote == name) {
+                    remotes.push(name.to_owned());
+                }
+            }
+        }
+
+        let haves = self.reference_tips()?;
+        let odb = Arc::new(git_odb::linked::Store::at(self.objects_dir())?);
+        let packed = self.refs.packed_buffer()?;
+        let mut edits = Vec::new();
+        for remote in &remotes {
+            let url = match config.get_raw_value("remote", Some(remote), "url") {
+                Ok(url) => url.into_owned(),
+                Err(_) => continue,
+            };
+            let mut specs = Vec::new();
+            for spec in config
+                .get_raw_multi_value("remote", Some(remote), "fetch")
+                .unwrap_or_default()
+            {
+                let spec = RefSpec::from_bytes(spec.as_bstr()).map_err(|source| Error::Refspec {
+                    remote: remote.clone(),
+                    spec: spec.as_ref().into(),
+                    source,
+                })?;
+                specs.extend(prefetch_refspec(spec));
+            }
+            if specs.is_empty() {
+                continue;
+            }
+
+            let mut delegate = Delegate {
+                specs,
+                haves: &haves,
+                odb: Arc::clone(&odb),
+                pack_directory: self.objects_dir().join("pack"),
+                updates: Vec::new(),
+            };
+            let mut transport = transport::connect(&url, transport::Protocol::V2)?;
+            git_protocol::fetch(
+                &mut transport,
+                &mut delegate,
+                git_protocol::credentials::helper,
+                git_features::progress::Discard,
+                git_protocol::FetchConnection::TerminateOnSuccessfulCompletion,
+            )?;
+            for (name, id) in delegate.updates {
+                let name: FullName = name.try_into()?;
+                let previous = self.refs.try_find(name.to_partial(), packed.as_ref())?;
+                if previous.map_or(false, |r| r.target.as_id() == Some(id.as_ref())) {
+                    continue;
+                }
+                edits.push(RefEdit {
+                    change: Change::Update {
+                        log: LogChange {
+                            mode: RefLog::AndReference,
+                            force_create_reflog: false,
+                            message: format!("maintenance: prefetch {}", remote).into(),
+                        },
+                        expected: PreviousValue::Any,
+                        new: Target::Peeled(id),
+                    },
+                    name,
+                    deref: false,
+                });
+            }
+        }
+
+        let updated_refs = edits.len();
+        if updated_refs != 0 {
+            self.to_easy().edit_references(edits, lock_mode, None)?;
+        }
+        Ok(updated_refs)
+    }
+}
+
+/// Rewrite `spec` to store the references it matches in `refs/prefetch/` and to allow forced updates, or return `None` if it
+/// doesn't store anything or matches tags, like `git fetch --prefetch` does.
+fn prefetch_refspec(spec: RefSpec) -> Option<RefSpec> {
+    let destination = spec.destination?;
+    if spec.source.starts_with(b"refs/tags/") {
+        return None;
+    }
+    let mut prefetch_destination = BString::from("refs/prefetch/");
+    prefetch_destination.extend_from_slice(destination.strip_prefix(b"refs/".as_ref()).unwrap_or(&destination));
+    Some(RefSpec {
+        mode: Mode::Force,
+        source: spec.source,
+        destination: Some(prefetch_destination),
+    })
+}
+
+struct Delegate<'a> {
+    specs: Vec<RefSpec>,
+    /// The tips of all local references, which are what we tell the remote we have.
+    haves: &'a [ObjectId],
+    odb: Arc<git_odb::linked::Store>,
+    pack_directory: PathBuf,
+    /// The local names of all remote references matched by our refspecs, along with the objects they point to.
+    updates: Vec<(BString, ObjectId)>,
+}
+
+impl Delegate<'_> {
+    fn wants(&self) -> Vec<ObjectId> {
+        let mut wants: Vec<_> = self
+            .updates
+            .iter()
+            .map(|(_, id)| *id)
+            .filter(|id| !self.odb.contains(id))
+            .collect();
+        wants.sort();
+        wants.dedup();
+        wants
+    }
+}
+
+impl git_protocol::fetch::DelegateBlocking for Delegate<'_> {
+    fn prepare_ls_refs(
+        &mut self,
+        server: &transport::client::Capabilities,
+        arguments: &mut Vec<BString>,
+        _features: &mut Vec<(&str, Option<&str>)>,
+    ) -> io::Result<LsRefsAction> {
+        if server.contains("ls-refs") {
+            arguments.extend(
+                self.specs
+                    .iter()
+                    .flat_map(|spec| spec.prefixes())
+                    .map(|prefix| format!("ref-prefix {}", prefix).into()),
+            );
+        }
+        Ok(LsRefsAction::Continue)
+    }
+
+    fn prepare_fetch(
+        &mut self,
+        _version: transport::Protocol,
+        _server: &transport::client::Capabilities,
+        _features: &mut Vec<(&str, Option<&str>)>,
+        refs: &[Ref],
+    ) -> io::Result<Action> {
+        self.updates = refspec::map(&self.specs, refs.iter().map(|r| r.unpack().0.as_bstr()))
+            .into_iter()
+            .filter_map(|mapping| {
+                let id = refs.iter().find(|r| *r.unpack().0 == mapping.remote)?.unpack().1?;
+                Some((mapping.local?, *id))
+            })
+            .collect();
+        Ok(if self.wants().is_empty() {
+            Action::Cancel
+        } else {
+            Action::Continue
+        })
+    }
+
+    fn negotiate(
+        &mut self,
+        _refs: &[Ref],
+        arguments: &mut Arguments,
+        _previous_response: Option<&Response>,
+    ) -> io::Result<Action> {
+        for id in self.wants() {
+            arguments.want(id);
+        }
+        for id in self.haves {
+            arguments.have(id);
+        }
+        Ok(Action::Cancel)
+    }
+}
+
+impl git_protocol::fetch::Delegate for Delegate<'_> {
+    fn receive_pack(
+        &mut self,
+        input: impl io::BufRead,
+        progress: impl Progress,
+        _refs: &[Ref],
+        _previous_response: &Response,
+    ) -> io::Result<()> {
+        let odb = Arc::clone(&self.odb);
+        git_pack::Bundle::write_to_directory(
+            input,
+            Some(&self.pack_directory),
+            progress,
+            &AtomicBool::new(false),
+            Some(Box::new(move |oid, buf| {
+                odb.try_find(oid, buf, &mut git_pack::cache::Never).ok().flatten()
+            })),
+            Default::default(),
+        )
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+//! Read the options `git pack-objects` and `git repack` use to tune the packs they produce, like `pack.depth` or
+//! `repack.writeBitmaps`, so packs created by `gitoxide` can be tuned the same way.
+use std::{borrow::Cow, convert::TryFrom};
+
+use git_config::{
+    file::GitConfig,
+    values::{Boolean, Integer},
+};
+
+use crate::{bstr::BString, Repository};
+
+/// The maximum delta chain length `git` supports, with larger values of `pack.depth` being reduced to it.
+pub const MAX_DEPTH: u32 = 4095;
+
+/// Options for generating packs, as obtained by [`Options::from_config()`].
+///
+/// Note that the pack generator doesn't produce deltas yet, which leaves all delta-related options unused for now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Options {
+    /// The amount of objects to consider as delta base for each object, configured with `pack.window`.
+    pub window: u32,
+    /// The amount of memory in bytes the objects in the delta window of each thread may use, or `None` if it is unlimited,
+    /// configured with `pack.windowMemory`.
+    pub window_memory: Option<u64>,
+    /// The maximum length of delta chains, configured with `pack.depth` and at most [`MAX_DEPTH`].
+    pub depth: u32,
+    /// The amount of threads to use, or `None` to use as many as there are logical cores, configured with `pack.threads`.
+    pub thread_limit: Option<usize>,
+    /// The amount of memory in bytes to use for caching deltas before writing them, or `None` if it is unlimited,
+    /// configured with `pack.deltaCacheSize`.
+    pub delta_cache_size: Option<u64>,
+    /// If true, write a reachability bitmap along with the pack, configured with `repack.writeBitmaps` or its deprecated
+    /// alias `pack.writeBitmaps`.
+    pub write_bitmaps: bool,
+}
+
+impl Default for Options {
+    /// The defaults used by `git` for repositories with a work tree.
+    fn default() -> Self {
+        Options {
+            window: 10,
+            window_memory: None,
+            depth: 50,
+            thread_limit: None,
+            delta_cache_size: Some(256 * 1024 * 1024),
+            write_bitmaps: false,
+        }
+    }
+}
+
+impl Options {
+    /// Obtain the options from `config`, using the [defaults][Options::default()] for all values that aren't set.
+    ///
+    /// As in `git`, `write_bitmaps` defaults to `is_bare`, and sizes may have a `k`, `m` or `g` suffix.
+    pub fn from_config(config: &GitConfig<'_>, is_bare: bool) -> Result<Self, Error> {
+        let default = Options::default();
+        let non_zero = |value: u64| Some(value).filter(|value| *value != 0);
+        Ok(Options {
+            window: unsigned(config, "window")?
+                .map_or(default.window, |window| u32::try_from(window).unwrap_or(u32::MAX)),
+            window_memory: unsigned(config, "windowMemory")?.map_or(default.window_memory, non_zero),
+            depth: unsigned(config, "depth")?.map_or(default.depth, |depth| depth.min(MAX_DEPTH as u64) as u32),
+            thread_limit: unsigned(config, "threads")?.map_or(default.thread_limit, |threads| {
+                non_zero(threads).map(|threads| usize::try_from(threads).unwrap_or(usize::MAX))
+            }),
+            delta_cache_size: unsigned(config, "deltaCacheSize")?.map_or(default.delta_cache_size, non_zero),
+            write_bitmaps: match boolean(config, ("repack", "writeBitmaps"))? {
+                Some(write_bitmaps) => write_bitmaps,
+                None => boolean(config, ("pack", "writeBitmaps"))?.unwrap_or(is_bare),
+            },
+        })
+    }
+}
+
+/// Return the value of `pack.<key>` as non-negative integer with its suffix applied, or `None` if it isn't set.
+fn unsigned(config: &GitConfig<'_>, key: &'static str) -> Result<Option<u64>, Error> {
+    let value = match config.value::<Cow<'_, [u8]>>("pack", None, key) {
+        Ok(value) => value,
+        Err(_) => return Ok(None),
+    };
+    Integer::try_from(value.to_ascii_lowercase().as_slice())
+        .ok()
+        .filter(|integer| integer.value >= 0)
+        .map(|integer| {
+            let shift = integer.suffix.map_or(0, |suffix| suffix.bitwise_offset());
+            (integer.value as u64).checked_shl(shift as u32).unwrap_or(u64::MAX)
+        })
+        .map(Some)
+        .ok_or_else(|| Error::InvalidValue {
+            key: format!("pack.{}", key),
+            value: value.into_owned().into(),
+        })
+}
+
+/// Return the value of `section.key` as boolean, or `None` if it isn't set.
+fn boolean(config: &GitConfig<'_>, (section, key): (&'static str, &'static str)) -> Result<Option<bool>, Error> {
+    let value = match config.value::<Cow<'_, [u8]>>(section, None, key) {
+        Ok(value) => value,
+        Err(_) => return Ok(None),
+    };
+    Boolean::try_from(value.clone().into_owned())
+        .map(|boolean| Some(boolean.into()))
+        .map_err(|_| Error::InvalidValue {
+            key: format!("{}.{}", section, key),
+            value: value.into_owned().into(),
+        })
+}
+
+/// The error returned by [`Options::from_config()`] and [`Repository::pack_generation_options()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The value '{}' of '{}' is invalid", .value, .key)]
+    InvalidValue { key: String, value: BString },
+    #[error(transparent)]
+    Config(#[from] crate::config::Error),
+}
+
+impl Repository {
+    /// Return the options for generating packs as configured in this repository.
+    pub fn pack_generation_options(&self) -> Result<Options, Error> {
+        let config = crate::config::open(self)?;
+        Options::from_config(&config, self.kind().is_bare())
+    }
+}
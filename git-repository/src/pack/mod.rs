@@ -0,0 +1,3 @@
+//! Configuration for creating packs, as read from the `pack.*` and `repack.*` sections of the git configuration.
+///
+pub mod generation;
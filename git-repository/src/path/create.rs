@@ -4,9 +4,9 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use crate::bstr::ByteSlice;
+use crate::bstr::{BString, ByteSlice};
 
-/// The error used in [`into()`].
+/// The error used in [`into()`] and [`into_opts()`].
 #[derive(Debug, thiserror::Error)]
 #[allow(missing_docs)]
 pub enum Error {
@@ -20,6 +20,30 @@ pub enum Error {
     DirectoryNotEmpty { path: PathBuf },
     #[error("Could not create directory at '{}'", .path.display())]
     CreateDirectory { source: std::io::Error, path: PathBuf },
+    #[error("'{name}' is not a valid branch name")]
+    InvalidInitialBranch {
+        source: git_validate::reference::name::Error,
+        name: BString,
+    },
+}
+
+/// Options for [`into_opts()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// If true, write the sample hooks git ships with into the `hooks` directory. They are built in and never read from a
+    /// template directory of a git installation.
+    pub hook_samples: bool,
+    /// The name of the branch `HEAD` points to, like `git init --initial-branch`, or `None` to use the built-in default of `main`.
+    pub initial_branch: Option<BString>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            hook_samples: true,
+            initial_branch: None,
+        }
+    }
 }
 
 const GIT_DIR_NAME: &str = ".git";
@@ -100,22 +124,51 @@ fn create_dir(p: &Path) -> Result<(), Error> {
 
 /// Create a new `.git` repository of `kind` within the possibly non-existing `directory`
 /// and return its path.
+///
+/// The filesystem is probed to set `core.ignoreCase` and `core.precomposeUnicode` as needed.
 pub fn into(directory: impl Into<PathBuf>, kind: crate::Kind) -> Result<crate::Path, Error> {
+    into_opts(directory, kind, Options::default())
+}
+
+/// Like [`into()`], but configurable with `options`.
+///
+/// All files are built in, so no installation of git is needed.
+pub fn into_opts(
+    directory: impl Into<PathBuf>,
+    kind: crate::Kind,
+    Options {
+        hook_samples,
+        initial_branch,
+    }: Options,
+) -> Result<crate::Path, Error> {
+    let head = match initial_branch {
+        Some(name) => {
+            let mut head = BString::from("ref: refs/heads/");
+            head.extend_from_slice(&name);
+            git_validate::reference::name(head[5..].as_bstr())
+                .map_err(|source| Error::InvalidInitialBranch { source, name })?;
+            head.push(b'\n');
+            head
+        }
+        None => TPL_HEAD.into(),
+    };
     let mut dot_git = directory.into();
 
     match kind {
-        crate::Kind::Bare => {
-            if std::fs::read_dir(&dot_git)
-                .map_err(|err| Error::IoOpen {
+        crate::Kind::Bare => match std::fs::read_dir(&dot_git) {
+            Ok(entries) => {
+                if entries.count() != 0 {
+                    return Err(Error::DirectoryNotEmpty { path: dot_git });
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(Error::IoOpen {
                     source: err,
-                    path: dot_git.clone(),
-                })?
-                .count()
-                != 0
-            {
-                return Err(Error::DirectoryNotEmpty { path: dot_git });
+                    path: dot_git,
+                })
             }
-        }
+        },
         crate::Kind::WorkTree => {
             dot_git.push(GIT_DIR_NAME);
 
@@ -131,7 +184,7 @@ pub fn into(directory: impl Into<PathBuf>, kind: crate::Kind) -> Result<crate::P
         write_file(TPL_INFO_EXCLUDE, PathCursor(cursor.as_mut()).at("exclude"))?;
     }
 
-    {
+    if hook_samples {
         let mut cursor = NewDir(&mut dot_git).at("hooks")?;
         for (tpl, filename) in &[
             (TPL_HOOKS_UPDATE, "update.sample"),
@@ -149,6 +202,8 @@ pub fn into(directory: impl Into<PathBuf>, kind: crate::Kind) -> Result<crate::P
         ] {
             write_file(tpl, PathCursor(cursor.as_mut()).at(filename))?;
         }
+    } else {
+        create_dir(PathCursor(&mut dot_git).at("hooks"))?;
     }
 
     {
@@ -164,21 +219,26 @@ pub fn into(directory: impl Into<PathBuf>, kind: crate::Kind) -> Result<crate::P
     }
 
     for (tpl, filename) in &[
-        (TPL_HEAD, "HEAD"),
+        (head.as_slice(), "HEAD"),
         (TPL_DESCRIPTION, "description"),
         (TPL_CONFIG, "config"),
     ] {
         if *filename == "config" {
-            write_file(
-                &tpl.replace(
-                    "{bare-value}",
-                    match kind {
-                        crate::Kind::Bare => "true",
-                        crate::Kind::WorkTree => "false",
-                    },
-                ),
-                PathCursor(&mut dot_git).at(filename),
-            )?;
+            let mut config = tpl.replace(
+                "{bare-value}",
+                match kind {
+                    crate::Kind::Bare => "true",
+                    crate::Kind::WorkTree => "false",
+                },
+            );
+            let caps = crate::fs::Capabilities::probe(&dot_git);
+            if caps.ignore_case {
+                config.extend_from_slice(b"\tignorecase = true\n");
+            }
+            if caps.precompose_unicode {
+                config.extend_from_slice(b"\tprecomposeunicode = true\n");
+            }
+            write_file(&config, PathCursor(&mut dot_git).at(filename))?;
         } else {
             write_file(tpl, PathCursor(&mut dot_git).at(filename))?;
         }
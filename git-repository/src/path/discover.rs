@@ -18,12 +18,20 @@ pub mod existing {
         InaccessibleDirectory { path: PathBuf },
         #[error("Could find a git repository in '{}' or in any of its parents", .path.display())]
         NoGitRepository { path: PathBuf },
+        #[error("The '.git' file or symbolic link at '{}' doesn't lead to a valid git directory", .path.display())]
+        InvalidDotGit {
+            path: PathBuf,
+            source: crate::path::is::Error,
+        },
     }
 }
 
 /// Find the location of the git repository directly in `directory` or in any of its parent directories.
 ///
-/// Fail if no valid-looking git repository could be found.
+/// `.git` entries may be symbolic links or gitfiles, which are followed to the git directory.
+///
+/// Fail if no valid-looking git repository could be found. Unlike invalid `.git` directories, which are skipped, `.git` files
+/// and symbolic links that don't lead to a valid git directory are an error.
 pub fn existing(directory: impl AsRef<Path>) -> Result<crate::Path, existing::Error> {
     // Canonicalize the path so that `Path::parent` _actually_ gives
     // us the parent directory. (`Path::parent` just strips off the last
@@ -44,8 +52,12 @@ pub fn existing(directory: impl AsRef<Path>) -> Result<crate::Path, existing::Er
             break Ok(crate::Path::from_dot_git_dir(cursor, kind));
         }
         let git_dir = cursor.join(".git");
-        if let Ok(kind) = path::is::git(&git_dir) {
-            break Ok(crate::Path::from_dot_git_dir(git_dir, kind));
+        match path::is::git(&git_dir) {
+            Ok(kind) => break Ok(crate::Path::from_dot_git_dir(git_dir, kind)),
+            Err(source) if is_indirection(&git_dir) => {
+                break Err(existing::Error::InvalidDotGit { path: git_dir, source })
+            }
+            Err(_) => {}
         }
         match cursor.parent() {
             Some(parent) => cursor = parent,
@@ -58,6 +70,11 @@ pub fn existing(directory: impl AsRef<Path>) -> Result<crate::Path, existing::Er
     }
 }
 
+/// Return true if `path` exists, but isn't a directory.
+fn is_indirection(path: &Path) -> bool {
+    path.symlink_metadata().map_or(false, |metadata| !metadata.is_dir())
+}
+
 fn maybe_canonicalize(path: &Path) -> std::io::Result<Cow<'_, Path>> {
     let ends_with_relative_component = path
         .components()
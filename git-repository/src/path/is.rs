@@ -13,6 +13,8 @@ pub enum Error {
     MissingObjectsDirectory { missing: PathBuf },
     #[error("Expected a refs directory at '{}'", .missing.display())]
     MissingRefsDirectory { missing: PathBuf },
+    #[error(transparent)]
+    Resolve(#[from] crate::path::resolve::Error),
 }
 
 /// Returns true if the given `git_dir` seems to be a bare repository.
@@ -31,9 +33,15 @@ pub fn bare(git_dir: impl AsRef<Path>) -> bool {
 /// * [x] an objects directory
 ///   * [x] respect GIT_OBJECT_DIRECTORY
 /// * [x] a refs directory
+/// * [x] symbolic links and gitfiles leading to the git directory, which always indicate a work tree
 pub fn git(git_dir: impl AsRef<Path>) -> Result<crate::Kind, Error> {
-    let dot_git = git_dir.as_ref();
+    git_inner(git_dir.as_ref(), true)
+}
 
+/// Like [`git()`], but only respects `GIT_OBJECT_DIRECTORY` if `use_env` is true.
+pub(crate) fn git_inner(dot_git: &Path, use_env: bool) -> Result<crate::Kind, Error> {
+    let is_gitfile = dot_git.is_file();
+    let dot_git = &crate::path::resolve::git_dir(dot_git)?;
     {
         let refs = git_ref::file::Store::at(&dot_git, Default::default());
         let head = refs.find_loose("HEAD")?;
@@ -46,8 +54,10 @@ pub fn git(git_dir: impl AsRef<Path>) -> Result<crate::Kind, Error> {
 
     {
         let objects_path = std::env::var("GIT_OBJECT_DIRECTORY")
+            .ok()
+            .filter(|_| use_env)
             .map(PathBuf::from)
-            .unwrap_or_else(|_| dot_git.join("objects"));
+            .unwrap_or_else(|| dot_git.join("objects"));
         if !objects_path.is_dir() {
             return Err(Error::MissingObjectsDirectory { missing: objects_path });
         }
@@ -59,9 +69,29 @@ pub fn git(git_dir: impl AsRef<Path>) -> Result<crate::Kind, Error> {
         }
     }
 
-    Ok(if bare(git_dir) {
+    Ok(if !is_gitfile && bare(dot_git) {
         crate::Kind::Bare
     } else {
         crate::Kind::WorkTree
     })
 }
+
+/// Returns true if `path` is owned by the user running this process.
+///
+/// On platforms other than unix, ownership can't be determined yet and `true` is returned.
+pub fn owned_by_current_user(path: impl AsRef<Path>) -> std::io::Result<bool> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let owner = std::fs::symlink_metadata(path)?.uid();
+        #[allow(unsafe_code)]
+        // SAFETY: geteuid() has no preconditions and can't fail.
+        let current_user = unsafe { libc::geteuid() };
+        Ok(owner == current_user)
+    }
+    #[cfg(not(unix))]
+    {
+        std::fs::symlink_metadata(path)?;
+        Ok(true)
+    }
+}
@@ -6,6 +6,7 @@ use crate::{Kind, Path};
 pub mod create;
 pub mod discover;
 pub mod is;
+pub mod resolve;
 
 impl AsRef<std::path::Path> for Path {
     fn as_ref(&self) -> &std::path::Path {
@@ -0,0 +1,98 @@
+//! Resolve `.git` entries which are symbolic links or gitfiles to the git directory they refer to.
+//!
+//! A gitfile is a plain file containing `gitdir: <path>`, as used by linked work trees and submodules, where `<path>` is
+//! relative to the directory containing the gitfile unless it is absolute.
+use std::path::{Path, PathBuf};
+
+use crate::bstr::ByteSlice;
+
+/// The maximum amount of symbolic links and gitfiles to follow before giving up.
+pub const MAX_DEPTH: usize = 10;
+
+/// The error returned by [`git_dir()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not read '{}'", .path.display())]
+    Io { path: PathBuf, source: std::io::Error },
+    #[error("The file at '{}' is not a gitfile as it doesn't contain 'gitdir: <path>'", .path.display())]
+    InvalidGitFile { path: PathBuf },
+    #[error("Encountered '{}' twice while following symbolic links and gitfiles", .path.display())]
+    Cycle { path: PathBuf },
+    #[error("Gave up at '{}' after following {} symbolic links and gitfiles", .path.display(), .max_depth)]
+    TooDeep { path: PathBuf, max_depth: usize },
+}
+
+/// Follow `path` through symbolic links and gitfiles until a directory is reached and return its path, which is `path`
+/// itself if it is a directory.
+///
+/// Fail if the chain is longer than [`MAX_DEPTH`] or contains a cycle, or if a file isn't a gitfile.
+pub fn git_dir(path: impl AsRef<Path>) -> Result<PathBuf, Error> {
+    let mut path = path.as_ref().to_owned();
+    let mut seen = Vec::new();
+    loop {
+        let metadata = std::fs::symlink_metadata(&path).map_err(|source| Error::Io {
+            path: path.clone(),
+            source,
+        })?;
+        if metadata.is_dir() {
+            return Ok(path);
+        }
+
+        let location = canonical_location(&path)?;
+        if seen.contains(&location) {
+            return Err(Error::Cycle { path });
+        }
+        if seen.len() == MAX_DEPTH {
+            return Err(Error::TooDeep {
+                path,
+                max_depth: MAX_DEPTH,
+            });
+        }
+        seen.push(location);
+
+        let target = if metadata.file_type().is_symlink() {
+            std::fs::read_link(&path).map_err(|source| Error::Io {
+                path: path.clone(),
+                source,
+            })?
+        } else {
+            read_gitfile(&path)?
+        };
+        path = match path.parent() {
+            Some(parent) if target.is_relative() => parent.join(target),
+            _ => target,
+        };
+    }
+}
+
+/// Return the path of the git directory stored in the gitfile at `path`.
+fn read_gitfile(path: &Path) -> Result<PathBuf, Error> {
+    let content = std::fs::read(path).map_err(|source| Error::Io {
+        path: path.to_owned(),
+        source,
+    })?;
+    content
+        .strip_prefix(b"gitdir: ")
+        .map(|target| target.trim_end())
+        .filter(|target| !target.is_empty())
+        .and_then(|target| target.to_path().ok())
+        .map(ToOwned::to_owned)
+        .ok_or_else(|| Error::InvalidGitFile { path: path.to_owned() })
+}
+
+/// Return `path` with its parent directory canonicalized, which identifies the entry at `path` without following it.
+fn canonical_location(path: &Path) -> Result<PathBuf, Error> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let parent = parent.canonicalize().map_err(|source| Error::Io {
+        path: parent.to_owned(),
+        source,
+    })?;
+    Ok(match path.file_name() {
+        Some(name) => parent.join(name),
+        None => parent,
+    })
+}
@@ -0,0 +1,216 @@
+//! Keep incoming objects apart in a temporary object directory until they were checked, similar to what `git receive-pack`
+//! does with pushed objects.
+//!
+//! Objects are written into the [quarantine][Quarantine] which has the object directory of the repository as alternate,
+//! so thin packs can be resolved and [verified][Quarantine::verify()] against objects that exist already.
+//! Hooks like `pre-receive` see all objects if they run with the [environment][Quarantine::env()] of the quarantine.
+//! Only once all checks passed the objects are [migrated][Quarantine::migrate()] into the repository, otherwise
+//! dropping the quarantine deletes them.
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use git_hash::ObjectId;
+use git_object::tree::EntryMode;
+use git_odb::Find;
+
+use crate::Repository;
+
+/// The error returned by [`Repository::quarantine()`] and the methods of [`Quarantine`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not create, read or move files of the quarantine directory")]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Alternate(#[from] git_odb::alternate::edit::Error),
+    #[error(transparent)]
+    OpenOdb(#[from] git_odb::linked::init::Error),
+    #[error(transparent)]
+    FindObject(#[from] git_odb::compound::find::Error),
+    #[error(transparent)]
+    DecodeObject(#[from] git_object::decode::Error),
+    #[error(transparent)]
+    WritePack(#[from] git_pack::bundle::write::Error),
+    #[error("Object {id} is referenced by incoming objects but doesn't exist")]
+    MissingObject { id: ObjectId },
+}
+
+/// A temporary object directory for incoming objects, created with [`Repository::quarantine()`].
+///
+/// Dropping it without [migrating][Quarantine::migrate()] removes it along with all objects in it.
+pub struct Quarantine<'repo> {
+    repo: &'repo Repository,
+    /// The absolute path to the object directory of `repo`.
+    objects_dir: PathBuf,
+    /// The quarantine directory, or `None` once it was migrated.
+    path: Option<PathBuf>,
+}
+
+/// The result of [`Quarantine::migrate()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    /// The amount of packs moved into the repository.
+    pub packs: usize,
+    /// The amount of loose objects moved into the repository, without the ones it had already.
+    pub loose_objects: usize,
+}
+
+impl Repository {
+    /// Create a new [`Quarantine`] to receive objects into, named `tmp_objdir-incoming-*` within the object directory like
+    /// the one created by `git receive-pack`.
+    pub fn quarantine(&self) -> Result<Quarantine<'_>, Error> {
+        let objects_dir = self.objects_dir().canonicalize()?;
+        let mut attempt = 0;
+        let path = loop {
+            let path = objects_dir.join(format!("tmp_objdir-incoming-{}-{}", std::process::id(), attempt));
+            match fs::create_dir(&path) {
+                Ok(()) => break path,
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => attempt += 1,
+                Err(err) => return Err(err.into()),
+            }
+        };
+        let quarantine = Quarantine {
+            repo: self,
+            objects_dir,
+            path: Some(path),
+        };
+        fs::create_dir(quarantine.path().join("pack"))?;
+        git_odb::alternate::add(quarantine.path(), &quarantine.objects_dir)?;
+        Ok(quarantine)
+    }
+}
+
+impl<'repo> Quarantine<'repo> {
+    /// The path to the quarantine directory, which is what `git` provides to hooks as `GIT_QUARANTINE_PATH`.
+    pub fn path(&self) -> &Path {
+        self.path.as_deref().expect("only unset when consumed")
+    }
+
+    /// The environment variables to set for hooks like `pre-receive` so that `git` sees the quarantined objects
+    /// alongside the ones of the repository.
+    pub fn env(&self) -> [(&'static str, &Path); 3] {
+        [
+            ("GIT_QUARANTINE_PATH", self.path()),
+            ("GIT_OBJECT_DIRECTORY", self.path()),
+            ("GIT_ALTERNATE_OBJECT_DIRECTORIES", &self.objects_dir),
+        ]
+    }
+
+    /// Open an object database to write loose objects into the quarantine, which also sees all objects of the repository.
+    pub fn odb(&self) -> Result<git_odb::linked::Store, Error> {
+        Ok(git_odb::linked::Store::at(self.path())?)
+    }
+
+    /// Write the `pack` received from a client into the quarantine, resolving bases of thin packs with objects of the repository.
+    pub fn receive_pack(
+        &self,
+        pack: impl io::BufRead,
+        should_interrupt: &AtomicBool,
+    ) -> Result<git_pack::bundle::write::Outcome, Error> {
+        let odb = Arc::new(git_odb::linked::Store::at(&self.objects_dir)?);
+        Ok(git_pack::Bundle::write_to_directory(
+            pack,
+            Some(self.path().join("pack")),
+            git_features::progress::Discard,
+            should_interrupt,
+            Some(Box::new(move |oid, buf| {
+                odb.try_find(oid, buf, &mut git_pack::cache::Never).ok().flatten()
+            })),
+            Default::default(),
+        )?)
+    }
+
+    /// Assure all objects reachable from `tips`, usually the new targets of references, exist, and return the amount of
+    /// quarantined objects that were traversed.
+    ///
+    /// Traversal stops at objects the repository has already as these are assumed to be complete.
+    /// Submodule commits in trees aren't followed.
+    pub fn verify(&self, tips: impl IntoIterator<Item = ObjectId>) -> Result<usize, Error> {
+        let odb = self.odb()?;
+        let mut seen = HashSet::new();
+        let mut queue: Vec<_> = tips.into_iter().collect();
+        let mut buf = Vec::new();
+        while let Some(id) = queue.pop() {
+            if !seen.insert(id) || self.repo.odb.contains(id) {
+                continue;
+            }
+            let obj = odb
+                .try_find(id, &mut buf, &mut git_pack::cache::Never)?
+                .ok_or(Error::MissingObject { id })?;
+            match obj.decode()? {
+                git_object::ObjectRef::Commit(commit) => {
+                    queue.push(commit.tree());
+                    queue.extend(commit.parents());
+                }
+                git_object::ObjectRef::Tree(tree) => queue.extend(
+                    tree.entries
+                        .iter()
+                        .filter(|entry| entry.mode != EntryMode::Commit)
+                        .map(|entry| entry.oid.to_owned()),
+                ),
+                git_object::ObjectRef::Tag(tag) => queue.push(tag.target()),
+                git_object::ObjectRef::Blob(_) => {}
+            }
+        }
+        Ok(seen.iter().filter(|id| !self.repo.odb.contains(id)).count())
+    }
+
+    /// Move all quarantined objects into the repository and remove the quarantine.
+    ///
+    /// Data files of packs are moved before their index, so other processes never see a pack with missing data.
+    /// Loose objects the repository has already are kept as they are.
+    pub fn migrate(mut self) -> Result<Outcome, Error> {
+        let path = self.path.take().expect("only unset when consumed");
+        let mut out = Outcome::default();
+
+        let mut pack_files: Vec<_> = fs::read_dir(path.join("pack"))?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<_, _>>()?;
+        pack_files.sort_by_key(|path| path.extension().map_or(false, |ext| ext == "idx"));
+        let pack_dir = self.objects_dir.join("pack");
+        fs::create_dir_all(&pack_dir)?;
+        for file in pack_files {
+            if file.extension().map_or(false, |ext| ext == "idx") {
+                out.packs += 1;
+            }
+            fs::rename(&file, pack_dir.join(file.file_name().expect("directory entry")))?;
+        }
+
+        for fanout in fs::read_dir(&path)? {
+            let fanout = fanout?;
+            let name = fanout.file_name();
+            let is_fanout = name.len() == 2
+                && name
+                    .to_str()
+                    .map_or(false, |n| n.bytes().all(|b| b.is_ascii_hexdigit()));
+            if !is_fanout {
+                continue;
+            }
+            let destination = self.objects_dir.join(&name);
+            fs::create_dir_all(&destination)?;
+            for object in fs::read_dir(fanout.path())? {
+                let object = object?;
+                let destination = destination.join(object.file_name());
+                if !destination.exists() {
+                    fs::rename(object.path(), destination)?;
+                    out.loose_objects += 1;
+                }
+            }
+        }
+
+        fs::remove_dir_all(path)?;
+        Ok(out)
+    }
+}
+
+impl Drop for Quarantine<'_> {
+    fn drop(&mut self) {
+        if let Some(path) = self.path.take() {
+            fs::remove_dir_all(path).ok();
+        }
+    }
+}
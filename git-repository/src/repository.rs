@@ -22,18 +22,90 @@ mod from_path {
 
         fn try_from(value: Path) -> Result<Self, Self::Error> {
             let (git_dir, worktree_dir) = value.into_repository_and_work_tree_directories();
-            crate::Repository::open_from_paths(git_dir, worktree_dir)
+            crate::Repository::open_from_paths(git_dir, worktree_dir, Default::default())
         }
     }
 }
 
 ///
 pub mod open {
-    use std::{borrow::Cow, path::PathBuf};
+    use std::{
+        borrow::Cow,
+        path::{Path, PathBuf},
+    };
 
     use git_config::values::{Boolean, Integer};
 
-    use crate::Repository;
+    use crate::{bstr::ByteSlice, Repository};
+
+    /// Control which resources outside of the repository directory may influence how it is opened and used.
+    ///
+    /// Hosting providers and other security-sensitive applications should use [`Permissions::isolated()`] to assure
+    /// neither the environment nor configuration files outside of the repository can alter its behaviour.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct Permissions {
+        /// If true, environment variables like `GIT_OBJECT_DIRECTORY`, `GIT_CONFIG_*`, `HOME` or `GITOXIDE_*` are respected.
+        ///
+        /// Note that without it, the global configuration can't be located.
+        pub env: bool,
+        /// If true, the system-wide configuration file at `/etc/gitconfig` or `GIT_CONFIG_SYSTEM` is read.
+        pub system_config: bool,
+        /// If true, the configuration file of the current user at `~/.gitconfig`, `$XDG_CONFIG_HOME/git/config` or
+        /// `GIT_CONFIG_GLOBAL` is read.
+        pub global_config: bool,
+        /// If true, programs configured as filters or hooks may be executed.
+        pub execute_programs: bool,
+    }
+
+    impl Default for Permissions {
+        fn default() -> Self {
+            Permissions::all()
+        }
+    }
+
+    impl Permissions {
+        /// Allow everything, which is the behaviour of `git` itself.
+        pub fn all() -> Self {
+            Permissions {
+                env: true,
+                system_config: true,
+                global_config: true,
+                execute_programs: true,
+            }
+        }
+
+        /// Only allow the repository itself to influence its behaviour, without being able to execute programs it configures.
+        pub fn isolated() -> Self {
+            Permissions {
+                env: false,
+                system_config: false,
+                global_config: false,
+                execute_programs: false,
+            }
+        }
+    }
+
+    /// Options for use in [`Repository::open_opts()`].
+    #[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
+    pub struct Options {
+        /// Control what may influence the repository aside from its own files.
+        pub permissions: Permissions,
+        /// Directories which may be opened even though they are owned by another user, in addition to the ones configured
+        /// with `safe.directory` in the system-wide, global and environment configuration. Use `*` to allow all directories.
+        ///
+        /// For repositories with a work tree, its directory must be listed, otherwise the `.git` directory.
+        pub safe_directories: Vec<PathBuf>,
+    }
+
+    impl Options {
+        /// Options to open a repository in full isolation from the environment and configuration files outside of it.
+        pub fn isolated() -> Self {
+            Options {
+                permissions: Permissions::isolated(),
+                safe_directories: Vec::new(),
+            }
+        }
+    }
 
     /// The error returned by [`Repository::open()`].
     #[derive(Debug, thiserror::Error)]
@@ -47,28 +119,47 @@ pub mod open {
         ObjectStoreInitialization(#[from] git_odb::linked::init::Error),
         #[error("Cannot handle objects formatted as {:?}", .name)]
         UnsupportedObjectFormat { name: crate::bstr::BString },
+        #[error("Could not read the configuration outside of the repository")]
+        ExternalConfig(#[from] crate::config::Error),
+        #[error(transparent)]
+        CompressionLevel(#[from] crate::config::InvalidCompressionLevel),
+        #[error("Could not determine the owner of '{}'", .path.display())]
+        Ownership { path: PathBuf, source: std::io::Error },
+        #[error("The repository at '{}' is owned by another user and not configured as safe directory", .path.display())]
+        UnsafeDirectory { path: PathBuf },
+        #[error(transparent)]
+        ResolveGitDir(#[from] crate::path::resolve::Error),
     }
 
     impl Repository {
         /// Open a git repository at the given `path`, possibly expanding it to `path/.git` if `path` is a work tree dir.
         pub fn open(path: impl Into<std::path::PathBuf>) -> Result<Self, Error> {
+            Repository::open_opts(path, Options::default())
+        }
+
+        /// Like [`open()`][Repository::open()], but with `options` to control how the repository is opened and used.
+        pub fn open_opts(path: impl Into<std::path::PathBuf>, options: Options) -> Result<Self, Error> {
             let path = path.into();
-            let (path, kind) = match crate::path::is::git(&path) {
+            let use_env = options.permissions.env;
+            let (path, kind) = match crate::path::is::git_inner(&path, use_env) {
                 Ok(kind) => (path, kind),
                 Err(_) => {
                     let git_dir = path.join(".git");
-                    crate::path::is::git(&git_dir).map(|kind| (git_dir, kind))?
+                    crate::path::is::git_inner(&git_dir, use_env).map(|kind| (git_dir, kind))?
                 }
             };
             let (git_dir, worktree_dir) =
                 crate::Path::from_dot_git_dir(path, kind).into_repository_and_work_tree_directories();
-            Repository::open_from_paths(git_dir, worktree_dir)
+            Repository::open_from_paths(git_dir, worktree_dir, options)
         }
 
         pub(in crate::repository) fn open_from_paths(
             git_dir: PathBuf,
             mut worktree_dir: Option<PathBuf>,
+            options: Options,
         ) -> Result<Self, Error> {
+            let git_dir = crate::path::resolve::git_dir(git_dir)?;
+            check_ownership(&git_dir, worktree_dir.as_deref(), &options)?;
             let config = git_config::file::GitConfig::open(git_dir.join("config"))?;
             if worktree_dir.is_none() {
                 let is_bare = config
@@ -99,21 +190,74 @@ pub mod open {
                 git_hash::Kind::Sha1
             };
 
+            let mut odb = git_odb::linked::Store::at(git_dir.join("objects"))?;
+            if let Some(level) = crate::config::compression_level(&config, ("core", "looseCompression"))? {
+                odb.dbs[0].loose.compression_level = level;
+            }
+            let fsync = crate::config::fsync(&config);
+            odb.dbs[0].loose.fsync = fsync;
+            let mut refs = git_ref::file::Store::at(
+                git_dir,
+                if worktree_dir.is_none() {
+                    git_ref::file::WriteReflog::Disable
+                } else {
+                    git_ref::file::WriteReflog::Normal
+                },
+            );
+            refs.fsync = fsync;
+
             Ok(crate::Repository {
-                odb: git_odb::linked::Store::at(git_dir.join("objects"))?,
-                refs: git_ref::file::Store::at(
-                    git_dir,
-                    if worktree_dir.is_none() {
-                        git_ref::file::WriteReflog::Disable
-                    } else {
-                        git_ref::file::WriteReflog::Normal
-                    },
-                ),
+                odb: crate::store::Objects::Linked(odb),
+                refs: crate::store::References::File(refs),
                 work_tree: worktree_dir,
                 hash_kind,
+                permissions: options.permissions,
             })
         }
     }
+
+    /// Fail if the work tree or git directory isn't owned by the current user, unless it is declared safe in `options` or
+    /// configuration files that can't be controlled by the repository.
+    fn check_ownership(git_dir: &Path, worktree_dir: Option<&Path>, options: &Options) -> Result<(), Error> {
+        let owned = |path: &Path| {
+            crate::path::is::owned_by_current_user(path).map_err(|source| Error::Ownership {
+                path: path.to_owned(),
+                source,
+            })
+        };
+        let dir = worktree_dir.unwrap_or(git_dir);
+        if owned(dir)? && (worktree_dir.is_none() || owned(git_dir)?) {
+            return Ok(());
+        }
+
+        let config = crate::config::open_external(options.permissions)?;
+        let mut safe_directories: Vec<PathBuf> = Vec::new();
+        for value in config
+            .get_raw_multi_value("safe", None, "directory")
+            .unwrap_or_default()
+        {
+            if value.is_empty() {
+                safe_directories.clear();
+            } else if let Ok(path) = value.to_path() {
+                safe_directories.push(path.to_owned());
+            }
+        }
+        safe_directories.extend(options.safe_directories.iter().cloned());
+
+        let canonical_dir = dir.canonicalize().ok();
+        let is_safe = safe_directories.iter().any(|safe| {
+            safe == Path::new("*")
+                || safe == dir
+                || canonical_dir
+                    .as_ref()
+                    .map_or(false, |dir| safe.canonicalize().map_or(false, |safe| &safe == dir))
+        });
+        if is_safe {
+            Ok(())
+        } else {
+            Err(Error::UnsafeDirectory { path: dir.to_owned() })
+        }
+    }
 }
 
 ///
@@ -138,26 +282,244 @@ pub mod init {
         /// Fails without action if there is already a `.git` repository inside of `directory`, but
         /// won't mind if the `directory` otherwise is non-empty.
         pub fn init(directory: impl AsRef<Path>, kind: crate::Kind) -> Result<Self, Error> {
-            let path = crate::path::create::into(directory.as_ref(), kind)?;
+            Self::init_opts(directory, kind, Default::default())
+        }
+
+        /// Like [`Repository::init()`], but configurable with `options`, for instance to leave out sample hooks or to choose the
+        /// initial branch.
+        pub fn init_opts(
+            directory: impl AsRef<Path>,
+            kind: crate::Kind,
+            options: crate::path::create::Options,
+        ) -> Result<Self, Error> {
+            let path = crate::path::create::into_opts(directory.as_ref(), kind, options)?;
             Ok(path.try_into()?)
         }
     }
 }
 
-mod location {
+///
+pub mod ahead_behind {
+    use std::collections::{hash_map::Entry, BinaryHeap, HashMap};
+
+    use git_hash::ObjectId;
+    use git_odb::FindExt;
+
     use crate::Repository;
 
+    /// The outcome of [`Repository::ahead_behind()`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct Outcome {
+        /// The amount of commits only reachable from the local commit.
+        pub ahead: usize,
+        /// The amount of commits only reachable from the upstream commit.
+        pub behind: usize,
+    }
+
+    /// The error returned by [`Repository::ahead_behind()`].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        FindExistingCommit(#[from] git_pack::find::existing_object::Error<git_odb::compound::find::Error>),
+        #[error(transparent)]
+        CommitGraph(#[from] git_commitgraph::file::commit::Error),
+    }
+
+    const LOCAL: u8 = 1;
+    const UPSTREAM: u8 = 1 << 1;
+    const BOTH: u8 = LOCAL | UPSTREAM;
+
+    /// A commit in the queue, ordered by generation number first and commit time second, so that descendants are
+    /// visited before their ancestors.
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    struct Queued {
+        generation: u32,
+        time: u64,
+        id: ObjectId,
+        parents: Vec<ObjectId>,
+        /// True if the commit wasn't reachable from both sides when it was queued, or if its generation number isn't accurate.
+        active: bool,
+    }
+
+    impl Repository {
+        /// Count the commits reachable only from `local` and only from `upstream`, which is what one typically wants to know
+        /// when comparing a branch with its remote tracking branch.
+        ///
+        /// Instead of traversing both histories entirely, the traversal stops as soon as the remaining commits are reachable
+        /// from both sides, which is determined by visiting commits in order of their generation number as provided by the
+        /// commit-graph. Commits not contained in the commit-graph are visited first and can't be used to stop early, so without
+        /// a commit-graph all commits reachable from either side are traversed once.
+        pub fn ahead_behind(
+            &self,
+            local: impl Into<ObjectId>,
+            upstream: impl Into<ObjectId>,
+        ) -> Result<Outcome, Error> {
+            let graph = if self.is_in_memory() {
+                None
+            } else {
+                git_commitgraph::Graph::from_info_dir(self.objects_dir().join("info")).ok()
+            };
+            let mut buf = Vec::new();
+            let mut lookup = |id: ObjectId, flags: u8| -> Result<Queued, Error> {
+                let mut queued = match graph.as_ref().and_then(|g| g.commit_by_id(id).map(|c| (g, c))) {
+                    Some((graph, commit)) => Queued {
+                        generation: match commit.generation() {
+                            0 => git_commitgraph::GENERATION_NUMBER_INFINITY,
+                            generation => generation,
+                        },
+                        time: commit.committer_timestamp(),
+                        parents: commit
+                            .iter_parents()
+                            .map(|pos| pos.map(|pos| graph.id_at(pos).to_owned()))
+                            .collect::<Result<_, _>>()?,
+                        id,
+                        active: false,
+                    },
+                    None => {
+                        let commit = self.odb.find_commit(id, &mut buf, &mut git_pack::cache::Never)?;
+                        Queued {
+                            generation: git_commitgraph::GENERATION_NUMBER_INFINITY,
+                            time: commit.committer.time.time as u64,
+                            parents: commit.parents().collect(),
+                            id,
+                            active: false,
+                        }
+                    }
+                };
+                queued.active = flags != BOTH || queued.generation == git_commitgraph::GENERATION_NUMBER_INFINITY;
+                Ok(queued)
+            };
+
+            let mut flags = HashMap::<ObjectId, u8>::new();
+            for (id, flag) in [(local.into(), LOCAL), (upstream.into(), UPSTREAM)] {
+                *flags.entry(id).or_default() |= flag;
+            }
+            let mut queue = BinaryHeap::new();
+            let mut num_active = 0;
+            for (id, commit_flags) in &flags {
+                let commit = lookup(*id, *commit_flags)?;
+                num_active += commit.active as usize;
+                queue.push(commit);
+            }
+
+            // Only with accurate generation numbers is it certain that all descendants of a queued commit were visited, and
+            // thus that there can't be any commit left which isn't reachable from both sides once no active commit is queued.
+            while num_active != 0 {
+                let commit = queue.pop().expect("active commits are queued");
+                num_active -= commit.active as usize;
+                let commit_flags = flags[&commit.id];
+                for parent_id in commit.parents {
+                    match flags.entry(parent_id) {
+                        Entry::Occupied(mut entry) => {
+                            if *entry.get() | commit_flags == *entry.get() {
+                                continue;
+                            }
+                            // Commits are queued again if their flags change after they were visited, which can only happen if
+                            // they are ordered by commit time, to propagate the change to their ancestors.
+                            *entry.get_mut() |= commit_flags;
+                        }
+                        Entry::Vacant(entry) => {
+                            entry.insert(commit_flags);
+                        }
+                    }
+                    let parent = lookup(parent_id, flags[&parent_id])?;
+                    num_active += parent.active as usize;
+                    queue.push(parent);
+                }
+            }
+
+            let count = |flag: u8| flags.values().filter(|f| **f == flag).count();
+            Ok(Outcome {
+                ahead: count(LOCAL),
+                behind: count(UPSTREAM),
+            })
+        }
+    }
+}
+
+mod in_memory {
+    use std::convert::TryInto;
+
+    use git_ref::{
+        transaction::{Change, LogChange, PreviousValue, RefEdit},
+        Target,
+    };
+
+    use crate::{
+        store::{Objects, References},
+        Repository,
+    };
+
+    impl Repository {
+        /// Create a bare repository which keeps all objects and references in memory and never accesses the filesystem,
+        /// with `HEAD` pointing to the unborn branch `main`.
+        ///
+        /// It isn't configured, can't run hooks and has no reference logs, which makes it most useful for tests or for
+        /// creating objects and references which are to be discarded. Operations which need a location, like the ones on the
+        /// index or maintenance, panic when [accessing it][Repository::git_dir()].
+        pub fn new_in_memory() -> Self {
+            let refs = git_ref::memory::Store::default();
+            refs.transaction()
+                .prepare(
+                    Some(RefEdit {
+                        change: Change::Update {
+                            log: LogChange::default(),
+                            expected: PreviousValue::MustNotExist,
+                            new: Target::Symbolic("refs/heads/main".try_into().expect("valid name")),
+                        },
+                        name: "HEAD".try_into().expect("valid name"),
+                        deref: false,
+                    }),
+                    git_lock::acquire::Fail::Immediately,
+                )
+                .expect("a new store is neither locked nor contains HEAD")
+                .commit();
+            Repository {
+                refs: References::Memory(refs),
+                odb: Objects::Memory(git_odb::memory::store()),
+                work_tree: None,
+                hash_kind: git_hash::Kind::Sha1,
+                permissions: crate::open::Permissions::isolated(),
+            }
+        }
+
+        /// Return true if this repository keeps its objects and references in memory only, as created by
+        /// [`new_in_memory()`][Repository::new_in_memory()].
+        pub fn is_in_memory(&self) -> bool {
+            matches!(self.refs, References::Memory(_))
+        }
+    }
+}
+
+mod location {
+    use crate::{
+        store::{Objects, References},
+        Repository,
+    };
+
     impl Repository {
         /// The path to the `.git` directory itself, or equivalent if this is a bare repository.
+        ///
+        /// # Panics
+        ///
+        /// If the repository is [kept in memory][Repository::is_in_memory()].
         pub fn path(&self) -> &std::path::Path {
-            &self.refs.base
+            self.git_dir()
         }
 
         /// Return the path to the repository itself, containing objects, references, configuration, and more.
         ///
         /// Synonymous to [`path()`][Repository::path()].
+        ///
+        /// # Panics
+        ///
+        /// If the repository is [kept in memory][Repository::is_in_memory()].
         pub fn git_dir(&self) -> &std::path::Path {
-            &self.refs.base
+            match &self.refs {
+                References::File(store) => &store.base,
+                References::Memory(_) => panic!("BUG: repositories in memory don't have a git directory"),
+            }
         }
 
         /// Return the path to the working directory if this is not a bare repository.
@@ -166,8 +528,15 @@ mod location {
         }
 
         /// Return the path to the directory containing all objects.
+        ///
+        /// # Panics
+        ///
+        /// If the repository is [kept in memory][Repository::is_in_memory()].
         pub fn objects_dir(&self) -> &std::path::Path {
-            &self.odb.dbs[0].loose.path
+            match &self.odb {
+                Objects::Linked(store) => &store.dbs[0].loose.path,
+                Objects::Memory(_) => panic!("BUG: repositories in memory don't have an objects directory"),
+            }
         }
     }
 }
@@ -202,6 +571,9 @@ mod impls {
 
     impl std::fmt::Debug for Repository {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            if self.is_in_memory() {
+                return write!(f, "Repository(in memory)");
+            }
             write!(
                 f,
                 "Repository(git = '{}', working_tree: {:?}",
@@ -211,8 +583,13 @@ mod impls {
         }
     }
 
+    /// Repositories are equal if they are at the same location, which for repositories in memory means that they are the
+    /// same instance.
     impl PartialEq<Repository> for Repository {
         fn eq(&self, other: &Repository) -> bool {
+            if self.is_in_memory() || other.is_in_memory() {
+                return std::ptr::eq(self, other);
+            }
             self.git_dir() == other.git_dir() && self.work_tree == other.work_tree
         }
     }
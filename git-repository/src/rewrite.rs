@@ -0,0 +1,358 @@
+//! Rewrite the history reachable from references, similar to [`git filter-repo`](https://github.com/newren/git-filter-repo).
+//!
+//! A [`Filter`] decides about the path of each file and the message of each commit and tag, which allows to purge files
+//! from history, move directories or extract a subdirectory into a history of its own.
+//! All rewritten objects are staged in an [in-memory object database][git_odb::memory::Proxy] and only written to
+//! the actual object database along with the updated references once the [`Outcome`] is [applied][Repository::apply_rewrite()].
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+};
+
+use git_hash::ObjectId;
+use git_object::bstr::{BStr, BString, ByteSlice};
+use git_odb::{FindExt, Write};
+use git_ref::{
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+    FullName, Target,
+};
+
+use crate::{
+    easy::ext::ReferenceAccessExt,
+    fast_import::import::{insert, join, write_tree, Files},
+    Repository,
+};
+
+/// Decide how files and messages are rewritten, with all methods leaving everything unchanged by default.
+pub trait Filter {
+    /// Return the path the file at `path` should have in the rewritten history, or `None` to remove it.
+    ///
+    /// The result is cached for the duration of the rewrite, hence it must only depend on `path`.
+    fn path(&mut self, path: &BStr) -> Option<BString> {
+        Some(path.to_owned())
+    }
+
+    /// Return the message of the rewritten commit or annotated tag with the original `id`, based on its current `message`.
+    fn message(&mut self, id: &git_hash::oid, message: &BStr) -> BString {
+        let _ = id;
+        message.to_owned()
+    }
+}
+
+/// Configure how history is rewritten.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// If true, commits with less than two parents are dropped if they end up with the same tree as their rewritten parent,
+    /// or with an empty tree if they have no parent. This is what's desired when removing or extracting files.
+    ///
+    /// Defaults to true.
+    pub prune_empty: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options { prune_empty: true }
+    }
+}
+
+/// The way a reference changes when a rewrite is applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Update {
+    /// The object the reference currently points to.
+    pub previous: ObjectId,
+    /// The object the reference will point to, or `None` if it will be deleted as all of its commits were pruned.
+    pub new: Option<ObjectId>,
+}
+
+/// The outcome of [`Repository::rewrite_history()`], to be written with [`Repository::apply_rewrite()`].
+pub struct Outcome {
+    /// A mapping of all original commits to the commits they were rewritten into, or `None` if they were pruned and had
+    /// no ancestor to take their place.
+    pub commits: HashMap<ObjectId, Option<ObjectId>>,
+    /// The references which are changed by the rewrite by their full name.
+    pub references: BTreeMap<BString, Update>,
+    /// All objects that were written as part of the rewrite, some of which may already exist in the object database.
+    pub objects: git_odb::memory::Storage,
+}
+
+///
+pub mod apply {
+    /// The error returned by [`Repository::apply_rewrite()`][crate::Repository::apply_rewrite()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        WriteObject(#[from] git_odb::loose::write::Error),
+        #[error(transparent)]
+        ReferenceName(#[from] git_validate::refname::Error),
+        #[error(transparent)]
+        EditReferences(#[from] crate::easy::reference::edit::Error),
+    }
+}
+
+/// The error returned by [`Repository::rewrite_history()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    FindReference(#[from] git_ref::file::find::existing::Error),
+    #[error(transparent)]
+    PackedRefsOpen(#[from] git_ref::packed::buffer::open::Error),
+    #[error(transparent)]
+    FindExistingObject(#[from] git_pack::find::existing::Error<git_odb::compound::find::Error>),
+    #[error(transparent)]
+    FindExistingTypedObject(#[from] git_pack::find::existing_object::Error<git_odb::compound::find::Error>),
+    #[error(transparent)]
+    WriteObject(#[from] std::io::Error),
+}
+
+impl Repository {
+    /// Rewrite the history reachable from the full reference names in `references`, like `refs/heads/main` or `HEAD`,
+    /// using `filter` to alter paths and messages and `options` to control which commits to keep.
+    ///
+    /// Commits are rewritten parents first, and annotated tags pointing to rewritten commits are rewritten as well.
+    /// Signatures of rewritten commits and tags are dropped as they would be invalid.
+    /// Nothing is written to the repository until the returned [`Outcome`] is [applied][Repository::apply_rewrite()].
+    pub fn rewrite_history(
+        &self,
+        references: impl IntoIterator<Item = impl AsRef<str>>,
+        filter: &mut impl Filter,
+        options: Options,
+    ) -> Result<Outcome, Error> {
+        let packed = self.refs.packed_buffer()?;
+        let mut state = State {
+            odb: git_odb::memory::proxy(&self.odb),
+            hash_kind: self.hash_kind,
+            filter,
+            options,
+            commits: HashMap::new(),
+            tree_of_commit: HashMap::new(),
+            trees: HashMap::new(),
+            paths: HashMap::new(),
+            buf: Vec::new(),
+        };
+        let mut updates = BTreeMap::new();
+        for name in references {
+            let mut reference = self.refs.find(name.as_ref(), packed.as_ref())?;
+            while let Some(next) = self.refs.follow(&reference, packed.as_ref()) {
+                reference = next?;
+            }
+            let previous = reference
+                .target
+                .as_id()
+                .expect("peeled after following symbolic refs")
+                .to_owned();
+            let kind = state
+                .odb
+                .find(previous, &mut state.buf, &mut git_pack::cache::Never)?
+                .kind;
+            let new = match kind {
+                git_object::Kind::Commit => state.rewrite_history(previous)?,
+                git_object::Kind::Tag => state.rewrite_tag(previous)?,
+                _ => continue,
+            };
+            if new != Some(previous) {
+                updates.insert(reference.name.into_inner(), Update { previous, new });
+            }
+        }
+        Ok(Outcome {
+            commits: state.commits,
+            references: updates,
+            objects: state.odb.into_storage(),
+        })
+    }
+
+    /// Write all objects of a rewrite's `outcome` that don't yet exist into the object database and change all of its
+    /// references in a single transaction, acquiring locks according to `lock_mode`.
+    ///
+    /// References which changed since the rewrite was performed cause the transaction to fail.
+    pub fn apply_rewrite(&self, outcome: Outcome, lock_mode: git_lock::acquire::Fail) -> Result<(), apply::Error> {
+        for (id, (kind, data)) in &outcome.objects {
+            self.odb
+                .write_stream_if_missing(*kind, data.len() as u64, data.as_slice(), Some(id), self.hash_kind)?;
+        }
+
+        let mut edits = Vec::new();
+        for (name, update) in outcome.references {
+            let expected = PreviousValue::MustExistAndMatch(Target::Peeled(update.previous));
+            let change = match update.new {
+                Some(new) => Change::Update {
+                    log: LogChange {
+                        mode: RefLog::AndReference,
+                        force_create_reflog: false,
+                        message: "rewrite history".into(),
+                    },
+                    expected,
+                    new: Target::Peeled(new),
+                },
+                None => Change::Delete {
+                    expected,
+                    log: RefLog::AndReference,
+                },
+            };
+            edits.push(RefEdit {
+                change,
+                name: FullName::try_from(name.as_bstr())?,
+                deref: false,
+            });
+        }
+        self.to_easy().edit_references(edits, lock_mode, None)?;
+        Ok(())
+    }
+}
+
+struct State<'repo, 'filter, F> {
+    odb: git_odb::memory::Proxy<&'repo crate::store::Objects>,
+    hash_kind: git_hash::Kind,
+    filter: &'filter mut F,
+    options: Options,
+    /// Original commits mapped to their rewritten counterparts.
+    commits: HashMap<ObjectId, Option<ObjectId>>,
+    /// The tree of each rewritten commit.
+    tree_of_commit: HashMap<ObjectId, ObjectId>,
+    /// Original root trees mapped to their rewritten counterparts, along with a flag telling whether they are empty.
+    trees: HashMap<ObjectId, (ObjectId, bool)>,
+    /// Original paths mapped to their rewritten counterparts.
+    paths: HashMap<BString, Option<BString>>,
+    buf: Vec<u8>,
+}
+
+impl<'repo, 'filter, F> State<'repo, 'filter, F>
+where
+    F: Filter,
+{
+    /// Rewrite all commits reachable from `tip` that weren't rewritten yet, parents first, and return what `tip` became.
+    fn rewrite_history(&mut self, tip: ObjectId) -> Result<Option<ObjectId>, Error> {
+        let mut pending = HashMap::new();
+        let mut stack = vec![(tip, false)];
+        while let Some((id, parents_rewritten)) = stack.pop() {
+            if self.commits.contains_key(&id) {
+                continue;
+            }
+            if parents_rewritten {
+                let commit = pending.remove(&id).expect("loaded when first seen");
+                let new = self.rewrite_commit(id, commit)?;
+                self.commits.insert(id, new);
+                continue;
+            }
+            let commit: git_object::Commit = self
+                .odb
+                .find_commit(id, &mut self.buf, &mut git_pack::cache::Never)?
+                .into();
+            stack.push((id, true));
+            stack.extend(
+                commit
+                    .parents
+                    .iter()
+                    .rev()
+                    .filter(|parent| !self.commits.contains_key(*parent))
+                    .map(|parent| (*parent, false)),
+            );
+            pending.insert(id, commit);
+        }
+        Ok(self.commits[&tip])
+    }
+
+    fn rewrite_commit(&mut self, id: ObjectId, mut commit: git_object::Commit) -> Result<Option<ObjectId>, Error> {
+        let (tree, is_empty) = self.rewrite_tree(commit.tree)?;
+        let mut parents = Vec::<ObjectId>::with_capacity(commit.parents.len());
+        for parent in commit.parents.iter().filter_map(|parent| self.commits[parent]) {
+            if !parents.contains(&parent) {
+                parents.push(parent);
+            }
+        }
+        if self.options.prune_empty && parents.len() < 2 {
+            let unchanged = match parents.first() {
+                Some(parent) => self
+                    .tree_of_commit
+                    .get(parent)
+                    .map_or(false, |parent_tree| *parent_tree == tree),
+                None => is_empty,
+            };
+            if unchanged {
+                return Ok(parents.first().copied());
+            }
+        }
+
+        let message = self.filter.message(&id, commit.message.as_ref());
+        let is_unchanged = tree == commit.tree && message == commit.message && parents.iter().eq(commit.parents.iter());
+        if is_unchanged {
+            self.tree_of_commit.insert(id, tree);
+            return Ok(Some(id));
+        }
+        commit.tree = tree;
+        commit.parents = parents.into_iter().collect();
+        commit.message = message;
+        commit.extra_headers.retain(|(name, _)| name != "gpgsig");
+        let new_id = self.odb.write(commit, self.hash_kind)?;
+        self.tree_of_commit.insert(new_id, tree);
+        Ok(Some(new_id))
+    }
+
+    /// Rewrite the annotated tag `id` if the commit it points to was rewritten, or return `None` if the commit was pruned.
+    fn rewrite_tag(&mut self, id: ObjectId) -> Result<Option<ObjectId>, Error> {
+        let mut tag: git_object::Tag = self
+            .odb
+            .find_tag(id, &mut self.buf, &mut git_pack::cache::Never)?
+            .into();
+        if tag.target_kind != git_object::Kind::Commit {
+            return Ok(Some(id));
+        }
+        let target = match self.rewrite_history(tag.target)? {
+            Some(target) => target,
+            None => return Ok(None),
+        };
+        let message = self.filter.message(&id, tag.message.as_ref());
+        if target == tag.target && message == tag.message {
+            return Ok(Some(id));
+        }
+        tag.target = target;
+        tag.message = message;
+        tag.pgp_signature = None;
+        Ok(Some(self.odb.write(tag, self.hash_kind)?))
+    }
+
+    /// Rewrite the root `tree` by passing all of its file paths through the filter, returning the new tree and whether it is empty.
+    fn rewrite_tree(&mut self, tree: ObjectId) -> Result<(ObjectId, bool), Error> {
+        if let Some(rewritten) = self.trees.get(&tree) {
+            return Ok(*rewritten);
+        }
+        let mut original = Files::new();
+        self.collect_files(tree, BString::default(), &mut original)?;
+        let mut files = Files::new();
+        for (path, (mode, id)) in original {
+            let filter = &mut self.filter;
+            let new_path = self
+                .paths
+                .entry(path)
+                .or_insert_with_key(|path| filter.path(path.as_ref()).filter(|p| !p.is_empty()))
+                .clone();
+            if let Some(path) = new_path {
+                insert(&mut files, path, mode, id);
+            }
+        }
+        let rewritten = (write_tree(&self.odb, self.hash_kind, &files)?, files.is_empty());
+        self.trees.insert(tree, rewritten);
+        Ok(rewritten)
+    }
+
+    /// Recursively add all non-tree entries of `tree` to `files`, with their paths prefixed with `prefix`.
+    fn collect_files(&mut self, tree: ObjectId, prefix: BString, files: &mut Files) -> Result<(), Error> {
+        let entries: Vec<_> = self
+            .odb
+            .find_tree(tree, &mut self.buf, &mut git_pack::cache::Never)?
+            .entries
+            .into_iter()
+            .map(|entry| (entry.filename.to_owned(), entry.mode, entry.oid.to_owned()))
+            .collect();
+        for (name, mode, id) in entries {
+            let path = join(prefix.as_ref(), name.as_ref());
+            if mode.is_tree() {
+                self.collect_files(id, path, files)?;
+            } else {
+                files.insert(path, (mode, id));
+            }
+        }
+        Ok(())
+    }
+}
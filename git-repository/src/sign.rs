@@ -0,0 +1,201 @@
+//! Sign commits and tags, with the signature embedded as `gpgsig` header of commits or appended to the message of tags.
+//!
+//! A [signer][Fn] receives the canonical payload, which is the object as serialized without signature, and returns the
+//! ASCII armored signature. [`gpg()`] and [`ssh()`] create signers running `gpg` or `ssh-keygen` like `git` does, and
+//! [`from_config()`] creates the one configured for a repository.
+use std::{
+    borrow::Cow,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+use git_object::{
+    bstr::{BString, ByteSlice},
+    WriteTo,
+};
+
+use crate::Repository;
+
+/// A function to sign the canonical payload of an object, returning the ASCII armored signature.
+pub type Fn = dyn FnMut(&[u8]) -> Result<BString, Box<dyn std::error::Error + Send + Sync>>;
+
+/// The error returned by [`commit()`] and [`tag()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not serialize the object to sign")]
+    Encode(#[from] std::io::Error),
+    #[error("Could not sign the object")]
+    Sign(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("The signer returned an empty signature")]
+    EmptySignature,
+}
+
+/// Sign `commit` with `sign` and store the signature in its `gpgsig` header, replacing previous signatures.
+pub fn commit(commit: &mut git_object::Commit, sign: &mut Fn) -> Result<(), Error> {
+    commit.extra_headers.retain(|(name, _)| name != "gpgsig");
+    let mut payload = Vec::new();
+    commit.write_to(&mut payload)?;
+    let signature = signature(&payload, sign)?;
+    commit.extra_headers.push(("gpgsig".into(), signature));
+    Ok(())
+}
+
+/// Sign `tag` with `sign` and store the signature to be appended to its message, replacing a previous signature.
+pub fn tag(tag: &mut git_object::Tag, sign: &mut Fn) -> Result<(), Error> {
+    tag.pgp_signature = None;
+    let mut payload = Vec::new();
+    tag.write_to(&mut payload)?;
+    // The signature is separated from the message by a newline, which is part of the signed payload.
+    payload.push(b'\n');
+    let mut signature = signature(&payload, sign)?;
+    signature.push(b'\n');
+    tag.pgp_signature = Some(signature);
+    Ok(())
+}
+
+fn signature(payload: &[u8], sign: &mut Fn) -> Result<BString, Error> {
+    let mut signature = sign(payload).map_err(Error::Sign)?;
+    while signature.last() == Some(&b'\n') {
+        signature.pop();
+    }
+    if signature.is_empty() {
+        return Err(Error::EmptySignature);
+    }
+    Ok(signature)
+}
+
+///
+pub mod program {
+    use std::path::PathBuf;
+
+    use git_object::bstr::BString;
+
+    /// The error returned by signers running a program, like [`gpg()`][super::gpg()] and [`ssh()`][super::ssh()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not run '{}' to sign the object", program.display())]
+        Spawn { source: std::io::Error, program: PathBuf },
+        #[error("'{}' failed to sign the object with {status}: {stderr}", program.display())]
+        Failed {
+            program: PathBuf,
+            status: std::process::ExitStatus,
+            stderr: BString,
+        },
+    }
+}
+
+/// Return a signer running `program`, which is `gpg` or compatible like `gpgsm`, to create a detached ASCII armored signature
+/// with `key` or the default key of `program` if `None`, like `git` does.
+pub fn gpg(program: impl Into<PathBuf>, key: Option<String>) -> Box<Fn> {
+    let program = program.into();
+    Box::new(move |payload: &[u8]| {
+        let mut args = vec!["--status-fd=2".to_owned(), "-bsa".to_owned()];
+        if let Some(key) = &key {
+            args.push("-u".into());
+            args.push(key.clone());
+        }
+        run(&program, &args, payload)
+    })
+}
+
+/// Return a signer running `program`, which is `ssh-keygen` or compatible, to create a signature in the `git` namespace with
+/// the private key at `key`, or the key in the ssh agent matching the public key at `key`, like `git` does.
+pub fn ssh(program: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Box<Fn> {
+    let program = program.into();
+    let key = key.into();
+    Box::new(move |payload: &[u8]| {
+        let args = [
+            "-Y".into(),
+            "sign".into(),
+            "-n".into(),
+            "git".into(),
+            "-f".into(),
+            key.to_string_lossy().into_owned(),
+        ];
+        run(&program, &args, payload)
+    })
+}
+
+/// Run `program` with `args` and `payload` on stdin, and return its output as signature.
+fn run(
+    program: &std::path::Path,
+    args: &[String],
+    payload: &[u8],
+) -> Result<BString, Box<dyn std::error::Error + Send + Sync>> {
+    let spawn_error = |source| program::Error::Spawn {
+        source,
+        program: program.to_owned(),
+    };
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(spawn_error)?;
+    {
+        let mut stdin = child.stdin.take().expect("configured");
+        // The program may fail before reading all of its input, which is reported by its exit status.
+        stdin.write_all(payload).ok();
+    }
+    let output = child.wait_with_output().map_err(spawn_error)?;
+    if !output.status.success() {
+        return Err(program::Error::Failed {
+            program: program.to_owned(),
+            status: output.status,
+            stderr: output.stderr.trim_end().into(),
+        }
+        .into());
+    }
+    Ok(output.stdout.into())
+}
+
+///
+pub mod from_config {
+    /// The error returned by [`from_config()`][super::from_config()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Config(#[from] crate::config::Error),
+        #[error("The signature format '{format}' in 'gpg.format' is unknown, expected 'openpgp', 'x509' or 'ssh'")]
+        UnknownFormat { format: String },
+        #[error("'user.signingKey' must be set to sign with ssh")]
+        MissingSshKey,
+    }
+}
+
+/// Return the signer configured for `repo` by `gpg.format`, `gpg.<format>.program`, `gpg.program` and `user.signingKey`, or
+/// `None` if the [permissions][crate::open::Permissions::execute_programs] of the repository don't allow executing programs.
+///
+/// Without `user.signingKey`, `gpg` uses its default key whereas `git` would use the one matching the committer.
+/// Signing with ssh requires `user.signingKey` to be the path to a key, as literal keys aren't supported.
+/// Whether commits or tags are supposed to be signed, as configured by `commit.gpgSign` and `tag.gpgSign`, is up to the caller.
+pub fn from_config(repo: &Repository) -> Result<Option<Box<Fn>>, from_config::Error> {
+    if !repo.permissions.execute_programs {
+        return Ok(None);
+    }
+    let config = crate::config::open(repo)?;
+    let string = |section: &str, subsection: Option<&str>, key: &str| {
+        config
+            .value::<Cow<'_, [u8]>>(section, subsection, key)
+            .ok()
+            .map(|value| value.to_str_lossy().into_owned())
+    };
+    let format = string("gpg", None, "format").unwrap_or_else(|| "openpgp".into());
+    let key = string("user", None, "signingKey");
+    let program = |default: &str| {
+        string("gpg", Some(&format), "program")
+            .or_else(|| (format == "openpgp").then(|| string("gpg", None, "program")).flatten())
+            .unwrap_or_else(|| default.into())
+    };
+    Ok(Some(match format.as_str() {
+        "openpgp" => gpg(program("gpg"), key),
+        "x509" => gpg(program("gpgsm"), key),
+        "ssh" => ssh(program("ssh-keygen"), key.ok_or(from_config::Error::MissingSshKey)?),
+        _ => return Err(from_config::Error::UnknownFormat { format }),
+    }))
+}
@@ -0,0 +1,192 @@
+//! Compare the index with the work tree, like `git status` does for changes that aren't staged.
+use std::path::Path;
+
+use git_features::parallel::{in_parallel_if, Reduce};
+use git_object::bstr::{BString, ByteSlice};
+use git_odb::Write;
+
+use crate::{
+    add::{self, Conversion},
+    dirwalk,
+    index::{entry, Entry},
+    Repository,
+};
+
+/// The amount of index entries to compare on a thread at a time.
+const CHUNK_SIZE: usize = 500;
+
+/// The error returned by [`Repository::status()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("A bare repository has no work tree to compare the index with")]
+    NoWorkTree,
+    #[error(transparent)]
+    Config(#[from] crate::config::Error),
+    #[error(transparent)]
+    Dirwalk(#[from] dirwalk::Error),
+    #[error(transparent)]
+    Index(#[from] crate::index::Error),
+    #[error(transparent)]
+    Add(#[from] add::Error),
+    #[error("The path '{path}' can't be represented on this platform")]
+    IllformedPath { path: BString },
+    #[error("Could not read {}", path.display())]
+    Io {
+        source: std::io::Error,
+        path: std::path::PathBuf,
+    },
+}
+
+/// The outcome of [`Repository::status()`], with all paths sorted.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// Tracked paths whose content or mode in the work tree differs from the index.
+    pub modified: Vec<BString>,
+    /// Tracked paths that don't exist in the work tree anymore.
+    pub deleted: Vec<BString>,
+    /// Paths in the work tree that aren't tracked and aren't ignored.
+    pub untracked: Vec<BString>,
+}
+
+impl Repository {
+    /// Compare all entries of the index with the files in the work tree and find untracked files, like `git status` does for
+    /// changes that aren't staged.
+    ///
+    /// Files whose metadata matches the one in the index are considered unchanged, unless their metadata
+    /// [is racy][entry::Stat::is_racy()] in relation to the time the index was written, in which case their content is hashed
+    /// and compared just like the content of files with different metadata. This way, files modified in the same timestamp
+    /// granularity as the index was written are never reported as unchanged.
+    ///
+    /// The index isn't changed, and entries of submodules, entries not expected in the work tree and entries assumed to be
+    /// unchanged are skipped. Conversions are applied as described in [`Repository::add()`].
+    /// Paths in the work tree are tracked if they refer to the same file as a path in the index according to the
+    /// [path comparison][Repository::path_comparison()] of the repository.
+    ///
+    /// Entries are compared and the work tree is walked using the [thread limit of the repository][Repository::thread_limit()].
+    pub fn status(&self) -> Result<Outcome, Error> {
+        let work_tree = self.workdir().ok_or(Error::NoWorkTree)?;
+        let conversion = Conversion::from_config(self)?;
+        let thread_limit = self.thread_limit()?.map(|limit| limit.num_threads());
+        let state = self.index()?;
+        let index_time = self.index_timestamp();
+        let hash_kind = self.hash_kind;
+
+        let mut out = Outcome::default();
+        let mut candidates = Vec::new();
+        for entry in state.entries() {
+            if entry.mode == entry::Mode::Commit || entry.flags.skip_worktree || entry.flags.assume_valid {
+                continue;
+            }
+            if entry.flags.stage != 0 {
+                if out.modified.last() != Some(&entry.path) {
+                    out.modified.push(entry.path.clone());
+                }
+                continue;
+            }
+            candidates.push(entry);
+        }
+
+        let num_candidates = candidates.len();
+        in_parallel_if(
+            || num_candidates > CHUNK_SIZE,
+            candidates.chunks(CHUNK_SIZE),
+            thread_limit,
+            |_| (),
+            |entries, _| {
+                entries
+                    .iter()
+                    .map(|entry| {
+                        compare(entry, work_tree, &conversion, index_time, hash_kind)
+                            .map(|change| change.map(|change| (change, entry.path.clone())))
+                    })
+                    .filter_map(Result::transpose)
+                    .collect::<Result<Vec<_>, _>>()
+            },
+            Collect { out: &mut out },
+        )?;
+        out.modified.sort();
+        out.deleted.sort();
+
+        let comparison = self.path_comparison()?;
+        let tracked = comparison.tracked_keys(&state);
+        out.untracked = self
+            .dirwalk(dirwalk::Options {
+                thread_limit,
+                ..Default::default()
+            })?
+            .into_iter()
+            .filter(|e| matches!(e.kind, dirwalk::Kind::File | dirwalk::Kind::Symlink))
+            .map(|e| e.path)
+            .filter(|path| !tracked.contains(&*comparison.key(path.as_ref())))
+            .collect();
+        Ok(out)
+    }
+}
+
+/// How an entry differs from its file in the work tree.
+enum Change {
+    Modified,
+    Deleted,
+}
+
+/// Compare `entry` with its file in `work_tree`, or return `None` if it is unchanged.
+fn compare(
+    entry: &Entry,
+    work_tree: &Path,
+    Conversion { autocrlf, file_mode }: &Conversion,
+    index_time: Option<entry::Time>,
+    hash_kind: git_hash::Kind,
+) -> Result<Option<Change>, Error> {
+    let fs_path = work_tree.join(entry.path.to_path().map_err(|_| Error::IllformedPath {
+        path: entry.path.clone(),
+    })?);
+    let meta = match std::fs::symlink_metadata(&fs_path) {
+        Ok(meta) if meta.is_file() || meta.file_type().is_symlink() => meta,
+        Ok(_) => return Ok(Some(Change::Deleted)),
+        Err(err) if matches!(err.kind(), std::io::ErrorKind::NotFound) || add::is_not_a_directory(&err) => {
+            return Ok(Some(Change::Deleted))
+        }
+        Err(source) => return Err(Error::Io { source, path: fs_path }),
+    };
+
+    let mode = add::to_mode(&meta, *file_mode, Some(entry));
+    let is_racy = index_time.map_or(true, |index_time| entry.stat.is_racy(index_time));
+    if mode != entry.mode || entry.flags.intent_to_add {
+        return Ok(Some(Change::Modified));
+    }
+    if entry.stat.matches(&add::to_stat(&meta)) && !is_racy {
+        return Ok(None);
+    }
+    let data = add::read_content(&fs_path, &meta, *autocrlf)?;
+    let id = git_odb::sink()
+        .write_buf(git_object::Kind::Blob, &data, hash_kind)
+        .map_err(|source| Error::Io { source, path: fs_path })?;
+    Ok((id != entry.id).then(|| Change::Modified))
+}
+
+/// Sort the changes found on all threads into the outcome.
+struct Collect<'a> {
+    out: &'a mut Outcome,
+}
+
+impl<'a> Reduce for Collect<'a> {
+    type Input = Result<Vec<(Change, BString)>, Error>;
+    type FeedProduce = ();
+    type Output = ();
+    type Error = Error;
+
+    fn feed(&mut self, item: Self::Input) -> Result<Self::FeedProduce, Self::Error> {
+        for (change, path) in item? {
+            match change {
+                Change::Modified => self.out.modified.push(path),
+                Change::Deleted => self.out.deleted.push(path),
+            }
+        }
+        Ok(())
+    }
+
+    fn finalize(self) -> Result<Self::Output, Self::Error> {
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+//! The object and reference stores of a [`Repository`][crate::Repository], which are either backed by files in the
+//! repository directory or kept in memory only.
+use std::ops::{Deref, DerefMut};
+
+/// The object database of a [`Repository`][crate::Repository].
+pub enum Objects {
+    /// Objects in the `objects` directory of the repository and its alternates.
+    Linked(git_odb::linked::Store),
+    /// Objects kept in memory only, as used by [`Repository::new_in_memory()`][crate::Repository::new_in_memory()].
+    Memory(git_odb::memory::Proxy<git_odb::linked::Store>),
+}
+
+/// The reference database of a [`Repository`][crate::Repository].
+pub enum References {
+    /// Loose and packed references in the repository directory.
+    File(git_ref::file::Store),
+    /// References kept in memory only, as used by [`Repository::new_in_memory()`][crate::Repository::new_in_memory()].
+    Memory(git_ref::memory::Store),
+}
+
+/// Access to the linked object databases on disk, of which there are none if objects are kept in memory.
+impl Deref for Objects {
+    type Target = git_odb::linked::Store;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Objects::Linked(store) => store,
+            Objects::Memory(store) => store,
+        }
+    }
+}
+
+impl DerefMut for Objects {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Objects::Linked(store) => store,
+            Objects::Memory(store) => store,
+        }
+    }
+}
+
+mod objects;
+
+///
+pub mod references;
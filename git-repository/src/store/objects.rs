@@ -0,0 +1,128 @@
+use std::io::Read;
+
+use git_hash::{oid, ObjectId};
+use git_odb::{compound, linked, loose, write::Outcome, Find, Write};
+use git_pack::{bundle::Location, data::Object, find::Entry, Bundle};
+
+use crate::store::Objects;
+
+impl Objects {
+    /// Return true if the object with `id` is contained in the store.
+    pub fn contains(&self, id: impl AsRef<oid>) -> bool {
+        match self {
+            Objects::Linked(store) => store.contains(id),
+            Objects::Memory(store) => store.contains(id),
+        }
+    }
+
+    /// Synchronize all objects written in [batch mode][git_features::fs::Fsync::Batch] to permanent storage, which is a
+    /// no-op if objects are kept in memory.
+    pub fn sync_batch(&self) -> Result<(), loose::write::Error> {
+        match self {
+            Objects::Linked(store) => store.sync_batch(),
+            Objects::Memory(_) => Ok(()),
+        }
+    }
+
+    /// Refresh the stable data like memory maps of packs to reflect the changed state on disk, which is a no-op if
+    /// objects are kept in memory.
+    pub fn refresh(&mut self) -> Result<&mut Self, linked::init::Error> {
+        if let Objects::Linked(store) = self {
+            store.refresh()?;
+        }
+        Ok(self)
+    }
+}
+
+impl Find for Objects {
+    type Error = compound::find::Error;
+
+    fn try_find<'a>(
+        &self,
+        id: impl AsRef<oid>,
+        buffer: &'a mut Vec<u8>,
+        pack_cache: &mut impl git_pack::cache::DecodeEntry,
+    ) -> Result<Option<Object<'a>>, Self::Error> {
+        match self {
+            Objects::Linked(store) => store.try_find(id, buffer, pack_cache),
+            Objects::Memory(store) => store.try_find(id, buffer, pack_cache),
+        }
+    }
+
+    fn location_by_oid(&self, id: impl AsRef<oid>, buf: &mut Vec<u8>) -> Option<Location> {
+        match self {
+            Objects::Linked(store) => store.location_by_oid(id, buf),
+            Objects::Memory(store) => store.location_by_oid(id, buf),
+        }
+    }
+
+    fn bundle_by_pack_id(&self, pack_id: u32) -> Option<&Bundle> {
+        match self {
+            Objects::Linked(store) => store.bundle_by_pack_id(pack_id),
+            Objects::Memory(store) => store.bundle_by_pack_id(pack_id),
+        }
+    }
+
+    fn entry_by_location(&self, location: &Location) -> Option<Entry<'_>> {
+        match self {
+            Objects::Linked(store) => store.entry_by_location(location),
+            Objects::Memory(store) => store.entry_by_location(location),
+        }
+    }
+}
+
+impl Find for &Objects {
+    type Error = compound::find::Error;
+
+    fn try_find<'a>(
+        &self,
+        id: impl AsRef<oid>,
+        buffer: &'a mut Vec<u8>,
+        pack_cache: &mut impl git_pack::cache::DecodeEntry,
+    ) -> Result<Option<Object<'a>>, Self::Error> {
+        (*self).try_find(id, buffer, pack_cache)
+    }
+
+    fn location_by_oid(&self, id: impl AsRef<oid>, buf: &mut Vec<u8>) -> Option<Location> {
+        (*self).location_by_oid(id, buf)
+    }
+
+    fn bundle_by_pack_id(&self, pack_id: u32) -> Option<&Bundle> {
+        (*self).bundle_by_pack_id(pack_id)
+    }
+
+    fn entry_by_location(&self, location: &Location) -> Option<Entry<'_>> {
+        (*self).entry_by_location(location)
+    }
+}
+
+impl Write for Objects {
+    type Error = loose::write::Error;
+
+    fn write_stream(
+        &self,
+        kind: git_object::Kind,
+        size: u64,
+        from: impl Read,
+        hash: git_hash::Kind,
+    ) -> Result<ObjectId, Self::Error> {
+        match self {
+            Objects::Linked(store) => store.write_stream(kind, size, from, hash),
+            Objects::Memory(store) => Ok(store.write_stream(kind, size, from, hash)?),
+        }
+    }
+
+    fn write_stream_if_missing(
+        &self,
+        kind: git_object::Kind,
+        size: u64,
+        from: impl Read,
+        expected_id: Option<&oid>,
+        hash: git_hash::Kind,
+    ) -> Result<Outcome, Self::Error> {
+        match self {
+            Objects::Linked(store) => store.write_stream_if_missing(kind, size, from, expected_id, hash),
+            Objects::Memory(store) => Ok(store.write_stream_if_missing(kind, size, from, expected_id, hash)?),
+        }
+    }
+}
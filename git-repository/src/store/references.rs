@@ -0,0 +1,145 @@
+use std::{convert::TryInto, path::Path};
+
+use git_hash::ObjectId;
+use git_ref::{
+    file::{self, iter::loose_then_packed, ReferenceExt},
+    packed, peel, Namespace, PartialNameRef, Reference,
+};
+
+use crate::store::References;
+
+/// An iterator over the references of a [`References`] store, as returned by [`References::iter()`] and
+/// [`References::iter_prefixed()`].
+pub enum Iter<'p, 's> {
+    /// Loose references first and packed references second.
+    File(file::iter::LooseThenPacked<'p, 's>),
+    /// A snapshot of all references in memory.
+    Memory(git_ref::memory::Iter),
+}
+
+impl<'p, 's> Iterator for Iter<'p, 's> {
+    type Item = Result<Reference, loose_then_packed::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Iter::File(iter) => iter.next(),
+            Iter::Memory(iter) => iter.next().map(Ok),
+        }
+    }
+}
+
+impl References {
+    /// Return the file store if references are kept in the repository directory, or `None` if they are kept in memory.
+    pub fn as_file(&self) -> Option<&file::Store> {
+        match self {
+            References::File(store) => Some(store),
+            References::Memory(_) => None,
+        }
+    }
+
+    /// The namespace used for all reads and edits, if set.
+    pub fn namespace(&self) -> Option<&Namespace> {
+        match self {
+            References::File(store) => store.namespace.as_ref(),
+            References::Memory(store) => store.namespace.as_ref(),
+        }
+    }
+
+    /// Mutable access to the namespace used for all reads and edits.
+    pub fn namespace_mut(&mut self) -> &mut Option<Namespace> {
+        match self {
+            References::File(store) => &mut store.namespace,
+            References::Memory(store) => &mut store.namespace,
+        }
+    }
+
+    /// Open the `packed-refs` file, or return `None` if there is none, which is always the case if references are kept
+    /// in memory.
+    pub fn packed_buffer(&self) -> Result<Option<packed::Buffer>, packed::buffer::open::Error> {
+        match self {
+            References::File(store) => store.packed_buffer(),
+            References::Memory(_) => Ok(None),
+        }
+    }
+
+    /// Find a single reference by the given `partial` name, using `packed` references if references are kept in files.
+    ///
+    /// See [`file::Store::try_find()`] for details.
+    pub fn try_find<'a, Name, E>(
+        &self,
+        partial: Name,
+        packed: Option<&packed::Buffer>,
+    ) -> Result<Option<Reference>, file::find::Error>
+    where
+        Name: TryInto<PartialNameRef<'a>, Error = E>,
+        file::find::Error: From<E>,
+    {
+        match self {
+            References::File(store) => store.try_find(partial, packed),
+            References::Memory(store) => store.try_find(partial),
+        }
+    }
+
+    /// Similar to [`References::try_find()`], but a non-existing reference is treated as error.
+    pub fn find<'a, Name, E>(
+        &self,
+        partial: Name,
+        packed: Option<&packed::Buffer>,
+    ) -> Result<Reference, file::find::existing::Error>
+    where
+        Name: TryInto<PartialNameRef<'a>, Error = E>,
+        git_ref::name::Error: From<E>,
+    {
+        match self {
+            References::File(store) => store.find(partial, packed),
+            References::Memory(store) => store.find(partial),
+        }
+    }
+
+    /// Return an iterator over all references, including `packed` ones if references are kept in files.
+    pub fn iter<'p, 's>(&'s self, packed: Option<&'p packed::Buffer>) -> std::io::Result<Iter<'p, 's>> {
+        Ok(match self {
+            References::File(store) => Iter::File(store.iter(packed)?),
+            References::Memory(store) => Iter::Memory(store.iter()),
+        })
+    }
+
+    /// As [`iter(…)`][References::iter()], but filters by `prefix`, i.e. "refs/heads".
+    pub fn iter_prefixed<'p, 's>(
+        &'s self,
+        packed: Option<&'p packed::Buffer>,
+        prefix: impl AsRef<Path>,
+    ) -> std::io::Result<Iter<'p, 's>> {
+        Ok(match self {
+            References::File(store) => Iter::File(store.iter_prefixed(packed, prefix)?),
+            References::Memory(store) => Iter::Memory(store.iter_prefixed(prefix)),
+        })
+    }
+
+    /// Follow `reference` one level if it is symbolic and return the reference it refers to, or `None` if it isn't symbolic.
+    pub fn follow(
+        &self,
+        reference: &Reference,
+        packed: Option<&packed::Buffer>,
+    ) -> Option<Result<Reference, file::find::existing::Error>> {
+        match self {
+            References::File(store) => reference.follow(store, packed),
+            References::Memory(store) => store.follow(reference),
+        }
+    }
+
+    /// Follow all symbolic targets of `reference` and peel the object it ultimately points to using `find`, and return its id.
+    ///
+    /// See [`ReferenceExt::peel_to_id_in_place()`] for details.
+    pub fn peel_to_id_in_place<E: std::error::Error + Send + Sync + 'static>(
+        &self,
+        reference: &mut Reference,
+        packed: Option<&packed::Buffer>,
+        find: impl FnMut(ObjectId, &mut Vec<u8>) -> Result<Option<(git_object::Kind, &[u8])>, E>,
+    ) -> Result<ObjectId, peel::to_id::Error> {
+        match self {
+            References::File(store) => reference.peel_to_id_in_place(store, packed, find),
+            References::Memory(store) => store.peel_to_id_in_place(reference, find),
+        }
+    }
+}
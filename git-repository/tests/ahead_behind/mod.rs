@@ -0,0 +1,28 @@
+use git_repository::{ahead_behind::Outcome, prelude::ReferenceAccessExt, ObjectId, Repository};
+
+fn ids(repo: &Repository) -> crate::Result<(ObjectId, ObjectId)> {
+    let easy = repo.to_easy();
+    let id = |name: &str| -> crate::Result<_> { Ok(easy.find_reference(name)?.into_fully_peeled_id()?.detach()) };
+    Ok((id("local")?, id("upstream")?))
+}
+
+#[test]
+fn with_commit_graph() -> crate::Result {
+    let repo = crate::repo("make_diverged_repo.sh")?;
+    assert!(repo.objects_dir().join("info").join("commit-graph").is_file());
+    let (local, upstream) = ids(&repo)?;
+    assert_eq!(repo.ahead_behind(local, upstream)?, Outcome { ahead: 3, behind: 4 });
+    assert_eq!(repo.ahead_behind(upstream, local)?, Outcome { ahead: 4, behind: 3 });
+    assert_eq!(repo.ahead_behind(local, local)?, Outcome { ahead: 0, behind: 0 });
+    Ok(())
+}
+
+#[test]
+fn without_commit_graph() -> crate::Result {
+    let (repo, _keep) = crate::repo_rw("make_diverged_repo.sh")?;
+    std::fs::remove_file(repo.objects_dir().join("info").join("commit-graph"))?;
+    let (local, upstream) = ids(&repo)?;
+    assert_eq!(repo.ahead_behind(local, upstream)?, Outcome { ahead: 3, behind: 4 });
+    assert_eq!(repo.ahead_behind(upstream, local)?, Outcome { ahead: 4, behind: 3 });
+    Ok(())
+}
@@ -0,0 +1,204 @@
+use std::path::Path;
+
+use git_features::progress;
+use git_repository::{
+    checkout::{Conflict, Options},
+    easy::object::Kind,
+    objs::{bstr::BString, tree},
+    prelude::{ObjectAccessExt, ReferenceAccessExt},
+    ObjectId, Repository,
+};
+
+fn tree_of(repo: &Repository, name: &str) -> crate::Result<ObjectId> {
+    let repo = repo.to_easy();
+    let id = repo.find_reference(name)?.id().detach();
+    let tree = repo.find_object(id)?.peel_to_kind(Kind::Tree)?.id;
+    Ok(tree)
+}
+
+fn read(repo: &Repository, path: &str) -> crate::Result<BString> {
+    Ok(std::fs::read(work_tree(repo).join(path))?.into())
+}
+
+fn work_tree(repo: &Repository) -> &Path {
+    repo.work_tree.as_deref().expect("non-bare")
+}
+
+/// Writable fixtures are copies which resolve symbolic links, so restore the one written by git.
+fn writable_repo() -> crate::Result<(Repository, tempfile::TempDir)> {
+    let (repo, keep) = crate::repo_rw("make_fast_export_repo.sh")?;
+    #[cfg(unix)]
+    {
+        let link = work_tree(&repo).join("link");
+        std::fs::remove_file(&link)?;
+        std::os::unix::fs::symlink("dir/b", link)?;
+    }
+    Ok((repo, keep))
+}
+
+#[test]
+fn switching_trees_updates_tracked_files_and_removes_the_ones_not_present_anymore() -> crate::Result {
+    let (repo, _keep) = writable_repo()?;
+    let (main, v1) = (tree_of(&repo, "refs/heads/main")?, tree_of(&repo, "refs/tags/v1")?);
+    let outcome = repo.checkout(
+        v1,
+        Options {
+            previous: Some(main),
+            ..Default::default()
+        },
+        progress::Discard,
+    )?;
+    assert!(outcome.conflicts.is_empty(), "{:?}", outcome.conflicts);
+    assert_eq!(outcome.files_written, 2);
+    assert_eq!(outcome.files_removed, 3, "dir/run, link and the file with spaces");
+    assert!(!work_tree(&repo).join("link").exists());
+    assert!(work_tree(&repo).join("dir").is_dir(), "dir/b is still present");
+
+    std::fs::remove_dir_all(work_tree(&repo).join("dir"))?;
+    let outcome = repo.checkout(
+        main,
+        Options {
+            previous: Some(v1),
+            ..Default::default()
+        },
+        progress::Discard,
+    )?;
+    assert!(outcome.conflicts.is_empty(), "{:?}", outcome.conflicts);
+    assert_eq!(outcome.files_written, 5);
+    assert_eq!(outcome.symlinks_written, 1, "the symlink is counted separately as well");
+    assert_eq!(
+        outcome.bytes_written, 33,
+        "the content of all files and the target of the link"
+    );
+    assert_eq!(outcome.files_removed, 0);
+    assert_eq!(read(&repo, "dir/b")?, "b\n");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let link = work_tree(&repo).join("link");
+        assert_eq!(std::fs::read_link(link)?, Path::new("dir/b"));
+        let mode = std::fs::metadata(work_tree(&repo).join("dir/run"))?
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o111, 0o111, "executable bits are set");
+    }
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn untracked_files_are_only_overwritten_if_forced() -> crate::Result {
+    let (repo, _keep) = writable_repo()?;
+    let (main, v1) = (tree_of(&repo, "refs/heads/main")?, tree_of(&repo, "refs/tags/v1")?);
+    let options = Options {
+        previous: Some(v1),
+        ..Default::default()
+    };
+    let outcome = repo.checkout(main, options, progress::Discard)?;
+    assert!(
+        outcome.conflicts.is_empty(),
+        "untracked files which already have the desired content don't conflict"
+    );
+
+    std::fs::write(work_tree(&repo).join("file with spaces"), b"precious")?;
+    std::fs::remove_file(work_tree(&repo).join("a"))?;
+    let outcome = repo.checkout(main, options, progress::Discard)?;
+    assert_eq!(
+        outcome.conflicts,
+        vec![Conflict::UntrackedFile {
+            path: "file with spaces".into()
+        }]
+    );
+    assert_eq!(outcome.files_written, 0);
+    assert!(!work_tree(&repo).join("a").exists(), "nothing is written");
+    assert_eq!(read(&repo, "file with spaces")?, "precious");
+
+    let outcome = repo.checkout(main, Options { force: true, ..options }, progress::Discard)?;
+    assert_eq!(outcome.conflicts.len(), 1, "conflicts are reported even if forced");
+    assert_eq!(outcome.files_written, 5);
+    assert_eq!(read(&repo, "file with spaces")?, "spaces\n");
+    assert_eq!(read(&repo, "a")?, "changed\n");
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn untracked_symlinks_are_not_written_through() -> crate::Result {
+    let (repo, _keep) = writable_repo()?;
+    let main = tree_of(&repo, "refs/heads/main")?;
+    let outside = tempfile::tempdir()?;
+    std::fs::remove_dir_all(work_tree(&repo).join("dir"))?;
+    std::os::unix::fs::symlink(outside.path(), work_tree(&repo).join("dir"))?;
+
+    let outcome = repo.checkout(main, Options::default(), progress::Discard)?;
+    assert_eq!(
+        outcome.conflicts,
+        vec![
+            Conflict::Symlink {
+                path: "dir/b".into(),
+                link: "dir".into()
+            },
+            Conflict::Symlink {
+                path: "dir/run".into(),
+                link: "dir".into()
+            }
+        ]
+    );
+    assert_eq!(std::fs::read_dir(outside.path())?.count(), 0);
+
+    let outcome = repo.checkout(
+        main,
+        Options {
+            force: true,
+            ..Default::default()
+        },
+        progress::Discard,
+    )?;
+    assert_eq!(outcome.conflicts.len(), 2);
+    assert_eq!(std::fs::read_dir(outside.path())?.count(), 0, "the link was replaced");
+    assert!(std::fs::symlink_metadata(work_tree(&repo).join("dir"))?.is_dir());
+    assert_eq!(read(&repo, "dir/b")?, "b\n");
+    Ok(())
+}
+
+#[test]
+fn paths_colliding_on_case_insensitive_filesystems_are_reported() -> crate::Result {
+    let (repo, _keep) = writable_repo()?;
+    let config = repo.git_dir().join("config");
+    let mut content = std::fs::read(&config)?;
+    content.extend_from_slice(b"[core]\n\tignoreCase = true\n");
+    std::fs::write(config, content)?;
+
+    let tree = {
+        let easy = repo.to_easy();
+        let blob = easy
+            .write_object(git_repository::objs::Blob {
+                data: b"changed\n".to_vec(),
+            })?
+            .detach();
+        let entry = |name: &str| tree::Entry {
+            mode: tree::EntryMode::Blob,
+            filename: name.into(),
+            oid: blob,
+        };
+        let tree = git_repository::objs::Tree {
+            entries: vec![entry("A"), entry("a"), entry("b")],
+        };
+        let id = easy.write_object(tree)?.detach();
+        id
+    };
+    let outcome = repo.checkout(tree, Options::default(), progress::Discard)?;
+    assert_eq!(
+        outcome.conflicts,
+        vec![Conflict::Collision {
+            paths: vec!["A".into(), "a".into()]
+        }]
+    );
+    assert_eq!(
+        outcome.collisions().collect::<Vec<_>>(),
+        vec![&[BString::from("A"), BString::from("a")][..]]
+    );
+    assert_eq!(outcome.files_written, 0);
+    assert!(!work_tree(&repo).join("b").exists());
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use git_repository::{
+    commit::amend::{Changes, Error},
+    objs::CommitRef,
+    prelude::{ObjectAccessExt, ReferenceAccessExt},
+    Repository,
+};
+
+const LOCK_MODE: git_repository::lock::acquire::Fail = git_repository::lock::acquire::Fail::Immediately;
+
+fn main_id(repo: &Repository) -> crate::Result<git_repository::ObjectId> {
+    Ok(repo.to_easy().find_reference("refs/heads/main")?.id().detach())
+}
+
+fn decode<T>(repo: &Repository, id: git_repository::ObjectId, f: impl FnOnce(CommitRef<'_>) -> T) -> crate::Result<T> {
+    let repo = repo.to_easy();
+    let object = repo.find_object(id)?;
+    let out = f(CommitRef::from_bytes(&object.data)?);
+    Ok(out)
+}
+
+#[test]
+fn rewording_through_head_keeps_parents_and_tree() -> crate::Result {
+    let (repo, _keep) = crate::repo_rw("make_basic_repo.sh")?;
+    let head = main_id(&repo)?;
+    let outcome = repo.amend(
+        "HEAD",
+        head,
+        Changes {
+            message: Some("reworded\n\nwith body".into()),
+            ..Default::default()
+        },
+        LOCK_MODE,
+    )?;
+    assert_eq!(outcome.previous, head);
+    assert_ne!(outcome.id, head);
+    assert_eq!(main_id(&repo)?, outcome.id, "the branch HEAD points to is updated");
+
+    let (previous_parents, previous_tree) = decode(&repo, head, |c| (c.parents().collect::<Vec<_>>(), c.tree()))?;
+    decode(&repo, outcome.id, |c| {
+        assert_eq!(c.message, "reworded\n\nwith body");
+        assert_eq!(c.parents().collect::<Vec<_>>(), previous_parents);
+        assert_eq!(c.tree(), previous_tree);
+    })?;
+
+    for log in ["logs/HEAD", "logs/refs/heads/main"] {
+        let log = std::fs::read_to_string(repo.git_dir().join(log))?;
+        let last = log.lines().last().expect("at least one line");
+        assert!(last.ends_with("\tcommit (amend): reworded"), "{}", last);
+        assert!(last.starts_with(&format!("{} {}", head, outcome.id)), "{}", last);
+    }
+    Ok(())
+}
+
+#[test]
+fn tree_and_signatures_can_be_replaced() -> crate::Result {
+    let (repo, _keep) = crate::repo_rw("make_basic_repo.sh")?;
+    let head = main_id(&repo)?;
+    let parent_tree = {
+        let parent = decode(&repo, head, |c| c.parents().next().expect("parent"))?;
+        decode(&repo, parent, |c| c.tree())?
+    };
+    let signature = git_repository::actor::Signature {
+        name: "bot".into(),
+        email: "bot@example.com".into(),
+        time: git_repository::actor::Time {
+            time: 42,
+            offset: 0,
+            sign: git_repository::actor::Sign::Plus,
+        },
+    };
+    let outcome = repo.amend(
+        "refs/heads/main",
+        head,
+        Changes {
+            tree: Some(parent_tree),
+            author: Some(signature.clone()),
+            committer: Some(signature.clone()),
+            ..Default::default()
+        },
+        LOCK_MODE,
+    )?;
+    decode(&repo, outcome.id, |c| {
+        assert_eq!(c.tree(), parent_tree);
+        assert_eq!(c.author.to_owned(), signature);
+        assert_eq!(c.committer.to_owned(), signature);
+        assert_eq!(c.message, "c2\n", "unchanged parts are retained");
+    })?;
+    Ok(())
+}
+
+#[test]
+fn amending_a_commit_the_reference_does_not_point_to_fails() -> crate::Result {
+    let (repo, _keep) = crate::repo_rw("make_basic_repo.sh")?;
+    let head = main_id(&repo)?;
+    let parent = decode(&repo, head, |c| c.parents().next().expect("parent"))?;
+    let err = repo.amend("HEAD", parent, Changes::default(), LOCK_MODE).unwrap_err();
+    assert!(
+        matches!(err, Error::ReferenceMoved { expected, actual, .. } if expected == parent && actual == head),
+        "{:?}",
+        err
+    );
+    assert_eq!(main_id(&repo)?, head, "nothing changed");
+    Ok(())
+}
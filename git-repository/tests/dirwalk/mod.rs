@@ -0,0 +1,129 @@
+use git_repository::{
+    dirwalk::{Entry, Kind, Options},
+    open, Repository,
+};
+
+fn repo() -> crate::Result<Repository> {
+    let repo_path = git_testtools::scripted_fixture_repo_read_only("make_dirwalk_repo.sh")?;
+    Ok(Repository::open_opts(repo_path, open::Options::isolated())?)
+}
+
+fn walk(repo: &Repository, options: Options) -> crate::Result<Vec<(String, Kind, bool)>> {
+    Ok(repo
+        .dirwalk(options)?
+        .into_iter()
+        .map(|Entry { path, kind, ignored }| (path.to_string(), kind, ignored))
+        .collect())
+}
+
+fn entries(expected: &[(&str, Kind, bool)]) -> Vec<(String, Kind, bool)> {
+    expected
+        .iter()
+        .map(|(path, kind, ignored)| (path.to_string(), *kind, *ignored))
+        .collect()
+}
+
+#[test]
+fn ignored_paths_git_directories_and_nested_repositories_are_skipped() -> crate::Result {
+    let repo = repo()?;
+    for thread_limit in [None, Some(1)] {
+        assert_eq!(
+            walk(
+                &repo,
+                Options {
+                    thread_limit,
+                    ..Default::default()
+                }
+            )?,
+            entries(&[
+                (".gitignore", Kind::File, false),
+                ("dir/.gitignore", Kind::File, false),
+                ("dir/file", Kind::File, false),
+                ("dir/sub/file", Kind::File, false),
+                ("file", Kind::File, false),
+                ("keep.log", Kind::File, false),
+                ("link", Kind::Symlink, false),
+                ("local", Kind::File, false),
+                ("nested", Kind::Repository, false),
+            ]),
+            "the result doesn't depend on the amount of threads"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn ignored_paths_can_be_emitted_without_entering_ignored_directories() -> crate::Result {
+    let repo = repo()?;
+    let ignored: Vec<_> = walk(
+        &repo,
+        Options {
+            emit_ignored: true,
+            ..Default::default()
+        },
+    )?
+    .into_iter()
+    .filter(|e| e.2)
+    .collect();
+    assert_eq!(
+        ignored,
+        entries(&[
+            ("a.log", Kind::File, true),
+            ("dir/local", Kind::File, true),
+            ("dir/sub/x.tmp", Kind::File, true),
+            ("excluded", Kind::File, true),
+            ("target", Kind::Directory, true),
+        ])
+    );
+    Ok(())
+}
+
+#[test]
+fn ignore_files_can_be_disregarded() -> crate::Result {
+    let repo = repo()?;
+    let paths: Vec<_> = walk(
+        &repo,
+        Options {
+            respect_ignore: false,
+            ..Default::default()
+        },
+    )?
+    .into_iter()
+    .map(|e| e.0)
+    .collect();
+    assert!(paths.iter().all(|p| !p.starts_with(".git/")));
+    for path in ["a.log", "dir/local", "excluded", "target/debug/bin"] {
+        assert!(paths.iter().any(|p| p == path), "{} is present", path);
+    }
+    Ok(())
+}
+
+#[test]
+fn the_configured_excludes_file_applies_to_all_directories() -> crate::Result {
+    let (_repo, keep) = crate::repo_rw("make_dirwalk_repo.sh")?;
+    let excludes = keep.path().join("excludes");
+    std::fs::write(&excludes, "file\n")?;
+    let config = keep.path().join(".git").join("config");
+    let mut content = std::fs::read_to_string(&config)?;
+    content.push_str(&format!("[core]\n\texcludesFile = {}\n", excludes.display()));
+    std::fs::write(config, content)?;
+
+    let repo = Repository::open_opts(keep.path(), open::Options::isolated())?;
+    let paths: Vec<_> = walk(&repo, Options::default())?.into_iter().map(|e| e.0).collect();
+    assert!(!paths
+        .iter()
+        .any(|p| p == "file" || p == "dir/file" || p == "dir/sub/file"));
+    assert!(paths.iter().any(|p| p == "excludes"));
+    Ok(())
+}
+
+#[test]
+fn bare_repositories_have_nothing_to_walk() -> crate::Result {
+    let repo = crate::repo("make_basic_repo.sh")?;
+    let bare = Repository::open(repo.work_tree.expect("non-bare").join("bare.git"))?;
+    assert!(matches!(
+        bare.dirwalk(Options::default()),
+        Err(git_repository::dirwalk::Error::NoWorkTree)
+    ));
+    Ok(())
+}
@@ -99,3 +99,144 @@ mod existing {
         git_testtools::scripted_fixture_repo_read_only("make_basic_repo.sh")
     }
 }
+
+mod dot_git_indirections {
+    use std::path::{Path, PathBuf};
+
+    use git_repository::{
+        path::{is, resolve},
+        Kind,
+    };
+
+    fn write_gitfile(dir: &Path, target: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(dir.join(".git"), format!("gitdir: {}\n", target.as_ref().display()))
+    }
+
+    #[test]
+    fn gitfile_yields_work_tree_next_to_it_and_git_dir_it_points_to() -> crate::Result {
+        let tmp = tempfile::tempdir()?;
+        let work_tree = tmp.path().join("worktree");
+        write_gitfile(&work_tree, git_dir()?)?;
+        std::fs::create_dir(work_tree.join("subdir"))?;
+
+        let path = git_repository::path::discover::existing(work_tree.join("subdir"))?;
+        assert_eq!(path.kind(), Kind::WorkTree, "gitfiles always indicate a work tree");
+
+        let repo = git_repository::discover(&work_tree)?;
+        assert_eq!(repo.git_dir(), git_dir()?);
+        assert_eq!(repo.work_tree.as_deref(), Some(work_tree.as_path()));
+        Ok(())
+    }
+
+    #[test]
+    fn chained_gitfiles_with_relative_paths_are_followed() -> crate::Result {
+        let tmp = tempfile::tempdir()?;
+        write_gitfile(&tmp.path().join("a"), Path::new("..").join("b").join(".git"))?;
+        write_gitfile(&tmp.path().join("b"), git_dir()?)?;
+
+        assert_eq!(resolve::git_dir(tmp.path().join("a").join(".git"))?, git_dir()?);
+        assert_eq!(git_repository::discover(tmp.path().join("a"))?.git_dir(), git_dir()?);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn symlinked_git_dir_is_followed() -> crate::Result {
+        let tmp = tempfile::tempdir()?;
+        let work_tree = tmp.path().join("worktree");
+        std::fs::create_dir(&work_tree)?;
+        std::os::unix::fs::symlink(git_dir()?, work_tree.join(".git"))?;
+
+        let repo = git_repository::discover(&work_tree)?;
+        assert_eq!(repo.git_dir(), git_dir()?);
+        assert_eq!(repo.work_tree.as_deref(), Some(work_tree.as_path()));
+        Ok(())
+    }
+
+    #[test]
+    fn cycles_are_detected_instead_of_searching_parent_directories() -> crate::Result {
+        let tmp = tempfile::tempdir()?;
+        write_gitfile(&tmp.path().join("a"), Path::new("..").join("b").join(".git"))?;
+        write_gitfile(&tmp.path().join("b"), Path::new("..").join("a").join(".git"))?;
+
+        let err = git_repository::path::discover::existing(tmp.path().join("a")).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                git_repository::path::discover::existing::Error::InvalidDotGit {
+                    source: is::Error::Resolve(resolve::Error::Cycle { .. }),
+                    ..
+                }
+            ),
+            "{:?}",
+            err
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn gitfiles_pointing_to_non_repositories_are_errors_instead_of_searching_parent_directories() -> crate::Result {
+        let tmp = tempfile::tempdir()?;
+        std::fs::create_dir(tmp.path().join("empty"))?;
+        write_gitfile(&tmp.path().join("worktree"), Path::new("..").join("empty"))?;
+
+        let err = git_repository::path::discover::existing(tmp.path().join("worktree")).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                git_repository::path::discover::existing::Error::InvalidDotGit {
+                    source: is::Error::FindHeadRef(_),
+                    ..
+                }
+            ),
+            "{:?}",
+            err
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn chains_longer_than_the_maximum_depth_fail() -> crate::Result {
+        let tmp = tempfile::tempdir()?;
+        for idx in 0..resolve::MAX_DEPTH {
+            write_gitfile(
+                &tmp.path().join(idx.to_string()),
+                Path::new("..").join((idx + 1).to_string()).join(".git"),
+            )?;
+        }
+        write_gitfile(&tmp.path().join(resolve::MAX_DEPTH.to_string()), git_dir()?)?;
+
+        assert!(
+            matches!(resolve::git_dir(tmp.path().join("1").join(".git")), Ok(_)),
+            "exactly the maximum amount of indirections is fine"
+        );
+        assert!(matches!(
+            resolve::git_dir(tmp.path().join("0").join(".git")),
+            Err(resolve::Error::TooDeep {
+                max_depth: resolve::MAX_DEPTH,
+                ..
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn files_without_gitdir_line_are_invalid() -> crate::Result {
+        let tmp = tempfile::tempdir()?;
+        std::fs::write(tmp.path().join(".git"), "not a gitfile")?;
+        assert!(matches!(
+            git_repository::open(tmp.path()),
+            Err(git_repository::open::Error::NotARepository(is::Error::Resolve(
+                resolve::Error::InvalidGitFile { .. }
+            )))
+        ));
+        Ok(())
+    }
+
+    fn git_dir() -> crate::Result<PathBuf> {
+        Ok(git_testtools::scripted_fixture_repo_read_only("make_basic_repo.sh")?
+            .join(".git")
+            .canonicalize()?)
+    }
+}
@@ -0,0 +1,33 @@
+use std::sync::atomic::AtomicBool;
+
+use git_repository::{prelude::ReferenceAccessExt, Repository};
+
+#[test]
+fn borrowed_objects_are_packed_and_alternates_are_removed() -> crate::Result {
+    let (repo, keep) = crate::repo_rw("make_alternates_repo.sh")?;
+    let alternates = repo.objects_dir().join("info").join("alternates");
+    assert!(alternates.is_file());
+
+    let outcome = repo.dissociate(1, &AtomicBool::new(false))?;
+    assert_eq!(
+        outcome.packed, 7,
+        "two commits with their trees and blobs as well as the annotated tag, but not the unreachable branch"
+    );
+    assert_eq!(outcome.alternates.len(), 1);
+    assert!(!alternates.exists(), "the alternates file is removed once empty");
+
+    std::fs::remove_dir_all(keep.path().join("reference"))?;
+    let repo = Repository::discover(keep.path())?;
+    let easy = repo.to_easy();
+    let head = easy.head()?.into_fully_peeled_id().expect("born")?;
+    assert_eq!(
+        head.ancestors()?.all().collect::<Result<Vec<_>, _>>()?.len(),
+        3,
+        "all commits are available without the reference repository"
+    );
+    easy.find_reference("v1")?.into_fully_peeled_id()?;
+
+    let outcome = repo.dissociate(1, &AtomicBool::new(false))?;
+    assert_eq!(outcome.packed, 0, "nothing to do without alternates");
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use std::convert::TryInto;
+
+use git_repository::{prelude::ReferenceAccessExt, refs::transaction::PreviousValue};
+
+#[test]
+fn create_rename_and_delete() -> crate::Result {
+    let (repo, _keep) = crate::basic_rw_repo()?;
+    let head_id = repo.head()?.into_fully_peeled_id().expect("born")?.detach();
+    let branches = repo.branches();
+
+    let branch = branches.create("feature", head_id, PreviousValue::MustNotExist)?;
+    assert_eq!(branch.name(), "refs/heads/feature".try_into()?);
+    assert!(
+        branches
+            .create(
+                "feature",
+                git_repository::ObjectId::empty_tree(git_repository::hash::Kind::Sha1),
+                PreviousValue::MustNotExist
+            )
+            .is_err(),
+        "existing branches are not overwritten with a different value"
+    );
+
+    branches.set_upstream("feature", ".", "refs/heads/main")?;
+    let renamed = branches.rename("feature", "renamed", PreviousValue::MustNotExist)?;
+    assert_eq!(renamed.name(), "refs/heads/renamed".try_into()?);
+    assert!(repo.try_find_reference("feature")?.is_none(), "the old branch is gone");
+
+    let renamed = repo.find_reference("renamed")?;
+    let mut logs = renamed.logs()?;
+    let messages: Vec<_> = logs
+        .iter()?
+        .expect("log exists")
+        .map(|line| line.map(|line| line.message.to_owned()))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(
+        messages,
+        vec![
+            format!("branch: Created from {}", head_id),
+            "Branch: renamed refs/heads/feature to refs/heads/renamed".into()
+        ],
+        "the reflog is carried over and extended"
+    );
+    assert_eq!(
+        branches.upstream("feature")?,
+        None,
+        "the configuration moved with the branch"
+    );
+    assert!(branches.upstream("renamed")?.is_some());
+
+    branches.delete("renamed")?;
+    assert!(repo.try_find_reference("renamed")?.is_none());
+    assert_eq!(
+        branches.upstream("renamed")?,
+        None,
+        "the configuration is removed as well"
+    );
+    assert!(
+        branches.delete("renamed").is_err(),
+        "the branch must exist to be deleted"
+    );
+    Ok(())
+}
+
+#[test]
+fn rename_of_checked_out_branch_updates_head_and_delete_is_refused() -> crate::Result {
+    let (repo, _keep) = crate::basic_rw_repo()?;
+    let branches = repo.branches();
+    assert!(
+        matches!(
+            branches.delete("main"),
+            Err(git_repository::easy::branch::delete::Error::CheckedOut { .. })
+        ),
+        "HEAD points to main"
+    );
+
+    branches.rename("main", "trunk", PreviousValue::MustNotExist)?;
+    assert_eq!(
+        repo.head()?.referent_name().expect("symbolic"),
+        "refs/heads/trunk".try_into()?
+    );
+    Ok(())
+}
+
+#[test]
+fn upstream_and_ahead_behind() -> crate::Result {
+    let (repo, _keep) = crate::basic_rw_repo()?;
+    let branches = repo.branches();
+    assert_eq!(branches.upstream("main")?, None);
+    assert_eq!(branches.ahead_behind("main")?, None, "no upstream, nothing to compare");
+
+    let c2 = repo.head()?.into_fully_peeled_id().expect("born")?;
+    let c1 = c2.ancestors()?.all().nth(1).expect("parent")?.detach();
+    branches.create("old", c1, PreviousValue::MustNotExist)?;
+    branches.set_upstream("main", ".", "refs/heads/old")?;
+
+    let upstream = branches.upstream("main")?.expect("set");
+    assert_eq!(upstream.remote, ".");
+    assert_eq!(upstream.merge.as_bstr(), "refs/heads/old");
+    assert_eq!(
+        upstream.tracking,
+        Some(upstream.merge.clone()),
+        "local upstreams track themselves"
+    );
+    assert_eq!(
+        branches.ahead_behind("main")?,
+        Some(git_repository::ahead_behind::Outcome { ahead: 1, behind: 0 })
+    );
+
+    branches.set_upstream("old", ".", "refs/heads/main")?;
+    assert_eq!(
+        branches.ahead_behind("old")?,
+        Some(git_repository::ahead_behind::Outcome { ahead: 0, behind: 1 })
+    );
+    Ok(())
+}
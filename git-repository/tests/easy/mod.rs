@@ -1,5 +1,7 @@
 mod access;
+mod branch;
 mod ext;
 mod object;
 mod oid;
 mod reference;
+mod watch;
@@ -17,3 +17,89 @@ fn oid_size_in_memory() {
         "the size of this structure should not changed unexpectedly"
     )
 }
+
+mod tree {
+    use git_repository::{easy::object::tree::Cache, objs::tree::EntryMode, prelude::*};
+
+    fn repo() -> crate::Result<git_repository::Easy> {
+        Ok(crate::repo("make_fast_export_repo.sh")?.into_easy())
+    }
+
+    fn tree_of(repo: &git_repository::Easy, reference: &str) -> crate::Result<git_repository::ObjectId> {
+        let commit = repo.find_reference(reference)?.id().detach();
+        let commit = repo.find_object(commit)?;
+        let tree = git_repository::objs::CommitRef::from_bytes(&commit.data)?.tree();
+        Ok(tree)
+    }
+
+    #[test]
+    fn lookup_entry_follows_slash_separated_paths() -> crate::Result {
+        let repo = repo()?;
+        let tree = tree_of(&repo, "refs/heads/other")?;
+        let lookup = |path: &str| -> crate::Result<Option<EntryMode>> {
+            Ok(repo
+                .find_object(tree)?
+                .into_tree()
+                .lookup_entry(path)?
+                .map(|entry| entry.mode))
+        };
+        assert_eq!(lookup("dir/sub/c")?, Some(EntryMode::Blob));
+        assert_eq!(lookup("dir/run")?, Some(EntryMode::BlobExecutable));
+        assert_eq!(
+            lookup("/dir//sub/")?,
+            Some(EntryMode::Tree),
+            "empty components are ignored"
+        );
+        assert_eq!(lookup("dir/b/c")?, None, "blobs have no entries");
+        assert_eq!(lookup("dir/missing")?, None);
+        assert_eq!(lookup("")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn lookup_entry_cached_remembers_resolved_components() -> crate::Result {
+        let repo = repo()?;
+        let mut cache = Cache::default();
+        let (other, main) = (tree_of(&repo, "refs/heads/other")?, tree_of(&repo, "refs/heads/main")?);
+        let lookup = |tree, path: &str, cache: &mut Cache| -> crate::Result<Option<git_repository::ObjectId>> {
+            Ok(repo
+                .find_object(tree)?
+                .into_tree()
+                .lookup_entry_cached(path, cache)?
+                .map(|entry| entry.oid))
+        };
+
+        let c = lookup(other, "dir/sub/c", &mut cache)?;
+        assert_eq!(c, lookup(other, "dir/sub/c", &mut Cache::default())?);
+        assert_eq!(cache.len(), 3, "one entry per component");
+        assert_eq!(lookup(other, "dir/sub/c", &mut cache)?, c);
+        assert_eq!(cache.len(), 3, "everything was served from the cache");
+
+        assert_eq!(lookup(main, "dir/sub/c", &mut cache)?, None, "removed on main");
+        assert!(lookup(main, "dir/run", &mut cache)?.is_some());
+        assert_eq!(
+            lookup(main, "link", &mut cache)?,
+            repo.find_object(main)?.into_tree().lookup_entry("link")?.map(|e| e.oid)
+        );
+
+        cache.clear();
+        assert!(cache.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn lookup_fails_on_corrupt_trees_instead_of_skipping_entries() -> crate::Result {
+        let repo = repo()?;
+        let data = std::cell::RefCell::new(b"100644 a\0short".to_vec());
+        let tree = git_repository::easy::TreeRef::from_id_and_data(
+            git_repository::ObjectId::null_sha1(),
+            std::cell::Ref::map(data.borrow(), |v| v.as_slice()),
+            &repo,
+        );
+        assert!(matches!(
+            tree.lookup_entry("b"),
+            Err(git_repository::easy::object::tree::lookup::Error::Decode(_))
+        ));
+        Ok(())
+    }
+}
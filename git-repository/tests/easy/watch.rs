@@ -0,0 +1,75 @@
+use git_repository as git;
+use git_repository::prelude::{ReferenceAccessExt, RepositoryAccessExt};
+
+#[test]
+fn changes_are_classified_by_the_cached_state_they_affect() -> crate::Result {
+    use git::easy::watch::Change;
+
+    let repo = crate::repo("make_references_repo.sh")?;
+    // Watchers report absolute paths
+    let objects = repo.objects_dir().canonicalize()?;
+    for (path, expected) in [
+        (
+            repo.git_dir().canonicalize()?.join("packed-refs"),
+            Some(Change::PackedRefs),
+        ),
+        ("packed-refs".into(), Some(Change::PackedRefs)),
+        ("packed-refs.lock".into(), None),
+        ("refs/heads/main".into(), None),
+        (objects.join("pack").join("pack-abc.idx"), Some(Change::ObjectDatabase)),
+        (objects.join("pack").join("pack-abc.pack"), Some(Change::ObjectDatabase)),
+        (
+            objects.join("pack").join("multi-pack-index"),
+            Some(Change::ObjectDatabase),
+        ),
+        (objects.join("pack").join("tmp_pack_abc"), None),
+        (objects.join("info").join("alternates"), Some(Change::ObjectDatabase)),
+        (objects.join("ab").join("cdef"), None),
+        (std::path::PathBuf::from("/elsewhere/packed-refs"), None),
+    ]
+    .iter()
+    {
+        assert_eq!(Change::classify(&repo, path), *expected, "{:?}", path);
+    }
+    Ok(())
+}
+
+#[test]
+fn packed_refs_are_reloaded_even_if_their_modification_time_is_unchanged() -> crate::Result {
+    let (repo, _keep) = crate::repo_rw("make_references_repo.sh")?;
+    let packed_refs_path = repo.git_dir().canonicalize()?.join("packed-refs");
+    let repo = repo.into_easy_arc_exclusive();
+    let original = repo.find_reference("dt1")?.target().as_id().expect("peeled").to_owned();
+
+    let modified = std::fs::metadata(&packed_refs_path)?.modified()?;
+    let packed_refs = std::fs::read_to_string(&packed_refs_path)?;
+    let other = git::ObjectId::empty_tree(git::hash::Kind::Sha1);
+    std::fs::write(
+        &packed_refs_path,
+        packed_refs.replace(
+            &format!("{} refs/tags/dt1", original),
+            &format!("{} refs/tags/dt1", other),
+        ),
+    )?;
+    std::fs::File::options()
+        .write(true)
+        .open(&packed_refs_path)?
+        .set_modified(modified)?;
+    assert_eq!(
+        repo.find_reference("dt1")?.target().as_id().expect("peeled"),
+        original,
+        "the change isn't visible as the modification time is the same"
+    );
+
+    let outcome = repo.apply_changed_paths(Some(&packed_refs_path))?;
+    assert!(outcome.packed_refs_invalidated);
+    assert!(!outcome.object_database_refreshed);
+    assert_eq!(repo.find_reference("dt1")?.target().as_id().expect("peeled"), other);
+
+    let outcome = repo.apply_changed_paths(Some("objects/pack/pack-abc.idx"))?;
+    assert!(
+        outcome.object_database_refreshed,
+        "exclusive handles can refresh the object database"
+    );
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+
+use git_repository::{
+    fast_import::{decode, Command},
+    objs::{bstr::BString, CommitRef},
+    prelude::{ObjectAccessExt, ReferenceAccessExt},
+    ObjectId, Repository,
+};
+
+const LOCK_MODE: git_repository::lock::acquire::Fail = git_repository::lock::acquire::Fail::Immediately;
+const REFS: [&str; 4] = [
+    "refs/heads/main",
+    "refs/heads/other",
+    "refs/tags/lightweight",
+    "refs/tags/v1",
+];
+
+fn peeled_references(repo: &Repository) -> crate::Result<BTreeMap<BString, ObjectId>> {
+    let repo = repo.to_easy();
+    let platform = repo.references()?;
+    let mut out = BTreeMap::new();
+    for reference in platform.all()? {
+        let reference = reference.expect("valid reference");
+        if let Some(id) = reference.target().as_id() {
+            out.insert(reference.name().as_bstr().to_owned(), id.to_owned());
+        }
+    }
+    Ok(out)
+}
+
+fn empty_repo() -> crate::Result<(Repository, tempfile::TempDir)> {
+    let dir = tempfile::tempdir()?;
+    let repo = git_repository::init_bare(dir.path())?;
+    Ok((repo, dir))
+}
+
+#[test]
+fn export_and_import_round_trips_to_identical_objects() -> crate::Result {
+    let source = crate::repo("make_fast_export_repo.sh")?;
+    let mut stream = Vec::new();
+    let outcome = source.fast_export(REFS, &mut stream)?;
+    assert_eq!(outcome.commits, 4);
+    assert_eq!(outcome.tags, 1);
+    assert_eq!(outcome.blobs, 7, "each distinct blob is exported only once");
+
+    let (repo, _keep) = empty_repo()?;
+    let imported = repo.fast_import(&stream[..], LOCK_MODE)?;
+    assert_eq!(imported.commits, outcome.commits);
+    assert_eq!(imported.blobs, outcome.blobs);
+    assert_eq!(imported.tags, outcome.tags);
+
+    let expected: BTreeMap<_, _> = peeled_references(&source)?
+        .into_iter()
+        .filter(|(name, _)| name != "HEAD")
+        .collect();
+    assert_eq!(
+        imported.references, expected,
+        "all references point to the same objects after the round-trip"
+    );
+    assert_eq!(peeled_references(&repo)?, expected);
+    Ok(())
+}
+
+#[test]
+fn streams_written_by_git_can_be_imported() -> crate::Result {
+    let source = crate::repo("make_fast_export_repo.sh")?;
+    let stream = std::fs::read(source.git_dir().join("fast-export.stream"))?;
+
+    let (repo, _keep) = empty_repo()?;
+    repo.fast_import(&stream[..], LOCK_MODE)?;
+    let (expected, actual) = (peeled_references(&source)?, peeled_references(&repo)?);
+    for name in REFS {
+        let name = BString::from(name);
+        assert_eq!(actual.get(&name), expected.get(&name), "{}", name);
+    }
+    Ok(())
+}
+
+#[test]
+fn import_continues_existing_branches_and_applies_all_file_changes() -> crate::Result {
+    let (repo, _keep) = crate::repo_rw("make_basic_repo.sh")?;
+    let previous_tip = repo.to_easy().find_reference("refs/heads/main")?.id().detach();
+    let stream = b"blob
+mark :1
+data 6
+hello
+
+commit refs/heads/main
+mark :2
+committer committer <c@example.com> 946771200 +0000
+data <<END
+a message
+with two lines
+END
+M 644 :1 \"quoted \\\"name\\\"\"
+M 755 inline dir/script
+data 3
+run
+C dir dir-copy
+R this renamed
+D dir/script
+
+reset refs/heads/copy
+from :2
+
+done
+blob
+data 0
+";
+    let outcome = repo.fast_import(&stream[..], LOCK_MODE)?;
+    assert_eq!(outcome.commits, 1);
+    assert_eq!(
+        outcome.blobs, 2,
+        "inline data counts as blob, the blob after 'done' isn't read anymore"
+    );
+    assert_eq!(outcome.references.len(), 2);
+
+    let commit_id = outcome.marks[&2];
+    assert_eq!(outcome.references[&BString::from("refs/heads/main")], commit_id);
+    assert_eq!(outcome.references[&BString::from("refs/heads/copy")], commit_id);
+
+    let repo = repo.into_easy();
+    let tree_id = {
+        let commit = repo.find_object(commit_id)?;
+        let commit = CommitRef::from_bytes(&commit.data)?;
+        assert_eq!(commit.message, "a message\nwith two lines\n");
+        assert_eq!(
+            commit.parents().collect::<Vec<_>>(),
+            vec![previous_tip],
+            "the branch is continued"
+        );
+        assert_eq!(commit.author, commit.committer, "the author defaults to the committer");
+        commit.tree()
+    };
+
+    let mut paths = Vec::new();
+    for path in ["quoted \"name\"", "dir-copy/script", "renamed", "this", "dir/script"] {
+        let tree = repo.find_object(tree_id)?.into_tree();
+        paths.push((path, tree.lookup_path(path.split('/'))?.is_some()));
+    }
+    assert_eq!(
+        paths,
+        vec![
+            ("quoted \"name\"", true),
+            ("dir-copy/script", true),
+            ("renamed", true),
+            ("this", false),
+            ("dir/script", false)
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn commands_round_trip_through_their_serialization() -> crate::Result {
+    let source = crate::repo("make_fast_export_repo.sh")?;
+    let stream = std::fs::read(source.git_dir().join("fast-export.stream"))?;
+    let commands = decode::Iter::new(&stream[..]).collect::<Result<Vec<_>, _>>()?;
+    assert!(commands.iter().any(|c| matches!(c, Command::Tag(_))));
+    assert!(commands
+        .iter()
+        .any(|c| matches!(c, Command::Commit(c) if c.merges.len() == 1)));
+
+    let mut written = Vec::new();
+    for command in &commands {
+        command.write_to(&mut written)?;
+    }
+    assert_eq!(
+        decode::Iter::new(&written[..]).collect::<Result<Vec<_>, _>>()?,
+        commands
+    );
+    Ok(())
+}
+
+#[test]
+fn decode_errors_carry_line_numbers() {
+    let err = decode::Iter::new(&b"blob\nmark :1\ndata 1\na\nunknown\n"[..])
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_err();
+    assert!(
+        matches!(err, decode::Error::Unsupported { line_number: 5, .. }),
+        "{:?}",
+        err
+    );
+}
@@ -0,0 +1,67 @@
+use git_repository::{bstr::ByteSlice, fetch_head::Entry, ObjectId};
+
+fn entry(name: &str, for_merge: bool) -> Entry {
+    Entry {
+        id: ObjectId::null_sha1(),
+        name: name.into(),
+        for_merge,
+    }
+}
+
+#[test]
+fn description_matches_git() {
+    let url = "../src/.git/".as_bytes().as_bstr();
+    for (name, expected) in &[
+        ("HEAD", "../src/"),
+        ("refs/heads/main", "branch 'main' of ../src/"),
+        ("refs/tags/v1", "tag 'v1' of ../src/"),
+        ("refs/remotes/origin/x", "remote-tracking branch 'origin/x' of ../src/"),
+        ("refs/other/y", "'refs/other/y' of ../src/"),
+    ] {
+        assert_eq!(entry(name, true).description(url), *expected);
+    }
+    assert_eq!(
+        entry("HEAD", true).description("https://example.com/repo//".as_bytes().as_bstr()),
+        "https://example.com/repo"
+    );
+    assert_eq!(
+        entry("HEAD", true).description("a.git".as_bytes().as_bstr()),
+        "a.git",
+        "too short urls are kept"
+    );
+}
+
+#[test]
+fn entries_for_merge_come_first() -> crate::Result {
+    let mut buf = Vec::new();
+    git_repository::fetch_head::encode(
+        &[
+            entry("refs/heads/dev", false),
+            entry("refs/heads/main", true),
+            entry("refs/tags/v1", false),
+        ],
+        "https://example.com/repo.git".as_bytes().as_bstr(),
+        &mut buf,
+    )?;
+    assert_eq!(
+        buf.as_bstr(),
+        "0000000000000000000000000000000000000000\t\tbranch 'main' of https://example.com/repo\n\
+         0000000000000000000000000000000000000000\tnot-for-merge\tbranch 'dev' of https://example.com/repo\n\
+         0000000000000000000000000000000000000000\tnot-for-merge\ttag 'v1' of https://example.com/repo\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn write_fetch_head_replaces_the_file() -> crate::Result {
+    let (repo, _keep) = crate::repo_rw("make_basic_repo.sh")?;
+    let url = "https://example.com/repo".as_bytes().as_bstr();
+    repo.write_fetch_head(&[entry("refs/heads/dev", false)], url)?;
+    let path = repo.write_fetch_head(&[entry("HEAD", true)], url)?;
+    assert_eq!(path, repo.git_dir().join("FETCH_HEAD"));
+    assert_eq!(
+        std::fs::read(path)?.as_bstr(),
+        "0000000000000000000000000000000000000000\t\thttps://example.com/repo\n"
+    );
+    Ok(())
+}
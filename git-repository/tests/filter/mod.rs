@@ -0,0 +1,40 @@
+use git_repository::filter::Smudge;
+
+fn smudge(autocrlf: bool, input: &[u8]) -> crate::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let written = Smudge { autocrlf }.apply(input, &mut out)?;
+    assert_eq!(written, out.len() as u64);
+    Ok(out)
+}
+
+#[test]
+fn line_feeds_of_text_are_converted_with_autocrlf() -> crate::Result {
+    assert_eq!(smudge(true, b"a\nb\r\nc\n")?, b"a\r\nb\r\nc\r\n");
+    assert_eq!(smudge(false, b"a\nb\n")?, b"a\nb\n", "no conversion is configured");
+    Ok(())
+}
+
+#[test]
+fn line_feeds_are_converted_across_chunks() -> crate::Result {
+    let input = "line\n".repeat(4000);
+    assert_eq!(smudge(true, input.as_bytes())?, "line\r\n".repeat(4000).into_bytes());
+    Ok(())
+}
+
+#[test]
+fn binary_input_is_left_as_is() -> crate::Result {
+    assert_eq!(smudge(true, b"a\0\nb\n")?, b"a\0\nb\n");
+    Ok(())
+}
+
+#[test]
+fn autocrlf_is_read_from_the_configuration() -> crate::Result {
+    let (repo, _keep) = crate::repo_rw("make_basic_repo.sh")?;
+    assert_eq!(repo.smudge_filter()?, Smudge::default());
+    std::process::Command::new("git")
+        .args(&["config", "core.autocrlf", "true"])
+        .current_dir(repo.git_dir())
+        .status()?;
+    assert_eq!(repo.smudge_filter()?, Smudge { autocrlf: true });
+    Ok(())
+}
@@ -0,0 +1,28 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q reference
+(cd reference
+  git config commit.gpgsign false
+  git checkout -q -b main
+  touch this
+  git add this
+  git commit -q -m c1
+  echo hello >> this
+  git commit -q -am c2
+  git tag -a -m "annotated" v1
+  git checkout -q -b other HEAD~1
+  echo other > that
+  git add that
+  git commit -q -m c3
+)
+
+git init -q
+git config commit.gpgsign false
+echo ../../reference/.git/objects > .git/objects/info/alternates
+git update-ref refs/heads/main "$(git -C reference rev-parse main)"
+git update-ref refs/tags/v1 "$(git -C reference rev-parse v1)"
+git checkout -q main
+echo local > local
+git add local
+git commit -q -m local
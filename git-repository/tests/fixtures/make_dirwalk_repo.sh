@@ -0,0 +1,15 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+printf '*.log\n!keep.log\ntarget/\n' > .gitignore
+mkdir -p dir/sub target/debug
+touch a.log keep.log file local excluded dir/file dir/local dir/sub/file dir/sub/x.tmp target/debug/bin
+printf '/local\n*.tmp\n' > dir/.gitignore
+echo excluded >> .git/info/exclude
+ln -s dir link
+
+git init -q nested
+touch nested/file
@@ -0,0 +1,21 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+git checkout -q -b main
+for i in 1 2 3; do git commit -q --allow-empty -m c$i; done
+
+git checkout -q -b local
+for i in 1 2 3 4; do git commit -q --allow-empty -m l$i; done
+
+git checkout -q -b upstream main
+for i in 1 2; do git commit -q --allow-empty -m u$i; done
+git merge -q --no-ff -m "merge l2" local~2
+
+git commit-graph write --no-progress --reachable
+
+git commit -q --allow-empty -m "u3 after commit-graph"
+git checkout -q local
+git commit -q --allow-empty -m "l5 after commit-graph"
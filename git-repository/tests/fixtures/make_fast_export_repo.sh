@@ -0,0 +1,32 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+git checkout -q -b main
+mkdir -p dir/sub
+echo a > a
+echo b > dir/b
+echo c > dir/sub/c
+git add .
+git commit -q -m c1
+
+git checkout -q -b other
+echo executable > dir/run
+chmod +x dir/run
+ln -s dir/b link
+echo spaces > "file with spaces"
+git add .
+git commit -q -m c2
+
+git checkout -q main
+git rm -q dir/sub/c
+echo changed > a
+git commit -q -am c3
+git merge -q --no-ff -m merge other
+
+git tag -m "annotated tag" v1 HEAD~1
+git tag lightweight HEAD
+
+git fast-export --all --signed-tags=strip > .git/fast-export.stream
@@ -0,0 +1,39 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+
+git checkout -q -b main
+mkdir dir
+seq 1 100 > dir/a
+echo other > other
+git add .
+git commit -q -m "add dir/a"
+
+seq 101 110 >> dir/a
+git commit -q -am "modify dir/a"
+
+git mv dir/a b
+git commit -q -m "rename dir/a to b"
+
+git mv b c
+seq 111 115 >> c
+git commit -q -am "rename b to c with changes"
+
+echo changed > other
+git commit -q -am "change other"
+
+seq 116 120 >> c
+git commit -q -am "modify c"
+
+git rm -q other
+seq 1 100 > new
+git add new
+git commit -q -m "replace other with unrelated new"
+
+git rm -q new
+mkdir sub
+echo x > sub/x
+git add sub
+git commit -q -m "replace new with sub/x"
@@ -0,0 +1,62 @@
+use git_repository::{follow, prelude::*};
+
+fn follow(path: &str) -> crate::Result<Vec<(String, String, Option<String>)>> {
+    let easy = crate::repo("make_rename_repo.sh")?.into_easy();
+    let head = easy.head()?.into_fully_peeled_id().expect("born")?.detach();
+    let repo = crate::repo("make_rename_repo.sh")?;
+    repo.follow(head, path, follow::Options::default())
+        .map(|item| {
+            let item = item?;
+            let message = easy.find_object(item.id)?.to_commit().message.to_string();
+            Ok((
+                message.trim().to_owned(),
+                item.path.to_string(),
+                item.renamed_from.map(|p| p.to_string()),
+            ))
+        })
+        .collect()
+}
+
+#[test]
+fn files_are_followed_through_exact_and_inexact_renames() -> crate::Result {
+    assert_eq!(
+        follow("c")?,
+        vec![
+            ("modify c".into(), "c".into(), None),
+            ("rename b to c with changes".into(), "c".into(), Some("b".into())),
+            ("rename dir/a to b".into(), "b".into(), Some("dir/a".into())),
+            ("modify dir/a".into(), "dir/a".into(), None),
+            ("add dir/a".into(), "dir/a".into(), None),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn dissimilar_files_replacing_deleted_ones_are_not_renames() -> crate::Result {
+    assert_eq!(
+        follow("new")?,
+        vec![("replace other with unrelated new".into(), "new".into(), None)],
+        "'other' was deleted when 'new' was added, but they aren't similar"
+    );
+    Ok(())
+}
+
+#[test]
+fn missing_paths_yield_nothing() -> crate::Result {
+    assert!(follow("does-not-exist")?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn directories_at_the_followed_path_are_not_followed() -> crate::Result {
+    assert!(
+        follow("sub")?.is_empty(),
+        "'sub' is a tree, which is treated as if the path didn't exist"
+    );
+    assert_eq!(
+        follow("sub/x")?,
+        vec![("replace new with sub/x".into(), "sub/x".into(), None)]
+    );
+    Ok(())
+}
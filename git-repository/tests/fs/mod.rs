@@ -0,0 +1,121 @@
+use git_repository::{
+    bstr::{BStr, ByteSlice},
+    fs::{Capabilities, PathComparison},
+};
+
+const NFC: &str = "caf\u{00e9}";
+const NFD: &str = "cafe\u{0301}";
+
+fn p(s: &str) -> &BStr {
+    s.as_bytes().as_bstr()
+}
+
+mod path_comparison {
+    use std::cmp::Ordering;
+
+    use git_repository::bstr::ByteSlice;
+
+    use super::{p, PathComparison, NFC, NFD};
+
+    #[test]
+    fn exact_compares_bytes() {
+        let cmp = PathComparison::exact();
+        assert!(cmp.eq(p("a/b"), p("a/b")));
+        assert!(!cmp.eq(p("README"), p("readme")));
+        assert!(!cmp.eq(p(NFC), p(NFD)));
+        assert_eq!(cmp.cmp(p("B"), p("a")), Ordering::Less, "uppercase sorts first");
+        assert!(cmp.collisions([p("README"), p("readme")]).is_empty());
+    }
+
+    #[test]
+    fn ignore_case_folds_ascii_only_like_git() {
+        let cmp = PathComparison {
+            ignore_case: true,
+            ..Default::default()
+        };
+        assert!(cmp.eq(p("dir/README.md"), p("DIR/readme.MD")));
+        assert_eq!(cmp.cmp(p("B"), p("a")), Ordering::Greater);
+        assert_eq!(cmp.key(p("Dir/File")), p("dir/file"));
+        assert!(
+            !cmp.eq(p("\u{00c4}"), p("\u{00e4}")),
+            "non-ASCII characters are compared as they are"
+        );
+        assert!(!cmp.eq(p(NFC), p(NFD)), "unicode isn't normalized");
+    }
+
+    #[test]
+    fn normalize_unicode_makes_nfc_and_nfd_equal() {
+        let cmp = PathComparison {
+            normalize_unicode: true,
+            ..Default::default()
+        };
+        assert!(cmp.eq(p(NFC), p(NFD)));
+        assert_eq!(cmp.key(p(NFD)), p(NFC), "the precomposed form is canonical");
+        assert!(!cmp.eq(p("README"), p("readme")));
+        assert!(
+            cmp.eq(b"\xff\xfe".as_bstr(), b"\xff\xfe".as_bstr()),
+            "illformed UTF-8 is compared byte-wise"
+        );
+    }
+
+    #[test]
+    fn collisions_are_grouped_in_order_of_appearance() {
+        let cmp = PathComparison {
+            ignore_case: true,
+            normalize_unicode: true,
+        };
+        let nfd_upper = format!("{}/X", NFD.to_uppercase());
+        let nfc_lower = format!("{}/x", NFC);
+        let paths = [p("a"), p("B"), p(&nfd_upper), p("b"), p("c"), p(&nfc_lower), p("A")];
+        assert_eq!(
+            cmp.collisions(paths),
+            vec![vec![p("a"), p("A")], vec![p("B"), p("b")]],
+            "uppercase non-ASCII characters don't fold"
+        );
+
+        let nfd_lower = format!("{}/X", NFD);
+        let paths = [p(&nfd_lower), p(&nfc_lower)];
+        assert_eq!(cmp.collisions(paths), vec![vec![p(&nfd_lower), p(&nfc_lower)]]);
+    }
+}
+
+#[test]
+fn probing_leaves_no_trace() -> crate::Result {
+    let dir = tempfile::tempdir()?;
+    let caps = Capabilities::probe(dir.path());
+    assert_eq!(std::fs::read_dir(dir.path())?.count(), 0);
+    if cfg!(target_os = "linux") {
+        assert_eq!(
+            caps,
+            Capabilities {
+                ignore_case: false,
+                precompose_unicode: false
+            }
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn repositories_compare_paths_according_to_their_configuration() -> crate::Result {
+    let dir = tempfile::tempdir()?;
+    let repo = git_repository::init(dir.path())?;
+    assert_eq!(
+        repo.path_comparison()?,
+        Capabilities::probe(dir.path()).into(),
+        "initialization writes the probed capabilities"
+    );
+
+    let config = repo.git_dir().join("config");
+    let mut content = std::fs::read(&config)?;
+    content.extend_from_slice(b"[core]\n\tignoreCase = true\n\tprecomposeunicode = true\n");
+    std::fs::write(config, content)?;
+    assert_eq!(
+        repo.path_comparison()?,
+        PathComparison {
+            ignore_case: true,
+            normalize_unicode: true
+        }
+    );
+    Ok(())
+}
@@ -0,0 +1,94 @@
+mod reflog {
+    use std::io::Write;
+
+    use git_repository::{
+        prelude::ReferenceAccessExt,
+        refs::{file::log::expire::Outcome, transaction::PreviousValue},
+    };
+
+    const FIXTURE_TIME: u32 = 946771200;
+
+    fn append_config(repo: &git_repository::Repository, config: &str) -> crate::Result {
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(repo.git_dir().join("config"))?;
+        file.write_all(config.as_bytes())?;
+        Ok(())
+    }
+
+    #[test]
+    fn nothing_expires_with_default_policy() -> crate::Result {
+        let (repo, _keep) = crate::repo_rw("make_basic_repo.sh")?;
+        let outcome = repo.expire_reflogs(FIXTURE_TIME + 24 * 60 * 60)?;
+        assert_eq!(outcome.removed, 0);
+        assert_ne!(outcome.kept, 0);
+        assert_eq!(repo.expire_reflog("refs/heads/does-not-exist", FIXTURE_TIME)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn pattern_specific_configuration_overrides_global_one() -> crate::Result {
+        let (repo, _keep) = crate::repo_rw("make_basic_repo.sh")?;
+        append_config(
+            &repo,
+            "[gc]\n\treflogExpire = never\n[gc \"refs/heads/*\"]\n\treflogExpire = now\n",
+        )?;
+        let main_entries = repo.expire_reflog("refs/heads/main", 0)?.expect("exists").kept;
+
+        let outcome = repo.expire_reflogs(FIXTURE_TIME + 1)?;
+        assert_eq!(outcome.removed, main_entries, "only the reflog of main is cleared");
+        assert_ne!(outcome.kept, 0, "HEAD's reflog is kept");
+        assert_eq!(
+            repo.expire_reflog("refs/heads/main", FIXTURE_TIME + 1)?,
+            Some(Outcome { removed: 0, kept: 0 })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unreachable_entries_expire_separately() -> crate::Result {
+        let (repo, _keep) = crate::repo_rw("make_basic_repo.sh")?;
+        append_config(
+            &repo,
+            "[gc]\n\treflogExpire = never\n\treflogExpireUnreachable = 1.day.ago\n",
+        )?;
+        {
+            let easy = repo.to_easy();
+            let c2 = easy.head()?.into_fully_peeled_id().expect("born")?;
+            let c1 = c2.ancestors()?.all().nth(1).expect("parent")?.detach();
+            easy.reference("refs/heads/main", c1, PreviousValue::Any, "reset: moving to c1")?;
+        }
+        assert_eq!(
+            repo.expire_reflog("refs/heads/main", FIXTURE_TIME + 1)?,
+            Some(Outcome { removed: 0, kept: 3 }),
+            "nothing is old enough"
+        );
+        assert_eq!(
+            repo.expire_reflog("refs/heads/main", u32::MAX)?,
+            Some(Outcome { removed: 1, kept: 2 }),
+            "c2 isn't reachable anymore"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn traversal_errors_abort_expiry_without_touching_the_reflog() -> crate::Result {
+        let (repo, _keep) = crate::repo_rw("make_basic_repo.sh")?;
+        append_config(&repo, "[gc]\n\treflogExpireUnreachable = now\n")?;
+        let log_path = repo.git_dir().join("logs/refs/heads/main");
+        let log = std::fs::read(&log_path)?;
+        {
+            let easy = repo.to_easy();
+            let c2 = easy.head()?.into_fully_peeled_id().expect("born")?;
+            let c1 = c2.ancestors()?.all().nth(1).expect("parent")?.detach().to_string();
+            std::fs::remove_file(repo.objects_dir().join(&c1[..2]).join(&c1[2..]))?;
+        }
+
+        assert!(
+            repo.expire_reflog("refs/heads/main", u32::MAX).is_err(),
+            "the parent of the tip can't be found"
+        );
+        assert_eq!(std::fs::read(log_path)?, log, "nothing was expired");
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use std::{fs, path::Path};
+
+use git_repository::{
+    easy::reference, open, prelude::ReferenceAccessExt, refs, refs::transaction::PreviousValue, ObjectId, Repository,
+};
+
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// Install a `reference-transaction` hook which logs its invocations into `hook.log` in the working tree, and rejects
+/// prepared transactions if the `reject` file exists.
+#[cfg(unix)]
+fn install_hook(work_tree: &Path) -> crate::Result {
+    use std::os::unix::fs::PermissionsExt;
+    let hook = work_tree.join(".git").join("hooks").join("reference-transaction");
+    fs::create_dir_all(hook.parent().expect("parent"))?;
+    fs::write(
+        &hook,
+        "#!/bin/sh\necho \"$1\" >> hook.log\ncat >> hook.log\nif [ \"$1\" = prepared ] && [ -f reject ]; then exit 1; fi\n",
+    )?;
+    fs::set_permissions(&hook, fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+fn hook_log(work_tree: &Path) -> crate::Result<String> {
+    Ok(fs::read_to_string(work_tree.join("hook.log")).unwrap_or_default())
+}
+
+#[test]
+#[cfg(unix)]
+fn reference_transaction_sees_all_states_and_can_reject() -> crate::Result {
+    let (repo, keep) = crate::repo_rw("make_basic_repo.sh")?;
+    install_hook(keep.path())?;
+    let repo = repo.into_easy();
+    let id = ObjectId::from_hex(EMPTY_TREE.as_bytes())?;
+
+    repo.tag_reference("new-tag", id, PreviousValue::MustNotExist)?;
+    let null = ObjectId::null_sha1();
+    assert_eq!(
+        hook_log(keep.path())?,
+        format!(
+            "prepared\n{null} {id} refs/tags/new-tag\ncommitted\n{null} {id} refs/tags/new-tag\n",
+            null = null,
+            id = id
+        )
+    );
+
+    fs::write(keep.path().join("reject"), "")?;
+    fs::remove_file(keep.path().join("hook.log"))?;
+    let res = repo.tag_reference("rejected-tag", id, PreviousValue::MustNotExist);
+    assert!(matches!(
+        res,
+        Err(reference::edit::Error::FileTransactionPrepare(
+            refs::file::transaction::prepare::Error::Hook(_)
+        ))
+    ));
+    assert_eq!(
+        hook_log(keep.path())?,
+        format!(
+            "prepared\n{null} {id} refs/tags/rejected-tag\naborted\n{null} {id} refs/tags/rejected-tag\n",
+            null = null,
+            id = id
+        )
+    );
+    assert!(repo.try_find_reference("refs/tags/rejected-tag")?.is_none());
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn reference_transaction_is_not_run_without_permission_to_execute_programs() -> crate::Result {
+    let (_repo, keep) = crate::repo_rw("make_basic_repo.sh")?;
+    install_hook(keep.path())?;
+    let repo = Repository::open_opts(keep.path(), open::Options::isolated())?.into_easy();
+    assert!(git_repository::hook::path(&repo.repo, "reference-transaction")?.is_none());
+
+    repo.tag_reference(
+        "new-tag",
+        ObjectId::from_hex(EMPTY_TREE.as_bytes())?,
+        PreviousValue::MustNotExist,
+    )?;
+    assert_eq!(hook_log(keep.path())?, "", "the hook didn't run");
+    Ok(())
+}
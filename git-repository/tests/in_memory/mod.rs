@@ -0,0 +1,119 @@
+use git_repository::{
+    actor,
+    objs::{self, tree::EntryMode},
+    prelude::{ObjectAccessExt, ReferenceAccessExt},
+    refs::transaction::PreviousValue,
+    ObjectId, Repository,
+};
+
+fn signature() -> actor::Signature {
+    actor::Signature {
+        name: "name".into(),
+        email: "name@example.com".into(),
+        time: actor::Time {
+            time: 42,
+            offset: 0,
+            sign: actor::Sign::Plus,
+        },
+    }
+}
+
+#[test]
+fn new_repositories_have_an_unborn_head_and_no_location() {
+    let repo = Repository::new_in_memory();
+    assert!(repo.is_in_memory());
+    assert_eq!(repo.kind(), git_repository::Kind::Bare);
+    assert_ne!(repo, Repository::new_in_memory(), "each repository is distinct");
+
+    let repo = repo.into_easy();
+    let head = repo.head().expect("HEAD exists");
+    assert_eq!(head.referent_name().expect("symbolic").as_bstr(), "refs/heads/main");
+    assert!(head.id().is_none(), "the branch is unborn");
+}
+
+#[test]
+fn commits_tags_and_references_are_created_without_filesystem_access() -> crate::Result {
+    let repo = Repository::new_in_memory().into_easy();
+    let signature = signature();
+    let blob = repo
+        .write_object(&objs::Blob {
+            data: b"content".to_vec(),
+        })?
+        .detach();
+    let tree = repo
+        .write_object(&objs::Tree {
+            entries: vec![objs::tree::Entry {
+                mode: EntryMode::Blob,
+                filename: "file".into(),
+                oid: blob,
+            }],
+        })?
+        .detach();
+
+    let first = repo
+        .commit(
+            "HEAD",
+            &signature.to_ref(),
+            &signature.to_ref(),
+            "first",
+            tree,
+            None::<ObjectId>,
+        )?
+        .detach();
+    let second = repo
+        .commit(
+            "HEAD",
+            &signature.to_ref(),
+            &signature.to_ref(),
+            "second",
+            tree,
+            Some(first),
+        )?
+        .detach();
+    assert_eq!(repo.head()?.into_fully_peeled_id().expect("born")?, second);
+    assert_eq!(repo.find_reference("main")?.id(), second);
+
+    let tag = repo.tag(
+        "v1",
+        first,
+        objs::Kind::Commit,
+        Some(&signature.to_ref()),
+        "the first commit",
+        PreviousValue::MustNotExist,
+    )?;
+    assert_ne!(tag.id(), first, "annotated tags point to a tag object");
+    assert_eq!(repo.find_reference("v1")?.into_fully_peeled_id()?, first);
+    repo.reference("refs/heads/dev", first, PreviousValue::MustNotExist, "")?;
+
+    let names = repo
+        .references()?
+        .all()?
+        .filter_map(Result::ok)
+        .map(|r| r.name().as_bstr().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["refs/heads/dev", "refs/heads/main", "refs/tags/v1"]);
+
+    let ancestors = repo
+        .find_reference("main")?
+        .id()
+        .ancestors()?
+        .all()
+        .map(|id| id.map(|id| id.detach()))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(ancestors, vec![second, first]);
+
+    assert!(
+        repo.commit(
+            "HEAD",
+            &signature.to_ref(),
+            &signature.to_ref(),
+            "based on the wrong parent",
+            tree,
+            Some(blob),
+        )
+        .is_err(),
+        "expectations on previous values are enforced"
+    );
+    assert_eq!(repo.find_reference("main")?.id(), second, "nothing changed");
+    Ok(())
+}
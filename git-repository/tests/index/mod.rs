@@ -0,0 +1,250 @@
+use std::{path::Path, process::Command};
+
+use git_repository::{
+    bstr::{BString, ByteSlice},
+    hash::ObjectId,
+    index::{self, entry::Mode, Entry},
+    open,
+    prelude::ObjectAccessExt,
+    Repository,
+};
+
+/// Writable fixtures are copies which resolve symbolic links, so restore the one written by the script.
+fn writable_repo() -> crate::Result<(Repository, tempfile::TempDir)> {
+    let (_repo, keep) = crate::repo_rw("make_dirwalk_repo.sh")?;
+    #[cfg(unix)]
+    {
+        let link = keep.path().join("link");
+        std::fs::remove_dir_all(&link)?;
+        std::os::unix::fs::symlink("dir", link)?;
+    }
+    Ok((Repository::open_opts(keep.path(), open::Options::isolated())?, keep))
+}
+
+fn git(work_tree: &Path, args: &[&str]) -> crate::Result<BString> {
+    let out = Command::new("git").args(args).current_dir(work_tree).output()?;
+    assert!(out.status.success(), "{}", out.stderr.as_bstr());
+    Ok(out.stdout.into())
+}
+
+fn paths(paths: &[&str]) -> Vec<BString> {
+    paths.iter().map(|p| (*p).into()).collect()
+}
+
+#[test]
+fn add_stages_untracked_files_and_updates_tracked_ones() -> crate::Result {
+    let (repo, keep) = writable_repo()?;
+    let out = repo.add(Some(".".into()))?;
+    assert_eq!(
+        out.added,
+        paths(&[
+            ".gitignore",
+            "dir/.gitignore",
+            "dir/file",
+            "dir/sub/file",
+            "file",
+            "keep.log",
+            "link",
+            "local"
+        ]),
+        "ignored files and nested repositories are skipped"
+    );
+    assert!(out.modified.is_empty() && out.removed.is_empty());
+    assert_eq!(
+        git(keep.path(), &["diff", "--name-only"])?,
+        "",
+        "git agrees that the index matches the work tree"
+    );
+    assert_eq!(repo.add(Some(".".into()))?, Default::default(), "nothing changed");
+
+    std::fs::write(keep.path().join("dir/file"), "changed")?;
+    std::fs::write(keep.path().join("file"), "changed")?;
+    std::fs::remove_file(keep.path().join("dir/sub/file"))?;
+    let out = repo.add(Some("dir/".into()))?;
+    assert_eq!(
+        out.modified,
+        paths(&["dir/file"]),
+        "only paths below the pathspec are staged"
+    );
+    assert_eq!(out.removed, paths(&["dir/sub/file"]));
+    assert_eq!(git(keep.path(), &["diff", "--name-only"])?, "file\n");
+    Ok(())
+}
+
+#[test]
+fn add_converts_line_endings_with_autocrlf() -> crate::Result {
+    let (repo, keep) = writable_repo()?;
+    let config = keep.path().join(".git").join("config");
+    let mut content = std::fs::read_to_string(&config)?;
+    content.push_str("[core]\n\tautocrlf = true\n");
+    std::fs::write(config, content)?;
+    std::fs::write(keep.path().join("text"), "a\r\nb\r\n")?;
+    std::fs::write(keep.path().join("binary"), "\0a\r\n")?;
+
+    repo.add(["text".into(), "binary".into()])?;
+    let index = repo.index()?;
+    let easy = repo.to_easy();
+    let content_of = |path: &str| -> crate::Result<BString> {
+        let id = index.entry_by_path(path.into()).expect("staged").id;
+        Ok(easy.find_object(id)?.data.to_vec().into())
+    };
+    assert_eq!(content_of("text")?, "a\nb\n");
+    assert_eq!(content_of("binary")?, "\0a\r\n", "binary files are never converted");
+    Ok(())
+}
+
+#[test]
+fn update_index_inserts_entries_for_existing_objects_only() -> crate::Result {
+    let (repo, keep) = writable_repo()?;
+    repo.add(Some("file".into()))?;
+    let id = repo.index()?.entry_by_path("file".into()).expect("staged").id;
+
+    repo.update_index(Some(Entry::from_cacheinfo(Mode::FileExecutable, id, "not/in/worktree")))?;
+    assert_eq!(
+        git(keep.path(), &["ls-files", "-s"])?,
+        format!("100644 {0} 0\tfile\n100755 {0} 0\tnot/in/worktree\n", id)
+    );
+
+    let missing = ObjectId::from_hex(b"0000000000000000000000000000000000000001")?;
+    assert!(matches!(
+        repo.update_index(Some(Entry::from_cacheinfo(Mode::File, missing, "missing"))),
+        Err(index::Error::MissingObject { .. })
+    ));
+    assert!(
+        repo.update_index(Some(Entry::from_cacheinfo(Mode::Commit, missing, "submodule")))
+            .is_ok(),
+        "submodule commits don't have to exist"
+    );
+    assert_eq!(repo.index()?.entries().len(), 3);
+    Ok(())
+}
+
+#[test]
+fn write_tree_and_read_tree_agree_with_git() -> crate::Result {
+    let (repo, keep) = writable_repo()?;
+    repo.add(Some(".".into()))?;
+    let tree = repo.write_tree()?;
+    assert_eq!(git(keep.path(), &["write-tree"])?.trim(), tree.to_string().as_bytes());
+    assert_eq!(repo.write_tree()?, tree, "writing again yields the same tree");
+
+    let content = std::fs::read(keep.path().join("dir/file"))?;
+    std::fs::write(keep.path().join("dir/file"), content)?;
+    assert_eq!(repo.add(Some(".".into()))?, Default::default());
+    assert_eq!(
+        repo.index()?.tree().and_then(|t| t.id),
+        Some(tree),
+        "re-adding unchanged content keeps the cache tree valid"
+    );
+
+    repo.read_tree(&tree, Some("copy".into()))?;
+    let files = git(keep.path(), &["ls-files"])?;
+    assert_eq!(files.lines().filter(|l| l.starts_with(b"copy/")).count(), 8);
+    assert!(matches!(
+        repo.read_tree(&tree, Some("copy/dir".into())),
+        Err(index::Error::ReadTree(_))
+    ));
+
+    repo.read_tree(&tree, None)?;
+    assert_eq!(git(keep.path(), &["ls-files"])?.lines().count(), 8);
+    assert_eq!(git(keep.path(), &["write-tree"])?.trim(), tree.to_string().as_bytes());
+    Ok(())
+}
+
+#[test]
+fn status_lists_changes_to_the_index_and_untracked_files() -> crate::Result {
+    let (repo, keep) = writable_repo()?;
+    repo.add(["file".into(), "dir/".into()])?;
+    assert_eq!(
+        repo.status()?.untracked,
+        paths(&[".gitignore", "keep.log", "link", "local"]),
+        "ignored files and nested repositories aren't untracked"
+    );
+    assert!(repo.status()?.modified.is_empty());
+
+    std::fs::write(keep.path().join("dir/file"), "changed")?;
+    std::fs::remove_file(keep.path().join("dir/sub/file"))?;
+    let out = repo.status()?;
+    assert_eq!(out.modified, paths(&["dir/file"]));
+    assert_eq!(out.deleted, paths(&["dir/sub/file"]));
+    Ok(())
+}
+
+#[test]
+fn status_and_add_compare_work_tree_paths_like_the_filesystem() -> crate::Result {
+    let (repo, keep) = writable_repo()?;
+    repo.add(Some("file".into()))?;
+    std::fs::write(keep.path().join("FILE"), "")?;
+    assert!(
+        repo.status()?.untracked.contains(&"FILE".into()),
+        "paths are compared exactly by default"
+    );
+
+    git(keep.path(), &["config", "core.ignoreCase", "true"])?;
+    assert!(
+        !repo.status()?.untracked.contains(&"FILE".into()),
+        "FILE is the tracked file if the case of paths is ignored"
+    );
+    let out = repo.add(Some(".".into()))?;
+    assert!(
+        !out.added.contains(&"FILE".into()),
+        "no second entry is added for the same file"
+    );
+    assert!(repo.index()?.entry_by_path("FILE".into()).is_none());
+    Ok(())
+}
+
+#[test]
+fn status_compares_racily_clean_files_by_content() -> crate::Result {
+    let (repo, keep) = writable_repo()?;
+    let path = keep.path().join("file");
+    let set_mtime = |time| std::fs::OpenOptions::new().write(true).open(&path)?.set_modified(time);
+
+    let past = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+    std::fs::write(&path, "one")?;
+    set_mtime(past)?;
+    repo.add(Some("file".into()))?;
+    assert_eq!(
+        repo.index()?.entry_by_path("file".into()).expect("staged").stat.size,
+        3,
+        "files modified before the index was written aren't racy"
+    );
+
+    let future = std::time::SystemTime::now() + std::time::Duration::from_secs(3600);
+    set_mtime(future)?;
+    repo.add(Some("file".into()))?;
+    let entry = repo.index()?.entry_by_path("file".into()).expect("staged").clone();
+    assert_eq!(entry.stat.size, 0, "racy entries are smudged when writing the index");
+    assert!(repo.status()?.modified.is_empty(), "unchanged content is detected");
+    assert_eq!(git(keep.path(), &["diff", "--name-only"])?, "", "git agrees");
+
+    std::fs::write(&path, "two")?;
+    set_mtime(future)?;
+    assert_eq!(
+        repo.status()?.modified,
+        paths(&["file"]),
+        "a modification that doesn't change the metadata is seen"
+    );
+    Ok(())
+}
+
+#[test]
+fn status_uses_the_thread_limit_of_the_repository() -> crate::Result {
+    let (repo, keep) = writable_repo()?;
+    repo.add(["file".into(), "dir/".into()])?;
+    std::fs::write(keep.path().join("dir/file"), "changed")?;
+    std::fs::remove_file(keep.path().join("dir/sub/file"))?;
+    let expected = repo.status()?;
+
+    for threads in &["1", "3"] {
+        git(keep.path(), &["config", "gitoxide.threads", threads])?;
+        assert_eq!(
+            repo.status()?,
+            expected,
+            "the result doesn't depend on the amount of threads"
+        );
+    }
+
+    git(keep.path(), &["config", "gitoxide.threads", "many"])?;
+    assert!(repo.status().is_err(), "invalid thread limits are errors");
+    Ok(())
+}
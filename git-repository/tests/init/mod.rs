@@ -16,6 +16,18 @@ mod bare {
         assert_eq!(git_repository::open(repo.git_dir()).unwrap(), repo);
     }
 
+    #[test]
+    fn init_into_non_existing_directory_creates_it() -> crate::Result {
+        let tmp = tempfile::tempdir()?;
+        let dir = tmp.path().join("some").join("repo.git");
+        let repo = git_repository::init_bare(&dir)?;
+        assert_eq!(repo.git_dir(), dir);
+        assert!(dir.join("HEAD").is_file());
+        assert!(dir.join("objects").is_dir());
+        assert!(dir.join("refs").is_dir());
+        Ok(())
+    }
+
     #[test]
     fn init_into_non_empty_directory_is_not_allowed() {
         let tmp = tempfile::tempdir().unwrap();
@@ -61,3 +73,75 @@ mod non_bare {
         Ok(())
     }
 }
+
+mod options {
+    use git_repository::{path::create::Options, prelude::ReferenceAccessExt, Kind, Repository};
+
+    #[test]
+    fn hook_samples_can_be_left_out() -> crate::Result {
+        let tmp = tempfile::tempdir()?;
+        let repo = Repository::init_opts(
+            tmp.path(),
+            Kind::Bare,
+            Options {
+                hook_samples: false,
+                ..Default::default()
+            },
+        )?;
+        let hooks = repo.git_dir().join("hooks");
+        assert!(hooks.is_dir(), "the hooks directory is still present");
+        assert_eq!(std::fs::read_dir(hooks)?.count(), 0);
+        assert!(repo.git_dir().join("info").join("exclude").is_file());
+        Ok(())
+    }
+
+    #[test]
+    fn the_initial_branch_is_main_by_default_and_configurable() -> crate::Result {
+        let tmp = tempfile::tempdir()?;
+        let repo = git_repository::init(tmp.path().join("default"))?;
+        assert_eq!(std::fs::read(repo.git_dir().join("HEAD"))?, b"ref: refs/heads/main\n");
+        assert!(
+            repo.git_dir().join("hooks").join("pre-commit.sample").is_file(),
+            "hook samples are built in"
+        );
+
+        let repo = Repository::init_opts(
+            tmp.path().join("custom"),
+            Kind::WorkTree,
+            Options {
+                initial_branch: Some("feature/x".into()),
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(
+            std::fs::read(repo.git_dir().join("HEAD"))?,
+            b"ref: refs/heads/feature/x\n"
+        );
+        assert_eq!(
+            repo.to_easy()
+                .head()?
+                .referent_name()
+                .map(|name| name.as_bstr().to_owned()),
+            Some("refs/heads/feature/x".into())
+        );
+
+        assert!(matches!(
+            Repository::init_opts(
+                tmp.path().join("invalid"),
+                Kind::WorkTree,
+                Options {
+                    initial_branch: Some("a..b".into()),
+                    ..Default::default()
+                },
+            ),
+            Err(git_repository::init::Error::Init(
+                git_repository::path::create::Error::InvalidInitialBranch { .. }
+            ))
+        ));
+        assert!(
+            !tmp.path().join("invalid").exists(),
+            "nothing is written for invalid names"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,283 @@
+use std::{path::Path, process::Command};
+
+use git_repository::{
+    maintenance::{Error, Options, Task, TaskOutcome},
+    prelude::ReferenceAccessExt,
+    Repository,
+};
+
+fn loose_object_count(repo: &Repository) -> std::io::Result<usize> {
+    let mut count = 0;
+    for entry in std::fs::read_dir(repo.objects_dir())? {
+        let entry = entry?;
+        if entry.file_name().len() == 2 && entry.file_type()?.is_dir() {
+            count += std::fs::read_dir(entry.path())?.count();
+        }
+    }
+    Ok(count)
+}
+
+fn pack_sizes(repo: &Repository) -> std::io::Result<Vec<u64>> {
+    let mut sizes = Vec::new();
+    for entry in std::fs::read_dir(repo.objects_dir().join("pack"))? {
+        let path = entry?.path();
+        if path.extension().map_or(false, |ext| ext == "pack") {
+            sizes.push(path.metadata()?.len());
+        }
+    }
+    Ok(sizes)
+}
+
+fn git(git_dir: &Path, args: &[&str]) -> std::io::Result<bool> {
+    Ok(Command::new("git")
+        .arg("--git-dir")
+        .arg(git_dir)
+        .args(args)
+        .status()?
+        .success())
+}
+
+fn options(tasks: &[Task]) -> Options {
+    Options {
+        tasks: tasks.to_vec(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn loose_objects_are_packed_and_deleted_in_the_next_run() -> crate::Result {
+    let (repo, keep) = crate::repo_rw("make_basic_repo.sh")?;
+    let num_loose = loose_object_count(&repo)?;
+    assert_ne!(num_loose, 0, "the fixture has loose objects");
+
+    let opts = Options {
+        loose_objects_batch_size: num_loose - 1,
+        ..options(&[Task::LooseObjects])
+    };
+    let outcome = repo.maintenance(&opts)?;
+    assert_eq!(
+        outcome.tasks,
+        vec![(
+            Task::LooseObjects,
+            TaskOutcome::LooseObjects {
+                deleted: 0,
+                packed: num_loose - 1
+            }
+        )],
+        "packing is limited by the batch size, objects are only deleted once a pack contains them"
+    );
+
+    let repo = Repository::discover(keep.path())?;
+    let outcome = repo.maintenance(&opts)?;
+    assert_eq!(
+        outcome.tasks,
+        vec![(
+            Task::LooseObjects,
+            TaskOutcome::LooseObjects {
+                deleted: num_loose - 1,
+                packed: 1
+            }
+        )]
+    );
+
+    let repo = Repository::discover(keep.path())?;
+    let outcome = repo.maintenance(&opts)?;
+    assert_eq!(
+        outcome.tasks,
+        vec![(Task::LooseObjects, TaskOutcome::LooseObjects { deleted: 1, packed: 0 })]
+    );
+    assert_eq!(loose_object_count(&repo)?, 0);
+
+    let repo = Repository::discover(keep.path())?.into_easy();
+    let head = repo.head()?.into_fully_peeled_id().expect("born")?;
+    assert_eq!(
+        head.ancestors()?.all().collect::<Result<Vec<_>, _>>()?.len(),
+        2,
+        "objects can still be found in packs"
+    );
+    Ok(())
+}
+
+#[test]
+fn incremental_repack_writes_a_multi_pack_index_and_repacks_small_packs() -> crate::Result {
+    let (repo, keep) = crate::repo_rw("make_basic_repo.sh")?;
+    let num_loose = loose_object_count(&repo)?;
+    let loose_objects = Options {
+        loose_objects_batch_size: num_loose - 1,
+        ..options(&[Task::LooseObjects])
+    };
+    repo.maintenance(&loose_objects)?;
+    let repo = Repository::discover(keep.path())?;
+    repo.maintenance(&loose_objects)?;
+    let sizes = pack_sizes(&repo)?;
+    assert_eq!(sizes.len(), 2, "each run of the loose-objects task writes a pack");
+
+    let auto = options(&[Task::IncrementalRepack]);
+    assert_eq!(
+        repo.maintenance(&auto)?.tasks,
+        vec![(
+            Task::IncrementalRepack,
+            TaskOutcome::IncrementalRepack {
+                expired_packs: 0,
+                repacked_packs: 0,
+                packed_objects: 0
+            }
+        )],
+        "with two packs, the largest one is too big to be repacked and the other one alone isn't repacked"
+    );
+    assert!(git(repo.git_dir(), &["multi-pack-index", "verify"])?);
+
+    let opts = Options {
+        incremental_repack_batch_size: Some(sizes.iter().sum()),
+        ..auto
+    };
+    assert_eq!(
+        repo.maintenance(&opts)?.tasks,
+        vec![(
+            Task::IncrementalRepack,
+            TaskOutcome::IncrementalRepack {
+                expired_packs: 0,
+                repacked_packs: 2,
+                packed_objects: num_loose
+            }
+        )]
+    );
+    assert_eq!(
+        pack_sizes(&repo)?.len(),
+        3,
+        "repacked packs are kept until the next run"
+    );
+    assert!(git(repo.git_dir(), &["multi-pack-index", "verify"])?);
+
+    assert_eq!(
+        repo.maintenance(&opts)?.tasks,
+        vec![(
+            Task::IncrementalRepack,
+            TaskOutcome::IncrementalRepack {
+                expired_packs: 2,
+                repacked_packs: 0,
+                packed_objects: 0
+            }
+        )],
+        "the multi-pack index refers to the new pack for all objects of the repacked ones"
+    );
+    assert_eq!(pack_sizes(&repo)?.len(), 1);
+    assert!(git(repo.git_dir(), &["multi-pack-index", "verify"])?);
+
+    let repo = Repository::discover(keep.path())?.into_easy();
+    let head = repo.head()?.into_fully_peeled_id().expect("born")?;
+    assert_eq!(
+        head.ancestors()?.all().collect::<Result<Vec<_>, _>>()?.len(),
+        2,
+        "objects can still be found in the remaining pack"
+    );
+    Ok(())
+}
+
+#[test]
+fn commit_graph_contains_all_commits_reachable_from_references() -> crate::Result {
+    let (repo, _keep) = crate::repo_rw("make_basic_repo.sh")?;
+    assert_eq!(
+        repo.maintenance(&options(&[Task::CommitGraph]))?.tasks,
+        vec![(Task::CommitGraph, TaskOutcome::CommitGraph { num_commits: 2 })]
+    );
+    assert!(repo.objects_dir().join("info").join("commit-graph").is_file());
+    assert!(git(repo.git_dir(), &["commit-graph", "verify"])?);
+    Ok(())
+}
+
+#[cfg(feature = "blocking-network-client")]
+#[test]
+fn prefetch_fetches_into_the_prefetch_namespace_only() -> crate::Result {
+    let (source, keep) = crate::repo_rw("make_basic_repo.sh")?;
+    let git_dir = keep.path().join("bare.git");
+    let mut config = std::fs::OpenOptions::new().append(true).open(git_dir.join("config"))?;
+    std::io::Write::write_all(
+        &mut config,
+        format!(
+            "[remote \"origin\"]\n\turl = {}\n\tfetch = +refs/heads/*:refs/remotes/origin/*\n\tfetch = +refs/tags/*:refs/tags/*\n",
+            source.git_dir().display()
+        )
+        .as_bytes(),
+    )?;
+    let repo = Repository::open(&git_dir)?;
+    let opts = options(&[Task::Prefetch]);
+    assert_eq!(
+        repo.maintenance(&opts)?.tasks,
+        vec![(Task::Prefetch, TaskOutcome::Prefetch { updated_refs: 1 })]
+    );
+
+    let repo = Repository::open(&git_dir)?;
+    let source_main = source.to_easy().find_reference("main")?.id().detach();
+    let easy = repo.to_easy();
+    assert_eq!(
+        easy.find_reference("refs/prefetch/remotes/origin/main")?.id().detach(),
+        source_main,
+        "objects are received and the reference is created"
+    );
+    assert!(git(&git_dir, &["cat-file", "-e", &source_main.to_string()])?);
+    assert!(easy.try_find_reference("refs/remotes/origin/main")?.is_none());
+    assert!(
+        !git_dir.join("logs/refs/prefetch/remotes/origin/main").exists(),
+        "prefetched references have no reflog"
+    );
+
+    assert_eq!(
+        repo.maintenance(&opts)?.tasks,
+        vec![(Task::Prefetch, TaskOutcome::Prefetch { updated_refs: 0 })],
+        "references which didn't change aren't counted"
+    );
+    Ok(())
+}
+
+#[test]
+fn all_tasks_run_in_order_in_a_single_run() -> crate::Result {
+    let (repo, _keep) = crate::repo_rw("make_basic_repo.sh")?;
+    let num_loose = loose_object_count(&repo)?;
+    let mut tasks = Task::ALL.to_vec();
+    tasks.reverse();
+    let outcome = repo.maintenance(&options(&tasks))?;
+    assert_eq!(
+        outcome.tasks.iter().map(|(task, _)| *task).collect::<Vec<_>>(),
+        Task::ALL,
+        "tasks run in their predefined order, not in the given one"
+    );
+    let mut expected = Vec::new();
+    #[cfg(feature = "blocking-network-client")]
+    expected.push((Task::Prefetch, TaskOutcome::Prefetch { updated_refs: 0 }));
+    expected.extend(vec![
+        (
+            Task::LooseObjects,
+            TaskOutcome::LooseObjects {
+                deleted: 0,
+                packed: num_loose,
+            },
+        ),
+        (
+            Task::IncrementalRepack,
+            TaskOutcome::IncrementalRepack {
+                expired_packs: 0,
+                repacked_packs: 0,
+                packed_objects: 0,
+            },
+        ),
+        (Task::CommitGraph, TaskOutcome::CommitGraph { num_commits: 2 }),
+    ]);
+    assert_eq!(outcome.tasks, expected, "without remotes, there is nothing to prefetch");
+    assert_eq!(pack_sizes(&repo)?.len(), 1);
+    assert!(git(repo.git_dir(), &["multi-pack-index", "verify"])?);
+    assert!(git(repo.git_dir(), &["commit-graph", "verify"])?);
+    Ok(())
+}
+
+#[test]
+fn concurrent_maintenance_is_prevented_by_a_lock() -> crate::Result {
+    let (repo, _keep) = crate::repo_rw("make_basic_repo.sh")?;
+    let _lock = git_repository::lock::Marker::acquire_to_hold_resource(
+        repo.objects_dir().join("maintenance"),
+        git_repository::lock::acquire::Fail::Immediately,
+        None,
+    )?;
+    assert!(matches!(repo.maintenance(&Options::default()), Err(Error::Lock(_))));
+    Ok(())
+}
@@ -0,0 +1,207 @@
+use git_repository::{open, prelude::ReferenceAccessExt, Repository};
+
+#[test]
+fn isolated_repositories_can_be_opened_and_read() -> crate::Result {
+    let repo_path = git_testtools::scripted_fixture_repo_read_only("make_basic_repo.sh")?;
+    let repo = Repository::open_opts(&repo_path, open::Options::isolated())?;
+    assert_eq!(repo, Repository::open(&repo_path)?);
+    assert_eq!(repo.kind(), git_repository::Kind::WorkTree);
+
+    let repo = repo.into_easy();
+    let head = repo.head()?.into_fully_peeled_id().expect("born")?;
+    assert_eq!(head.ancestors()?.all().count(), 2);
+    Ok(())
+}
+
+#[test]
+fn isolated_repositories_still_use_their_own_configuration() -> crate::Result {
+    let (repo, keep) = crate::repo_rw("make_basic_repo.sh")?;
+    let mut config = std::fs::OpenOptions::new()
+        .append(true)
+        .open(repo.git_dir().join("config"))?;
+    std::io::Write::write_all(
+        &mut config,
+        b"[gc]\n\treflogExpire = now\n\treflogExpireUnreachable = now\n",
+    )?;
+
+    let repo = Repository::open_opts(keep.path(), open::Options::isolated())?;
+    let outcome = repo.expire_reflogs(946771200 + 1)?;
+    assert_ne!(outcome.removed, 0);
+    assert_eq!(outcome.kept, 0);
+    Ok(())
+}
+
+#[test]
+fn loose_objects_are_compressed_as_configured() -> crate::Result {
+    use git_repository::prelude::ObjectAccessExt;
+
+    let (repo, keep) = crate::repo_rw("make_basic_repo.sh")?;
+    let config_path = repo.git_dir().join("config");
+    let mut config = std::fs::OpenOptions::new().append(true).open(&config_path)?;
+    std::io::Write::write_all(&mut config, b"[core]\n\tcompression = 9\n\tlooseCompression = 0\n")?;
+
+    let data = b"hello world, hello world, hello world".repeat(100);
+    let repo = Repository::open(keep.path())?.into_easy();
+    let id = repo.write_object(git_repository::objs::Blob { data: data.clone() })?;
+    let hex = id.detach().to_sha1_hex_string();
+    let path = repo.repo.objects_dir().join(&hex[..2]).join(&hex[2..]);
+    assert!(
+        std::fs::metadata(path)?.len() as usize > data.len(),
+        "objects are stored uncompressed"
+    );
+
+    std::io::Write::write_all(&mut config, b"[core]\n\tlooseCompression = 10\n")?;
+    assert!(matches!(
+        Repository::open(keep.path()),
+        Err(open::Error::CompressionLevel(err)) if err.key == "core.looseCompression" && err.value == 10
+    ));
+    Ok(())
+}
+
+#[test]
+fn fsync_policy_is_configured() -> crate::Result {
+    use git_features::fs::Fsync;
+
+    let (repo, keep) = crate::repo_rw("make_basic_repo.sh")?;
+    let config_path = repo.git_dir().join("config");
+    let fsync = |repo: &Repository| repo.refs.as_file().expect("references on disk").fsync;
+    assert_eq!(fsync(&repo), Fsync::None, "nothing is synchronized by default");
+
+    let mut config = std::fs::OpenOptions::new().append(true).open(&config_path)?;
+    std::io::Write::write_all(&mut config, b"[core]\n\tfsyncObjectFiles = true\n")?;
+    assert_eq!(fsync(&Repository::open(keep.path())?), Fsync::Objects);
+
+    std::io::Write::write_all(&mut config, b"[core]\n\tfsync = pack, -reference\n")?;
+    assert_eq!(
+        fsync(&Repository::open(keep.path())?),
+        Fsync::Objects,
+        "core.fsync takes precedence and removed components are ignored"
+    );
+
+    std::io::Write::write_all(&mut config, b"[core]\n\tfsync = objects,committed\n")?;
+    assert_eq!(fsync(&Repository::open(keep.path())?), Fsync::Everything);
+
+    std::io::Write::write_all(&mut config, b"[core]\n\tfsyncMethod = batch\n")?;
+    let repo = Repository::open(keep.path())?;
+    assert_eq!(fsync(&repo), Fsync::Batch);
+
+    std::fs::write(keep.path().join("new"), b"content")?;
+    let outcome = repo.add(Some("new".into()))?;
+    assert_eq!(
+        outcome.added,
+        vec!["new"],
+        "batched objects are synchronized before the index is written"
+    );
+    Ok(())
+}
+
+#[cfg(feature = "unstable")]
+#[test]
+fn batched_objects_are_synchronized_before_references_are_updated() -> crate::Result {
+    let (repo, keep) = crate::repo_rw("make_basic_repo.sh")?;
+    let mut config = std::fs::OpenOptions::new()
+        .append(true)
+        .open(repo.git_dir().join("config"))?;
+    std::io::Write::write_all(&mut config, b"[core]\n\tfsync = committed\n\tfsyncMethod = batch\n")?;
+    let repo = Repository::open(keep.path())?;
+
+    let unsynced = |repo: &Repository| repo.odb.dbs[0].loose.num_unsynced();
+    assert_eq!(unsynced(&repo), 0);
+    let head = {
+        use git_repository::prelude::{ObjectAccessExt, ReferenceAccessExt};
+        let easy = repo.to_easy();
+        easy.write_object(&git_repository::objs::Blob {
+            data: b"unreferenced".to_vec(),
+        })?;
+        easy.head()?.into_fully_peeled_id().expect("born")?.detach()
+    };
+    assert_eq!(unsynced(&repo), 1, "single objects are written without synchronization");
+    repo.amend(
+        "HEAD",
+        head,
+        git_repository::commit::amend::Changes {
+            message: Some("amended".into()),
+            ..Default::default()
+        },
+        git_repository::lock::acquire::Fail::Immediately,
+    )?;
+    assert_eq!(
+        unsynced(&repo),
+        0,
+        "all objects are synchronized before references are updated"
+    );
+    Ok(())
+}
+
+#[test]
+fn thread_limit_is_configured() -> crate::Result {
+    use git_features::parallel::threads::Limit;
+
+    let (repo, _keep) = crate::repo_rw("make_basic_repo.sh")?;
+    assert_eq!(repo.thread_limit()?, None, "nothing is configured by default");
+
+    let mut config = std::fs::OpenOptions::new()
+        .append(true)
+        .open(repo.git_dir().join("config"))?;
+    std::io::Write::write_all(&mut config, b"[gitoxide]\n\tthreads = soft\n")?;
+    assert_eq!(repo.thread_limit()?, Some(Limit::AllButOne));
+
+    std::io::Write::write_all(&mut config, b"[gitoxide]\n\tthreads = 2\n")?;
+    assert_eq!(repo.thread_limit()?, Some(Limit::Count(2)));
+
+    std::io::Write::write_all(&mut config, b"[gitoxide]\n\tthreads = many\n")?;
+    assert!(matches!(
+        repo.thread_limit(),
+        Err(git_repository::config::Error::InvalidThreadLimit(err)) if err.value == "many"
+    ));
+    Ok(())
+}
+
+mod ownership {
+    use std::path::Path;
+
+    use git_repository::{open, Repository};
+
+    /// Hand `dir` to another user, which is only possible if we are privileged.
+    fn chown_to_other_user(dir: &Path) -> std::io::Result<bool> {
+        Ok(std::process::Command::new("chown")
+            .args(["-R", "54321"])
+            .arg(dir)
+            .stderr(std::process::Stdio::null())
+            .status()?
+            .success())
+    }
+
+    #[test]
+    fn repositories_of_other_users_are_refused_unless_declared_safe() -> crate::Result {
+        let (repo, keep) = crate::repo_rw("make_basic_repo.sh")?;
+        let work_tree = repo.work_tree.clone().expect("non-bare");
+        drop(repo);
+        if !chown_to_other_user(keep.path())? {
+            return Ok(());
+        }
+
+        for options in [open::Options::default(), open::Options::isolated()] {
+            match Repository::open_opts(&work_tree, options) {
+                Err(open::Error::UnsafeDirectory { path }) => assert_eq!(path, work_tree),
+                res => panic!("expected the repository to be refused, got {:?}", res),
+            }
+        }
+        assert!(
+            matches!(
+                Repository::discover(&work_tree),
+                Err(git_repository::discover::Error::Open(open::Error::UnsafeDirectory { .. }))
+            ),
+            "discovery refuses to open unsafe directories as well"
+        );
+
+        for safe_directory in [work_tree.clone(), "*".into()] {
+            let options = open::Options {
+                safe_directories: vec![safe_directory],
+                ..open::Options::isolated()
+            };
+            assert_eq!(Repository::open_opts(&work_tree, options)?.work_tree, Some(work_tree.clone()));
+        }
+        Ok(())
+    }
+}
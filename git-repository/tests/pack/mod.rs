@@ -0,0 +1,62 @@
+mod generation {
+    use std::io::Write;
+
+    use git_repository::{pack::generation::Options, Repository};
+
+    fn append_config(repo: &Repository, config: &str) -> crate::Result {
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(repo.git_dir().join("config"))?;
+        file.write_all(config.as_bytes())?;
+        Ok(())
+    }
+
+    #[test]
+    fn defaults_depend_on_the_repository_being_bare() -> crate::Result {
+        let (repo, keep) = crate::repo_rw("make_basic_repo.sh")?;
+        assert_eq!(repo.pack_generation_options()?, Options::default());
+
+        let bare = Repository::open(keep.path().join("bare.git"))?;
+        assert_eq!(
+            bare.pack_generation_options()?,
+            Options {
+                write_bitmaps: true,
+                ..Default::default()
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn configured_values_are_used() -> crate::Result {
+        let (repo, _keep) = crate::repo_rw("make_basic_repo.sh")?;
+        append_config(
+            &repo,
+            "[pack]\n\twindow = 20\n\twindowMemory = 1G\n\tdepth = 10000\n\tthreads = 3\n\tdeltaCacheSize = 0\n\twriteBitmaps = true\n[repack]\n\twriteBitmaps = false\n",
+        )?;
+        assert_eq!(
+            repo.pack_generation_options()?,
+            Options {
+                window: 20,
+                window_memory: Some(1024 * 1024 * 1024),
+                depth: git_repository::pack::generation::MAX_DEPTH,
+                thread_limit: Some(3),
+                delta_cache_size: None,
+                write_bitmaps: false,
+            },
+            "repack.writeBitmaps takes precedence over its deprecated alias, and 0 means unlimited"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_values_are_an_error() -> crate::Result {
+        let (repo, _keep) = crate::repo_rw("make_basic_repo.sh")?;
+        append_config(&repo, "[pack]\n\tdepth = -1\n")?;
+        assert_eq!(
+            repo.pack_generation_options().unwrap_err().to_string(),
+            "The value '-1' of 'pack.depth' is invalid"
+        );
+        Ok(())
+    }
+}
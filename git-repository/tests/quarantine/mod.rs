@@ -0,0 +1,145 @@
+use std::{process::Command, sync::atomic::AtomicBool};
+
+use git_repository::{
+    hash::Kind::Sha1,
+    objs::Kind,
+    prelude::{ReferenceAccessExt, Write},
+    quarantine::Error,
+    ObjectId, Repository,
+};
+
+fn head_id(repo: &Repository) -> crate::Result<ObjectId> {
+    Ok(repo.to_easy().head()?.into_fully_peeled_id().expect("born")?.detach())
+}
+
+fn has_object(repo: &Repository, id: ObjectId) -> std::io::Result<bool> {
+    Ok(Command::new("git")
+        .arg("--git-dir")
+        .arg(repo.git_dir())
+        .args(&["cat-file", "-e"])
+        .arg(id.to_string())
+        .status()?
+        .success())
+}
+
+/// Write a commit on top of `parent` with a single file into the odb of `quarantine`, returning its id.
+fn write_commit(
+    quarantine: &git_repository::quarantine::Quarantine<'_>,
+    parent: ObjectId,
+) -> crate::Result<(ObjectId, ObjectId)> {
+    let odb = quarantine.odb()?;
+    let blob = odb.write_buf(Kind::Blob, b"quarantined", Sha1)?;
+    let mut tree = b"100644 file\0".to_vec();
+    tree.extend_from_slice(blob.as_bytes());
+    let tree = odb.write_buf(Kind::Tree, &tree, Sha1)?;
+    let commit = format!(
+        "tree {}\nparent {}\nauthor a <a@example.com> 0 +0000\ncommitter a <a@example.com> 0 +0000\n\nincoming\n",
+        tree, parent
+    );
+    Ok((odb.write_buf(Kind::Commit, commit.as_bytes(), Sha1)?, blob))
+}
+
+#[test]
+fn verified_objects_are_migrated_into_the_repository() -> crate::Result {
+    let (repo, _keep) = crate::repo_rw("make_basic_repo.sh")?;
+    let quarantine = repo.quarantine()?;
+    let path = quarantine.path().to_owned();
+    assert!(path.starts_with(repo.objects_dir().canonicalize()?));
+    assert!(path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map_or(false, |n| n.starts_with("tmp_objdir-incoming-")));
+
+    let (commit, blob) = write_commit(&quarantine, head_id(&repo)?)?;
+    assert!(
+        quarantine.odb()?.contains(head_id(&repo)?),
+        "the repository is an alternate"
+    );
+    assert!(!has_object(&repo, commit)?);
+    assert_eq!(quarantine.verify(Some(commit))?, 3, "commit, tree and blob");
+
+    let outcome = quarantine.migrate()?;
+    assert_eq!(outcome.loose_objects, 3);
+    assert_eq!(outcome.packs, 0);
+    assert!(!path.exists());
+    assert!(has_object(&repo, commit)? && has_object(&repo, blob)?);
+    assert!(
+        !repo.objects_dir().join("info/alternates").exists(),
+        "the quarantine isn't left as alternate"
+    );
+    Ok(())
+}
+
+#[test]
+fn dropped_quarantines_are_removed_along_with_their_objects() -> crate::Result {
+    let (repo, _keep) = crate::repo_rw("make_basic_repo.sh")?;
+    let quarantine = repo.quarantine()?;
+    let path = quarantine.path().to_owned();
+    let (commit, _) = write_commit(&quarantine, head_id(&repo)?)?;
+    drop(quarantine);
+
+    assert!(!path.exists());
+    assert!(!has_object(&repo, commit)?);
+    Ok(())
+}
+
+#[test]
+fn verify_fails_on_missing_objects() -> crate::Result {
+    let (repo, _keep) = crate::repo_rw("make_basic_repo.sh")?;
+    let quarantine = repo.quarantine()?;
+    let missing = ObjectId::from_hex(b"0123456789012345678901234567890123456789")?;
+    let (commit, _) = write_commit(&quarantine, missing)?;
+    assert!(matches!(
+        quarantine.verify(Some(commit)),
+        Err(Error::MissingObject { id }) if id == missing
+    ));
+    Ok(())
+}
+
+#[test]
+fn received_packs_are_visible_to_hooks_and_migrated_index_last() -> crate::Result {
+    let source = crate::repo("make_basic_repo.sh")?;
+    let tip = head_id(&source)?;
+    let pack = {
+        let mut child = Command::new("git")
+            .arg("--git-dir")
+            .arg(source.git_dir())
+            .args(&["pack-objects", "--stdout", "--revs", "-q"])
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()?;
+        std::io::Write::write_all(child.stdin.as_mut().expect("piped"), b"HEAD\n")?;
+        let out = child.wait_with_output()?;
+        assert!(out.status.success());
+        out.stdout
+    };
+
+    let dir = tempfile::tempdir()?;
+    let repo = git_repository::init_bare(dir.path())?;
+    let quarantine = repo.quarantine()?;
+    quarantine.receive_pack(pack.as_slice(), &AtomicBool::new(false))?;
+    assert_eq!(
+        quarantine.verify(Some(tip))?,
+        6,
+        "two commits, three trees and one blob"
+    );
+
+    let cat_file = |with_env: bool| -> std::io::Result<bool> {
+        let mut cmd = Command::new("git");
+        cmd.arg("--git-dir")
+            .arg(dir.path())
+            .args(&["cat-file", "-e"])
+            .arg(tip.to_string());
+        if with_env {
+            cmd.envs(quarantine.env().iter().copied());
+        }
+        Ok(cmd.status()?.success())
+    };
+    assert!(cat_file(true)?, "hooks see quarantined objects");
+    assert!(!cat_file(false)?, "others don't");
+
+    let outcome = quarantine.migrate()?;
+    assert_eq!(outcome.packs, 1);
+    assert!(has_object(&repo, tip)?);
+    Ok(())
+}
@@ -24,7 +24,27 @@ fn basic_rw_repo() -> crate::Result<(Easy, tempfile::TempDir)> {
     easy_repo_rw("make_basic_repo.sh")
 }
 
+mod ahead_behind;
+mod checkout;
+mod commit;
+mod dirwalk;
 mod discover;
+mod dissociate;
 mod easy;
+mod fast_import;
+mod fetch_head;
+mod filter;
+mod follow;
+mod fs;
+mod gc;
+mod hook;
+mod in_memory;
+mod index;
 mod init;
+mod maintenance;
+mod open;
+mod pack;
+mod quarantine;
 mod reference;
+mod rewrite;
+mod sign;
@@ -0,0 +1,200 @@
+use git_repository::{
+    bstr::{BStr, BString, ByteSlice},
+    easy::Access,
+    objs::{CommitRef, TagRef},
+    prelude::{ObjectAccessExt, ReferenceAccessExt},
+    rewrite::{Filter, Options},
+    ObjectId,
+};
+
+const LOCK_MODE: git_repository::lock::acquire::Fail = git_repository::lock::acquire::Fail::Immediately;
+const REFS: [&str; 4] = [
+    "refs/heads/main",
+    "refs/heads/other",
+    "refs/tags/lightweight",
+    "refs/tags/v1",
+];
+
+struct Paths<F>(F);
+
+impl<F> Filter for Paths<F>
+where
+    F: FnMut(&BStr) -> Option<BString>,
+{
+    fn path(&mut self, path: &BStr) -> Option<BString> {
+        (self.0)(path)
+    }
+}
+
+struct UppercaseMessages;
+
+impl Filter for UppercaseMessages {
+    fn message(&mut self, _id: &git_repository::oid, message: &BStr) -> BString {
+        message.to_uppercase().into()
+    }
+}
+
+fn peeled(repo: &impl Access, name: &str) -> crate::Result<ObjectId> {
+    Ok(repo.find_reference(name)?.id().detach())
+}
+
+fn commit_of(repo: &impl Access, name: &str) -> crate::Result<ObjectId> {
+    let mut id = peeled(repo, name)?;
+    let object = repo.find_object(id)?;
+    if let Ok(tag) = TagRef::from_bytes(&object.data) {
+        id = tag.target();
+    }
+    Ok(id)
+}
+
+fn paths_of(repo: &impl Access, name: &str) -> crate::Result<Vec<String>> {
+    fn collect(repo: &impl Access, tree: ObjectId, prefix: &str, out: &mut Vec<String>) -> crate::Result {
+        let entries: Vec<_> = {
+            let tree = repo.find_object(tree)?;
+            git_repository::objs::TreeRef::from_bytes(&tree.data)?
+                .entries
+                .into_iter()
+                .map(|e| (e.filename.to_string(), e.mode.is_tree(), e.oid.to_owned()))
+                .collect()
+        };
+        for (name, is_tree, id) in entries {
+            let path = format!("{}{}", prefix, name);
+            if is_tree {
+                collect(repo, id, &format!("{}/", path), out)?;
+            } else {
+                out.push(path);
+            }
+        }
+        Ok(())
+    }
+    let commit = repo.find_object(commit_of(repo, name)?)?;
+    let tree = CommitRef::from_bytes(&commit.data)?.tree();
+    drop(commit);
+    let mut out = Vec::new();
+    collect(repo, tree, "", &mut out)?;
+    Ok(out)
+}
+
+#[test]
+fn files_can_be_purged_from_history() -> crate::Result {
+    let (repo, _keep) = crate::repo_rw("make_fast_export_repo.sh")?;
+    let outcome = repo.rewrite_history(
+        REFS,
+        &mut Paths(|path: &BStr| (path != "file with spaces").then(|| path.to_owned())),
+        Options::default(),
+    )?;
+    assert_eq!(
+        outcome
+            .references
+            .keys()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>(),
+        vec!["refs/heads/main", "refs/heads/other", "refs/tags/lightweight"],
+        "v1 points to a commit which never had the file"
+    );
+    assert!(outcome.commits.values().all(Option::is_some), "no commit is empty");
+
+    let easy = repo.to_easy();
+    assert_eq!(
+        peeled(&easy, "refs/heads/main")?,
+        outcome.references[&BString::from("refs/heads/main")].previous,
+        "nothing changes before the rewrite is applied"
+    );
+    assert!(paths_of(&easy, "refs/heads/main")?.contains(&"file with spaces".into()));
+    drop(easy);
+
+    let references = outcome.references.clone();
+    repo.apply_rewrite(outcome, LOCK_MODE)?;
+    let easy = repo.into_easy();
+    for (name, update) in references {
+        assert_eq!(Some(peeled(&easy, name.to_str()?)?), update.new);
+    }
+    assert_eq!(
+        paths_of(&easy, "refs/heads/main")?,
+        vec!["a", "dir/b", "dir/run", "link"]
+    );
+    assert_eq!(paths_of(&easy, "refs/tags/v1")?, vec!["a", "dir/b"]);
+    Ok(())
+}
+
+#[test]
+fn subdirectories_can_be_extracted_with_empty_commits_pruned() -> crate::Result {
+    let (repo, _keep) = crate::repo_rw("make_fast_export_repo.sh")?;
+    let easy = repo.to_easy();
+    let (c3, c2) = {
+        let merge = easy.find_object(peeled(&easy, "refs/heads/main")?)?;
+        let merge = CommitRef::from_bytes(&merge.data)?;
+        let mut parents = merge.parents();
+        (parents.next().expect("first"), parents.next().expect("second"))
+    };
+    let c1 = {
+        let c2 = easy.find_object(c2)?;
+        let c2 = CommitRef::from_bytes(&c2.data)?;
+        let parent = c2.parents().next().expect("parent");
+        parent
+    };
+    drop(easy);
+
+    let outcome = repo.rewrite_history(
+        ["refs/heads/main"],
+        &mut Paths(|path: &BStr| path.strip_prefix(b"dir/sub/").map(|p| p.as_bstr().to_owned())),
+        Options::default(),
+    )?;
+    assert_eq!(
+        outcome.commits[&c2], outcome.commits[&c1],
+        "c2 doesn't touch the subdirectory and is replaced by its parent"
+    );
+    assert_ne!(outcome.commits[&c3], outcome.commits[&c1], "c3 deletes the file");
+
+    repo.apply_rewrite(outcome, LOCK_MODE)?;
+    let easy = repo.into_easy();
+    assert_eq!(paths_of(&easy, "refs/heads/main")?, Vec::<String>::new());
+    assert_eq!(
+        paths_of(&easy, "refs/heads/other")?.len(),
+        6,
+        "references that weren't rewritten are untouched"
+    );
+    Ok(())
+}
+
+#[test]
+fn messages_of_commits_and_tags_can_be_rewritten() -> crate::Result {
+    let (repo, _keep) = crate::repo_rw("make_fast_export_repo.sh")?;
+    let outcome = repo.rewrite_history(REFS, &mut UppercaseMessages, Options::default())?;
+    assert_eq!(outcome.commits.len(), 4);
+    assert!(outcome.commits.iter().all(|(old, new)| Some(*old) != *new));
+    repo.apply_rewrite(outcome, LOCK_MODE)?;
+
+    let easy = repo.into_easy();
+    let tag = easy.find_object(peeled(&easy, "refs/tags/v1")?)?;
+    assert_eq!(TagRef::from_bytes(&tag.data)?.message, "ANNOTATED TAG\n");
+    drop(tag);
+    let commit = easy.find_object(commit_of(&easy, "refs/tags/lightweight")?)?;
+    assert_eq!(CommitRef::from_bytes(&commit.data)?.message, "MERGE\n");
+    Ok(())
+}
+
+#[test]
+fn unchanged_history_does_not_change_references() -> crate::Result {
+    let repo = crate::repo("make_fast_export_repo.sh")?;
+    struct Noop;
+    impl Filter for Noop {}
+    let outcome = repo.rewrite_history(REFS, &mut Noop, Options::default())?;
+    assert!(outcome.references.is_empty());
+    assert!(outcome.commits.iter().all(|(old, new)| Some(*old) == *new));
+    Ok(())
+}
+
+#[test]
+fn references_are_deleted_if_all_their_commits_are_pruned() -> crate::Result {
+    let (repo, _keep) = crate::repo_rw("make_fast_export_repo.sh")?;
+    let outcome = repo.rewrite_history(REFS, &mut Paths(|_: &BStr| None), Options::default())?;
+    assert!(outcome.references.values().all(|update| update.new.is_none()));
+    repo.apply_rewrite(outcome, LOCK_MODE)?;
+
+    let easy = repo.into_easy();
+    for name in REFS {
+        assert!(easy.try_find_reference(name)?.is_none());
+    }
+    Ok(())
+}
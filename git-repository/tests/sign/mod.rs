@@ -0,0 +1,188 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use git_repository as git;
+use git_repository::{
+    bstr::{BString, ByteSlice},
+    prelude::{ObjectAccessExt, ReferenceAccessExt, RepositoryAccessExt},
+    sign,
+};
+
+const SIGNATURE: &str = "-----BEGIN PGP SIGNATURE-----\n\nc2lnbmF0dXJl\n-----END PGP SIGNATURE-----\n";
+
+/// Return a signer which records all payloads it signed into `payloads`.
+fn recording_signer(payloads: std::rc::Rc<std::cell::RefCell<Vec<BString>>>) -> Box<sign::Fn> {
+    Box::new(move |payload: &[u8]| {
+        payloads.borrow_mut().push(payload.into());
+        Ok(SIGNATURE.into())
+    })
+}
+
+#[test]
+fn commit_signed_embeds_the_signature_of_the_unsigned_commit() -> crate::Result {
+    let tmp = tempfile::tempdir()?;
+    let repo = git::init(&tmp)?.into_easy();
+    let empty_tree_id = repo.write_object(&git::objs::Tree::empty())?.detach();
+    let author = git::actor::Signature::empty();
+    let payloads = Default::default();
+    let id = repo.commit_signed(
+        "HEAD",
+        &author.to_ref(),
+        &author.to_ref(),
+        "initial",
+        empty_tree_id,
+        git::commit::NO_PARENT_IDS,
+        &mut *recording_signer(std::rc::Rc::clone(&payloads)),
+    )?;
+
+    let object = id.object()?;
+    let commit = object.to_commit();
+    assert_eq!(
+        commit.extra_headers().pgp_signature(),
+        Some(SIGNATURE.trim_end().as_bytes().as_bstr()),
+        "the signature is stored without trailing newline like git does"
+    );
+    let payloads = payloads.borrow();
+    assert_eq!(payloads.len(), 1);
+    let unsigned = git::objs::Commit {
+        extra_headers: Vec::new(),
+        ..commit.into()
+    };
+    let mut expected_payload = Vec::new();
+    git::objs::WriteTo::write_to(&unsigned, &mut expected_payload)?;
+    assert_eq!(payloads[0], expected_payload, "the commit without signature is signed");
+    assert_eq!(repo.head()?.into_fully_peeled_id().expect("born")?, id);
+    Ok(())
+}
+
+#[test]
+fn tag_signed_appends_the_signature_to_the_signed_payload() -> crate::Result {
+    let (repo, _keep) = crate::basic_rw_repo()?;
+    let head_id = repo.head()?.peeled()?.id().expect("born");
+    let payloads = Default::default();
+    let tag_ref = repo.tag_signed(
+        "v1.0.0",
+        &head_id,
+        git::objs::Kind::Commit,
+        Some(&repo.committer()?.to_ref()),
+        "the message",
+        git::refs::transaction::PreviousValue::MustNotExist,
+        &mut *recording_signer(std::rc::Rc::clone(&payloads)),
+    )?;
+
+    let object = tag_ref.id().object()?;
+    let payloads = payloads.borrow();
+    assert_eq!(payloads.len(), 1);
+    assert_eq!(
+        *object.data,
+        [payloads[0].as_slice(), SIGNATURE.as_bytes()].concat(),
+        "git verifies tags by checking the signature against everything before it"
+    );
+    let tag = object.try_to_tag()?;
+    assert_eq!(tag.message, "the message");
+    assert_eq!(tag.pgp_signature, Some(SIGNATURE.as_bytes().as_bstr()));
+    Ok(())
+}
+
+#[test]
+fn failing_signers_abort_the_commit() -> crate::Result {
+    let tmp = tempfile::tempdir()?;
+    let repo = git::init(&tmp)?.into_easy();
+    let empty_tree_id = repo.write_object(&git::objs::Tree::empty())?.detach();
+    let author = git::actor::Signature::empty();
+    let err = repo
+        .commit_signed(
+            "HEAD",
+            &author.to_ref(),
+            &author.to_ref(),
+            "initial",
+            empty_tree_id,
+            git::commit::NO_PARENT_IDS,
+            &mut |_: &[u8]| Ok(BString::from("\n")),
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        git::easy::commit::Error::Sign(sign::Error::EmptySignature)
+    ));
+    assert!(repo.head()?.id().is_none(), "no commit was made");
+    Ok(())
+}
+
+/// Install a program into `dir` which records its arguments and input in `args` and `payload` files next to it, and outputs a
+/// signature or fails with a message if the `fail` file exists.
+#[cfg(unix)]
+fn install_signer(dir: &Path) -> crate::Result<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+    let program = dir.join("signer");
+    fs::write(
+        &program,
+        format!(
+            "#!/bin/sh\ncd '{}'\necho \"$@\" > args\ncat > payload\nif [ -f fail ]; then echo 'no key' >&2; exit 2; fi\nprintf '%b' '{}'\n",
+            dir.display(),
+            SIGNATURE.replace('\n', "\\n")
+        ),
+    )?;
+    fs::set_permissions(&program, fs::Permissions::from_mode(0o755))?;
+    Ok(program)
+}
+
+#[test]
+#[cfg(unix)]
+fn gpg_signs_the_payload_with_the_given_key() -> crate::Result {
+    let dir = tempfile::tempdir()?;
+    let mut sign = sign::gpg(install_signer(dir.path())?, Some("KEYID".into()));
+    assert_eq!(sign(b"payload").expect("signing succeeds"), SIGNATURE);
+    assert_eq!(
+        fs::read_to_string(dir.path().join("args"))?,
+        "--status-fd=2 -bsa -u KEYID\n"
+    );
+    assert_eq!(fs::read(dir.path().join("payload"))?, b"payload");
+
+    fs::write(dir.path().join("fail"), "")?;
+    let err = sign(b"payload").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "'{}' failed to sign the object with exit status: 2: no key",
+            dir.path().join("signer").display()
+        )
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn from_config_uses_the_configured_format_program_and_key() -> crate::Result {
+    let (repo, keep) = crate::repo_rw("make_basic_repo.sh")?;
+    let program = install_signer(keep.path())?;
+    let config = keep.path().join(".git").join("config");
+    let mut content = fs::read_to_string(&config)?;
+    content.push_str("[gpg]\n\tformat = ssh\n");
+    fs::write(&config, &content)?;
+    assert!(matches!(
+        sign::from_config(&repo),
+        Err(sign::from_config::Error::MissingSshKey)
+    ));
+
+    content.push_str(&format!(
+        "[gpg \"ssh\"]\n\tprogram = {}\n[user]\n\tsigningKey = /path/to/key\n",
+        program.display()
+    ));
+    fs::write(&config, &content)?;
+    let mut sign = sign::from_config(&repo)?.expect("programs may be executed");
+    assert_eq!(sign(b"payload").expect("signing succeeds"), SIGNATURE);
+    assert_eq!(
+        fs::read_to_string(keep.path().join("args"))?,
+        "-Y sign -n git -f /path/to/key\n"
+    );
+
+    let isolated = git::Repository::open_opts(keep.path(), git::open::Options::isolated())?;
+    assert!(
+        sign::from_config(&isolated)?.is_none(),
+        "no signer is created without permission to execute programs"
+    );
+    Ok(())
+}
@@ -0,0 +1,22 @@
+#![no_main]
+
+use git_transport::client::{capabilities::Mode, Capabilities};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    for mode in &[Mode::Strict, Mode::Lenient] {
+        if let Ok((caps, _)) = Capabilities::from_bytes_with_mode(data, *mode) {
+            for cap in caps.iter() {
+                // Don't name this _; Rust may optimize it out.
+                let _values = cap.values().map(Iterator::count);
+            }
+        }
+        let text = String::from_utf8_lossy(data);
+        let mut lines = text.lines().map(|line| Ok(line.to_owned()));
+        let first_line = lines.next();
+        let remaining_lines = lines.filter_map(Result::ok).collect::<Vec<String>>().join("\n");
+        if let Ok(caps) = Capabilities::from_lines_with_mode(first_line, remaining_lines, *mode) {
+            let _count = caps.iter().count();
+        }
+    }
+});
@@ -76,3 +76,21 @@ impl<'a, T: io::Read> ExtendedBufRead for git_packetline::read::WithSidebands<'a
         })
     }
 }
+
+impl<'a, T: ExtendedBufRead> ExtendedBufRead for git_features::interrupt::Read<'a, T> {
+    fn set_progress_handler(&mut self, handle_progress: Option<HandleProgress>) {
+        self.inner.set_progress_handler(handle_progress)
+    }
+    fn peek_data_line(&mut self) -> Option<io::Result<Result<&[u8], Error>>> {
+        if self.should_interrupt.load(std::sync::atomic::Ordering::Relaxed) {
+            return Some(Err(git_features::interrupt::error()));
+        }
+        self.inner.peek_data_line()
+    }
+    fn reset(&mut self, version: Protocol) {
+        self.inner.reset(version)
+    }
+    fn stopped_at(&self) -> Option<MessageKind> {
+        self.inner.stopped_at()
+    }
+}
@@ -1,9 +1,12 @@
-use std::process::{self, Command, Stdio};
+use std::{
+    process::{self, Command, Stdio},
+    sync::{atomic::AtomicBool, Arc},
+};
 
 use bstr::{BString, ByteSlice};
 
 use crate::{
-    client::{self, git, MessageKind, RequestWriter, SetServiceResponse, WriteMode},
+    client::{self, git, MessageKind, RequestWriter, SetServiceResponse, TransportWithoutIO, WriteMode},
     Protocol, Service,
 };
 
@@ -36,13 +39,17 @@ pub struct SpawnProcessOnDemand {
     ssh_program: Option<String>,
     ssh_args: Vec<String>,
     ssh_env: Vec<(&'static str, String)>,
+    ssh_auth: Option<client::SshAuth>,
     connection: Option<git::Connection<process::ChildStdout, process::ChildStdin>>,
     child: Option<process::Child>,
+    should_interrupt: Arc<AtomicBool>,
 }
 
 impl Drop for SpawnProcessOnDemand {
     fn drop(&mut self) {
         if let Some(mut child) = self.child.take() {
+            // Close stdin first, the process might still be waiting for input.
+            self.connection.take();
             child.wait().ok();
         }
     }
@@ -63,9 +70,11 @@ impl SpawnProcessOnDemand {
             ssh_program: Some(program),
             ssh_args: args.into_iter().map(|s| s.into()).collect(),
             ssh_env: env.into_iter().map(|(k, v)| (k, v.into())).collect(),
+            ssh_auth: None,
             child: None,
             connection: None,
             desired_version: version,
+            should_interrupt: Default::default(),
         }
     }
     fn new_local(path: BString, version: Protocol) -> SpawnProcessOnDemand {
@@ -81,9 +90,11 @@ impl SpawnProcessOnDemand {
             ssh_program: None,
             ssh_args: Vec::new(),
             ssh_env: Vec::new(),
+            ssh_auth: None,
             child: None,
             connection: None,
             desired_version: version,
+            should_interrupt: Default::default(),
         }
     }
 }
@@ -100,6 +111,21 @@ impl client::TransportWithoutIO for SpawnProcessOnDemand {
             .request(write_mode, on_into_read)
     }
 
+    fn set_ssh_auth(&mut self, auth: client::SshAuth) -> Result<(), client::Error> {
+        if self.ssh_program.is_none() {
+            return Err(client::Error::SshAuthUnsupported);
+        }
+        self.ssh_auth = Some(auth);
+        Ok(())
+    }
+
+    fn set_should_interrupt(&mut self, should_interrupt: Arc<AtomicBool>) {
+        if let Some(connection) = self.connection.as_mut() {
+            connection.set_should_interrupt(Arc::clone(&should_interrupt));
+        }
+        self.should_interrupt = should_interrupt;
+    }
+
     fn to_url(&self) -> String {
         self.url.to_string()
     }
@@ -127,6 +153,14 @@ impl client::Transport for SpawnProcessOnDemand {
             cmd.env_remove(env_to_remove);
         }
         cmd.envs(std::mem::take(&mut self.ssh_env));
+        if let Some(auth) = &self.ssh_auth {
+            for path in &auth.identity_files {
+                cmd.arg("-i").arg(path);
+            }
+            if !auth.use_agent {
+                cmd.arg("-o").arg("IdentityAgent=none");
+            }
+        }
         cmd.args(&mut self.ssh_args);
         cmd.stdin(Stdio::piped()).stdout(Stdio::piped());
         if self.ssh_program.is_some() {
@@ -134,13 +168,17 @@ impl client::Transport for SpawnProcessOnDemand {
         }
         cmd.arg("--strict").arg("--timeout=0").arg(self.path.to_os_str_lossy());
 
+        // the process isn't waited on, so its exit isn't traced
+        git_features::trace::child(&mut cmd);
         let mut child = cmd.spawn()?;
-        self.connection = Some(git::Connection::new_for_spawned_process(
+        let mut connection = git::Connection::new_for_spawned_process(
             child.stdout.take().expect("stdout configured"),
             child.stdin.take().expect("stdin configured"),
             self.desired_version,
             self.path.clone(),
-        ));
+        );
+        connection.set_should_interrupt(Arc::clone(&self.should_interrupt));
+        self.connection = Some(connection);
         self.child = Some(child);
         let c = self
             .connection
@@ -17,11 +17,14 @@ struct Handler {
     send_data: Option<pipe::Writer>,
     receive_body: Option<pipe::Reader>,
     checked_status: bool,
+    /// The status of a redirect response whose headers are being read, along with its location once seen.
+    redirect: Option<(usize, Option<String>)>,
 }
 
 impl Handler {
     fn reset(&mut self) {
         self.checked_status = false;
+        self.redirect = None;
     }
     fn parse_status_inner(data: &[u8]) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
         let code = data
@@ -60,12 +63,36 @@ impl curl::easy::Handler for Handler {
     fn header(&mut self, data: &[u8]) -> bool {
         match self.send_header.as_mut() {
             Some(writer) => {
-                if self.checked_status {
+                if let Some((status, location)) = self.redirect.as_mut() {
+                    let line = String::from_utf8_lossy(data);
+                    let line = line.trim();
+                    if !line.is_empty() {
+                        if let Some((name, value)) = line.split_once(':') {
+                            if name.eq_ignore_ascii_case("location") {
+                                *location = Some(value.trim().to_owned());
+                            }
+                        }
+                        return true;
+                    }
+                    let err: Box<dyn std::error::Error + Send + Sync> = match location.take() {
+                        Some(location) => Box::new(http::Error::Redirect {
+                            status: *status,
+                            location,
+                        }),
+                        None => format!("Received HTTP status {}", status).into(),
+                    };
+                    writer.channel.send(Err(io::Error::new(io::ErrorKind::Other, err))).ok();
+                    false
+                } else if self.checked_status {
                     writer.write_all(data).is_ok()
                 } else {
                     self.checked_status = true;
                     match Handler::parse_status(data) {
                         None => true,
+                        Some((status, _)) if (300..=399).contains(&status) => {
+                            self.redirect = Some((status, None));
+                            true
+                        }
                         Some((status, err)) => {
                             writer
                                 .channel
@@ -2,6 +2,7 @@ use std::{
     borrow::Cow,
     convert::Infallible,
     io::{self, BufRead, Read},
+    sync::{atomic::AtomicBool, Arc},
 };
 
 use git_packetline::PacketLineRef;
@@ -33,6 +34,8 @@ pub struct Transport<H: Http> {
     service: Option<Service>,
     line_provider: Option<git_packetline::StreamingPeekableIter<H::ResponseBody>>,
     identity: Option<client::Identity>,
+    extra_headers: Vec<String>,
+    should_interrupt: Arc<AtomicBool>,
 }
 
 impl Transport<Impl> {
@@ -48,19 +51,16 @@ impl Transport<Impl> {
             http: Impl::default(),
             line_provider: None,
             identity: None,
+            extra_headers: Vec::new(),
+            should_interrupt: Default::default(),
         }
     }
 }
 
 impl<H: Http> Transport<H> {
-    fn check_content_type(service: Service, kind: &str, headers: <H as Http>::Headers) -> Result<(), client::Error> {
+    fn check_content_type(service: Service, kind: &str, headers: &[String]) -> Result<(), client::Error> {
         let wanted_content_type = format!("Content-Type: application/x-{}-{}", service.as_str(), kind);
-        if !headers
-            .lines()
-            .collect::<Result<Vec<_>, _>>()?
-            .iter()
-            .any(|l| l == &wanted_content_type)
-        {
+        if !headers.iter().any(|l| l == &wanted_content_type) {
             return Err(client::Error::Http(Error::Detail(format!(
                 "Didn't find '{}' header to indicate 'smart' protocol, and 'dumb' protocol is not supported.",
                 wanted_content_type
@@ -69,28 +69,111 @@ impl<H: Http> Transport<H> {
         Ok(())
     }
 
+    /// Follow the redirect to `location` in response to the request for `url`, which was made by appending `suffix` to our
+    /// url, and return the url to request instead.
+    ///
+    /// Like `git`, only the initial request may be redirected to another host, in which case the identity is dropped as it
+    /// belongs to the original host. Our url is updated to the one of the repository the request was redirected to.
+    fn follow_redirect(
+        &mut self,
+        url: &str,
+        location: &str,
+        suffix: &str,
+        num_redirects: &mut usize,
+    ) -> Result<String, client::Error> {
+        let refuse = |reason: String| Err(client::Error::Http(Error::Detail(reason)));
+        *num_redirects += 1;
+        if *num_redirects > MAX_REDIRECTS {
+            return refuse(format!("Refusing to follow more than {} redirects", MAX_REDIRECTS));
+        }
+        let target = resolve_location(url, location);
+        if !(target.starts_with("http://") || target.starts_with("https://")) {
+            return refuse(format!("Refusing to follow redirect to non-http url '{}'", target));
+        }
+        if url.starts_with("https://") && target.starts_with("http://") {
+            return refuse(format!("Refusing to follow redirect from https to '{}'", target));
+        }
+        if !origin(url).eq_ignore_ascii_case(origin(&target)) {
+            if self.service.is_some() {
+                return refuse(format!(
+                    "Refusing to follow redirect to '{}' on another host as only the initial request may be redirected there",
+                    target
+                ));
+            }
+            self.identity = None;
+        }
+        match target.strip_suffix(suffix) {
+            Some(base) => self.url = base.trim_end_matches('/').to_owned(),
+            None => {
+                return refuse(format!(
+                    "Cannot update the repository url from redirect to '{}' which doesn't end in '{}'",
+                    target, suffix
+                ))
+            }
+        }
+        Ok(target)
+    }
+
     #[allow(clippy::unnecessary_wraps, unknown_lints)]
-    fn add_basic_auth_if_present(&self, headers: &mut Vec<Cow<'_, str>>) -> Result<(), client::Error> {
+    fn add_auth_and_extra_headers(&self, headers: &mut Vec<Cow<'_, str>>) -> Result<(), client::Error> {
         if let Some(identity) = &self.identity {
-            match identity {
-                client::Identity::Account { username, password } => {
-                    #[cfg(not(debug_assertions))]
-                    if self.url.starts_with("http://") {
-                        return Err(client::Error::AuthenticationRefused(
-                            "Will not send credentials in clear text over http",
-                        ));
-                    }
-                    headers.push(Cow::Owned(format!(
-                        "Authorization: Basic {}",
-                        base64::encode(format!("{}:{}", username, password))
-                    )))
-                }
+            #[cfg(not(debug_assertions))]
+            if self.url.starts_with("http://") {
+                return Err(client::Error::AuthenticationRefused(
+                    "Will not send credentials in clear text over http",
+                ));
             }
+            headers.push(Cow::Owned(match identity {
+                client::Identity::Account { username, password } => format!(
+                    "Authorization: Basic {}",
+                    base64::encode(format!("{}:{}", username, password))
+                ),
+                client::Identity::Token { token } => format!("Authorization: Bearer {}", token),
+            }))
         }
+        headers.extend(self.extra_headers.iter().cloned().map(Cow::Owned));
         Ok(())
     }
 }
 
+/// The maximum amount of redirects to follow for a single request, which is the same as for `git`.
+const MAX_REDIRECTS: usize = 20;
+
+/// Return the location of the redirect if `err` was caused by one.
+fn redirect_location(err: &io::Error) -> Option<&str> {
+    match err.get_ref().and_then(|err| err.downcast_ref::<Error>()) {
+        Some(Error::Redirect { location, .. }) => Some(location),
+        _ => None,
+    }
+}
+
+/// Return the scheme and host of `url`, including the port if present, like `https://example.com:8080`.
+fn origin(url: &str) -> &str {
+    let host_start = url.find("://").map_or(0, |pos| pos + 3);
+    match url[host_start..].find(&['/', '?'][..]) {
+        Some(pos) => &url[..host_start + pos],
+        None => url,
+    }
+}
+
+/// Resolve `location` of a redirect in response to the request for `url`, which may be relative to it.
+fn resolve_location(url: &str, location: &str) -> String {
+    if location.contains("://") {
+        location.to_owned()
+    } else if location.starts_with("//") {
+        format!("{}:{}", url.split(':').next().unwrap_or("http"), location)
+    } else if location.starts_with('/') {
+        format!("{}{}", origin(url), location)
+    } else {
+        let path = url.split('?').next().unwrap_or(url);
+        format!(
+            "{}{}",
+            &path[..path.rfind('/').map_or(path.len(), |pos| pos + 1)],
+            location
+        )
+    }
+}
+
 fn append_url(base: &str, suffix: &str) -> String {
     if base.ends_with('/') {
         format!("{}{}", base, suffix)
@@ -105,6 +188,17 @@ impl<H: Http> client::TransportWithoutIO for Transport<H> {
         Ok(())
     }
 
+    fn set_extra_headers(&mut self, headers: Vec<String>) -> Result<(), client::Error> {
+        if let Some(malformed) = headers
+            .iter()
+            .find(|header| !header.contains(':') || header.contains(&['\r', '\n'][..]))
+        {
+            return Err(client::Error::MalformedExtraHeader(malformed.to_owned()));
+        }
+        self.extra_headers = headers;
+        Ok(())
+    }
+
     fn request(
         &mut self,
         write_mode: client::WriteMode,
@@ -119,7 +213,7 @@ impl<H: Http> client::TransportWithoutIO for Transport<H> {
             "Expect:".into(), // needed to avoid sending Expect: 100-continue, which adds another response and only CURL wants that
         ];
         let mut dynamic_headers = Vec::new();
-        self.add_basic_auth_if_present(&mut dynamic_headers)?;
+        self.add_auth_and_extra_headers(&mut dynamic_headers)?;
         if self.actual_version != Protocol::V1 {
             dynamic_headers.push(Cow::Owned(format!(
                 "Git-Protocol: version={}",
@@ -139,16 +233,23 @@ impl<H: Http> client::TransportWithoutIO for Transport<H> {
         line_provider.replace(body);
         Ok(RequestWriter::new_from_bufread(
             post_body,
-            Box::new(HeadersThenBody::<H, _> {
-                service,
-                headers: Some(headers),
-                body: line_provider.as_read_without_sidebands(),
+            Box::new(git_features::interrupt::Read {
+                inner: HeadersThenBody::<H, _> {
+                    service,
+                    headers: Some(headers),
+                    body: line_provider.as_read_without_sidebands(),
+                },
+                should_interrupt: &self.should_interrupt,
             }),
             write_mode,
             on_into_read,
         ))
     }
 
+    fn set_should_interrupt(&mut self, should_interrupt: Arc<AtomicBool>) {
+        self.should_interrupt = should_interrupt;
+    }
+
     fn to_url(&self) -> String {
         self.url.to_owned()
     }
@@ -168,9 +269,10 @@ impl<H: Http> client::Transport for Transport<H> {
         service: Service,
         extra_parameters: &'a [(&'a str, Option<&'a str>)],
     ) -> Result<client::SetServiceResponse<'_>, client::Error> {
-        let url = append_url(&self.url, &format!("info/refs?service={}", service.as_str()));
+        let suffix = format!("info/refs?service={}", service.as_str());
+        let mut url = append_url(&self.url, &suffix);
         let static_headers = [Cow::Borrowed(self.user_agent_header)];
-        let mut dynamic_headers = Vec::<Cow<'_, str>>::new();
+        let mut git_protocol_header = None;
         if self.desired_version != Protocol::V1 || !extra_parameters.is_empty() {
             let mut parameters = if self.desired_version != Protocol::V1 {
                 let mut p = format!("version={}", self.desired_version as usize);
@@ -191,11 +293,23 @@ impl<H: Http> client::Transport for Transport<H> {
                     .collect::<Vec<_>>()
                     .join(":"),
             );
-            dynamic_headers.push(format!("Git-Protocol: {}", parameters).into());
+            git_protocol_header = Some(format!("Git-Protocol: {}", parameters));
         }
-        self.add_basic_auth_if_present(&mut dynamic_headers)?;
-        let GetResponse { headers, body } = self.http.get(&url, static_headers.iter().chain(&dynamic_headers))?;
-        <Transport<H>>::check_content_type(service, "advertisement", headers)?;
+        let mut num_redirects = 0;
+        let (headers, body) = loop {
+            let mut dynamic_headers = Vec::<Cow<'_, str>>::new();
+            dynamic_headers.extend(git_protocol_header.as_deref().map(Cow::Borrowed));
+            self.add_auth_and_extra_headers(&mut dynamic_headers)?;
+            let GetResponse { headers, body } = self.http.get(&url, static_headers.iter().chain(&dynamic_headers))?;
+            match headers.lines().collect::<Result<Vec<_>, _>>() {
+                Ok(headers) => break (headers, body),
+                Err(err) => match redirect_location(&err) {
+                    Some(location) => url = self.follow_redirect(&url, location, &suffix, &mut num_redirects)?,
+                    None => return Err(err.into()),
+                },
+            }
+        };
+        <Transport<H>>::check_content_type(service, "advertisement", &headers)?;
 
         let line_reader = self
             .line_provider
@@ -212,6 +326,7 @@ impl<H: Http> client::Transport for Transport<H> {
             ))));
         }
 
+        let should_interrupt = &*self.should_interrupt;
         let capabilities::recv::Outcome {
             capabilities,
             refs,
@@ -222,7 +337,12 @@ impl<H: Http> client::Transport for Transport<H> {
         Ok(client::SetServiceResponse {
             actual_protocol,
             capabilities,
-            refs,
+            refs: refs.map(|inner| {
+                Box::new(git_features::interrupt::Read {
+                    inner,
+                    should_interrupt,
+                }) as Box<dyn io::BufRead + '_>
+            }),
         })
     }
 }
@@ -236,7 +356,8 @@ struct HeadersThenBody<H: Http, B: Unpin> {
 impl<H: Http, B: Unpin> HeadersThenBody<H, B> {
     fn handle_headers(&mut self) -> io::Result<()> {
         if let Some(headers) = self.headers.take() {
-            <Transport<H>>::check_content_type(self.service, "result", headers)
+            let headers = headers.lines().collect::<Result<Vec<_>, _>>()?;
+            <Transport<H>>::check_content_type(self.service, "result", &headers)
                 .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?
         }
         Ok(())
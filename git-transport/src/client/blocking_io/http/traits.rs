@@ -15,6 +15,9 @@ quick_error! {
             from()
             source(err)
         }
+        Redirect { status: usize, location: String } {
+            display("Received HTTP status {} redirecting to '{}'", status, location)
+        }
     }
 }
 
@@ -48,6 +51,9 @@ impl<A, B, C> From<PostResponse<A, B, C>> for GetResponse<A, B> {
 }
 
 /// A trait to abstract the HTTP operations needed to power all git interactions: read via GET and write via POST.
+///
+/// Implementations must not follow redirects themselves, but fail reading the headers with an [`io::Error`] wrapping
+/// [`Error::Redirect`] to let the transport decide whether following them is safe.
 #[allow(clippy::type_complexity)]
 pub trait Http {
     /// A type providing headers line by line.
@@ -0,0 +1,254 @@
+//! Verify host keys against `known_hosts` files as written by OpenSSH.
+//!
+//! Transports connecting with the `ssh` program leave this to the program, but ssh implementations which can't spawn it,
+//! like library backends, have to verify the host key presented by the server themselves before authenticating.
+//!
+//! Plain and hashed host names, wildcards, negations and non-standard ports are supported. Entries with the
+//! `@cert-authority` marker are parsed but never match as host certificates aren't supported.
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+use bstr::{BString, ByteSlice};
+use git_features::hash::Sha1;
+
+/// The error returned by [`File::from_bytes()`], [`File::at()`] and [`verify()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not read or write known hosts file")]
+    Io(#[from] io::Error),
+    #[error("Line {line_number} of the known hosts file is malformed: {message}")]
+    Malformed { line_number: usize, message: &'static str },
+}
+
+/// A marker in front of an [`Entry`] to change its meaning.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Marker {
+    /// The key is the one of a certificate authority signing host certificates, which aren't supported.
+    CertAuthority,
+    /// The key is revoked and must never be accepted.
+    Revoked,
+}
+
+/// A line of a known hosts file, associating host patterns with a key.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Entry {
+    /// The marker in front of the entry, if present.
+    pub marker: Option<Marker>,
+    /// The comma separated host patterns, or a hashed host name like `|1|<salt>|<hash>`.
+    pub hosts: BString,
+    /// The type of the key, like `ssh-ed25519`.
+    pub key_type: BString,
+    /// The key in the wire format used by the ssh protocol.
+    pub key: Vec<u8>,
+}
+
+/// The outcome of verifying a host key with [`File::verify()`].
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Verdict {
+    /// The key is known for the host.
+    Trusted,
+    /// No key of the same type is known for the host, and the user should decide whether to trust it.
+    Unknown,
+    /// A different key of the same type is known for the host, which may indicate a man-in-the-middle attack.
+    Changed,
+    /// The key is revoked.
+    Revoked,
+}
+
+/// What to do with an [unknown][Verdict::Unknown] host key, as decided by the callback passed to [`verify()`].
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Decision {
+    /// Don't trust the key and abort the connection.
+    Reject,
+    /// Trust the key for this connection only.
+    AcceptOnce,
+    /// Trust the key and add it to the known hosts file.
+    AcceptAndRemember,
+}
+
+/// All entries of a known hosts file.
+#[derive(Default, Debug, PartialEq, Eq, Clone)]
+pub struct File {
+    /// The entries in the order they appear in the file.
+    pub entries: Vec<Entry>,
+}
+
+impl File {
+    /// Parse all entries in `data`, skipping empty lines and comments.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Error> {
+        let mut entries = Vec::new();
+        for (idx, line) in data.lines().enumerate() {
+            let line = line.trim_with(|c| c.is_ascii_whitespace());
+            if line.is_empty() || line.starts_with(b"#") {
+                continue;
+            }
+            let malformed = |message| Error::Malformed {
+                line_number: idx + 1,
+                message,
+            };
+            let mut fields = line.fields();
+            let mut hosts = fields.next().expect("non-empty line");
+            let marker = match hosts {
+                b"@cert-authority" => Some(Marker::CertAuthority),
+                b"@revoked" => Some(Marker::Revoked),
+                _ if hosts.starts_with(b"@") => return Err(malformed("unknown marker")),
+                _ => None,
+            };
+            if marker.is_some() {
+                hosts = fields.next().ok_or_else(|| malformed("missing host patterns"))?;
+            }
+            let key_type = fields.next().ok_or_else(|| malformed("missing key type"))?;
+            let key = fields.next().ok_or_else(|| malformed("missing key"))?;
+            entries.push(Entry {
+                marker,
+                hosts: hosts.into(),
+                key_type: key_type.into(),
+                key: base64::decode(key).map_err(|_| malformed("the key isn't valid base64"))?,
+            });
+        }
+        Ok(File { entries })
+    }
+
+    /// Read the known hosts file at `path`, which is considered empty if it doesn't exist.
+    pub fn at(path: impl AsRef<Path>) -> Result<Self, Error> {
+        match std::fs::read(path) {
+            Ok(data) => Self::from_bytes(&data),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(File::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Verify that `key` of `key_type` belongs to `host`, listening on `port` or the default port if `None`.
+    ///
+    /// Like OpenSSH, only keys of the same type are compared, so a host known with a key of another type is
+    /// [unknown][Verdict::Unknown].
+    pub fn verify(&self, host: &str, port: Option<u16>, key_type: &str, key: &[u8]) -> Verdict {
+        let name = host_name(host, port);
+        let mut verdict = Verdict::Unknown;
+        for entry in self.entries.iter().filter(|entry| entry.matches(&name)) {
+            match entry.marker {
+                Some(Marker::Revoked) if entry.key == key => return Verdict::Revoked,
+                Some(_) => {}
+                None if entry.key_type != key_type => {}
+                None if entry.key == key => verdict = Verdict::Trusted,
+                None if verdict == Verdict::Unknown => verdict = Verdict::Changed,
+                None => {}
+            }
+        }
+        verdict
+    }
+}
+
+impl Entry {
+    /// Return true if this entry applies to `host` listening on `port`, or the default port if `None`.
+    pub fn matches_host(&self, host: &str, port: Option<u16>) -> bool {
+        self.matches(&host_name(host, port))
+    }
+
+    fn matches(&self, name: &[u8]) -> bool {
+        if let Some(hashed) = self.hosts.strip_prefix(b"|1|") {
+            let mut parts = hashed.splitn_str(2, "|");
+            let salt = parts.next().and_then(|salt| base64::decode(salt).ok());
+            let hash = parts.next().and_then(|hash| base64::decode(hash).ok());
+            return match (salt, hash) {
+                (Some(salt), Some(hash)) => hmac_sha1(&salt, name)[..] == hash[..],
+                _ => false,
+            };
+        }
+        let mut matched = false;
+        for pattern in self.hosts.split_str(",") {
+            match pattern.strip_prefix(b"!") {
+                Some(negated) if wildmatch(&negated.to_ascii_lowercase(), name) => return false,
+                Some(_) => {}
+                None => matched |= wildmatch(&pattern.to_ascii_lowercase(), name),
+            }
+        }
+        matched
+    }
+}
+
+/// Verify `key` of `key_type` presented by `host` listening on `port` against the known hosts file at `path`, and return true
+/// if it may be used, like OpenSSH does with `StrictHostKeyChecking=ask`.
+///
+/// If the key is [unknown][Verdict::Unknown], `decide` is called to determine whether it should be trusted, and the key is
+/// appended to the file at `path` if it is to be remembered. [Changed][Verdict::Changed] and [revoked][Verdict::Revoked] keys
+/// are always rejected.
+pub fn verify(
+    path: impl AsRef<Path>,
+    host: &str,
+    port: Option<u16>,
+    key_type: &str,
+    key: &[u8],
+    decide: impl FnOnce() -> Decision,
+) -> Result<bool, Error> {
+    let path = path.as_ref();
+    Ok(match File::at(path)?.verify(host, port, key_type, key) {
+        Verdict::Trusted => true,
+        Verdict::Changed | Verdict::Revoked => false,
+        Verdict::Unknown => match decide() {
+            Decision::Reject => false,
+            Decision::AcceptOnce => true,
+            Decision::AcceptAndRemember => {
+                if let Some(dir) = path.parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+                let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+                file.write_all(&line(host, port, key_type, key))?;
+                true
+            }
+        },
+    })
+}
+
+/// Return a line of a known hosts file to trust `key` of `key_type` for `host` listening on `port`, or the default port if `None`.
+pub fn line(host: &str, port: Option<u16>, key_type: &str, key: &[u8]) -> BString {
+    let mut line = host_name(host, port);
+    line.push(b' ');
+    line.extend_from_slice(key_type.as_bytes());
+    line.push(b' ');
+    line.extend_from_slice(base64::encode(key).as_bytes());
+    line.push(b'\n');
+    line
+}
+
+/// Return the name of `host` as stored in known hosts files, which includes the `port` unless it is the default one.
+fn host_name(host: &str, port: Option<u16>) -> BString {
+    let host = host.to_ascii_lowercase();
+    match port {
+        Some(port) if port != 22 => format!("[{}]:{}", host, port).into(),
+        _ => host.into(),
+    }
+}
+
+/// Return true if `name` matches `pattern`, in which `*` matches any amount of characters and `?` matches exactly one.
+fn wildmatch(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|skip| wildmatch(rest, &name[skip..])),
+        Some((b'?', rest)) => !name.is_empty() && wildmatch(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && wildmatch(rest, &name[1..]),
+    }
+}
+
+/// Compute the HMAC-SHA1 of `message` with `key`, as used to hash host names.
+fn hmac_sha1(key: &[u8], message: &[u8]) -> [u8; 20] {
+    const BLOCK_SIZE: usize = 64;
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        let mut hasher = Sha1::default();
+        hasher.update(key);
+        block[..20].copy_from_slice(&hasher.digest());
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let mut inner = Sha1::default();
+    inner.update(&block.map(|b| b ^ 0x36));
+    inner.update(message);
+    let mut outer = Sha1::default();
+    outer.update(&block.map(|b| b ^ 0x5c));
+    outer.update(&inner.digest());
+    outer.digest()
+}
@@ -5,6 +5,10 @@ use quick_error::quick_error;
 
 use crate::{client::blocking_io, Protocol};
 
+///
+#[cfg(feature = "ssh-known-hosts")]
+pub mod known_hosts;
+
 quick_error! {
     /// The error used in [`connect()`].
     #[derive(Debug)]
@@ -28,6 +32,9 @@ quick_error! {
 ///
 /// Use `GIT_SSH_COMMAND` to override the `ssh` program to execute. This can be a script dealing with using the correct
 /// ssh key, for example.
+///
+/// Use [`set_ssh_auth()`][crate::client::TransportWithoutIO::set_ssh_auth()] on the returned transport to choose the keys to
+/// authenticate with.
 pub fn connect(
     host: &str,
     path: BString,
@@ -7,6 +7,9 @@ use quick_error::quick_error;
 use crate::client;
 use crate::Protocol;
 
+/// Determines how strictly capabilities are parsed.
+pub use git_packetline::decode::Mode;
+
 quick_error! {
     /// The error used in [`Capabilities::from_bytes()`] and [`Capabilities::from_lines()`].
     #[derive(Debug)]
@@ -27,6 +30,12 @@ quick_error! {
         UnsupportedVersion(wanted: Protocol, got: String) {
             display("Got unsupported version '{}', expected '{}'", got, *wanted as usize)
         }
+        DuplicateCapability(capability: BString) {
+            display("The capability '{}' was advertised more than once", capability)
+        }
+        TrailingGarbage(garbage: BString) {
+            display("Unexpected bytes '{}' after the capabilities", garbage)
+        }
         Io(err: io::Error) {
             display("An IO error occurred while reading V2 lines")
             from()
@@ -35,11 +44,25 @@ quick_error! {
     }
 }
 
+/// A recoverable deviation from the capabilities format as tolerated in [lenient][Mode::Lenient] mode.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub enum Anomaly {
+    /// The given capability was advertised more than once, and all but its first mention were dropped.
+    DuplicateCapability(BString),
+    /// The given bytes followed the capabilities or the version line and were ignored.
+    TrailingGarbage(BString),
+    /// The V1 line carrying the capabilities didn't end with a newline.
+    ///
+    /// Note that this is accepted in [strict][Mode::Strict] mode as well as receivers must not complain about it.
+    MissingNewline,
+}
+
 /// A structure to represent multiple [capabilities][Capability] or features supported by the server.
 #[derive(Debug, Clone)]
 pub struct Capabilities {
     data: BString,
     value_sep: u8,
+    anomalies: Vec<Anomaly>,
 }
 
 /// The name of a single capability.
@@ -79,20 +102,46 @@ impl Capabilities {
     ///
     /// Useful in case they are encoded within a `ref` behind a null byte.
     pub fn from_bytes(bytes: &[u8]) -> Result<(Capabilities, usize), Error> {
+        Self::from_bytes_with_mode(bytes, Mode::Strict)
+    }
+
+    /// Like [`from_bytes()`][Capabilities::from_bytes()], but parse according to `mode`, recording all tolerated
+    /// deviations from the format in [`anomalies()`][Capabilities::anomalies()].
+    pub fn from_bytes_with_mode(bytes: &[u8], mode: Mode) -> Result<(Capabilities, usize), Error> {
         let delimiter_pos = bytes.find_byte(0).ok_or(Error::MissingDelimitingNullByte)?;
         if delimiter_pos + 1 == bytes.len() {
             return Err(Error::NoCapabilities);
         }
-        let capabilities = &bytes[delimiter_pos + 1..];
+        let mut anomalies = Vec::new();
+        let mut capabilities = &bytes[delimiter_pos + 1..];
+        if let Some(garbage_pos) = capabilities.find_byte(0) {
+            let garbage = capabilities[garbage_pos..].as_bstr().to_owned();
+            match mode {
+                Mode::Strict => return Err(Error::TrailingGarbage(garbage)),
+                Mode::Lenient => anomalies.push(Anomaly::TrailingGarbage(garbage)),
+            }
+            capabilities = &capabilities[..garbage_pos];
+        }
         Ok((
             Capabilities {
-                data: capabilities.as_bstr().to_owned(),
+                data: deduplicate(capabilities, b' ', mode, &mut anomalies)?,
                 value_sep: b' ',
+                anomalies,
             },
             delimiter_pos,
         ))
     }
 
+    /// The capabilities of a V1 server which didn't advertise any, as is the case for repositories without references.
+    #[cfg(any(feature = "blocking-client", feature = "async-client"))]
+    fn none_advertised() -> Capabilities {
+        Capabilities {
+            data: BString::default(),
+            value_sep: b' ',
+            anomalies: Vec::new(),
+        }
+    }
+
     /// Parse capabilities from the given a `first_line` and the rest of the lines as single newline
     /// separated string via `remaining_lines`.
     ///
@@ -102,6 +151,16 @@ impl Capabilities {
     pub fn from_lines(
         first_line: Option<impl Into<std::io::Result<String>>>,
         remaining_lines: impl Into<String>,
+    ) -> Result<Capabilities, Error> {
+        Self::from_lines_with_mode(first_line, remaining_lines, Mode::Strict)
+    }
+
+    /// Like [`from_lines()`][Capabilities::from_lines()], but parse according to `mode`, recording all tolerated
+    /// deviations from the format in [`anomalies()`][Capabilities::anomalies()].
+    pub fn from_lines_with_mode(
+        first_line: Option<impl Into<std::io::Result<String>>>,
+        remaining_lines: impl Into<String>,
+        mode: Mode,
     ) -> Result<Capabilities, Error> {
         let version_line = first_line.map(Into::into).ok_or(Error::MissingVersionLine)??;
         let (name, value) = version_line.split_at(
@@ -112,15 +171,28 @@ impl Capabilities {
         if name != "version" {
             return Err(Error::MalformattedVersionLine(version_line));
         }
+        let mut anomalies = Vec::new();
         if value != " 2" {
-            return Err(Error::UnsupportedVersion(Protocol::V2, value.to_owned()));
+            match value.strip_prefix(" 2") {
+                Some(garbage) if mode == Mode::Lenient && !garbage.starts_with(|c: char| c.is_ascii_digit()) => {
+                    anomalies.push(Anomaly::TrailingGarbage(garbage.into()))
+                }
+                _ => return Err(Error::UnsupportedVersion(Protocol::V2, value.to_owned())),
+            }
         }
+        let data = remaining_lines.into();
         Ok(Capabilities {
             value_sep: b'\n',
-            data: remaining_lines.into().into(),
+            data: deduplicate(data.as_bytes(), b'\n', mode, &mut anomalies)?,
+            anomalies,
         })
     }
 
+    /// Returns all deviations from the format tolerated while parsing in [lenient][Mode::Lenient] mode, in the order they were encountered.
+    pub fn anomalies(&self) -> &[Anomaly] {
+        &self.anomalies
+    }
+
     /// Returns true of the given `feature` is mentioned in this list of capabilities.
     pub fn contains(&self, feature: &str) -> bool {
         self.capability(feature).is_some()
@@ -135,15 +207,38 @@ impl Capabilities {
     pub fn iter(&self) -> impl Iterator<Item = Capability<'_>> {
         self.data
             .split(move |b| *b == self.value_sep)
+            .filter(|c| !c.is_empty())
             .map(|c| Capability(c.as_bstr()))
     }
 }
 
+/// Return `capabilities` separated by `value_sep` without exact duplicates, which are an error in [strict][Mode::Strict] mode.
+fn deduplicate(capabilities: &[u8], value_sep: u8, mode: Mode, anomalies: &mut Vec<Anomaly>) -> Result<BString, Error> {
+    let mut seen = Vec::<&[u8]>::new();
+    let mut out = BString::default();
+    for capability in capabilities.split(|b| *b == value_sep) {
+        if seen.contains(&capability) {
+            match mode {
+                Mode::Strict => return Err(Error::DuplicateCapability(capability.into())),
+                Mode::Lenient => anomalies.push(Anomaly::DuplicateCapability(capability.into())),
+            }
+            continue;
+        }
+        if !seen.is_empty() {
+            out.push(value_sep);
+        }
+        out.extend_from_slice(capability);
+        seen.push(capability);
+    }
+    Ok(out)
+}
+
 /// internal use
 #[cfg(any(feature = "blocking-client", feature = "async-client"))]
 impl Capabilities {
     fn extract_protocol<'a>(
         capabilities_or_version: &'a git_packetline::PacketLineRef<'_>,
+        mode: Mode,
     ) -> Result<(git_packetline::TextRef<'a>, Protocol), client::Error> {
         let first_line = capabilities_or_version
             .as_text()
@@ -151,6 +246,10 @@ impl Capabilities {
 
         let line = first_line.as_bstr();
         let version = if line.starts_with_str("version ") {
+            if mode == Mode::Lenient && line.starts_with_str("version 2") {
+                // trailing garbage is recorded when parsing the version line once more.
+                return Ok((first_line, Protocol::V2));
+            }
             if line.len() != "version X".len() {
                 return Err(client::Error::UnsupportedProtocolVersion(line.as_bstr().into()));
             }
@@ -171,7 +270,14 @@ impl Capabilities {
 pub mod recv {
     use std::{io, io::BufRead};
 
-    use crate::{client, client::Capabilities, Protocol};
+    use crate::{
+        client,
+        client::{
+            capabilities::{Anomaly, Mode},
+            Capabilities,
+        },
+        Protocol,
+    };
 
     /// Success outcome of [`Capabilities::from_lines_with_version_detection`].
     pub struct Outcome<'a> {
@@ -200,14 +306,31 @@ pub mod recv {
             // format looks like, thus there is no binary blob that could ever look like an ERR line by accident.
             rd.fail_on_err_lines(true);
 
+            let mode = rd.mode();
+            if rd.peek_line().is_none() && rd.stopped_at() == Some(git_packetline::PacketLineRef::Flush) {
+                // Repositories without references send nothing but a flush packet in V1, not even capabilities.
+                return Ok(Outcome {
+                    capabilities: Capabilities::none_advertised(),
+                    refs: Some(Box::new(rd.as_read())),
+                    protocol: Protocol::V1,
+                });
+            }
             let capabilities_or_version = rd
                 .peek_line()
                 .ok_or(client::Error::ExpectedLine("capabilities or version"))???;
 
-            let (first_line, version) = Capabilities::extract_protocol(&capabilities_or_version)?;
+            let (first_line, version) = Capabilities::extract_protocol(&capabilities_or_version, mode)?;
             match version {
                 Protocol::V1 => {
-                    let (capabilities, delimiter_position) = Capabilities::from_bytes(first_line.0)?;
+                    let (mut capabilities, delimiter_position) =
+                        Capabilities::from_bytes_with_mode(first_line.0, mode)?;
+                    if mode == Mode::Lenient
+                        && !capabilities_or_version
+                            .as_slice()
+                            .map_or(false, |line| line.ends_with(b"\n"))
+                    {
+                        capabilities.anomalies.push(Anomaly::MissingNewline);
+                    }
                     rd.peek_buffer_replace_and_truncate(delimiter_position, b'\n');
                     Ok(Outcome {
                         capabilities,
@@ -219,7 +342,11 @@ pub mod recv {
                     capabilities: {
                         let rd = rd.as_read();
                         let mut lines = rd.lines();
-                        Capabilities::from_lines(lines.next(), lines.collect::<Result<Vec<_>, _>>()?.join("\n"))?
+                        Capabilities::from_lines_with_mode(
+                            lines.next(),
+                            lines.collect::<Result<Vec<_>, _>>()?.join("\n"),
+                            mode,
+                        )?
                     },
                     refs: None,
                     protocol: Protocol::V2,
@@ -236,7 +363,14 @@ pub mod recv {
     use futures_io::{AsyncBufRead, AsyncRead};
     use futures_lite::{AsyncBufReadExt, StreamExt};
 
-    use crate::{client, client::Capabilities, Protocol};
+    use crate::{
+        client,
+        client::{
+            capabilities::{Anomaly, Mode},
+            Capabilities,
+        },
+        Protocol,
+    };
 
     /// Success outcome of [`Capabilities::from_lines_with_version_detection`].
     pub struct Outcome<'a> {
@@ -265,15 +399,32 @@ pub mod recv {
             // format looks like, thus there is no binary blob that could ever look like an ERR line by accident.
             rd.fail_on_err_lines(true);
 
+            let mode = rd.mode();
+            if rd.peek_line().await.is_none() && rd.stopped_at() == Some(git_packetline::PacketLineRef::Flush) {
+                // Repositories without references send nothing but a flush packet in V1, not even capabilities.
+                return Ok(Outcome {
+                    capabilities: Capabilities::none_advertised(),
+                    refs: Some(Box::new(rd.as_read())),
+                    protocol: Protocol::V1,
+                });
+            }
             let capabilities_or_version = rd
                 .peek_line()
                 .await
                 .ok_or(client::Error::ExpectedLine("capabilities or version"))???;
 
-            let (first_line, version) = Capabilities::extract_protocol(&capabilities_or_version)?;
+            let (first_line, version) = Capabilities::extract_protocol(&capabilities_or_version, mode)?;
             match version {
                 Protocol::V1 => {
-                    let (capabilities, delimiter_position) = Capabilities::from_bytes(first_line.0)?;
+                    let (mut capabilities, delimiter_position) =
+                        Capabilities::from_bytes_with_mode(first_line.0, mode)?;
+                    if mode == Mode::Lenient
+                        && !capabilities_or_version
+                            .as_slice()
+                            .map_or(false, |line| line.ends_with(b"\n"))
+                    {
+                        capabilities.anomalies.push(Anomaly::MissingNewline);
+                    }
                     rd.peek_buffer_replace_and_truncate(delimiter_position, b'\n');
                     Ok(Outcome {
                         capabilities,
@@ -290,7 +441,11 @@ pub mod recv {
                             lines.push(line?);
                         }
                         let mut lines = lines.into_iter();
-                        Capabilities::from_lines(lines.next().map(Ok), lines.collect::<Vec<_>>().join("\n"))?
+                        Capabilities::from_lines_with_mode(
+                            lines.next().map(Ok),
+                            lines.collect::<Vec<_>>().join("\n"),
+                            mode,
+                        )?
                     },
                     refs: None,
                     protocol: Protocol::V2,
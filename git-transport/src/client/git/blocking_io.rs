@@ -1,4 +1,8 @@
-use std::{io, io::Write};
+use std::{
+    io,
+    io::Write,
+    sync::{atomic::AtomicBool, Arc},
+};
 
 use bstr::BString;
 use git_packetline::PacketLineRef;
@@ -20,12 +24,19 @@ where
     ) -> Result<client::RequestWriter<'_>, client::Error> {
         Ok(client::RequestWriter::new_from_bufread(
             &mut self.writer,
-            Box::new(self.line_provider.as_read_without_sidebands()),
+            Box::new(git_features::interrupt::Read {
+                inner: self.line_provider.as_read_without_sidebands(),
+                should_interrupt: &self.should_interrupt,
+            }),
             write_mode,
             on_into_read,
         ))
     }
 
+    fn set_should_interrupt(&mut self, should_interrupt: Arc<AtomicBool>) {
+        self.should_interrupt = should_interrupt;
+    }
+
     fn to_url(&self) -> String {
         self.custom_url.as_ref().map_or_else(
             || {
@@ -81,6 +92,7 @@ where
             line_writer.flush()?;
         }
 
+        let should_interrupt = &*self.should_interrupt;
         let capabilities::recv::Outcome {
             capabilities,
             refs,
@@ -89,7 +101,12 @@ where
         Ok(SetServiceResponse {
             actual_protocol,
             capabilities,
-            refs,
+            refs: refs.map(|inner| {
+                Box::new(git_features::interrupt::Read {
+                    inner,
+                    should_interrupt,
+                }) as Box<dyn io::BufRead + '_>
+            }),
         })
     }
 }
@@ -120,6 +137,7 @@ where
             custom_url: None,
             supported_versions: [desired_version],
             mode,
+            should_interrupt: Default::default(),
         }
     }
     pub(crate) fn new_for_spawned_process(
@@ -24,6 +24,8 @@ pub struct Connection<R, W> {
     supported_versions: [Protocol; 1],
     custom_url: Option<String>,
     pub(in crate::client) mode: ConnectMode,
+    #[cfg(feature = "blocking-client")]
+    pub(in crate::client) should_interrupt: std::sync::Arc<std::sync::atomic::AtomicBool>,
 }
 
 impl<R, W> Connection<R, W> {
@@ -41,6 +43,15 @@ impl<R, W> Connection<R, W> {
         self.custom_url = url;
         self
     }
+
+    /// Set the `mode` in which packet lines and the capabilities advertised by the server are decoded.
+    ///
+    /// Use [lenient][git_packetline::decode::Mode::Lenient] mode to talk to servers which emit slightly malformed responses
+    /// that stock git tolerates.
+    pub fn decode_mode(mut self, mode: git_packetline::decode::Mode) -> Self {
+        self.line_provider.set_mode(mode);
+        self
+    }
 }
 
 mod message {
@@ -26,7 +26,7 @@ pub mod capabilities;
 pub use capabilities::Capabilities;
 
 mod non_io_types;
-pub use non_io_types::{Error, Identity, MessageKind, WriteMode};
+pub use non_io_types::{Error, Identity, MessageKind, SshAuth, WriteMode};
 
 ///
 #[cfg(any(feature = "blocking-client", feature = "async-client"))]
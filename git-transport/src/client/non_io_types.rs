@@ -40,6 +40,33 @@ pub enum Identity {
         /// The user's password
         password: String,
     },
+    /// A token sent as bearer token in the `Authorization` header, like the ones issued to CI jobs
+    Token {
+        /// The token itself
+        token: String,
+    },
+}
+
+/// Options for authenticating with an ssh server, for use by ssh implementations of the transport layer.
+///
+/// Transports using the `ssh` program translate these into its command-line options, whereas ssh implementations not
+/// using the program have to load the keys and talk to the agent themselves.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct SshAuth {
+    /// Files with private keys to try in order, like `IdentityFile` in the ssh configuration.
+    pub identity_files: Vec<std::path::PathBuf>,
+    /// If true, the keys held by the ssh agent listening on `SSH_AUTH_SOCK` are tried as well.
+    pub use_agent: bool,
+}
+
+impl Default for SshAuth {
+    fn default() -> Self {
+        SshAuth {
+            identity_files: Vec::new(),
+            use_agent: true,
+        }
+    }
 }
 
 pub(crate) mod connect {
@@ -117,6 +144,12 @@ mod error {
         AuthenticationUnsupported,
         #[error("The transport layer refuses to use a given identity: {0}")]
         AuthenticationRefused(&'static str),
+        #[error("The transport layer does not authenticate with ssh")]
+        SshAuthUnsupported,
+        #[error("The transport layer does not support sending extra headers")]
+        ExtraHeadersUnsupported,
+        #[error("The extra header {0:?} must be a single line of the form 'Name: value'")]
+        MalformedExtraHeader(String),
         #[error("The protocol version indicated by {:?} is unsupported", {0})]
         UnsupportedProtocolVersion(BString),
         #[error(transparent)]
@@ -1,9 +1,12 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{atomic::AtomicBool, Arc},
+};
 
 #[cfg(any(feature = "blocking-client", feature = "async-client"))]
 use crate::client::{MessageKind, RequestWriter, WriteMode};
 use crate::{
-    client::{Error, Identity},
+    client::{Error, Identity, SshAuth},
     Protocol,
 };
 
@@ -19,6 +22,22 @@ pub trait TransportWithoutIO {
     fn set_identity(&mut self, _identity: Identity) -> Result<(), Error> {
         Err(Error::AuthenticationUnsupported)
     }
+    /// Authenticate with ssh servers as configured by `auth`, instead of using the defaults of the ssh implementation.
+    /// Please note that only transports using ssh support this and all others return [an error][Error::SshAuthUnsupported] here.
+    fn set_ssh_auth(&mut self, _auth: SshAuth) -> Result<(), Error> {
+        Err(Error::SshAuthUnsupported)
+    }
+    /// Send `headers`, each of the form `Name: value`, with every subsequent request in addition to the ones sent anyway,
+    /// replacing the extra headers set previously. This is what `http.extraHeader` does in `git`, and is useful to
+    /// authenticate with proxies or to pass tokens in custom headers.
+    /// Please note that only transports using http support extra headers and all others return [an error][Error::ExtraHeadersUnsupported] here.
+    fn set_extra_headers(&mut self, _headers: Vec<String>) -> Result<(), Error> {
+        Err(Error::ExtraHeadersUnsupported)
+    }
+    /// Fail all subsequent reads of responses from the remote, like the ref advertisement or the pack, once `should_interrupt`
+    /// is set. That way transfers stop promptly if the user requests it, instead of when the server is done sending.
+    /// Please note that only the blocking transports of this crate can be interrupted, all others ignore the flag.
+    fn set_should_interrupt(&mut self, _should_interrupt: Arc<AtomicBool>) {}
     /// Get a writer for sending data and obtaining the response. It can be configured in various ways
     /// to support the task at hand.
     /// `write_mode` determines how calls to the `write(…)` method are interpreted, and `on_into_read` determines
@@ -29,6 +48,7 @@ pub trait TransportWithoutIO {
     /// Returns the canonical URL pointing to the destination of this transport.
     /// Please note that local paths may not be represented correctly, as they will go through a potentially lossy
     /// unicode conversion.
+    /// Transports following redirects return the URL they were redirected to, which callers may use to update their configuration.
     fn to_url(&self) -> String;
 
     /// If the actually advertised server version is contained in the returned slice or empty, continue as normal,
@@ -57,6 +77,18 @@ impl<T: TransportWithoutIO + ?Sized> TransportWithoutIO for Box<T> {
         self.deref_mut().set_identity(identity)
     }
 
+    fn set_ssh_auth(&mut self, auth: SshAuth) -> Result<(), Error> {
+        self.deref_mut().set_ssh_auth(auth)
+    }
+
+    fn set_extra_headers(&mut self, headers: Vec<String>) -> Result<(), Error> {
+        self.deref_mut().set_extra_headers(headers)
+    }
+
+    fn set_should_interrupt(&mut self, should_interrupt: Arc<AtomicBool>) {
+        self.deref_mut().set_should_interrupt(should_interrupt)
+    }
+
     #[cfg(any(feature = "blocking-client", feature = "async-client"))]
     fn request(&mut self, write_mode: WriteMode, on_into_read: MessageKind) -> Result<RequestWriter<'_>, Error> {
         self.deref_mut().request(write_mode, on_into_read)
@@ -80,6 +112,18 @@ impl<T: TransportWithoutIO + ?Sized> TransportWithoutIO for &mut T {
         self.deref_mut().set_identity(identity)
     }
 
+    fn set_ssh_auth(&mut self, auth: SshAuth) -> Result<(), Error> {
+        self.deref_mut().set_ssh_auth(auth)
+    }
+
+    fn set_extra_headers(&mut self, headers: Vec<String>) -> Result<(), Error> {
+        self.deref_mut().set_extra_headers(headers)
+    }
+
+    fn set_should_interrupt(&mut self, should_interrupt: Arc<AtomicBool>) {
+        self.deref_mut().set_should_interrupt(should_interrupt)
+    }
+
     #[cfg(any(feature = "blocking-client", feature = "async-client"))]
     fn request(&mut self, write_mode: WriteMode, on_into_read: MessageKind) -> Result<RequestWriter<'_>, Error> {
         self.deref_mut().request(write_mode, on_into_read)
@@ -1,6 +1,6 @@
 use std::{
     io::{Read, Write},
-    net::SocketAddr,
+    net::{SocketAddr, TcpListener},
     time::Duration,
 };
 
@@ -28,14 +28,7 @@ pub struct Server {
 
 impl Server {
     pub fn new(fixture: Vec<u8>) -> Self {
-        let ports = (15411..).take(10);
-        let listener = std::net::TcpListener::bind(
-            ports
-                .map(|port| SocketAddr::from(([127, 0, 0, 1], port)))
-                .collect::<Vec<_>>()
-                .as_slice(),
-        )
-        .expect("one of these ports to be free");
+        let listener = listen();
         let addr = listener.local_addr().expect("a local address");
         let (send_result, recv_result) = std::sync::mpsc::sync_channel(0);
         let (send_command, recv_commands) = std::sync::mpsc::sync_channel(0);
@@ -43,17 +36,7 @@ impl Server {
             for command in recv_commands {
                 match command {
                     Command::ReadAndRespond(response) => {
-                        let (mut stream, _) = listener.accept().expect("accept to always work");
-                        stream
-                            .set_read_timeout(Some(Duration::from_millis(50)))
-                            .expect("timeout to always work");
-                        stream
-                            .set_write_timeout(Some(Duration::from_millis(50)))
-                            .expect("timeout to always work");
-                        let mut out = Vec::new();
-                        stream.read_to_end(&mut out).ok();
-                        stream.write_all(&response).expect("write to always work");
-                        stream.flush().expect("flush to work");
+                        let out = read_and_respond(&listener, &response);
                         if send_result.send(CommandResult::ReadAndRespond(out)).is_err() {
                             break;
                         }
@@ -88,6 +71,64 @@ impl Server {
     }
 }
 
+/// A server answering one connection after another with the next of the given responses, without waiting for the requests
+/// to be observed in between. This is needed if a single call makes multiple requests, like when following redirects.
+pub struct Sequence {
+    pub addr: SocketAddr,
+    requests: std::thread::JoinHandle<Vec<Vec<u8>>>,
+}
+
+impl Sequence {
+    pub fn new(responses: Vec<Vec<u8>>) -> Self {
+        let listener = listen();
+        let addr = listener.local_addr().expect("a local address");
+        let requests = std::thread::spawn(move || {
+            responses
+                .iter()
+                .map(|response| read_and_respond(&listener, response))
+                .collect()
+        });
+        Sequence { addr, requests }
+    }
+
+    /// Wait until all responses were sent and return the requests they answered, in order.
+    pub fn received_as_strings(self) -> Vec<String> {
+        self.requests
+            .join()
+            .expect("server thread to not panic")
+            .into_iter()
+            .map(|request| request.into_string().expect("utf8 only"))
+            .collect()
+    }
+}
+
+fn listen() -> TcpListener {
+    let ports = (15411..).take(10);
+    TcpListener::bind(
+        ports
+            .map(|port| SocketAddr::from(([127, 0, 0, 1], port)))
+            .collect::<Vec<_>>()
+            .as_slice(),
+    )
+    .expect("one of these ports to be free")
+}
+
+/// Accept the next connection, read the request and answer it with `response`, returning the request.
+fn read_and_respond(listener: &TcpListener, response: &[u8]) -> Vec<u8> {
+    let (mut stream, _) = listener.accept().expect("accept to always work");
+    stream
+        .set_read_timeout(Some(Duration::from_millis(50)))
+        .expect("timeout to always work");
+    stream
+        .set_write_timeout(Some(Duration::from_millis(50)))
+        .expect("timeout to always work");
+    let mut out = Vec::new();
+    stream.read_to_end(&mut out).ok();
+    stream.write_all(response).expect("write to always work");
+    stream.flush().expect("flush to work");
+    out
+}
+
 pub fn serve_once(name: &str) -> Server {
     Server::new(fixture_bytes(name))
 }
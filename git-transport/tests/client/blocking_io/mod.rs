@@ -1,2 +1,3 @@
 #[cfg(feature = "http-client-curl")]
 mod http;
+mod ssh;
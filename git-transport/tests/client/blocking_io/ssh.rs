@@ -0,0 +1,202 @@
+mod auth {
+    use git_transport::{
+        client::{self, SshAuth, TransportWithoutIO},
+        Protocol,
+    };
+
+    #[test]
+    fn only_ssh_transports_accept_ssh_auth() -> crate::Result {
+        let auth = SshAuth {
+            identity_files: vec!["id_ed25519".into()],
+            use_agent: false,
+        };
+        let mut ssh = client::ssh::connect("example.com", "repo".into(), Protocol::V2, None, None)?;
+        ssh.set_ssh_auth(auth.clone())?;
+
+        let mut local = client::file::connect("repo", Protocol::V2)?;
+        assert!(matches!(
+            local.set_ssh_auth(auth),
+            Err(client::Error::SshAuthUnsupported)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn the_agent_is_used_by_default() {
+        assert!(SshAuth::default().use_agent);
+    }
+}
+
+#[cfg(feature = "ssh-known-hosts")]
+mod known_hosts {
+    use git_transport::client::ssh::known_hosts::{self, Decision, File, Marker, Verdict};
+
+    const KEY: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIDQlFf4A/+3muLdbUqdeutBtYgOk/0vkNbnUdAkEJKfJ";
+    const OTHER_KEY: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIHct4MUEqEAn2gn9XShEDMrnIJl1bRHS19xzwHEPP/iO";
+
+    fn key(base64: &str) -> Vec<u8> {
+        base64::decode(base64).expect("valid base64")
+    }
+
+    #[test]
+    fn plain_host_patterns_with_wildcards_negations_and_ports() -> crate::Result {
+        let file = File::from_bytes(
+            format!(
+                "# comment\n\n*.example.com,!evil.example.com ssh-ed25519 {key}\n[git.example.org]:2222 ssh-ed25519 {key} comment\n",
+                key = KEY
+            )
+            .as_bytes(),
+        )?;
+        assert_eq!(file.entries.len(), 2);
+        assert_eq!(
+            file.verify("git.example.com", None, "ssh-ed25519", &key(KEY)),
+            Verdict::Trusted
+        );
+        assert_eq!(
+            file.verify("GIT.Example.com", Some(22), "ssh-ed25519", &key(KEY)),
+            Verdict::Trusted,
+            "host names are case-insensitive and the default port is the same as none"
+        );
+        assert_eq!(
+            file.verify("evil.example.com", None, "ssh-ed25519", &key(KEY)),
+            Verdict::Unknown,
+            "negations take precedence"
+        );
+        assert_eq!(
+            file.verify("git.example.com", Some(2222), "ssh-ed25519", &key(KEY)),
+            Verdict::Unknown,
+            "entries without port only match the default port"
+        );
+        assert_eq!(
+            file.verify("git.example.org", Some(2222), "ssh-ed25519", &key(KEY)),
+            Verdict::Trusted
+        );
+        assert_eq!(
+            file.verify("git.example.org", None, "ssh-ed25519", &key(KEY)),
+            Verdict::Unknown
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn hashed_host_names_as_written_by_ssh_keygen() -> crate::Result {
+        let file = File::from_bytes(
+            format!(
+                "|1|pmZ/3BoyXItf42sgGrK5Atok91s=|cXs3dalzDHwAJIGmlda/Esn0dTk= ssh-ed25519 {key}\n|1|lqGsZikTrjOtaL/nAvxIAACQNOs=|SdkmELcGpbEdD3xb8hXXcAsu9ms= ssh-ed25519 {key}\n",
+                key = KEY
+            )
+            .as_bytes(),
+        )?;
+        assert!(file.entries[0].matches_host("example.com", None));
+        assert!(!file.entries[0].matches_host("example.org", None));
+        assert!(file.entries[1].matches_host("example.com", Some(2222)));
+        assert!(!file.entries[1].matches_host("example.com", None));
+        Ok(())
+    }
+
+    #[test]
+    fn changed_and_revoked_keys() -> crate::Result {
+        let file = File::from_bytes(
+            format!(
+                "example.com ssh-ed25519 {key}\nexample.com ssh-rsa {other}\n@revoked * ssh-ed25519 {other}\n",
+                key = KEY,
+                other = OTHER_KEY
+            )
+            .as_bytes(),
+        )?;
+        assert_eq!(file.entries[2].marker, Some(Marker::Revoked));
+        assert_eq!(
+            file.verify("example.com", None, "ssh-ed25519", &key(KEY)),
+            Verdict::Trusted
+        );
+        assert_eq!(
+            file.verify("example.com", None, "ssh-ed25519", &key(OTHER_KEY)),
+            Verdict::Revoked
+        );
+        assert_eq!(
+            file.verify("example.com", None, "ssh-rsa", &key(KEY)),
+            Verdict::Changed,
+            "keys are only compared to ones of the same type"
+        );
+        assert_eq!(
+            file.verify("example.com", None, "ecdsa-sha2-nistp256", &key(KEY)),
+            Verdict::Unknown
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn malformed_lines_are_rejected() {
+        for (input, expected) in &[
+            (
+                "example.com ssh-ed25519",
+                "Line 1 of the known hosts file is malformed: missing key",
+            ),
+            (
+                "\n@unknown example.com ssh-ed25519 AAAA",
+                "Line 2 of the known hosts file is malformed: unknown marker",
+            ),
+            (
+                "example.com ssh-ed25519 not-base64!",
+                "Line 1 of the known hosts file is malformed: the key isn't valid base64",
+            ),
+        ] {
+            assert_eq!(File::from_bytes(input.as_bytes()).unwrap_err().to_string(), *expected);
+        }
+    }
+
+    #[test]
+    fn verify_asks_for_unknown_keys_and_remembers_them() -> crate::Result {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(".ssh").join("known_hosts");
+        let presented = key(KEY);
+        assert!(!known_hosts::verify(
+            &path,
+            "example.com",
+            Some(2222),
+            "ssh-ed25519",
+            &presented,
+            || Decision::Reject
+        )?);
+        assert!(!path.exists(), "rejected keys aren't written");
+        assert!(known_hosts::verify(
+            &path,
+            "example.com",
+            Some(2222),
+            "ssh-ed25519",
+            &presented,
+            || Decision::AcceptOnce
+        )?);
+        assert!(!path.exists(), "keys accepted once aren't written");
+
+        assert!(known_hosts::verify(
+            &path,
+            "example.com",
+            Some(2222),
+            "ssh-ed25519",
+            &presented,
+            || { Decision::AcceptAndRemember }
+        )?);
+        assert_eq!(
+            std::fs::read_to_string(&path)?,
+            format!("[example.com]:2222 ssh-ed25519 {}\n", KEY)
+        );
+        assert!(known_hosts::verify(
+            &path,
+            "example.com",
+            Some(2222),
+            "ssh-ed25519",
+            &presented,
+            || unreachable!("known keys don't need a decision")
+        )?);
+        assert!(!known_hosts::verify(
+            &path,
+            "example.com",
+            Some(2222),
+            "ssh-ed25519",
+            &key(OTHER_KEY),
+            || unreachable!("changed keys are always rejected")
+        )?);
+        Ok(())
+    }
+}
@@ -49,3 +49,114 @@ fn from_bytes() -> crate::Result {
     );
     Ok(())
 }
+
+mod lenient {
+    use bstr::ByteSlice;
+    use git_transport::client::{
+        capabilities::{Anomaly, Mode},
+        Capabilities,
+    };
+
+    const DUPLICATES_AND_GARBAGE: &[u8] =
+        b"7814e8a05a59c0cf5fb186661d1551c75d1299b5 HEAD\0multi_ack thin-pack multi_ack agent=git/2.28.0\0garbage";
+
+    #[test]
+    fn strict_mode_rejects_duplicate_capabilities_and_trailing_garbage() {
+        assert_eq!(
+            Capabilities::from_bytes(DUPLICATES_AND_GARBAGE)
+                .unwrap_err()
+                .to_string(),
+            "Unexpected bytes '\0garbage' after the capabilities"
+        );
+        assert_eq!(
+            Capabilities::from_bytes(&DUPLICATES_AND_GARBAGE[..DUPLICATES_AND_GARBAGE.len() - 8])
+                .unwrap_err()
+                .to_string(),
+            "The capability 'multi_ack' was advertised more than once"
+        );
+    }
+
+    #[test]
+    fn lenient_mode_drops_duplicate_capabilities_and_trailing_garbage() -> crate::Result {
+        let (caps, delim_pos) = Capabilities::from_bytes_with_mode(DUPLICATES_AND_GARBAGE, Mode::Lenient)?;
+        assert_eq!(delim_pos, 45);
+        assert_eq!(
+            caps.iter().map(|c| c.name().to_owned()).collect::<Vec<_>>(),
+            vec!["multi_ack", "thin-pack", "agent"]
+        );
+        assert_eq!(
+            caps.anomalies(),
+            &[
+                Anomaly::TrailingGarbage("\0garbage".into()),
+                Anomaly::DuplicateCapability("multi_ack".into())
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn capabilities_with_the_same_name_but_different_values_are_no_duplicates() -> crate::Result {
+        let (caps, _) =
+            Capabilities::from_bytes(b"0000 HEAD\0symref=HEAD:refs/heads/main symref=refs/heads/a:refs/heads/b")?;
+        assert_eq!(caps.iter().count(), 2);
+        assert!(caps.anomalies().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn lenient_mode_ignores_trailing_garbage_in_the_version_line() -> crate::Result {
+        assert_eq!(
+            Capabilities::from_lines(Some(Ok("version 2 ".into())), "ls-refs\nfetch=shallow")
+                .unwrap_err()
+                .to_string(),
+            "Got unsupported version ' 2 ', expected '2'"
+        );
+        let caps = Capabilities::from_lines_with_mode(
+            Some(Ok("version 2 ".into())),
+            "ls-refs\nfetch=shallow\nls-refs",
+            Mode::Lenient,
+        )?;
+        assert_eq!(
+            caps.iter().map(|c| c.name().to_owned()).collect::<Vec<_>>(),
+            vec!["ls-refs", "fetch"]
+        );
+        assert_eq!(
+            caps.anomalies(),
+            &[
+                Anomaly::TrailingGarbage(" ".into()),
+                Anomaly::DuplicateCapability("ls-refs".into())
+            ]
+        );
+        assert!(
+            Capabilities::from_lines_with_mode(Some(Ok("version 20".into())), "", Mode::Lenient).is_err(),
+            "other versions are never mistaken for version 2"
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "blocking-client")]
+    #[test]
+    fn lenient_mode_records_a_missing_newline_in_the_v1_advertisement() -> crate::Result {
+        let input = b"001cHEAD\0multi_ack thin-pack0000";
+        let mut rd = git_packetline::StreamingPeekableIter::new(&input[..], &[git_packetline::PacketLineRef::Flush]);
+        let outcome = Capabilities::from_lines_with_version_detection(&mut rd)?;
+        assert!(
+            outcome.capabilities.anomalies().is_empty(),
+            "this is accepted in strict mode"
+        );
+
+        let mut rd = git_packetline::StreamingPeekableIter::new(&input[..], &[git_packetline::PacketLineRef::Flush]);
+        rd.set_mode(Mode::Lenient);
+        let outcome = Capabilities::from_lines_with_version_detection(&mut rd)?;
+        assert_eq!(outcome.capabilities.anomalies(), &[Anomaly::MissingNewline]);
+        assert_eq!(
+            outcome
+                .capabilities
+                .iter()
+                .map(|c| c.name().as_bytes().to_owned())
+                .collect::<Vec<_>>(),
+            vec![b"multi_ack".to_vec(), b"thin-pack".to_vec()]
+        );
+        Ok(())
+    }
+}
@@ -143,6 +143,51 @@ async fn handshake_v1_and_request() -> crate::Result {
     Ok(())
 }
 
+#[cfg(feature = "blocking-client")]
+#[test]
+fn interrupted_reads_fail_ref_advertisement_and_responses() -> crate::Result {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let mut out = Vec::new();
+    let server_response = fixture_bytes("v1/clone.response");
+    let mut c = git::Connection::new(
+        server_response.as_slice(),
+        &mut out,
+        Protocol::V1,
+        "/foo.git",
+        Some(("example.org", None)),
+        git::ConnectMode::Daemon,
+    );
+    let should_interrupt = Arc::new(AtomicBool::new(false));
+    c.set_should_interrupt(Arc::clone(&should_interrupt));
+    let mut res = c.handshake(Service::UploadPack, &[])?;
+    should_interrupt.store(true, Ordering::Relaxed);
+    let err = res
+        .refs
+        .as_mut()
+        .expect("v1 protocol provides refs")
+        .lines()
+        .next()
+        .expect("an error")
+        .unwrap_err();
+    assert_eq!(
+        err.kind(),
+        std::io::ErrorKind::Other,
+        "not 'Interrupted' as it would be retried"
+    );
+    drop(res);
+
+    let mut reader = c
+        .request(client::WriteMode::Binary, client::MessageKind::Flush)?
+        .into_read()?;
+    assert!(
+        matches!(reader.peek_data_line(), Some(Err(err)) if err.kind() == std::io::ErrorKind::Other),
+        "responses can be interrupted just the same"
+    );
+    assert!(reader.fill_buf().is_err());
+    Ok(())
+}
+
 #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
 async fn handshake_v1_process_mode() -> crate::Result {
     let mut out = Vec::new();
@@ -165,6 +210,29 @@ async fn handshake_v1_process_mode() -> crate::Result {
     Ok(())
 }
 
+#[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+async fn handshake_v1_with_empty_repository() -> crate::Result {
+    let mut out = Vec::new();
+    let mut c = git::Connection::new(
+        &b"0000"[..],
+        &mut out,
+        Protocol::V1,
+        "/empty.git",
+        Some(("example.org", None)),
+        git::ConnectMode::Daemon,
+    );
+    let res = c.handshake(Service::UploadPack, &[]).await?;
+    assert_eq!(res.actual_protocol, Protocol::V1);
+    assert_eq!(
+        res.capabilities.iter().count(),
+        0,
+        "repositories without refs advertise no capabilities"
+    );
+    let num_refs = res.refs.expect("V1 always has refs").lines().count().await;
+    assert_eq!(num_refs, 0, "and there are no refs either");
+    Ok(())
+}
+
 #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
 async fn handshake_v2_downgrade_to_v1() -> crate::Result {
     let mut out = Vec::new();
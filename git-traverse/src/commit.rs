@@ -46,6 +46,11 @@ pub mod ancestors {
                 source(err)
                 from()
             }
+            CommitField(err: git_object::commit::extract::Error) {
+                display("A field of a commit could not be extracted")
+                source(err)
+                from()
+            }
         }
     }
 
@@ -155,13 +160,10 @@ pub mod ancestors {
             let res = state.next.pop_front();
             if let Some(oid) = res {
                 match (self.find)(&oid, &mut state.buf) {
-                    Some(mut commit_iter) => {
-                        if let Some(Err(decode_tree_err)) = commit_iter.next() {
-                            return Some(Err(decode_tree_err.into()));
-                        }
-                        for token in commit_iter {
-                            match token {
-                                Ok(git_object::commit::ref_iter::Token::Parent { id }) => {
+                    Some(commit_iter) => {
+                        for id in commit_iter.parent_ids() {
+                            match id {
+                                Ok(id) => {
                                     let was_inserted = state.seen.insert(id);
                                     if was_inserted && (self.predicate)(&id) {
                                         state.next.push_back(id);
@@ -170,7 +172,6 @@ pub mod ancestors {
                                         break;
                                     }
                                 }
-                                Ok(_a_token_past_the_parents) => break,
                                 Err(err) => return Some(Err(err.into())),
                             }
                         }
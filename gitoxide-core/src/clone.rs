@@ -0,0 +1,890 @@
+use std::{
+    borrow::Cow,
+    convert::TryInto,
+    io,
+    path::{Path, PathBuf},
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use anyhow::{anyhow, bail, Context as AnyhowContext};
+use git_repository as git;
+use git_repository::{
+    bstr::{BString, ByteSlice},
+    hash::ObjectId,
+    odb::{linked, pack, Find, FindExt},
+    prelude::ReferenceAccessExt,
+    protocol,
+    protocol::{
+        fetch::{Action, Arguments, LsRefsAction, Ref, Response},
+        refspec::{Mode, RefSpec},
+        transport,
+    },
+    refs::{
+        transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+        Target,
+    },
+    Progress,
+};
+
+use crate::{output, pack::receive::JsonBundleWriteOutcome, OutputFormat};
+
+pub const PROGRESS_RANGE: std::ops::RangeInclusive<u8> = 1..=3;
+
+/// The name of the remote the repository is cloned from.
+const REMOTE: &str = "origin";
+
+pub struct Context<W> {
+    pub thread_limit: Option<usize>,
+    pub format: OutputFormat,
+    pub should_interrupt: Arc<AtomicBool>,
+    pub out: W,
+    /// If set, create a bare repository whose branches are the ones of the remote, instead of checking out its `HEAD`
+    /// and tracking its branches in `refs/remotes/origin/`.
+    pub bare: bool,
+    /// If set, create a bare repository with all references of the remote, configured to overwrite all of them when
+    /// fetching, like `git clone --mirror`. Implies `bare`.
+    pub mirror: bool,
+    /// The branch to check out instead of the one the remote `HEAD` points to, or a tag to detach `HEAD` at,
+    /// like `git clone --branch`.
+    pub branch: Option<String>,
+    /// If set, only clone `branch` or the branch the remote `HEAD` points to, along with the tags pointing into its history,
+    /// and configure the remote to only fetch this branch, like `git clone --single-branch`.
+    pub single_branch: bool,
+    /// If unset, don't clone any tags and configure the remote not to fetch them either, like `git clone --no-tags`.
+    pub tags: bool,
+    /// If set and the repository is given as path, copy its object database instead of fetching a pack, like `git clone --local`.
+    pub local: bool,
+    /// If set, hardlink the files of the object database when copying it, falling back to copying them if that fails.
+    pub hardlinks: bool,
+    /// A local repository whose objects are used through `objects/info/alternates` instead of fetching them again,
+    /// like `git clone --reference`.
+    pub reference: Option<PathBuf>,
+    /// If set, copy all objects borrowed from alternates into the clone and stop using them, like `git clone --dissociate`.
+    pub dissociate: bool,
+    /// If set, write the built-in sample hooks into the new repository, like `git clone` does with its default template.
+    pub hook_samples: bool,
+}
+
+/// Return the directory `git clone` would clone `url` into, which is the last component of its path without `.git`,
+/// and with `.git` if the clone is `bare`.
+pub fn directory_from_url(url: &str, bare: bool) -> PathBuf {
+    let path = url.trim_end_matches('/');
+    let path = path.strip_suffix("/.git").unwrap_or(path);
+    let name = path.rsplit(&['/', ':', '\\'][..]).next().unwrap_or(path);
+    let name = name.strip_suffix(".git").unwrap_or(name);
+    if bare {
+        format!("{}.git", name).into()
+    } else {
+        name.into()
+    }
+}
+
+struct CloneDelegate {
+    /// If true, all objects are present already and only the references are obtained from the remote.
+    have_objects: bool,
+    /// If true, all references are cloned.
+    mirror: bool,
+    /// The name of the branch or tag to check out, as given by the user.
+    branch: Option<String>,
+    single_branch: bool,
+    tags: bool,
+    /// The full name of the remote reference to check out, or `None` to check out what the remote `HEAD` points to.
+    checkout: Option<BString>,
+    local_objects: Option<LocalObjects>,
+    /// The tips of the reference repository, which are available through alternates.
+    haves: Vec<ObjectId>,
+    odb: Arc<linked::Store>,
+    pack_directory: PathBuf,
+    thread_limit: Option<usize>,
+    should_interrupt: Arc<AtomicBool>,
+    refs: Vec<Ref>,
+    pack: Option<pack::bundle::write::Outcome>,
+}
+
+impl protocol::fetch::DelegateBlocking for CloneDelegate {
+    fn prepare_ls_refs(
+        &mut self,
+        server: &transport::client::Capabilities,
+        arguments: &mut Vec<BString>,
+        _features: &mut Vec<(&str, Option<&str>)>,
+    ) -> io::Result<LsRefsAction> {
+        if server.contains("ls-refs") && !self.mirror {
+            let mut prefixes = vec!["HEAD".to_owned()];
+            prefixes.push(match (&self.branch, self.single_branch) {
+                (Some(branch), true) => format!("refs/heads/{}", branch),
+                _ => "refs/heads/".into(),
+            });
+            match (&self.branch, self.tags) {
+                (_, true) => prefixes.push("refs/tags/".into()),
+                (Some(branch), false) => prefixes.push(format!("refs/tags/{}", branch)),
+                (None, false) => {}
+            }
+            arguments.extend(prefixes.iter().map(|prefix| format!("ref-prefix {}", prefix).into()));
+        }
+        Ok(LsRefsAction::Continue)
+    }
+
+    fn prepare_fetch(
+        &mut self,
+        _version: transport::Protocol,
+        server: &transport::client::Capabilities,
+        features: &mut Vec<(&str, Option<&str>)>,
+        refs: &[Ref],
+    ) -> io::Result<Action> {
+        self.checkout = match &self.branch {
+            Some(branch) => Some(find_branch(refs, branch)?),
+            None if self.single_branch => refs.iter().find_map(|r| match r {
+                Ref::Symbolic { path, target, .. } | Ref::Unborn { path, target } if path == "HEAD" => {
+                    Some(target.clone())
+                }
+                _ => None,
+            }),
+            None => None,
+        };
+        self.refs = refs.iter().filter(|r| self.is_cloned(r.unpack().0)).cloned().collect();
+        if self.single_branch && self.tags && server.contains("include-tag") {
+            features.push(("include-tag", None));
+        }
+        // An empty repository has no refs at all, or only an unborn `HEAD`, so there is nothing to fetch. The same is true
+        // if a reference repository has all the objects already.
+        Ok(if self.have_objects || self.wants().all(|id| self.odb.contains(id)) {
+            Action::Cancel
+        } else {
+            Action::Continue
+        })
+    }
+
+    fn negotiate(
+        &mut self,
+        _refs: &[Ref],
+        arguments: &mut Arguments,
+        _previous_response: Option<&Response>,
+    ) -> io::Result<Action> {
+        for id in self.wants() {
+            arguments.want(id);
+        }
+        for id in &self.haves {
+            arguments.have(id);
+        }
+        Ok(Action::Cancel)
+    }
+}
+
+/// Return the full name of the remote branch called `name`, or of the tag of that name if there is no such branch.
+fn find_branch(refs: &[Ref], name: &str) -> io::Result<BString> {
+    ["refs/heads/", "refs/tags/"]
+        .iter()
+        .map(|prefix| BString::from(format!("{}{}", prefix, name)))
+        .find(|path| refs.iter().any(|r| r.unpack().0 == path))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Remote branch '{}' not found in upstream {}", name, REMOTE),
+            )
+        })
+}
+
+impl CloneDelegate {
+    /// Return true if the remote reference at `path` is cloned. Only `HEAD`, branches and tags are cloned like `git clone`
+    /// does, unless all references are cloned when creating a `mirror`. `HEAD` isn't needed if a single other branch is cloned.
+    fn is_cloned(&self, path: &BString) -> bool {
+        if path == "HEAD" {
+            return self.mirror || !(self.single_branch && self.branch.is_some());
+        }
+        if self.mirror || self.checkout.as_ref() == Some(path) {
+            return true;
+        }
+        if path.starts_with_str("refs/tags/") {
+            self.tags
+        } else {
+            path.starts_with_str("refs/heads/") && !self.single_branch
+        }
+    }
+
+    /// Return true if the tag at `path` is only cloned if it points into the history of the single cloned branch, which
+    /// is only known once the remote sent it.
+    fn is_followed(&self, path: &BString) -> bool {
+        self.single_branch && !self.mirror && path.starts_with_str("refs/tags/") && self.checkout.as_ref() != Some(path)
+    }
+
+    fn wants(&self) -> impl Iterator<Item = &ObjectId> + '_ {
+        self.refs
+            .iter()
+            .filter(move |r| !self.is_followed(r.unpack().0))
+            .filter_map(|r| r.unpack().1)
+    }
+
+    fn receive_pack(&mut self, input: impl io::BufRead, progress: impl Progress) -> io::Result<()> {
+        let options = pack::bundle::write::Options {
+            thread_limit: self.thread_limit,
+            index_kind: pack::index::Version::V2,
+            iteration_mode: pack::data::input::Mode::Verify,
+            write_reverse_index: false,
+            fsync: Default::default(),
+        };
+        let odb = Arc::clone(&self.odb);
+        self.pack = Some(
+            pack::Bundle::write_to_directory(
+                input,
+                Some(&self.pack_directory),
+                progress,
+                &self.should_interrupt,
+                Some(Box::new(move |oid, buf| {
+                    odb.try_find(oid, buf, &mut pack::cache::Never).ok().flatten()
+                })),
+                options,
+            )
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?,
+        );
+        Ok(())
+    }
+}
+
+#[cfg(feature = "blocking-client")]
+mod blocking_io {
+    use std::{io, io::BufRead, path::PathBuf, sync::Arc};
+
+    use git_repository::{
+        protocol,
+        protocol::fetch::{Ref, Response},
+        Progress,
+    };
+
+    use super::{finish, init, repository_url, CloneDelegate, Context};
+    use crate::{diagnostics::Operation, net};
+
+    impl protocol::fetch::Delegate for CloneDelegate {
+        fn receive_pack(
+            &mut self,
+            input: impl BufRead,
+            progress: impl Progress,
+            _refs: &[Ref],
+            _previous_response: &Response,
+        ) -> io::Result<()> {
+            CloneDelegate::receive_pack(self, input, progress)
+        }
+    }
+
+    pub fn clone<P: Progress, W: io::Write>(
+        protocol: Option<net::Protocol>,
+        url: &str,
+        directory: Option<PathBuf>,
+        mut progress: P,
+        ctx: Context<W>,
+    ) -> anyhow::Result<()> {
+        let _op = Operation::start(module_path!(), "clone");
+        let (destination, mut delegate) = init(url, directory, &ctx)?;
+        let mut transport = net::connect(repository_url(url).as_bytes(), protocol.unwrap_or_default().into())?;
+        transport.set_should_interrupt(Arc::clone(&ctx.should_interrupt));
+        protocol::fetch(
+            transport,
+            &mut delegate,
+            protocol::credentials::helper,
+            progress.add_child("fetch"),
+            protocol::FetchConnection::TerminateOnSuccessfulCompletion,
+        )?;
+        finish(destination, url, delegate, progress, ctx)
+    }
+}
+
+#[cfg(feature = "blocking-client")]
+pub use blocking_io::clone;
+
+#[cfg(feature = "async-client")]
+mod async_io {
+    use std::{io, path::PathBuf};
+
+    use async_trait::async_trait;
+    use futures_io::AsyncBufRead;
+    use git_repository::{
+        protocol,
+        protocol::fetch::{Ref, Response},
+        Progress,
+    };
+
+    use super::{finish, init, repository_url, CloneDelegate, Context};
+    use crate::{diagnostics::Operation, net};
+
+    #[async_trait(?Send)]
+    impl protocol::fetch::Delegate for CloneDelegate {
+        async fn receive_pack(
+            &mut self,
+            input: impl AsyncBufRead + Unpin + 'async_trait,
+            progress: impl Progress,
+            _refs: &[Ref],
+            _previous_response: &Response,
+        ) -> io::Result<()> {
+            CloneDelegate::receive_pack(self, futures_lite::io::BlockOn::new(input), progress)
+        }
+    }
+
+    pub async fn clone<P: Progress + 'static, W: io::Write + Send + 'static>(
+        protocol: Option<net::Protocol>,
+        url: &str,
+        directory: Option<PathBuf>,
+        mut progress: P,
+        ctx: Context<W>,
+    ) -> anyhow::Result<()> {
+        let _op = Operation::start(module_path!(), "clone");
+        let (destination, mut delegate) = init(url, directory, &ctx)?;
+        let transport = net::connect(repository_url(url).as_bytes(), protocol.unwrap_or_default().into()).await?;
+        let fetch_progress = progress.add_child("fetch");
+        let delegate = blocking::unblock(move || {
+            futures_lite::future::block_on(protocol::fetch(
+                transport,
+                &mut delegate,
+                protocol::credentials::helper,
+                fetch_progress,
+                protocol::FetchConnection::TerminateOnSuccessfulCompletion,
+            ))
+            .map(|_| delegate)
+        })
+        .await?;
+        finish(destination, url, delegate, progress, ctx)
+    }
+}
+
+#[cfg(feature = "async-client")]
+pub use self::async_io::clone;
+
+/// `git upload-pack --strict` only accepts the path to a repository itself, but `git clone` also accepts the path to its
+/// work tree, so turn the latter into the former.
+pub(crate) fn repository_url(url: &str) -> Cow<'_, str> {
+    let (scheme, path) = match url.strip_prefix("file://") {
+        Some(path) => ("file://", path),
+        None if !url.contains("://") => ("", url),
+        None => return url.into(),
+    };
+    let dot_git = Path::new(path).join(".git");
+    if dot_git.is_dir() {
+        format!("{}{}", scheme, dot_git.display()).into()
+    } else {
+        url.into()
+    }
+}
+
+/// Like `git clone`, remember local repositories by their absolute path so they can still be fetched from within the clone.
+fn remote_url(url: &str) -> Cow<'_, str> {
+    match Path::new(url).canonicalize() {
+        Ok(path) if !url.contains("://") => path.display().to_string().into(),
+        _ => url.into(),
+    }
+}
+
+/// Return the objects directory of the repository at `url` if it is a path, which is the only case in which `git clone`
+/// copies objects instead of fetching them. `file://` URLs are always fetched from.
+fn local_objects_dir(url: &str) -> Option<PathBuf> {
+    if url.contains("://") {
+        return None;
+    }
+    let objects_dir = Path::new(repository_url(url).as_ref()).join("objects");
+    if objects_dir.is_dir() {
+        Some(objects_dir)
+    } else {
+        None
+    }
+}
+
+/// Hardlink or copy all files in the `source` object directory into `destination`, like `git clone --local` does.
+///
+/// Relative paths in `info/alternates` are made absolute as they would otherwise resolve relative to `destination`.
+fn copy_objects(source: &Path, destination: &Path, mut hardlinks: bool) -> anyhow::Result<LocalObjects> {
+    let mut out = LocalObjects::default();
+    let mut directories = vec![PathBuf::new()];
+    while let Some(relative_dir) = directories.pop() {
+        std::fs::create_dir_all(destination.join(&relative_dir))?;
+        for entry in std::fs::read_dir(source.join(&relative_dir))? {
+            let entry = entry?;
+            let relative_path = relative_dir.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                directories.push(relative_path);
+                continue;
+            }
+            let (from, to) = (entry.path(), destination.join(&relative_path));
+            if relative_path == Path::new("info").join("alternates") {
+                let alternates = std::fs::read_to_string(&from)?;
+                let absolute: Vec<_> = alternates
+                    .lines()
+                    .map(|line| match line {
+                        "" => line.into(),
+                        line if line.starts_with('#') || Path::new(line).is_absolute() => line.into(),
+                        line => source.join(line).display().to_string(),
+                    })
+                    .collect();
+                std::fs::write(&to, absolute.join("\n") + "\n")?;
+                out.copied += 1;
+                continue;
+            }
+            if hardlinks {
+                if std::fs::hard_link(&from, &to).is_ok() {
+                    out.linked += 1;
+                    continue;
+                }
+                // Most likely the destination is on another device, so don't try again.
+                hardlinks = false;
+            }
+            std::fs::copy(&from, &to)?;
+            out.copied += 1;
+        }
+    }
+    Ok(out)
+}
+
+/// The directory being cloned into, which is removed again unless the clone [succeeds][Destination::keep()].
+struct Destination {
+    path: PathBuf,
+    existed: bool,
+    keep: bool,
+}
+
+impl Destination {
+    fn keep(mut self) -> PathBuf {
+        self.keep = true;
+        std::mem::take(&mut self.path)
+    }
+}
+
+impl Drop for Destination {
+    fn drop(&mut self) {
+        if self.keep {
+            return;
+        }
+        log::debug!("removing clone that didn't complete path={}", self.path.display());
+        if self.existed {
+            if let Ok(entries) = std::fs::read_dir(&self.path) {
+                for entry in entries.filter_map(Result::ok) {
+                    let path = entry.path();
+                    if path.is_dir() {
+                        std::fs::remove_dir_all(path).ok();
+                    } else {
+                        std::fs::remove_file(path).ok();
+                    }
+                }
+            }
+        } else {
+            std::fs::remove_dir_all(&self.path).ok();
+        }
+    }
+}
+
+/// Create the repository to clone `url` into, failing if `directory` exists and isn't empty, just like `git clone`.
+fn init<W>(url: &str, directory: Option<PathBuf>, ctx: &Context<W>) -> anyhow::Result<(Destination, CloneDelegate)> {
+    let bare = ctx.bare || ctx.mirror;
+    let path = directory.unwrap_or_else(|| directory_from_url(url, bare));
+    let existed = match std::fs::read_dir(&path) {
+        Ok(mut entries) => {
+            if entries.next().is_some() {
+                bail!(
+                    "Destination '{}' already exists and is not an empty directory",
+                    path.display()
+                )
+            }
+            true
+        }
+        Err(_) => false,
+    };
+    let destination = Destination {
+        path,
+        existed,
+        keep: false,
+    };
+    log::debug!("writing path={}", destination.path.display());
+    std::fs::create_dir_all(&destination.path)?;
+    let kind = if bare { git::Kind::Bare } else { git::Kind::WorkTree };
+    let options = git::path::create::Options {
+        hook_samples: ctx.hook_samples,
+        ..Default::default()
+    };
+    let repo = git::Repository::init_opts(&destination.path, kind, options)
+        .with_context(|| format!("Could not create repository at '{}'", destination.path.display()))?;
+    let local_objects = match local_objects_dir(url) {
+        Some(objects_dir) if ctx.local => Some({
+            log::debug!(
+                "copying objects path={} hardlinks={}",
+                objects_dir.display(),
+                ctx.hardlinks
+            );
+            copy_objects(&objects_dir, repo.objects_dir(), ctx.hardlinks)
+                .with_context(|| format!("Could not copy objects from '{}'", objects_dir.display()))?
+        }),
+        _ => None,
+    };
+    let mut haves = Vec::new();
+    if let Some(reference) = &ctx.reference {
+        log::debug!("borrowing objects of reference repository path={}", reference.display());
+        let reference = git::Repository::open(reference)
+            .with_context(|| format!("Could not open reference repository at '{}'", reference.display()))?;
+        git::odb::alternate::add(repo.objects_dir(), reference.objects_dir().canonicalize()?)?;
+        let easy = reference.to_easy();
+        // Annotated tags are sent as they are, so they are only known to be present if their objects are advertised.
+        for reference in easy.references()?.all()? {
+            haves.extend(
+                reference
+                    .map_err(|err| anyhow!(err))?
+                    .target()
+                    .as_id()
+                    .map(ToOwned::to_owned),
+            );
+        }
+        haves.sort();
+        haves.dedup();
+    }
+    Ok((
+        destination,
+        CloneDelegate {
+            have_objects: local_objects.is_some(),
+            mirror: ctx.mirror,
+            branch: ctx.branch.clone(),
+            single_branch: ctx.single_branch,
+            tags: ctx.tags,
+            checkout: None,
+            local_objects,
+            haves,
+            odb: Arc::new(linked::Store::at(repo.objects_dir())?),
+            pack_directory: repo.objects_dir().join("pack"),
+            thread_limit: ctx.thread_limit,
+            should_interrupt: Arc::clone(&ctx.should_interrupt),
+            refs: Vec::new(),
+            pack: None,
+        },
+    ))
+}
+
+/// Configure the remote to fetch with the `fetch` refspec, if any, and to not fetch tags unless `tags` is set.
+fn write_remote_config(
+    git_dir: &Path,
+    url: &str,
+    fetch: Option<&RefSpec>,
+    mirror: bool,
+    tags: bool,
+) -> anyhow::Result<()> {
+    let path = git_dir.join("config");
+    let mut config = git_config::file::GitConfig::open(&path)?;
+    let mut section = config.new_section("remote", Cow::Borrowed(REMOTE));
+    section.push("url".into(), Cow::Borrowed(url.as_bytes()));
+    if let Some(fetch) = fetch {
+        section.push("fetch".into(), Cow::Owned(fetch.to_string().into_bytes()));
+    }
+    if mirror {
+        section.push("mirror".into(), Cow::Borrowed(b"true"));
+    }
+    if !tags {
+        section.push("tagOpt".into(), Cow::Borrowed(b"--no-tags"));
+    }
+    std::fs::write(&path, Vec::<u8>::from(&config))?;
+    Ok(())
+}
+
+/// Configure the remote and write the references received by `delegate`, point `HEAD` to the remote's `HEAD` or the branch
+/// to check out, and check it out.
+fn finish<W: io::Write>(
+    destination: Destination,
+    url: &str,
+    delegate: CloneDelegate,
+    mut progress: impl Progress,
+    ctx: Context<W>,
+) -> anyhow::Result<()> {
+    let Context {
+        format,
+        out,
+        bare,
+        mirror,
+        single_branch,
+        tags,
+        dissociate,
+        should_interrupt,
+        ..
+    } = ctx;
+    let bare = bare || mirror;
+    // Open the repository again to see the pack we just received.
+    let repo = git::Repository::open(&destination.path)?;
+    let refs: Vec<_> = delegate
+        .refs
+        .iter()
+        .filter(|r| {
+            let (path, id) = r.unpack();
+            !delegate.is_followed(path) || id.map_or(false, |id| repo.odb.contains(id))
+        })
+        .cloned()
+        .collect();
+    let CloneDelegate {
+        pack,
+        local_objects,
+        checkout,
+        ..
+    } = delegate;
+
+    let mut refs_progress = progress.add_child("update references");
+    let log = |name: &str, new: Target| RefEdit {
+        change: Change::Update {
+            log: LogChange {
+                mode: RefLog::AndReference,
+                force_create_reflog: false,
+                message: format!("clone: from {}", url).into(),
+            },
+            expected: PreviousValue::Any,
+            new,
+        },
+        name: name.try_into().expect("valid reference name"),
+        deref: false,
+    };
+    // Branches are stored as remote tracking branches unless the repository is bare, all other references keep their name.
+    let tracking = (!bare).then(|| RefSpec {
+        mode: Mode::Force,
+        source: "refs/heads/*".into(),
+        destination: Some(format!("refs/remotes/{}/*", REMOTE).into()),
+    });
+    let tracking_name = |path: &BString| {
+        tracking
+            .as_ref()
+            .and_then(|spec| spec.destination_of(path.as_bstr()))
+            .unwrap_or_else(|| path.clone())
+            .to_string()
+    };
+    let mut edits: Vec<_> = refs
+        .iter()
+        .filter_map(|r| match r {
+            Ref::Direct { path, object }
+            | Ref::Peeled { path, tag: object, .. }
+            | Ref::Symbolic { path, object, .. }
+                if path != "HEAD" =>
+            {
+                Some(log(&tracking_name(path), Target::Peeled(*object)))
+            }
+            _ => None,
+        })
+        .collect();
+    let fetch_refspec = match &checkout {
+        _ if mirror => Some(RefSpec {
+            mode: Mode::Force,
+            source: "refs/*".into(),
+            destination: Some("refs/*".into()),
+        }),
+        _ if bare => None,
+        Some(path) if single_branch => Some(RefSpec {
+            mode: Mode::Force,
+            source: path.clone(),
+            destination: Some(tracking_name(path).into()),
+        }),
+        None if single_branch => None,
+        _ => tracking.clone(),
+    };
+    write_remote_config(repo.git_dir(), &remote_url(url), fetch_refspec.as_ref(), mirror, tags)?;
+
+    let remote_head = refs.iter().find(|r| r.unpack().0 == "HEAD");
+    if let (Some(Ref::Symbolic { target, .. }), false) = (remote_head, bare) {
+        if refs.iter().any(|r| r.unpack().0 == target) {
+            edits.push(log(
+                &format!("refs/remotes/{}/HEAD", REMOTE),
+                Target::Symbolic(tracking_name(target).try_into()?),
+            ));
+        }
+    }
+    // Check out the selected branch like the remote `HEAD` pointing to it, or detach `HEAD` at the selected tag.
+    let head = match checkout.and_then(|name| refs.iter().find(|r| *r.unpack().0 == name)) {
+        Some(Ref::Direct { path, object } | Ref::Symbolic { path, object, .. })
+            if path.starts_with_str("refs/heads/") =>
+        {
+            Some(Ref::Symbolic {
+                path: "HEAD".into(),
+                target: path.clone(),
+                object: *object,
+            })
+        }
+        Some(Ref::Direct { object, .. } | Ref::Peeled { object, .. } | Ref::Symbolic { object, .. }) => {
+            Some(Ref::Direct {
+                path: "HEAD".into(),
+                object: *object,
+            })
+        }
+        Some(Ref::Unborn { .. }) | None => remote_head.cloned(),
+    };
+    let branch = match &head {
+        Some(Ref::Symbolic { target, object, .. }) => {
+            if !bare {
+                edits.push(log(&target.to_string(), Target::Peeled(*object)));
+            }
+            edits.push(log("HEAD", Target::Symbolic(target.to_string().try_into()?)));
+            target
+                .strip_prefix(b"refs/heads/")
+                .map(|name| name.as_bstr().to_string())
+        }
+        Some(Ref::Unborn { target, .. }) => {
+            edits.push(log("HEAD", Target::Symbolic(target.to_string().try_into()?)));
+            target
+                .strip_prefix(b"refs/heads/")
+                .map(|name| name.as_bstr().to_string())
+        }
+        Some(Ref::Direct { object, .. }) => {
+            edits.push(log("HEAD", Target::Peeled(*object)));
+            None
+        }
+        _ => None,
+    };
+    refs_progress.init(Some(edits.len()), git::progress::count("references"));
+    {
+        let easy = repo.to_easy();
+        easy.edit_references(edits, git::lock::acquire::Fail::Immediately, None)?;
+        if let (Some(branch), false) = (&branch, bare) {
+            easy.branches()
+                .set_upstream(branch, REMOTE, &format!("refs/heads/{}", branch))?;
+        }
+    }
+    refs_progress.set(refs_progress.max().unwrap_or_default());
+
+    let dissociated = if dissociate {
+        let mut dissociate_progress = progress.add_child("dissociate");
+        dissociate_progress.init(None, git::progress::count("objects"));
+        // Use the fastest compression as the borrowed objects are likely to be repacked later anyway.
+        let outcome = repo.dissociate(1, &should_interrupt)?;
+        dissociate_progress.set(outcome.packed);
+        Some(outcome.packed)
+    } else {
+        None
+    };
+
+    let mut checkout = git::checkout::Outcome::default();
+    let head_id = head.as_ref().and_then(|r| r.unpack().1.copied());
+    if let (Some(head_id), false) = (head_id, bare) {
+        let tree = repo
+            .odb
+            .find_commit(head_id, &mut Vec::new(), &mut pack::cache::Never)?
+            .tree();
+        // The work tree is empty, so the only possible conflicts are paths colliding on this filesystem. Like `git`, check
+        // them out anyway and let the last one of each group win.
+        checkout = repo.checkout(
+            tree,
+            git::checkout::Options {
+                previous: None,
+                force: true,
+            },
+            progress.add_child("checkout"),
+        )?;
+        for paths in checkout.collisions() {
+            log::warn!(
+                "paths collided on this filesystem and only the last one is in the work tree paths={}",
+                paths
+                    .iter()
+                    .map(|path| path.to_str_lossy())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            );
+        }
+        repo.read_tree(&tree, None)?;
+    }
+
+    output::write_one(
+        format,
+        out,
+        &Outcome {
+            directory: destination.keep(),
+            pack: pack.map(|outcome| outcome.index.into()),
+            local_objects,
+            dissociated,
+            refs: refs.iter().filter(|r| r.unpack().1.is_some()).count(),
+            branch,
+            head: head_id.map(|id| id.to_string()),
+            files_written: checkout.files_written,
+            symlinks_written: checkout.symlinks_written,
+            bytes_written: checkout.bytes_written,
+            collisions: checkout.collisions().count(),
+        },
+    )?;
+    Ok(())
+}
+
+/// The amount of files of a local object database that were hardlinked or copied.
+#[derive(Default)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct LocalObjects {
+    /// The amount of files that were hardlinked.
+    pub linked: usize,
+    /// The amount of files that were copied, either because hardlinks were disabled or because they could not be created.
+    pub copied: usize,
+}
+
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outcome {
+    /// The directory the repository was cloned into.
+    pub directory: PathBuf,
+    /// The received pack, or `None` if the remote repository is empty or its objects were copied.
+    pub pack: Option<JsonBundleWriteOutcome>,
+    /// The files of the object database of a local repository, if they were copied instead of fetching a pack.
+    pub local_objects: Option<LocalObjects>,
+    /// The amount of objects copied from alternates into the clone if it was dissociated from them.
+    pub dissociated: Option<usize>,
+    /// The amount of cloned references.
+    pub refs: usize,
+    /// The branch `HEAD` points to, or `None` if it is detached or the remote repository is empty and didn't advertise its
+    /// default branch.
+    pub branch: Option<String>,
+    /// The commit `HEAD` points to, or `None` if the remote repository is empty.
+    pub head: Option<String>,
+    /// The amount of files written into the work tree, including symbolic links.
+    pub files_written: usize,
+    /// The amount of symbolic links written into the work tree.
+    pub symlinks_written: usize,
+    /// The amount of bytes written into the work tree.
+    pub bytes_written: u64,
+    /// The amount of groups of paths which refer to the same file on this filesystem, of which only one file was written.
+    pub collisions: usize,
+}
+
+impl output::Record for Outcome {
+    fn write_human(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        writeln!(out, "Cloned into '{}'", self.directory.display())?;
+        if let Some(pack) = &self.pack {
+            writeln!(out, "pack: {} ({} objects)", pack.data_hash, pack.num_objects)?;
+        }
+        if let Some(local) = &self.local_objects {
+            writeln!(out, "object files: {} linked, {} copied", local.linked, local.copied)?;
+        }
+        if let Some(packed) = self.dissociated {
+            writeln!(out, "dissociated: {} objects copied from alternates", packed)?;
+        }
+        writeln!(out, "references: {}", self.refs)?;
+        match (&self.branch, &self.head) {
+            (Some(branch), Some(head)) => writeln!(out, "HEAD: {} ({})", branch, head)?,
+            (None, Some(head)) => writeln!(out, "HEAD: {} (detached)", head)?,
+            (Some(branch), None) => writeln!(out, "HEAD: {} (unborn, the remote repository is empty)", branch)?,
+            _ => writeln!(out, "HEAD: unborn, the remote repository is empty")?,
+        }
+        writeln!(
+            out,
+            "files written: {} ({} symlinks, {})",
+            self.files_written,
+            self.symlinks_written,
+            bytesize::ByteSize(self.bytes_written)
+        )?;
+        if self.collisions != 0 {
+            writeln!(
+                out,
+                "collisions: {} groups of paths refer to the same file, of which only one is in the work tree",
+                self.collisions
+            )?;
+        }
+        Ok(())
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        use output::field;
+        let mut fields = vec![field("directory", self.directory.display())];
+        if let Some(pack) = &self.pack {
+            fields.push(field("pack.data_hash", &pack.data_hash));
+            fields.push(field("pack.num_objects", pack.num_objects));
+        }
+        if let Some(local) = &self.local_objects {
+            fields.push(field("local_objects.linked", local.linked));
+            fields.push(field("local_objects.copied", local.copied));
+        }
+        fields.extend(self.dissociated.iter().map(|packed| field("dissociated", packed)));
+        fields.push(field("refs", self.refs));
+        fields.extend(self.branch.iter().map(|branch| field("branch", branch)));
+        fields.extend(self.head.iter().map(|head| field("head", head)));
+        fields.push(field("files_written", self.files_written));
+        fields.push(field("symlinks_written", self.symlinks_written));
+        fields.push(field("bytes_written", self.bytes_written));
+        fields.push(field("collisions", self.collisions));
+        fields
+    }
+}
@@ -2,40 +2,49 @@ use std::{io, path::Path};
 
 use anyhow::{Context as AnyhowContext, Result};
 use git_commitgraph::{graph::verify::Outcome, Graph};
+use git_repository as git;
+use git_repository::odb::FindExt;
 
-use crate::OutputFormat;
+use crate::{diagnostics::Operation, output, OutputFormat};
 
 /// A general purpose context for many operations provided here
-pub struct Context<W1: io::Write, W2: io::Write> {
-    /// A stream to which to output errors
-    pub err: W2,
+pub struct Context<W: io::Write> {
     /// A stream to which to output operation results
-    pub out: W1,
+    pub out: W,
     pub output_statistics: Option<OutputFormat>,
+    /// If set, also verify each commit in the graph against its object in the object database, which is expected to be
+    /// the parent directory of the `info` directory containing the graph.
+    pub verify_objects: bool,
+    /// If set, don't use more than this amount of threads for verifying objects.
+    pub thread_limit: Option<usize>,
 }
 
-impl Default for Context<Vec<u8>, Vec<u8>> {
+impl Default for Context<Vec<u8>> {
     fn default() -> Self {
         Context {
-            err: Vec::new(),
             out: Vec::new(),
             output_statistics: None,
+            verify_objects: false,
+            thread_limit: None,
         }
     }
 }
 
-pub fn graph_or_file<W1, W2>(
+pub fn graph_or_file<W>(
     path: impl AsRef<Path>,
     Context {
-        err: _err,
         mut out,
         output_statistics,
-    }: Context<W1, W2>,
+        verify_objects,
+        thread_limit,
+    }: Context<W>,
 ) -> Result<git_commitgraph::graph::verify::Outcome>
 where
-    W1: io::Write,
-    W2: io::Write,
+    W: io::Write,
 {
+    let _op = Operation::start(module_path!(), "commit-graph-verify");
+    let path = path.as_ref();
+    log::debug!("reading path={}", path.display());
     let g = Graph::at(path).with_context(|| "Could not open commit graph")?;
 
     #[allow(clippy::unnecessary_wraps, unknown_lints)]
@@ -46,17 +55,57 @@ where
         .verify_integrity(noop_processor)
         .with_context(|| "Verification failure")?;
 
-    #[cfg_attr(not(feature = "serde1"), allow(clippy::single_match))]
-    match output_statistics {
-        Some(OutputFormat::Human) => drop(print_human_output(&mut out, &stats)),
-        #[cfg(feature = "serde1")]
-        Some(OutputFormat::Json) => serde_json::to_writer_pretty(out, &stats)?,
-        _ => {}
+    if verify_objects {
+        let objects_dir = path
+            .ancestors()
+            .find(|dir| dir.file_name().map_or(false, |name| name == "info"))
+            .and_then(Path::parent)
+            .with_context(|| {
+                format!(
+                    "Could not find the object database of the commit graph at '{}'",
+                    path.display()
+                )
+            })?;
+        let odb = git::odb::linked::Store::at(objects_dir)
+            .with_context(|| format!("Could not open the object database at '{}'", objects_dir.display()))?;
+        g.verify_objects(
+            |id, buf| odb.find_commit_iter(id, buf, &mut git::odb::pack::cache::Never).ok(),
+            thread_limit,
+        )
+        .with_context(|| "Verification of commit objects failed")?;
+    }
+
+    if let Some(format) = output_statistics {
+        output::write_one(format, &mut out, &stats)?;
     }
 
     Ok(stats)
 }
 
+impl output::Record for Outcome {
+    fn write_human(&self, mut out: &mut dyn io::Write) -> io::Result<()> {
+        print_human_output(&mut out, self)
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        use output::field;
+        let mut fields = vec![
+            field(
+                "longest_path_length",
+                self.longest_path_length
+                    .map_or_else(|| "unknown".into(), |n| n.to_string()),
+            ),
+            field("num_commits", self.num_commits),
+        ];
+        fields.extend(
+            self.parent_counts
+                .iter()
+                .map(|(parents, commits)| field(format!("parent_counts.{}", parents), commits)),
+        );
+        fields
+    }
+}
+
 fn print_human_output(out: &mut impl io::Write, stats: &Outcome) -> io::Result<()> {
     writeln!(out, "number of commits with the given number of parents")?;
     let mut parent_counts: Vec<_> = stats.parent_counts.iter().map(|(a, b)| (*a, *b)).collect();
@@ -0,0 +1,165 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context as AnyhowContext};
+use git_repository as git;
+use git_repository::{
+    hash::ObjectId,
+    objs::commit::extract,
+    odb::{pack, FindExt},
+    prelude::ReferenceAccessExt,
+    progress, Progress,
+};
+
+use crate::{diagnostics::Operation, output, OutputFormat};
+
+pub const PROGRESS_RANGE: std::ops::RangeInclusive<u8> = 1..=2;
+
+mod find_commit {
+    use git_repository::{hash::ObjectId, objs, odb};
+    use quick_error::quick_error;
+
+    quick_error! {
+        #[derive(Debug)]
+        pub enum Error {
+            Find(err: odb::compound::find::Error) {
+                display("A commit could not be read from the object database")
+                source(err)
+            }
+            ObjectKind { id: ObjectId, actual: objs::Kind } {
+                display("Expected {} to be a commit, but it is a {}", id, actual)
+            }
+            Extract(err: objs::commit::extract::Error) {
+                display("A commit could not be decoded")
+                from()
+                source(err)
+            }
+        }
+    }
+}
+
+pub struct Context<W> {
+    pub format: OutputFormat,
+    pub out: W,
+}
+
+/// The commit-graph file that was written.
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outcome {
+    /// The path of the written file.
+    pub path: PathBuf,
+    /// The checksum of the written file.
+    pub checksum: String,
+    /// The amount of commits in the written file.
+    pub num_commits: u32,
+    /// The largest generation number of all commits, which is the length of the longest path from a root commit, or `None`
+    /// if there are no commits.
+    pub max_generation: Option<u32>,
+}
+
+impl output::Record for Outcome {
+    fn write_human(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        writeln!(out, "{} ({} commits)", self.path.display(), self.num_commits)?;
+        writeln!(out, "checksum: {}", self.checksum)?;
+        if let Some(generation) = self.max_generation {
+            writeln!(out, "max generation: {}", generation)?;
+        }
+        Ok(())
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        use output::field;
+        let mut fields = vec![
+            field("path", self.path.display()),
+            field("checksum", &self.checksum),
+            field("num_commits", self.num_commits),
+        ];
+        if let Some(generation) = self.max_generation {
+            fields.push(field("max_generation", generation));
+        }
+        fields
+    }
+}
+
+/// Write the `objects/info/commit-graph` file of the repository at `repository` with all commits reachable from the
+/// commit ids read from `tips`, one per line, or from all references if `tips` is `None`, like `git commit-graph write` does.
+pub fn graph(
+    repository: impl AsRef<Path>,
+    tips: Option<impl io::BufRead>,
+    mut progress: impl Progress,
+    Context { format, out }: Context<impl io::Write>,
+) -> anyhow::Result<()> {
+    let _op = Operation::start(module_path!(), "commit-graph-write");
+    let repo = git::discover(repository)?;
+    let odb = &repo.odb;
+    let tips = match tips {
+        Some(input) => input
+            .lines()
+            .map(|line| {
+                let line = line?;
+                ObjectId::from_hex(line.trim().as_bytes())
+                    .with_context(|| format!("Could not decode '{}' as commit id", line))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?,
+        None => {
+            log::debug!("using all references as tips as none were given");
+            let mut tips = Vec::new();
+            let easy = repo.to_easy();
+            for reference in easy.references()?.all()?.peeled() {
+                let id = reference.map_err(|err| anyhow!(err))?.id().detach();
+                // References may point to trees or blobs, which aren't part of the commit graph.
+                if odb
+                    .find_commit_iter(id, &mut Vec::new(), &mut pack::cache::Never)
+                    .is_ok()
+                {
+                    tips.push(id);
+                }
+            }
+            tips
+        }
+    };
+
+    let start = std::time::Instant::now();
+    let mut walk_progress = progress.add_child("traversing");
+    walk_progress.init(None, progress::count("commits"));
+    let mut buf = Vec::new();
+    let info_dir = repo.objects_dir().join("info");
+    std::fs::create_dir_all(&info_dir)?;
+    let outcome = git_commitgraph::write(&info_dir, tips, |id| {
+        walk_progress.inc();
+        // Only the fields stored in the graph are read, which is faster than decoding the entire commit.
+        let object = match odb.find(id, &mut buf, &mut pack::cache::Never) {
+            Ok(object) => object,
+            Err(pack::find::existing::Error::NotFound { .. }) => return Ok(None),
+            Err(pack::find::existing::Error::Find(err)) => return Err(find_commit::Error::Find(err)),
+        };
+        if object.kind != git::objs::Kind::Commit {
+            return Err(find_commit::Error::ObjectKind {
+                id: id.to_owned(),
+                actual: object.kind,
+            });
+        }
+        Ok(Some(git_commitgraph::write::Commit {
+            tree: extract::tree_id(object.data)?,
+            parents: extract::parent_ids(object.data).collect::<Result<_, _>>()?,
+            committer_timestamp: extract::committer_time(object.data)?.time as u64,
+        }))
+    })
+    .with_context(|| format!("Could not write commit-graph into '{}'", info_dir.display()))?;
+    walk_progress.show_throughput(start);
+    log::debug!("wrote path={}", outcome.path.display());
+
+    output::write_one(
+        format,
+        out,
+        &Outcome {
+            path: outcome.path,
+            checksum: outcome.id.to_string(),
+            num_commits: outcome.num_commits,
+            max_generation: outcome.max_generation,
+        },
+    )?;
+    Ok(())
+}
@@ -0,0 +1,41 @@
+//! Diagnostic events of all operations, emitted through the `log` crate instead of being written to a stream.
+//!
+//! This way applications embedding this crate decide where events go and how verbose they are by configuring their logger.
+//! Events use the module path of the operation as target, and their message is followed by `key=value` pairs to be easy to
+//! parse, like `finished operation=pack-verify elapsed=1.20s`.
+//!
+//! * **info** - the start and duration of operations.
+//! * **debug** - the paths operations read from or write to.
+//! * **warn** - fallback decisions that reduce what an operation can do, like verifying only the index if a pack is missing.
+use std::time::Instant;
+
+/// An operation which logs when it starts, and how long it took once dropped.
+#[must_use = "the operation finishes when dropped"]
+pub(crate) struct Operation {
+    target: &'static str,
+    name: &'static str,
+    start: Instant,
+}
+
+impl Operation {
+    /// Log the start of the operation `name` with the given `target`, which should be the `module_path!()` of the caller.
+    pub(crate) fn start(target: &'static str, name: &'static str) -> Self {
+        log::info!(target: target, "started operation={}", name);
+        Operation {
+            target,
+            name,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        log::info!(
+            target: self.target,
+            "finished operation={} elapsed={:.2}s",
+            self.name,
+            self.start.elapsed().as_secs_f32()
+        );
+    }
+}
@@ -0,0 +1,679 @@
+use std::{
+    convert::TryInto,
+    fmt, io,
+    path::PathBuf,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use anyhow::{anyhow, bail};
+use git_repository as git;
+use git_repository::{
+    bstr::{BStr, BString, ByteSlice},
+    fetch_head,
+    odb::{linked, pack, Find},
+    prelude::ReferenceAccessExt,
+    protocol,
+    protocol::{
+        fetch::{Action, Arguments, LsRefsAction, Ref, Response},
+        refspec::{self, RefSpec},
+        transport,
+    },
+    refs::{
+        transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+        Target,
+    },
+    ObjectId, Progress,
+};
+
+use crate::{output, pack::receive::JsonBundleWriteOutcome, OutputFormat};
+
+pub const PROGRESS_RANGE: std::ops::RangeInclusive<u8> = 1..=3;
+
+/// The name of the remote to fetch from if none is given.
+const DEFAULT_REMOTE: &str = "origin";
+
+pub struct Context<W> {
+    pub thread_limit: Option<usize>,
+    pub format: OutputFormat,
+    pub should_interrupt: Arc<AtomicBool>,
+    pub out: W,
+    /// Which tags to fetch, or `None` to use `remote.<name>.tagOpt`, following tags by default.
+    pub tags: Option<Tags>,
+}
+
+/// Which tags to fetch in addition to the references matched by the refspecs of a remote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tags {
+    /// Fetch the tags pointing to objects which are present after fetching, like `git fetch` does by default.
+    Follow,
+    /// Fetch all tags, like `git fetch --tags`.
+    All,
+    /// Don't fetch any tags unless the refspecs match them, like `git fetch --no-tags`.
+    None,
+}
+
+impl Tags {
+    /// Interpret the value of `remote.<name>.tagOpt`, which is `--tags` or `--no-tags`, and follow tags otherwise.
+    fn from_tag_opt(value: Option<&BStr>) -> Self {
+        match value.map(|v| v.as_bytes()) {
+            Some(b"--tags") => Tags::All,
+            Some(b"--no-tags") => Tags::None,
+            _ => Tags::Follow,
+        }
+    }
+}
+
+/// Return the local name of the remote reference `name` along with the refspec mapping it, if any of `refspecs` does.
+fn local_name<'a>(refspecs: &'a [RefSpec], name: &BStr) -> Option<(BString, &'a RefSpec)> {
+    refspec::map(refspecs, Some(name))
+        .into_iter()
+        .find_map(|mapping| Some((mapping.local?, &refspecs[mapping.spec_index])))
+}
+
+struct FetchDelegate {
+    refspecs: Vec<RefSpec>,
+    /// The tips of all local references, which are what we tell the remote we have.
+    haves: Vec<ObjectId>,
+    odb: Arc<linked::Store>,
+    pack_directory: PathBuf,
+    thread_limit: Option<usize>,
+    should_interrupt: Arc<AtomicBool>,
+    /// The remote references matched by any of our refspecs, without unborn ones as there is nothing to fetch for them.
+    refs: Vec<Ref>,
+    /// If true, tags not matched by any refspec are fetched if they point to objects we have or receive.
+    follow_tags: bool,
+    /// The remote tags not matched by any refspec, which are candidates for being followed.
+    tags: Vec<Ref>,
+    pack: Option<pack::bundle::write::Outcome>,
+}
+
+impl FetchDelegate {
+    fn wants<'a>(&'a self, refs: &'a [Ref]) -> impl Iterator<Item = &'a ObjectId> + 'a {
+        refs.iter()
+            .filter(move |r| local_name(&self.refspecs, r.unpack().0.as_bstr()).is_some())
+            .filter_map(|r| r.unpack().1)
+            .chain(self.tags.iter().filter_map(move |r| match r {
+                // Annotated tags pointing into history we have already won't be sent along with new objects.
+                Ref::Peeled { tag, object, .. } if self.odb.contains(object) => Some(tag),
+                _ => None,
+            }))
+            .filter(move |id| !self.odb.contains(id))
+    }
+}
+
+impl protocol::fetch::DelegateBlocking for FetchDelegate {
+    fn prepare_ls_refs(
+        &mut self,
+        server: &transport::client::Capabilities,
+        arguments: &mut Vec<BString>,
+        _features: &mut Vec<(&str, Option<&str>)>,
+    ) -> io::Result<LsRefsAction> {
+        if server.contains("ls-refs") {
+            arguments.extend(
+                self.refspecs
+                    .iter()
+                    .flat_map(|spec| spec.prefixes())
+                    .chain(self.follow_tags.then(|| "refs/tags/".into()))
+                    .map(|prefix| format!("ref-prefix {}", prefix).into()),
+            );
+        }
+        Ok(LsRefsAction::Continue)
+    }
+
+    fn prepare_fetch(
+        &mut self,
+        _version: transport::Protocol,
+        server: &transport::client::Capabilities,
+        features: &mut Vec<(&str, Option<&str>)>,
+        refs: &[Ref],
+    ) -> io::Result<Action> {
+        if self.follow_tags {
+            self.tags = refs
+                .iter()
+                .filter(|r| {
+                    let name = r.unpack().0;
+                    name.starts_with_str("refs/tags/") && local_name(&self.refspecs, name.as_bstr()).is_none()
+                })
+                .cloned()
+                .collect();
+            if server.contains("include-tag") {
+                features.push(("include-tag", None));
+            }
+        }
+        self.refs = refs
+            .iter()
+            .filter(|r| {
+                let (name, id) = r.unpack();
+                id.is_some() && local_name(&self.refspecs, name.as_bstr()).is_some()
+            })
+            .cloned()
+            .collect();
+        Ok(if self.wants(refs).next().is_none() {
+            Action::Cancel
+        } else {
+            Action::Continue
+        })
+    }
+
+    fn negotiate(
+        &mut self,
+        refs: &[Ref],
+        arguments: &mut Arguments,
+        _previous_response: Option<&Response>,
+    ) -> io::Result<Action> {
+        for id in self.wants(refs) {
+            arguments.want(id);
+        }
+        for id in &self.haves {
+            arguments.have(id);
+        }
+        Ok(Action::Cancel)
+    }
+}
+
+impl FetchDelegate {
+    fn receive_pack(&mut self, input: impl io::BufRead, progress: impl Progress) -> io::Result<()> {
+        let options = pack::bundle::write::Options {
+            thread_limit: self.thread_limit,
+            index_kind: pack::index::Version::V2,
+            iteration_mode: pack::data::input::Mode::Verify,
+            write_reverse_index: false,
+            fsync: Default::default(),
+        };
+        let odb = Arc::clone(&self.odb);
+        self.pack = Some(
+            pack::Bundle::write_to_directory(
+                input,
+                Some(&self.pack_directory),
+                progress,
+                &self.should_interrupt,
+                Some(Box::new(move |oid, buf| {
+                    odb.try_find(oid, buf, &mut pack::cache::Never).ok().flatten()
+                })),
+                options,
+            )
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?,
+        );
+        Ok(())
+    }
+}
+
+#[cfg(feature = "blocking-client")]
+mod blocking_io {
+    use std::{io, io::BufRead, path::PathBuf, sync::Arc};
+
+    use git_repository::{
+        protocol,
+        protocol::fetch::{Ref, Response},
+        Progress,
+    };
+
+    use super::{finish, init, Context, FetchDelegate};
+    use crate::{clone::repository_url, diagnostics::Operation, net};
+
+    impl protocol::fetch::Delegate for FetchDelegate {
+        fn receive_pack(
+            &mut self,
+            input: impl BufRead,
+            progress: impl Progress,
+            _refs: &[Ref],
+            _previous_response: &Response,
+        ) -> io::Result<()> {
+            FetchDelegate::receive_pack(self, input, progress)
+        }
+    }
+
+    pub fn fetch<P: Progress, W: io::Write>(
+        protocol: Option<net::Protocol>,
+        repository: PathBuf,
+        remote: Option<String>,
+        mut progress: P,
+        ctx: Context<W>,
+    ) -> anyhow::Result<()> {
+        let _op = Operation::start(module_path!(), "fetch");
+        let (remote, mut delegate) = init(repository, remote, &ctx)?;
+        log::debug!("fetching url={}", remote.url);
+        let mut transport = net::connect(
+            repository_url(&remote.url).as_bytes(),
+            protocol.unwrap_or_default().into(),
+        )?;
+        remote.configure(&mut transport)?;
+        transport.set_should_interrupt(Arc::clone(&ctx.should_interrupt));
+        let url = transport.to_url();
+        protocol::fetch(
+            &mut transport,
+            &mut delegate,
+            protocol::credentials::helper,
+            progress.add_child("fetch"),
+            protocol::FetchConnection::TerminateOnSuccessfulCompletion,
+        )?;
+        remote.report_redirect(&url, &transport);
+        finish(remote, delegate, progress, ctx)
+    }
+}
+
+#[cfg(feature = "blocking-client")]
+pub use blocking_io::fetch;
+
+#[cfg(feature = "async-client")]
+mod async_io {
+    use std::{io, path::PathBuf};
+
+    use async_trait::async_trait;
+    use futures_io::AsyncBufRead;
+    use git_repository::{
+        protocol,
+        protocol::{
+            fetch::{Ref, Response},
+            transport::client::TransportWithoutIO,
+        },
+        Progress,
+    };
+
+    use super::{finish, init, Context, FetchDelegate};
+    use crate::{clone::repository_url, diagnostics::Operation, net};
+
+    #[async_trait(?Send)]
+    impl protocol::fetch::Delegate for FetchDelegate {
+        async fn receive_pack(
+            &mut self,
+            input: impl AsyncBufRead + Unpin + 'async_trait,
+            progress: impl Progress,
+            _refs: &[Ref],
+            _previous_response: &Response,
+        ) -> io::Result<()> {
+            FetchDelegate::receive_pack(self, futures_lite::io::BlockOn::new(input), progress)
+        }
+    }
+
+    pub async fn fetch<P: Progress + 'static, W: io::Write + Send + 'static>(
+        protocol: Option<net::Protocol>,
+        repository: PathBuf,
+        remote: Option<String>,
+        mut progress: P,
+        ctx: Context<W>,
+    ) -> anyhow::Result<()> {
+        let _op = Operation::start(module_path!(), "fetch");
+        let (remote, mut delegate) = init(repository, remote, &ctx)?;
+        log::debug!("fetching url={}", remote.url);
+        let mut transport = net::connect(
+            repository_url(&remote.url).as_bytes(),
+            protocol.unwrap_or_default().into(),
+        )
+        .await?;
+        remote.configure(&mut transport)?;
+        let url = transport.to_url();
+        let fetch_progress = progress.add_child("fetch");
+        let (delegate, transport) = blocking::unblock(move || {
+            futures_lite::future::block_on(protocol::fetch(
+                &mut transport,
+                &mut delegate,
+                protocol::credentials::helper,
+                fetch_progress,
+                protocol::FetchConnection::TerminateOnSuccessfulCompletion,
+            ))
+            .map(|_| (delegate, transport))
+        })
+        .await?;
+        remote.report_redirect(&url, &transport);
+        finish(remote, delegate, progress, ctx)
+    }
+}
+
+#[cfg(feature = "async-client")]
+pub use self::async_io::fetch;
+
+/// The remote to fetch from, as configured in the repository.
+struct Remote {
+    repository: git::Repository,
+    name: String,
+    url: String,
+    /// The headers to send with each request if the transport uses http, as configured by `http.extraHeader`.
+    extra_headers: Vec<String>,
+}
+
+impl Remote {
+    /// Prepare `transport` to talk to this remote.
+    fn configure(&self, transport: &mut impl transport::client::TransportWithoutIO) -> anyhow::Result<()> {
+        match transport.set_extra_headers(self.extra_headers.clone()) {
+            // Like in `git`, the `http.*` configuration doesn't affect other transports.
+            Err(transport::client::Error::ExtraHeadersUnsupported) => Ok(()),
+            res => res.map_err(Into::into),
+        }
+    }
+
+    /// Warn if `transport` was redirected away from `url` while fetching, as the remote was probably moved.
+    fn report_redirect(&self, url: &str, transport: &impl transport::client::TransportWithoutIO) {
+        let new_url = transport.to_url();
+        if new_url != url {
+            log::warn!(
+                "repository moved, consider updating remote.{}.url remote={} url={} new_url={}",
+                self.name,
+                self.name,
+                url,
+                new_url
+            );
+        }
+    }
+}
+
+/// Read the configuration of the remote called `name`, or `origin`, from the repository at `repository` and prepare fetching from it.
+fn init<W>(repository: PathBuf, name: Option<String>, ctx: &Context<W>) -> anyhow::Result<(Remote, FetchDelegate)> {
+    let repo = git::discover(repository)?;
+    let name = name.unwrap_or_else(|| DEFAULT_REMOTE.into());
+    let config = git_config::file::GitConfig::open(repo.git_dir().join("config"))?;
+    let url = match config.get_raw_value("remote", Some(&name), "url") {
+        Ok(url) => url.to_str_lossy().into_owned(),
+        Err(_) => bail!("The remote '{}' does not exist or has no url", name),
+    };
+    let mut refspecs = config
+        .get_raw_multi_value("remote", Some(&name), "fetch")
+        .unwrap_or_default()
+        .into_iter()
+        .map(|spec| {
+            RefSpec::from_bytes(spec.as_bstr())
+                .map_err(|err| anyhow::Error::from(err).context(format!("Invalid refspec '{}'", spec.as_bstr())))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let tags = ctx.tags.unwrap_or_else(|| {
+        Tags::from_tag_opt(
+            config
+                .get_raw_value("remote", Some(&name), "tagOpt")
+                .ok()
+                .as_deref()
+                .map(|v| v.as_bstr()),
+        )
+    });
+    if tags == Tags::All {
+        refspecs.push(RefSpec {
+            mode: refspec::Mode::Normal,
+            source: "refs/tags/*".into(),
+            destination: Some("refs/tags/*".into()),
+        });
+    }
+    if refspecs.is_empty() {
+        bail!("The remote '{}' has no fetch refspecs configured", name)
+    }
+    let mut extra_headers = Vec::new();
+    for header in config
+        .get_raw_multi_value("http", None, "extraHeader")
+        .unwrap_or_default()
+    {
+        // An empty value resets the headers configured so far, which allows overriding them in more specific configuration files.
+        match header.to_str_lossy() {
+            header if header.is_empty() => extra_headers.clear(),
+            header => extra_headers.push(header.into_owned()),
+        }
+    }
+
+    let mut haves = Vec::new();
+    {
+        let easy = repo.to_easy();
+        for reference in easy.references()?.all()?.peeled() {
+            haves.push(reference.map_err(|err| anyhow!(err))?.id().detach());
+        }
+    }
+    haves.sort();
+    haves.dedup();
+
+    let delegate = FetchDelegate {
+        refspecs,
+        haves,
+        odb: Arc::new(linked::Store::at(repo.objects_dir())?),
+        pack_directory: repo.objects_dir().join("pack"),
+        thread_limit: ctx.thread_limit,
+        should_interrupt: Arc::clone(&ctx.should_interrupt),
+        refs: Vec::new(),
+        follow_tags: tags == Tags::Follow,
+        tags: Vec::new(),
+        pack: None,
+    };
+    Ok((
+        Remote {
+            repository: repo,
+            name,
+            url,
+            extra_headers,
+        },
+        delegate,
+    ))
+}
+
+/// Update the local references of all remote references received by `delegate` according to the refspecs of `remote`,
+/// and record them in `FETCH_HEAD`.
+fn finish<W: io::Write>(
+    remote: Remote,
+    FetchDelegate {
+        refspecs,
+        refs,
+        tags,
+        pack,
+        ..
+    }: FetchDelegate,
+    mut progress: impl Progress,
+    ctx: Context<W>,
+) -> anyhow::Result<()> {
+    let Context { format, out, .. } = ctx;
+    // Open the repository again to see the pack we just received.
+    let repo = git::Repository::open(remote.repository.git_dir())?;
+    let easy = repo.to_easy();
+
+    // Tags are followed if we have their objects now, and keep their name without ever being forced like `git fetch` does.
+    let followed = tags
+        .iter()
+        .filter(|r| r.unpack().1.map_or(false, |id| repo.odb.contains(id)));
+    let mappings: Vec<_> = refs
+        .iter()
+        .map(|r| {
+            let (local, spec) =
+                local_name(&refspecs, r.unpack().0.as_bstr()).expect("only matching references are kept");
+            (r, local, spec.mode == refspec::Mode::Force)
+        })
+        .chain(followed.map(|r| (r, r.unpack().0.clone(), false)))
+        .collect();
+
+    let mut refs_progress = progress.add_child("update references");
+    refs_progress.init(Some(mappings.len()), git::progress::count("references"));
+    let mut updates = Vec::new();
+    let mut edits = Vec::new();
+    for (r, local, forced) in &mappings {
+        let (name, new) = r.unpack();
+        let new = *new.expect("only references with objects are kept");
+        let previous = match easy.try_find_reference(local.to_str()?)? {
+            Some(reference) => match reference.target().as_id() {
+                Some(id) => Some(id.to_owned()),
+                None => bail!("Refusing to update symbolic reference '{}'", local),
+            },
+            None => None,
+        };
+        let mode = Mode::of_update(&repo, previous, new, *forced);
+        if let Some(message) = mode.log_message() {
+            edits.push(RefEdit {
+                change: Change::Update {
+                    log: LogChange {
+                        mode: RefLog::AndReference,
+                        force_create_reflog: false,
+                        message: format!("fetch {}: {}", remote.name, message).into(),
+                    },
+                    expected: match previous {
+                        Some(previous) => PreviousValue::MustExistAndMatch(Target::Peeled(previous)),
+                        None => PreviousValue::MustNotExist,
+                    },
+                    new: Target::Peeled(new),
+                },
+                name: local.clone().try_into()?,
+                deref: false,
+            });
+        }
+        updates.push(Update {
+            remote: name.to_string(),
+            local: local.to_string(),
+            previous: previous.map(|id| id.to_string()),
+            new: new.to_string(),
+            mode,
+        });
+    }
+    easy.edit_references(edits, git::lock::acquire::Fail::Immediately, None)?;
+    refs_progress.set(mappings.len());
+
+    let merge = match easy.head()?.referent_name() {
+        Some(name) => match name.as_bstr().strip_prefix(b"refs/heads/") {
+            Some(branch) => easy
+                .branches()
+                .upstream(branch.to_str()?)?
+                .filter(|upstream| upstream.remote == remote.name)
+                .map(|upstream| upstream.merge),
+            None => None,
+        },
+        None => None,
+    };
+    let entries: Vec<_> = mappings
+        .iter()
+        .map(|(r, ..)| {
+            let (name, id) = r.unpack();
+            fetch_head::Entry {
+                id: *id.expect("only references with objects are kept"),
+                name: name.clone(),
+                for_merge: merge.as_ref().map_or(false, |merge| merge.as_bstr() == name),
+            }
+        })
+        .collect();
+    repo.write_fetch_head(&entries, remote.url.as_bytes().as_bstr())?;
+
+    let rejected = updates.iter().filter(|u| u.mode == Mode::Rejected).count();
+    output::write_one(
+        format,
+        out,
+        &Outcome {
+            remote: remote.name,
+            url: remote.url,
+            pack: pack.map(|outcome| outcome.index.into()),
+            updates,
+        },
+    )?;
+    if rejected > 0 {
+        bail!(
+            "{} reference(s) could not be updated as they are not fast-forwards",
+            rejected
+        )
+    }
+    Ok(())
+}
+
+/// Return true if `new` is a descendant of `previous`. Objects which aren't commits, like annotated tags, can't be fast-forwarded.
+fn is_fast_forward(repo: &git::Repository, previous: ObjectId, new: ObjectId) -> bool {
+    repo.ahead_behind(previous, new)
+        .map_or(false, |outcome| outcome.ahead == 0)
+}
+
+/// How a local reference was changed by the fetch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub enum Mode {
+    /// The local reference didn't exist and was created.
+    New,
+    /// The local reference already pointed to the remote object.
+    UpToDate,
+    /// The local reference was moved forward to a descendant of its previous value.
+    FastForward,
+    /// The local reference was changed to an unrelated object as allowed by a refspec starting with `+`.
+    Forced,
+    /// The local reference wasn't changed as it is not a fast-forward and the refspec doesn't allow forced updates.
+    Rejected,
+}
+
+impl Mode {
+    /// Return how a local reference pointing to `previous`, or `None` if it doesn't exist, changes when set to `new`, which
+    /// must be available in `repo`. `forced` is true if the refspec allows updates which aren't fast-forwards.
+    pub(crate) fn of_update(repo: &git::Repository, previous: Option<ObjectId>, new: ObjectId, forced: bool) -> Self {
+        match previous {
+            None => Mode::New,
+            Some(previous) if previous == new => Mode::UpToDate,
+            Some(previous) if is_fast_forward(repo, previous, new) => Mode::FastForward,
+            Some(_) if forced => Mode::Forced,
+            Some(_) => Mode::Rejected,
+        }
+    }
+
+    /// The message of the reflog entry to write when performing the change, like `git fetch` does, or `None` if nothing changes.
+    fn log_message(&self) -> Option<&'static str> {
+        match self {
+            Mode::New => Some("storing head"),
+            Mode::FastForward => Some("fast-forward"),
+            Mode::Forced => Some("forced-update"),
+            Mode::UpToDate | Mode::Rejected => None,
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Mode::New => "new",
+            Mode::UpToDate => "up to date",
+            Mode::FastForward => "fast-forward",
+            Mode::Forced => "forced update",
+            Mode::Rejected => "rejected",
+        })
+    }
+}
+
+/// A remote reference and the change it caused to its local counterpart.
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Update {
+    /// The full name of the reference on the remote, like `refs/heads/main`.
+    pub remote: String,
+    /// The full name of the local reference, like `refs/remotes/origin/main`.
+    pub local: String,
+    /// The object the local reference pointed to before the fetch, or `None` if it didn't exist.
+    pub previous: Option<String>,
+    /// The object the remote reference points to.
+    pub new: String,
+    /// How the local reference was changed.
+    pub mode: Mode,
+}
+
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outcome {
+    /// The name of the remote fetched from.
+    pub remote: String,
+    /// The URL of the remote fetched from.
+    pub url: String,
+    /// The received pack, or `None` if all objects were present already.
+    pub pack: Option<JsonBundleWriteOutcome>,
+    /// The remote references matched by the refspecs of the remote.
+    pub updates: Vec<Update>,
+}
+
+impl output::Record for Outcome {
+    fn write_human(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        writeln!(out, "From {}", self.url)?;
+        if let Some(pack) = &self.pack {
+            writeln!(out, "pack: {} ({} objects)", pack.data_hash, pack.num_objects)?;
+        }
+        for update in &self.updates {
+            let range = match (&update.previous, update.mode) {
+                (Some(previous), Mode::FastForward | Mode::Forced | Mode::Rejected) => {
+                    format!("{}..{}", &previous[..7], &update.new[..7])
+                }
+                _ => update.new[..7].to_owned(),
+            };
+            writeln!(out, "{} {} -> {} ({})", range, update.remote, update.local, update.mode)?;
+        }
+        Ok(())
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        use output::field;
+        let mut fields = vec![field("remote", &self.remote), field("url", &self.url)];
+        if let Some(pack) = &self.pack {
+            fields.push(field("pack.data_hash", &pack.data_hash));
+            fields.push(field("pack.num_objects", pack.num_objects));
+        }
+        for update in &self.updates {
+            fields.push(field(format!("{}.mode", update.local), update.mode));
+            fields.push(field(format!("{}.new", update.local), &update.new));
+        }
+        fields
+    }
+}
@@ -0,0 +1,236 @@
+use std::{collections::BTreeMap, io, path::PathBuf, time::Instant};
+
+use anyhow::{anyhow, bail, Context as AnyhowContext};
+use git_repository as git;
+use git_repository::{
+    bstr::{BStr, BString, ByteSlice},
+    easy::object::Kind,
+    hash::ObjectId,
+    objs::tree::EntryMode,
+    odb::{pack, FindExt},
+    prelude::{ObjectAccessExt, TreeIterExt},
+    progress, traverse, Progress,
+};
+
+use crate::diagnostics::Operation;
+
+/// Additional configuration for [`grep()`].
+pub struct Context<W> {
+    /// Match `pattern` case-insensitively.
+    pub ignore_case: bool,
+    /// Interpret `pattern` as literal string instead of as regular expression.
+    pub fixed_strings: bool,
+    /// Only search files whose path starts with one of these slash-separated prefixes, or all files if empty.
+    pub paths: Vec<BString>,
+    /// If set, don't use more than this amount of threads.
+    /// Otherwise, use the amount of threads configured in `gitoxide.threads` of the repository, or the
+    /// default of `git_features::parallel::threads::default()`.
+    /// A value of 0 is interpreted as no-limit
+    pub thread_limit: Option<usize>,
+    /// The output stream receiving all matches.
+    pub out: W,
+}
+
+/// Search for `pattern` in all files of the tree at `revision` in the repository at `repository`, or in the work tree if `revision`
+/// is `None`, writing each matching line as `[<revision>:]<path>:<line-number>:<line>` to `out` in path order.
+/// Return the amount of matching lines.
+///
+/// `revision` is a full object id or a reference name, optionally followed by `~<n>` and `^<n>`, as supported by
+/// [`cat_at()`][crate::repository::cat_at()].
+/// Binary files are skipped, as are symbolic links and submodules. Blobs are decoded and searched in parallel.
+///
+/// In the work tree, paths excluded by ignore files aren't searched, but untracked files are, as the index isn't consulted,
+/// similar to `git grep --untracked`.
+pub fn grep<W, P>(
+    repository: PathBuf,
+    revision: Option<&str>,
+    pattern: &str,
+    mut progress: P,
+    Context {
+        ignore_case,
+        fixed_strings,
+        paths,
+        thread_limit,
+        mut out,
+    }: Context<W>,
+) -> anyhow::Result<usize>
+where
+    W: io::Write,
+    P: Progress,
+{
+    let _op = Operation::start(module_path!(), "grep");
+    let matcher = {
+        let pattern = if fixed_strings {
+            regex::escape(pattern)
+        } else {
+            pattern.to_owned()
+        };
+        regex::bytes::RegexBuilder::new(&pattern)
+            .case_insensitive(ignore_case)
+            .build()?
+    };
+    let repo = git::discover(repository)?;
+    let thread_limit = thread_limit.or(repo.thread_limit()?.map(|limit| limit.num_threads()));
+    let is_selected = |path: &BStr| {
+        paths.is_empty()
+            || paths.iter().any(|prefix| {
+                let prefix = prefix.trim_end_with(|c| c == '/');
+                path.starts_with(prefix) && (path.len() == prefix.len() || path[prefix.len()] == b'/')
+            })
+    };
+
+    let mut files = progress.add_child("collect files");
+    files.init(None, progress::count("files"));
+    let (prefix, input) = match revision {
+        Some(revision) => {
+            let tree = {
+                let easy = repo.to_easy();
+                let id = crate::repository::resolve_revision(&easy, revision)?;
+                let tree = easy
+                    .find_object(id)?
+                    .peel_to_kind(Kind::Tree)
+                    .with_context(|| format!("Revision '{}' doesn't point to a tree", revision))?
+                    .id;
+                tree
+            };
+            let mut buf = Vec::new();
+            let mut recorder = traverse::tree::Recorder::default();
+            repo.odb
+                .find_tree_iter(tree, &mut buf, &mut pack::cache::Never)?
+                .traverse(
+                    traverse::tree::breadthfirst::State::default(),
+                    |oid, buf| repo.odb.find_tree_iter(oid, buf, &mut pack::cache::Never).ok(),
+                    &mut recorder,
+                )?;
+            let mut blobs: Vec<_> = recorder
+                .records
+                .into_iter()
+                .filter(|entry| matches!(entry.mode, EntryMode::Blob | EntryMode::BlobExecutable))
+                .filter(|entry| is_selected(entry.filepath.as_ref()))
+                .map(|entry| (entry.filepath, Source::Blob(entry.oid)))
+                .collect();
+            blobs.sort_by(|a, b| a.0.cmp(&b.0));
+            (Some(revision), blobs)
+        }
+        None => {
+            let work_tree = repo
+                .workdir()
+                .ok_or_else(|| anyhow!("Cannot search the work tree of a bare repository"))?
+                .to_owned();
+            let entries: Vec<_> = repo
+                .dirwalk(git::dirwalk::Options {
+                    thread_limit,
+                    ..Default::default()
+                })?
+                .into_iter()
+                .filter(|entry| entry.kind == git::dirwalk::Kind::File && is_selected(entry.path.as_ref()))
+                .map(|entry| {
+                    let file = work_tree.join(entry.path.to_path()?);
+                    Ok((entry.path, Source::File(file)))
+                })
+                .collect::<anyhow::Result<_>>()?;
+            (None, entries)
+        }
+    };
+    files.inc_by(input.len());
+    files.done(format!("Found {} files to search", input.len()));
+
+    let mut searched = progress.add_child("search");
+    searched.init(Some(input.len()), progress::count("files"));
+    let reduce = InOrder {
+        out: &mut out,
+        prefix,
+        next: 0,
+        pending: BTreeMap::new(),
+        num_matches: 0,
+        progress: searched,
+        start: Instant::now(),
+    };
+    let num_matches = git::parallel::in_parallel(
+        input.into_iter().enumerate(),
+        thread_limit,
+        |_| Vec::new(),
+        |(index, (path, source)), buf| -> anyhow::Result<FileMatches> {
+            let data: &[u8] = match source {
+                Source::Blob(id) => {
+                    repo.odb
+                        .find_blob(id, buf, &mut pack::cache::Never)
+                        .with_context(|| format!("Could not read blob for '{}'", path))?
+                        .data
+                }
+                Source::File(file) => {
+                    *buf = std::fs::read(file)?;
+                    buf.as_slice()
+                }
+            };
+            let mut matches = Vec::new();
+            if !git::filter::is_binary(data) {
+                for (line_number, line) in data.lines().enumerate() {
+                    if matcher.is_match(line) {
+                        matches.push((line_number + 1, line.into()));
+                    }
+                }
+            }
+            Ok((index, path, matches))
+        },
+        reduce,
+    )?;
+    Ok(num_matches)
+}
+
+/// The index of a file in submission order, its path and all matching lines along with their one-based line number.
+type FileMatches = (usize, BString, Vec<(usize, BString)>);
+
+enum Source {
+    Blob(ObjectId),
+    File(PathBuf),
+}
+
+/// Write matches of each file in the order files were submitted, to produce the same output no matter how many threads are used.
+struct InOrder<'a, W, P> {
+    out: W,
+    prefix: Option<&'a str>,
+    next: usize,
+    pending: BTreeMap<usize, (BString, Vec<(usize, BString)>)>,
+    num_matches: usize,
+    progress: P,
+    start: Instant,
+}
+
+impl<'a, W, P> git::parallel::Reduce for InOrder<'a, W, P>
+where
+    W: io::Write,
+    P: Progress,
+{
+    type Input = anyhow::Result<FileMatches>;
+    type FeedProduce = ();
+    type Output = usize;
+    type Error = anyhow::Error;
+
+    fn feed(&mut self, item: Self::Input) -> Result<Self::FeedProduce, Self::Error> {
+        if git::interrupt::is_triggered() {
+            bail!("Cancelled by user")
+        }
+        let (index, path, matches) = item?;
+        self.progress.inc();
+        self.pending.insert(index, (path, matches));
+        while let Some((path, matches)) = self.pending.remove(&self.next) {
+            self.next += 1;
+            self.num_matches += matches.len();
+            for (line_number, line) in matches {
+                if let Some(prefix) = self.prefix {
+                    write!(self.out, "{}:", prefix)?;
+                }
+                write!(self.out, "{}:{}:", path, line_number)?;
+                self.out.write_all(&line)?;
+                writeln!(self.out)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finalize(mut self) -> Result<Self::Output, Self::Error> {
+        self.progress.show_throughput(self.start);
+        Ok(self.num_matches)
+    }
+}
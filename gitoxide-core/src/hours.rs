@@ -1,5 +1,5 @@
 use std::{
-    collections::{hash_map::Entry, HashMap},
+    collections::{hash_map::Entry, HashMap, HashSet, VecDeque},
     ffi::OsStr,
     fmt,
     fmt::{Display, Formatter},
@@ -9,12 +9,12 @@ use std::{
 };
 
 use anyhow::{anyhow, bail};
-use git_repository::{
-    actor, bstr::BString, interrupt, objs, odb, odb::pack, prelude::*, progress, refs::file::ReferenceExt, Progress,
-};
+use git_repository::{actor, bstr::BString, interrupt, objs, odb, odb::pack, prelude::*, progress, ObjectId, Progress};
 use itertools::Itertools;
 use rayon::prelude::*;
 
+use crate::diagnostics::Operation;
+
 /// Additional configuration for the hours estimation functionality.
 pub struct Context<W> {
     /// Show personally identifiable information before the summary. Includes names and email addresses.
@@ -46,36 +46,53 @@ where
     W: io::Write,
     P: Progress,
 {
+    let _op = Operation::start(module_path!(), "estimate-hours");
     let repo = git_repository::discover(working_dir)?;
+    log::debug!("reading path={}", repo.git_dir().display());
     let packed = repo.refs.packed_buffer()?;
+    let mut reference = repo.refs.find(refname.to_string_lossy().as_ref(), packed.as_ref())?;
     let commit_id = repo
         .refs
-        .find(refname.to_string_lossy().as_ref(), packed.as_ref())?
-        .peel_to_id_in_place(&repo.refs, packed.as_ref(), |oid, buf| {
+        .peel_to_id_in_place(&mut reference, packed.as_ref(), |oid, buf| {
             repo.odb
                 .try_find(oid, buf, &mut pack::cache::Never)
                 .map(|obj| obj.map(|obj| (obj.kind, obj.data)))
-        })?
-        .to_owned();
+        })?;
 
     let all_commits = {
         let start = Instant::now();
         let mut progress = progress.add_child("Traverse commit graph");
         progress.init(None, progress::count("commits"));
-        let mut pack_cache = odb::pack::cache::Never;
-        let mut commits: Vec<Vec<u8>> = Vec::new();
-        for c in interrupt::Iter::new(
-            commit_id.ancestors(|oid, buf| {
-                progress.inc();
-                repo.odb.find(oid, buf, &mut pack_cache).ok().map(|o| {
-                    commits.push(o.data.to_owned());
-                    objs::CommitRefIter::from_bytes(o.data)
-                })
-            }),
-            || anyhow!("Cancelled by user"),
-        ) {
-            c??;
-        }
+        let commits = match git_commitgraph::Graph::from_info_dir(repo.objects_dir().join("info")) {
+            Ok(graph) => {
+                // The graph provides all parents without reading any object, leaving only the authors to be read, in parallel.
+                let ids = ancestor_ids(&repo, &graph, commit_id, &mut progress)?;
+                ids.into_par_iter()
+                    .map_init(Vec::new, |buf, id| {
+                        repo.odb
+                            .find(id, buf, &mut pack::cache::Never)
+                            .map(|o| o.data.to_owned())
+                    })
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+            Err(_no_or_invalid_graph) => {
+                let mut pack_cache = odb::pack::cache::Never;
+                let mut commits: Vec<Vec<u8>> = Vec::new();
+                for c in interrupt::Iter::new(
+                    commit_id.ancestors(|oid, buf| {
+                        progress.inc();
+                        repo.odb.find(oid, buf, &mut pack_cache).ok().map(|o| {
+                            commits.push(o.data.to_owned());
+                            objs::CommitRefIter::from_bytes(o.data)
+                        })
+                    }),
+                    || anyhow!("Cancelled by user"),
+                ) {
+                    c??;
+                }
+                commits
+            }
+        };
         progress.show_throughput(start);
         commits
     };
@@ -174,6 +191,49 @@ where
     Ok(())
 }
 
+/// Return the ids of `tip` and all of its ancestors, using `graph` to find the parents of all commits it contains and reading
+/// the remaining ones, which were committed after the graph was written, from the object database.
+fn ancestor_ids(
+    repo: &git_repository::Repository,
+    graph: &git_commitgraph::Graph,
+    tip: ObjectId,
+    progress: &mut impl Progress,
+) -> anyhow::Result<Vec<ObjectId>> {
+    let mut ids = Vec::new();
+    let mut seen = HashSet::new();
+    let mut next = VecDeque::new();
+    let mut parents = Vec::new();
+    let mut buf = Vec::new();
+    seen.insert(tip);
+    next.push_back(tip);
+    while let Some(id) = next.pop_front() {
+        if interrupt::is_triggered() {
+            bail!("Cancelled by user");
+        }
+        match graph.commit_by_id(id) {
+            Some(commit) => {
+                for parent in commit.iter_parents() {
+                    parents.push(graph.id_at(parent?).to_owned());
+                }
+            }
+            None => {
+                let object = repo.odb.find(id, &mut buf, &mut pack::cache::Never)?;
+                for parent in objs::commit::extract::parent_ids(object.data) {
+                    parents.push(parent?);
+                }
+            }
+        }
+        for parent in parents.drain(..) {
+            if seen.insert(parent) {
+                next.push_back(parent);
+            }
+        }
+        ids.push(id);
+        progress.inc();
+    }
+    Ok(ids)
+}
+
 const MINUTES_PER_HOUR: f32 = 60.0;
 const HOURS_PER_WORKDAY: f32 = 8.0;
 
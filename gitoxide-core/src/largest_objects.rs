@@ -0,0 +1,345 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    io,
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, bail};
+use git_repository::{
+    bstr::{BString, ByteVec},
+    hash::{self, ObjectId},
+    objs::{self, tree::EntryMode},
+    odb::{pack, FindExt},
+    prelude::*,
+    progress, traverse, Progress,
+};
+
+use crate::{diagnostics::Operation, output, OutputFormat};
+
+/// The size by which to rank objects in [`largest_objects()`].
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
+pub enum Order {
+    /// Rank objects by the size of their content.
+    Size,
+    /// Rank objects by the amount of bytes they occupy on disk, which is the compressed size of loose objects or the size of
+    /// the pack entry, which may be a delta.
+    DiskSize,
+}
+
+impl Default for Order {
+    fn default() -> Self {
+        Order::Size
+    }
+}
+
+impl Order {
+    pub fn variants() -> &'static [&'static str] {
+        &["size", "disk-size"]
+    }
+}
+
+impl std::str::FromStr for Order {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "size" => Order::Size,
+            "disk-size" => Order::DiskSize,
+            _ => return Err(format!("Unknown value for order: '{}'", s)),
+        })
+    }
+}
+
+/// An object of the object database along with its sizes.
+#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Object {
+    /// The id of the object.
+    pub id: String,
+    /// The kind of the object, one of `commit`, `tree`, `blob` or `tag`, or `None` if it is a delta whose base isn't in the
+    /// same pack.
+    pub kind: Option<String>,
+    /// The size of the object's content in bytes.
+    pub size: u64,
+    /// The amount of bytes the object occupies on disk.
+    pub disk_size: u64,
+    /// The path of the object in the first commit it was encountered in while traversing the history from all references,
+    /// or `None` if it isn't a file or directory reachable from a reference.
+    pub path: Option<String>,
+}
+
+impl output::Record for Object {
+    fn write_human(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        write!(
+            out,
+            "{} {:<6} {:>12} {:>12}",
+            self.id,
+            self.kind.as_deref().unwrap_or("?"),
+            self.size,
+            self.disk_size
+        )?;
+        if let Some(path) = &self.path {
+            write!(out, " {}", path)?;
+        }
+        writeln!(out)
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        use output::field;
+        let mut fields = vec![field("id", &self.id)];
+        fields.extend(self.kind.iter().map(|kind| field("kind", kind)));
+        fields.extend(vec![field("size", self.size), field("disk_size", self.disk_size)]);
+        fields.extend(self.path.iter().map(|path| field("path", path)));
+        fields
+    }
+}
+
+/// Find the `count` largest objects of `repo` according to `order`, in packs as well as loose objects of the repository and its
+/// alternates, largest first, and set their path by traversing the history from all references.
+///
+/// Objects present more than once, like in multiple packs, are only considered once.
+pub fn find(
+    repo: &git_repository::Repository,
+    count: usize,
+    order: Order,
+    mut progress: impl Progress,
+) -> anyhow::Result<Vec<Object>> {
+    let _op = Operation::start(module_path!(), "largest-objects");
+    let mut objects = HashMap::<ObjectId, Sizes>::new();
+    {
+        let mut progress = progress.add_child("collecting");
+        progress.init(None, progress::count("objects"));
+        let mut buf = Vec::new();
+        for db in &repo.odb.dbs {
+            for bundle in &db.bundles {
+                for (id, sizes) in pack_objects(bundle, &mut buf)? {
+                    objects.entry(id).or_insert(sizes);
+                    progress.inc();
+                }
+            }
+            for id in db.loose.iter() {
+                let id = id?;
+                if objects.contains_key(&id) {
+                    continue;
+                }
+                let disk_size = {
+                    let mut buf = [0u8; hash::Kind::longest().len_in_hex()];
+                    let hex = id.hex_to_buf(&mut buf);
+                    std::fs::metadata(db.loose.path.join(&hex[..2]).join(&hex[2..]))?.len()
+                };
+                let object = db
+                    .loose
+                    .try_find(id, &mut buf)?
+                    .ok_or_else(|| anyhow!("Loose object {} disappeared while reading it", id))?;
+                objects.insert(
+                    id,
+                    Sizes {
+                        kind: Some(object.kind),
+                        size: object.data.len() as u64,
+                        disk_size,
+                    },
+                );
+                progress.inc();
+            }
+        }
+    }
+
+    let mut objects: Vec<_> = objects.into_iter().collect();
+    objects.sort_by(|(a_id, a), (b_id, b)| {
+        let key = |sizes: &Sizes| match order {
+            Order::Size => (sizes.size, sizes.disk_size),
+            Order::DiskSize => (sizes.disk_size, sizes.size),
+        };
+        key(b).cmp(&key(a)).then_with(|| a_id.cmp(b_id))
+    });
+    objects.truncate(count);
+
+    let mut paths = find_paths(repo, &objects, progress.add_child("traversing"))?;
+    Ok(objects
+        .into_iter()
+        .map(|(id, sizes)| Object {
+            id: id.to_string(),
+            kind: sizes.kind.map(|kind| kind.to_string()),
+            size: sizes.size,
+            disk_size: sizes.disk_size,
+            path: paths.remove(&id).map(|path| path.to_string()),
+        })
+        .collect())
+}
+
+/// Write the `count` largest objects of the repository at `repository` according to `order` to `out` in `format`, as found by
+/// [`find()`].
+pub fn largest_objects(
+    repository: PathBuf,
+    count: usize,
+    order: Order,
+    progress: impl Progress,
+    format: OutputFormat,
+    mut out: impl io::Write,
+) -> anyhow::Result<()> {
+    let repo = git_repository::discover(repository)?;
+    let objects = find(&repo, count, order, progress)?;
+    output::write_all(format, &mut out, &objects)?;
+    Ok(())
+}
+
+/// The kind and sizes of an object, see [`Object`] for details.
+struct Sizes {
+    kind: Option<objs::Kind>,
+    size: u64,
+    disk_size: u64,
+}
+
+/// Return all objects in the pack of `bundle`, resolving the kind and size of deltas by following their chain and decompressing
+/// only the delta itself.
+fn pack_objects(bundle: &pack::Bundle, buf: &mut Vec<u8>) -> anyhow::Result<Vec<(ObjectId, Sizes)>> {
+    let mut entries: Vec<_> = bundle
+        .index
+        .iter()
+        .map(|entry| (entry.pack_offset, entry.oid))
+        .collect();
+    entries.sort_by_key(|(offset, _)| *offset);
+
+    let mut kinds = HashMap::<u64, objs::Kind>::new();
+    let mut objects = Vec::with_capacity(entries.len());
+    for (idx, (offset, id)) in entries.iter().enumerate() {
+        let next_offset = entries
+            .get(idx + 1)
+            .map_or(bundle.pack.pack_end() as u64, |(offset, _)| *offset);
+        let entry = bundle.pack.entry(*offset);
+        let size = if entry.header.is_delta() {
+            buf.resize(entry.decompressed_size as usize, 0);
+            bundle.pack.decompress_entry(&entry, buf)?;
+            let (_base_size, consumed) = pack::data::delta::decode_header_size(buf);
+            pack::data::delta::decode_header_size(&buf[consumed..]).0
+        } else {
+            entry.decompressed_size
+        };
+        objects.push((
+            *id,
+            Sizes {
+                kind: resolve_kind(bundle, *offset, &mut kinds)?,
+                size,
+                disk_size: next_offset - offset,
+            },
+        ));
+    }
+    Ok(objects)
+}
+
+/// Return the kind of the object at `offset`, following delta chains within the pack and memoizing the kinds of all objects on
+/// the chain in `kinds`.
+fn resolve_kind(
+    bundle: &pack::Bundle,
+    offset: u64,
+    kinds: &mut HashMap<u64, objs::Kind>,
+) -> anyhow::Result<Option<objs::Kind>> {
+    let mut chain = Vec::new();
+    let mut current = offset;
+    let kind = loop {
+        if let Some(kind) = kinds.get(&current) {
+            break Some(*kind);
+        }
+        if chain.len() > bundle.index.num_objects() as usize {
+            bail!("Delta chain at offset {} in pack is cyclic", offset);
+        }
+        chain.push(current);
+        let entry = bundle.pack.entry(current);
+        use pack::data::entry::Header::*;
+        current = match entry.header {
+            OfsDelta { base_distance } => entry.base_pack_offset(base_distance),
+            RefDelta { base_id } => match bundle.index.lookup(base_id) {
+                Some(idx) => bundle.index.pack_offset_at_index(idx),
+                None => break None,
+            },
+            header => break header.as_kind(),
+        };
+    };
+    if let Some(kind) = kind {
+        for offset in chain {
+            kinds.insert(offset, kind);
+        }
+    }
+    Ok(kind)
+}
+
+/// Return the path of all `objects` that are trees or blobs, which is the one they have in the first commit in which they are
+/// encountered while traversing the commit graph from all references, skipping trees that were seen before.
+fn find_paths(
+    repo: &git_repository::Repository,
+    objects: &[(ObjectId, Sizes)],
+    mut progress: impl Progress,
+) -> anyhow::Result<HashMap<ObjectId, BString>> {
+    let mut missing: HashSet<ObjectId> = objects
+        .iter()
+        .filter(|(_, sizes)| matches!(sizes.kind, Some(objs::Kind::Tree) | Some(objs::Kind::Blob) | None))
+        .map(|(id, _)| *id)
+        .collect();
+    let mut paths = HashMap::new();
+    if missing.is_empty() {
+        return Ok(paths);
+    }
+
+    let mut tips = Vec::new();
+    {
+        let easy = repo.to_easy();
+        for reference in easy.references()?.all()?.peeled() {
+            let id = reference.map_err(|err| anyhow!(err))?.id().detach();
+            let mut buf = Vec::new();
+            if repo.odb.find_commit_iter(id, &mut buf, &mut pack::cache::Never).is_ok() {
+                tips.push(id);
+            }
+        }
+    }
+
+    progress.init(None, progress::count("commits"));
+    let mut seen_trees = HashSet::new();
+    let mut buf = Vec::new();
+    let mut tree_buf = Vec::new();
+    let commits = traverse::commit::Ancestors::new(tips, traverse::commit::ancestors::State::default(), |oid, buf| {
+        repo.odb.find_commit_iter(oid, buf, &mut pack::cache::Never).ok()
+    });
+    for commit in commits {
+        let commit = commit?;
+        progress.inc();
+        let tree = repo
+            .odb
+            .find_commit_iter(commit, &mut buf, &mut pack::cache::Never)?
+            .tree_id()
+            .ok_or_else(|| anyhow!("Commit {} has no tree", commit))?;
+        let mut queue = VecDeque::new();
+        if seen_trees.insert(tree) {
+            queue.push_back((tree, BString::default()));
+        }
+        while let Some((tree, path)) = queue.pop_front() {
+            if missing.remove(&tree) {
+                paths.insert(tree, path.clone());
+            }
+            for entry in repo.odb.find_tree_iter(tree, &mut tree_buf, &mut pack::cache::Never)? {
+                let entry = entry?;
+                let mut entry_path = path.clone();
+                if !entry_path.is_empty() {
+                    entry_path.push(b'/');
+                }
+                entry_path.push_str(entry.filename);
+                match entry.mode {
+                    EntryMode::Tree => {
+                        if seen_trees.insert(entry.oid.to_owned()) {
+                            queue.push_back((entry.oid.to_owned(), entry_path));
+                        }
+                    }
+                    EntryMode::Commit => {}
+                    _ => {
+                        if missing.remove(entry.oid) {
+                            paths.insert(entry.oid.to_owned(), entry_path);
+                        }
+                    }
+                }
+            }
+            if missing.is_empty() {
+                return Ok(paths);
+            }
+        }
+    }
+    Ok(paths)
+}
@@ -9,6 +9,11 @@ pub enum OutputFormat {
     Human,
     #[cfg(feature = "serde1")]
     Json,
+    /// One JSON object per line and record.
+    #[cfg(feature = "serde1")]
+    JsonLines,
+    /// The fields of each record as `name\nvalue\0`, with each record terminated by another `\0`.
+    NulDelimited,
 }
 
 impl OutputFormat {
@@ -17,6 +22,9 @@ impl OutputFormat {
             "human",
             #[cfg(feature = "serde1")]
             "json",
+            #[cfg(feature = "serde1")]
+            "json-lines",
+            "nul",
         ]
     }
 }
@@ -30,19 +38,48 @@ impl FromStr for OutputFormat {
             "human" => OutputFormat::Human,
             #[cfg(feature = "serde1")]
             "json" => OutputFormat::Json,
+            #[cfg(feature = "serde1")]
+            "json-lines" => OutputFormat::JsonLines,
+            "nul" => OutputFormat::NulDelimited,
             _ => return Err(format!("Invalid output format: '{}'", s)),
         })
     }
 }
 
+mod diagnostics;
 pub mod net;
+pub mod output;
 
+#[cfg(any(feature = "async-client", feature = "blocking-client"))]
+pub mod clone;
+#[cfg(any(feature = "async-client", feature = "blocking-client"))]
+pub use clone::clone;
 pub mod commitgraph;
+#[cfg(any(feature = "async-client", feature = "blocking-client"))]
+pub mod fetch;
+#[cfg(any(feature = "async-client", feature = "blocking-client"))]
+pub use fetch::fetch;
+pub mod grep;
+pub use grep::grep;
 #[cfg(feature = "estimate-hours")]
 pub mod hours;
+pub mod largest_objects;
+pub use largest_objects::largest_objects;
+pub mod odb_stats;
+pub use odb_stats::odb_stats;
+pub mod ls_tree;
+pub use ls_tree::ls_tree;
+pub mod merge_tree;
+pub use merge_tree::merge_tree;
 #[cfg(feature = "organize")]
 pub mod organize;
 pub mod pack;
+#[cfg(feature = "progress-json")]
+pub mod progress;
+pub mod reachability;
+pub use reachability::reachability_path;
+pub mod update_refs;
+pub use update_refs::update_refs_from_stdin;
 #[cfg(any(feature = "async-client", feature = "blocking-client"))]
 pub mod remote;
 pub mod repository;
@@ -0,0 +1,258 @@
+use std::{collections::VecDeque, io, path::PathBuf};
+
+use anyhow::Context as AnyhowContext;
+use git_repository::{
+    bstr::{BStr, BString, ByteSlice, ByteVec},
+    easy::object::Kind,
+    objs::tree,
+    odb::{pack, FindExt},
+    prelude::*,
+    traverse::{self, tree::visit::Action},
+};
+
+use crate::{diagnostics::Operation, output, OutputFormat};
+
+/// Options for [`ls_tree()`].
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+    /// If true, descend into trees and list the entries within them instead of the trees themselves, like `git ls-tree -r`.
+    pub recursive: bool,
+    /// If true, obtain the size of blobs, like `git ls-tree --long`.
+    pub long: bool,
+    /// If true, only write the path of each entry in human-readable output, like `git ls-tree --name-only`.
+    pub name_only: bool,
+    /// Slash-separated paths to limit the listing to, matching entries at or below them.
+    ///
+    /// Trees leading to a path are descended into even without [`recursive`][Options::recursive], and a path with a trailing
+    /// slash lists the content of the tree at that path.
+    pub paths: Vec<BString>,
+}
+
+/// An entry of a tree.
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Entry {
+    /// The mode of the entry as six octal digits, like `100644`.
+    pub mode: String,
+    /// The kind of object the entry points to, one of `blob`, `tree` or `commit`.
+    pub kind: String,
+    /// The id of the object.
+    pub id: String,
+    /// The size of blobs, only set if [`Options::long`] is true.
+    pub size: Option<u64>,
+    /// The path of the entry relative to the listed tree.
+    pub path: String,
+    #[cfg_attr(feature = "serde1", serde(skip))]
+    style: Style,
+}
+
+/// How to write entries in human-readable form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Default,
+    Long,
+    NameOnly,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style::Default
+    }
+}
+
+impl output::Record for Entry {
+    fn write_human(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        match self.style {
+            Style::Default => writeln!(out, "{} {} {}\t{}", self.mode, self.kind, self.id, self.path),
+            Style::Long => writeln!(
+                out,
+                "{} {} {} {:>7}\t{}",
+                self.mode,
+                self.kind,
+                self.id,
+                self.size.map_or_else(|| "-".into(), |size| size.to_string()),
+                self.path
+            ),
+            Style::NameOnly => writeln!(out, "{}", self.path),
+        }
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        use output::field;
+        let mut fields = vec![
+            field("mode", &self.mode),
+            field("kind", &self.kind),
+            field("id", &self.id),
+        ];
+        fields.extend(self.size.iter().map(|size| field("size", size)));
+        fields.push(field("path", &self.path));
+        fields
+    }
+}
+
+/// Write the entries of the tree `treeish` points to in the repository at `repository` to `out`, similar to `git ls-tree`.
+///
+/// `treeish` is a full object id or a reference name, optionally followed by `~<n>` and `^<n>`, and may point to a commit or tag
+/// which is peeled to its tree. Entries are written in the order `git` stores them in trees.
+pub fn ls_tree(
+    repository: PathBuf,
+    treeish: &str,
+    options: Options,
+    format: OutputFormat,
+    mut out: impl io::Write,
+) -> anyhow::Result<()> {
+    let _op = Operation::start(module_path!(), "ls-tree");
+    let repo = git_repository::discover(repository)?;
+    let tree = {
+        let easy = repo.to_easy();
+        let id = crate::repository::resolve_revision(&easy, treeish)?;
+        let tree = easy
+            .find_object(id)?
+            .peel_to_kind(Kind::Tree)
+            .with_context(|| format!("Revision '{}' doesn't point to a tree", treeish))?
+            .id;
+        tree
+    };
+
+    let mut collector = Collector {
+        path_deque: VecDeque::new(),
+        path: BString::default(),
+        recursive: options.recursive,
+        paths: options.paths,
+        entries: Vec::new(),
+    };
+    let mut buf = Vec::new();
+    repo.odb
+        .find_tree_iter(tree, &mut buf, &mut pack::cache::Never)?
+        .traverse(
+            traverse::tree::breadthfirst::State::default(),
+            |oid, buf| repo.odb.find_tree_iter(oid, buf, &mut pack::cache::Never).ok(),
+            &mut collector,
+        )?;
+    // Trees sort as if their name ended with a slash, which is the order of entries within trees.
+    let sort_key = |(mode, _, path): &(tree::EntryMode, _, BString)| {
+        let mut key = path.clone();
+        if mode.is_tree() {
+            key.push(b'/');
+        }
+        key
+    };
+    collector.entries.sort_by_cached_key(sort_key);
+
+    let style = match (options.name_only, options.long) {
+        (true, _) => Style::NameOnly,
+        (false, true) => Style::Long,
+        (false, false) => Style::Default,
+    };
+    let mut entries = Vec::with_capacity(collector.entries.len());
+    for (mode, id, path) in collector.entries {
+        let kind = match mode {
+            tree::EntryMode::Tree => "tree",
+            tree::EntryMode::Commit => "commit",
+            tree::EntryMode::Blob | tree::EntryMode::BlobExecutable | tree::EntryMode::Link => "blob",
+        };
+        let size = if options.long && kind == "blob" {
+            Some(
+                repo.odb
+                    .find_blob(id, &mut buf, &mut pack::cache::Never)
+                    .with_context(|| format!("Could not read blob for '{}'", path))?
+                    .data
+                    .len() as u64,
+            )
+        } else {
+            None
+        };
+        entries.push(Entry {
+            mode: format!("{:06o}", mode as u16),
+            kind: kind.into(),
+            id: id.to_string(),
+            size,
+            path: path.to_string(),
+            style,
+        });
+    }
+    output::write_all(format, &mut out, &entries)?;
+    Ok(())
+}
+
+/// A [`Visit`][traverse::tree::Visit] implementation collecting the entries to list, and descending only into trees that may
+/// contain some of them.
+struct Collector {
+    path_deque: VecDeque<BString>,
+    path: BString,
+    recursive: bool,
+    paths: Vec<BString>,
+    entries: Vec<(tree::EntryMode, git_repository::hash::ObjectId, BString)>,
+}
+
+impl Collector {
+    /// Return true if the current path is one of the `paths` or below it, or if there are no `paths`.
+    fn is_selected(&self) -> bool {
+        self.paths.is_empty()
+            || self.paths.iter().any(|path| {
+                let path = path.as_bstr();
+                self.path == path
+                    || (self.path.starts_with(path) && (path.ends_with(b"/") || self.path[path.len()] == b'/'))
+            })
+    }
+
+    /// Return true if the current path is a directory containing one of the `paths`.
+    fn leads_to_path(&self) -> bool {
+        self.paths
+            .iter()
+            .any(|path| path.len() > self.path.len() && path.starts_with(&self.path) && path[self.path.len()] == b'/')
+    }
+
+    fn push_element(&mut self, name: &BStr) {
+        if !self.path.is_empty() {
+            self.path.push(b'/');
+        }
+        self.path.push_str(name);
+    }
+}
+
+impl traverse::tree::Visit for Collector {
+    fn pop_front_tracked_path_and_set_current(&mut self) {
+        self.path = self
+            .path_deque
+            .pop_front()
+            .expect("every call is matched with push_tracked_path_component");
+    }
+
+    fn push_back_tracked_path_component(&mut self, component: &BStr) {
+        self.push_element(component);
+        self.path_deque.push_back(self.path.clone());
+    }
+
+    fn push_path_component(&mut self, component: &BStr) {
+        self.push_element(component);
+    }
+
+    fn pop_path_component(&mut self) {
+        match self.path.rfind_byte(b'/') {
+            Some(pos) => self.path.resize(pos, 0),
+            None => self.path.clear(),
+        }
+    }
+
+    fn visit_tree(&mut self, entry: &tree::EntryRef<'_>) -> Action {
+        if self.leads_to_path() {
+            Action::Continue
+        } else if self.is_selected() {
+            if self.recursive {
+                Action::Continue
+            } else {
+                self.entries.push((entry.mode, entry.oid.to_owned(), self.path.clone()));
+                Action::Skip
+            }
+        } else {
+            Action::Skip
+        }
+    }
+
+    fn visit_nontree(&mut self, entry: &tree::EntryRef<'_>) -> Action {
+        if self.is_selected() {
+            self.entries.push((entry.mode, entry.oid.to_owned(), self.path.clone()));
+        }
+        Action::Continue
+    }
+}
@@ -0,0 +1,261 @@
+use std::{collections::BTreeMap, io, path::PathBuf};
+
+use anyhow::{anyhow, Context as AnyhowContext};
+use git_repository::{
+    bstr::{BStr, BString, ByteSlice, ByteVec},
+    easy::object::Kind,
+    hash::ObjectId,
+    objs::{self, tree::EntryMode},
+    prelude::*,
+};
+
+use crate::{diagnostics::Operation, output, OutputFormat};
+
+/// The result of merging three trees.
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outcome {
+    /// The id of the merged tree, which contains the entry of `ours` at conflicting paths, or the one of `theirs` if `ours` deleted it.
+    pub tree: String,
+    /// All paths which couldn't be merged trivially, in tree order.
+    pub conflicts: Vec<Conflict>,
+}
+
+/// A path at which both sides changed an entry differently.
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Conflict {
+    /// The path of the conflicting entry.
+    pub path: String,
+    /// The kind of conflict, one of `content`, `add/add`, `modify/delete` or `file/directory`.
+    pub kind: String,
+    /// The entries at `path` in the base (stage 1), our (stage 2) and their (stage 3) tree, if present.
+    pub stages: Vec<Stage>,
+}
+
+/// The entry of one of the merged trees at a conflicting path.
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stage {
+    /// 1 for the base, 2 for ours and 3 for theirs, as used in the index for unmerged entries.
+    pub stage: u8,
+    /// The mode of the entry as six octal digits, like `100644`.
+    pub mode: String,
+    /// The id of the object.
+    pub id: String,
+}
+
+impl Conflict {
+    fn message(&self) -> String {
+        match self.kind.as_str() {
+            "modify/delete" => {
+                let (deleted, modified) = if self.stages.iter().any(|stage| stage.stage == 2) {
+                    ("theirs", "ours")
+                } else {
+                    ("ours", "theirs")
+                };
+                format!("{} deleted in {} and modified in {}", self.path, deleted, modified)
+            }
+            "file/directory" => format!("{} is a file on one side and a directory on the other", self.path),
+            _ => format!("Merge conflict in {}", self.path),
+        }
+    }
+}
+
+impl output::Record for Outcome {
+    /// Write the tree id, followed by one line per conflicting entry and a message per conflict, like
+    /// `git merge-tree --write-tree` does.
+    fn write_human(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        writeln!(out, "{}", self.tree)?;
+        for conflict in &self.conflicts {
+            for stage in &conflict.stages {
+                writeln!(out, "{} {} {}\t{}", stage.mode, stage.id, stage.stage, conflict.path)?;
+            }
+        }
+        if !self.conflicts.is_empty() {
+            writeln!(out)?;
+            for conflict in &self.conflicts {
+                writeln!(out, "CONFLICT ({}): {}", conflict.kind, conflict.message())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        use output::field;
+        let mut fields = vec![field("tree", &self.tree)];
+        for (idx, conflict) in self.conflicts.iter().enumerate() {
+            fields.push(field(format!("conflicts.{}.path", idx), &conflict.path));
+            fields.push(field(format!("conflicts.{}.kind", idx), &conflict.kind));
+            for stage in &conflict.stages {
+                let name = |field_name: &str| format!("conflicts.{}.stages.{}.{}", idx, stage.stage, field_name);
+                fields.push(field(name("mode"), &stage.mode));
+                fields.push(field(name("id"), &stage.id));
+            }
+        }
+        fields
+    }
+}
+
+/// Merge the trees `base`, `ours` and `theirs` point to in `repo` and write all merged trees into its object database, without
+/// touching the index or the work tree.
+///
+/// Each of them is a full object id or reference name, optionally followed by `~<n>` and `^<n>`, and is peeled to a tree.
+/// The merge is trivial: an entry changed only on one side, or in the same way on both, is taken as is, and trees changed
+/// on both sides are merged recursively. All other changes are [conflicts][Conflict], as the content of files isn't merged.
+pub fn merge(
+    repo: &impl git_repository::easy::Access,
+    base: &str,
+    ours: &str,
+    theirs: &str,
+) -> anyhow::Result<Outcome> {
+    let _op = Operation::start(module_path!(), "merge-tree");
+    let mut conflicts = Vec::new();
+    let tree = merge_trees(
+        repo,
+        Some(resolve_tree(repo, base)?),
+        Some(resolve_tree(repo, ours)?),
+        Some(resolve_tree(repo, theirs)?),
+        BString::default(),
+        &mut conflicts,
+    )?;
+    let tree = match tree {
+        Some(tree) => tree,
+        None => repo.write_object(objs::Tree::empty())?.detach(),
+    };
+    Ok(Outcome {
+        tree: tree.to_string(),
+        conflicts,
+    })
+}
+
+/// Merge `base`, `ours` and `theirs` in the repository at `repository` as described in [`merge()`] and write the merged tree id
+/// along with all conflicts to `out`.
+///
+/// Fails after writing the output if there are conflicts, similar to `git merge-tree --write-tree` which exits with status 1.
+pub fn merge_tree(
+    repository: PathBuf,
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    format: OutputFormat,
+    mut out: impl io::Write,
+) -> anyhow::Result<()> {
+    let repo = git_repository::discover(repository)?.into_easy();
+    let outcome = merge(&repo, base, ours, theirs)?;
+    output::write_one(format, &mut out, &outcome)?;
+    match outcome.conflicts.len() {
+        0 => Ok(()),
+        n => Err(anyhow!("The merge has {} conflict(s)", n)),
+    }
+}
+
+fn resolve_tree(repo: &impl git_repository::easy::Access, revision: &str) -> anyhow::Result<ObjectId> {
+    let id = crate::repository::resolve_revision(repo, revision)?;
+    let tree = repo
+        .find_object(id)?
+        .peel_to_kind(Kind::Tree)
+        .with_context(|| format!("Revision '{}' doesn't point to a tree", revision))?
+        .id;
+    Ok(tree)
+}
+
+type Entry = (EntryMode, ObjectId);
+
+/// Return the entries of `tree` by filename, or no entries if there is no tree.
+fn entries(
+    repo: &impl git_repository::easy::Access,
+    tree: Option<ObjectId>,
+) -> anyhow::Result<BTreeMap<BString, Entry>> {
+    let mut out = BTreeMap::new();
+    if let Some(tree) = tree {
+        let object = repo.find_object(tree)?;
+        for entry in objs::TreeRefIter::from_bytes(&object.data) {
+            let entry = entry?;
+            out.insert(entry.filename.to_owned(), (entry.mode, entry.oid.to_owned()));
+        }
+    }
+    Ok(out)
+}
+
+/// Merge the trees `base`, `ours` and `theirs` at `path`, any of which may be missing, write the result and return its id,
+/// or `None` if it would be empty.
+fn merge_trees(
+    repo: &impl git_repository::easy::Access,
+    base: Option<ObjectId>,
+    ours: Option<ObjectId>,
+    theirs: Option<ObjectId>,
+    path: BString,
+    conflicts: &mut Vec<Conflict>,
+) -> anyhow::Result<Option<ObjectId>> {
+    let (base, ours, theirs) = (entries(repo, base)?, entries(repo, ours)?, entries(repo, theirs)?);
+    let mut names: Vec<_> = base.keys().chain(ours.keys()).chain(theirs.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    let mut merged = Vec::new();
+    for name in names {
+        let (b, o, t) = (base.get(name), ours.get(name), theirs.get(name));
+        let entry_path = join(path.as_bstr(), name.as_bstr());
+        let entry = if o == t || b == t {
+            o.copied()
+        } else if b == o {
+            t.copied()
+        } else if [b, o, t].iter().flatten().all(|(mode, _)| mode.is_tree()) {
+            let tree_id = |entry: Option<&Entry>| entry.map(|(_, id)| *id);
+            merge_trees(repo, tree_id(b), tree_id(o), tree_id(t), entry_path, conflicts)?
+                .map(|id| (EntryMode::Tree, id))
+        } else {
+            let kind = if [o, t].iter().flatten().any(|(mode, _)| mode.is_tree()) {
+                "file/directory"
+            } else if o.is_none() || t.is_none() {
+                "modify/delete"
+            } else if b.is_none() {
+                "add/add"
+            } else {
+                "content"
+            };
+            conflicts.push(Conflict {
+                path: entry_path.to_string(),
+                kind: kind.into(),
+                stages: [(1, b), (2, o), (3, t)]
+                    .iter()
+                    .filter_map(|(stage, entry)| {
+                        entry.map(|(mode, id)| Stage {
+                            stage: *stage,
+                            mode: format!("{:06o}", *mode as u16),
+                            id: id.to_string(),
+                        })
+                    })
+                    .collect(),
+            });
+            o.or(t).copied()
+        };
+        if let Some((mode, oid)) = entry {
+            merged.push(objs::tree::Entry {
+                mode,
+                filename: name.to_owned(),
+                oid,
+            });
+        }
+    }
+
+    if merged.is_empty() {
+        return Ok(None);
+    }
+    // Trees sort as if their name ended with a slash.
+    merged.sort_by_cached_key(|entry| {
+        let mut key = entry.filename.clone();
+        if entry.mode.is_tree() {
+            key.push(b'/');
+        }
+        key
+    });
+    Ok(Some(repo.write_object(objs::Tree { entries: merged })?.detach()))
+}
+
+fn join(path: &BStr, name: &BStr) -> BString {
+    let mut out = path.to_owned();
+    if !out.is_empty() {
+        out.push(b'/');
+    }
+    out.push_str(name);
+    out
+}
@@ -0,0 +1,231 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use git_repository::{hash, odb::pack, progress, Progress};
+
+use crate::{diagnostics::Operation, largest_objects, output, OutputFormat};
+
+/// The statistics of an object database as collected by [`collect()`].
+#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Statistics {
+    /// The amount of object databases that were scanned, which is one for the repository plus one for each of its alternates.
+    pub num_databases: usize,
+    /// The amount of loose objects.
+    pub num_loose_objects: u64,
+    /// The amount of bytes all loose objects occupy on disk.
+    pub loose_objects_size: u64,
+    /// All packs along with their sizes, in the order they were found in.
+    pub packs: Vec<Pack>,
+    /// The amount of objects in all packs, which counts objects present in multiple packs more than once.
+    pub num_packed_objects: u64,
+    /// The amount of bytes of all pack data files.
+    pub packs_size: u64,
+    /// The amount of bytes of all pack index files.
+    pub indices_size: u64,
+    /// Information about the multi-pack-index of the repository, or `None` if there is none.
+    pub multi_pack_index: Option<MultiPackIndex>,
+    /// The largest objects by size on disk, largest first.
+    pub largest_objects: Vec<largest_objects::Object>,
+}
+
+/// A pack along with its sizes.
+#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pack {
+    /// The path to the pack data file.
+    pub path: PathBuf,
+    /// The amount of objects in the pack.
+    pub num_objects: u32,
+    /// The amount of bytes of the pack data file.
+    pub size: u64,
+    /// The amount of bytes of the pack index file.
+    pub index_size: u64,
+}
+
+/// The multi-pack-index of a repository.
+#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct MultiPackIndex {
+    /// The path to the multi-pack-index file.
+    pub path: PathBuf,
+    /// The amount of packs it covers.
+    pub num_packs: u32,
+    /// The amount of objects it indexes.
+    pub num_objects: u32,
+    /// The amount of bytes of the file.
+    pub size: u64,
+}
+
+impl output::Record for Statistics {
+    fn write_human(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        writeln!(out, "databases: {}", self.num_databases)?;
+        writeln!(
+            out,
+            "loose objects: {} ({} bytes)",
+            self.num_loose_objects, self.loose_objects_size
+        )?;
+        writeln!(
+            out,
+            "packs: {} ({} objects, {} bytes, {} bytes of indices)",
+            self.packs.len(),
+            self.num_packed_objects,
+            self.packs_size,
+            self.indices_size
+        )?;
+        for pack in &self.packs {
+            writeln!(
+                out,
+                "\t{}: {} objects, {} bytes, {} bytes of index",
+                pack.path
+                    .file_name()
+                    .map_or(pack.path.display(), |name| Path::new(name).display()),
+                pack.num_objects,
+                pack.size,
+                pack.index_size
+            )?;
+        }
+        match &self.multi_pack_index {
+            Some(midx) => writeln!(
+                out,
+                "multi-pack-index: {} packs, {} objects, {} bytes",
+                midx.num_packs, midx.num_objects, midx.size
+            )?,
+            None => writeln!(out, "multi-pack-index: none")?,
+        }
+        if !self.largest_objects.is_empty() {
+            writeln!(out, "largest objects:")?;
+            for object in &self.largest_objects {
+                write!(out, "\t")?;
+                output::Record::write_human(object, out)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        use output::field;
+        let mut fields = vec![
+            field("num_databases", self.num_databases),
+            field("num_loose_objects", self.num_loose_objects),
+            field("loose_objects_size", self.loose_objects_size),
+            field("num_packs", self.packs.len()),
+            field("num_packed_objects", self.num_packed_objects),
+            field("packs_size", self.packs_size),
+            field("indices_size", self.indices_size),
+        ];
+        for (idx, pack) in self.packs.iter().enumerate() {
+            fields.extend(vec![
+                field(format!("packs.{}.path", idx), pack.path.display()),
+                field(format!("packs.{}.num_objects", idx), pack.num_objects),
+                field(format!("packs.{}.size", idx), pack.size),
+                field(format!("packs.{}.index_size", idx), pack.index_size),
+            ]);
+        }
+        if let Some(midx) = &self.multi_pack_index {
+            fields.extend(vec![
+                field("multi_pack_index.path", midx.path.display()),
+                field("multi_pack_index.num_packs", midx.num_packs),
+                field("multi_pack_index.num_objects", midx.num_objects),
+                field("multi_pack_index.size", midx.size),
+            ]);
+        }
+        for (idx, object) in self.largest_objects.iter().enumerate() {
+            fields.extend(
+                output::Record::fields(object)
+                    .into_iter()
+                    .map(|(name, value)| (format!("largest_objects.{}.{}", idx, name), value)),
+            );
+        }
+        fields
+    }
+}
+
+/// Collect the [`Statistics`] of the object database of `repo` and its alternates, including the `largest` objects by size on disk.
+pub fn collect(
+    repo: &git_repository::Repository,
+    largest: usize,
+    mut progress: impl Progress,
+) -> anyhow::Result<Statistics> {
+    let _op = Operation::start(module_path!(), "odb-stats");
+    let mut num_loose_objects = 0;
+    let mut loose_objects_size = 0;
+    let mut packs = Vec::new();
+    {
+        let mut progress = progress.add_child("scanning");
+        progress.init(None, progress::count("loose objects"));
+        for db in &repo.odb.dbs {
+            log::debug!("reading path={}", db.loose.path.display());
+            for bundle in &db.bundles {
+                packs.push(Pack {
+                    path: bundle.pack.path().to_owned(),
+                    num_objects: bundle.pack.num_objects(),
+                    size: bundle.pack.data_len() as u64,
+                    index_size: std::fs::metadata(bundle.index.path())?.len(),
+                });
+            }
+            for id in db.loose.iter() {
+                let mut buf = [0u8; hash::Kind::longest().len_in_hex()];
+                let hex = id?.hex_to_buf(&mut buf);
+                loose_objects_size += std::fs::metadata(db.loose.path.join(&hex[..2]).join(&hex[2..]))?.len();
+                num_loose_objects += 1;
+                progress.inc();
+            }
+        }
+    }
+
+    let multi_pack_index = {
+        let path = repo.odb.dbs[0].loose.path.join("pack").join("multi-pack-index");
+        if path.is_file() {
+            let file = pack::multi_index::File::at(&path)?;
+            Some(MultiPackIndex {
+                size: std::fs::metadata(&path)?.len(),
+                num_packs: file.num_packs(),
+                num_objects: file.num_objects(),
+                path,
+            })
+        } else {
+            None
+        }
+    };
+
+    let largest_objects = if largest == 0 {
+        Vec::new()
+    } else {
+        largest_objects::find(
+            repo,
+            largest,
+            largest_objects::Order::DiskSize,
+            progress.add_child("largest objects"),
+        )?
+    };
+
+    Ok(Statistics {
+        num_databases: repo.odb.dbs.len(),
+        num_loose_objects,
+        loose_objects_size,
+        num_packed_objects: packs.iter().map(|pack| pack.num_objects as u64).sum(),
+        packs_size: packs.iter().map(|pack| pack.size).sum(),
+        indices_size: packs.iter().map(|pack| pack.index_size).sum(),
+        packs,
+        multi_pack_index,
+        largest_objects,
+    })
+}
+
+/// Write the [`Statistics`] of the object database of the repository at `repository` to `out` in `format`, as collected by
+/// [`collect()`].
+pub fn odb_stats(
+    repository: PathBuf,
+    largest: usize,
+    progress: impl Progress,
+    format: OutputFormat,
+    out: impl io::Write,
+) -> anyhow::Result<()> {
+    let repo = git_repository::discover(repository)?;
+    let stats = collect(&repo, largest, progress)?;
+    output::write_one(format, out, &stats)?;
+    Ok(())
+}
@@ -3,6 +3,8 @@ use std::path::{Path, PathBuf};
 use git_config::file::GitConfig;
 use git_repository::{objs::bstr::ByteSlice, progress, Progress};
 
+use crate::diagnostics::Operation;
+
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub enum Mode {
     Execute,
@@ -100,10 +102,22 @@ where
     })
 }
 
-fn find_origin_remote(repo: &Path) -> anyhow::Result<Option<git_url::Url>> {
+/// Return the URL of the primary remote of `repo`, which is `origin`, or the only remote if there is exactly one.
+fn find_primary_remote(repo: &Path) -> anyhow::Result<Option<git_url::Url>> {
     let non_bare = repo.join(".git").join("config");
     let config = GitConfig::open(non_bare.as_path()).or_else(|_| GitConfig::open(repo.join("config").as_path()))?;
-    Ok(config.value("remote", Some("origin"), "url").ok())
+    if let Ok(url) = config.value("remote", Some("origin"), "url") {
+        return Ok(Some(url));
+    }
+    let url_key = "url".into();
+    let mut urls = config
+        .sections_by_name("remote")
+        .into_iter()
+        .filter_map(|remote| remote.value(&url_key));
+    Ok(match (urls.next(), urls.next()) {
+        (Some(url), None) => Some(git_url::parse(&url)?),
+        _ => None,
+    })
 }
 
 fn handle(
@@ -147,10 +161,10 @@ fn handle(
         return Ok(());
     }
 
-    let url = match find_origin_remote(git_workdir)? {
+    let url = match find_primary_remote(git_workdir)? {
         None => {
             progress.info(format!(
-                "Skipping repository {:?} without 'origin' remote",
+                "Skipping repository {:?} without 'origin' remote or a single other remote",
                 git_workdir.display()
             ));
             return Ok(());
@@ -196,6 +210,11 @@ fn handle(
         )),
         Mode::Execute => {
             std::fs::create_dir_all(destination.parent().expect("repo destination is not the root"))?;
+            log::debug!(
+                "moving path={} destination={}",
+                git_workdir.display(),
+                destination.display()
+            );
             progress.done(format!("Moving {} to {}", git_workdir.display(), destination.display()));
             std::fs::rename(git_workdir, &destination)?;
         }
@@ -212,6 +231,7 @@ pub fn discover<P: Progress>(
 where
     <<P as Progress>::SubProgress as Progress>::SubProgress: Sync,
 {
+    let _op = Operation::start(module_path!(), "organize-discover");
     for (git_workdir, _kind) in find_git_repository_workdirs(source_dir, progress.add_child("Searching repositories")) {
         writeln!(&mut out, "{}", git_workdir.display())?;
     }
@@ -227,6 +247,7 @@ pub fn run<P: Progress>(
 where
     <<P as Progress>::SubProgress as Progress>::SubProgress: Sync,
 {
+    let _op = Operation::start(module_path!(), "organize");
     let mut num_errors = 0usize;
     let destination = destination.as_ref().canonicalize()?;
     for (path_to_move, kind) in find_git_repository_workdirs(source_dir, progress.add_child("Searching repositories")) {
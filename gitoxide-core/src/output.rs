@@ -0,0 +1,78 @@
+//! Write the results of commands as records in the [`OutputFormat`] selected by the user.
+//!
+//! Commands produce [`Record`]s, like a reference or the statistics of a verification, and leave it to [`write_one()`] and
+//! [`write_all()`] to present them as human-readable text, JSON or NUL-delimited fields, so that scripts don't have to
+//! parse text meant for humans.
+use std::io;
+
+use crate::OutputFormat;
+
+/// A type which can be serialized if the `serde1` feature is enabled, to be written as JSON.
+#[cfg(feature = "serde1")]
+pub trait Serialize: serde::Serialize {}
+#[cfg(feature = "serde1")]
+impl<T: serde::Serialize> Serialize for T {}
+
+/// A type which can be serialized if the `serde1` feature is enabled, to be written as JSON.
+#[cfg(not(feature = "serde1"))]
+pub trait Serialize {}
+#[cfg(not(feature = "serde1"))]
+impl<T> Serialize for T {}
+
+/// A single result of a command which can be written in any [`OutputFormat`].
+pub trait Record: Serialize {
+    /// Write this record as human-readable text to `out`.
+    fn write_human(&self, out: &mut dyn io::Write) -> io::Result<()>;
+    /// Return the name and value of each field of this record for use in the NUL-delimited format.
+    ///
+    /// Nested fields are named after their parents and separated by a dot, like `counts.input_objects`.
+    fn fields(&self) -> Vec<(String, String)>;
+}
+
+/// Return a field named `name` with `value` for use in [`Record::fields()`].
+pub fn field(name: impl Into<String>, value: impl ToString) -> (String, String) {
+    (name.into(), value.to_string())
+}
+
+/// Write a single `record` in `format` to `out`.
+pub fn write_one(format: OutputFormat, mut out: impl io::Write, record: &impl Record) -> io::Result<()> {
+    match format {
+        OutputFormat::Human => record.write_human(&mut out)?,
+        #[cfg(feature = "serde1")]
+        OutputFormat::Json => serde_json::to_writer_pretty(out, record)?,
+        #[cfg(feature = "serde1")]
+        OutputFormat::JsonLines => write_json_line(&mut out, record)?,
+        OutputFormat::NulDelimited => write_nul_delimited(&mut out, record)?,
+    }
+    Ok(())
+}
+
+/// Write all `records` in `format` to `out`.
+///
+/// With [`OutputFormat::Json`], all records are written as a single array.
+pub fn write_all<T: Record>(format: OutputFormat, mut out: impl io::Write, records: &[T]) -> io::Result<()> {
+    #[cfg(feature = "serde1")]
+    if format == OutputFormat::Json {
+        serde_json::to_writer_pretty(out, records)?;
+        return Ok(());
+    }
+    for record in records {
+        write_one(format, &mut out, record)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "serde1")]
+fn write_json_line(out: &mut impl io::Write, record: &impl Record) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, record)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Write each field as `name\nvalue\0` like `git config -z` does, and terminate the record with another `\0`.
+fn write_nul_delimited(out: &mut impl io::Write, record: &impl Record) -> io::Result<()> {
+    for (name, value) in record.fields() {
+        write!(out, "{}\n{}\0", name, value)?;
+    }
+    out.write_all(b"\0")
+}
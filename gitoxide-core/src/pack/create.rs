@@ -1,6 +1,7 @@
 use std::{convert::TryFrom, ffi::OsStr, io, path::Path, str::FromStr, sync::Arc, time::Instant};
 
-use anyhow::anyhow;
+use anyhow::{anyhow, bail};
+use bytesize::ByteSize;
 use git_repository as git;
 use git_repository::{
     hash,
@@ -12,7 +13,7 @@ use git_repository::{
     progress, traverse, Progress,
 };
 
-use crate::OutputFormat;
+use crate::{diagnostics::Operation, output, OutputFormat};
 
 pub const PROGRESS_RANGE: std::ops::RangeInclusive<u8> = 1..=2;
 
@@ -71,11 +72,22 @@ pub struct Context<W> {
     /// If unset, counting will only use one thread and thus yield the same sequence of objects in any case.
     /// If the `thread_limit` is 1, the count is always deterministic.
     pub nondeterministic_count: bool,
+    /// If set, the pack will be byte-identical for the same objects and object database, even if `nondeterministic_count`
+    /// is set, as objects are written in an order that only depends on the objects themselves.
+    pub deterministic: bool,
     /// If true, delta objects may refer to their base as reference, allowing it not to be included in the created back.
     /// Otherwise these have to be recompressed in order to make the pack self-contained.
     pub thin: bool,
+    /// If true, deltas are copied from existing packs. Otherwise all objects are written as base objects, which makes the
+    /// pack larger but allows reading each object without resolving deltas.
+    pub deltas: bool,
+    /// If true, the pack is written to `out` instead of into the output directory, and its name isn't printed.
+    ///
+    /// This can't be combined with an output directory or `statistics`, which would be written to `out` as well.
+    pub pack_to_out: bool,
     /// If set, don't use more than this amount of threads.
-    /// Otherwise, usually use as many threads as there are logical cores.
+    /// Otherwise, use the amount of threads configured in `pack.threads` or `gitoxide.threads` of the repository, or the
+    /// default of `git_features::parallel::threads::default()`.
     /// A value of 0 is interpreted as no-limit
     pub thread_limit: Option<usize>,
     /// If set, statistics about the operation will be written to the output stream.
@@ -104,7 +116,10 @@ pub fn create<W>(
     Context {
         expansion,
         nondeterministic_count,
+        deterministic,
         thin,
+        deltas,
+        pack_to_out,
         thread_limit,
         statistics,
         pack_cache_size_in_bytes,
@@ -115,7 +130,19 @@ pub fn create<W>(
 where
     W: std::io::Write,
 {
+    if pack_to_out && output_directory.is_some() {
+        bail!("The pack can't be written to the output stream and into an output directory at the same time")
+    }
+    if pack_to_out && statistics.is_some() {
+        bail!("Statistics can't be written to the output stream as it receives the pack")
+    }
+    let _op = Operation::start(module_path!(), "pack-create");
     let repo = git::discover(repository_path)?;
+    log::debug!("reading path={}", repo.git_dir().display());
+    let thread_limit = match thread_limit.or(repo.pack_generation_options()?.thread_limit) {
+        Some(limit) => Some(limit),
+        None => repo.thread_limit()?.map(|limit| limit.num_threads()),
+    };
     progress.init(Some(2), progress::steps());
     let tips = tips.into_iter();
     let make_cancellation_err = || anyhow!("Cancelled by user");
@@ -175,6 +202,7 @@ where
         let thread_limit = if may_use_multiple_threads {
             thread_limit
         } else {
+            log::debug!("counting objects with a single thread to produce a deterministic count");
             Some(1)
         };
         let (_, _, thread_count) = git::parallel::optimize_chunk_size_and_thread_limit(50, None, thread_limit, None);
@@ -237,8 +265,15 @@ where
             progress,
             pack::data::output::entry::iter_from_counts::Options {
                 thread_limit,
-                mode: pack::data::output::entry::iter_from_counts::Mode::PackCopyAndBaseObjects,
+                mode: if deltas {
+                    pack::data::output::entry::iter_from_counts::Mode::PackCopyAndBaseObjects
+                } else {
+                    pack::data::output::entry::iter_from_counts::Mode::BaseObjects
+                },
                 allow_thin_pack: thin,
+                allow_ofs_delta: true,
+                deterministic,
+                compression_level: 1,
                 chunk_size,
                 version: Default::default(),
             },
@@ -258,6 +293,7 @@ where
             named_tempfile_store = Some(tempfile::NamedTempFile::new_in(dir.as_ref())?);
             (named_tempfile_store.as_mut().expect("packfile just set"), Some(dir))
         }
+        None if pack_to_out => (&mut out, None),
         None => {
             sink_store = std::io::sink();
             (&mut sink_store, None)
@@ -270,11 +306,12 @@ where
                     entries_progress.inc_by(entries.len())
                 }
             }),
-            &mut pack_file,
+            interrupt::Write::new(&mut pack_file),
             num_objects as u32,
             pack::data::Version::default(),
             hash::Kind::default(),
-        ),
+        )
+        .validate_deltas(true, thin),
         make_cancellation_err,
     );
     for io_res in interruptible_output_iter.by_ref() {
@@ -288,8 +325,10 @@ where
         .expect("iteration is done");
     let pack_name = format!("{}.pack", hash);
     if let (Some(pack_file), Some(dir)) = (named_tempfile_store.take(), output_directory) {
-        pack_file.persist(dir.as_ref().join(pack_name))?;
-    } else {
+        let pack_path = dir.as_ref().join(pack_name);
+        log::debug!("writing path={}", pack_path.display());
+        pack_file.persist(pack_path)?;
+    } else if !pack_to_out {
         writeln!(out, "{}", pack_name)?;
     }
     stats.entries = in_order_entries.inner.finalize()?;
@@ -305,10 +344,38 @@ where
 }
 
 fn print(stats: Statistics, format: OutputFormat, out: impl std::io::Write) -> anyhow::Result<()> {
-    match format {
-        OutputFormat::Human => human_output(stats, out).map_err(Into::into),
-        #[cfg(feature = "serde1")]
-        OutputFormat::Json => serde_json::to_writer_pretty(out, &stats).map_err(Into::into),
+    output::write_one(format, out, &stats).map_err(Into::into)
+}
+
+impl output::Record for Statistics {
+    fn write_human(&self, out: &mut dyn std::io::Write) -> std::io::Result<()> {
+        human_output(*self, out)
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        use output::field;
+        vec![
+            field("counts.input_objects", self.counts.input_objects),
+            field("counts.expanded_objects", self.counts.expanded_objects),
+            field("counts.decoded_objects", self.counts.decoded_objects),
+            field("counts.total_objects", self.counts.total_objects),
+            field("counts.seen_objects_bytes", self.counts.seen_objects_bytes),
+            field("counts.peak_buffer_bytes", self.counts.peak_buffer_bytes),
+            field("counts.peak_cache_bytes", self.counts.peak_cache_bytes),
+            field(
+                "entries.decoded_and_recompressed_objects",
+                self.entries.decoded_and_recompressed_objects,
+            ),
+            field("entries.missing_objects", self.entries.missing_objects),
+            field(
+                "entries.objects_copied_from_pack",
+                self.entries.objects_copied_from_pack,
+            ),
+            field("entries.ref_delta_objects", self.entries.ref_delta_objects),
+            field("entries.peak_chunk_bytes", self.entries.peak_chunk_bytes),
+            field("entries.peak_buffer_bytes", self.entries.peak_buffer_bytes),
+            field("entries.peak_cache_bytes", self.entries.peak_cache_bytes),
+        ]
     }
 }
 
@@ -320,6 +387,9 @@ fn human_output(
                 expanded_objects,
                 decoded_objects,
                 total_objects,
+                seen_objects_bytes,
+                peak_buffer_bytes: count_buffer_bytes,
+                peak_cache_bytes: count_cache_bytes,
             },
         entries:
             pack::data::output::entry::iter_from_counts::Outcome {
@@ -327,6 +397,9 @@ fn human_output(
                 missing_objects,
                 objects_copied_from_pack,
                 ref_delta_objects,
+                peak_chunk_bytes,
+                peak_buffer_bytes: entry_buffer_bytes,
+                peak_cache_bytes: entry_cache_bytes,
             },
     }: Statistics,
     mut out: impl std::io::Write,
@@ -354,10 +427,23 @@ fn human_output(
         "missing objects", missing_objects,
         width = width
     )?;
+    writeln!(out, "memory usage")?;
+    #[rustfmt::skip]
+    writeln!(
+        out,
+        "\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}",
+        "seen object ids", ByteSize(seen_objects_bytes as u64),
+        "counting buffers per thread", ByteSize(count_buffer_bytes as u64),
+        "counting caches per thread", ByteSize(count_cache_bytes as u64),
+        "generation buffer per thread", ByteSize(entry_buffer_bytes as u64),
+        "generation cache per thread", ByteSize(entry_cache_bytes as u64),
+        "largest chunk of entries", ByteSize(peak_chunk_bytes as u64),
+        width = width
+    )?;
     Ok(())
 }
 
-#[derive(Default)]
+#[derive(Default, Clone, Copy)]
 #[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
 struct Statistics {
     counts: pack::data::output::count::objects::Outcome,
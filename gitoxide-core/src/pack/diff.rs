@@ -0,0 +1,176 @@
+use std::{collections::BTreeMap, io, path::Path};
+
+use anyhow::{bail, Context as AnyhowContext, Result};
+
+use crate::{output, pack::inspect::Entry, OutputFormat};
+
+/// An object which is only contained in one of two packs, or which is represented differently in them.
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Difference {
+    /// The id of the object.
+    pub id: String,
+    /// The entry of the object in the first pack, if it contains it.
+    pub old: Option<Entry>,
+    /// The entry of the object in the second pack, if it contains it.
+    pub new: Option<Entry>,
+}
+
+impl Difference {
+    fn write_entry(out: &mut dyn io::Write, entry: &Entry) -> io::Result<()> {
+        write!(out, "{}", entry.kind)?;
+        if let Some(base_id) = &entry.base_id {
+            write!(out, " base {}", base_id)?;
+        }
+        write!(out, " {}", entry.size_in_pack)
+    }
+}
+
+impl output::Record for Difference {
+    fn write_human(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => {
+                write!(out, "~ {} ", self.id)?;
+                Self::write_entry(out, old)?;
+                write!(out, " -> ")?;
+                Self::write_entry(out, new)?;
+            }
+            (Some(entry), None) | (None, Some(entry)) => {
+                write!(out, "{} {} ", if self.old.is_some() { '-' } else { '+' }, self.id)?;
+                Self::write_entry(out, entry)?;
+            }
+            (None, None) => unreachable!("differences are between existing entries"),
+        }
+        writeln!(out)
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        let mut fields = vec![output::field("id", &self.id)];
+        for (prefix, entry) in [("old", &self.old), ("new", &self.new)] {
+            fields.extend(
+                entry
+                    .iter()
+                    .flat_map(output::Record::fields)
+                    .filter(|(name, _)| name != "id")
+                    .map(|(name, value)| (format!("{}.{}", prefix, name), value)),
+            );
+        }
+        fields
+    }
+}
+
+/// Statistics about two packs compared with [`diff()`].
+#[derive(Default, Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Statistics {
+    /// The amount of objects contained in both packs.
+    pub objects_in_both: usize,
+    /// The amount of objects contained in both packs, but with a different kind or delta base.
+    pub objects_with_different_representation: usize,
+    /// The amount of objects only contained in the first pack.
+    pub objects_only_in_old: usize,
+    /// The amount of objects only contained in the second pack.
+    pub objects_only_in_new: usize,
+    /// The amount of bytes all entries occupy in the first pack.
+    pub old_size_in_pack: u64,
+    /// The amount of bytes all entries occupy in the second pack.
+    pub new_size_in_pack: u64,
+}
+
+impl output::Record for Statistics {
+    fn write_human(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        writeln!(
+            out,
+            "objects in both packs: {}, with different representation: {}",
+            self.objects_in_both, self.objects_with_different_representation
+        )?;
+        writeln!(out, "objects only in old pack: {}", self.objects_only_in_old)?;
+        writeln!(out, "objects only in new pack: {}", self.objects_only_in_new)?;
+        writeln!(
+            out,
+            "size of entries: {} -> {} bytes ({:+})",
+            self.old_size_in_pack,
+            self.new_size_in_pack,
+            self.new_size_in_pack as i64 - self.old_size_in_pack as i64
+        )
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        use output::field;
+        vec![
+            field("objects_in_both", self.objects_in_both),
+            field(
+                "objects_with_different_representation",
+                self.objects_with_different_representation,
+            ),
+            field("objects_only_in_old", self.objects_only_in_old),
+            field("objects_only_in_new", self.objects_only_in_new),
+            field("old_size_in_pack", self.old_size_in_pack),
+            field("new_size_in_pack", self.new_size_in_pack),
+        ]
+    }
+}
+
+/// Write all objects which are only contained in one of the packs at `old` and `new`, or which have a different kind or delta
+/// base in them, to `out` in `format`, ordered by id. `old` and `new` may point to the `.pack` or `.idx` file, but both packs
+/// must have an index to identify their objects.
+///
+/// This is useful to compare packs created by different implementations, like `git pack-objects` and `gixp pack-create`.
+/// Human readable output marks objects only in `old` with `-`, objects only in `new` with `+` and objects represented
+/// differently with `~`, followed by the kind, delta base and size in pack of their entries, and ends with the returned
+/// statistics.
+pub fn diff(
+    old: impl AsRef<Path>,
+    new: impl AsRef<Path>,
+    format: OutputFormat,
+    mut out: impl io::Write,
+) -> Result<Statistics> {
+    let mut entries_by_id = BTreeMap::<String, (Option<Entry>, Option<Entry>)>::new();
+    let mut stats = Statistics::default();
+    for (path, is_old) in [(old.as_ref(), true), (new.as_ref(), false)] {
+        if !path.with_extension("idx").is_file() {
+            bail!(
+                "The pack at '{}' needs an index to identify its objects",
+                path.display()
+            );
+        }
+        let entries =
+            super::inspect::entries(path).with_context(|| format!("Could not read pack at '{}'", path.display()))?;
+        for entry in entries {
+            let id = entry.id.clone().expect("the index is present");
+            let slot = entries_by_id.entry(id).or_default();
+            if is_old {
+                stats.old_size_in_pack += entry.size_in_pack;
+                slot.0 = Some(entry);
+            } else {
+                stats.new_size_in_pack += entry.size_in_pack;
+                slot.1 = Some(entry);
+            }
+        }
+    }
+
+    let mut differences = Vec::new();
+    for (id, (old, new)) in entries_by_id {
+        match (&old, &new) {
+            (Some(old), Some(new)) => {
+                stats.objects_in_both += 1;
+                if old.kind == new.kind && old.base_id == new.base_id {
+                    continue;
+                }
+                stats.objects_with_different_representation += 1;
+            }
+            (Some(_), None) => stats.objects_only_in_old += 1,
+            (None, Some(_)) => stats.objects_only_in_new += 1,
+            (None, None) => unreachable!("each id has at least one entry"),
+        }
+        differences.push(Difference { id, old, new });
+    }
+
+    output::write_all(format, &mut out, &differences)?;
+    if format == OutputFormat::Human {
+        if !differences.is_empty() {
+            writeln!(out)?;
+        }
+        output::write_one(format, &mut out, &stats)?;
+    }
+    Ok(stats)
+}
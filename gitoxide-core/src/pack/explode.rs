@@ -16,6 +16,8 @@ use git_repository::{
 };
 use quick_error::quick_error;
 
+use crate::diagnostics::Operation;
+
 #[derive(PartialEq, Debug)]
 pub enum SafetyCheck {
     SkipFileChecksumVerification,
@@ -169,7 +171,9 @@ pub fn pack_or_pack_index(
 ) -> Result<()> {
     use anyhow::Context;
 
+    let _op = Operation::start(module_path!(), "pack-explode");
     let path = pack_path.as_ref();
+    log::debug!("reading path={}", path.display());
     let bundle = pack::Bundle::at(path).with_context(|| {
         format!(
             "Could not find .idx or .pack file from given file at '{}'",
@@ -187,6 +191,11 @@ pub fn pack_or_pack_index(
         ));
     }
 
+    match object_path.as_ref() {
+        Some(object_path) => log::debug!("writing objects path={}", object_path.as_ref().display()),
+        None => log::debug!("discarding objects as no object directory was given"),
+    }
+
     let algorithm = object_path
         .as_ref()
         .map(|_| pack::index::traverse::Algorithm::Lookup)
@@ -243,6 +252,8 @@ pub fn pack_or_pack_index(
                 thread_limit,
                 check: check.into(),
                 should_interrupt,
+                num_largest_objects: 0,
+                on_object: None,
             },
         )
         .map(|(_, _, c)| progress::DoOrDiscard::from(c))
@@ -261,6 +272,8 @@ pub fn pack_or_pack_index(
                     data_path.display()
                 )
             })?;
+        log::debug!("removed path={}", index_path.display());
+        log::debug!("removed path={}", data_path.display());
         progress.info(format!(
             "Removed '{}' and '{}'",
             index_path.display(),
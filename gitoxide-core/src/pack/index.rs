@@ -2,7 +2,7 @@ use std::{fs, io, path::PathBuf, str::FromStr, sync::atomic::AtomicBool};
 
 use git_repository::{odb::pack, Progress};
 
-use crate::OutputFormat;
+use crate::{diagnostics::Operation, output, OutputFormat};
 
 #[derive(PartialEq, Debug)]
 pub enum IterationMode {
@@ -81,15 +81,19 @@ pub fn from_pack(
     ctx: Context<'static, impl io::Write>,
 ) -> anyhow::Result<()> {
     use anyhow::Context;
+    let _op = Operation::start(module_path!(), "pack-index-create");
     let options = pack::bundle::write::Options {
         thread_limit: ctx.thread_limit,
         iteration_mode: ctx.iteration_mode.into(),
         index_kind: pack::index::Version::default(),
+        write_reverse_index: false,
+        fsync: Default::default(),
     };
     let out = ctx.out;
     let format = ctx.format;
     let res = match pack {
         PathOrRead::Path(pack) => {
+            log::debug!("reading path={}", pack.display());
             let pack_len = pack.metadata()?.len();
             let pack_file = fs::File::open(pack)?;
             pack::Bundle::write_to_directory_eagerly(
@@ -113,15 +117,32 @@ pub fn from_pack(
         ),
     }
     .with_context(|| "Failed to write pack and index")?;
-    match format {
-        OutputFormat::Human => drop(human_output(out, res)),
-        #[cfg(feature = "serde1")]
-        OutputFormat::Json => serde_json::to_writer_pretty(out, &res)?,
-    };
+    for path in res.index_path.iter().chain(res.data_path.iter()) {
+        log::debug!("wrote path={}", path.display());
+    }
+    output::write_one(format, out, &res)?;
     Ok(())
 }
 
-fn human_output(mut out: impl io::Write, res: pack::bundle::write::Outcome) -> io::Result<()> {
-    writeln!(&mut out, "index: {}", res.index.index_hash)?;
-    writeln!(&mut out, "pack: {}", res.index.data_hash)
+impl output::Record for pack::bundle::write::Outcome {
+    fn write_human(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        writeln!(out, "index: {}", self.index.index_hash)?;
+        writeln!(out, "pack: {}", self.index.data_hash)
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        use output::field;
+        let mut fields = vec![
+            field("index.index_hash", self.index.index_hash),
+            field("index.data_hash", self.index.data_hash),
+            field("index.num_objects", self.index.num_objects),
+        ];
+        fields.extend(
+            self.index_path
+                .iter()
+                .map(|path| field("index_path", path.display()))
+                .chain(self.data_path.iter().map(|path| field("data_path", path.display()))),
+        );
+        fields
+    }
 }
@@ -0,0 +1,195 @@
+use std::{collections::HashMap, fs, io, path::Path};
+
+use anyhow::{Context as AnyhowContext, Result};
+use git_repository::{hash::ObjectId, odb::pack};
+
+use crate::{output, OutputFormat};
+
+/// A single entry of a pack data file along with its place in a delta chain.
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Entry {
+    /// The offset of the entry's header in the pack.
+    pub offset: u64,
+    /// The id of the object, only known if the pack has an index.
+    pub id: Option<String>,
+    /// The kind of entry, one of `commit`, `tree`, `blob`, `tag`, `ofs-delta` or `ref-delta`.
+    pub kind: String,
+    /// The size of the object, or of the delta instructions for deltas.
+    pub size: u64,
+    /// The amount of bytes the entry occupies in the pack, including its header.
+    pub size_in_pack: u64,
+    /// The offset of the base object of a delta, if it is in the pack.
+    pub base_offset: Option<u64>,
+    /// The id of the base object of a ref-delta, or of an ofs-delta if the pack has an index.
+    pub base_id: Option<String>,
+    /// The amount of deltas to apply to reach this object, 0 for non-delta objects, or `None` if the chain leaves the pack.
+    pub depth: Option<u32>,
+}
+
+impl output::Record for Entry {
+    fn write_human(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        write!(
+            out,
+            "{:>10} {:<9} {:>8} {:>8}",
+            self.offset, self.kind, self.size, self.size_in_pack
+        )?;
+        if let Some(id) = &self.id {
+            write!(out, " {}", id)?;
+        }
+        if self.kind.ends_with("delta") {
+            match self.depth {
+                Some(depth) => write!(out, " depth {}", depth)?,
+                None => write!(out, " depth ?")?,
+            }
+            match (&self.base_id, self.base_offset) {
+                (Some(id), _) => write!(out, " base {}", id)?,
+                (None, Some(offset)) => write!(out, " base @{}", offset)?,
+                (None, None) => {}
+            }
+        }
+        writeln!(out)
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        use output::field;
+        let mut fields = vec![
+            field("offset", self.offset),
+            field("kind", &self.kind),
+            field("size", self.size),
+            field("size_in_pack", self.size_in_pack),
+        ];
+        fields.extend(self.id.iter().map(|id| field("id", id)));
+        fields.extend(self.base_offset.iter().map(|offset| field("base_offset", offset)));
+        fields.extend(self.base_id.iter().map(|id| field("base_id", id)));
+        fields.extend(self.depth.iter().map(|depth| field("depth", depth)));
+        fields
+    }
+}
+
+/// Write all entries of the pack at `path` to `out` in `format`, including their delta base and chain depth, similar to
+/// `git verify-pack -v`. `path` may point to the `.pack` or `.idx` file.
+///
+/// The index is used to learn object ids and to resolve the bases of ref-deltas, but isn't required.
+/// Human readable output ends with a histogram of chain lengths.
+pub fn inspect(path: impl AsRef<Path>, format: OutputFormat, mut out: impl io::Write) -> Result<()> {
+    let entries = entries(path.as_ref())?;
+    output::write_all(format, &mut out, &entries)?;
+    if format == OutputFormat::Human {
+        print_chain_lengths(&mut out, &entries)?;
+    }
+    Ok(())
+}
+
+/// Read all entries of the pack at `path`, which may point to the `.pack` or `.idx` file, in the order they are stored.
+pub(crate) fn entries(path: &Path) -> Result<Vec<Entry>> {
+    let pack_path = path.with_extension("pack");
+    let index_path = path.with_extension("idx");
+    let ids_by_offset: HashMap<u64, ObjectId> = if index_path.is_file() {
+        pack::index::File::at(&index_path)
+            .with_context(|| format!("Could not open pack index at '{}'", index_path.display()))?
+            .iter()
+            .map(|entry| (entry.pack_offset, entry.oid))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+    let offsets_by_id: HashMap<ObjectId, u64> = ids_by_offset.iter().map(|(offset, id)| (*id, *offset)).collect();
+
+    let pack = io::BufReader::new(
+        fs::File::open(&pack_path).with_context(|| format!("Could not open pack at '{}'", pack_path.display()))?,
+    );
+    let mut entries = Vec::new();
+    for entry in pack::data::input::BytesToEntriesIter::new_from_header(
+        pack,
+        pack::data::input::Mode::AsIs,
+        pack::data::input::EntryDataMode::Ignore,
+    )? {
+        let entry = entry?;
+        use pack::data::entry::Header::*;
+        let (kind, base_offset, base_id) = match entry.header {
+            Commit => ("commit", None, None),
+            Tree => ("tree", None, None),
+            Blob => ("blob", None, None),
+            Tag => ("tag", None, None),
+            OfsDelta { base_distance } => {
+                let base_offset =
+                    pack::data::entry::Header::verified_base_pack_offset(entry.pack_offset, base_distance);
+                (
+                    "ofs-delta",
+                    base_offset,
+                    base_offset.and_then(|offset| ids_by_offset.get(&offset).copied()),
+                )
+            }
+            RefDelta { base_id } => ("ref-delta", offsets_by_id.get(&base_id).copied(), Some(base_id)),
+        };
+        entries.push(Entry {
+            offset: entry.pack_offset,
+            id: ids_by_offset.get(&entry.pack_offset).map(ToString::to_string),
+            kind: kind.into(),
+            size: entry.decompressed_size,
+            size_in_pack: entry.bytes_in_pack(),
+            base_offset,
+            base_id: base_id.map(|id| id.to_string()),
+            depth: None,
+        });
+    }
+    assign_depths(&mut entries);
+    Ok(entries)
+}
+
+/// Set the depth of all entries whose delta chain can be followed to a non-delta object within the pack.
+fn assign_depths(entries: &mut [Entry]) {
+    let index_by_offset: HashMap<u64, usize> = entries
+        .iter()
+        .enumerate()
+        .map(|(idx, entry)| (entry.offset, idx))
+        .collect();
+    let mut chain = Vec::new();
+    for idx in 0..entries.len() {
+        chain.clear();
+        let mut current = idx;
+        let depth = loop {
+            if let Some(depth) = entries[current].depth {
+                break Some(depth);
+            }
+            if !entries[current].kind.ends_with("delta") {
+                break Some(0);
+            }
+            // A chain longer than the amount of entries contains a cycle.
+            if chain.len() > entries.len() {
+                break None;
+            }
+            chain.push(current);
+            match entries[current]
+                .base_offset
+                .and_then(|offset| index_by_offset.get(&offset).copied())
+            {
+                Some(base) => current = base,
+                None => break None,
+            }
+        };
+        if chain.is_empty() {
+            entries[idx].depth = depth;
+            continue;
+        }
+        for (distance, entry) in chain.iter().rev().enumerate() {
+            entries[*entry].depth = depth.map(|depth| depth + distance as u32 + 1);
+        }
+    }
+}
+
+fn print_chain_lengths(out: &mut impl io::Write, entries: &[Entry]) -> io::Result<()> {
+    let mut objects_per_depth = std::collections::BTreeMap::<Option<u32>, usize>::new();
+    for entry in entries {
+        *objects_per_depth.entry(entry.depth).or_default() += 1;
+    }
+    writeln!(out)?;
+    for (depth, objects) in objects_per_depth {
+        match depth {
+            Some(0) => writeln!(out, "non delta: {} objects", objects)?,
+            Some(depth) => writeln!(out, "chain length = {}: {} objects", depth, objects)?,
+            None => writeln!(out, "chain leaving the pack: {} objects", objects)?,
+        }
+    }
+    Ok(())
+}
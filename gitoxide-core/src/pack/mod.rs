@@ -1,5 +1,9 @@
+pub mod diff;
+pub use diff::diff;
 pub mod explode;
 pub mod index;
+pub mod inspect;
+pub use inspect::inspect;
 pub mod verify;
 
 #[cfg(any(feature = "async-client", feature = "blocking-client"))]
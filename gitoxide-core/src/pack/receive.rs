@@ -1,11 +1,11 @@
 use std::{
     io,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{atomic::AtomicBool, Arc},
 };
 
 use git_repository::{
-    hash::ObjectId,
+    fetch_head,
     objs::bstr::{BString, ByteSlice},
     odb::pack,
     protocol,
@@ -16,7 +16,7 @@ use git_repository::{
     },
 };
 
-use crate::{remote::refs::JsonRef, OutputFormat};
+use crate::{output, remote::refs::JsonRef, OutputFormat};
 
 pub const PROGRESS_RANGE: std::ops::RangeInclusive<u8> = 1..=3;
 
@@ -33,6 +33,7 @@ struct CloneDelegate<W> {
     refs_directory: Option<PathBuf>,
     ref_filter: Option<&'static [&'static str]>,
     wanted_refs: Vec<BString>,
+    url: String,
 }
 static FILTER: &[&str] = &["HEAD", "refs/tags", "refs/heads"];
 
@@ -87,7 +88,10 @@ impl<W> protocol::fetch::DelegateBlocking for CloneDelegate<W> {
     ) -> io::Result<Action> {
         if self.wanted_refs.is_empty() {
             for r in refs {
-                let (path, id) = r.unpack();
+                let (path, id) = match r.unpack() {
+                    (path, Some(id)) => (path, id),
+                    (_, None) => continue,
+                };
                 match self.ref_filter {
                     Some(ref_prefixes) => {
                         if ref_prefixes.iter().any(|prefix| path.starts_with_str(prefix)) {
@@ -108,7 +112,7 @@ impl<W> protocol::fetch::DelegateBlocking for CloneDelegate<W> {
 
 #[cfg(feature = "blocking-client")]
 mod blocking_io {
-    use std::{io, io::BufRead, path::PathBuf};
+    use std::{io, io::BufRead, path::PathBuf, sync::Arc};
 
     use git_repository::{
         bstr::BString,
@@ -117,8 +121,8 @@ mod blocking_io {
         Progress,
     };
 
-    use super::{receive_pack_blocking, CloneDelegate, Context};
-    use crate::net;
+    use super::{receive_pack_blocking, CloneDelegate, Context, RefsDirectory};
+    use crate::{diagnostics::Operation, net};
 
     impl<W: io::Write> protocol::fetch::Delegate for CloneDelegate<W> {
         fn receive_pack(
@@ -128,9 +132,12 @@ mod blocking_io {
             refs: &[Ref],
             _previous_response: &Response,
         ) -> io::Result<()> {
+            let (url, wanted_refs) = (self.url.as_str(), self.wanted_refs.as_slice());
             receive_pack_blocking(
                 self.directory.take(),
-                self.refs_directory.take(),
+                self.refs_directory
+                    .take()
+                    .map(|path| RefsDirectory { path, url, wanted_refs }),
                 &mut self.ctx,
                 input,
                 progress,
@@ -148,13 +155,17 @@ mod blocking_io {
         progress: P,
         ctx: Context<W>,
     ) -> anyhow::Result<()> {
-        let transport = net::connect(url.as_bytes(), protocol.unwrap_or_default().into())?;
+        let _op = Operation::start(module_path!(), "pack-receive");
+        log::debug!("fetching url={}", url);
+        let mut transport = net::connect(url.as_bytes(), protocol.unwrap_or_default().into())?;
+        transport.set_should_interrupt(Arc::clone(&ctx.should_interrupt));
         let delegate = CloneDelegate {
             ctx,
             directory,
             refs_directory,
             ref_filter: None,
             wanted_refs,
+            url: url.into(),
         };
         protocol::fetch(
             transport,
@@ -184,8 +195,8 @@ mod async_io {
         Progress,
     };
 
-    use super::{print, receive_pack_blocking, write_raw_refs, CloneDelegate, Context};
-    use crate::{net, OutputFormat};
+    use super::{receive_pack_blocking, write_raw_refs, CloneDelegate, Context, RefsDirectory};
+    use crate::{diagnostics::Operation, net, OutputFormat};
 
     #[async_trait(?Send)]
     impl<W: io::Write + Send + 'static> protocol::fetch::Delegate for CloneDelegate<W> {
@@ -196,9 +207,12 @@ mod async_io {
             refs: &[Ref],
             _previous_response: &Response,
         ) -> io::Result<()> {
+            let (url, wanted_refs) = (self.url.as_str(), self.wanted_refs.as_slice());
             receive_pack_blocking(
                 self.directory.take(),
-                self.refs_directory.take(),
+                self.refs_directory
+                    .take()
+                    .map(|path| RefsDirectory { path, url, wanted_refs }),
                 &mut self.ctx,
                 futures_lite::io::BlockOn::new(input),
                 progress,
@@ -216,6 +230,8 @@ mod async_io {
         progress: P,
         ctx: Context<W>,
     ) -> anyhow::Result<()> {
+        let _op = Operation::start(module_path!(), "pack-receive");
+        log::debug!("fetching url={}", url);
         let transport = net::connect(url.as_bytes(), protocol.unwrap_or_default().into()).await?;
         let mut delegate = CloneDelegate {
             ctx,
@@ -223,6 +239,7 @@ mod async_io {
             refs_directory,
             ref_filter: None,
             wanted_refs,
+            url: url.into(),
         };
         blocking::unblock(move || {
             futures_lite::future::block_on(protocol::fetch(
@@ -284,21 +301,48 @@ impl JsonOutcome {
     }
 }
 
-fn print_hash_and_path(out: &mut impl io::Write, name: &str, id: ObjectId, path: Option<PathBuf>) -> io::Result<()> {
+impl output::Record for JsonOutcome {
+    fn write_human(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        print_hash_and_path(out, "index", &self.index.index_hash, self.index_path.as_ref())?;
+        print_hash_and_path(out, "pack", &self.index.data_hash, self.data_path.as_ref())?;
+        writeln!(out)?;
+        for r in &self.refs {
+            r.write_human(out)?;
+        }
+        Ok(())
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        use output::field;
+        let mut fields = vec![
+            field("index.index_hash", &self.index.index_hash),
+            field("index.data_hash", &self.index.data_hash),
+            field("index.num_objects", self.index.num_objects),
+        ];
+        fields.extend(
+            self.index_path
+                .iter()
+                .map(|path| field("index_path", path.display()))
+                .chain(self.data_path.iter().map(|path| field("data_path", path.display()))),
+        );
+        for (idx, r) in self.refs.iter().enumerate() {
+            fields.extend(
+                r.fields()
+                    .into_iter()
+                    .map(|(name, value)| (format!("refs.{}.{}", idx, name), value)),
+            );
+        }
+        fields
+    }
+}
+
+fn print_hash_and_path(out: &mut dyn io::Write, name: &str, id: &str, path: Option<&PathBuf>) -> io::Result<()> {
     match path {
         Some(path) => writeln!(out, "{}: {} ({})", name, id, path.display()),
         None => writeln!(out, "{}: {}", name, id),
     }
 }
 
-fn print(out: &mut impl io::Write, res: pack::bundle::write::Outcome, refs: &[Ref]) -> io::Result<()> {
-    print_hash_and_path(out, "index", res.index.index_hash, res.index_path)?;
-    print_hash_and_path(out, "pack", res.index.data_hash, res.data_path)?;
-    writeln!(out)?;
-    crate::remote::refs::print(out, refs)?;
-    Ok(())
-}
-
 fn write_raw_refs(refs: &[Ref], directory: PathBuf) -> std::io::Result<()> {
     let assure_dir_exists = |path: &BString| {
         assert!(!path.starts_with_str("/"), "no ref start with a /, they are relative");
@@ -307,7 +351,9 @@ fn write_raw_refs(refs: &[Ref], directory: PathBuf) -> std::io::Result<()> {
     };
     for r in refs {
         let (path, content) = match r {
-            Ref::Symbolic { path, target, .. } => (assure_dir_exists(path)?, format!("ref: {}", target)),
+            Ref::Symbolic { path, target, .. } | Ref::Unborn { path, target } => {
+                (assure_dir_exists(path)?, format!("ref: {}", target))
+            }
             Ref::Peeled { path, tag: object, .. } | Ref::Direct { path, object } => {
                 (assure_dir_exists(path)?, object.to_string())
             }
@@ -317,9 +363,45 @@ fn write_raw_refs(refs: &[Ref], directory: PathBuf) -> std::io::Result<()> {
     Ok(())
 }
 
+/// The directory to write received references to, along with what's needed to write `FETCH_HEAD` into it.
+struct RefsDirectory<'a> {
+    path: PathBuf,
+    url: &'a str,
+    wanted_refs: &'a [BString],
+}
+
+/// Write `FETCH_HEAD` into `directory`, marking the `wanted_refs` for merge, or `HEAD` if no refs were asked for explicitly,
+/// like `git fetch <url>` does.
+fn write_fetch_head(refs: &[Ref], url: &str, wanted_refs: &[BString], directory: &Path) -> io::Result<()> {
+    let entries: Vec<_> = refs
+        .iter()
+        .filter_map(|r| {
+            let (name, id) = match r {
+                Ref::Symbolic { path, object, .. }
+                | Ref::Peeled { path, tag: object, .. }
+                | Ref::Direct { path, object } => (path, *object),
+                Ref::Unborn { .. } => return None,
+            };
+            Some(fetch_head::Entry {
+                id,
+                name: name.to_owned(),
+                for_merge: if wanted_refs.is_empty() {
+                    name == "HEAD"
+                } else {
+                    wanted_refs.contains(name)
+                },
+            })
+        })
+        .collect();
+    std::fs::create_dir_all(directory)?;
+    let mut buf = Vec::new();
+    fetch_head::encode(&entries, url.into(), &mut buf)?;
+    std::fs::write(directory.join(fetch_head::FILE_NAME), buf)
+}
+
 fn receive_pack_blocking<W: io::Write>(
     mut directory: Option<PathBuf>,
-    mut refs_directory: Option<PathBuf>,
+    refs_directory: Option<RefsDirectory<'_>>,
     ctx: &mut Context<W>,
     input: impl io::BufRead,
     progress: impl git_repository::Progress,
@@ -329,21 +411,25 @@ fn receive_pack_blocking<W: io::Write>(
         thread_limit: ctx.thread_limit,
         index_kind: pack::index::Version::V2,
         iteration_mode: pack::data::input::Mode::Verify,
+        write_reverse_index: false,
+        fsync: Default::default(),
     };
     let outcome =
         pack::Bundle::write_to_directory(input, directory.take(), progress, &ctx.should_interrupt, None, options)
             .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+    for path in outcome.index_path.iter().chain(outcome.data_path.iter()) {
+        log::debug!("wrote path={}", path.display());
+    }
 
-    if let Some(directory) = refs_directory.take() {
-        write_raw_refs(refs, directory)?;
+    if let Some(RefsDirectory { path, url, wanted_refs }) = refs_directory {
+        log::debug!("writing refs path={}", path.display());
+        write_fetch_head(refs, url, wanted_refs, &path)?;
+        write_raw_refs(refs, path)?;
     }
 
-    match ctx.format {
-        OutputFormat::Human => drop(print(&mut ctx.out, outcome, refs)),
-        #[cfg(feature = "serde1")]
-        OutputFormat::Json => {
-            serde_json::to_writer_pretty(&mut ctx.out, &JsonOutcome::from_outcome_and_refs(outcome, refs))?
-        }
-    };
-    Ok(())
+    output::write_one(
+        ctx.format,
+        &mut ctx.out,
+        &JsonOutcome::from_outcome_and_refs(outcome, refs),
+    )
 }
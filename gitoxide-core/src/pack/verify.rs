@@ -16,7 +16,7 @@ use git_repository::{
 };
 pub use index::verify::Mode;
 
-use crate::OutputFormat;
+use crate::{diagnostics::Operation, output, OutputFormat};
 
 #[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
 pub enum Algorithm {
@@ -53,36 +53,90 @@ impl From<Algorithm> for index::traverse::Algorithm {
 }
 
 /// A general purpose context for many operations provided here
-pub struct Context<W1: io::Write, W2: io::Write> {
+///
+/// Diagnostics, like the decision to only verify the index if its pack is missing, are emitted as log events.
+pub struct Context<W: io::Write> {
     /// If set, provide statistics to `out` in the given format
     pub output_statistics: Option<OutputFormat>,
+    /// If set, write an [`Object`] record to `out` in the given format for each object as soon as it was verified.
+    ///
+    /// With [`OutputFormat::JsonLines`], the records can be piped into other tools without holding them all in memory.
+    pub output_objects: Option<OutputFormat>,
     /// A stream to which to output operation results
-    pub out: W1,
-    /// A stream to which to errors
-    pub err: W2,
+    pub out: W,
     /// If set, don't use more than this amount of threads.
-    /// Otherwise, usually use as many threads as there are logical cores.
+    /// Otherwise, use the thread limit of the repository the pack is in, if there is one and its limit is configured,
+    /// or the default thread limit.
     /// A value of 0 is interpreted as no-limit
     pub thread_limit: Option<usize>,
+    /// The amount of largest objects by decompressed size to track while traversing the pack, to be included in the statistics.
+    pub num_largest_objects: usize,
     pub mode: index::verify::Mode,
     pub algorithm: Algorithm,
     pub should_interrupt: Arc<AtomicBool>,
 }
 
-impl Default for Context<Vec<u8>, Vec<u8>> {
+impl Default for Context<Vec<u8>> {
     fn default() -> Self {
         Context {
             output_statistics: None,
+            output_objects: None,
             thread_limit: None,
+            num_largest_objects: 0,
             mode: index::verify::Mode::Sha1Crc32,
             algorithm: Algorithm::LessMemory,
             out: Vec::new(),
-            err: Vec::new(),
             should_interrupt: Default::default(),
         }
     }
 }
 
+/// An object of a pack as written by [`pack_or_pack_index()`] once it was verified.
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Object {
+    /// The id of the object.
+    pub id: String,
+    /// The offset of the object's entry in the pack.
+    pub offset: u64,
+    /// The kind of the object, one of `commit`, `tree`, `blob` or `tag`.
+    pub kind: String,
+    /// The amount of deltas to apply to reach this object, 0 for non-delta objects.
+    pub num_deltas: u32,
+    /// The size of the compressed entry data in the pack, without its header.
+    pub compressed_size: u64,
+    /// The size of the decompressed entry data, which are the delta instructions for deltas.
+    pub decompressed_size: u64,
+    /// The size of the object after resolving all deltas.
+    pub size: u64,
+    /// The CRC32 of the entry in the pack, only known if the index is of version 2 or higher.
+    pub crc32: Option<u32>,
+}
+
+impl output::Record for Object {
+    fn write_human(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        writeln!(
+            out,
+            "{} {:>10} {:<6} {:>8} {:>8} {:>8} depth {}",
+            self.id, self.offset, self.kind, self.size, self.compressed_size, self.decompressed_size, self.num_deltas
+        )
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        use output::field;
+        let mut fields = vec![
+            field("id", &self.id),
+            field("offset", self.offset),
+            field("kind", &self.kind),
+            field("num_deltas", self.num_deltas),
+            field("compressed_size", self.compressed_size),
+            field("decompressed_size", self.decompressed_size),
+            field("size", self.size),
+        ];
+        fields.extend(self.crc32.iter().map(|crc32| field("crc32", crc32)));
+        fields
+    }
+}
+
 enum EitherCache<const SIZE: usize> {
     Left(pack::cache::Never),
     Right(pack::cache::lru::StaticLinkedList<SIZE>),
@@ -102,29 +156,56 @@ impl<const SIZE: usize> pack::cache::DecodeEntry for EitherCache<SIZE> {
             EitherCache::Right(v) => v.get(pack_id, offset, out),
         }
     }
+
+    fn memory_in_bytes(&self) -> usize {
+        match self {
+            EitherCache::Left(v) => v.memory_in_bytes(),
+            EitherCache::Right(v) => v.memory_in_bytes(),
+        }
+    }
 }
 
-pub fn pack_or_pack_index<W1, W2>(
+pub fn pack_or_pack_index<W>(
     path: impl AsRef<Path>,
     progress: Option<impl Progress>,
     Context {
         mut out,
-        mut err,
         mode,
         output_statistics,
+        output_objects,
         thread_limit,
+        num_largest_objects,
         algorithm,
         should_interrupt,
-    }: Context<W1, W2>,
+    }: Context<W>,
 ) -> Result<(ObjectId, Option<index::traverse::Outcome>)>
 where
-    W1: io::Write,
-    W2: io::Write,
+    W: io::Write + Send,
 {
+    let _op = Operation::start(module_path!(), "pack-verify");
     let path = path.as_ref();
+    log::debug!("reading path={}", path.display());
+    let thread_limit = match thread_limit {
+        Some(limit) => Some(limit),
+        None => match path.parent().map(git_repository::discover) {
+            Some(Ok(repo)) => repo.thread_limit()?.map(|limit| limit.num_threads()),
+            _ => None,
+        },
+    };
+    if path.file_name().map_or(false, |name| name == "multi-pack-index") {
+        let index = odb::pack::multi_index::File::at(path).with_context(|| "Could not open multi-pack index file")?;
+        let id = index
+            .verify_integrity(
+                progress::DoOrDiscard::from(progress).add_child("multi-pack index"),
+                true,
+                &should_interrupt,
+            )
+            .with_context(|| "Verification failure")?;
+        return Ok((id, None));
+    }
     let ext = path.extension().and_then(|ext| ext.to_str()).ok_or_else(|| {
         anyhow!(
-            "Cannot determine data type on path without extension '{}', expecting default extensions 'idx' and 'pack'",
+            "Cannot determine data type on path without extension '{}', expecting default extensions 'idx' and 'pack', or a 'multi-pack-index'",
             path.display()
         )
     })?;
@@ -142,13 +223,11 @@ where
             let packfile_path = path.with_extension("pack");
             let pack = odb::pack::data::File::at(&packfile_path)
                 .map_err(|e| {
-                    writeln!(
-                        err,
+                    log::warn!(
                         "Could not find matching pack file at '{}' - only index file will be verified, error was: {}",
                         packfile_path.display(),
                         e
-                    )
-                    .ok();
+                    );
                     e
                 })
                 .ok();
@@ -166,40 +245,112 @@ where
                 }
             };
 
+            let mut write_object =
+                |entry: &index::Entry, outcome: &pack::data::decode_entry::Outcome| -> Result<(), _> {
+                    let object = Object {
+                        id: entry.oid.to_string(),
+                        offset: entry.pack_offset,
+                        kind: outcome.kind.to_string(),
+                        num_deltas: outcome.num_deltas,
+                        compressed_size: outcome.compressed_size as u64,
+                        decompressed_size: outcome.decompressed_size,
+                        size: outcome.object_size,
+                        crc32: entry.crc32,
+                    };
+                    output::write_one(output_objects.expect("set if called"), &mut out, &object).map_err(Into::into)
+                };
             idx.verify_integrity(
                 pack.as_ref().map(|p| (p, mode, algorithm.into(), cache)),
                 thread_limit,
+                num_largest_objects,
+                output_objects.map(|_| &mut write_object as &mut index::traverse::ObjectFn<'_>),
                 progress,
                 should_interrupt,
             )
             .map(|(a, b, _)| (a, b))
             .with_context(|| "Verification failure")?
         }
-        ext => return Err(anyhow!("Unknown extension {:?}, expecting 'idx' or 'pack'", ext)),
+        ext => {
+            return Err(anyhow!(
+                "Unknown extension {:?}, expecting 'idx' or 'pack', or a 'multi-pack-index'",
+                ext
+            ))
+        }
     };
-    if let Some(stats) = res.1.as_ref() {
-        #[cfg_attr(not(feature = "serde1"), allow(clippy::single_match))]
-        match output_statistics {
-            Some(OutputFormat::Human) => drop(print_statistics(&mut out, stats)),
-            #[cfg(feature = "serde1")]
-            Some(OutputFormat::Json) => serde_json::to_writer_pretty(out, stats)?,
-            _ => {}
-        };
+    if let (Some(stats), Some(format)) = (res.1.as_ref(), output_statistics) {
+        output::write_one(format, &mut out, stats)?;
     }
     Ok(res)
 }
 
+impl output::Record for index::traverse::Outcome {
+    fn write_human(&self, mut out: &mut dyn io::Write) -> io::Result<()> {
+        print_statistics(&mut out, self)
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        use output::field;
+        let mut fields: Vec<_> = self
+            .objects_per_chain_length
+            .iter()
+            .map(|(chain_length, objects)| field(format!("objects_per_chain_length.{}", chain_length), objects))
+            .collect();
+        fields.extend(
+            self.objects_per_size_class
+                .iter()
+                .map(|(size_class, objects)| field(format!("objects_per_size_class.{}", size_class), objects)),
+        );
+        fields.extend(vec![
+            field("average.num_deltas", self.average.num_deltas),
+            field("average.decompressed_size", self.average.decompressed_size),
+            field("average.compressed_size", self.average.compressed_size),
+            field("average.object_size", self.average.object_size),
+            field("total_compressed_entries_size", self.total_compressed_entries_size),
+            field("total_decompressed_entries_size", self.total_decompressed_entries_size),
+            field("total_object_size", self.total_object_size),
+            field("pack_size", self.pack_size),
+            field("num_commits", self.num_commits),
+            field("num_trees", self.num_trees),
+            field("num_tags", self.num_tags),
+            field("num_blobs", self.num_blobs),
+        ]);
+        for (idx, object) in self.largest_objects.iter().enumerate() {
+            fields.extend(vec![
+                field(format!("largest_objects.{}.id", idx), object.id),
+                field(format!("largest_objects.{}.kind", idx), object.kind),
+                field(format!("largest_objects.{}.object_size", idx), object.object_size),
+                field(format!("largest_objects.{}.num_deltas", idx), object.num_deltas),
+            ]);
+        }
+        fields
+    }
+}
+
 fn print_statistics(out: &mut impl io::Write, stats: &index::traverse::Outcome) -> io::Result<()> {
     writeln!(out, "objects per delta chain length")?;
-    let mut chain_length_to_object: Vec<_> = stats.objects_per_chain_length.iter().map(|(a, b)| (*a, *b)).collect();
-    chain_length_to_object.sort_by_key(|e| e.0);
-    let mut total_object_count = 0;
-    for (chain_length, object_count) in chain_length_to_object.into_iter() {
-        total_object_count += object_count;
-        writeln!(out, "\t{:>2}: {}", chain_length, object_count)?;
-    }
+    let chain_length_to_object: Vec<_> = stats
+        .objects_per_chain_length
+        .iter()
+        .map(|(chain_length, object_count)| (format!("{:>2}", chain_length), *object_count))
+        .collect();
+    write_histogram(out, &chain_length_to_object)?;
+    let total_object_count: u32 = stats.objects_per_chain_length.values().sum();
     writeln!(out, "\t->: {}", total_object_count)?;
 
+    writeln!(out, "\nobjects per decompressed object size")?;
+    let size_class_to_object: Vec<_> = stats
+        .objects_per_size_class
+        .iter()
+        .map(|(size_class, object_count)| {
+            let label = match size_class {
+                0 => "0 B".into(),
+                size_class => format!("< {}", ByteSize(1 << size_class)),
+            };
+            (label, *object_count)
+        })
+        .collect();
+    write_histogram(out, &size_class_to_object)?;
+
     let pack::data::decode_entry::Outcome {
         kind: _,
         num_deltas,
@@ -253,5 +404,41 @@ fn print_statistics(out: &mut impl io::Write, stats: &index::traverse::Outcome)
         "pack overhead", (1.0 - (stats.total_compressed_entries_size as f64 / stats.pack_size as f64)) * 100.0,
         width = width
     )?;
+
+    if !stats.largest_objects.is_empty() {
+        writeln!(out, "\nlargest objects")?;
+        for object in &stats.largest_objects {
+            writeln!(
+                out,
+                "\t{} {:<6} {:>10} delta chain length: {}",
+                object.id,
+                object.kind,
+                ByteSize(object.object_size),
+                object.num_deltas
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Write one line per `(label, count)` pair of `rows`, with a bar scaled to the largest count to visualize the distribution.
+fn write_histogram(out: &mut impl io::Write, rows: &[(String, u32)]) -> io::Result<()> {
+    const BAR_WIDTH: usize = 40;
+    let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    let max_count = rows.iter().map(|(_, count)| *count as usize).max().unwrap_or(0);
+    let count_width = max_count.to_string().len();
+    for (label, count) in rows {
+        // Round up so that every non-empty row is visible.
+        let bar = (*count as usize * BAR_WIDTH) as f64 / max_count as f64;
+        writeln!(
+            out,
+            "\t{:>label_width$}: {:>count_width$} {}",
+            label,
+            count,
+            "#".repeat(bar.ceil() as usize),
+            label_width = label_width,
+            count_width = count_width
+        )?;
+    }
     Ok(())
 }
@@ -0,0 +1,229 @@
+//! Periodically take snapshots of a progress tree and hand them to a callback, or write them as JSON lines or plain text
+//! lines, to allow displaying progress in other applications or logs without rendering it in a terminal.
+use std::{
+    io,
+    ops::RangeInclusive,
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use prodash::{
+    messages::MessageLevel,
+    progress::{State, Step},
+    Tree,
+};
+
+/// The state of the entire progress tree at a point in time.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Snapshot {
+    /// The amount of milliseconds since snapshots are taken.
+    pub elapsed_ms: u64,
+    /// All tasks in hierarchical order, with children following their parent and having a higher level.
+    pub tasks: Vec<Task>,
+    /// All messages since the previous snapshot, from oldest to newest.
+    pub messages: Vec<Message>,
+}
+
+/// A task in the progress tree.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Task {
+    /// The depth of the task in the tree, starting at 1 for the top-level tasks.
+    pub level: u8,
+    /// The name of the task.
+    pub name: String,
+    /// The progress of the task, or `None` if it only groups its children.
+    pub progress: Option<Value>,
+}
+
+/// The progress made by a [`Task`].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Value {
+    /// The amount of steps made so far.
+    pub step: Step,
+    /// The step at which the task is done, or `None` if it is unbounded.
+    pub max: Option<Step>,
+    /// The name of the unit of the steps, like `objects`, if it has one.
+    pub unit: Option<String>,
+    /// One of `running`, `blocked` or `halted`.
+    pub state: String,
+    /// The reason for the task being blocked or halted.
+    pub reason: Option<String>,
+}
+
+/// A message emitted by a task.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Message {
+    /// One of `info`, `success` or `failure`.
+    pub level: String,
+    /// The name of the task that emitted the message.
+    pub origin: String,
+    /// The message itself.
+    pub message: String,
+}
+
+/// Options for [`stream()`], [`json_lines()`] and [`log_lines()`].
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    /// The time to wait between two snapshots.
+    pub interval: Duration,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            interval: Duration::from_millis(250),
+        }
+    }
+}
+
+/// A handle to the thread taking snapshots, which stops it once dropped.
+pub struct JoinHandle {
+    inner: Option<std::thread::JoinHandle<()>>,
+    shutdown: Option<mpsc::Sender<()>>,
+}
+
+impl JoinHandle {
+    /// Stop taking snapshots once the final one, taken after all work is done, was passed on.
+    pub fn shutdown_and_wait(self) {
+        drop(self)
+    }
+}
+
+impl Drop for JoinHandle {
+    fn drop(&mut self) {
+        self.shutdown.take();
+        self.inner.take().and_then(|handle| handle.join().ok());
+    }
+}
+
+/// Take a snapshot of `progress` every [`Options::interval`] and pass it to `consume` on a new thread, until the returned handle
+/// is dropped or `consume` fails.
+///
+/// A final snapshot is taken when shutting down, so the end state of all tasks and all messages are always seen.
+pub fn stream(
+    progress: Tree,
+    Options { interval }: Options,
+    mut consume: impl FnMut(&Snapshot) -> io::Result<()> + Send + 'static,
+) -> JoinHandle {
+    let (tx, rx) = mpsc::channel::<()>();
+    let inner = std::thread::spawn(move || {
+        let start = Instant::now();
+        let mut tasks = Vec::new();
+        let mut messages = Vec::new();
+        let mut message_state = None;
+        loop {
+            let shutdown = !matches!(rx.recv_timeout(interval), Err(mpsc::RecvTimeoutError::Timeout));
+            message_state = Some(progress.copy_new_messages(&mut messages, message_state));
+            progress.sorted_snapshot(&mut tasks);
+            let snapshot = Snapshot {
+                elapsed_ms: start.elapsed().as_millis() as u64,
+                tasks: tasks
+                    .drain(..)
+                    .map(|(key, task)| Task {
+                        level: key.level(),
+                        name: task.name,
+                        progress: task.progress.map(|value| {
+                            let (state, reason) = match value.state {
+                                State::Running => ("running", None),
+                                State::Blocked(reason, _) => ("blocked", Some(reason.to_owned())),
+                                State::Halted(reason, _) => ("halted", Some(reason.to_owned())),
+                            };
+                            Value {
+                                step: value.step,
+                                max: value.done_at,
+                                unit: value
+                                    .unit
+                                    .as_ref()
+                                    .map(|unit| {
+                                        let mut name = String::new();
+                                        unit.as_display_value().display_unit(&mut name, value.step).ok();
+                                        name
+                                    })
+                                    .filter(|name| !name.is_empty()),
+                                state: state.into(),
+                                reason,
+                            }
+                        }),
+                    })
+                    .collect(),
+                messages: messages
+                    .drain(..)
+                    .map(|message| Message {
+                        level: match message.level {
+                            MessageLevel::Info => "info",
+                            MessageLevel::Success => "success",
+                            MessageLevel::Failure => "failure",
+                        }
+                        .into(),
+                        origin: message.origin,
+                        message: message.message,
+                    })
+                    .collect(),
+            };
+            if consume(&snapshot).is_err() || shutdown {
+                break;
+            }
+        }
+    });
+    JoinHandle {
+        inner: Some(inner),
+        shutdown: Some(tx),
+    }
+}
+
+/// Like [`stream()`], but write each snapshot as JSON object on its own line to `out`.
+pub fn json_lines(mut out: impl io::Write + Send + 'static, progress: Tree, options: Options) -> JoinHandle {
+    stream(progress, options, move |snapshot| {
+        serde_json::to_writer(&mut out, snapshot)?;
+        writeln!(out)?;
+        out.flush()
+    })
+}
+
+/// Like [`stream()`], but write all messages and the progress of each task with a level within `levels` as plain text lines
+/// to `out`, which is suitable for logs of CI systems and other outputs which aren't terminals.
+///
+/// Each line starts with the seconds elapsed since snapshots are taken, and tasks are indented by their level.
+pub fn log_lines(
+    mut out: impl io::Write + Send + 'static,
+    progress: Tree,
+    levels: RangeInclusive<u8>,
+    options: Options,
+) -> JoinHandle {
+    stream(progress, options, move |snapshot| {
+        let elapsed = snapshot.elapsed_ms as f64 / 1000.0;
+        for message in &snapshot.messages {
+            writeln!(
+                out,
+                "[{:>8.1}s] {} {}: {}",
+                elapsed, message.level, message.origin, message.message
+            )?;
+        }
+        for task in snapshot.tasks.iter().filter(|task| levels.contains(&task.level)) {
+            let value = match &task.progress {
+                Some(value) => value,
+                None => continue,
+            };
+            write!(
+                out,
+                "[{:>8.1}s] {:indent$}{}: {}",
+                elapsed,
+                "",
+                task.name,
+                value.step,
+                indent = (task.level - levels.start()) as usize * 2
+            )?;
+            if let Some(max) = value.max {
+                write!(out, "/{}", max)?;
+            }
+            if let Some(unit) = &value.unit {
+                write!(out, " {}", unit)?;
+            }
+            if let Some(reason) = &value.reason {
+                write!(out, " ({}: {})", value.state, reason)?;
+            }
+            writeln!(out)?;
+        }
+        out.flush()
+    })
+}
@@ -0,0 +1,255 @@
+use std::{
+    collections::{hash_map::Entry, HashMap, VecDeque},
+    io,
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, Context as AnyhowContext};
+use git_repository::{
+    bstr::{BString, ByteVec},
+    hash::ObjectId,
+    objs::{self, tree::EntryMode},
+    prelude::*,
+};
+
+use crate::{diagnostics::Operation, output, OutputFormat};
+
+/// An object on the path from a reference to the object whose reachability is explained.
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Step {
+    /// The id of the object.
+    pub id: String,
+    /// The kind of the object, one of `commit`, `tree`, `blob` or `tag`.
+    pub kind: String,
+    /// A revision which `git rev-parse` resolves to this object, like `refs/heads/main~2:src/lib.rs`.
+    pub name: String,
+}
+
+impl output::Record for Step {
+    fn write_human(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        writeln!(out, "{} {:<6} {}", self.id, self.kind, self.name)
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        use output::field;
+        vec![
+            field("id", &self.id),
+            field("kind", &self.kind),
+            field("name", &self.name),
+        ]
+    }
+}
+
+/// How an object was reached from its predecessor.
+enum Edge {
+    /// The object is the target of a reference or reflog entry with the given name, like `HEAD` or `refs/heads/main@{2}`.
+    Start(String),
+    /// The object is the target of an annotated tag.
+    Peel,
+    /// The object is the parent with the given 1-based number of a commit.
+    Parent(usize),
+    /// The object is the tree of a commit.
+    Tree,
+    /// The object is the entry with the given name of a tree.
+    Entry(BString),
+}
+
+/// Write the path from a reference or reflog entry to the object with `id` in the repository at `repository` to `out`, to learn
+/// why it is retained, which is useful to find out why large blobs survive garbage collection.
+///
+/// References are searched first, and reflogs only if the object isn't reachable from a reference. Of all paths, one with the
+/// least amount of objects on it is written, starting at the reference, followed by the commits and trees leading to the object.
+/// Each object is named with a revision, like `refs/heads/main~2^2:src/lib.rs`.
+pub fn reachability_path(
+    repository: PathBuf,
+    id: ObjectId,
+    format: OutputFormat,
+    mut out: impl io::Write,
+) -> anyhow::Result<()> {
+    let _op = Operation::start(module_path!(), "reachability-path");
+    let repo = git_repository::discover(repository)?.into_easy();
+
+    let mut names = vec![String::from("HEAD")];
+    for reference in repo.references()?.all()? {
+        let reference = reference.map_err(|err| anyhow!(err))?;
+        names.push(reference.name().as_bstr().to_string());
+    }
+    let mut ref_tips = Vec::new();
+    let mut reflog_tips = Vec::new();
+    for name in names {
+        let reference = match repo.try_find_reference(name.as_str())? {
+            Some(reference) => reference,
+            None => continue,
+        };
+        if let git_repository::refs::TargetRef::Peeled(target) = reference.target() {
+            ref_tips.push((name.clone(), target.to_owned()));
+        }
+        let mut logs = reference.logs()?;
+        if let Some(lines) = logs.iter()? {
+            let mut ids = Vec::new();
+            for line in lines {
+                let line = line.with_context(|| format!("Could not parse reflog of '{}'", name))?;
+                ids.push(line.new_oid());
+            }
+            reflog_tips.extend(
+                ids.into_iter()
+                    .rev()
+                    .enumerate()
+                    .filter(|(_, id)| !id.is_null())
+                    .map(|(idx, id)| (format!("{}@{{{}}}", name, idx), id)),
+            );
+        }
+    }
+
+    let mut edges = HashMap::<ObjectId, (Option<ObjectId>, Edge)>::new();
+    let mut queue = VecDeque::new();
+    let mut found = false;
+    'tips: for tips in [ref_tips, reflog_tips] {
+        for (name, tip) in tips {
+            if let Entry::Vacant(entry) = edges.entry(tip) {
+                entry.insert((None, Edge::Start(name)));
+                queue.push_back(tip);
+            }
+        }
+        if edges.contains_key(&id) {
+            found = true;
+            break;
+        }
+        while let Some(current) = queue.pop_front() {
+            let children = match repo.try_find_object(current)? {
+                Some(object) => children(&object.data, object.kind)?,
+                None => continue,
+            };
+            for (child, edge, is_blob) in children {
+                if is_blob && child != id {
+                    continue;
+                }
+                if let Entry::Vacant(entry) = edges.entry(child) {
+                    entry.insert((Some(current), edge));
+                    if child == id {
+                        found = true;
+                        break 'tips;
+                    }
+                    queue.push_back(child);
+                }
+            }
+        }
+    }
+    if !found {
+        return Err(anyhow!("Object {} is not reachable from any reference or reflog", id));
+    }
+
+    let mut chain = vec![id];
+    while let Some((Some(predecessor), _)) = edges.get(chain.last().expect("non-empty")) {
+        chain.push(*predecessor);
+    }
+    chain.reverse();
+
+    let mut name = Name::default();
+    let mut steps = Vec::with_capacity(chain.len());
+    for id in chain {
+        name.apply(&edges[&id].1);
+        let kind = repo.find_object(id)?.kind;
+        steps.push(Step {
+            id: id.to_string(),
+            kind: kind.to_string(),
+            name: name.to_string(),
+        });
+    }
+    output::write_all(format, &mut out, &steps)?;
+    Ok(())
+}
+
+/// Return the ids of all objects directly referred to by the object of `kind` with `data`, and whether they are known to be a
+/// blob as these can't lead to other objects. Submodule commits are skipped as they aren't part of this repository.
+fn children(data: &[u8], kind: objs::Kind) -> anyhow::Result<Vec<(ObjectId, Edge, bool)>> {
+    Ok(match kind {
+        objs::Kind::Commit => {
+            let mut out = Vec::new();
+            let mut parents = 0;
+            for token in objs::CommitRefIter::from_bytes(data) {
+                match token? {
+                    objs::commit::ref_iter::Token::Tree { id } => out.push((id, Edge::Tree, false)),
+                    objs::commit::ref_iter::Token::Parent { id } => {
+                        parents += 1;
+                        out.push((id, Edge::Parent(parents), false))
+                    }
+                    _ => break,
+                }
+            }
+            out
+        }
+        objs::Kind::Tag => objs::TagRefIter::from_bytes(data)
+            .target_id()
+            .map(|id| vec![(id, Edge::Peel, false)])
+            .unwrap_or_default(),
+        objs::Kind::Tree => {
+            let mut out = Vec::new();
+            for entry in objs::TreeRefIter::from_bytes(data) {
+                let entry = entry?;
+                if entry.mode == EntryMode::Commit {
+                    continue;
+                }
+                out.push((
+                    entry.oid.to_owned(),
+                    Edge::Entry(entry.filename.to_owned()),
+                    entry.mode != EntryMode::Tree,
+                ));
+            }
+            out
+        }
+        objs::Kind::Blob => Vec::new(),
+    })
+}
+
+/// The revision naming the object reached by following edges so far.
+#[derive(Default)]
+struct Name {
+    /// The revision up to the last non-first-parent step.
+    base: String,
+    /// The amount of first parents to follow from `base`.
+    first_parents: usize,
+    /// The path of the tree entry relative to the tree of the revision, if the object is in a tree.
+    path: Option<BString>,
+}
+
+impl Name {
+    fn apply(&mut self, edge: &Edge) {
+        match edge {
+            Edge::Start(name) => self.base = name.clone(),
+            Edge::Peel => self.set_base("^{}"),
+            Edge::Parent(1) => self.first_parents += 1,
+            Edge::Parent(n) => self.set_base(&format!("^{}", n)),
+            Edge::Tree => self.path = Some(BString::default()),
+            Edge::Entry(filename) => {
+                let path = self.path.get_or_insert_with(Default::default);
+                if !path.is_empty() {
+                    path.push(b'/');
+                }
+                path.push_str(filename);
+            }
+        }
+    }
+
+    fn set_base(&mut self, suffix: &str) {
+        self.base = format!("{}{}", self.revision(), suffix);
+        self.first_parents = 0;
+    }
+
+    fn revision(&self) -> String {
+        match self.first_parents {
+            0 => self.base.clone(),
+            n => format!("{}~{}", self.base, n),
+        }
+    }
+}
+
+impl std::fmt::Display for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.path {
+            None => f.write_str(&self.revision()),
+            Some(path) if path.is_empty() => write!(f, "{}^{{tree}}", self.revision()),
+            Some(path) => write!(f, "{}:{}", self.revision(), path),
+        }
+    }
+}
@@ -1,13 +1,14 @@
 pub mod refs {
     use git_repository::{
+        bstr::BString,
         protocol,
         protocol::{
-            fetch::{Action, Arguments, Ref, Response},
+            fetch::{Action, Arguments, LsRefsAction, Ref, Response},
             transport,
         },
     };
 
-    use crate::OutputFormat;
+    use crate::{output, OutputFormat};
 
     pub const PROGRESS_RANGE: std::ops::RangeInclusive<u8> = 1..=2;
 
@@ -16,9 +17,27 @@ pub mod refs {
     #[derive(Default)]
     struct LsRemotes {
         refs: Vec<Ref>,
+        /// The prefixes of the references to list, or all references if empty.
+        prefixes: Vec<BString>,
     }
 
     impl protocol::fetch::DelegateBlocking for LsRemotes {
+        fn prepare_ls_refs(
+            &mut self,
+            server: &transport::client::Capabilities,
+            arguments: &mut Vec<BString>,
+            _features: &mut Vec<(&str, Option<&str>)>,
+        ) -> io::Result<LsRefsAction> {
+            if server.contains("ls-refs") {
+                arguments.extend(
+                    self.prefixes
+                        .iter()
+                        .map(|prefix| format!("ref-prefix {}", prefix).into()),
+                );
+            }
+            Ok(LsRefsAction::Continue)
+        }
+
         fn prepare_fetch(
             &mut self,
             _version: transport::Protocol,
@@ -47,13 +66,14 @@ pub mod refs {
         use async_trait::async_trait;
         use futures_io::AsyncBufRead;
         use git_repository::{
+            bstr::BString,
             protocol,
             protocol::fetch::{Ref, Response},
             Progress,
         };
 
-        use super::{Context, LsRemotes};
-        use crate::{net, remote::refs::print, OutputFormat};
+        use super::{Context, JsonRef, LsRemotes};
+        use crate::{net, output};
 
         #[async_trait(?Send)]
         impl protocol::fetch::Delegate for LsRemotes {
@@ -74,15 +94,36 @@ pub mod refs {
             progress: impl Progress,
             ctx: Context<impl io::Write + Send + 'static>,
         ) -> anyhow::Result<()> {
-            let url = url.to_owned();
+            let refs = refs(protocol, url, Vec::new(), progress).await?;
+            blocking::unblock(move || {
+                output::write_all(
+                    ctx.format,
+                    ctx.out,
+                    &refs.into_iter().map(JsonRef::from).collect::<Vec<_>>(),
+                )
+            })
+            .await?;
+            Ok(())
+        }
+
+        /// Return the references of the remote at `url` whose names start with any of `prefixes`, or all of them if there are
+        /// no prefixes. Note that older servers ignore the prefixes.
+        pub(crate) async fn refs(
+            protocol: Option<net::Protocol>,
+            url: &str,
+            prefixes: Vec<BString>,
+            progress: impl Progress,
+        ) -> anyhow::Result<Vec<Ref>> {
             let transport = net::connect(url.as_bytes(), protocol.unwrap_or_default().into()).await?;
             blocking::unblock(
                 // `blocking` really needs a way to unblock futures, which is what it does internally anyway.
-                // Both fetch() needs unblocking as it executes blocking code within the future, and the other
-                // block does blocking IO because it's primarily a blocking codebase.
+                // fetch() needs unblocking as it executes blocking code within the future.
                 move || {
                     futures_lite::future::block_on(async move {
-                        let mut delegate = LsRemotes::default();
+                        let mut delegate = LsRemotes {
+                            prefixes,
+                            ..Default::default()
+                        };
                         protocol::fetch(
                             transport,
                             &mut delegate,
@@ -91,16 +132,7 @@ pub mod refs {
                             protocol::FetchConnection::TerminateOnSuccessfulCompletion,
                         )
                         .await?;
-
-                        match ctx.format {
-                            OutputFormat::Human => drop(print(ctx.out, &delegate.refs)),
-                            #[cfg(feature = "serde1")]
-                            OutputFormat::Json => serde_json::to_writer_pretty(
-                                ctx.out,
-                                &delegate.refs.into_iter().map(JsonRef::from).collect::<Vec<_>>(),
-                            )?,
-                        }
-                        Ok(())
+                        Ok(delegate.refs)
                     })
                 },
             )
@@ -109,21 +141,22 @@ pub mod refs {
     }
     #[cfg(feature = "async-client")]
     pub use self::async_io::list;
+    #[cfg(feature = "async-client")]
+    pub(crate) use self::async_io::refs;
 
     #[cfg(feature = "blocking-client")]
     mod blocking_io {
         use std::io;
 
         use git_repository::{
+            bstr::BString,
             protocol,
             protocol::fetch::{Ref, Response},
             Progress,
         };
 
-        #[cfg(feature = "serde1")]
-        use super::JsonRef;
-        use super::{print, Context, LsRemotes};
-        use crate::{net, OutputFormat};
+        use super::{Context, JsonRef, LsRemotes};
+        use crate::{net, output};
 
         impl protocol::fetch::Delegate for LsRemotes {
             fn receive_pack(
@@ -143,8 +176,28 @@ pub mod refs {
             progress: impl Progress,
             ctx: Context<impl io::Write>,
         ) -> anyhow::Result<()> {
+            let refs = refs(protocol, url, Vec::new(), progress)?;
+            output::write_all(
+                ctx.format,
+                ctx.out,
+                &refs.into_iter().map(JsonRef::from).collect::<Vec<_>>(),
+            )?;
+            Ok(())
+        }
+
+        /// Return the references of the remote at `url` whose names start with any of `prefixes`, or all of them if there are
+        /// no prefixes. Note that older servers ignore the prefixes.
+        pub(crate) fn refs(
+            protocol: Option<net::Protocol>,
+            url: &str,
+            prefixes: Vec<BString>,
+            progress: impl Progress,
+        ) -> anyhow::Result<Vec<Ref>> {
             let transport = net::connect(url.as_bytes(), protocol.unwrap_or_default().into())?;
-            let mut delegate = LsRemotes::default();
+            let mut delegate = LsRemotes {
+                prefixes,
+                ..Default::default()
+            };
             protocol::fetch(
                 transport,
                 &mut delegate,
@@ -152,20 +205,13 @@ pub mod refs {
                 progress,
                 protocol::FetchConnection::TerminateOnSuccessfulCompletion,
             )?;
-
-            match ctx.format {
-                OutputFormat::Human => drop(print(ctx.out, &delegate.refs)),
-                #[cfg(feature = "serde1")]
-                OutputFormat::Json => serde_json::to_writer_pretty(
-                    ctx.out,
-                    &delegate.refs.into_iter().map(JsonRef::from).collect::<Vec<_>>(),
-                )?,
-            };
-            Ok(())
+            Ok(delegate.refs)
         }
     }
     #[cfg(feature = "blocking-client")]
     pub use blocking_io::list;
+    #[cfg(feature = "blocking-client")]
+    pub(crate) use blocking_io::refs;
 
     pub struct Context<W: io::Write> {
         pub thread_limit: Option<usize>,
@@ -189,6 +235,10 @@ pub mod refs {
             target: String,
             object: String,
         },
+        Unborn {
+            path: String,
+            target: String,
+        },
     }
 
     impl From<Ref> for JsonRef {
@@ -208,28 +258,228 @@ pub mod refs {
                     tag: tag.to_string(),
                     object: object.to_string(),
                 },
+                Ref::Unborn { path, target } => JsonRef::Unborn {
+                    path: path.to_string(),
+                    target: target.to_string(),
+                },
             }
         }
     }
 
-    pub(crate) fn print(mut out: impl io::Write, refs: &[Ref]) -> io::Result<()> {
-        for r in refs {
-            match r {
-                Ref::Direct { path, object } => writeln!(&mut out, "{} {}", object.to_sha1_hex_string(), path),
-                Ref::Peeled { path, object, tag } => {
-                    writeln!(&mut out, "{} {} tag:{}", object.to_sha1_hex_string(), path, tag)
+    impl output::Record for JsonRef {
+        fn write_human(&self, out: &mut dyn io::Write) -> io::Result<()> {
+            match self {
+                JsonRef::Direct { path, object } => writeln!(out, "{} {}", object, path),
+                JsonRef::Peeled { path, object, tag } => writeln!(out, "{} {} tag:{}", object, path, tag),
+                JsonRef::Symbolic { path, target, object } => {
+                    writeln!(out, "{} {} symref-target:{}", object, path, target)
                 }
-                Ref::Symbolic { path, target, object } => {
-                    writeln!(
-                        &mut out,
-                        "{} {} symref-target:{}",
-                        object.to_sha1_hex_string(),
-                        path,
-                        target
-                    )
+                JsonRef::Unborn { path, target } => writeln!(out, "unborn {} symref-target:{}", path, target),
+            }
+        }
+
+        fn fields(&self) -> Vec<(String, String)> {
+            use output::field;
+            match self {
+                JsonRef::Direct { path, object } => vec![field("path", path), field("object", object)],
+                JsonRef::Peeled { path, object, tag } => {
+                    vec![field("path", path), field("object", object), field("tag", tag)]
                 }
-            }?;
+                JsonRef::Symbolic { path, target, object } => {
+                    vec![field("path", path), field("object", object), field("target", target)]
+                }
+                JsonRef::Unborn { path, target } => vec![field("path", path), field("target", target)],
+            }
+        }
+    }
+}
+
+pub mod ref_map {
+    use std::{io, path::PathBuf};
+
+    use anyhow::bail;
+    use git_repository as git;
+    use git_repository::{
+        bstr::{BString, ByteSlice},
+        prelude::ReferenceAccessExt,
+        protocol::{
+            fetch::Ref,
+            refspec::{self, RefSpec},
+        },
+    };
+
+    use crate::{fetch::Mode, output, OutputFormat};
+
+    pub const PROGRESS_RANGE: std::ops::RangeInclusive<u8> = 1..=2;
+
+    pub struct Context<W: io::Write> {
+        pub format: OutputFormat,
+        pub out: W,
+    }
+
+    /// A remote reference matched by a refspec, along with the local reference it maps to.
+    #[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+    pub struct Mapping {
+        /// The full name of the remote reference, like `refs/heads/main`.
+        pub remote: String,
+        /// The object the remote reference points to, or `None` if it is unborn.
+        pub object: Option<String>,
+        /// The refspec which matched the remote reference.
+        pub refspec: String,
+        /// The full name of the local reference it maps to, or `None` if the refspec has no destination.
+        pub local: Option<String>,
+        /// The object the local reference points to, or `None` if it doesn't exist or no repository was given.
+        pub previous: Option<String>,
+        /// How the local reference would be changed when fetching, or `None` if that's unknown as there is no local
+        /// reference or repository, or the remote object isn't available locally to learn if the change is a fast-forward.
+        pub mode: Option<Mode>,
+    }
+
+    impl output::Record for Mapping {
+        fn write_human(&self, out: &mut dyn io::Write) -> io::Result<()> {
+            let new = self.object.as_deref().map_or("unborn", |id| &id[..7]);
+            match &self.previous {
+                Some(previous) if self.object.as_ref() != Some(previous) => write!(out, "{}..{}", &previous[..7], new)?,
+                _ => write!(out, "{}", new)?,
+            }
+            write!(out, " {}", self.remote)?;
+            if let Some(local) = &self.local {
+                write!(out, " -> {}", local)?;
+            }
+            if let Some(mode) = self.mode {
+                write!(out, " ({})", mode)?;
+            }
+            writeln!(out)
+        }
+
+        fn fields(&self) -> Vec<(String, String)> {
+            use output::field;
+            let mut fields = vec![field("remote", &self.remote)];
+            fields.extend(self.object.iter().map(|id| field("object", id)));
+            fields.push(field("refspec", &self.refspec));
+            fields.extend(self.local.iter().map(|local| field("local", local)));
+            fields.extend(self.previous.iter().map(|id| field("previous", id)));
+            fields.extend(self.mode.iter().map(|mode| field("mode", mode)));
+            fields
         }
-        Ok(())
     }
+
+    fn parse(refspecs: &[BString]) -> anyhow::Result<Vec<RefSpec>> {
+        if refspecs.is_empty() {
+            bail!("At least one refspec is needed to map remote references");
+        }
+        refspecs
+            .iter()
+            .map(|spec| {
+                RefSpec::from_bytes(spec.as_bstr())
+                    .map_err(|err| anyhow::Error::from(err).context(format!("Invalid refspec '{}'", spec)))
+            })
+            .collect()
+    }
+
+    fn prefixes(specs: &[RefSpec]) -> Vec<BString> {
+        let mut prefixes: Vec<_> = specs.iter().flat_map(RefSpec::prefixes).collect();
+        prefixes.sort();
+        prefixes.dedup();
+        prefixes
+    }
+
+    /// Map `refs` through `specs` and learn how the local references in the repository at `repository`, if given, would change.
+    fn mappings(specs: &[RefSpec], refs: &[Ref], repository: Option<PathBuf>) -> anyhow::Result<Vec<Mapping>> {
+        let repo = repository.map(git::discover).transpose()?;
+        let easy = repo.as_ref().map(|repo| repo.to_easy());
+        let mut out = Vec::new();
+        for mapping in refspec::map(specs, refs.iter().map(|r| r.unpack().0.as_bstr())) {
+            let new = refs
+                .iter()
+                .find(|r| *r.unpack().0 == mapping.remote)
+                .and_then(|r| r.unpack().1.copied());
+            let spec = &specs[mapping.spec_index];
+            let (previous, mode) = match (&repo, &easy, &mapping.local) {
+                (Some(repo), Some(easy), Some(local)) => match easy.try_find_reference(local.to_str()?)? {
+                    Some(reference) => match reference.target().as_id() {
+                        Some(previous) => {
+                            let previous = previous.to_owned();
+                            let mode = new.filter(|new| previous == *new || repo.odb.contains(new)).map(|new| {
+                                Mode::of_update(repo, Some(previous), new, spec.mode == refspec::Mode::Force)
+                            });
+                            (Some(previous), mode)
+                        }
+                        // Symbolic references are never updated by fetches.
+                        None => (None, Some(Mode::Rejected)),
+                    },
+                    None => (None, new.map(|_| Mode::New)),
+                },
+                _ => (None, None),
+            };
+            out.push(Mapping {
+                remote: mapping.remote.to_string(),
+                object: new.map(|id| id.to_string()),
+                refspec: spec.to_string(),
+                local: mapping.local.map(|local| local.to_string()),
+                previous: previous.map(|id| id.to_string()),
+                mode,
+            });
+        }
+        Ok(out)
+    }
+
+    #[cfg(feature = "blocking-client")]
+    mod blocking_io {
+        use std::{io, path::PathBuf};
+
+        use git_repository::{bstr::BString, Progress};
+
+        use super::{mappings, parse, prefixes, Context};
+        use crate::{net, output, remote::refs};
+
+        /// Write all references of the remote at `url` matched by `refspecs` to `ctx.out`, along with the local references they
+        /// map to, and, if `repository` is given, how these would change when fetching, similar to what `git fetch --dry-run`
+        /// reports.
+        pub fn map(
+            protocol: Option<net::Protocol>,
+            url: &str,
+            refspecs: Vec<BString>,
+            repository: Option<PathBuf>,
+            progress: impl Progress,
+            ctx: Context<impl io::Write>,
+        ) -> anyhow::Result<()> {
+            let specs = parse(&refspecs)?;
+            let refs = refs::refs(protocol, url, prefixes(&specs), progress)?;
+            output::write_all(ctx.format, ctx.out, &mappings(&specs, &refs, repository)?)?;
+            Ok(())
+        }
+    }
+    #[cfg(feature = "blocking-client")]
+    pub use blocking_io::map;
+
+    #[cfg(feature = "async-client")]
+    mod async_io {
+        use std::{io, path::PathBuf};
+
+        use git_repository::{bstr::BString, Progress};
+
+        use super::{mappings, parse, prefixes, Context};
+        use crate::{net, output, remote::refs};
+
+        /// Write all references of the remote at `url` matched by `refspecs` to `ctx.out`, along with the local references they
+        /// map to, and, if `repository` is given, how these would change when fetching, similar to what `git fetch --dry-run`
+        /// reports.
+        pub async fn map(
+            protocol: Option<net::Protocol>,
+            url: &str,
+            refspecs: Vec<BString>,
+            repository: Option<PathBuf>,
+            progress: impl Progress,
+            ctx: Context<impl io::Write + Send + 'static>,
+        ) -> anyhow::Result<()> {
+            let specs = parse(&refspecs)?;
+            let refs = refs::refs(protocol, url, prefixes(&specs), progress).await?;
+            let mappings = blocking::unblock(move || mappings(&specs, &refs, repository)).await?;
+            blocking::unblock(move || output::write_all(ctx.format, ctx.out, &mappings)).await?;
+            Ok(())
+        }
+    }
+    #[cfg(feature = "async-client")]
+    pub use self::async_io::map;
 }
@@ -0,0 +1,207 @@
+use std::{io, path::PathBuf};
+
+use anyhow::{anyhow, bail, Context as AnyhowContext, Result};
+use git_repository::{
+    easy::object::Kind,
+    hash::{self, ObjectId, Prefix},
+    objs::{self, tree::EntryMode, CommitRef},
+    odb::{self, Write},
+    prelude::*,
+};
+
+use crate::diagnostics::Operation;
+
+pub mod verify;
+pub use verify::verify;
+
+/// Initialize a repository in `directory` or the current working directory, creating all missing directories along the way.
+///
+/// If `bare` is set, the repository has no work tree and `HEAD`, `objects` and `refs` are placed into `directory` itself.
+/// All files are built in as configured by `options`, so no installation of git is needed.
+pub fn init(
+    directory: Option<PathBuf>,
+    bare: bool,
+    options: git_repository::path::create::Options,
+) -> Result<git_repository::Path> {
+    let kind = if bare {
+        git_repository::Kind::Bare
+    } else {
+        git_repository::Kind::WorkTree
+    };
+    let _op = Operation::start(module_path!(), "init");
+    // An empty path can't be read as directory to see if it's empty, which bare repositories require.
+    let directory = directory.unwrap_or_else(|| if bare { ".".into() } else { PathBuf::new() });
+    log::debug!("writing path={}", directory.display());
+    git_repository::path::create::into_opts(directory, kind, options)
+        .with_context(|| "Repository initialization failed")
+}
+
+/// Write the content of the blob at `spec` in the repository at `repository` to `out`, similar to `git show REV:path`.
+///
+/// `spec` is of the form `<revision>:<path>`, where `<revision>` is a full or abbreviated object id or a reference name like
+/// `main`, `v1.0` or `HEAD`, optionally followed by `~<n>` to select the n-th first-parent ancestor and `^<n>` to select the
+/// n-th parent.
+/// If `filters` is set, the blob is converted as it would be when written to the work tree, like `git cat-file --filters`
+/// does, which is limited to the end-of-line conversion configured by `core.autocrlf`. Otherwise it is printed as stored,
+/// which is what `git show` does.
+///
+/// The blob is decompressed while it is written to `out`, so its content is never held in memory as a whole unless it
+/// is stored as delta.
+pub fn cat_at(repository: PathBuf, spec: &str, filters: bool, mut out: impl io::Write) -> Result<()> {
+    let (revision, path) = spec
+        .split_once(':')
+        .ok_or_else(|| anyhow!("Expected '<revision>:<path>', got '{}'", spec))?;
+    if revision.is_empty() {
+        bail!(
+            "Reading paths from the index isn't supported, a revision is required in '{}'",
+            spec
+        );
+    }
+    let repository = git_repository::discover(repository)?;
+    let repo = repository.to_easy();
+    let tree = repo
+        .find_object(resolve_revision(&repo, revision)?)?
+        .peel_to_kind(Kind::Tree)
+        .with_context(|| format!("Revision '{}' doesn't point to a tree", revision))?
+        .id;
+    let entry = repo
+        .find_object(tree)?
+        .into_tree()
+        .lookup_entry(path)?
+        .ok_or_else(|| anyhow!("Path '{}' does not exist in '{}'", path, revision))?;
+    match entry.mode {
+        EntryMode::Blob | EntryMode::BlobExecutable | EntryMode::Link => {
+            let mut buf = Vec::new();
+            let blob = repository
+                .odb
+                .try_stream(entry.oid, &mut buf)?
+                .ok_or_else(|| anyhow!("Blob {} of '{}' does not exist", entry.oid, path))?;
+            let smudge = if filters && entry.mode != EntryMode::Link {
+                repository.smudge_filter()?
+            } else {
+                Default::default()
+            };
+            smudge.apply(blob, &mut out)?;
+        }
+        EntryMode::Tree => bail!("Path '{}' in '{}' is a directory", path, revision),
+        EntryMode::Commit => bail!("Path '{}' in '{}' is a submodule", path, revision),
+    }
+    Ok(())
+}
+
+/// The part of an object to print with [`cat_object()`].
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum ObjectPart {
+    /// The content of the object as stored, without its header.
+    Content,
+    /// The kind of the object, one of `commit`, `tree`, `blob` or `tag`.
+    Kind,
+    /// The size of the object's content in bytes.
+    Size,
+}
+
+/// Write the `part` of the object identified by `id` in the repository at `repository` to `out`, similar to `git cat-file`.
+/// The content of loose and undeltified packed objects is decompressed while it is written, without holding it in memory.
+///
+/// `id` is a full object id or a prefix of at least 4 hexadecimal characters matching a single object in packs, loose objects
+/// or alternates.
+pub fn cat_object(repository: PathBuf, id: &str, part: ObjectPart, mut out: impl io::Write) -> Result<()> {
+    let repo = git_repository::discover(repository)?;
+    let prefix = Prefix::from_hex(id).with_context(|| format!("'{}' is not a valid object id or prefix of one", id))?;
+    let id = resolve_prefix(&repo.odb, prefix)?;
+    let mut buf = Vec::new();
+    let mut object = repo
+        .odb
+        .try_stream(id, &mut buf)?
+        .ok_or_else(|| anyhow!("Object {} does not exist", id))?;
+    match part {
+        ObjectPart::Content => {
+            io::copy(&mut object, &mut out)?;
+        }
+        ObjectPart::Kind => writeln!(out, "{}", object.kind)?,
+        ObjectPart::Size => writeln!(out, "{}", object.size)?,
+    }
+    Ok(())
+}
+
+/// Compute the id of the object of `kind` with the content read from `input` and write it to `out`, similar to
+/// `git hash-object`.
+///
+/// If `write` is set, the object is also written into the object database of the repository at `repository`, which isn't
+/// accessed otherwise. Objects other than blobs are validated to be parseable as `kind` before they are hashed.
+pub fn hash_object(
+    repository: PathBuf,
+    mut input: impl io::Read,
+    kind: objs::Kind,
+    write: bool,
+    mut out: impl io::Write,
+) -> Result<()> {
+    let mut data = Vec::new();
+    input.read_to_end(&mut data)?;
+    if kind != objs::Kind::Blob {
+        objs::ObjectRef::from_bytes(kind, &data).map_err(|_| anyhow!("Input is not a valid {} object", kind))?;
+    }
+    let id = if write {
+        git_repository::discover(repository)?
+            .odb
+            .write_buf(kind, &data, hash::Kind::Sha1)?
+    } else {
+        odb::sink().write_buf(kind, &data, hash::Kind::Sha1)?
+    };
+    id.write_hex_to(&mut out)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Resolve `prefix` to the id of the only object it matches.
+fn resolve_prefix(odb: &odb::linked::Store, prefix: Prefix) -> Result<ObjectId> {
+    match odb.lookup_prefix(prefix)? {
+        Some(Ok(id)) => Ok(id),
+        Some(Err(())) => bail!("Short object id '{}' is ambiguous", prefix),
+        None => bail!("Object '{}' does not exist", prefix),
+    }
+}
+
+/// Resolve `revision`, a full object id, reference name or unique object id prefix followed by any amount of `~<n>` and
+/// `^<n>` navigations, to an object id.
+pub(crate) fn resolve_revision(repo: &impl git_repository::easy::Access, revision: &str) -> Result<ObjectId> {
+    let navigation_start = revision.find(['~', '^']).unwrap_or(revision.len());
+    let (base, mut navigation) = revision.split_at(navigation_start);
+    let mut id = match ObjectId::from_hex(base.as_bytes()) {
+        Ok(id) => id,
+        Err(_) => match repo.try_find_reference(base)? {
+            Some(reference) => reference.into_fully_peeled_id()?.detach(),
+            None => match Prefix::from_hex(base) {
+                Ok(prefix) => resolve_prefix(&repo.repo()?.odb, prefix)?,
+                Err(_) => bail!("Could not find reference '{}'", base),
+            },
+        },
+    };
+    while let Some(op) = navigation.chars().next() {
+        let digits_end = navigation[1..]
+            .find(|c: char| !c.is_ascii_digit())
+            .map_or(navigation.len(), |pos| pos + 1);
+        let count = match &navigation[1..digits_end] {
+            "" => 1,
+            digits => digits.parse::<usize>()?,
+        };
+        navigation = &navigation[digits_end..];
+        match op {
+            '~' => {
+                for _ in 0..count {
+                    id = nth_parent(repo, id, 1, revision)?;
+                }
+            }
+            '^' if count == 0 => id = repo.find_object(id)?.peel_to_kind(Kind::Commit)?.id,
+            '^' => id = nth_parent(repo, id, count, revision)?,
+            _ => bail!("Cannot parse '{}' in revision '{}'", navigation, revision),
+        }
+    }
+    Ok(id)
+}
+
+fn nth_parent(repo: &impl git_repository::easy::Access, id: ObjectId, n: usize, revision: &str) -> Result<ObjectId> {
+    let commit = repo.find_object(id)?.peel_to_kind(Kind::Commit)?;
+    let parent = CommitRef::from_bytes(&commit.data)?.parents().nth(n - 1);
+    parent.ok_or_else(|| anyhow!("Commit {} has no parent number {} as required by '{}'", id, n, revision))
+}
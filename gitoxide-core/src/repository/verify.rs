@@ -0,0 +1,432 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt, io,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::{anyhow, bail};
+use git_repository::{
+    hash::{self, ObjectId},
+    objs::{self, tree::EntryMode},
+    odb::{self, pack, Write},
+    prelude::*,
+    progress, Progress,
+};
+
+use crate::{diagnostics::Operation, output, OutputFormat};
+
+/// The part of the repository a [`Problem`] was found in.
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub enum Location {
+    /// A loose object, identified by its id.
+    LooseObject,
+    /// A pack and its index, identified by the path to the index.
+    Pack,
+    /// A multi-pack-index, identified by its path.
+    MultiPackIndex,
+    /// The commit-graph, identified by the path to the `info` directory containing it.
+    CommitGraph,
+    /// A reference, identified by its name.
+    Reference,
+    /// An object reachable from a reference, identified by its id.
+    Object,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Location::LooseObject => "loose-object",
+            Location::Pack => "pack",
+            Location::MultiPackIndex => "multi-pack-index",
+            Location::CommitGraph => "commit-graph",
+            Location::Reference => "reference",
+            Location::Object => "object",
+        })
+    }
+}
+
+/// A problem found by [`verify()`].
+#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Problem {
+    /// The part of the repository the problem was found in.
+    pub location: Location,
+    /// The object id, path or reference name of the item with the problem.
+    pub subject: String,
+    /// A description of the problem.
+    pub message: String,
+}
+
+/// The outcome of [`verify()`], with all problems found in the repository.
+#[derive(Debug, Default, Eq, PartialEq, Hash, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Report {
+    /// The amount of loose objects that were checked.
+    pub num_loose_objects: u64,
+    /// The amount of packs that were checked along with their indices.
+    pub num_packs: usize,
+    /// The amount of objects in all checked packs.
+    pub num_packed_objects: u64,
+    /// The amount of multi-pack-indices that were checked.
+    pub num_multi_pack_indices: usize,
+    /// The amount of commits in the commit-graph, or `None` if there is none.
+    pub num_commit_graph_commits: Option<u32>,
+    /// The amount of references connectivity was checked from, including `HEAD`.
+    pub num_references: usize,
+    /// The amount of objects reachable from all references.
+    pub num_reachable_objects: u64,
+    /// All problems in the order they were found in, or an empty list if the repository is intact.
+    pub problems: Vec<Problem>,
+}
+
+impl Report {
+    fn problem(&mut self, location: Location, subject: impl fmt::Display, message: impl fmt::Display) {
+        let problem = Problem {
+            location,
+            subject: subject.to_string(),
+            message: message.to_string(),
+        };
+        log::debug!(
+            "found problem location={} subject={} message={:?}",
+            problem.location,
+            problem.subject,
+            problem.message
+        );
+        self.problems.push(problem);
+    }
+}
+
+impl output::Record for Report {
+    fn write_human(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        writeln!(out, "loose objects: {}", self.num_loose_objects)?;
+        writeln!(out, "packs: {} ({} objects)", self.num_packs, self.num_packed_objects)?;
+        writeln!(out, "multi-pack-indices: {}", self.num_multi_pack_indices)?;
+        match self.num_commit_graph_commits {
+            Some(num_commits) => writeln!(out, "commit-graph: {} commits", num_commits)?,
+            None => writeln!(out, "commit-graph: none")?,
+        }
+        writeln!(out, "references: {}", self.num_references)?;
+        writeln!(out, "reachable objects: {}", self.num_reachable_objects)?;
+        writeln!(out, "problems: {}", self.problems.len())?;
+        for problem in &self.problems {
+            writeln!(out, "\t{} {}: {}", problem.location, problem.subject, problem.message)?;
+        }
+        Ok(())
+    }
+
+    fn fields(&self) -> Vec<(String, String)> {
+        use output::field;
+        let mut fields = vec![
+            field("num_loose_objects", self.num_loose_objects),
+            field("num_packs", self.num_packs),
+            field("num_packed_objects", self.num_packed_objects),
+            field("num_multi_pack_indices", self.num_multi_pack_indices),
+            field(
+                "num_commit_graph_commits",
+                self.num_commit_graph_commits
+                    .map_or_else(String::new, |num_commits| num_commits.to_string()),
+            ),
+            field("num_references", self.num_references),
+            field("num_reachable_objects", self.num_reachable_objects),
+            field("num_problems", self.problems.len()),
+        ];
+        for (idx, problem) in self.problems.iter().enumerate() {
+            fields.extend(vec![
+                field(format!("problems.{}.location", idx), problem.location),
+                field(format!("problems.{}.subject", idx), &problem.subject),
+                field(format!("problems.{}.message", idx), &problem.message),
+            ]);
+        }
+        fields
+    }
+}
+
+/// Verify the repository `repo` like `git fsck` and return a [`Report`] with all problems found.
+///
+/// Every loose object is hashed and decoded, every pack is verified against its index including the decoding of all
+/// of its objects, and multi-pack-indices and the commit-graph are checked for consistency with the packs and commits they
+/// describe. Finally all objects reachable from `HEAD` and all references are looked up to assure the repository is connected.
+/// Problems don't stop the verification, only I/O errors preventing it and interrupts via `should_interrupt` do.
+/// Without a `thread_limit`, the [thread limit of the repository][git_repository::Repository::thread_limit()] is used.
+pub fn verify(
+    repo: &git_repository::Repository,
+    thread_limit: Option<usize>,
+    mut progress: impl Progress,
+    should_interrupt: Arc<AtomicBool>,
+) -> anyhow::Result<Report> {
+    let _op = Operation::start(module_path!(), "repository-verify");
+    log::debug!("reading path={}", repo.git_dir().display());
+    let thread_limit = thread_limit.or(repo.thread_limit()?.map(|limit| limit.num_threads()));
+    let mut report = Report::default();
+    loose_objects(
+        repo,
+        &mut report,
+        progress.add_child("loose objects"),
+        &should_interrupt,
+    )?;
+    packs(
+        repo,
+        &mut report,
+        thread_limit,
+        progress.add_child("packs"),
+        &should_interrupt,
+    )?;
+    commit_graph(repo, &mut report, thread_limit);
+    connectivity(repo, &mut report, progress.add_child("connectivity"), &should_interrupt)?;
+    Ok(report)
+}
+
+/// Verify the repository at `repository` with [`verify()`] and write the [`Report`] to `out` in `format`.
+///
+/// Fails after writing the report if any problem was found.
+pub fn print(
+    repository: PathBuf,
+    thread_limit: Option<usize>,
+    progress: impl Progress,
+    should_interrupt: Arc<AtomicBool>,
+    format: OutputFormat,
+    out: impl io::Write,
+) -> anyhow::Result<()> {
+    let repo = git_repository::discover(repository)?;
+    let report = verify(&repo, thread_limit, progress, should_interrupt)?;
+    output::write_one(format, out, &report)?;
+    if !report.problems.is_empty() {
+        bail!("Found {} problem(s) in the repository", report.problems.len());
+    }
+    Ok(())
+}
+
+fn loose_objects(
+    repo: &git_repository::Repository,
+    report: &mut Report,
+    mut progress: impl Progress,
+    should_interrupt: &AtomicBool,
+) -> anyhow::Result<()> {
+    progress.init(None, progress::count("objects"));
+    let mut buf = Vec::new();
+    for db in &repo.odb.dbs {
+        for id in db.loose.iter() {
+            if should_interrupt.load(Ordering::Relaxed) {
+                bail!("Cancelled by user");
+            }
+            let id = match id {
+                Ok(id) => id,
+                Err(err) => {
+                    report.problem(Location::LooseObject, db.loose.path.display(), chain(err));
+                    continue;
+                }
+            };
+            report.num_loose_objects += 1;
+            progress.inc();
+            let object = match db.loose.try_find(id, &mut buf) {
+                Ok(Some(object)) => object,
+                Ok(None) => {
+                    report.problem(Location::LooseObject, id, "disappeared while verifying");
+                    continue;
+                }
+                Err(err) => {
+                    report.problem(Location::LooseObject, id, chain(err));
+                    continue;
+                }
+            };
+            let actual = odb::sink().write_buf(object.kind, object.data, hash::Kind::Sha1)?;
+            if actual != id {
+                report.problem(Location::LooseObject, id, format!("content hashes to {}", actual));
+            } else if object.kind != objs::Kind::Blob {
+                if let Err(err) = objs::ObjectRef::from_bytes(object.kind, object.data) {
+                    report.problem(Location::LooseObject, id, format!("invalid {}: {}", object.kind, err));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn packs(
+    repo: &git_repository::Repository,
+    report: &mut Report,
+    thread_limit: Option<usize>,
+    mut progress: impl Progress,
+    should_interrupt: &Arc<AtomicBool>,
+) -> anyhow::Result<()> {
+    for db in &repo.odb.dbs {
+        for bundle in &db.bundles {
+            report.num_packs += 1;
+            report.num_packed_objects += bundle.pack.num_objects() as u64;
+            if let Err(err) = bundle.verify_integrity(
+                pack::index::verify::Mode::Sha1Crc32Decode,
+                pack::index::traverse::Algorithm::DeltaTreeLookup,
+                || pack::cache::Never,
+                thread_limit,
+                Some(progress.add_child(bundle.index.path().display().to_string())),
+                Arc::clone(should_interrupt),
+            ) {
+                if should_interrupt.load(Ordering::Relaxed) {
+                    bail!("Cancelled by user");
+                }
+                report.problem(Location::Pack, bundle.index.path().display(), chain(err));
+            }
+        }
+
+        let path = db.loose.path.join("pack").join("multi-pack-index");
+        if path.is_file() {
+            report.num_multi_pack_indices += 1;
+            let res = pack::multi_index::File::at(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|file| {
+                    file.verify_integrity(progress.add_child("multi-pack-index"), true, should_interrupt)
+                        .map_err(anyhow::Error::from)
+                });
+            if let Err(err) = res {
+                if should_interrupt.load(Ordering::Relaxed) {
+                    bail!("Cancelled by user");
+                }
+                report.problem(Location::MultiPackIndex, path.display(), format!("{:#}", err));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn commit_graph(repo: &git_repository::Repository, report: &mut Report, thread_limit: Option<usize>) {
+    let info_dir = repo.objects_dir().join("info");
+    if !info_dir.join("commit-graph").is_file() && !info_dir.join("commit-graphs").is_dir() {
+        log::debug!("skipping commit-graph as there is none path={}", info_dir.display());
+        return;
+    }
+    let graph = match git_commitgraph::Graph::from_info_dir(&info_dir) {
+        Ok(graph) => graph,
+        Err(err) => return report.problem(Location::CommitGraph, info_dir.display(), chain(err)),
+    };
+    report.num_commit_graph_commits = Some(graph.num_commits());
+
+    #[allow(clippy::unnecessary_wraps, unknown_lints)]
+    fn noop_processor(_commit: &git_commitgraph::file::Commit<'_>) -> std::result::Result<(), std::fmt::Error> {
+        Ok(())
+    }
+    if let Err(err) = graph.verify_integrity(noop_processor) {
+        return report.problem(Location::CommitGraph, info_dir.display(), chain(err));
+    }
+    if let Err(err) = graph.verify_objects(
+        |id, buf| repo.odb.find_commit_iter(id, buf, &mut pack::cache::Never).ok(),
+        thread_limit,
+    ) {
+        report.problem(Location::CommitGraph, info_dir.display(), chain(err));
+    }
+}
+
+fn connectivity(
+    repo: &git_repository::Repository,
+    report: &mut Report,
+    mut progress: impl Progress,
+    should_interrupt: &AtomicBool,
+) -> anyhow::Result<()> {
+    let easy = repo.to_easy();
+    let mut names = vec![String::from("HEAD")];
+    for reference in easy.references()?.all()? {
+        match reference {
+            Ok(reference) => names.push(reference.name().as_bstr().to_string()),
+            Err(err) => report.problem(
+                Location::Reference,
+                repo.git_dir().display(),
+                format!("{:#}", anyhow!(err)),
+            ),
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+    for name in names {
+        let reference = match easy.try_find_reference(name.as_str()) {
+            Ok(Some(reference)) => reference,
+            Ok(None) => continue,
+            Err(err) => {
+                report.problem(Location::Reference, &name, format!("{:#}", anyhow!(err)));
+                continue;
+            }
+        };
+        report.num_references += 1;
+        if let git_repository::refs::TargetRef::Peeled(target) = reference.target() {
+            if seen.insert(target.to_owned()) {
+                queue.push_back((target.to_owned(), name));
+            }
+        }
+    }
+
+    progress.init(None, progress::count("objects"));
+    let mut buf = Vec::new();
+    while let Some((id, referenced_by)) = queue.pop_front() {
+        if should_interrupt.load(Ordering::Relaxed) {
+            bail!("Cancelled by user");
+        }
+        progress.inc();
+        let object = match repo.odb.try_find(id, &mut buf, &mut pack::cache::Never) {
+            Ok(Some(object)) => object,
+            Ok(None) => {
+                report.problem(
+                    Location::Object,
+                    id,
+                    format!("missing, referenced by {}", referenced_by),
+                );
+                continue;
+            }
+            Err(err) => {
+                report.problem(Location::Object, id, chain(err));
+                continue;
+            }
+        };
+        report.num_reachable_objects += 1;
+        match children(object.kind, object.data) {
+            Ok(ids) => {
+                for (child, is_blob) in ids {
+                    if !seen.insert(child) {
+                        continue;
+                    }
+                    if is_blob {
+                        if repo.odb.contains(child) {
+                            report.num_reachable_objects += 1;
+                        } else {
+                            report.problem(Location::Object, child, format!("missing, referenced by {}", id));
+                        }
+                    } else {
+                        queue.push_back((child, id.to_string()));
+                    }
+                }
+            }
+            Err(err) => report.problem(Location::Object, id, format!("invalid {}: {}", object.kind, err)),
+        }
+    }
+    Ok(())
+}
+
+/// Return the ids of all objects directly referred to by the object of `kind` with `data`, and whether they are known to be a
+/// blob as these don't have to be decoded. Submodule commits are skipped as they aren't part of this repository.
+fn children(kind: objs::Kind, data: &[u8]) -> Result<Vec<(ObjectId, bool)>, objs::decode::Error> {
+    Ok(match kind {
+        objs::Kind::Commit => {
+            let commit = objs::CommitRef::from_bytes(data)?;
+            std::iter::once(commit.tree())
+                .chain(commit.parents())
+                .map(|id| (id, false))
+                .collect()
+        }
+        objs::Kind::Tag => vec![(objs::TagRef::from_bytes(data)?.target(), false)],
+        objs::Kind::Tree => objs::TreeRef::from_bytes(data)?
+            .entries
+            .into_iter()
+            .filter(|entry| entry.mode != EntryMode::Commit)
+            .map(|entry| (entry.oid.to_owned(), entry.mode != EntryMode::Tree))
+            .collect(),
+        objs::Kind::Blob => Vec::new(),
+    })
+}
+
+/// Render `err` along with all of its sources.
+fn chain(err: impl std::error::Error + Send + Sync + 'static) -> String {
+    format!("{:#}", anyhow::Error::from(err))
+}
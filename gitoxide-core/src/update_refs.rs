@@ -0,0 +1,289 @@
+use std::{convert::TryFrom, io, path::PathBuf};
+
+use anyhow::{anyhow, bail, Context as AnyhowContext};
+use git_repository::{
+    bstr::ByteSlice,
+    hash::ObjectId,
+    lock,
+    prelude::*,
+    refs::{
+        file::{self, ReferenceExt},
+        transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+        FullName, Target,
+    },
+};
+
+use crate::diagnostics::Operation;
+
+/// Options for [`update_refs_from_stdin()`].
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+    /// If true, commands and their arguments are terminated by NUL bytes instead of newlines, like `git update-ref -z --stdin`.
+    pub nul_terminated: bool,
+    /// The message to write into the reference logs of all updated references.
+    pub message: Option<String>,
+}
+
+/// The state of the transaction while reading commands.
+enum State<'s> {
+    /// Edits are collected and will be committed once the input ends, as no `start` command was received.
+    Open,
+    /// A `start` command was received and edits are collected until `prepare`, `commit` or `abort`.
+    Started,
+    /// The edits were applied to the transaction which holds all locks, waiting for `commit` or `abort`.
+    Prepared(Box<file::Transaction<'s>>),
+    /// The previous transaction was committed or aborted, and a new one must be started to make more edits.
+    Closed,
+}
+
+/// A reference to check without changing it, and the id it must point to, or `None` if it must not exist.
+struct Verification {
+    name: FullName,
+    deref: bool,
+    expected: Option<ObjectId>,
+}
+
+/// Apply the commands read from `input` to the references of the repository at `repository` in a transaction, like
+/// `git update-ref --stdin` does, and write the replies to `start`, `prepare`, `commit` and `abort` to `out`.
+///
+/// The following commands are supported, each terminated by a newline, with arguments separated by spaces. Values are
+/// full object ids or reference names, optionally followed by `~<n>` and `^<n>`, and the null id stands for a reference
+/// that doesn't exist.
+///
+/// * `update <ref> <new> [<old>]` - set `<ref>` to `<new>`, after assuring it currently is `<old>` if given
+/// * `create <ref> <new>` - create `<ref>` pointing to `<new>`, which must not exist yet
+/// * `delete <ref> [<old>]` - delete `<ref>`, after assuring it currently is `<old>` if given
+/// * `verify <ref> [<old>]` - assure `<ref>` currently is `<old>`, or doesn't exist if `<old>` is missing, without changing it
+/// * `option no-deref` - change the reference itself instead of the one it points to with the next command
+/// * `start`, `prepare`, `commit` and `abort` - control the transaction explicitly
+///
+/// With [`Options::nul_terminated`], each command is terminated by a NUL byte instead, and each of its values follows in
+/// its own NUL-terminated field which is empty for missing values, like `update <ref>\0<new>\0<old>\0`.
+///
+/// Without `start`, all edits are committed once `input` ends. A transaction that was prepared but not committed is aborted
+/// when `input` ends, and all changes are rolled back if any command fails.
+pub fn update_refs_from_stdin(
+    repository: PathBuf,
+    options: Options,
+    mut input: impl io::BufRead,
+    mut out: impl io::Write,
+) -> anyhow::Result<()> {
+    let _op = Operation::start(module_path!(), "update-refs");
+    let repo = git_repository::discover(repository)?;
+    let easy = repo.to_easy();
+    let committer = easy.committer()?;
+    let log = LogChange {
+        message: options.message.map(Into::into).unwrap_or_default(),
+        ..Default::default()
+    };
+    let terminator = if options.nul_terminated { 0 } else { b'\n' };
+
+    let mut state = State::Open;
+    let mut edits = Vec::new();
+    let mut verifications = Vec::new();
+    let mut deref = true;
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if input.read_until(terminator, &mut line)? == 0 {
+            break;
+        }
+        if line.last() == Some(&terminator) {
+            line.pop();
+        }
+        let line = line.to_str().context("Commands must be valid UTF-8")?;
+        let mut tokens = line.split(' ');
+        let command = tokens.next().unwrap_or_default();
+        let name = tokens.next();
+        let mut args = Vec::new();
+        if options.nul_terminated {
+            let count = match command {
+                "update" => 2,
+                "create" | "delete" | "verify" => 1,
+                _ => 0,
+            };
+            for _ in 0..count {
+                args.push(read_field(&mut input)?);
+            }
+        } else {
+            args.extend(tokens.map(ToOwned::to_owned));
+        }
+        let value = |idx: usize| -> anyhow::Result<Option<ObjectId>> {
+            match args.get(idx).map(String::as_str) {
+                None | Some("") => Ok(None),
+                Some(revision) => crate::repository::resolve_revision(&easy, revision)
+                    .map(Some)
+                    .with_context(|| format!("Invalid value '{}' in command '{}'", revision, command)),
+            }
+        };
+
+        match command {
+            "update" | "create" | "delete" | "verify" => {
+                if let State::Prepared(_) | State::Closed = state {
+                    bail!("Command '{}' requires a started transaction", command)
+                }
+                let name =
+                    FullName::try_from(name.ok_or_else(|| anyhow!("Command '{}' requires a reference", command))?)?;
+                let max_args = if command == "update" { 2 } else { 1 };
+                if args.len() > max_args {
+                    bail!("Command '{}' has too many arguments", command)
+                }
+                let change = match command {
+                    "update" => {
+                        let new = value(0)?.ok_or_else(|| anyhow!("Command 'update' requires a new value"))?;
+                        let expected = match value(1)? {
+                            None => PreviousValue::Any,
+                            Some(old) if old.is_null() => PreviousValue::MustNotExist,
+                            Some(old) => PreviousValue::MustExistAndMatch(Target::Peeled(old)),
+                        };
+                        if new.is_null() {
+                            delete(expected, &name)?
+                        } else {
+                            Change::Update {
+                                log: log.clone(),
+                                expected,
+                                new: Target::Peeled(new),
+                            }
+                        }
+                    }
+                    "create" => match value(0)? {
+                        Some(new) if !new.is_null() => Change::Update {
+                            log: log.clone(),
+                            expected: PreviousValue::MustNotExist,
+                            new: Target::Peeled(new),
+                        },
+                        _ => bail!("Command 'create' requires a new value that isn't null"),
+                    },
+                    "delete" => match value(0)? {
+                        None => delete(PreviousValue::Any, &name)?,
+                        Some(old) if old.is_null() => delete(PreviousValue::MustNotExist, &name)?,
+                        Some(old) => delete(PreviousValue::MustExistAndMatch(Target::Peeled(old)), &name)?,
+                    },
+                    _ => {
+                        verifications.push(Verification {
+                            name,
+                            deref: std::mem::replace(&mut deref, true),
+                            expected: value(0)?.filter(|old| !old.is_null()),
+                        });
+                        continue;
+                    }
+                };
+                edits.push(RefEdit {
+                    change,
+                    name,
+                    deref: std::mem::replace(&mut deref, true),
+                });
+            }
+            "option" => match name {
+                Some("no-deref") => deref = false,
+                Some(option) => bail!("Option '{}' is unknown", option),
+                None => bail!("Command 'option' requires an option"),
+            },
+            "start" => {
+                match state {
+                    State::Open | State::Closed => state = State::Started,
+                    _ => bail!("Command 'start' is only allowed at the beginning or after 'commit' or 'abort'"),
+                }
+                writeln!(out, "start: ok")?;
+            }
+            "prepare" => {
+                state = match state {
+                    State::Open | State::Started => {
+                        State::Prepared(Box::new(prepare(&repo, &mut edits, &mut verifications)?))
+                    }
+                    _ => bail!("Command 'prepare' requires a started transaction"),
+                };
+                writeln!(out, "prepare: ok")?;
+            }
+            "commit" => {
+                let transaction = match std::mem::replace(&mut state, State::Closed) {
+                    State::Open | State::Started => prepare(&repo, &mut edits, &mut verifications)?,
+                    State::Prepared(transaction) => *transaction,
+                    State::Closed => bail!("Command 'commit' requires a started transaction"),
+                };
+                transaction.commit(&committer)?;
+                writeln!(out, "commit: ok")?;
+            }
+            "abort" => {
+                if let State::Closed = std::mem::replace(&mut state, State::Closed) {
+                    bail!("Command 'abort' requires a started transaction")
+                }
+                edits.clear();
+                verifications.clear();
+                writeln!(out, "abort: ok")?;
+            }
+            "" => bail!("Empty command in input"),
+            _ => bail!("Command '{}' is unknown", command),
+        }
+    }
+
+    if let State::Open | State::Started = state {
+        prepare(&repo, &mut edits, &mut verifications)?.commit(&committer)?;
+    }
+    Ok(())
+}
+
+/// Read the next NUL-terminated argument from `input`.
+fn read_field(input: &mut impl io::BufRead) -> anyhow::Result<String> {
+    let mut field = Vec::new();
+    input.read_until(0, &mut field)?;
+    if field.pop() != Some(0) {
+        bail!("Unexpected end of input while reading an argument")
+    }
+    Ok(String::from_utf8(field)?)
+}
+
+fn delete(expected: PreviousValue, name: &FullName) -> anyhow::Result<Change> {
+    if let PreviousValue::MustNotExist = expected {
+        bail!("Cannot delete '{}' with a null old value", name.as_bstr())
+    }
+    Ok(Change::Delete {
+        expected,
+        log: RefLog::AndReference,
+    })
+}
+
+/// Lock all references to change as described by `edits`, and check all `verifications` while the locks are held.
+fn prepare<'s>(
+    repo: &'s git_repository::Repository,
+    edits: &mut Vec<RefEdit>,
+    verifications: &mut Vec<Verification>,
+) -> anyhow::Result<file::Transaction<'s>> {
+    let refs = repo
+        .refs
+        .as_file()
+        .ok_or_else(|| anyhow!("References can only be updated in repositories on disk"))?;
+    let mut transaction = refs.transaction();
+    if let Some(hook) = git_repository::hook::reference_transaction(repo)? {
+        transaction = transaction.hook(hook);
+    }
+    let transaction = transaction.prepare(edits.drain(..), lock::acquire::Fail::Immediately)?;
+    let packed = refs.packed_buffer()?;
+    for Verification { name, deref, expected } in verifications.drain(..) {
+        let mut reference = refs.try_find(name.to_partial(), packed.as_ref())?;
+        if deref {
+            while let Some(next) = reference.as_ref().and_then(|r| r.follow(refs, packed.as_ref())) {
+                reference = Some(next?);
+            }
+        }
+        let actual = reference.map(|r| r.target);
+        match (expected, actual) {
+            (None, None) => {}
+            (Some(expected), Some(Target::Peeled(actual))) if expected == actual => {}
+            (expected, actual) => bail!(
+                "Reference '{}' is {} but was expected to {}",
+                name.as_bstr(),
+                match actual {
+                    Some(Target::Peeled(id)) => id.to_string(),
+                    Some(Target::Symbolic(name)) => format!("'{}'", name.as_bstr()),
+                    None => "missing".into(),
+                },
+                match expected {
+                    Some(id) => format!("be {}", id),
+                    None => "not exist".into(),
+                }
+            ),
+        }
+    }
+    Ok(transaction)
+}
@@ -1,5 +1,5 @@
 use std::{
-    io::{self, stderr, stdin, stdout},
+    io::{self, stdin, stdout},
     path::PathBuf,
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -32,23 +32,30 @@ pub fn main() -> Result<()> {
             repository,
             expansion,
             nondeterministic_count,
+            deterministic,
             statistics,
             tips,
             thin,
+            no_deltas,
             pack_cache_size_mb,
             object_cache_size_mb,
             output_directory,
+            stdout: pack_to_out,
+            input,
         }) => {
             let (_handle, progress) = prepare(verbose, "pack-create", Some(core::pack::create::PROGRESS_RANGE));
             let has_tips = !tips.is_empty();
-            let input = if has_tips {
-                None
-            } else {
-                #[cfg(feature = "atty")]
-                if atty::is(atty::Stream::Stdin) {
-                    anyhow::bail!("Refusing to read from standard input as no path is given, but it's a terminal.")
+            let input: Option<Box<dyn io::BufRead + Send>> = match input {
+                Some(_) if has_tips => anyhow::bail!("Objects can't be read from a file if tips are given"),
+                Some(path) => Some(Box::new(io::BufReader::new(std::fs::File::open(path)?))),
+                None if has_tips => None,
+                None => {
+                    #[cfg(feature = "atty")]
+                    if atty::is(atty::Stream::Stdin) {
+                        anyhow::bail!("Refusing to read from standard input as no path is given, but it's a terminal.")
+                    }
+                    Some(Box::new(io::BufReader::new(stdin())))
                 }
-                Some(io::BufReader::new(stdin()))
             };
             let expansion = expansion.unwrap_or_else(|| {
                 if has_tips {
@@ -66,7 +73,10 @@ pub fn main() -> Result<()> {
                 core::pack::create::Context {
                     expansion,
                     thin,
+                    deltas: !no_deltas,
+                    pack_to_out,
                     nondeterministic_count,
+                    deterministic,
                     pack_cache_size_in_bytes: pack_cache_size_mb.unwrap_or(0) * 1_000_000,
                     object_cache_size_in_bytes: object_cache_size_mb.unwrap_or(0) * 1_000_000,
                     statistics: if statistics { Some(OutputFormat::Human) } else { None },
@@ -94,6 +104,30 @@ pub fn main() -> Result<()> {
             return futures_lite::future::block_on(res);
         }
         #[cfg(any(feature = "gitoxide-core-async-client", feature = "gitoxide-core-blocking-client"))]
+        SubCommands::RemoteRefMap(options::RemoteRefMap {
+            protocol,
+            repository,
+            url,
+            refspecs,
+        }) => {
+            let (_handle, progress) = prepare(verbose, "remote-ref-map", Some(core::remote::ref_map::PROGRESS_RANGE));
+            let res = core::remote::ref_map::map(
+                protocol,
+                &url,
+                refspecs.into_iter().map(|s| s.into()).collect(),
+                repository,
+                DoOrDiscard::from(progress),
+                core::remote::ref_map::Context {
+                    format: OutputFormat::Human,
+                    out: io::stdout(),
+                },
+            );
+            #[cfg(feature = "gitoxide-core-blocking-client")]
+            return res;
+            #[cfg(feature = "gitoxide-core-async-client")]
+            return futures_lite::future::block_on(res);
+        }
+        #[cfg(any(feature = "gitoxide-core-async-client", feature = "gitoxide-core-blocking-client"))]
         SubCommands::PackReceive(options::PackReceive {
             protocol,
             url,
@@ -176,6 +210,8 @@ pub fn main() -> Result<()> {
         SubCommands::PackVerify(options::PackVerify {
             path,
             statistics,
+            largest_objects,
+            objects,
             algorithm,
             decode,
             re_encode,
@@ -191,36 +227,234 @@ pub fn main() -> Result<()> {
                     } else {
                         None
                     },
+                    output_objects: if objects { Some(core::OutputFormat::Human) } else { None },
                     algorithm: algorithm.unwrap_or(verify::Algorithm::LessTime),
                     thread_limit,
+                    num_largest_objects: largest_objects.unwrap_or(0),
                     mode: match (decode, re_encode) {
                         (true, false) => verify::Mode::Sha1Crc32Decode,
                         (true, true) | (false, true) => verify::Mode::Sha1Crc32DecodeEncode,
                         (false, false) => verify::Mode::Sha1Crc32,
                     },
                     out: stdout(),
-                    err: stderr(),
                     should_interrupt,
                 },
             )
             .map(|_| ())
         }
-        SubCommands::CommitGraphVerify(options::CommitGraphVerify { path, statistics }) => {
+        SubCommands::PackInspect(options::PackInspect { path }) => {
+            core::pack::inspect(path, OutputFormat::Human, stdout())
+        }
+        SubCommands::PackDiff(options::PackDiff { old, new }) => {
+            core::pack::diff(old, new, OutputFormat::Human, stdout()).map(|_| ())
+        }
+        SubCommands::CommitGraphVerify(options::CommitGraphVerify {
+            path,
+            statistics,
+            objects,
+        }) => {
             use self::core::commitgraph::verify;
 
             verify::graph_or_file(
                 path,
                 verify::Context {
-                    err: stderr(),
                     out: stdout(),
                     output_statistics: if statistics {
                         Some(core::OutputFormat::Human)
                     } else {
                         None
                     },
+                    verify_objects: objects,
+                    thread_limit,
                 },
             )
             .map(|_| ())
         }
+        SubCommands::CommitGraphWrite(options::CommitGraphWrite {
+            repository,
+            stdin_commits,
+        }) => {
+            let (_handle, progress) = prepare(
+                verbose,
+                "commit-graph-write",
+                Some(core::commitgraph::write::PROGRESS_RANGE),
+            );
+            core::commitgraph::write::graph(
+                repository.unwrap_or_else(|| PathBuf::from(".")),
+                stdin_commits.then(|| io::BufReader::new(stdin())),
+                DoOrDiscard::from(progress),
+                core::commitgraph::write::Context {
+                    format: OutputFormat::Human,
+                    out: stdout(),
+                },
+            )
+        }
+        SubCommands::RepositoryCat(options::RepositoryCat {
+            repository,
+            filters,
+            spec,
+        }) => core::repository::cat_at(
+            repository.unwrap_or_else(|| PathBuf::from(".")),
+            &spec,
+            filters,
+            stdout(),
+        ),
+        SubCommands::RepositoryCatObject(options::RepositoryCatObject {
+            repository,
+            kind,
+            size,
+            object,
+        }) => {
+            use core::repository::ObjectPart;
+            let part = match (kind, size) {
+                (true, true) => anyhow::bail!("Only one of --kind and --size can be given"),
+                (true, false) => ObjectPart::Kind,
+                (false, true) => ObjectPart::Size,
+                (false, false) => ObjectPart::Content,
+            };
+            core::repository::cat_object(
+                repository.unwrap_or_else(|| PathBuf::from(".")),
+                &object,
+                part,
+                stdout(),
+            )
+        }
+        SubCommands::RepositoryHashObject(options::RepositoryHashObject {
+            repository,
+            kind,
+            write,
+            path,
+        }) => {
+            let input: Box<dyn io::Read> = match path {
+                Some(path) => Box::new(std::fs::File::open(path)?),
+                None => {
+                    #[cfg(feature = "atty")]
+                    if atty::is(atty::Stream::Stdin) {
+                        anyhow::bail!("Refusing to read from standard input as no path is given, but it's a terminal.")
+                    }
+                    Box::new(stdin())
+                }
+            };
+            core::repository::hash_object(
+                repository.unwrap_or_else(|| PathBuf::from(".")),
+                input,
+                kind.unwrap_or(git_repository::objs::Kind::Blob),
+                write,
+                stdout(),
+            )
+        }
+        SubCommands::RepositoryGrep(options::RepositoryGrep {
+            repository,
+            revision,
+            ignore_case,
+            fixed_strings,
+            pattern,
+            paths,
+        }) => {
+            let (_handle, progress) = prepare(verbose, "repository-grep", None);
+            core::grep(
+                repository.unwrap_or_else(|| PathBuf::from(".")),
+                revision.as_deref(),
+                &pattern,
+                DoOrDiscard::from(progress),
+                core::grep::Context {
+                    ignore_case,
+                    fixed_strings,
+                    paths: paths.into_iter().map(Into::into).collect(),
+                    thread_limit,
+                    out: stdout(),
+                },
+            )
+            .map(|_| ())
+        }
+        SubCommands::RepositoryReachability(options::RepositoryReachability { repository, id }) => {
+            core::reachability_path(
+                repository.unwrap_or_else(|| PathBuf::from(".")),
+                id,
+                OutputFormat::Human,
+                stdout(),
+            )
+        }
+        SubCommands::RepositoryLargestObjects(options::RepositoryLargestObjects {
+            repository,
+            count,
+            order,
+        }) => {
+            let (_handle, progress) = prepare(verbose, "repository-largest-objects", None);
+            core::largest_objects(
+                repository.unwrap_or_else(|| PathBuf::from(".")),
+                count.unwrap_or(10),
+                order.unwrap_or_default(),
+                DoOrDiscard::from(progress),
+                OutputFormat::Human,
+                stdout(),
+            )
+        }
+        SubCommands::RepositoryOdbStats(options::RepositoryOdbStats { repository, largest }) => {
+            let (_handle, progress) = prepare(verbose, "repository-odb-stats", None);
+            core::odb_stats(
+                repository.unwrap_or_else(|| PathBuf::from(".")),
+                largest.unwrap_or(10),
+                DoOrDiscard::from(progress),
+                OutputFormat::Human,
+                stdout(),
+            )
+        }
+        SubCommands::RepositoryVerify(options::RepositoryVerify { repository }) => {
+            let (_handle, progress) = prepare(verbose, "repository-verify", None);
+            core::repository::verify::print(
+                repository.unwrap_or_else(|| PathBuf::from(".")),
+                thread_limit,
+                DoOrDiscard::from(progress),
+                should_interrupt,
+                OutputFormat::Human,
+                stdout(),
+            )
+        }
+        SubCommands::RepositoryLsTree(options::RepositoryLsTree {
+            repository,
+            recursive,
+            long,
+            name_only,
+            treeish,
+            paths,
+        }) => core::ls_tree(
+            repository.unwrap_or_else(|| PathBuf::from(".")),
+            &treeish,
+            core::ls_tree::Options {
+                recursive,
+                long,
+                name_only,
+                paths: paths.into_iter().map(Into::into).collect(),
+            },
+            OutputFormat::Human,
+            stdout(),
+        ),
+        SubCommands::RepositoryMergeTree(options::RepositoryMergeTree {
+            repository,
+            base,
+            ours,
+            theirs,
+        }) => core::merge_tree(
+            repository.unwrap_or_else(|| PathBuf::from(".")),
+            &base,
+            &ours,
+            &theirs,
+            OutputFormat::Human,
+            stdout(),
+        ),
+        SubCommands::RepositoryUpdateRefs(options::RepositoryUpdateRefs {
+            repository,
+            nul_terminated,
+            message,
+        }) => core::update_refs_from_stdin(
+            repository.unwrap_or_else(|| PathBuf::from(".")),
+            core::update_refs::Options {
+                nul_terminated,
+                message,
+            },
+            stdin().lock(),
+            stdout(),
+        ),
     }
 }
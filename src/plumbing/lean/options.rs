@@ -18,7 +18,8 @@ pub struct Args {
     #[argh(option, short = 't')]
     /// the amount of threads to use for some operations.
     ///
-    /// If unset, or the value is 0, there is no limit and all logical cores can be used.
+    /// If unset, or the value is 0, the amount configured in `gitoxide.threads` of the repository or in the GITOXIDE_THREADS
+    /// environment variable is used, which is a number, 'all' or 'soft' to leave one core free. By default, all logical cores can be used.
     pub threads: Option<usize>,
 
     #[argh(subcommand)]
@@ -30,13 +31,29 @@ pub struct Args {
 pub enum SubCommands {
     PackCreate(PackCreate),
     PackVerify(PackVerify),
+    PackInspect(PackInspect),
+    PackDiff(PackDiff),
     PackExplode(PackExplode),
     IndexFromPack(IndexFromPack),
     #[cfg(any(feature = "gitoxide-core-async-client", feature = "gitoxide-core-blocking-client"))]
     RemoteRefList(RemoteRefList),
     #[cfg(any(feature = "gitoxide-core-async-client", feature = "gitoxide-core-blocking-client"))]
+    RemoteRefMap(RemoteRefMap),
+    #[cfg(any(feature = "gitoxide-core-async-client", feature = "gitoxide-core-blocking-client"))]
     PackReceive(PackReceive),
     CommitGraphVerify(CommitGraphVerify),
+    CommitGraphWrite(CommitGraphWrite),
+    RepositoryCat(RepositoryCat),
+    RepositoryCatObject(RepositoryCatObject),
+    RepositoryHashObject(RepositoryHashObject),
+    RepositoryGrep(RepositoryGrep),
+    RepositoryReachability(RepositoryReachability),
+    RepositoryLargestObjects(RepositoryLargestObjects),
+    RepositoryOdbStats(RepositoryOdbStats),
+    RepositoryVerify(RepositoryVerify),
+    RepositoryLsTree(RepositoryLsTree),
+    RepositoryMergeTree(RepositoryMergeTree),
+    RepositoryUpdateRefs(RepositoryUpdateRefs),
 }
 
 /// Create an index from a packfile.
@@ -87,6 +104,35 @@ pub struct RemoteRefList {
     pub url: String,
 }
 
+/// Show which local references the given refspecs map the references of a remote identified by a url to.
+///
+/// This is similar to what `git fetch --dry-run` reports, without receiving any objects.
+/// Supported URLs are documented here: https://www.git-scm.com/docs/git-clone#_git_urls
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "remote-ref-map")]
+#[cfg(any(feature = "gitoxide-core-async-client", feature = "gitoxide-core-blocking-client"))]
+pub struct RemoteRefMap {
+    /// the protocol version to use. Valid values are 1 and 2
+    #[argh(option, short = 'p')]
+    pub protocol: Option<core::net::Protocol>,
+
+    /// the repository whose local references to compare with to learn how they would be updated.
+    ///
+    /// If unset, only the names of local references are shown.
+    #[argh(option, short = 'r')]
+    pub repository: Option<PathBuf>,
+
+    /// the URLs or path from which to receive references
+    ///
+    /// See here for a list of supported URLs: https://www.git-scm.com/docs/git-clone#_git_urls
+    #[argh(positional)]
+    pub url: String,
+
+    /// the refspecs to map remote references with, like `+refs/heads/*:refs/remotes/origin/*`.
+    #[argh(positional)]
+    pub refspecs: Vec<String>,
+}
+
 /// Receive a pack from a remote identified by a url.
 ///
 /// This is the plumbing equivalent of `git clone` and `git-fetch`.
@@ -189,6 +235,13 @@ pub struct PackCreate {
     /// way the resulting pack is structured.
     pub nondeterministic_count: bool,
 
+    #[argh(switch)]
+    /// if set, the pack will be byte-identical for the same objects and repository, making it a reproducible artifact.
+    ///
+    /// Objects are written in an order which doesn't depend on how they were counted, so this works well together
+    /// with --nondeterministic-count.
+    pub deterministic: bool,
+
     #[argh(switch, short = 's')]
     /// if set statistical information will be presented to inform about pack creation details.
     /// It's a form of instrumentation for developers to help improve pack generation.
@@ -218,14 +271,26 @@ pub struct PackCreate {
     /// Packs produced with this option enabled are only valid in transit, but not at rest.
     pub thin: bool,
 
+    #[argh(switch)]
+    /// if set, all objects are written as base objects instead of copying deltas from existing packs.
+    pub no_deltas: bool,
+
     /// the directory into which to write the pack file.
     #[argh(option, short = 'o')]
     pub output_directory: Option<PathBuf>,
 
+    #[argh(switch)]
+    /// if set, the pack is written to stdout instead of into the output directory.
+    pub stdout: bool,
+
+    /// a file to read the ids of objects to put into the pack from, one per line, instead of stdin.
+    #[argh(option, short = 'i')]
+    pub input: Option<PathBuf>,
+
     /// the tips from which to start the commit graph iteration, either as fully qualified commit hashes
     /// or as branch names.
     ///
-    /// If empty, we expect to read objects on stdin and default to 'none' as expansion mode.
+    /// If empty, we expect to read objects from the input file or stdin and default to 'none' as expansion mode.
     /// Otherwise the expansion mode is 'tree-traversal' by default.
     #[argh(positional)]
     pub tips: Vec<OsString>,
@@ -260,11 +325,40 @@ pub struct PackVerify {
     /// output statistical information about the pack
     #[argh(switch, short = 's')]
     pub statistics: bool,
-    /// the '.pack' or '.idx' file whose checksum to validate.
+    #[argh(option, short = 'n')]
+    /// the amount of largest objects by decompressed size to list in the statistics, along with their kind and delta chain length.
+    ///
+    /// Default is 0.
+    pub largest_objects: Option<usize>,
+    /// write the id, offset, kind, sizes and CRC32 of each object as soon as it was verified.
+    #[argh(switch)]
+    pub objects: bool,
+    /// the '.pack' or '.idx' file whose checksum to validate, or a 'multi-pack-index' to validate along with the indices of its packs.
+    #[argh(positional)]
+    pub path: PathBuf,
+}
+
+/// Print all entries of a pack along with their delta base and chain depth, like `git verify-pack -v`
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "pack-inspect")]
+pub struct PackInspect {
+    /// the '.pack' or '.idx' file to inspect. Object ids are only shown if the index exists.
     #[argh(positional)]
     pub path: PathBuf,
 }
 
+/// Print objects which are only in one of two packs or stored with a different kind or delta base, along with their sizes
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "pack-diff")]
+pub struct PackDiff {
+    /// the '.pack' or '.idx' file of the first pack. Both packs must have an index.
+    #[argh(positional)]
+    pub old: PathBuf,
+    /// the '.pack' or '.idx' file of the second pack.
+    #[argh(positional)]
+    pub new: PathBuf,
+}
+
 /// Verify a commit graph
 #[derive(FromArgs, PartialEq, Debug)]
 #[argh(subcommand, name = "commit-graph-verify")]
@@ -276,4 +370,263 @@ pub struct CommitGraphVerify {
     /// output statistical information about the pack
     #[argh(switch, short = 's')]
     pub statistics: bool,
+
+    /// also verify the root tree, parents and commit date of each commit against its object in the object database.
+    ///
+    /// The object database is expected to be the parent directory of the 'info' directory containing the graph.
+    #[argh(switch, short = 'o')]
+    pub objects: bool,
+}
+
+/// Write a commit graph with all commits reachable from all references, like `git commit-graph write --reachable`
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "commit-graph-write")]
+pub struct CommitGraphWrite {
+    #[argh(option, short = 'r')]
+    /// the directory containing the '.git' repository, or any directory within it.
+    ///
+    /// Defaults to the current working directory.
+    pub repository: Option<PathBuf>,
+
+    /// read the ids of the commits to start from on standard input, one per line, instead of using all references.
+    #[argh(switch)]
+    pub stdin_commits: bool,
+}
+
+/// Print the content of a file at a revision, like `git show <revision>:<path>`
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "repository-cat")]
+pub struct RepositoryCat {
+    #[argh(option, short = 'r')]
+    /// the directory containing the '.git' repository, or any directory within it.
+    ///
+    /// Defaults to the current working directory.
+    pub repository: Option<PathBuf>,
+
+    /// convert the file as it would be when checked out, like the line endings configured by 'core.autocrlf'.
+    #[argh(switch)]
+    pub filters: bool,
+
+    /// the file to print as '<revision>:<path>', like 'main:README.md' or 'HEAD~1:src/lib.rs'.
+    #[argh(positional)]
+    pub spec: String,
+}
+
+/// Print the content, kind or size of an object given by its id or a unique prefix of it, like 'git cat-file'
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "repository-cat-object")]
+pub struct RepositoryCatObject {
+    #[argh(option, short = 'r')]
+    /// the directory containing the '.git' repository, or any directory within it.
+    ///
+    /// Defaults to the current working directory.
+    pub repository: Option<PathBuf>,
+
+    /// print the kind of the object instead of its content.
+    #[argh(switch, short = 't')]
+    pub kind: bool,
+
+    /// print the size of the object's content in bytes instead of the content itself.
+    #[argh(switch, short = 's')]
+    pub size: bool,
+
+    /// the id of the object, or a prefix of at least 4 hexadecimal characters which matches a single object.
+    #[argh(positional)]
+    pub object: String,
+}
+
+/// Compute the id of an object read from a file or standard input and optionally write it into the object database,
+/// like 'git hash-object'
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "repository-hash-object")]
+pub struct RepositoryHashObject {
+    #[argh(option, short = 'r')]
+    /// the directory containing the '.git' repository, or any directory within it.
+    ///
+    /// Defaults to the current working directory. It's only accessed if the object is written.
+    pub repository: Option<PathBuf>,
+
+    #[argh(option, short = 't')]
+    /// the kind of object to create, one of "blob", "tree", "commit" or "tag". Default is "blob".
+    pub kind: Option<git_repository::objs::Kind>,
+
+    /// write the object into the object database.
+    #[argh(switch, short = 'w')]
+    pub write: bool,
+
+    /// the file to read the object's content from.
+    ///
+    /// If unset, the content is read from standard input.
+    #[argh(positional)]
+    pub path: Option<PathBuf>,
+}
+
+/// Search for lines matching a pattern in all files at a revision or in the work tree, like `git grep`
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "repository-grep")]
+pub struct RepositoryGrep {
+    #[argh(option, short = 'r')]
+    /// the directory containing the '.git' repository, or any directory within it.
+    ///
+    /// Defaults to the current working directory.
+    pub repository: Option<PathBuf>,
+
+    #[argh(option)]
+    /// the revision whose files to search, like 'main' or 'HEAD~1'.
+    ///
+    /// If unset, files in the work tree are searched.
+    pub revision: Option<String>,
+
+    #[argh(switch, short = 'i')]
+    /// match the pattern case-insensitively.
+    pub ignore_case: bool,
+
+    #[argh(switch, short = 'F')]
+    /// interpret the pattern as literal string instead of as regular expression.
+    pub fixed_strings: bool,
+
+    /// the regular expression to match each line against.
+    #[argh(positional)]
+    pub pattern: String,
+
+    /// slash-separated directories or files to limit the search to.
+    #[argh(positional)]
+    pub paths: Vec<String>,
+}
+
+/// Print the path from a reference or reflog entry to an object to learn why it's retained
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "repository-reachability")]
+pub struct RepositoryReachability {
+    #[argh(option, short = 'r')]
+    /// the directory containing the '.git' repository, or any directory within it.
+    ///
+    /// Defaults to the current working directory.
+    pub repository: Option<PathBuf>,
+
+    /// the full hexadecimal id of the object whose reachability to explain.
+    #[argh(positional)]
+    pub id: git_repository::hash::ObjectId,
+}
+
+/// Print the largest objects in packs and loose storage along with the path they were first seen at
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "repository-largest-objects")]
+pub struct RepositoryLargestObjects {
+    #[argh(option, short = 'r')]
+    /// the directory containing the '.git' repository, or any directory within it.
+    ///
+    /// Defaults to the current working directory.
+    pub repository: Option<PathBuf>,
+
+    #[argh(option, short = 'n')]
+    /// the amount of objects to print. Default is 10.
+    pub count: Option<usize>,
+
+    #[argh(option, short = 'o')]
+    /// the size to rank objects by.
+    ///
+    /// Possible values are "size" and "disk-size". Default is "size".
+    pub order: Option<core::largest_objects::Order>,
+}
+
+/// Print statistics about the object database, like the amount and size of loose objects and packs.
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "repository-odb-stats")]
+pub struct RepositoryOdbStats {
+    #[argh(option, short = 'r')]
+    /// the directory containing the '.git' repository, or any directory within it.
+    ///
+    /// Defaults to the current working directory.
+    pub repository: Option<PathBuf>,
+
+    #[argh(option, short = 'n')]
+    /// the amount of largest objects by size on disk to print, or 0 to skip finding them. Default is 10.
+    pub largest: Option<usize>,
+}
+
+/// Verify all loose objects, packs, the commit-graph and the connectivity of all references, like 'git fsck'.
+///
+/// All problems are listed and the command fails if there was at least one.
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "repository-verify")]
+pub struct RepositoryVerify {
+    #[argh(option, short = 'r')]
+    /// the directory containing the '.git' repository, or any directory within it.
+    ///
+    /// Defaults to the current working directory.
+    pub repository: Option<PathBuf>,
+}
+
+/// List the entries of a tree, like 'git ls-tree'
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "repository-ls-tree")]
+pub struct RepositoryLsTree {
+    #[argh(option, short = 'r')]
+    /// the directory containing the '.git' repository, or any directory within it.
+    ///
+    /// Defaults to the current working directory.
+    pub repository: Option<PathBuf>,
+
+    #[argh(switch, short = 'R')]
+    /// list the entries of all trees recursively instead of the trees themselves.
+    pub recursive: bool,
+
+    #[argh(switch, short = 'l')]
+    /// print the size of blobs.
+    pub long: bool,
+
+    #[argh(switch)]
+    /// print only the path of each entry.
+    pub name_only: bool,
+
+    /// the revision of the tree to list, like 'main', 'HEAD~1' or the id of a tree.
+    #[argh(positional)]
+    pub treeish: String,
+
+    /// slash-separated paths to limit the listing to.
+    #[argh(positional)]
+    pub paths: Vec<String>,
+}
+
+/// Merge two trees with their merge base without touching the index, and print the merged tree and all conflicts.
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "repository-merge-tree")]
+pub struct RepositoryMergeTree {
+    #[argh(option, short = 'r')]
+    /// the directory containing the '.git' repository, or any directory within it.
+    ///
+    /// Defaults to the current working directory.
+    pub repository: Option<PathBuf>,
+
+    /// the revision of the merge base, like 'main~1' or the id of a tree.
+    #[argh(positional)]
+    pub base: String,
+
+    /// the revision of our side of the merge.
+    #[argh(positional)]
+    pub ours: String,
+
+    /// the revision of their side of the merge.
+    #[argh(positional)]
+    pub theirs: String,
+}
+
+/// Update references in a transaction with commands read from stdin, like 'git update-ref --stdin'
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "repository-update-refs")]
+pub struct RepositoryUpdateRefs {
+    #[argh(option, short = 'r')]
+    /// the directory containing the '.git' repository, or any directory within it.
+    ///
+    /// Defaults to the current working directory.
+    pub repository: Option<PathBuf>,
+
+    #[argh(switch, short = 'z')]
+    /// read commands and their arguments terminated by NUL bytes instead of newlines.
+    pub nul_terminated: bool,
+
+    #[argh(option, short = 'm')]
+    /// the message to write into the reference logs of all updated references.
+    pub message: Option<String>,
 }
@@ -1,5 +1,5 @@
 use std::{
-    io::{stdin, BufReader},
+    io::{stdin, BufRead, BufReader},
     path::PathBuf,
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -14,7 +14,7 @@ use gitoxide_core::pack::verify;
 
 use crate::{
     plumbing::pretty::options::{Args, Subcommands},
-    shared::pretty::prepare_and_run,
+    shared::pretty::{prepare_and_run, LineRenderer},
 };
 
 pub fn main() -> Result<()> {
@@ -23,9 +23,12 @@ pub fn main() -> Result<()> {
         verbose,
         progress,
         progress_keep_open,
+        progress_json,
+        progress_log,
         format,
         cmd,
     } = Args::parse();
+    let line_renderer = LineRenderer::from_flags(progress_json, progress_log);
     let should_interrupt = Arc::new(AtomicBool::new(false));
     git_repository::interrupt::init_handler({
         let should_interrupt = Arc::clone(&should_interrupt);
@@ -39,10 +42,14 @@ pub fn main() -> Result<()> {
             thin,
             statistics,
             nondeterministic_count,
+            deterministic,
             tips,
             pack_cache_size_mb,
             object_cache_size_mb,
             output_directory,
+            no_deltas,
+            stdout: pack_to_out,
+            input,
         } => {
             let has_tips = !tips.is_empty();
             prepare_and_run(
@@ -50,23 +57,30 @@ pub fn main() -> Result<()> {
                 verbose,
                 progress,
                 progress_keep_open,
+                line_renderer,
                 core::pack::create::PROGRESS_RANGE,
                 move |progress, out, _err| {
-                    let input = if has_tips {
-                        None
-                    } else {
-                        if atty::is(atty::Stream::Stdin) {
-                            anyhow::bail!(
-                                "Refusing to read from standard input as no path is given, but it's a terminal."
-                            )
+                    let input: Option<Box<dyn BufRead + Send>> = match input {
+                        Some(_) if has_tips => anyhow::bail!("Objects can't be read from a file if tips are given"),
+                        Some(path) => Some(Box::new(BufReader::new(std::fs::File::open(path)?))),
+                        None if has_tips => None,
+                        None => {
+                            if atty::is(atty::Stream::Stdin) {
+                                anyhow::bail!(
+                                    "Refusing to read from standard input as no path is given, but it's a terminal."
+                                )
+                            }
+                            Some(Box::new(BufReader::new(stdin())))
                         }
-                        Some(BufReader::new(stdin()))
                     };
                     let repository = repository.unwrap_or_else(|| PathBuf::from("."));
                     let context = core::pack::create::Context {
                         thread_limit,
                         thin,
+                        deltas: !no_deltas,
+                        pack_to_out,
                         nondeterministic_count,
+                        deterministic,
                         pack_cache_size_in_bytes: pack_cache_size_mb.unwrap_or(0) * 1_000_000,
                         object_cache_size_in_bytes: object_cache_size_mb.unwrap_or(0) * 1_000_000,
                         statistics: if statistics { Some(format) } else { None },
@@ -96,6 +110,7 @@ pub fn main() -> Result<()> {
             verbose,
             progress,
             progress_keep_open,
+            line_renderer,
             core::pack::receive::PROGRESS_RANGE,
             move |progress, out, _err| {
                 core::pack::receive(
@@ -120,6 +135,7 @@ pub fn main() -> Result<()> {
             verbose,
             progress,
             progress_keep_open,
+            line_renderer,
             core::remote::refs::PROGRESS_RANGE,
             move |progress, out, _err| {
                 core::remote::refs::list(
@@ -134,6 +150,30 @@ pub fn main() -> Result<()> {
                 )
             },
         ),
+        #[cfg(any(feature = "gitoxide-core-async-client", feature = "gitoxide-core-blocking-client"))]
+        Subcommands::RemoteRefMap {
+            protocol,
+            repository,
+            url,
+            refspecs,
+        } => prepare_and_run(
+            "remote-ref-map",
+            verbose,
+            progress,
+            progress_keep_open,
+            line_renderer,
+            core::remote::ref_map::PROGRESS_RANGE,
+            move |progress, out, _err| {
+                core::remote::ref_map::map(
+                    protocol,
+                    &url,
+                    refspecs.into_iter().map(|s| s.into()).collect(),
+                    repository,
+                    git_features::progress::DoOrDiscard::from(progress),
+                    core::remote::ref_map::Context { format, out },
+                )
+            },
+        ),
         Subcommands::PackIndexFromData {
             iteration_mode,
             pack_path,
@@ -143,6 +183,7 @@ pub fn main() -> Result<()> {
             verbose,
             progress,
             progress_keep_open,
+            line_renderer,
             core::pack::index::PROGRESS_RANGE,
             move |progress, out, _err| {
                 use gitoxide_core::pack::index::PathOrRead;
@@ -180,6 +221,7 @@ pub fn main() -> Result<()> {
             verbose,
             progress,
             progress_keep_open,
+            line_renderer,
             None,
             move |progress, _out, _err| {
                 core::pack::explode::pack_or_pack_index(
@@ -203,27 +245,32 @@ pub fn main() -> Result<()> {
             decode,
             re_encode,
             statistics,
+            largest_objects,
+            objects,
         } => prepare_and_run(
             "pack-verify",
             verbose,
             progress,
             progress_keep_open,
+            line_renderer,
             None,
-            move |progress, out, err| {
+            move |progress, out, _err| {
                 let mode = match (decode, re_encode) {
                     (true, false) => verify::Mode::Sha1Crc32Decode,
                     (true, true) | (false, true) => verify::Mode::Sha1Crc32DecodeEncode,
                     (false, false) => verify::Mode::Sha1Crc32,
                 };
                 let output_statistics = if statistics { Some(format) } else { None };
+                let output_objects = if objects { Some(format) } else { None };
                 verify::pack_or_pack_index(
                     path,
                     progress,
                     verify::Context {
                         output_statistics,
+                        output_objects,
                         out,
-                        err,
                         thread_limit,
+                        num_largest_objects: largest_objects,
                         mode,
                         algorithm,
                         should_interrupt,
@@ -232,25 +279,317 @@ pub fn main() -> Result<()> {
             },
         )
         .map(|_| ()),
-        Subcommands::CommitGraphVerify { path, statistics } => prepare_and_run(
+        Subcommands::PackInspect { path } => prepare_and_run(
+            "pack-inspect",
+            verbose,
+            progress,
+            progress_keep_open,
+            line_renderer,
+            None,
+            move |_progress, out, _err| core::pack::inspect(path, format, out),
+        ),
+        Subcommands::PackDiff { old, new } => prepare_and_run(
+            "pack-diff",
+            verbose,
+            progress,
+            progress_keep_open,
+            line_renderer,
+            None,
+            move |_progress, out, _err| core::pack::diff(old, new, format, out).map(|_| ()),
+        ),
+        Subcommands::CommitGraphVerify {
+            path,
+            statistics,
+            objects,
+        } => prepare_and_run(
             "commit-graph-verify",
             verbose,
             progress,
             progress_keep_open,
+            line_renderer,
             None,
-            move |_progress, out, err| {
+            move |_progress, out, _err| {
                 let output_statistics = if statistics { Some(format) } else { None };
                 core::commitgraph::verify::graph_or_file(
                     path,
                     core::commitgraph::verify::Context {
-                        err,
                         out,
                         output_statistics,
+                        verify_objects: objects,
+                        thread_limit,
                     },
                 )
             },
         )
         .map(|_| ()),
+        Subcommands::CommitGraphWrite {
+            repository,
+            stdin_commits,
+        } => prepare_and_run(
+            "commit-graph-write",
+            verbose,
+            progress,
+            progress_keep_open,
+            line_renderer,
+            core::commitgraph::write::PROGRESS_RANGE,
+            move |progress, out, _err| {
+                core::commitgraph::write::graph(
+                    repository.unwrap_or_else(|| PathBuf::from(".")),
+                    stdin_commits.then(|| BufReader::new(stdin())),
+                    git_features::progress::DoOrDiscard::from(progress),
+                    core::commitgraph::write::Context { format, out },
+                )
+            },
+        ),
+        Subcommands::RepositoryCat {
+            repository,
+            filters,
+            spec,
+        } => prepare_and_run(
+            "repository-cat",
+            verbose,
+            progress,
+            progress_keep_open,
+            line_renderer,
+            None,
+            move |_progress, out, _err| {
+                core::repository::cat_at(repository.unwrap_or_else(|| PathBuf::from(".")), &spec, filters, out)
+            },
+        ),
+        Subcommands::RepositoryCatObject {
+            repository,
+            kind,
+            size,
+            object,
+        } => prepare_and_run(
+            "repository-cat-object",
+            verbose,
+            progress,
+            progress_keep_open,
+            line_renderer,
+            None,
+            move |_progress, out, _err| {
+                core::repository::cat_object(
+                    repository.unwrap_or_else(|| PathBuf::from(".")),
+                    &object,
+                    if kind {
+                        core::repository::ObjectPart::Kind
+                    } else if size {
+                        core::repository::ObjectPart::Size
+                    } else {
+                        core::repository::ObjectPart::Content
+                    },
+                    out,
+                )
+            },
+        ),
+        Subcommands::RepositoryHashObject {
+            repository,
+            kind,
+            write,
+            path,
+        } => prepare_and_run(
+            "repository-hash-object",
+            verbose,
+            progress,
+            progress_keep_open,
+            line_renderer,
+            None,
+            move |_progress, out, _err| {
+                let input: Box<dyn std::io::Read + Send> = match path {
+                    Some(path) => Box::new(std::fs::File::open(path)?),
+                    None => {
+                        if atty::is(atty::Stream::Stdin) {
+                            anyhow::bail!(
+                                "Refusing to read from standard input as no path is given, but it's a terminal."
+                            )
+                        }
+                        Box::new(stdin())
+                    }
+                };
+                core::repository::hash_object(
+                    repository.unwrap_or_else(|| PathBuf::from(".")),
+                    input,
+                    kind,
+                    write,
+                    out,
+                )
+            },
+        ),
+        Subcommands::RepositoryGrep {
+            repository,
+            revision,
+            ignore_case,
+            fixed_strings,
+            pattern,
+            paths,
+        } => prepare_and_run(
+            "repository-grep",
+            verbose,
+            progress,
+            progress_keep_open,
+            line_renderer,
+            None,
+            move |progress, out, _err| {
+                core::grep(
+                    repository.unwrap_or_else(|| PathBuf::from(".")),
+                    revision.as_deref(),
+                    &pattern,
+                    git_features::progress::DoOrDiscard::from(progress),
+                    core::grep::Context {
+                        ignore_case,
+                        fixed_strings,
+                        paths: paths.into_iter().map(Into::into).collect(),
+                        thread_limit,
+                        out,
+                    },
+                )
+                .map(|_| ())
+            },
+        ),
+        Subcommands::RepositoryReachability { repository, id } => prepare_and_run(
+            "repository-reachability",
+            verbose,
+            progress,
+            progress_keep_open,
+            line_renderer,
+            None,
+            move |_progress, out, _err| {
+                core::reachability_path(repository.unwrap_or_else(|| PathBuf::from(".")), id, format, out)
+            },
+        ),
+        Subcommands::RepositoryLargestObjects {
+            repository,
+            count,
+            order,
+        } => prepare_and_run(
+            "repository-largest-objects",
+            verbose,
+            progress,
+            progress_keep_open,
+            line_renderer,
+            None,
+            move |progress, out, _err| {
+                core::largest_objects(
+                    repository.unwrap_or_else(|| PathBuf::from(".")),
+                    count,
+                    order,
+                    git_features::progress::DoOrDiscard::from(progress),
+                    format,
+                    out,
+                )
+            },
+        ),
+        Subcommands::RepositoryOdbStats { repository, largest } => prepare_and_run(
+            "repository-odb-stats",
+            verbose,
+            progress,
+            progress_keep_open,
+            line_renderer,
+            None,
+            move |progress, out, _err| {
+                core::odb_stats(
+                    repository.unwrap_or_else(|| PathBuf::from(".")),
+                    largest,
+                    git_features::progress::DoOrDiscard::from(progress),
+                    format,
+                    out,
+                )
+            },
+        ),
+        Subcommands::RepositoryVerify { repository } => prepare_and_run(
+            "repository-verify",
+            verbose,
+            progress,
+            progress_keep_open,
+            line_renderer,
+            None,
+            move |progress, out, _err| {
+                core::repository::verify::print(
+                    repository.unwrap_or_else(|| PathBuf::from(".")),
+                    thread_limit,
+                    git_features::progress::DoOrDiscard::from(progress),
+                    should_interrupt,
+                    format,
+                    out,
+                )
+            },
+        ),
+        Subcommands::RepositoryLsTree {
+            repository,
+            recursive,
+            long,
+            name_only,
+            treeish,
+            paths,
+        } => prepare_and_run(
+            "repository-ls-tree",
+            verbose,
+            progress,
+            progress_keep_open,
+            line_renderer,
+            None,
+            move |_progress, out, _err| {
+                core::ls_tree(
+                    repository.unwrap_or_else(|| PathBuf::from(".")),
+                    &treeish,
+                    core::ls_tree::Options {
+                        recursive,
+                        long,
+                        name_only,
+                        paths: paths.into_iter().map(Into::into).collect(),
+                    },
+                    format,
+                    out,
+                )
+            },
+        ),
+        Subcommands::RepositoryMergeTree {
+            repository,
+            base,
+            ours,
+            theirs,
+        } => prepare_and_run(
+            "repository-merge-tree",
+            verbose,
+            progress,
+            progress_keep_open,
+            line_renderer,
+            None,
+            move |_progress, out, _err| {
+                core::merge_tree(
+                    repository.unwrap_or_else(|| PathBuf::from(".")),
+                    &base,
+                    &ours,
+                    &theirs,
+                    format,
+                    out,
+                )
+            },
+        ),
+        Subcommands::RepositoryUpdateRefs {
+            repository,
+            nul_terminated,
+            message,
+        } => prepare_and_run(
+            "repository-update-refs",
+            verbose,
+            progress,
+            progress_keep_open,
+            line_renderer,
+            None,
+            move |_progress, out, _err| {
+                core::update_refs_from_stdin(
+                    repository.unwrap_or_else(|| PathBuf::from(".")),
+                    core::update_refs::Options {
+                        nul_terminated,
+                        message,
+                    },
+                    stdin().lock(),
+                    out,
+                )
+            },
+        ),
     }?;
     Ok(())
 }
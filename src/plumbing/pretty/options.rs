@@ -10,7 +10,8 @@ pub struct Args {
     #[clap(long, short = 't')]
     /// The amount of threads to use for some operations.
     ///
-    /// If unset, or the value is 0, there is no limit and all logical cores can be used.
+    /// If unset, or the value is 0, the amount configured in `gitoxide.threads` of the repository or in the GITOXIDE_THREADS
+    /// environment variable is used, which is a number, 'all' or 'soft' to leave one core free. By default, all logical cores can be used.
     pub threads: Option<usize>,
 
     /// Display verbose messages and progress information
@@ -27,7 +28,16 @@ pub struct Args {
     #[clap(long, conflicts_with("verbose"), requires("progress"))]
     pub progress_keep_open: bool,
 
-    /// Determine the format to use when outputting statistics.
+    /// Write snapshots of the progress tree as JSON object per line to stderr, for consumption by other programs.
+    #[clap(long, conflicts_with_all(&["verbose", "progress"]))]
+    pub progress_json: bool,
+
+    /// Periodically write the progress of all tasks as plain text lines to stderr, suitable for CI logs and other outputs
+    /// which aren't terminals.
+    #[clap(long, conflicts_with_all(&["verbose", "progress", "progress-json"]))]
+    pub progress_log: bool,
+
+    /// Determine the format to use when outputting statistics and other results, like `json-lines` or `nul` for scripting.
     #[clap(
         long,
         short = 'f',
@@ -61,6 +71,13 @@ pub enum Subcommands {
         /// way the resulting pack is structured.
         nondeterministic_count: bool,
 
+        #[clap(long)]
+        /// if set, the pack will be byte-identical for the same objects and repository, making it a reproducible artifact.
+        ///
+        /// Objects are written in an order which doesn't depend on how they were counted, so this works well together
+        /// with --nondeterministic-count.
+        deterministic: bool,
+
         #[clap(long, short = 's')]
         /// If set statistical information will be presented to inform about pack creation details.
         /// It's a form of instrumentation for developers to help improve pack generation.
@@ -90,14 +107,28 @@ pub enum Subcommands {
         /// Packs produced with this option enabled are only valid in transit, but not at rest.
         thin: bool,
 
+        #[clap(long)]
+        /// if set, all objects are written as base objects instead of copying deltas from existing packs.
+        ///
+        /// This yields larger packs, but each of their objects can be read without resolving deltas.
+        no_deltas: bool,
+
         /// The directory into which to write the pack file.
         #[clap(long, short = 'o')]
         output_directory: Option<PathBuf>,
 
+        /// if set, the pack is written to stdout instead of into the output directory.
+        #[clap(long, conflicts_with_all(&["output-directory", "statistics"]))]
+        stdout: bool,
+
+        /// A file to read the ids of objects to put into the pack from, one per line, instead of stdin.
+        #[clap(long, short = 'i')]
+        input: Option<PathBuf>,
+
         /// The tips from which to start the commit graph iteration, either as fully qualified commit hashes
         /// or as branch names.
         ///
-        /// If empty, we expect to read objects on stdin and default to 'none' as expansion mode.
+        /// If empty, we expect to read objects from the input file or stdin and default to 'none' as expansion mode.
         /// Otherwise the expansion mode is 'tree-traversal' by default.
         tips: Vec<OsString>,
     },
@@ -146,6 +177,32 @@ pub enum Subcommands {
         /// See here for a list of supported URLs: <https://www.git-scm.com/docs/git-clone#_git_urls>
         url: String,
     },
+    /// Show which local references the given refspecs map the references of a remote identified by a url to.
+    ///
+    /// This is similar to what `git fetch --dry-run` reports, without receiving any objects.
+    /// Supported URLs are documented here: <https://www.git-scm.com/docs/git-clone#_git_urls>
+    #[clap(setting = AppSettings::DisableVersionFlag)]
+    #[cfg(any(feature = "gitoxide-core-async-client", feature = "gitoxide-core-blocking-client"))]
+    RemoteRefMap {
+        /// The protocol version to use. Valid values are 1 and 2
+        #[clap(long, short = 'p')]
+        protocol: Option<core::net::Protocol>,
+
+        /// The repository whose local references to compare with to learn how they would be updated.
+        ///
+        /// If unset, only the names of local references are shown.
+        #[clap(long, short = 'r')]
+        repository: Option<PathBuf>,
+
+        /// the URLs or path from which to receive references
+        ///
+        /// See here for a list of supported URLs: <https://www.git-scm.com/docs/git-clone#_git_urls>
+        url: String,
+
+        /// The refspecs to map remote references with, like `+refs/heads/*:refs/remotes/origin/*`.
+        #[clap(required = true)]
+        refspecs: Vec<String>,
+    },
     #[clap(setting = AppSettings::DisableVersionFlag)]
     PackIndexFromData {
         /// Specify how to iterate the pack, defaults to 'verify'
@@ -219,6 +276,14 @@ pub enum Subcommands {
         /// output statistical information about the pack
         #[clap(long, short = 's')]
         statistics: bool,
+        /// The amount of largest objects by decompressed size to list in the statistics, along with their kind and delta chain
+        /// length, to find accidentally committed binaries for example.
+        #[clap(long, short = 'n', default_value = "0")]
+        largest_objects: usize,
+        /// Write the id, offset, kind, sizes and CRC32 of each object as soon as it was verified, which is best used with
+        /// `--format json-lines` to pipe them into other tools.
+        #[clap(long)]
+        objects: bool,
         /// The algorithm used to verify the pack. They differ in costs.
         #[clap(
             long,
@@ -244,10 +309,27 @@ pub enum Subcommands {
         /// owned objects, causing plenty of allocation to occour.
         re_encode: bool,
 
-        /// The '.pack' or '.idx' file whose checksum to validate.
+        /// The '.pack' or '.idx' file whose checksum to validate, or a 'multi-pack-index' to validate along with the indices of its packs.
         #[clap(parse(from_os_str))]
         path: PathBuf,
     },
+    /// Print all entries of a pack along with their delta base and chain depth, like `git verify-pack -v`
+    #[clap(setting = AppSettings::DisableVersionFlag)]
+    PackInspect {
+        /// The '.pack' or '.idx' file to inspect. Object ids are only shown if the index exists.
+        #[clap(parse(from_os_str))]
+        path: PathBuf,
+    },
+    /// Print objects which are only in one of two packs or stored with a different kind or delta base, along with their sizes
+    #[clap(setting = AppSettings::DisableVersionFlag)]
+    PackDiff {
+        /// The '.pack' or '.idx' file of the first pack. Both packs must have an index.
+        #[clap(parse(from_os_str))]
+        old: PathBuf,
+        /// The '.pack' or '.idx' file of the second pack.
+        #[clap(parse(from_os_str))]
+        new: PathBuf,
+    },
     /// Verify the integrity of a commit graph
     #[clap(setting = AppSettings::DisableVersionFlag)]
     CommitGraphVerify {
@@ -257,5 +339,203 @@ pub enum Subcommands {
         /// output statistical information about the pack
         #[clap(long, short = 's')]
         statistics: bool,
+        /// Also verify the root tree, parents and commit date of each commit against its object in the object database.
+        ///
+        /// The object database is expected to be the parent directory of the 'info' directory containing the graph.
+        #[clap(long, short = 'o')]
+        objects: bool,
+    },
+    /// Write a commit graph with all commits reachable from all references, like `git commit-graph write --reachable`
+    #[clap(setting = AppSettings::DisableVersionFlag)]
+    CommitGraphWrite {
+        #[clap(long, short = 'r')]
+        /// the directory containing the '.git' repository, or any directory within it.
+        ///
+        /// Defaults to the current working directory.
+        repository: Option<PathBuf>,
+        /// Read the ids of the commits to start from on standard input, one per line, instead of using all references.
+        #[clap(long)]
+        stdin_commits: bool,
+    },
+    /// Print the content of a file at a revision, like `git show <revision>:<path>`
+    #[clap(setting = AppSettings::DisableVersionFlag)]
+    RepositoryCat {
+        #[clap(long, short = 'r')]
+        /// the directory containing the '.git' repository, or any directory within it.
+        ///
+        /// Defaults to the current working directory.
+        repository: Option<PathBuf>,
+        /// convert the file as it would be when checked out, like the line endings configured by 'core.autocrlf'.
+        #[clap(long)]
+        filters: bool,
+        /// the file to print as '<revision>:<path>', like 'main:README.md' or 'HEAD~1:src/lib.rs'.
+        spec: String,
+    },
+    /// Print the content, kind or size of an object given by its id or a unique prefix of it, like 'git cat-file'
+    #[clap(setting = AppSettings::DisableVersionFlag)]
+    RepositoryCatObject {
+        #[clap(long, short = 'r')]
+        /// the directory containing the '.git' repository, or any directory within it.
+        ///
+        /// Defaults to the current working directory.
+        repository: Option<PathBuf>,
+        /// print the kind of the object instead of its content.
+        #[clap(long, short = 't', conflicts_with("size"))]
+        kind: bool,
+        /// print the size of the object's content in bytes instead of the content itself.
+        #[clap(long, short = 's')]
+        size: bool,
+        /// the id of the object, or a prefix of at least 4 hexadecimal characters which matches a single object.
+        object: String,
+    },
+    /// Compute the id of an object read from a file or standard input and optionally write it into the object database,
+    /// like 'git hash-object'
+    #[clap(setting = AppSettings::DisableVersionFlag)]
+    RepositoryHashObject {
+        #[clap(long, short = 'r')]
+        /// the directory containing the '.git' repository, or any directory within it.
+        ///
+        /// Defaults to the current working directory. It's only accessed if the object is written.
+        repository: Option<PathBuf>,
+        /// the kind of object to create.
+        #[clap(long, short = 't', default_value = "blob", possible_values(&["blob", "tree", "commit", "tag"]))]
+        kind: git_repository::objs::Kind,
+        /// write the object into the object database.
+        #[clap(long, short = 'w')]
+        write: bool,
+        /// the file to read the object's content from.
+        ///
+        /// If unset, the content is read from standard input.
+        path: Option<PathBuf>,
+    },
+    /// Search for lines matching a pattern in all files at a revision or in the work tree, like `git grep`
+    #[clap(setting = AppSettings::DisableVersionFlag)]
+    RepositoryGrep {
+        #[clap(long, short = 'r')]
+        /// the directory containing the '.git' repository, or any directory within it.
+        ///
+        /// Defaults to the current working directory.
+        repository: Option<PathBuf>,
+        /// the revision whose files to search, like 'main' or 'HEAD~1'.
+        ///
+        /// If unset, files in the work tree are searched.
+        #[clap(long)]
+        revision: Option<String>,
+        /// match the pattern case-insensitively.
+        #[clap(long, short = 'i')]
+        ignore_case: bool,
+        /// interpret the pattern as literal string instead of as regular expression.
+        #[clap(long, short = 'F')]
+        fixed_strings: bool,
+        /// the regular expression to match each line against.
+        pattern: String,
+        /// slash-separated directories or files to limit the search to.
+        paths: Vec<String>,
+    },
+    /// Print the path from a reference or reflog entry to an object to learn why it's retained
+    #[clap(setting = AppSettings::DisableVersionFlag)]
+    RepositoryReachability {
+        #[clap(long, short = 'r')]
+        /// the directory containing the '.git' repository, or any directory within it.
+        ///
+        /// Defaults to the current working directory.
+        repository: Option<PathBuf>,
+        /// the full hexadecimal id of the object whose reachability to explain.
+        id: git_repository::hash::ObjectId,
+    },
+    /// Print the largest objects in packs and loose storage along with the path they were first seen at
+    #[clap(setting = AppSettings::DisableVersionFlag)]
+    RepositoryLargestObjects {
+        #[clap(long, short = 'r')]
+        /// the directory containing the '.git' repository, or any directory within it.
+        ///
+        /// Defaults to the current working directory.
+        repository: Option<PathBuf>,
+        /// the amount of objects to print.
+        #[clap(long, short = 'n', default_value = "10")]
+        count: usize,
+        /// the size to rank objects by, either the size of their content or the amount of bytes they occupy on disk.
+        #[clap(
+            long,
+            short = 'o',
+            default_value = "size",
+            possible_values(core::largest_objects::Order::variants())
+        )]
+        order: core::largest_objects::Order,
+    },
+    /// Print statistics about the object database, like the amount and size of loose objects and packs.
+    #[clap(setting = AppSettings::DisableVersionFlag)]
+    RepositoryOdbStats {
+        #[clap(long, short = 'r')]
+        /// the directory containing the '.git' repository, or any directory within it.
+        ///
+        /// Defaults to the current working directory.
+        repository: Option<PathBuf>,
+        /// the amount of largest objects by size on disk to print, or 0 to skip finding them.
+        #[clap(long, short = 'n', default_value = "10")]
+        largest: usize,
+    },
+    /// Verify all loose objects, packs, the commit-graph and the connectivity of all references, like 'git fsck'.
+    ///
+    /// All problems are listed and the command fails if there was at least one.
+    #[clap(setting = AppSettings::DisableVersionFlag)]
+    RepositoryVerify {
+        #[clap(long, short = 'r')]
+        /// the directory containing the '.git' repository, or any directory within it.
+        ///
+        /// Defaults to the current working directory.
+        repository: Option<PathBuf>,
+    },
+    /// List the entries of a tree, like 'git ls-tree'
+    #[clap(setting = AppSettings::DisableVersionFlag)]
+    RepositoryLsTree {
+        #[clap(long, short = 'r')]
+        /// the directory containing the '.git' repository, or any directory within it.
+        ///
+        /// Defaults to the current working directory.
+        repository: Option<PathBuf>,
+        /// list the entries of all trees recursively instead of the trees themselves.
+        #[clap(long, short = 'R')]
+        recursive: bool,
+        /// print the size of blobs.
+        #[clap(long, short = 'l')]
+        long: bool,
+        /// print only the path of each entry.
+        #[clap(long)]
+        name_only: bool,
+        /// the revision of the tree to list, like 'main', 'HEAD~1' or the id of a tree.
+        treeish: String,
+        /// slash-separated paths to limit the listing to.
+        paths: Vec<String>,
+    },
+    /// Merge two trees with their merge base without touching the index, and print the merged tree and all conflicts.
+    #[clap(setting = AppSettings::DisableVersionFlag)]
+    RepositoryMergeTree {
+        #[clap(long, short = 'r')]
+        /// the directory containing the '.git' repository, or any directory within it.
+        ///
+        /// Defaults to the current working directory.
+        repository: Option<PathBuf>,
+        /// the revision of the merge base, like 'main~1' or the id of a tree.
+        base: String,
+        /// the revision of our side of the merge.
+        ours: String,
+        /// the revision of their side of the merge.
+        theirs: String,
+    },
+    /// Update references in a transaction with commands read from stdin, like 'git update-ref --stdin'
+    #[clap(setting = AppSettings::DisableVersionFlag)]
+    RepositoryUpdateRefs {
+        #[clap(long, short = 'r')]
+        /// the directory containing the '.git' repository, or any directory within it.
+        ///
+        /// Defaults to the current working directory.
+        repository: Option<PathBuf>,
+        /// read commands and their arguments terminated by NUL bytes instead of newlines.
+        #[clap(short = 'z')]
+        nul_terminated: bool,
+        /// the message to write into the reference logs of all updated references.
+        #[clap(long, short = 'm')]
+        message: Option<String>,
     },
 }
@@ -10,7 +10,7 @@ use gitoxide_core as core;
 
 use crate::{
     porcelain::options::{Args, EstimateHours, Subcommands, ToolCommands},
-    shared::pretty::prepare_and_run,
+    shared::pretty::{prepare_and_run, LineRenderer},
 };
 
 pub fn main() -> Result<()> {
@@ -23,6 +23,7 @@ pub fn main() -> Result<()> {
     let verbose = !args.quiet;
     let progress = args.progress;
     let progress_keep_open = args.progress_keep_open;
+    let line_renderer = LineRenderer::from_flags(args.progress_json, args.progress_log);
 
     match args.cmd {
         #[cfg(debug_assertions)]
@@ -31,10 +32,104 @@ pub fn main() -> Result<()> {
             verbose,
             progress,
             progress_keep_open,
+            line_renderer,
             crate::shared::STANDARD_RANGE,
             move |_progress, _out, _err| panic!("something went very wrong"),
         ),
-        Subcommands::Init { directory } => core::repository::init(directory).map(|_| ()),
+        Subcommands::Init {
+            directory,
+            bare,
+            initial_branch,
+            no_hook_samples,
+        } => core::repository::init(
+            directory,
+            bare,
+            git_repository::path::create::Options {
+                hook_samples: !no_hook_samples,
+                initial_branch: initial_branch.map(Into::into),
+            },
+        )
+        .map(|_| ()),
+        #[cfg(feature = "gitoxide-core-blocking-client")]
+        Subcommands::Clone {
+            protocol,
+            bare,
+            mirror,
+            branch,
+            single_branch,
+            no_tags,
+            no_local,
+            no_hardlinks,
+            reference,
+            dissociate,
+            no_hook_samples,
+            url,
+            directory,
+        } => prepare_and_run(
+            "clone",
+            verbose,
+            progress,
+            progress_keep_open,
+            line_renderer,
+            core::clone::PROGRESS_RANGE,
+            move |progress, out, _err| {
+                core::clone(
+                    protocol,
+                    &url,
+                    directory,
+                    DoOrDiscard::from(progress),
+                    core::clone::Context {
+                        thread_limit: None,
+                        format: core::OutputFormat::Human,
+                        should_interrupt,
+                        out,
+                        bare,
+                        mirror,
+                        branch,
+                        single_branch,
+                        tags: !no_tags,
+                        local: !no_local,
+                        hardlinks: !no_hardlinks,
+                        reference,
+                        dissociate,
+                        hook_samples: !no_hook_samples,
+                    },
+                )
+            },
+        ),
+        #[cfg(feature = "gitoxide-core-blocking-client")]
+        Subcommands::Fetch {
+            protocol,
+            tags,
+            no_tags,
+            remote,
+        } => prepare_and_run(
+            "fetch",
+            verbose,
+            progress,
+            progress_keep_open,
+            line_renderer,
+            core::fetch::PROGRESS_RANGE,
+            move |progress, out, _err| {
+                core::fetch(
+                    protocol,
+                    std::env::current_dir()?,
+                    Some(remote),
+                    DoOrDiscard::from(progress),
+                    core::fetch::Context {
+                        thread_limit: None,
+                        format: core::OutputFormat::Human,
+                        should_interrupt,
+                        out,
+                        tags: match (tags, no_tags) {
+                            (true, _) => Some(core::fetch::Tags::All),
+                            (_, true) => Some(core::fetch::Tags::None),
+                            _ => None,
+                        },
+                    },
+                )
+            },
+        ),
         Subcommands::Tools(tool) => match tool {
             ToolCommands::EstimateHours(EstimateHours {
                 working_dir,
@@ -48,6 +143,7 @@ pub fn main() -> Result<()> {
                     verbose,
                     progress,
                     progress_keep_open,
+                    line_renderer,
                     crate::shared::STANDARD_RANGE,
                     move |progress, out, _err| {
                         hours::estimate(
@@ -70,6 +166,7 @@ pub fn main() -> Result<()> {
                     verbose,
                     progress,
                     progress_keep_open,
+                    line_renderer,
                     crate::shared::STANDARD_RANGE,
                     move |progress, out, _err| {
                         organize::discover(
@@ -91,6 +188,7 @@ pub fn main() -> Result<()> {
                     verbose,
                     progress,
                     progress_keep_open,
+                    line_renderer,
                     crate::shared::STANDARD_RANGE,
                     move |progress, _out, _err| {
                         organize::run(
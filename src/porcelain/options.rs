@@ -20,21 +20,120 @@ pub struct Args {
     #[clap(long, conflicts_with("quiet"), requires("progress"))]
     pub progress_keep_open: bool,
 
+    /// Write snapshots of the progress tree as JSON object per line to stderr, for consumption by other programs.
+    #[clap(long, conflicts_with_all(&["quiet", "progress"]))]
+    pub progress_json: bool,
+
+    /// Periodically write the progress of all tasks as plain text lines to stderr, suitable for CI logs and other outputs
+    /// which aren't terminals.
+    #[clap(long, conflicts_with_all(&["quiet", "progress", "progress-json"]))]
+    pub progress_log: bool,
+
     #[clap(subcommand)]
     pub cmd: Subcommands,
 }
 
 #[derive(Debug, clap::Parser)]
 pub enum Subcommands {
-    /// Initialize the repository in the current directory.
+    /// Initialize a repository in the current or the given directory.
     #[clap(visible_alias = "initialize")]
     #[clap(setting = AppSettings::DisableVersionFlag)]
     Init {
+        /// Create a repository without a work tree, placing the contents of `.git` into the directory itself.
+        #[clap(long)]
+        bare: bool,
+
+        /// The name of the branch `HEAD` points to. Defaults to 'main'.
+        #[clap(long, short = 'b')]
+        initial_branch: Option<String>,
+
+        /// Don't write the sample hooks into the 'hooks' directory.
+        #[clap(long)]
+        no_hook_samples: bool,
+
         /// The directory in which to initialize a new git repository.
         ///
         /// Defaults to the current working directory.
         directory: Option<PathBuf>,
     },
+    /// Clone a repository into a new directory, checking out the branch its `HEAD` points to.
+    #[clap(setting = AppSettings::DisableVersionFlag)]
+    #[cfg(feature = "gitoxide-core-blocking-client")]
+    Clone {
+        /// The protocol version to use. Valid values are 1 and 2
+        #[clap(long, short = 'p')]
+        protocol: Option<gitoxide_core::net::Protocol>,
+
+        /// Create a bare repository whose branches are the ones of the remote, without checking anything out.
+        #[clap(long)]
+        bare: bool,
+
+        /// Create a bare repository with all references of the remote, and overwrite all of them when fetching.
+        #[clap(long)]
+        mirror: bool,
+
+        /// Check out this branch instead of the one the remote `HEAD` points to, or detach `HEAD` at this tag.
+        #[clap(long, short = 'b')]
+        branch: Option<String>,
+
+        /// Only clone the branch to check out and the tags pointing into its history, and only fetch this branch later.
+        #[clap(long)]
+        single_branch: bool,
+
+        /// Don't clone any tags, and don't fetch them later either.
+        #[clap(long)]
+        no_tags: bool,
+
+        /// Fetch a pack even if the repository is given as path, instead of copying its object database.
+        #[clap(long)]
+        no_local: bool,
+
+        /// Copy the files of the object database of a repository given as path, instead of hardlinking them.
+        #[clap(long)]
+        no_hardlinks: bool,
+
+        /// Borrow objects from the repository at this path through alternates instead of fetching them again.
+        #[clap(long)]
+        reference: Option<PathBuf>,
+
+        /// Copy objects borrowed from alternates into the clone and stop using them afterwards.
+        #[clap(long)]
+        dissociate: bool,
+
+        /// Don't write the sample hooks into the 'hooks' directory.
+        #[clap(long)]
+        no_hook_samples: bool,
+
+        /// The URL or path of the repository to clone.
+        ///
+        /// See here for a list of supported URLs: <https://www.git-scm.com/docs/git-clone#_git_urls>
+        url: String,
+
+        /// The directory to clone into, which must not exist or be empty.
+        ///
+        /// Defaults to the last component of the URL's path without '.git', like `git clone` does.
+        directory: Option<PathBuf>,
+    },
+    /// Fetch from a remote of the repository in the current directory, updating its remote tracking branches.
+    #[clap(setting = AppSettings::DisableVersionFlag)]
+    #[cfg(feature = "gitoxide-core-blocking-client")]
+    Fetch {
+        /// The protocol version to use. Valid values are 1 and 2
+        #[clap(long, short = 'p')]
+        protocol: Option<gitoxide_core::net::Protocol>,
+
+        /// Fetch all tags of the remote in addition to the references matched by its refspecs.
+        #[clap(long, conflicts_with("no-tags"))]
+        tags: bool,
+
+        /// Don't fetch tags pointing to fetched history unless the refspecs of the remote match them.
+        #[clap(long)]
+        no_tags: bool,
+
+        /// The name of the remote to fetch from.
+        #[clap(default_value = "origin")]
+        remote: String,
+    },
     /// A selection of useful tools
     #[clap(subcommand)]
     Tools(ToolCommands),
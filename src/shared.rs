@@ -6,8 +6,8 @@ pub type ProgressRange = std::ops::RangeInclusive<prodash::progress::key::Level>
 #[allow(unused)]
 pub const STANDARD_RANGE: ProgressRange = 2..=2;
 
-/// If verbose is true, the env logger will be forcibly set to 'info' logging level. Otherwise env logging facilities
-/// will just be initialized.
+/// If verbose is true, the env logger will be forcibly set to 'info' logging level. Otherwise it defaults to the 'warn' level
+/// to show fallback decisions of operations, without timestamp as these are meant to be read like any other error message.
 #[cfg(feature = "env_logger")]
 #[allow(unused)] // Squelch warning because it's used in porcelain as well and we can't know that at compile time
 pub fn init_env_logger(verbose: bool) {
@@ -16,7 +16,10 @@ pub fn init_env_logger(verbose: bool) {
             .format_module_path(false)
             .init();
     } else {
-        env_logger::init();
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn"))
+            .format_timestamp(None)
+            .format_target(false)
+            .init();
     }
 }
 
@@ -75,13 +78,43 @@ pub mod pretty {
 
     use crate::shared::ProgressRange;
 
+    /// The way progress is rendered on stderr unless the progress TUI is requested.
+    #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+    pub enum LineRenderer {
+        /// Redraw the progress tree in place, which only shows progress if stderr is a terminal.
+        Line,
+        /// Periodically write the progress of all tasks as plain text lines, suitable for CI logs.
+        Log,
+        /// Write snapshots of the progress tree as JSON object per line, for consumption by other programs.
+        Json,
+    }
+
+    impl LineRenderer {
+        /// Select the renderer from the `--progress-json` and `--progress-log` flags, with the former taking precedence.
+        pub fn from_flags(json: bool, log: bool) -> Self {
+            match (json, log) {
+                (true, _) => LineRenderer::Json,
+                (false, true) => LineRenderer::Log,
+                (false, false) => LineRenderer::Line,
+            }
+        }
+    }
+
+    /// The time between two snapshots written by [`LineRenderer::Log`], long enough to not flood logs.
+    const LOG_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
     pub fn prepare_and_run<T: Send + 'static>(
         name: &str,
         verbose: bool,
         progress: bool,
         progress_keep_open: bool,
+        line_renderer: LineRenderer,
         range: impl Into<Option<ProgressRange>>,
-        run: impl FnOnce(Option<prodash::tree::Item>, &mut dyn std::io::Write, &mut dyn std::io::Write) -> Result<T>
+        run: impl FnOnce(
+                Option<prodash::tree::Item>,
+                &mut (dyn std::io::Write + Send),
+                &mut (dyn std::io::Write + Send),
+            ) -> Result<T>
             + Send
             + UnwindSafe
             + 'static,
@@ -90,7 +123,7 @@ pub mod pretty {
         crate::shared::init_env_logger(false);
         use git_repository::interrupt;
 
-        match (verbose, progress) {
+        match (verbose || line_renderer != LineRenderer::Line, progress) {
             (false, false) => run(None, &mut stdout(), &mut stderr()),
             (true, false) => {
                 enum Event<T> {
@@ -101,7 +134,26 @@ pub mod pretty {
                 let progress = crate::shared::progress_tree();
                 let sub_progress = progress.add_child(name);
                 let (tx, rx) = std::sync::mpsc::sync_channel::<Event<T>>(1);
-                let ui_handle = shared::setup_line_renderer_range(progress, range.into().unwrap_or(STANDARD_RANGE));
+                let range = range.into().unwrap_or(STANDARD_RANGE);
+                let shutdown_ui: Box<dyn FnOnce()> = match line_renderer {
+                    LineRenderer::Json => {
+                        let ui_handle = gitoxide_core::progress::json_lines(stderr(), progress, Default::default());
+                        Box::new(move || ui_handle.shutdown_and_wait())
+                    }
+                    LineRenderer::Log => {
+                        let ui_handle = gitoxide_core::progress::log_lines(
+                            stderr(),
+                            progress,
+                            range,
+                            gitoxide_core::progress::Options { interval: LOG_INTERVAL },
+                        );
+                        Box::new(move || ui_handle.shutdown_and_wait())
+                    }
+                    LineRenderer::Line => {
+                        let ui_handle = shared::setup_line_renderer_range(progress, range);
+                        Box::new(move || ui_handle.shutdown_and_wait())
+                    }
+                };
                 std::thread::spawn({
                     let tx = tx.clone();
                     move || loop {
@@ -128,12 +180,12 @@ pub mod pretty {
                 });
                 match rx.recv()? {
                     Event::UiDone => {
-                        ui_handle.shutdown_and_wait();
+                        shutdown_ui();
                         drop(join_handle);
                         Err(anyhow!("Operation cancelled by user"))
                     }
                     Event::ComputationDone(res) => {
-                        ui_handle.shutdown_and_wait();
+                        shutdown_ui();
                         join_handle.join().ok();
                         res
                     }
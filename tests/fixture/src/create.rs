@@ -0,0 +1,477 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::{Infallible, TryFrom},
+    path::Path,
+    sync::atomic::AtomicBool,
+};
+
+use git_hash::ObjectId;
+use git_object::{bstr::BString, tree};
+use git_odb::{Find as _, Write as _};
+use git_ref::{
+    transaction::{Change as RefChange, LogChange, PreviousValue, RefEdit},
+    FullName, Target,
+};
+
+use crate::{Change, Command, Script};
+
+/// The result of [`Script::create()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// The ids of all commits in the order they were created in.
+    pub commits: Vec<ObjectId>,
+    /// All branches and tags by their full name, along with the object they point to.
+    pub refs: BTreeMap<String, ObjectId>,
+}
+
+/// The error returned by [`Script::create()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("line {line}: Revision '{revision}' doesn't exist")]
+    UnknownRevision { line: usize, revision: String },
+    #[error("line {line}: Revision '{revision}' doesn't point to a commit")]
+    NotACommit { line: usize, revision: String },
+    #[error("line {line}: '{name}' is not a valid reference name")]
+    InvalidRefName { line: usize, name: String },
+    #[error("line {line}: '{path}' is not a valid path")]
+    InvalidPath { line: usize, path: String },
+    #[error("line {line}: There is nothing to delete at '{path}'")]
+    NoSuchPath { line: usize, path: String },
+    #[error("The directory to create the repository in exists already")]
+    DirectoryExists,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    WriteObject(#[from] git_odb::loose::write::Error),
+    #[error(transparent)]
+    PrepareRefs(#[from] git_ref::file::transaction::prepare::Error),
+    #[error(transparent)]
+    CommitRefs(#[from] git_ref::file::transaction::commit::Error),
+    #[error(transparent)]
+    OpenObjectDatabase(#[from] git_odb::linked::init::Error),
+    #[error(transparent)]
+    PackRefs(#[from] git_ref::file::packed::pack_refs::Error),
+    #[error(transparent)]
+    IterateLooseObjects(#[from] git_odb::loose::iter::Error),
+    #[error(transparent)]
+    FindLooseObject(#[from] git_odb::loose::find::Error),
+    #[error(transparent)]
+    CreatePackEntry(#[from] git_pack::data::output::entry::Error),
+    #[error(transparent)]
+    WritePackData(#[from] git_pack::data::output::bytes::Error<Infallible>),
+    #[error(transparent)]
+    WritePack(#[from] git_pack::bundle::write::Error),
+    #[error(transparent)]
+    WriteCommitGraph(#[from] git_commitgraph::write::Error<Infallible>),
+}
+
+/// The time of the first object, the 1st of January 2000.
+const START_TIME: u32 = 946_684_800;
+const HASH_KIND: git_hash::Kind = git_hash::Kind::Sha1;
+
+impl Script {
+    /// Create a new bare repository in `dir` by running all commands of this script, and return all commits and references
+    /// it created.
+    ///
+    /// `dir` must not exist yet.
+    pub fn create(&self, dir: &Path) -> Result<Outcome, Error> {
+        if dir.exists() {
+            return Err(Error::DirectoryExists);
+        }
+        let objects_dir = dir.join("objects");
+        for sub_dir in &["info", "pack"] {
+            std::fs::create_dir_all(objects_dir.join(sub_dir))?;
+        }
+        for sub_dir in &["heads", "tags"] {
+            std::fs::create_dir_all(dir.join("refs").join(sub_dir))?;
+        }
+        std::fs::write(
+            dir.join("config"),
+            "[core]\n\trepositoryformatversion = 0\n\tfilemode = true\n\tbare = true\n",
+        )?;
+        std::fs::write(dir.join("HEAD"), "ref: refs/heads/main\n")?;
+
+        let mut state = State {
+            odb: git_odb::loose::Store::at(&objects_dir),
+            refs: git_ref::file::Store::at(dir, git_ref::file::WriteReflog::Normal),
+            time: START_TIME,
+            objects: HashMap::new(),
+            commits: HashMap::new(),
+            files: HashMap::new(),
+            out: Outcome {
+                commits: Vec::new(),
+                refs: BTreeMap::new(),
+            },
+        };
+        for (line, command) in &self.commands {
+            state.run(*line, command)?;
+        }
+        Ok(state.out)
+    }
+}
+
+/// What we know about objects created by the script.
+enum Object {
+    Commit,
+    Tree,
+    Tag { target: ObjectId },
+}
+
+struct State {
+    odb: git_odb::loose::Store,
+    refs: git_ref::file::Store,
+    /// The time of the next object.
+    time: u32,
+    /// All commits, trees and tags created so far.
+    objects: HashMap<ObjectId, Object>,
+    /// The information about each commit needed to write commit-graphs.
+    commits: HashMap<ObjectId, git_commitgraph::write::Commit>,
+    /// The files of each commit, by path.
+    files: HashMap<ObjectId, BTreeMap<String, String>>,
+    out: Outcome,
+}
+
+impl State {
+    fn run(&mut self, line: usize, command: &Command) -> Result<(), Error> {
+        match command {
+            Command::Commit {
+                branch,
+                message,
+                changes,
+                merges,
+            } => {
+                let name = full_name(line, &format!("refs/heads/{}", branch))?;
+                let previous = self.out.refs.get(name.as_bstr().to_string().as_str()).copied();
+                let mut files = previous.map(|id| self.files[&id].clone()).unwrap_or_default();
+                for change in changes {
+                    apply(line, change, &mut files)?;
+                }
+                let mut parents: Vec<_> = previous.into_iter().collect();
+                for revision in merges {
+                    parents.push(self.peel_to_commit(line, revision)?);
+                }
+
+                let tree = self.write_tree(files.iter().map(|(path, content)| (path.as_str(), content.as_str())))?;
+                let signature = self.next_signature();
+                let committer_timestamp = u64::from(signature.time.time);
+                let id = self.odb.write(
+                    git_object::Commit {
+                        tree,
+                        parents: parents.clone().into(),
+                        author: signature.clone(),
+                        committer: signature,
+                        encoding: None,
+                        message: with_newline(message),
+                        extra_headers: Vec::new(),
+                    },
+                    HASH_KIND,
+                )?;
+                self.objects.insert(id, Object::Commit);
+                self.commits.insert(
+                    id,
+                    git_commitgraph::write::Commit {
+                        tree,
+                        parents,
+                        committer_timestamp,
+                    },
+                );
+                self.files.insert(id, files);
+                self.out.commits.push(id);
+                self.update_ref(
+                    name,
+                    id,
+                    format!("commit: {}", message.lines().next().unwrap_or_default()),
+                )?;
+            }
+            Command::Branch { name, revision } => {
+                let id = self.peel_to_commit(line, revision)?;
+                let name = full_name(line, &format!("refs/heads/{}", name))?;
+                self.update_ref(name, id, format!("branch: Created from {}", revision))?;
+            }
+            Command::Tag {
+                name,
+                revision,
+                message,
+            } => {
+                let mut id = self.resolve(line, revision)?;
+                if let Some(message) = message {
+                    let target_kind = match self.objects[&id] {
+                        Object::Commit => git_object::Kind::Commit,
+                        Object::Tree => git_object::Kind::Tree,
+                        Object::Tag { .. } => git_object::Kind::Tag,
+                    };
+                    let target = id;
+                    let tagger = self.next_signature();
+                    id = self.odb.write(
+                        git_object::Tag {
+                            target,
+                            target_kind,
+                            name: name.as_str().into(),
+                            tagger: Some(tagger),
+                            message: with_newline(message),
+                            pgp_signature: None,
+                        },
+                        HASH_KIND,
+                    )?;
+                    self.objects.insert(id, Object::Tag { target });
+                }
+                let name = full_name(line, &format!("refs/tags/{}", name))?;
+                self.update_ref(name, id, String::new())?;
+            }
+            Command::Head { branch } => {
+                let name = full_name(line, &format!("refs/heads/{}", branch))?;
+                self.edit_refs(RefEdit {
+                    change: RefChange::Update {
+                        log: Default::default(),
+                        expected: PreviousValue::Any,
+                        new: Target::Symbolic(name),
+                    },
+                    name: full_name(line, "HEAD")?,
+                    deref: false,
+                })?;
+            }
+            Command::Pack => self.pack()?,
+            Command::CommitGraph => {
+                let tips: Vec<_> = self
+                    .out
+                    .refs
+                    .values()
+                    .filter_map(|id| self.peeled_commit(*id))
+                    .collect();
+                let commits = &self.commits;
+                git_commitgraph::write::write(self.odb.path.join("info"), tips, |id| {
+                    Ok::<_, Infallible>(commits.get(id).cloned())
+                })?;
+            }
+            Command::PackRefs => {
+                let odb = git_odb::linked::Store::at(&self.odb.path)?;
+                self.refs.pack_refs(
+                    Box::new(move |oid, buf| {
+                        odb.try_find(oid, buf, &mut git_pack::cache::Never)
+                            .map(|obj| obj.map(|obj| obj.kind))
+                            .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send + Sync>)
+                    }),
+                    true,
+                    git_lock::acquire::Fail::Immediately,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Return the signature for the current time.
+    fn signature(&self) -> git_actor::Signature {
+        git_actor::Signature {
+            name: "author".into(),
+            email: "author@example.com".into(),
+            time: git_actor::Time {
+                time: self.time,
+                offset: 0,
+                sign: git_actor::Sign::Plus,
+            },
+        }
+    }
+
+    /// Return the signature of the next object, and advance the time.
+    fn next_signature(&mut self) -> git_actor::Signature {
+        let signature = self.signature();
+        self.time += 60;
+        signature
+    }
+
+    /// Write the trees and blobs for the sorted `files` and return the id of the root tree.
+    fn write_tree<'a>(&mut self, files: impl Iterator<Item = (&'a str, &'a str)>) -> Result<ObjectId, Error> {
+        let mut entries = Vec::new();
+        let mut files = files.peekable();
+        while let Some((path, content)) = files.next() {
+            match path.split_once('/') {
+                Some((dir, _)) => {
+                    let prefix = format!("{}/", dir);
+                    let mut children = vec![(&path[prefix.len()..], content)];
+                    while let Some((path, content)) = files.peek().filter(|(path, _)| path.starts_with(&prefix)) {
+                        children.push((&path[prefix.len()..], content));
+                        files.next();
+                    }
+                    entries.push(tree::Entry {
+                        mode: tree::EntryMode::Tree,
+                        filename: dir.into(),
+                        oid: self.write_tree(children.into_iter())?,
+                    });
+                }
+                None => entries.push(tree::Entry {
+                    mode: tree::EntryMode::Blob,
+                    filename: path.into(),
+                    oid: self.odb.write(
+                        git_object::Blob {
+                            data: content.as_bytes().into(),
+                        },
+                        HASH_KIND,
+                    )?,
+                }),
+            }
+        }
+        entries.sort();
+        let id = self.odb.write(git_object::Tree { entries }, HASH_KIND)?;
+        self.objects.insert(id, Object::Tree);
+        Ok(id)
+    }
+
+    /// Resolve `revision` as branch, tag, full reference name or object id, in that order.
+    fn resolve(&self, line: usize, revision: &str) -> Result<ObjectId, Error> {
+        ["refs/heads/", "refs/tags/", ""]
+            .iter()
+            .find_map(|prefix| self.out.refs.get(&format!("{}{}", prefix, revision)).copied())
+            .or_else(|| ObjectId::from_hex(revision.as_bytes()).ok())
+            .filter(|id| self.objects.contains_key(id))
+            .ok_or_else(|| Error::UnknownRevision {
+                line,
+                revision: revision.into(),
+            })
+    }
+
+    fn peel_to_commit(&self, line: usize, revision: &str) -> Result<ObjectId, Error> {
+        let id = self.resolve(line, revision)?;
+        self.peeled_commit(id).ok_or_else(|| Error::NotACommit {
+            line,
+            revision: revision.into(),
+        })
+    }
+
+    /// Return the commit `id` points to after following tags, or `None` if it's a tree.
+    fn peeled_commit(&self, mut id: ObjectId) -> Option<ObjectId> {
+        loop {
+            match self.objects[&id] {
+                Object::Commit => return Some(id),
+                Object::Tag { target } => id = target,
+                Object::Tree => return None,
+            }
+        }
+    }
+
+    fn update_ref(&mut self, name: FullName, id: ObjectId, message: String) -> Result<(), Error> {
+        self.out.refs.insert(name.as_bstr().to_string(), id);
+        self.edit_refs(RefEdit {
+            change: RefChange::Update {
+                log: LogChange {
+                    message: message.into(),
+                    ..Default::default()
+                },
+                expected: PreviousValue::Any,
+                new: Target::Peeled(id),
+            },
+            name,
+            deref: false,
+        })
+    }
+
+    fn edit_refs(&mut self, edit: RefEdit) -> Result<(), Error> {
+        self.refs
+            .transaction()
+            .prepare(Some(edit), git_lock::acquire::Fail::Immediately)?
+            .commit(&self.signature())?;
+        Ok(())
+    }
+
+    /// Move all loose objects into a new pack, like the `loose-objects` maintenance task does.
+    fn pack(&mut self) -> Result<(), Error> {
+        let ids = self.odb.iter().collect::<Result<Vec<_>, _>>()?;
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let mut buf = Vec::new();
+        let mut entries = Vec::with_capacity(ids.len());
+        for id in &ids {
+            let obj = self.odb.try_find(id, &mut buf)?.expect("listed objects exist");
+            let count = git_pack::data::output::Count::from_data(*id, &obj);
+            entries.push(git_pack::data::output::Entry::from_data(&count, &obj)?);
+        }
+        let pack = pack_data(entries)?;
+        git_pack::Bundle::write_to_directory(
+            pack.as_slice(),
+            Some(self.odb.path.join("pack")),
+            git_features::progress::Discard,
+            &AtomicBool::new(false),
+            None,
+            Default::default(),
+        )?;
+        for id in ids {
+            let hex = id.to_sha1_hex_string();
+            let dir = self.odb.path.join(&hex[..2]);
+            std::fs::remove_file(dir.join(&hex[2..]))?;
+            if std::fs::read_dir(&dir)?.next().is_none() {
+                std::fs::remove_dir(dir)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Return a pack containing all `entries`.
+pub(crate) fn pack_data(
+    entries: Vec<git_pack::data::output::Entry>,
+) -> Result<Vec<u8>, git_pack::data::output::bytes::Error<Infallible>> {
+    let mut pack = Vec::new();
+    let num_entries = entries.len() as u32;
+    let mut writer = git_pack::data::output::bytes::FromEntriesIter::new(
+        std::iter::once(Ok::<_, Infallible>(entries)),
+        &mut pack,
+        num_entries,
+        git_pack::data::Version::V2,
+        HASH_KIND,
+    );
+    for res in writer.by_ref() {
+        res?;
+    }
+    drop(writer);
+    Ok(pack)
+}
+
+fn full_name(line: usize, name: &str) -> Result<FullName, Error> {
+    FullName::try_from(name).map_err(|_| Error::InvalidRefName {
+        line,
+        name: name.into(),
+    })
+}
+
+fn with_newline(message: &str) -> BString {
+    let mut message = BString::from(message);
+    if !message.ends_with(b"\n") {
+        message.push(b'\n');
+    }
+    message
+}
+
+/// Apply `change` to `files`, replacing files and directories that are in the way of new files.
+fn apply(line: usize, change: &Change, files: &mut BTreeMap<String, String>) -> Result<(), Error> {
+    let path = match change {
+        Change::Write { path, .. } | Change::Delete { path } => path,
+    };
+    if path
+        .split('/')
+        .any(|component| component.is_empty() || component == "." || component == ".." || component == ".git")
+    {
+        return Err(Error::InvalidPath {
+            line,
+            path: path.into(),
+        });
+    }
+    let dir = format!("{}/", path);
+    let len_before = files.len();
+    files.retain(|existing, _| existing != path && !existing.starts_with(&dir));
+    match change {
+        Change::Write { content, .. } => {
+            files.retain(|existing, _| !path.starts_with(&format!("{}/", existing)));
+            files.insert(path.clone(), content.clone());
+        }
+        Change::Delete { .. } => {
+            if files.len() == len_before {
+                return Err(Error::NoSuchPath {
+                    line,
+                    path: path.into(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,127 @@
+//! Create git repositories for tests from declarative scripts, using gitoxide's own write paths instead of `git`.
+//!
+//! Scripts are line based, with one command per line and `#` starting a comment. Arguments are separated by whitespace, and
+//! double quotes can be used to have arguments with whitespace, supporting `\"`, `\\` and `\n` as escapes.
+//!
+//! * `commit <branch> <message> [<path>=<content>|-<path>|+<revision>]…`
+//!   * create a commit on top of `branch`, or a root commit if it doesn't exist yet, with the files of its parent. Files are
+//!     written with `<path>=<content>` and deleted with `-<path>`, while `+<revision>` adds another parent for merges.
+//!     Merges only have the files of their first parent, so files of other parents have to be written explicitly.
+//! * `branch <name> <revision>`
+//!   * create or move the branch `name` to point to `revision`.
+//! * `tag <name> <revision> [<message>]`
+//!   * create a lightweight tag pointing to `revision`, or an annotated one if a `message` is given.
+//! * `head <branch>`
+//!   * point `HEAD` to `branch`, which is `main` by default.
+//! * `pack`
+//!   * move all loose objects into a new pack.
+//! * `pack-refs`
+//!   * move all references except for `HEAD` into the `packed-refs` file.
+//! * `commit-graph`
+//!   * write a `commit-graph` file with all commits reachable from branches and tags, replacing the previous one.
+//!
+//! Revisions are names of branches or tags created by the script, or hexadecimal object ids.
+//!
+//! Repositories are bare and created deterministically: all commits and tags have the same author and committer, and a time
+//! that increases by a minute with every object.
+//!
+//! What servers send when cloning such repositories can be produced with the [`response`] module, to replace recordings
+//! of server output in tests.
+#![forbid(unsafe_code)]
+#![deny(missing_docs, rust_2018_idioms)]
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use once_cell::sync::Lazy;
+
+///
+pub mod create;
+pub use create::Outcome;
+///
+pub mod parse;
+///
+pub mod response;
+
+/// A parsed script, ready to [create][Script::create()] a repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Script {
+    /// The commands in the order they appear in the script, along with the line they are on.
+    pub commands: Vec<(usize, Command)>,
+}
+
+/// A command of a [`Script`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum Command {
+    /// Create a commit on top of `branch`.
+    Commit {
+        branch: String,
+        message: String,
+        changes: Vec<Change>,
+        merges: Vec<String>,
+    },
+    /// Create or move a branch.
+    Branch { name: String, revision: String },
+    /// Create a tag, which is annotated if there is a message.
+    Tag {
+        name: String,
+        revision: String,
+        message: Option<String>,
+    },
+    /// Point `HEAD` to `branch`.
+    Head { branch: String },
+    /// Move all loose objects into a new pack.
+    Pack,
+    /// Move all references except for `HEAD` into the `packed-refs` file.
+    PackRefs,
+    /// Write a `commit-graph` file with all commits reachable from branches and tags.
+    CommitGraph,
+}
+
+/// A change to the files of a commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum Change {
+    /// Write `content` to the file at `path`.
+    Write { path: String, content: String },
+    /// Delete the file or directory at `path`.
+    Delete { path: String },
+}
+
+static GENERATION_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// Create the repository described by the script at `tests/fixtures/<script_name>` once, and return the path to it.
+///
+/// Repositories are kept in `tests/fixtures/generated/<checksum>`, where `checksum` changes with the script. They must not be
+/// changed, but can be copied if needed.
+pub fn scripted_repo_read_only(script_name: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let script_path = Path::new("tests").join("fixtures").join(script_name);
+    let text = std::fs::read_to_string(&script_path)?;
+    let script = Script::parse(&text)?;
+
+    // keep the lock to not return directories that are still being written to threaded callers
+    let _lock = GENERATION_LOCK.lock().expect("no panics while generating");
+    let path = Path::new("tests")
+        .join("fixtures")
+        .join("generated")
+        .join(format!("{}", git_features::hash::crc32(text.as_bytes())));
+    if !path.is_dir() {
+        // other processes may generate the same repository, so only move complete ones into place
+        let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+        if tmp.is_dir() {
+            std::fs::remove_dir_all(&tmp)?;
+        }
+        script.create(&tmp)?;
+        if let Err(err) = std::fs::rename(&tmp, &path) {
+            std::fs::remove_dir_all(&tmp)?;
+            // another process was faster, which is fine as long as its repository is in place
+            if !path.is_dir() {
+                return Err(err.into());
+            }
+        }
+    }
+    Ok(path)
+}
@@ -0,0 +1,123 @@
+use crate::{Change, Command, Script};
+
+/// The error returned by [`Script::parse()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("line {line}: A quoted argument isn't terminated")]
+    UnterminatedQuote { line: usize },
+    #[error("line {line}: Unknown command '{command}'")]
+    UnknownCommand { line: usize, command: String },
+    #[error("line {line}: The '{command}' command is missing an argument")]
+    MissingArgument { line: usize, command: &'static str },
+    #[error("line {line}: The '{command}' command has too many arguments")]
+    TooManyArguments { line: usize, command: &'static str },
+    #[error("line {line}: '{token}' is neither a file to write or delete, nor a revision to merge")]
+    InvalidChange { line: usize, token: String },
+}
+
+impl Script {
+    /// Parse `text` as script, see the [crate documentation][crate] for its syntax.
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let mut commands = Vec::new();
+        for (line_idx, input) in text.lines().enumerate() {
+            let line = line_idx + 1;
+            let mut args = tokenize(input, line)?.into_iter();
+            let command = match args.next() {
+                Some(command) => command,
+                None => continue,
+            };
+            let mut next = |command: &'static str| args.next().ok_or(Error::MissingArgument { line, command });
+            let (name, command) = match command.as_str() {
+                "commit" => {
+                    let branch = next("commit")?;
+                    let message = next("commit")?;
+                    let mut changes = Vec::new();
+                    let mut merges = Vec::new();
+                    for token in args.by_ref() {
+                        if let Some(revision) = token.strip_prefix('+') {
+                            merges.push(revision.to_owned());
+                        } else if let Some(path) = token.strip_prefix('-') {
+                            changes.push(Change::Delete { path: path.to_owned() });
+                        } else if let Some((path, content)) = token.split_once('=') {
+                            changes.push(Change::Write {
+                                path: path.to_owned(),
+                                content: content.to_owned(),
+                            });
+                        } else {
+                            return Err(Error::InvalidChange { line, token });
+                        }
+                    }
+                    (
+                        "commit",
+                        Command::Commit {
+                            branch,
+                            message,
+                            changes,
+                            merges,
+                        },
+                    )
+                }
+                "branch" => (
+                    "branch",
+                    Command::Branch {
+                        name: next("branch")?,
+                        revision: next("branch")?,
+                    },
+                ),
+                "tag" => (
+                    "tag",
+                    Command::Tag {
+                        name: next("tag")?,
+                        revision: next("tag")?,
+                        message: args.next(),
+                    },
+                ),
+                "head" => ("head", Command::Head { branch: next("head")? }),
+                "pack" => ("pack", Command::Pack),
+                "pack-refs" => ("pack-refs", Command::PackRefs),
+                "commit-graph" => ("commit-graph", Command::CommitGraph),
+                _ => return Err(Error::UnknownCommand { line, command }),
+            };
+            if args.next().is_some() {
+                return Err(Error::TooManyArguments { line, command: name });
+            }
+            commands.push((line, command));
+        }
+        Ok(Script { commands })
+    }
+}
+
+/// Split `input` into whitespace separated arguments, handling quotes and ignoring comments.
+fn tokenize(input: &str, line: usize) -> Result<Vec<String>, Error> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    loop {
+        while chars.peek().map_or(false, |c| c.is_whitespace()) {
+            chars.next();
+        }
+        let mut token = String::new();
+        match chars.peek() {
+            None | Some('#') => break,
+            Some(_) => {}
+        }
+        let mut in_quotes = false;
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => in_quotes = !in_quotes,
+                '\\' if in_quotes => match chars.next() {
+                    Some('n') => token.push('\n'),
+                    Some(c) => token.push(c),
+                    None => return Err(Error::UnterminatedQuote { line }),
+                },
+                c if c.is_whitespace() && !in_quotes => break,
+                c => token.push(c),
+            }
+        }
+        if in_quotes {
+            return Err(Error::UnterminatedQuote { line });
+        }
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
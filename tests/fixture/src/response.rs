@@ -0,0 +1,144 @@
+use std::{convert::Infallible, path::Path};
+
+use git_hash::ObjectId;
+use git_odb::Find as _;
+
+use crate::create::pack_data;
+
+/// The error returned by [`v1_clone()`] and [`pack()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    OpenObjectDatabase(#[from] git_odb::linked::init::Error),
+    #[error(transparent)]
+    IterateObjects(#[from] git_odb::loose::iter::Error),
+    #[error(transparent)]
+    FindObject(#[from] git_odb::compound::find::Error),
+    #[error("Object {0} doesn't exist")]
+    MissingObject(ObjectId),
+    #[error(transparent)]
+    DecodeTag(#[from] git_object::decode::Error),
+    #[error(transparent)]
+    OpenPackedRefs(#[from] git_ref::packed::buffer::open::Error),
+    #[error(transparent)]
+    IterateRefs(#[from] git_ref::file::iter::loose_then_packed::Error),
+    #[error(transparent)]
+    FindHead(#[from] git_ref::file::find::existing::Error),
+    #[error(transparent)]
+    CreatePackEntry(#[from] git_pack::data::output::entry::Error),
+    #[error(transparent)]
+    WritePackData(#[from] git_pack::data::output::bytes::Error<Infallible>),
+}
+
+/// The capabilities advertised by [`v1_clone()`] along with the first reference, without `symref` which depends on `HEAD`.
+const CAPABILITIES: &str = "multi_ack thin-pack side-band side-band-64k ofs-delta shallow no-progress include-tag \
+                            multi_ack_detailed object-format=sha1 agent=git-testfixture";
+/// The largest amount of data in a packet line sent on a side-band, leaving room for the channel.
+const MAX_BAND_DATA_LEN: usize = 65515;
+
+/// Return what `git upload-pack` sends to a client using protocol V1 which clones the bare repository at `repo`, as created
+/// by [`scripted_repo_read_only()`][crate::scripted_repo_read_only()].
+///
+/// The server advertises all references along with `HEAD`, answers the request for all of them with `NAK` and sends a pack
+/// with all objects of the repository on the side-band. What the client actually sends is ignored, like recordings of
+/// server output used to be.
+pub fn v1_clone(repo: &Path) -> Result<Vec<u8>, Error> {
+    let odb = git_odb::linked::Store::at(repo.join("objects"))?;
+    let refs = git_ref::file::Store::at(repo, git_ref::file::WriteReflog::Normal);
+    let packed = refs.packed_buffer()?;
+    let mut advertised = Vec::new();
+    for reference in refs.iter(packed.as_ref())? {
+        let reference = reference?;
+        if let git_ref::Target::Peeled(id) = reference.target {
+            advertised.push((reference.name.as_bstr().to_string(), id));
+        }
+    }
+    let (head_id, head_target) = match refs.find_loose("HEAD")?.target {
+        git_ref::Target::Peeled(id) => (Some(id), None),
+        git_ref::Target::Symbolic(name) => {
+            let name = name.as_bstr().to_string();
+            let id = advertised.iter().find(|(n, _)| *n == name).map(|(_, id)| *id);
+            (id, Some(name))
+        }
+    };
+    let mut capabilities = CAPABILITIES.to_owned();
+    if let (Some(_), Some(name)) = (head_id, head_target) {
+        capabilities.push_str(&format!(" symref=HEAD:{}", name));
+    }
+
+    let mut out = Vec::new();
+    let mut lines = head_id
+        .map(|id| ("HEAD".to_owned(), id))
+        .into_iter()
+        .collect::<Vec<_>>();
+    let mut buf = Vec::new();
+    for (name, id) in &advertised {
+        lines.push((name.clone(), *id));
+        let peeled = peel_tags(&odb, *id, &mut buf)?;
+        if peeled != *id {
+            lines.push((format!("{}^{{}}", name), peeled));
+        }
+    }
+    for (idx, (name, id)) in lines.iter().enumerate() {
+        let line = if idx == 0 {
+            format!("{} {}\0{}\n", id, name, capabilities)
+        } else {
+            format!("{} {}\n", id, name)
+        };
+        packet_line(line.as_bytes(), &mut out);
+    }
+    out.extend_from_slice(b"0000");
+    if lines.is_empty() {
+        return Ok(out);
+    }
+
+    packet_line(b"NAK\n", &mut out);
+    for chunk in pack_with_objects(&odb)?.chunks(MAX_BAND_DATA_LEN) {
+        let mut data = vec![1];
+        data.extend_from_slice(chunk);
+        packet_line(&data, &mut out);
+    }
+    out.extend_from_slice(b"0000");
+    Ok(out)
+}
+
+/// Return the pack with all objects of the bare repository at `repo`, as sent by [`v1_clone()`].
+pub fn pack(repo: &Path) -> Result<Vec<u8>, Error> {
+    pack_with_objects(&git_odb::linked::Store::at(repo.join("objects"))?)
+}
+
+fn pack_with_objects(odb: &git_odb::linked::Store) -> Result<Vec<u8>, Error> {
+    let mut buf = Vec::new();
+    let mut entries = Vec::new();
+    for id in odb.iter() {
+        let id = id?;
+        let obj = odb
+            .try_find(id, &mut buf, &mut git_pack::cache::Never)?
+            .ok_or(Error::MissingObject(id))?;
+        let count = git_pack::data::output::Count::from_data(id, &obj);
+        entries.push(git_pack::data::output::Entry::from_data(&count, &obj)?);
+    }
+    Ok(pack_data(entries)?)
+}
+
+/// Return the object `id` points to after following annotated tags.
+fn peel_tags(odb: &git_odb::linked::Store, mut id: ObjectId, buf: &mut Vec<u8>) -> Result<ObjectId, Error> {
+    loop {
+        let obj = odb
+            .try_find(id, buf, &mut git_pack::cache::Never)?
+            .ok_or(Error::MissingObject(id))?;
+        if obj.kind != git_object::Kind::Tag {
+            return Ok(id);
+        }
+        id = git_object::TagRef::from_bytes(obj.data)?.target();
+    }
+}
+
+/// Append `data` to `out` as packet line.
+fn packet_line(data: &[u8], out: &mut Vec<u8>) {
+    out.extend_from_slice(format!("{:04x}", data.len() + 4).as_bytes());
+    out.extend_from_slice(data);
+}
@@ -0,0 +1,134 @@
+use std::{path::Path, process::Command};
+
+use git_testfixture::{parse, Change, Command as ScriptCommand, Script};
+
+type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+fn git(repo: &Path, args: &[&str]) -> Result<String> {
+    let out = Command::new("git").arg("--git-dir").arg(repo).args(args).output()?;
+    assert!(
+        out.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&out.stderr)
+    );
+    Ok(String::from_utf8(out.stdout)?)
+}
+
+#[test]
+fn parse_commands_with_quotes_and_comments() -> Result {
+    let script = Script::parse(
+        "# comment\n\ncommit main \"a \\\"message\\\"\" a=\"x y\\n\" -b +other # trailing\ntag v1 main\npack-refs\ncommit-graph\n",
+    )?;
+    assert_eq!(
+        script.commands,
+        vec![
+            (
+                3,
+                ScriptCommand::Commit {
+                    branch: "main".into(),
+                    message: "a \"message\"".into(),
+                    changes: vec![
+                        Change::Write {
+                            path: "a".into(),
+                            content: "x y\n".into()
+                        },
+                        Change::Delete { path: "b".into() }
+                    ],
+                    merges: vec!["other".into()],
+                }
+            ),
+            (
+                4,
+                ScriptCommand::Tag {
+                    name: "v1".into(),
+                    revision: "main".into(),
+                    message: None
+                }
+            ),
+            (5, ScriptCommand::PackRefs),
+            (6, ScriptCommand::CommitGraph)
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn parse_errors_name_the_line() {
+    assert!(matches!(
+        Script::parse("pack\ncommit \"main"),
+        Err(parse::Error::UnterminatedQuote { line: 2 })
+    ));
+    assert!(matches!(
+        Script::parse("frobnicate"),
+        Err(parse::Error::UnknownCommand { line: 1, .. })
+    ));
+    assert!(matches!(
+        Script::parse("branch main"),
+        Err(parse::Error::MissingArgument {
+            line: 1,
+            command: "branch"
+        })
+    ));
+    assert!(matches!(
+        Script::parse("\npack now"),
+        Err(parse::Error::TooManyArguments {
+            line: 2,
+            command: "pack"
+        })
+    ));
+    assert!(matches!(
+        Script::parse("commit main msg file"),
+        Err(parse::Error::InvalidChange { line: 1, .. })
+    ));
+}
+
+#[test]
+fn unknown_revisions_are_an_error() -> Result {
+    let dir = tempfile::tempdir()?;
+    let err = Script::parse("commit main one a=1\nbranch topic unknown")?
+        .create(&dir.path().join("repo"))
+        .unwrap_err();
+    assert_eq!(err.to_string(), "line 2: Revision 'unknown' doesn't exist");
+    Ok(())
+}
+
+#[test]
+fn scripted_repositories_are_valid_and_deterministic() -> Result {
+    let repo = git_testfixture::scripted_repo_read_only("make_basic_repo.txt")?;
+    git(&repo, &["fsck", "--strict", "--no-dangling"])?;
+
+    assert_eq!(git(&repo, &["rev-list", "--count", "main"])?.trim(), "6");
+    assert_eq!(git(&repo, &["rev-list", "--merges", "main"])?.lines().count(), 1);
+    assert_eq!(git(&repo, &["cat-file", "-t", "v1.0"])?.trim(), "tag");
+    assert_eq!(git(&repo, &["cat-file", "-p", "light:feature"])?, "the feature\n");
+    assert_eq!(git(&repo, &["cat-file", "-p", "main:dir/b"])?, "4");
+    assert_eq!(
+        git(&repo, &["ls-tree", "-r", "--name-only", "main"])?,
+        "a\ndir/b\nfeature\nnew\n"
+    );
+    assert_eq!(git(&repo, &["symbolic-ref", "HEAD"])?.trim(), "refs/heads/main");
+
+    let counts = git(&repo, &["count-objects", "-v"])?;
+    assert!(counts.lines().any(|l| l == "packs: 1"));
+    assert!(
+        counts.lines().any(|l| l.starts_with("count: ") && l != "count: 0"),
+        "objects written after packing remain loose"
+    );
+    assert!(repo.join("packed-refs").is_file());
+    assert!(!repo.join("refs/tags/v1.0").exists(), "tags were packed");
+    assert!(repo.join("refs/heads/main").is_file(), "main was updated after packing");
+    assert!(repo.join("objects/info/commit-graph").is_file());
+    git(&repo, &["commit-graph", "verify"])?;
+
+    let dir = tempfile::tempdir()?;
+    let outcome = Script::parse(&std::fs::read_to_string("tests/fixtures/make_basic_repo.txt")?)?
+        .create(&dir.path().join("repo"))?;
+    assert_eq!(outcome.commits.len(), 6);
+    assert_eq!(
+        git(&repo, &["rev-parse", "main"])?.trim(),
+        outcome.refs["refs/heads/main"].to_string(),
+        "the same script always yields the same objects"
+    );
+    Ok(())
+}
@@ -181,6 +181,271 @@ title "Porcelain ${kind}"
         )
       )
     )
+    (with "the --bare option set"
+      DIR=foo/bar.git
+      (with "a multi-element directory: $DIR"
+        (sandbox
+          it "succeeds" && {
+            WITH_SNAPSHOT="$snapshot/success-bare" \
+            expect_run $SUCCESSFULLY "$exe" init --bare $DIR
+          }
+
+          it "places the repository into the directory itself" && {
+            expect_run $SUCCESSFULLY test -f $DIR/HEAD
+            expect_run $WITH_FAILURE test -e $DIR/.git
+            WITH_SNAPSHOT="$snapshot/bare-config" \
+            expect_run $SUCCESSFULLY git --git-dir $DIR config core.bare
+          }
+
+          (when "trying to initialize the same directory again"
+            it "fails" && {
+              WITH_SNAPSHOT="$snapshot/fail-bare" \
+              expect_run $WITH_FAILURE "$exe" init --bare $DIR
+            }
+          )
+        )
+      )
+    )
+    (with "the --initial-branch and --no-hook-samples options set"
+      (sandbox
+        it "succeeds" && {
+          expect_run $SUCCESSFULLY "$exe" init --initial-branch trunk --no-hook-samples
+        }
+
+        it "points HEAD to the initial branch" && {
+          WITH_SNAPSHOT="$snapshot/initial-branch-head" \
+          expect_run $SUCCESSFULLY cat .git/HEAD
+        }
+
+        it "writes no hooks" && {
+          expect_run $SUCCESSFULLY test -d .git/hooks
+          expect_run $SUCCESSFULLY test -z "$(ls .git/hooks)"
+        }
+      )
+    )
+    (with "an invalid initial branch"
+      (sandbox
+        it "fails" && {
+          WITH_SNAPSHOT="$snapshot/fail-invalid-initial-branch" \
+          expect_run $WITH_FAILURE "$exe" init --initial-branch 'a..b'
+        }
+      )
+    )
+  )
+  title "gix clone"
+  (when "running 'clone'"
+    snapshot="$snapshot/clone"
+    (with "a local repository with branches and tags"
+      (small-repo-in-sandbox
+        it "succeeds" && {
+          expect_run $SUCCESSFULLY "$exe" -q clone . cloned
+        }
+
+        it "sets up remote tracking branches for all branches of the source" && {
+          WITH_SNAPSHOT="$snapshot/branches" \
+          expect_run $SUCCESSFULLY git -C cloned branch -a
+        }
+
+        it "checks out a clean work tree" && {
+          WITH_SNAPSHOT="$snapshot/status" \
+          expect_run $SUCCESSFULLY git -C cloned status --porcelain
+        }
+
+        it "copies all objects" && {
+          expect_run $SUCCESSFULLY git -C cloned fsck
+        }
+
+        (when "trying to clone into the same directory again"
+          it "fails" && {
+            expect_run $WITH_FAILURE "$exe" -q clone . cloned
+          }
+        )
+      )
+    )
+    (with "the --no-local option set"
+      (small-repo-in-sandbox
+        it "succeeds" && {
+          expect_run $SUCCESSFULLY "$exe" -q clone --no-local . cloned
+        }
+
+        it "sets up the same remote tracking branches as when copying objects" && {
+          WITH_SNAPSHOT="$snapshot/branches" \
+          expect_run $SUCCESSFULLY git -C cloned branch -a
+        }
+
+        it "receives all objects in a pack" && {
+          expect_run $SUCCESSFULLY git -C cloned fsck
+        }
+      )
+    )
+    (with "the --reference option set"
+      (small-repo-in-sandbox
+        it "succeeds" && {
+          expect_run $SUCCESSFULLY "$exe" -q clone --no-local --reference . . cloned
+        }
+
+        it "borrows all objects from the reference repository" && {
+          expect_run $SUCCESSFULLY test -f cloned/.git/objects/info/alternates
+          expect_run $SUCCESSFULLY git -C cloned fsck
+        }
+      )
+      (with "the --dissociate option set"
+        (small-repo-in-sandbox
+          it "succeeds" && {
+            expect_run $SUCCESSFULLY "$exe" -q clone --no-local --reference . --dissociate . cloned
+          }
+
+          it "copies all borrowed objects and stops using the reference repository" && {
+            expect_run $WITH_FAILURE test -e cloned/.git/objects/info/alternates
+            rm -Rf .git
+            expect_run $SUCCESSFULLY git -C cloned fsck
+          }
+        )
+      )
+    )
+    (with "an empty repository"
+      (sandbox
+        git init -q empty
+
+        it "succeeds" && {
+          expect_run $SUCCESSFULLY "$exe" -q clone empty cloned
+        }
+
+        it "creates a repository with an unborn HEAD" && {
+          WITH_SNAPSHOT="$snapshot/empty-status" \
+          expect_run $SUCCESSFULLY git -C cloned status --porcelain --branch
+        }
+      )
+    )
+    (with "the --bare option set"
+      (small-repo-in-sandbox
+        it "succeeds" && {
+          expect_run $SUCCESSFULLY "$exe" -q clone --bare . cloned.git
+        }
+
+        it "creates local branches without a work tree" && {
+          WITH_SNAPSHOT="$snapshot/bare-branches" \
+          expect_run $SUCCESSFULLY git -C cloned.git branch -a
+        }
+      )
+    )
+    (with "the --mirror option set"
+      (small-repo-in-sandbox
+        git update-ref refs/notes/commits HEAD
+
+        it "succeeds" && {
+          expect_run $SUCCESSFULLY "$exe" -q clone --mirror . cloned.git
+        }
+
+        it "creates all references of the remote without a work tree" && {
+          WITH_SNAPSHOT="$snapshot/mirror-refs" \
+          expect_run $SUCCESSFULLY git -C cloned.git for-each-ref --format '%(refname)'
+        }
+
+        it "configures the remote to overwrite all references when fetching" && {
+          WITH_SNAPSHOT="$snapshot/mirror-config" \
+          expect_run $SUCCESSFULLY git -C cloned.git config --get-regexp '^remote\.origin\.(fetch|mirror)$'
+        }
+      )
+    )
+    (with "the --single-branch and --branch options set"
+      (small-repo-in-sandbox
+        it "succeeds" && {
+          expect_run $SUCCESSFULLY "$exe" -q clone --no-local --single-branch --branch dev . cloned
+        }
+
+        it "only clones and checks out the given branch" && {
+          WITH_SNAPSHOT="$snapshot/single-branch-branches" \
+          expect_run $SUCCESSFULLY git -C cloned branch -a
+        }
+
+        it "clones the tags pointing into the history of the branch" && {
+          WITH_SNAPSHOT="$snapshot/single-branch-tags" \
+          expect_run $SUCCESSFULLY git -C cloned tag
+        }
+
+        it "configures the remote to only fetch the given branch" && {
+          WITH_SNAPSHOT="$snapshot/single-branch-config" \
+          expect_run $SUCCESSFULLY git -C cloned config --get remote.origin.fetch
+        }
+      )
+    )
+    (with "the --branch option set to a tag"
+      (small-repo-in-sandbox
+        it "succeeds" && {
+          expect_run $SUCCESSFULLY "$exe" -q clone --branch unannotated . cloned
+        }
+
+        it "detaches HEAD at the tag" && {
+          expect_run $WITH_FAILURE git -C cloned symbolic-ref -q HEAD
+          expect_run $SUCCESSFULLY test "$(git -C cloned rev-parse HEAD)" = "$(git rev-parse unannotated)"
+        }
+      )
+    )
+    (with "the --no-tags option set"
+      (small-repo-in-sandbox
+        it "succeeds" && {
+          expect_run $SUCCESSFULLY "$exe" -q clone --no-local --no-tags . cloned
+        }
+
+        it "doesn't clone any tags" && {
+          expect_run $SUCCESSFULLY test -z "$(git -C cloned tag)"
+        }
+
+        it "configures the remote not to fetch tags" && {
+          WITH_SNAPSHOT="$snapshot/no-tags-config" \
+          expect_run $SUCCESSFULLY git -C cloned config --get remote.origin.tagOpt
+        }
+      )
+    )
+  )
+  title "gix fetch"
+  (when "running 'fetch'"
+    snapshot="$snapshot/fetch"
+    (with "a clone of a local repository which received a new commit"
+      (small-repo-in-sandbox
+        "$exe" -q clone . cloned &>/dev/null
+        { echo more >> b && git commit -qam "fourth"; } &>/dev/null
+
+        it "succeeds" && {
+          (cd cloned && expect_run $SUCCESSFULLY "$exe" -q fetch)
+        }
+
+        it "fast-forwards the remote tracking branch" && {
+          WITH_SNAPSHOT="$snapshot/remote-branches" \
+          expect_run $SUCCESSFULLY git -C cloned branch -r -v
+        }
+
+        it "writes objects git can read" && {
+          expect_run $SUCCESSFULLY git -C cloned fsck
+        }
+
+        (when "fetching from a remote that doesn't exist"
+          it "fails" && {
+            (cd cloned && expect_run $WITH_FAILURE "$exe" -q fetch unknown)
+          }
+        )
+      )
+    )
+    (with "a clone without tags of a local repository which received a new tag"
+      (small-repo-in-sandbox
+        "$exe" -q clone --no-tags . cloned &>/dev/null
+        git tag -m "new tag" new &>/dev/null
+
+        it "doesn't fetch tags as configured" && {
+          (cd cloned && expect_run $SUCCESSFULLY "$exe" -q fetch)
+          expect_run $SUCCESSFULLY test -z "$(git -C cloned tag)"
+        }
+
+        (with "the --tags option set"
+          it "fetches all tags" && {
+            (cd cloned && expect_run $SUCCESSFULLY "$exe" -q fetch --tags)
+            WITH_SNAPSHOT="$snapshot/all-tags" \
+            expect_run $SUCCESSFULLY git -C cloned tag
+          }
+        )
+      )
+    )
   )
 )
 fi
@@ -272,6 +272,35 @@ title "gixp remote-ref-list"
   )
 )
 
+title "gixp remote-ref-map"
+(when "running 'remote-ref-map'"
+  snapshot="$snapshot/remote-ref-map"
+  (small-repo-in-sandbox
+    if [[ "$kind" != "small" && "$kind" != "async" ]]; then
+    (with "file:// protocol"
+      (with "version 1"
+        it "maps remote references to local ones and shows how they would change" && {
+          WITH_SNAPSHOT="$snapshot/file-v-any" \
+          expect_run $SUCCESSFULLY "$exe_plumbing" remote-ref-map -p 1 -r . .git '+refs/heads/*:refs/remotes/origin/*' 'refs/tags/*:refs/tags/*' '^refs/heads/dev'
+        }
+      )
+      (with "version 2"
+        it "maps remote references to local ones and shows how they would change" && {
+          WITH_SNAPSHOT="$snapshot/file-v-any" \
+          expect_run $SUCCESSFULLY "$exe_plumbing" remote-ref-map -p 2 -r . "$PWD/.git" '+refs/heads/*:refs/remotes/origin/*' 'refs/tags/*:refs/tags/*' '^refs/heads/dev'
+        }
+      )
+      (with "an invalid refspec"
+        it "fails with a descriptive error" && {
+          WITH_SNAPSHOT="$snapshot/invalid-refspec-failure" \
+          expect_run $WITH_FAILURE "$exe_plumbing" remote-ref-map .git 'refs/heads/*:refs/heads/main'
+        }
+      )
+    )
+    fi
+  )
+)
+
 title "gixp pack-index-from-data"
 (when "running 'pack-index-from-data"
   snapshot="$snapshot/pack-index-from-data"
@@ -335,6 +364,23 @@ title "gixp pack-index-from-data"
     )
   )
 )
+title "gixp pack-create"
+(when "running 'pack-create'"
+  (small-repo-in-sandbox
+    git rev-list --objects --all | cut -c1-40 > ids
+    (with "ids from an input file and --stdout"
+      it "writes a valid pack to stdout" && {
+        expect_run_sh $SUCCESSFULLY "'$exe_plumbing' pack-create -i ids --stdout > out.pack && git index-pack --strict out.pack >/dev/null"
+      }
+    )
+    (with "--no-deltas"
+      it "writes a valid pack without deltas" && {
+        expect_run_sh $SUCCESSFULLY "'$exe_plumbing' pack-create --no-deltas -i ids --stdout > out.pack && git index-pack --strict out.pack >/dev/null && ! git verify-pack -v out.idx | grep -q 'chain length'"
+      }
+    )
+  )
+)
+
 title "gixp pack-explode"
 (when "running 'pack-explode"
   snapshot="$snapshot/pack-explode"
@@ -459,6 +505,19 @@ title "gixp pack-verify"
           expect_run $SUCCESSFULLY "$exe_plumbing" pack-verify --algorithm less-memory --statistics "$PACK_INDEX_FILE"
         }
       )
+      (with "the largest objects"
+        it "verifies the pack index successfully and lists the largest objects along with the statistics" && {
+          WITH_SNAPSHOT="$snapshot/index-with-statistics-and-largest-objects-success" \
+          expect_run $SUCCESSFULLY "$exe_plumbing" pack-verify --statistics --largest-objects 3 "$PACK_INDEX_FILE"
+        }
+
+        (with "and the less-memory algorithm"
+          it "lists the same largest objects" && {
+            WITH_SNAPSHOT="$snapshot/index-with-statistics-and-largest-objects-success" \
+            expect_run $SUCCESSFULLY "$exe_plumbing" pack-verify --algorithm less-memory --statistics --largest-objects 3 "$PACK_INDEX_FILE"
+          }
+        )
+      )
     )
     (with "decode"
       it "verifies the pack index successfully and with desired output, and decodes all objects" && {
@@ -479,6 +538,12 @@ title "gixp pack-verify"
         expect_run $SUCCESSFULLY "$exe_plumbing" --format json --threads 1 pack-verify --statistics "$PACK_INDEX_FILE"
       }
     )
+    (with "objects (JSON lines)"
+      it "verifies the pack index successfully and writes one JSON object per verified object" && {
+        WITH_SNAPSHOT="$snapshot/index-objects-json-lines-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" --format json-lines --threads 1 pack-verify --objects "$PACK_INDEX_FILE"
+      }
+    )
     fi
   )
   (sandbox
@@ -492,7 +557,90 @@ title "gixp pack-verify"
       }
     )
   )
+  (small-repo-in-sandbox
+    (with "a multi-pack-index"
+      git repack -adq
+      git commit -q --allow-empty -m "empty"
+      git repack -q
+      git multi-pack-index write
+      it "verifies the multi-pack-index and the indices of its packs successfully" && {
+        expect_run $SUCCESSFULLY "$exe_plumbing" pack-verify .git/objects/pack/multi-pack-index
+      }
+    )
+  )
 )
+title "gixp pack-inspect"
+(when "running 'pack-inspect'"
+  snapshot="$snapshot/pack-inspect"
+  (with "a pack INDEX file"
+    PACK_INDEX_FILE="$fixtures/packs/pack-11fdfa9e156ab73caae3b6da867192221f2089c2.idx"
+    it "prints all entries with their ids, delta bases and chain lengths" && {
+      WITH_SNAPSHOT="$snapshot/index-success" \
+      expect_run $SUCCESSFULLY "$exe_plumbing" pack-inspect "$PACK_INDEX_FILE"
+    }
+  )
+  (sandbox
+    (with "a pack without its index"
+      cp "$fixtures/packs/pack-11fdfa9e156ab73caae3b6da867192221f2089c2.pack" pack.pack
+      it "prints all entries with the offsets of their delta bases" && {
+        WITH_SNAPSHOT="$snapshot/pack-without-index-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" pack-inspect pack.pack
+      }
+    )
+  )
+  if test "$kind" = "max"; then
+  (with "a pack INDEX file and JSON lines output"
+    PACK_INDEX_FILE="$fixtures/packs/pack-11fdfa9e156ab73caae3b6da867192221f2089c2.idx"
+    it "prints one JSON object per entry" && {
+      WITH_SNAPSHOT="$snapshot/index-json-lines-success" \
+      expect_run $SUCCESSFULLY "$exe_plumbing" --format json-lines pack-inspect "$PACK_INDEX_FILE"
+    }
+  )
+  fi
+)
+
+title "gixp pack-diff"
+(when "running 'pack-diff'"
+  snapshot="$snapshot/pack-diff"
+  PACK_FILE="$fixtures/packs/pack-11fdfa9e156ab73caae3b6da867192221f2089c2"
+  (with "the same pack twice"
+    it "finds no differences" && {
+      WITH_SNAPSHOT="$snapshot/same-pack-success" \
+      expect_run $SUCCESSFULLY "$exe_plumbing" pack-diff "$PACK_FILE.idx" "$PACK_FILE.pack"
+    }
+  )
+  (with "two packs without common objects"
+    it "lists all objects as only being in one of them" && {
+      WITH_SNAPSHOT="$snapshot/disjoint-packs-success" \
+      expect_run $SUCCESSFULLY "$exe_plumbing" pack-diff "$PACK_FILE.idx" "$fixtures/packs/pack-c0438c19fb16422b6bbcce24387b3264416d485b.idx"
+    }
+  )
+  (sandbox
+    (with "a pack with the same objects, but using ref-deltas"
+      git init -q
+      cp "$PACK_FILE".* .git/objects/pack/
+      git show-index < "$PACK_FILE.idx" | cut -d' ' -f2 | git pack-objects -q --window=0 new >/dev/null
+      it "lists the objects with a different representation" && {
+        WITH_SNAPSHOT="$snapshot/different-representation-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" pack-diff "$PACK_FILE.idx" new-*.idx
+      }
+      if test "$kind" = "max"; then
+      it "prints one JSON object per difference with JSON lines output" && {
+        WITH_SNAPSHOT="$snapshot/different-representation-json-lines-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" --format json-lines pack-diff "$PACK_FILE.idx" new-*.idx
+      }
+      fi
+    )
+    (with "a pack without index"
+      cp "$PACK_FILE.pack" pack.pack
+      it "fails as objects can't be identified" && {
+        WITH_SNAPSHOT="$snapshot/pack-without-index-failure" \
+        expect_run $WITH_FAILURE "$exe_plumbing" pack-diff "$PACK_FILE.idx" pack.pack
+      }
+    )
+  )
+)
+
 title "gixp commit-graph-verify"
 (when "running 'commit-graph-verify'"
   snapshot="$snapshot/commit-graph-verify"
@@ -513,6 +661,357 @@ title "gixp commit-graph-verify"
         }
       )
       fi
+      (with "--objects"
+        it "succeeds as all commits match their objects" && {
+          expect_run $SUCCESSFULLY "$exe_plumbing" commit-graph-verify --objects .git/objects/info/commit-graph
+        }
+      )
+    )
+  )
+)
+
+title "gixp commit-graph-write"
+(when "running 'commit-graph-write'"
+  snapshot="$snapshot/commit-graph-write"
+  (small-repo-in-sandbox
+    (with "no commit-graph file"
+      it "succeeds" && {
+        expect_run $SUCCESSFULLY "$exe_plumbing" commit-graph-write
+      }
+
+      it "writes a commit-graph git can verify" && {
+        expect_run $SUCCESSFULLY git commit-graph verify --no-progress
+      }
+
+      it "contains all commits reachable from all references" && {
+        WITH_SNAPSHOT="$snapshot/all-references-statistics" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" commit-graph-verify -s .git/objects/info
+      }
+    )
+    (with "--stdin-commits"
+      it "only writes commits reachable from the given commits" && {
+        git rev-parse main~1 | expect_run $SUCCESSFULLY "$exe_plumbing" commit-graph-write --stdin-commits
+        WITH_SNAPSHOT="$snapshot/stdin-commits-statistics" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" commit-graph-verify -s .git/objects/info
+      }
+    )
+  )
+)
+
+title "gixp repository-cat"
+(when "running 'repository-cat'"
+  snapshot="$snapshot/repository-cat"
+  (small-repo-in-sandbox
+    (with "a file at the tip of a branch"
+      it "prints its content" && {
+        WITH_SNAPSHOT="$snapshot/tip-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-cat main:b
+      }
+    )
+    (with "a file at an abbreviated commit id"
+      it "prints its content" && {
+        WITH_SNAPSHOT="$snapshot/tip-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-cat "$(git rev-parse --short HEAD):b"
+      }
+    )
+    (with "filters and core.autocrlf enabled"
+      git config core.autocrlf true
+      it "prints its content with CRLF line endings" && {
+        expect_run_sh $SUCCESSFULLY "'$exe_plumbing' repository-cat --filters main:b | od -c | grep -qF '\\r  \\n'"
+      }
+      git config --unset core.autocrlf
+    )
+    (with "a path that doesn't exist at the revision"
+      it "fails" && {
+        WITH_SNAPSHOT="$snapshot/missing-path-failure" \
+        expect_run $WITH_FAILURE "$exe_plumbing" repository-cat main~1:c
+      }
+    )
+  )
+)
+
+title "gixp repository-cat-object"
+(when "running 'repository-cat-object'"
+  snapshot="$snapshot/repository-cat-object"
+  (small-repo-in-sandbox
+    (with "a full object id"
+      it "prints the object's content" && {
+        WITH_SNAPSHOT="$snapshot/full-id-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-cat-object "$(git rev-parse HEAD)"
+      }
+    )
+    (with "a unique prefix and --kind"
+      it "prints the object's kind" && {
+        WITH_SNAPSHOT="$snapshot/prefix-kind-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-cat-object --kind "$(git rev-parse --short=7 HEAD^{tree})"
+      }
+    )
+    (with "a unique prefix and --size"
+      it "prints the object's size" && {
+        WITH_SNAPSHOT="$snapshot/prefix-size-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-cat-object -s "$(git rev-parse --short=4 HEAD)"
+      }
+    )
+    (with "a prefix that matches no object"
+      it "fails" && {
+        WITH_SNAPSHOT="$snapshot/missing-failure" \
+        expect_run $WITH_FAILURE "$exe_plumbing" repository-cat-object ffffff
+      }
+    )
+  )
+)
+
+title "gixp repository-hash-object"
+(when "running 'repository-hash-object'"
+  snapshot="$snapshot/repository-hash-object"
+  (small-repo-in-sandbox
+    (with "a blob read from standard input"
+      it "prints its id without writing it" && {
+        WITH_SNAPSHOT="$snapshot/stdin-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-hash-object < a
+      }
+      it "matches the id computed by git" && {
+        expect_run $SUCCESSFULLY test "$("$exe_plumbing" repository-hash-object < b)" = "$(git hash-object b)"
+      }
+    )
+    (with "a tree read from a file"
+      git cat-file tree HEAD^{tree} > tree
+      it "prints its id" && {
+        WITH_SNAPSHOT="$snapshot/tree-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-hash-object -t tree tree
+      }
+    )
+    (with "--write"
+      echo "new content" > new
+      it "prints the object's id" && {
+        WITH_SNAPSHOT="$snapshot/write-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-hash-object -w new
+      }
+      it "writes the object into the object database" && {
+        WITH_SNAPSHOT="$snapshot/write-cat-success" \
+        expect_run $SUCCESSFULLY git cat-file -p "$(git hash-object new)"
+      }
+    )
+    (with "content that isn't a valid commit"
+      it "fails" && {
+        WITH_SNAPSHOT="$snapshot/invalid-commit-failure" \
+        expect_run $WITH_FAILURE "$exe_plumbing" repository-hash-object -t commit a
+      }
+    )
+  )
+)
+
+title "gixp repository-grep"
+(when "running 'repository-grep'"
+  snapshot="$snapshot/repository-grep"
+  (small-repo-in-sandbox
+    (with "a revision"
+      it "prints matching lines of files at that revision" && {
+        WITH_SNAPSHOT="$snapshot/revision-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-grep --revision main hi
+      }
+    )
+    (with "no revision and case-insensitive matching"
+      echo hi > ignored.log
+      echo '*.log' > .git/info/exclude
+      it "prints matching lines of files in the work tree which aren't ignored" && {
+        WITH_SNAPSHOT="$snapshot/worktree-ignore-case-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-grep -i HI
+      }
+    )
+  )
+)
+
+title "gixp repository-reachability"
+(when "running 'repository-reachability'"
+  snapshot="$snapshot/repository-reachability"
+  (small-repo-in-sandbox
+    (with "a blob in the tip of a branch"
+      it "prints the path from a reference to the blob" && {
+        WITH_SNAPSHOT="$snapshot/blob-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-reachability "$(git rev-parse main:b)"
+      }
+    )
+    (with "an object that doesn't exist"
+      it "fails" && {
+        WITH_SNAPSHOT="$snapshot/unreachable-failure" \
+        expect_run $WITH_FAILURE "$exe_plumbing" repository-reachability 0000000000000000000000000000000000000001
+      }
+    )
+  )
+)
+
+title "gixp repository-largest-objects"
+(when "running 'repository-largest-objects'"
+  snapshot="$snapshot/repository-largest-objects"
+  (small-repo-in-sandbox
+    mkdir dir && seq 1000 > dir/large && git add dir && git commit -qm "add large file"
+    (with "the default order by size"
+      it "prints the largest objects along with their paths" && {
+        WITH_SNAPSHOT="$snapshot/size-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-largest-objects -n 3
+      }
+    )
+    (with "the order by size on disk"
+      it "prints the objects occupying the most space" && {
+        WITH_SNAPSHOT="$snapshot/disk-size-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-largest-objects -n 3 -o disk-size
+      }
+    )
+  )
+)
+
+title "gixp repository-odb-stats"
+(when "running 'repository-odb-stats'"
+  snapshot="$snapshot/repository-odb-stats"
+  (small-repo-in-sandbox
+    (with "only loose objects"
+      it "prints their amount and size" && {
+        WITH_SNAPSHOT="$snapshot/loose-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-odb-stats -n 0
+      }
+    )
+    git repack -qad && git multi-pack-index write
+    (with "a pack and a multi-pack-index"
+      it "prints the packs, the multi-pack-index and the largest objects" && {
+        WITH_SNAPSHOT="$snapshot/packed-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-odb-stats -n 2
+      }
+    )
+  )
+)
+
+title "gixp repository-verify"
+(when "running 'repository-verify'"
+  snapshot="$snapshot/repository-verify"
+  (small-repo-in-sandbox
+    (with "an intact repository with only loose objects"
+      it "succeeds and prints what was checked" && {
+        WITH_SNAPSHOT="$snapshot/loose-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-verify
+      }
+    )
+    chmod -R u+w .git/objects
+    cp .git/objects/e6/9de29bb2d1d6434b8b29ae775ad8c2e48c5391 .git/objects/45/b983be36b73c0788dc9cbcb76cbb80fc7bb057
+    tree="$(git rev-parse unannotated^{tree})" && rm ".git/objects/${tree:0:2}/${tree:2}"
+    (with "a corrupt and a missing loose object"
+      it "lists the problem and fails" && {
+        WITH_SNAPSHOT="$snapshot/loose-corrupt-failure" \
+        expect_run $WITH_FAILURE "$exe_plumbing" repository-verify
+      }
+    )
+  )
+  (small-repo-in-sandbox
+    git repack -qad && git multi-pack-index write && git commit-graph write --reachable
+    (with "a pack, a multi-pack-index and a commit-graph"
+      it "succeeds and prints what was checked" && {
+        WITH_SNAPSHOT="$snapshot/packed-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-verify
+      }
+    )
+  )
+)
+
+title "gixp repository-ls-tree"
+(when "running 'repository-ls-tree'"
+  snapshot="$snapshot/repository-ls-tree"
+  (small-repo-in-sandbox
+    mkdir -p dir/sub && echo content > dir/sub/file && echo other > dir/other && git add dir && git commit -qm "add directories"
+    (with "no options"
+      it "lists the top-level entries" && {
+        WITH_SNAPSHOT="$snapshot/top-level-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-ls-tree main
+      }
+    )
+    (with "--recursive and --long"
+      it "lists all blobs along with their size" && {
+        WITH_SNAPSHOT="$snapshot/recursive-long-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-ls-tree -R --long main
+      }
+    )
+    (with "--name-only and a path with trailing slash"
+      it "lists the paths of entries in the directory" && {
+        WITH_SNAPSHOT="$snapshot/name-only-path-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-ls-tree --name-only main dir/
+      }
+    )
+    (with "a revision that doesn't exist"
+      it "fails" && {
+        WITH_SNAPSHOT="$snapshot/missing-revision-failure" \
+        expect_run $WITH_FAILURE "$exe_plumbing" repository-ls-tree does-not-exist
+      }
+    )
+  )
+)
+
+title "gixp repository-merge-tree"
+(when "running 'repository-merge-tree'"
+  snapshot="$snapshot/repository-merge-tree"
+  (small-repo-in-sandbox
+    {
+      git checkout -b theirs dev && echo new > c && git add c && git commit -m "add c"
+      git checkout -b conflicting dev && echo other >> b && git commit -am "change b"
+      git checkout main
+    } &>/dev/null
+    (with "changes on both sides to different files"
+      it "prints the merged tree" && {
+        WITH_SNAPSHOT="$snapshot/clean-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-merge-tree dev main theirs
+      }
+    )
+    (with "changes on both sides to the same file"
+      it "prints the merged tree along with the conflicting entries and fails" && {
+        WITH_SNAPSHOT="$snapshot/conflict-failure" \
+        expect_run $WITH_FAILURE "$exe_plumbing" repository-merge-tree dev main conflicting
+      }
+    )
+    if test "$kind" = "max"; then
+    (with "--format json"
+      it "prints the merged tree and conflicts in JSON format" && {
+        WITH_SNAPSHOT="$snapshot/conflict-json-failure" \
+        expect_run $WITH_FAILURE "$exe_plumbing" --format json repository-merge-tree dev main conflicting
+      }
+    )
+    fi
+  )
+)
+
+title "gixp repository-update-refs"
+(when "running 'repository-update-refs'"
+  snapshot="$snapshot/repository-update-refs"
+  (small-repo-in-sandbox
+    (with "an explicit transaction"
+      printf 'start\ncreate refs/heads/new main~1\nupdate refs/heads/dev main dev\nverify refs/heads/missing\nprepare\ncommit\n' > commands
+      it "prints the state of the transaction" && {
+        WITH_SNAPSHOT="$snapshot/transaction-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-update-refs < commands
+      }
+      it "changes the references" && {
+        WITH_SNAPSHOT="$snapshot/transaction-refs" \
+        expect_run $SUCCESSFULLY git show-ref --heads
+      }
+    )
+    (with "NUL-terminated commands"
+      printf 'delete refs/heads/new\0\0update refs/heads/dev\0main~2\0\0' > commands
+      it "succeeds without output" && {
+        WITH_SNAPSHOT="$snapshot/nul-terminated-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" repository-update-refs -z < commands
+      }
+      it "changes the references" && {
+        WITH_SNAPSHOT="$snapshot/nul-terminated-refs" \
+        expect_run $SUCCESSFULLY git show-ref --heads
+      }
+    )
+    (with "a failing verification"
+      printf 'update refs/heads/dev main\nverify refs/heads/main dev\n' > commands
+      it "fails" && {
+        WITH_SNAPSHOT="$snapshot/verify-failure" \
+        expect_run $WITH_FAILURE "$exe_plumbing" repository-update-refs < commands
+      }
+      it "doesn't change any reference" && {
+        WITH_SNAPSHOT="$snapshot/nul-terminated-refs" \
+        expect_run $SUCCESSFULLY git show-ref --heads
+      }
     )
   )
 )